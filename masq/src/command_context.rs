@@ -122,10 +122,16 @@ impl CommandContext for CommandContextReal {
 
 impl CommandContextReal {
     pub fn new(
+        ui_host: &str,
         daemon_ui_port: u16,
+        wss: bool,
+        ui_token_opt: Option<String>,
         broadcast_stream_factory: Box<dyn StreamFactory>,
     ) -> Result<Self, ContextError> {
-        let mut connection = ConnectionManager::new();
+        let mut connection = ConnectionManager::new()
+            .ui_host(ui_host)
+            .wss(wss)
+            .ui_token(ui_token_opt);
         let broadcast_handler = BroadcastHandlerReal::new();
         let broadcast_handle = broadcast_handler.start(broadcast_stream_factory);
         match connection.connect(daemon_ui_port, broadcast_handle, REDIRECT_TIMEOUT_MILLIS) {
@@ -140,6 +146,63 @@ impl CommandContextReal {
     }
 }
 
+// Stands in for another context while a single command runs, keeping what the command prints and
+// every response it receives instead of letting the text through, so that the outcome can be
+// reported in some other form (--json, for example).
+pub struct CapturingContext<'a> {
+    delegate: &'a mut dyn CommandContext,
+    pub responses: Vec<MessageBody>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl<'a> CommandContext for CapturingContext<'a> {
+    fn active_port(&self) -> Option<u16> {
+        self.delegate.active_port()
+    }
+
+    fn send(&mut self, message: MessageBody) -> Result<(), ContextError> {
+        self.delegate.send(message)
+    }
+
+    fn transact(
+        &mut self,
+        message: MessageBody,
+        timeout_millis: u64,
+    ) -> Result<MessageBody, ContextError> {
+        let response = self.delegate.transact(message, timeout_millis)?;
+        self.responses.push(response.clone());
+        Ok(response)
+    }
+
+    fn stdin(&mut self) -> &mut dyn Read {
+        self.delegate.stdin()
+    }
+
+    fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.stderr
+    }
+
+    fn close(&mut self) {
+        self.delegate.close()
+    }
+}
+
+impl<'a> CapturingContext<'a> {
+    pub fn new(delegate: &'a mut dyn CommandContext) -> Self {
+        Self {
+            delegate,
+            responses: vec![],
+            stdout: vec![],
+            stderr: vec![],
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,6 +210,7 @@ mod tests {
         ConnectionDropped, ConnectionRefused, PayloadError,
     };
     use crate::communications::broadcast_handler::StreamFactoryReal;
+    use crate::test_utils::mocks::CommandContextMock;
     use masq_lib::messages::{
         FromMessageBody, UiCrashRequest, UiSetupRequest, TIMEOUT_ERROR, UNMARSHAL_ERROR,
     };
@@ -156,7 +220,8 @@ mod tests {
     use masq_lib::ui_gateway::MessageBody;
     use masq_lib::ui_gateway::MessagePath::Conversation;
     use masq_lib::ui_traffic_converter::{TrafficConversionError, UnmarshalError};
-    use masq_lib::utils::{find_free_port, running_test};
+    use masq_lib::utils::{find_free_port, localhost, running_test};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn error_conversion_happy_path() {
@@ -206,7 +271,14 @@ mod tests {
         let server = MockWebSocketsServer::new(port);
         let handle = server.start();
 
-        let subject = CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+        let subject = CommandContextReal::new(
+            &localhost().to_string(),
+            port,
+            false,
+            None,
+            Box::new(StreamFactoryReal::new()),
+        )
+        .unwrap();
 
         assert_eq!(subject.active_port(), Some(port));
         handle.stop();
@@ -223,8 +295,14 @@ mod tests {
         let stderr_arc = stderr.inner_arc();
         let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
         let stop_handle = server.start();
-        let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+        let mut subject = CommandContextReal::new(
+            &localhost().to_string(),
+            port,
+            false,
+            None,
+            Box::new(StreamFactoryReal::new()),
+        )
+        .unwrap();
         subject.stdin = Box::new(stdin);
         subject.stdout = Box::new(stdout);
         subject.stderr = Box::new(stderr);
@@ -256,7 +334,13 @@ mod tests {
         running_test();
         let port = find_free_port();
 
-        let result = CommandContextReal::new(port, Box::new(StreamFactoryReal::new()));
+        let result = CommandContextReal::new(
+            &localhost().to_string(),
+            port,
+            false,
+            None,
+            Box::new(StreamFactoryReal::new()),
+        );
 
         match result {
             Err(ConnectionRefused(_)) => (),
@@ -275,8 +359,14 @@ mod tests {
             payload: Err((101, "booga".to_string())),
         });
         let stop_handle = server.start();
-        let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+        let mut subject = CommandContextReal::new(
+            &localhost().to_string(),
+            port,
+            false,
+            None,
+            Box::new(StreamFactoryReal::new()),
+        )
+        .unwrap();
 
         let response = subject.transact(UiSetupRequest { values: vec![] }.tmb(1), 1000);
 
@@ -290,8 +380,14 @@ mod tests {
         let port = find_free_port();
         let server = MockWebSocketsServer::new(port).queue_string("disconnect");
         let stop_handle = server.start();
-        let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+        let mut subject = CommandContextReal::new(
+            &localhost().to_string(),
+            port,
+            false,
+            None,
+            Box::new(StreamFactoryReal::new()),
+        )
+        .unwrap();
 
         let response = subject.transact(UiSetupRequest { values: vec![] }.tmb(1), 1000);
 
@@ -314,7 +410,8 @@ mod tests {
         let server = MockWebSocketsServer::new(port);
         let stop_handle = server.start();
         let stream_factory = Box::new(StreamFactoryReal::new());
-        let subject_result = CommandContextReal::new(port, stream_factory);
+        let subject_result =
+            CommandContextReal::new(&localhost().to_string(), port, false, None, stream_factory);
         let mut subject = subject_result.unwrap();
         subject.stdin = Box::new(stdin);
         subject.stdout = Box::new(stdout);
@@ -344,4 +441,30 @@ mod tests {
         );
         stop_handle.stop();
     }
+
+    #[test]
+    fn capturing_context_keeps_output_and_responses_to_itself() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut delegate = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiShutdownResponse {}.tmb(1)))
+            .transact_result(Err(PayloadError(1234, "booga".to_string())));
+        let delegate_stdout_arc = delegate.stdout_arc();
+        let delegate_stderr_arc = delegate.stderr_arc();
+        let mut subject = CapturingContext::new(&mut delegate);
+
+        let first = subject.transact(UiShutdownRequest {}.tmb(1), 1000);
+        let second = subject.transact(UiCrashRequest::new("Dispatcher", "").tmb(2), 2000);
+        write!(subject.stdout(), "output").unwrap();
+        write!(subject.stderr(), "error").unwrap();
+
+        assert_eq!(first, Ok(UiShutdownResponse {}.tmb(1)));
+        assert_eq!(second, Err(PayloadError(1234, "booga".to_string())));
+        assert_eq!(subject.responses, vec![UiShutdownResponse {}.tmb(1)]);
+        assert_eq!(subject.stdout, b"output".to_vec());
+        assert_eq!(subject.stderr, b"error".to_vec());
+        assert_eq!(transact_params_arc.lock().unwrap().len(), 2);
+        assert_eq!(delegate_stdout_arc.lock().unwrap().get_string(), "");
+        assert_eq!(delegate_stderr_arc.lock().unwrap().get_string(), "");
+    }
 }
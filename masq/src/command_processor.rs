@@ -1,11 +1,12 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
-use crate::command_context::CommandContextReal;
+use crate::command_context::{CapturingContext, CommandContextReal};
 use crate::command_context::{CommandContext, ContextError};
 use crate::commands::commands_common::{Command, CommandError};
 use crate::communications::broadcast_handler::StreamFactory;
 use crate::schema::app;
 use clap::value_t;
+use masq_lib::ui_gateway::MessageBody;
 
 pub trait CommandProcessorFactory {
     fn make(
@@ -26,7 +27,18 @@ impl CommandProcessorFactory for CommandProcessorFactoryReal {
     ) -> Result<Box<dyn CommandProcessor>, CommandError> {
         let matches = app().get_matches_from(args);
         let ui_port = value_t!(matches, "ui-port", u16).expect("ui-port is not properly defaulted");
-        match CommandContextReal::new(ui_port, broadcast_stream_factory) {
+        let ui_host = matches
+            .value_of("ui-host")
+            .expect("ui-host is not properly defaulted");
+        let wss = matches.is_present("wss");
+        let ui_token_opt = matches.value_of("ui-token").map(|token| token.to_string());
+        match CommandContextReal::new(
+            ui_host,
+            ui_port,
+            wss,
+            ui_token_opt,
+            broadcast_stream_factory,
+        ) {
             Ok(context) => Ok(Box::new(CommandProcessorReal { context })),
             Err(ContextError::ConnectionRefused(s)) => Err(CommandError::ConnectionProblem(s)),
             Err(e) => panic!("Unexpected error: {:?}", e),
@@ -40,8 +52,17 @@ impl CommandProcessorFactoryReal {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct CapturedExecution {
+    pub result: Result<(), CommandError>,
+    pub responses: Vec<MessageBody>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
 pub trait CommandProcessor {
     fn process(&mut self, command: Box<dyn Command>) -> Result<(), CommandError>;
+    fn process_captured(&mut self, command: Box<dyn Command>) -> CapturedExecution;
     fn close(&mut self);
 }
 
@@ -55,6 +76,17 @@ impl CommandProcessor for CommandProcessorReal {
         command.execute(&mut self.context)
     }
 
+    fn process_captured(&mut self, command: Box<dyn Command>) -> CapturedExecution {
+        let mut capturing_context = CapturingContext::new(&mut self.context);
+        let result = command.execute(&mut capturing_context);
+        CapturedExecution {
+            result,
+            responses: capturing_context.responses,
+            stdout: String::from_utf8_lossy(&capturing_context.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&capturing_context.stderr).to_string(),
+        }
+    }
+
     fn close(&mut self) {
         self.context.close();
     }
@@ -69,6 +101,7 @@ mod tests {
     use masq_lib::messages::{UiShutdownRequest, UiShutdownResponse};
     use masq_lib::test_utils::mock_websockets_server::MockWebSocketsServer;
     use masq_lib::utils::find_free_port;
+    use std::io::Write;
 
     #[derive(Debug)]
     struct TestCommand {}
@@ -124,4 +157,92 @@ mod tests {
         let received = stop_handle.stop();
         assert_eq!(received, vec![Ok(UiShutdownRequest {}.tmb(1))]);
     }
+
+    #[test]
+    fn factory_connects_to_the_specified_host() {
+        let port = find_free_port();
+        let args = [
+            "masq".to_string(),
+            "--ui-host".to_string(),
+            "127.0.0.1".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+
+        let mut result = subject
+            .make(Box::new(StreamFactoryReal::new()), &args)
+            .unwrap();
+
+        let command = TestCommand {};
+        result.process(Box::new(command)).unwrap();
+        let received = stop_handle.stop();
+        assert_eq!(received, vec![Ok(UiShutdownRequest {}.tmb(1))]);
+    }
+
+    #[test]
+    fn factory_insists_on_tls_when_wss_is_specified() {
+        let port = find_free_port();
+        let args = [
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+            "--wss".to_string(),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+        let server = MockWebSocketsServer::new(port);
+        let stop_handle = server.start();
+
+        let result = subject.make(Box::new(StreamFactoryReal::new()), &args);
+
+        let _ = stop_handle.kill();
+        match result.err() {
+            Some(CommandError::ConnectionProblem(_)) => (),
+            x => panic!(
+                "Expected Some(CommandError::ConnectionProblem(_); got {:?} instead",
+                x
+            ),
+        }
+    }
+
+    #[derive(Debug)]
+    struct ChattyCommand {}
+
+    impl Command for ChattyCommand {
+        fn execute<'a>(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+            writeln!(context.stdout(), "Shutting down").unwrap();
+            writeln!(context.stderr(), "Are you sure?").unwrap();
+            match context.transact(UiShutdownRequest {}.tmb(1), 1000) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(CommandError::Other(format!("{:?}", e))),
+            }
+        }
+    }
+
+    #[test]
+    fn process_captured_keeps_what_the_command_prints_along_with_the_responses() {
+        let port = find_free_port();
+        let args = [
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+        let mut processor = subject
+            .make(Box::new(StreamFactoryReal::new()), &args)
+            .unwrap();
+
+        let result = processor.process_captured(Box::new(ChattyCommand {}));
+
+        stop_handle.stop();
+        assert_eq!(result.result, Ok(()));
+        assert_eq!(result.responses.len(), 1);
+        assert_eq!(result.responses[0].opcode, "shutdown".to_string());
+        assert_eq!(result.stdout, "Shutting down\n".to_string());
+        assert_eq!(result.stderr, "Are you sure?\n".to_string());
+    }
 }
@@ -6,7 +6,9 @@ use crate::commands::commands_common::{
     transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
 };
 use clap::{App, Arg, SubCommand};
-use masq_lib::messages::{UiConfigurationRequest, UiConfigurationResponse, NODE_NOT_RUNNING_ERROR};
+use masq_lib::messages::{
+    UiConfigurationRequest, UiConfigurationResponse, UiPaymentCurves, NODE_NOT_RUNNING_ERROR,
+};
 use masq_lib::short_writeln;
 use std::any::Any;
 use std::fmt::Debug;
@@ -84,6 +86,7 @@ impl ConfigurationCommand {
             &configuration.clandestine_port.to_string(),
         );
         Self::dump_configuration_line(stream, "Gas price:", &configuration.gas_price.to_string());
+        Self::dump_payment_curves(stream, &configuration.payment_curves);
         Self::dump_configuration_line(
             stream,
             "Mnemonic seed:",
@@ -113,6 +116,39 @@ impl ConfigurationCommand {
         );
     }
 
+    fn dump_payment_curves(stream: &mut dyn Write, curves: &UiPaymentCurves) {
+        Self::dump_configuration_line(
+            stream,
+            "Payment suggested after (sec):",
+            &curves.payment_suggested_after_sec.to_string(),
+        );
+        Self::dump_configuration_line(
+            stream,
+            "Payment grace before ban (sec):",
+            &curves.payment_grace_before_ban_sec.to_string(),
+        );
+        Self::dump_configuration_line(
+            stream,
+            "Permanent debt allowed (gwub):",
+            &curves.permanent_debt_allowed_gwub.to_string(),
+        );
+        Self::dump_configuration_line(
+            stream,
+            "Balance to decrease from (gwub):",
+            &curves.balance_to_decrease_from_gwub.to_string(),
+        );
+        Self::dump_configuration_line(
+            stream,
+            "Balance decreases for (sec):",
+            &curves.balance_decreases_for_sec.to_string(),
+        );
+        Self::dump_configuration_line(
+            stream,
+            "Unban when balance below (gwub):",
+            &curves.unban_when_balance_below_gwub.to_string(),
+        );
+    }
+
     fn dump_value_list(stream: &mut dyn Write, name: &str, values: &[String]) {
         if values.is_empty() {
             Self::dump_configuration_line(stream, name, "[?]");
@@ -214,6 +250,14 @@ mod tests {
             current_schema_version: "schema version".to_string(),
             clandestine_port: 1234,
             gas_price: 2345,
+            payment_curves: UiPaymentCurves {
+                payment_suggested_after_sec: 1000,
+                payment_grace_before_ban_sec: 1001,
+                permanent_debt_allowed_gwub: 1002,
+                balance_to_decrease_from_gwub: 1003,
+                balance_decreases_for_sec: 1004,
+                unban_when_balance_below_gwub: 1005,
+            },
             mnemonic_seed_opt: Some("mnemonic seed".to_string()),
             consuming_wallet_derivation_path_opt: Some("consuming path".to_string()),
             earning_wallet_address_opt: Some("earning address".to_string()),
@@ -250,6 +294,12 @@ mod tests {
 |Current schema version:           schema version\n\
 |Clandestine port:                 1234\n\
 |Gas price:                        2345\n\
+|Payment suggested after (sec):    1000\n\
+|Payment grace before ban (sec):   1001\n\
+|Permanent debt allowed (gwub):    1002\n\
+|Balance to decrease from (gwub):  1003\n\
+|Balance decreases for (sec):      1004\n\
+|Unban when balance below (gwub):  1005\n\
 |Mnemonic seed:                    mnemonic seed\n\
 |Consuming wallet derivation path: consuming path\n\
 |Earning wallet address:           earning address\n\
@@ -270,6 +320,14 @@ mod tests {
             current_schema_version: "schema version".to_string(),
             clandestine_port: 1234,
             gas_price: 2345,
+            payment_curves: UiPaymentCurves {
+                payment_suggested_after_sec: 1000,
+                payment_grace_before_ban_sec: 1001,
+                permanent_debt_allowed_gwub: 1002,
+                balance_to_decrease_from_gwub: 1003,
+                balance_decreases_for_sec: 1004,
+                unban_when_balance_below_gwub: 1005,
+            },
             mnemonic_seed_opt: None,
             consuming_wallet_derivation_path_opt: None,
             earning_wallet_address_opt: None,
@@ -304,6 +362,12 @@ NAME                              VALUE\n\
 Current schema version:           schema version\n\
 Clandestine port:                 1234\n\
 Gas price:                        2345\n\
+Payment suggested after (sec):    1000\n\
+Payment grace before ban (sec):   1001\n\
+Permanent debt allowed (gwub):    1002\n\
+Balance to decrease from (gwub):  1003\n\
+Balance decreases for (sec):      1004\n\
+Unban when balance below (gwub):  1005\n\
 Mnemonic seed:                    [?]\n\
 Consuming wallet derivation path: [?]\n\
 Earning wallet address:           [?]\n\
@@ -0,0 +1,579 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::CommandError::Payload;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, SubCommand};
+use masq_lib::messages::{UiFinancialsRequest, UiFinancialsResponse, NODE_NOT_RUNNING_ERROR};
+use masq_lib::short_writeln;
+use std::any::Any;
+use std::cmp::Ordering;
+use std::io::Write;
+
+const GWEI_PER_MASQ: u64 = 1_000_000_000;
+const MASQ_DECIMAL_PLACES: usize = 9;
+// The Node's database stores ages as signed 64-bit numbers, so this is as old as anything can be.
+pub const UNLIMITED_AGE_SEC: u64 = i64::MAX as u64;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FinancialsSort {
+    Amount,
+    Age,
+    Wallet,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FinancialsOutput {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FinancialsCommand {
+    pub minimum_amount_gwei: u64,
+    pub maximum_age_sec: u64,
+    pub sort: FinancialsSort,
+    pub output: FinancialsOutput,
+}
+
+pub fn financials_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("financials")
+        .about("Displays the running Node's payable and receivable accounts, with totals. Accounts that are too \
+               small or too old to pass the filters are left out of the list, but still count toward the totals.")
+        .arg(Arg::with_name("min-amount")
+            .help("Leave out accounts whose balance is less than this many MASQ (up to nine decimal places)")
+            .long("min-amount")
+            .value_name("MASQ")
+            .default_value("0")
+            .required(false)
+            .validator(validate_masq_amount)
+        )
+        .arg(Arg::with_name("max-age")
+            .help("Leave out accounts that haven't changed for more than this many seconds")
+            .long("max-age")
+            .value_name("SECONDS")
+            .required(false)
+            .validator(validate_age)
+        )
+        .arg(Arg::with_name("sort")
+            .help("amount lists the largest balances first; age lists the most recently changed accounts first; \
+                  wallet lists accounts in order of wallet address")
+            .long("sort")
+            .value_name("ORDER")
+            .possible_values(&["amount", "age", "wallet"])
+            .default_value("amount")
+            .required(false)
+            .case_insensitive(true)
+        )
+        .arg(Arg::with_name("json")
+            .help("Writes the report as JSON instead of a table. Amounts are in gwei, so that they're exact.")
+            .long("json")
+            .takes_value(false)
+            .conflicts_with("csv")
+        )
+        .arg(Arg::with_name("csv")
+            .help("Writes the accounts as comma-separated values instead of a table, one line per account. \
+                  Amounts are in gwei, so that they're exact.")
+            .long("csv")
+            .takes_value(false)
+        )
+}
+
+fn validate_masq_amount(amount: String) -> Result<(), String> {
+    masq_to_gwei(&amount).map(|_| ())
+}
+
+fn validate_age(age: String) -> Result<(), String> {
+    match age.parse::<u64>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "Age must be a whole number of seconds, not '{}'",
+            age
+        )),
+    }
+}
+
+fn masq_to_gwei(amount: &str) -> Result<u64, String> {
+    let complaint = || {
+        format!(
+            "Amount must be a nonnegative number of MASQ with no more than {} decimal places, not '{}'",
+            MASQ_DECIMAL_PLACES, amount
+        )
+    };
+    let (whole, fraction) = match amount.find('.') {
+        Some(idx) => (&amount[..idx], &amount[(idx + 1)..]),
+        None => (amount, ""),
+    };
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > MASQ_DECIMAL_PLACES
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(complaint());
+    }
+    let parse = |digits: &str| {
+        if digits.is_empty() {
+            Ok(0)
+        } else {
+            digits.parse::<u64>().map_err(|_| complaint())
+        }
+    };
+    let whole_gwei = parse(whole)?
+        .checked_mul(GWEI_PER_MASQ)
+        .ok_or_else(complaint)?;
+    let fraction_gwei = parse(&format!(
+        "{:0<width$}",
+        fraction,
+        width = MASQ_DECIMAL_PLACES
+    ))?;
+    whole_gwei.checked_add(fraction_gwei).ok_or_else(complaint)
+}
+
+fn gwei_to_masq(gwei: u64) -> String {
+    format!(
+        "{}.{:0width$}",
+        gwei / GWEI_PER_MASQ,
+        gwei % GWEI_PER_MASQ,
+        width = MASQ_DECIMAL_PLACES
+    )
+}
+
+impl Command for FinancialsCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let input = UiFinancialsRequest {
+            payable_minimum_amount: self.minimum_amount_gwei,
+            payable_maximum_age: self.maximum_age_sec,
+            receivable_minimum_amount: self.minimum_amount_gwei,
+            receivable_maximum_age: self.maximum_age_sec,
+        };
+        let output: Result<UiFinancialsResponse, CommandError> =
+            transaction(input, context, STANDARD_COMMAND_TIMEOUT_MILLIS);
+        let mut response = match output {
+            Ok(response) => response,
+            Err(Payload(code, message)) if code == NODE_NOT_RUNNING_ERROR => {
+                short_writeln!(
+                    context.stderr(),
+                    "MASQNode is not running; therefore its financials cannot be displayed."
+                );
+                return Err(Payload(code, message));
+            }
+            Err(e) => {
+                short_writeln!(context.stderr(), "Financials retrieval failed: {:?}", e);
+                return Err(e);
+            }
+        };
+        self.sort_accounts(&mut response);
+        match self.output {
+            FinancialsOutput::Table => Self::dump_table(context.stdout(), &response),
+            FinancialsOutput::Json => short_writeln!(
+                context.stdout(),
+                "{}",
+                serde_json::to_string_pretty(&response)
+                    .expect("Financials could not be rendered as JSON")
+            ),
+            FinancialsOutput::Csv => Self::dump_csv(context.stdout(), &response),
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl FinancialsCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match financials_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        let minimum_amount_gwei = masq_to_gwei(
+            matches
+                .value_of("min-amount")
+                .expect("min-amount is not properly defaulted"),
+        )?;
+        let maximum_age_sec = match matches.value_of("max-age") {
+            Some(age) => age
+                .parse::<u64>()
+                .map_err(|_| format!("Age must be a whole number of seconds, not '{}'", age))?,
+            None => UNLIMITED_AGE_SEC,
+        };
+        let sort = match matches
+            .value_of("sort")
+            .expect("sort is not properly defaulted")
+            .to_lowercase()
+            .as_str()
+        {
+            "age" => FinancialsSort::Age,
+            "wallet" => FinancialsSort::Wallet,
+            _ => FinancialsSort::Amount,
+        };
+        let output = if matches.is_present("json") {
+            FinancialsOutput::Json
+        } else if matches.is_present("csv") {
+            FinancialsOutput::Csv
+        } else {
+            FinancialsOutput::Table
+        };
+        Ok(Self {
+            minimum_amount_gwei,
+            maximum_age_sec,
+            sort,
+            output,
+        })
+    }
+
+    fn sort_accounts(&self, response: &mut UiFinancialsResponse) {
+        let sort = self.sort;
+        response.payables.sort_by(|a, b| {
+            Self::compare(
+                sort,
+                (&a.wallet, a.age, a.amount),
+                (&b.wallet, b.age, b.amount),
+            )
+        });
+        response.receivables.sort_by(|a, b| {
+            Self::compare(
+                sort,
+                (&a.wallet, a.age, a.amount),
+                (&b.wallet, b.age, b.amount),
+            )
+        });
+    }
+
+    fn compare(sort: FinancialsSort, a: (&str, u64, u64), b: (&str, u64, u64)) -> Ordering {
+        let (a_wallet, a_age, a_amount) = a;
+        let (b_wallet, b_age, b_amount) = b;
+        match sort {
+            FinancialsSort::Amount => b_amount.cmp(&a_amount).then(a_age.cmp(&b_age)),
+            FinancialsSort::Age => a_age.cmp(&b_age).then(b_amount.cmp(&a_amount)),
+            FinancialsSort::Wallet => a_wallet.cmp(b_wallet),
+        }
+    }
+
+    fn dump_table(stream: &mut dyn Write, response: &UiFinancialsResponse) {
+        short_writeln!(stream, "Payable:");
+        if response.payables.is_empty() {
+            short_writeln!(stream, "No payable accounts pass the filters");
+        } else {
+            Self::dump_account_line(
+                stream,
+                "WALLET",
+                "AGE (SEC)",
+                "AMOUNT (MASQ)",
+                "PENDING TRANSACTION",
+            );
+            response.payables.iter().for_each(|account| {
+                Self::dump_account_line(
+                    stream,
+                    &account.wallet,
+                    &account.age.to_string(),
+                    &gwei_to_masq(account.amount),
+                    account.pending_transaction.as_deref().unwrap_or(""),
+                )
+            });
+        }
+        short_writeln!(
+            stream,
+            "Total payable: {} MASQ\n",
+            gwei_to_masq(response.total_payable)
+        );
+        short_writeln!(stream, "Receivable:");
+        if response.receivables.is_empty() {
+            short_writeln!(stream, "No receivable accounts pass the filters");
+        } else {
+            Self::dump_account_line(stream, "WALLET", "AGE (SEC)", "AMOUNT (MASQ)", "");
+            response.receivables.iter().for_each(|account| {
+                Self::dump_account_line(
+                    stream,
+                    &account.wallet,
+                    &account.age.to_string(),
+                    &gwei_to_masq(account.amount),
+                    "",
+                )
+            });
+        }
+        short_writeln!(
+            stream,
+            "Total receivable: {} MASQ",
+            gwei_to_masq(response.total_receivable)
+        );
+    }
+
+    fn dump_account_line(
+        stream: &mut dyn Write,
+        wallet: &str,
+        age: &str,
+        amount: &str,
+        pending: &str,
+    ) {
+        let line = format!("{:<42}  {:>11}  {:>20}  {}", wallet, age, amount, pending);
+        short_writeln!(stream, "{}", line.trim_end());
+    }
+
+    fn dump_csv(stream: &mut dyn Write, response: &UiFinancialsResponse) {
+        short_writeln!(
+            stream,
+            "account,wallet,age_sec,amount_gwei,pending_transaction"
+        );
+        response.payables.iter().for_each(|account| {
+            short_writeln!(
+                stream,
+                "payable,{},{},{},{}",
+                account.wallet,
+                account.age,
+                account.amount,
+                account.pending_transaction.as_deref().unwrap_or("")
+            )
+        });
+        response.receivables.iter().for_each(|account| {
+            short_writeln!(
+                stream,
+                "receivable,{},{},{},",
+                account.wallet,
+                account.age,
+                account.amount
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::{ToMessageBody, UiPayableAccount, UiReceivableAccount};
+    use std::sync::{Arc, Mutex};
+
+    fn make_command(pieces: Vec<&str>) -> Result<FinancialsCommand, String> {
+        FinancialsCommand::new(pieces.into_iter().map(|s| s.to_string()).collect())
+    }
+
+    fn make_response() -> UiFinancialsResponse {
+        UiFinancialsResponse {
+            payables: vec![
+                UiPayableAccount {
+                    wallet: "0x0000000000000000000000000000000000000002".to_string(),
+                    age: 3600,
+                    amount: 2_500_000_000,
+                    pending_transaction: None,
+                },
+                UiPayableAccount {
+                    wallet: "0x0000000000000000000000000000000000000001".to_string(),
+                    age: 60,
+                    amount: 1_000_000_000,
+                    pending_transaction: Some("0x1234".to_string()),
+                },
+            ],
+            total_payable: 4_000_000_000,
+            receivables: vec![UiReceivableAccount {
+                wallet: "0x0000000000000000000000000000000000000003".to_string(),
+                age: 120,
+                amount: 123_456_789,
+            }],
+            total_receivable: 123_456_789,
+        }
+    }
+
+    #[test]
+    fn testing_command_factory_here() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec![
+                "financials".to_string(),
+                "--min-amount".to_string(),
+                "1.5".to_string(),
+            ])
+            .unwrap();
+
+        let financials_command: &FinancialsCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            financials_command,
+            &FinancialsCommand {
+                minimum_amount_gwei: 1_500_000_000,
+                maximum_age_sec: UNLIMITED_AGE_SEC,
+                sort: FinancialsSort::Amount,
+                output: FinancialsOutput::Table,
+            }
+        );
+    }
+
+    #[test]
+    fn new_understands_every_option() {
+        let result = make_command(vec![
+            "financials",
+            "--min-amount",
+            "12",
+            "--max-age",
+            "86400",
+            "--sort",
+            "Wallet",
+            "--csv",
+        ]);
+
+        assert_eq!(
+            result,
+            Ok(FinancialsCommand {
+                minimum_amount_gwei: 12_000_000_000,
+                maximum_age_sec: 86400,
+                sort: FinancialsSort::Wallet,
+                output: FinancialsOutput::Csv,
+            })
+        );
+        assert!(make_command(vec!["financials", "--json", "--csv"]).is_err());
+        assert!(make_command(vec!["financials", "--max-age", "-1"]).is_err());
+        assert!(make_command(vec!["financials", "--sort", "size"]).is_err());
+    }
+
+    #[test]
+    fn masq_amounts_are_converted_to_gwei_exactly() {
+        assert_eq!(masq_to_gwei("0"), Ok(0));
+        assert_eq!(masq_to_gwei("3"), Ok(3_000_000_000));
+        assert_eq!(masq_to_gwei("0.000000001"), Ok(1));
+        assert_eq!(masq_to_gwei(".25"), Ok(250_000_000));
+        assert_eq!(masq_to_gwei("7."), Ok(7_000_000_000));
+        vec!["", ".", "-1", "1.0000000001", "1,5", "abc", "18446744074"]
+            .into_iter()
+            .for_each(|amount| assert!(masq_to_gwei(amount).is_err(), "{}", amount));
+        assert_eq!(gwei_to_masq(1_234_567_890), "1.234567890".to_string());
+        assert_eq!(gwei_to_masq(5), "0.000000005".to_string());
+    }
+
+    #[test]
+    fn financials_are_requested_with_the_filters_and_displayed_as_a_table() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(make_response().tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec![
+            "financials",
+            "--min-amount",
+            "0.1",
+            "--max-age",
+            "7200",
+        ])
+        .unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiFinancialsRequest {
+                    payable_minimum_amount: 100_000_000,
+                    payable_maximum_age: 7200,
+                    receivable_minimum_amount: 100_000_000,
+                    receivable_maximum_age: 7200,
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Payable:\n\
+WALLET                                        AGE (SEC)         AMOUNT (MASQ)  PENDING TRANSACTION\n\
+0x0000000000000000000000000000000000000002         3600           2.500000000\n\
+0x0000000000000000000000000000000000000001           60           1.000000000  0x1234\n\
+Total payable: 4.000000000 MASQ\n\
+\n\
+Receivable:\n\
+WALLET                                        AGE (SEC)         AMOUNT (MASQ)\n\
+0x0000000000000000000000000000000000000003          120           0.123456789\n\
+Total receivable: 0.123456789 MASQ\n"
+        );
+    }
+
+    #[test]
+    fn empty_account_lists_are_reported_with_their_totals() {
+        let mut context = CommandContextMock::new().transact_result(Ok(UiFinancialsResponse {
+            payables: vec![],
+            total_payable: 0,
+            receivables: vec![],
+            total_receivable: 42,
+        }
+        .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["financials"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Payable:\n\
+No payable accounts pass the filters\n\
+Total payable: 0.000000000 MASQ\n\
+\n\
+Receivable:\n\
+No receivable accounts pass the filters\n\
+Total receivable: 0.000000042 MASQ\n"
+        );
+    }
+
+    #[test]
+    fn csv_output_is_sorted_as_requested() {
+        let mut context = CommandContextMock::new().transact_result(Ok(make_response().tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["financials", "--sort", "age", "--csv"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "account,wallet,age_sec,amount_gwei,pending_transaction\n\
+payable,0x0000000000000000000000000000000000000001,60,1000000000,0x1234\n\
+payable,0x0000000000000000000000000000000000000002,3600,2500000000,\n\
+receivable,0x0000000000000000000000000000000000000003,120,123456789,\n"
+        );
+    }
+
+    #[test]
+    fn json_output_round_trips_to_the_response() {
+        let mut context = CommandContextMock::new().transact_result(Ok(make_response().tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["financials", "--json"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let json = stdout_arc.lock().unwrap().get_string();
+        let actual: UiFinancialsResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual, make_response());
+    }
+
+    #[test]
+    fn financials_command_handles_node_not_running() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::PayloadError(NODE_NOT_RUNNING_ERROR, "irrelevant".to_string()),
+        ));
+        let stderr_arc = context.stderr_arc();
+        let subject = make_command(vec!["financials"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::Payload(
+                NODE_NOT_RUNNING_ERROR,
+                "irrelevant".to_string()
+            ))
+        );
+        assert_eq!(
+            stderr_arc.lock().unwrap().get_string(),
+            "MASQNode is not running; therefore its financials cannot be displayed.\n"
+        );
+    }
+}
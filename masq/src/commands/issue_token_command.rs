@@ -0,0 +1,164 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, SubCommand};
+use masq_lib::messages::{UiIssueTokenRequest, UiIssueTokenResponse};
+use masq_lib::short_writeln;
+use std::any::Any;
+
+#[derive(Debug, PartialEq)]
+pub struct IssueTokenCommand {
+    pub current_token_opt: Option<String>,
+}
+
+pub fn issue_token_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("issue-token")
+        .about("Asks the Daemon for a token that user interfaces can present to a Node running with --ui-auth on. \
+               Use it with masq --ui-token. The first token for a data directory is written into a file only \
+               you can read; later ones are printed. Must be the first command after masq starts up.")
+        .arg(Arg::with_name ("current-token")
+            .help ("A token you already have--leave it out only if no tokens have been issued for this data directory yet")
+            .index (1)
+            .required (false)
+            .case_insensitive(false)
+        )
+}
+
+impl Command for IssueTokenCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let input = UiIssueTokenRequest {
+            current_token_opt: self.current_token_opt.clone(),
+        };
+        let msg: UiIssueTokenResponse =
+            transaction(input, context, STANDARD_COMMAND_TIMEOUT_MILLIS)?;
+        match (msg.token_opt, msg.token_file_opt) {
+            (Some(token), _) => short_writeln!(context.stdout(), "{}", token),
+            (None, Some(token_file)) => short_writeln!(
+                context.stdout(),
+                "Your first UI token is in {}; only you can read it",
+                token_file
+            ),
+            (None, None) => short_writeln!(context.stderr(), "The Daemon issued no UI token"),
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl IssueTokenCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match issue_token_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        Ok(Self {
+            current_token_opt: matches.value_of("current-token").map(|r| r.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::commands::commands_common::{Command, CommandError};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::{ToMessageBody, UNAUTHORIZED_ERROR};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn testing_command_factory_here() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec!["issue-token".to_string(), "booga".to_string()])
+            .unwrap();
+
+        let issue_token_command: &IssueTokenCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            issue_token_command,
+            &IssueTokenCommand {
+                current_token_opt: Some("booga".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn issue_token_command_prints_new_token() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiIssueTokenResponse {
+                token_opt: Some("NewToken".to_string()),
+                token_file_opt: None,
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let stderr_arc = context.stderr_arc();
+        let subject = IssueTokenCommand::new(vec!["issue-token".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stdout_arc.lock().unwrap().get_string(), "NewToken\n");
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiIssueTokenRequest {
+                    current_token_opt: None,
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        )
+    }
+
+    #[test]
+    fn issue_token_command_tells_where_the_first_token_is() {
+        let mut context = CommandContextMock::new().transact_result(Ok(UiIssueTokenResponse {
+            token_opt: None,
+            token_file_opt: Some("/home/booga/.local/share/MASQ/ui-token".to_string()),
+        }
+        .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let stderr_arc = context.stderr_arc();
+        let subject = IssueTokenCommand::new(vec!["issue-token".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Your first UI token is in /home/booga/.local/share/MASQ/ui-token; only you can read it\n"
+        );
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+    }
+
+    #[test]
+    fn issue_token_command_reports_refusal() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::PayloadError(UNAUTHORIZED_ERROR, "No dice".to_string()),
+        ));
+        let subject =
+            IssueTokenCommand::new(vec!["issue-token".to_string(), "booga".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::Payload(
+                UNAUTHORIZED_ERROR,
+                "No dice".to_string()
+            ))
+        )
+    }
+}
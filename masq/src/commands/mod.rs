@@ -6,8 +6,13 @@ pub mod commands_common;
 pub mod configuration_command;
 pub mod crash_command;
 pub mod descriptor_command;
+pub mod financials_command;
 pub mod generate_wallets_command;
+pub mod issue_token_command;
+pub mod neighborhood_command;
+pub mod profile_command;
 pub mod recover_wallets_command;
+pub mod rotate_keys_command;
 pub mod setup_command;
 pub mod shutdown_command;
 pub mod start_command;
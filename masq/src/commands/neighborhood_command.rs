@@ -0,0 +1,274 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::CommandError::{Other, Payload};
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, SubCommand};
+use masq_lib::messages::{UiNeighborhoodRequest, UiNeighborhoodResponse, NODE_NOT_RUNNING_ERROR};
+use masq_lib::short_writeln;
+use serde_json::json;
+use std::any::Any;
+
+#[derive(Debug, PartialEq)]
+pub struct NeighborhoodCommand {
+    pub dot_graph: bool,
+}
+
+pub fn neighborhood_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("neighborhood")
+        .about("Displays the Neighborhood as the running Node knows it: every Node, its neighbors, its rate pack, whether it can be reached, and how many hops away from this Node it is.")
+        .arg(Arg::with_name ("format")
+            .help ("json for this Node's public key and a structured list of Nodes; dot for a graph that Graphviz can draw")
+            .long ("format")
+            .value_name ("FORMAT")
+            .possible_values (&["json", "dot"])
+            .default_value ("json")
+            .required (false)
+            .case_insensitive (true)
+        )
+}
+
+impl Command for NeighborhoodCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let input = UiNeighborhoodRequest {
+            dot_graph: self.dot_graph,
+        };
+        let output: Result<UiNeighborhoodResponse, CommandError> =
+            transaction(input, context, STANDARD_COMMAND_TIMEOUT_MILLIS);
+        let response = match output {
+            Ok(response) => response,
+            Err(Payload(code, message)) if code == NODE_NOT_RUNNING_ERROR => {
+                short_writeln!(
+                    context.stderr(),
+                    "MASQNode is not running; therefore its Neighborhood cannot be displayed."
+                );
+                return Err(Payload(code, message));
+            }
+            Err(e) => {
+                short_writeln!(context.stderr(), "Neighborhood retrieval failed: {:?}", e);
+                return Err(e);
+            }
+        };
+        let text = if self.dot_graph {
+            match response.dot_graph_opt {
+                Some(dot_graph) => dot_graph,
+                None => {
+                    short_writeln!(context.stderr(), "Node did not supply a DOT graph");
+                    return Err(Other("Node did not supply a DOT graph".to_string()));
+                }
+            }
+        } else {
+            // ourPublicKey is what tells a script which of the Nodes is this one.
+            serde_json::to_string_pretty(&json!({
+                "ourPublicKey": response.our_public_key,
+                "nodes": response.nodes,
+            }))
+            .expect("Neighborhood could not be rendered as JSON")
+        };
+        short_writeln!(context.stdout(), "{}", text);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl NeighborhoodCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match neighborhood_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        Ok(Self {
+            dot_graph: matches
+                .value_of("format")
+                .expect("format is not properly defaulted")
+                .eq_ignore_ascii_case("dot"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryError, CommandFactoryReal};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::{ToMessageBody, UiNeighborhoodNode, UiRatePack};
+    use std::sync::{Arc, Mutex};
+
+    fn make_response(dot_graph_opt: Option<String>) -> UiNeighborhoodResponse {
+        UiNeighborhoodResponse {
+            our_public_key: "AQIDBA".to_string(),
+            nodes: vec![UiNeighborhoodNode {
+                public_key: "AQIDBA".to_string(),
+                node_addr_opt: Some("1.2.3.4:1234".to_string()),
+                version: 2,
+                rate_pack: UiRatePack {
+                    routing_byte_rate: 1,
+                    routing_service_rate: 2,
+                    exit_byte_rate: 3,
+                    exit_service_rate: 4,
+                },
+                accepts_connections: true,
+                routes_data: true,
+                desirable: true,
+                neighbors: vec![],
+                hops_away_opt: Some(0),
+            }],
+            dot_graph_opt,
+        }
+    }
+
+    #[test]
+    fn testing_command_factory_with_default_format() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject.make(vec!["neighborhood".to_string()]).unwrap();
+
+        let neighborhood_command: &NeighborhoodCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            neighborhood_command,
+            &NeighborhoodCommand { dot_graph: false }
+        );
+    }
+
+    #[test]
+    fn testing_command_factory_with_dot_format() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec![
+                "neighborhood".to_string(),
+                "--format".to_string(),
+                "DOT".to_string(),
+            ])
+            .unwrap();
+
+        let neighborhood_command: &NeighborhoodCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            neighborhood_command,
+            &NeighborhoodCommand { dot_graph: true }
+        );
+    }
+
+    #[test]
+    fn testing_command_factory_with_bad_format() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject.make(vec![
+            "neighborhood".to_string(),
+            "--format".to_string(),
+            "booga".to_string(),
+        ]);
+
+        match result {
+            Err(CommandFactoryError::CommandSyntax(msg)) => {
+                assert!(msg.contains("booga"), "{}", msg)
+            }
+            x => panic!("Expected CommandSyntax error, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn neighborhood_command_writes_json() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(make_response(None).tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let stderr_arc = context.stderr_arc();
+        let subject = NeighborhoodCommand::new(vec!["neighborhood".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let stdout = stdout_arc.lock().unwrap().get_string();
+        assert!(stdout.starts_with("{\n"), "{}", stdout);
+        assert!(
+            stdout.contains("\"ourPublicKey\": \"AQIDBA\""),
+            "{}",
+            stdout
+        );
+        assert!(stdout.contains("\"nodes\": [\n    {\n"), "{}", stdout);
+        assert!(stdout.contains("\"publicKey\": \"AQIDBA\","), "{}", stdout);
+        assert!(
+            stdout.contains("\"nodeAddrOpt\": \"1.2.3.4:1234\","),
+            "{}",
+            stdout
+        );
+        assert!(stdout.contains("\"exitServiceRate\": 4"), "{}", stdout);
+        assert!(stdout.contains("\"hopsAwayOpt\": 0"), "{}", stdout);
+        assert!(!stdout.contains("dotGraphOpt"), "{}", stdout);
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiNeighborhoodRequest { dot_graph: false }.tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        )
+    }
+
+    #[test]
+    fn neighborhood_command_writes_dot_graph() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(make_response(Some("digraph db { }".to_string())).tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = NeighborhoodCommand { dot_graph: true };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stdout_arc.lock().unwrap().get_string(), "digraph db { }\n");
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiNeighborhoodRequest { dot_graph: true }.tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        )
+    }
+
+    #[test]
+    fn neighborhood_command_complains_when_node_is_not_running() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::PayloadError(NODE_NOT_RUNNING_ERROR, "irrelevant".to_string()),
+        ));
+        let stderr_arc = context.stderr_arc();
+        let subject = NeighborhoodCommand { dot_graph: false };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(Payload(NODE_NOT_RUNNING_ERROR, "irrelevant".to_string()))
+        );
+        assert_eq!(
+            stderr_arc.lock().unwrap().get_string(),
+            "MASQNode is not running; therefore its Neighborhood cannot be displayed.\n"
+        );
+    }
+
+    #[test]
+    fn neighborhood_command_handles_send_failure() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::ConnectionDropped("tummyache".to_string()),
+        ));
+        let subject = NeighborhoodCommand { dot_graph: false };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::ConnectionProblem("tummyache".to_string()))
+        )
+    }
+}
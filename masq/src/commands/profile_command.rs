@@ -0,0 +1,301 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use crate::commands::setup_command::SetupCommand;
+use clap::{App, AppSettings, Arg, SubCommand};
+use masq_lib::messages::{
+    UiProfileDeleteRequest, UiProfileDeleteResponse, UiProfileListRequest, UiProfileListResponse,
+    UiProfileLoadRequest, UiProfileLoadResponse, UiProfileSaveRequest, UiProfileSaveResponse,
+    UiSetupInner,
+};
+use masq_lib::short_writeln;
+use std::any::Any;
+
+#[derive(Debug, PartialEq)]
+pub enum ProfileCommand {
+    Save(String),
+    List,
+    Load(String),
+    Delete(String),
+}
+
+fn profile_name_arg() -> Arg<'static, 'static> {
+    Arg::with_name("name")
+        .help("The name of the profile: letters, digits, '-' and '_' only")
+        .index(1)
+        .required(true)
+        .case_insensitive(false)
+}
+
+pub fn profile_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("profile")
+        .about("Saves, lists, loads, and deletes named setups that the Daemon keeps on disk. Passwords and \
+               private keys are never saved in profiles.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("save")
+                .about("Saves the values you've specified in the current setup under a name, replacing any profile already saved under that name")
+                .arg(profile_name_arg()),
+        )
+        .subcommand(SubCommand::with_name("list").about("Lists the names of the saved profiles"))
+        .subcommand(
+            SubCommand::with_name("load")
+                .about("Replaces the values you've specified in the setup with those from a profile. Only works when the Node isn't running.")
+                .arg(profile_name_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .about("Deletes a saved profile")
+                .arg(profile_name_arg()),
+        )
+}
+
+impl Command for ProfileCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        match self {
+            ProfileCommand::Save(name) => {
+                let response: UiProfileSaveResponse = transaction(
+                    UiProfileSaveRequest { name: name.clone() },
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                if response.saved.is_empty() {
+                    short_writeln!(
+                        context.stdout(),
+                        "Profile '{}' saved, but it's empty: you haven't specified any setup values that can be saved",
+                        name
+                    );
+                } else {
+                    short_writeln!(
+                        context.stdout(),
+                        "Profile '{}' saved with {}",
+                        name,
+                        response.saved.join(", ")
+                    );
+                }
+            }
+            ProfileCommand::List => {
+                let response: UiProfileListResponse = transaction(
+                    UiProfileListRequest {},
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                if response.profiles.is_empty() {
+                    short_writeln!(context.stdout(), "No profiles have been saved");
+                } else {
+                    response
+                        .profiles
+                        .iter()
+                        .for_each(|profile| short_writeln!(context.stdout(), "{}", profile));
+                }
+            }
+            ProfileCommand::Load(name) => {
+                let response: UiProfileLoadResponse = transaction(
+                    UiProfileLoadRequest { name: name.clone() },
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                short_writeln!(context.stdout(), "Profile '{}' loaded:\n", name);
+                SetupCommand::dump_setup(UiSetupInner::from(response), context.stdout());
+            }
+            ProfileCommand::Delete(name) => {
+                let _: UiProfileDeleteResponse = transaction(
+                    UiProfileDeleteRequest { name: name.clone() },
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                short_writeln!(context.stdout(), "Profile '{}' deleted", name);
+            }
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl ProfileCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match profile_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        let name_of = |sub_matches: &clap::ArgMatches| {
+            sub_matches
+                .value_of("name")
+                .expect("name is required")
+                .to_string()
+        };
+        match matches.subcommand() {
+            ("save", Some(sub_matches)) => Ok(ProfileCommand::Save(name_of(sub_matches))),
+            ("list", _) => Ok(ProfileCommand::List),
+            ("load", Some(sub_matches)) => Ok(ProfileCommand::Load(name_of(sub_matches))),
+            ("delete", Some(sub_matches)) => Ok(ProfileCommand::Delete(name_of(sub_matches))),
+            (other, _) => Err(format!("Unrecognized profile subcommand '{}'", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::UiSetupResponseValueStatus::Set;
+    use masq_lib::messages::{ToMessageBody, UiSetupResponseValue, PROFILE_ERROR};
+    use std::sync::{Arc, Mutex};
+
+    fn make_command(pieces: Vec<&str>) -> Result<ProfileCommand, String> {
+        ProfileCommand::new(pieces.into_iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn testing_command_factory_here() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec![
+                "profile".to_string(),
+                "load".to_string(),
+                "ropsten".to_string(),
+            ])
+            .unwrap();
+
+        let profile_command: &ProfileCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            profile_command,
+            &ProfileCommand::Load("ropsten".to_string())
+        );
+    }
+
+    #[test]
+    fn new_recognizes_every_subcommand() {
+        assert_eq!(
+            make_command(vec!["profile", "save", "mainnet"]),
+            Ok(ProfileCommand::Save("mainnet".to_string()))
+        );
+        assert_eq!(
+            make_command(vec!["profile", "list"]),
+            Ok(ProfileCommand::List)
+        );
+        assert_eq!(
+            make_command(vec!["profile", "delete", "mainnet"]),
+            Ok(ProfileCommand::Delete("mainnet".to_string()))
+        );
+        assert!(make_command(vec!["profile", "save"]).is_err());
+        assert!(make_command(vec!["profile"]).is_err());
+    }
+
+    #[test]
+    fn save_reports_what_was_saved() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiProfileSaveResponse {
+                saved: vec!["chain".to_string(), "log-level".to_string()],
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["profile", "save", "mainnet"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Profile 'mainnet' saved with chain, log-level\n"
+        );
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiProfileSaveRequest {
+                    name: "mainnet".to_string()
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+    }
+
+    #[test]
+    fn list_prints_profile_names_or_says_there_are_none() {
+        let mut context = CommandContextMock::new()
+            .transact_result(Ok(UiProfileListResponse {
+                profiles: vec!["mainnet".to_string(), "zero-hop".to_string()],
+            }
+            .tmb(0)))
+            .transact_result(Ok(UiProfileListResponse { profiles: vec![] }.tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["profile", "list"]).unwrap();
+
+        subject.execute(&mut context).unwrap();
+        subject.execute(&mut context).unwrap();
+
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "mainnet\nzero-hop\nNo profiles have been saved\n"
+        );
+    }
+
+    #[test]
+    fn load_prints_the_resulting_setup() {
+        let mut context = CommandContextMock::new().transact_result(Ok(UiProfileLoadResponse {
+            values: vec![UiSetupResponseValue::new("chain", "ropsten", Set)],
+            errors: vec![],
+        }
+        .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["profile", "load", "ropsten"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Profile 'ropsten' loaded:\n\n\
+NAME                   VALUE                                                            STATUS\n\
+chain                  ropsten                                                          Set\n\
+\n"
+        );
+    }
+
+    #[test]
+    fn delete_reports_deletion() {
+        let mut context =
+            CommandContextMock::new().transact_result(Ok(UiProfileDeleteResponse {}.tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_command(vec!["profile", "delete", "mainnet"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Profile 'mainnet' deleted\n"
+        );
+    }
+
+    #[test]
+    fn daemon_complaints_are_passed_along() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::PayloadError(PROFILE_ERROR, "No profile named 'nope'".to_string()),
+        ));
+        let subject = make_command(vec!["profile", "load", "nope"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::Payload(
+                PROFILE_ERROR,
+                "No profile named 'nope'".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, SubCommand};
+use masq_lib::messages::{UiRotateKeysRequest, UiRotateKeysResponse};
+use masq_lib::short_writeln;
+use std::any::Any;
+
+#[derive(Debug, PartialEq)]
+pub struct RotateKeysCommand {
+    pub db_password: String,
+}
+
+pub fn rotate_keys_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("rotate-keys")
+        .about("Gives the Node a brand-new identity: new key pairs are stored in the database and take effect the next time the Node starts. Neighbors will not recognize the Node under its old public key.")
+        .arg(Arg::with_name ("db-password")
+            .help ("The current database password (a password must be set to use this command)")
+            .value_name("DB-PASSWORD")
+            .index (1)
+            .required (true)
+            .case_insensitive(false)
+        )
+}
+
+impl Command for RotateKeysCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let input = UiRotateKeysRequest {
+            db_password: self.db_password.clone(),
+        };
+        let msg: UiRotateKeysResponse =
+            transaction(input, context, STANDARD_COMMAND_TIMEOUT_MILLIS)?;
+        short_writeln!(
+            context.stdout(),
+            "New public key: {}\nRestart the Node to start using it.",
+            msg.public_key
+        );
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl RotateKeysCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match rotate_keys_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        Ok(Self {
+            db_password: matches
+                .value_of("db-password")
+                .expect("db-password is not properly required")
+                .to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryError, CommandFactoryReal};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::ToMessageBody;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn testing_command_factory_with_good_command() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec!["rotate-keys".to_string(), "bonkers".to_string()])
+            .unwrap();
+
+        let rotate_keys_command: &RotateKeysCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            rotate_keys_command,
+            &RotateKeysCommand {
+                db_password: "bonkers".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn testing_command_factory_without_password() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject.make(vec!["rotate-keys".to_string()]);
+
+        match result {
+            Err(CommandFactoryError::CommandSyntax(msg)) => {
+                assert!(msg.contains("<DB-PASSWORD>"), "{}", msg)
+            }
+            x => panic!("Expected CommandSyntax error, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn rotate_keys_command_works() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiRotateKeysResponse {
+                public_key: "AQIDBAUGBwg".to_string(),
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let stderr_arc = context.stderr_arc();
+        let subject =
+            RotateKeysCommand::new(vec!["rotate-keys".to_string(), "bonkers".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "New public key: AQIDBAUGBwg\nRestart the Node to start using it.\n"
+        );
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiRotateKeysRequest {
+                    db_password: "bonkers".to_string(),
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        )
+    }
+
+    #[test]
+    fn rotate_keys_command_handles_send_failure() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::ConnectionDropped("tummyache".to_string()),
+        ));
+        let subject =
+            RotateKeysCommand::new(vec!["rotate-keys".to_string(), "bonkers".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::ConnectionProblem("tummyache".to_string()))
+        )
+    }
+}
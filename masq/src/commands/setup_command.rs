@@ -89,7 +89,7 @@ impl SetupCommand {
         }
     }
 
-    fn dump_setup(mut inner: UiSetupInner, stdout: &mut dyn Write) {
+    pub fn dump_setup(mut inner: UiSetupInner, stdout: &mut dyn Write) {
         inner.values.sort_by(|a, b| {
             a.name
                 .partial_cmp(&b.name)
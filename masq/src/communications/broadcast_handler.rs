@@ -3,9 +3,12 @@
 use crate::commands::change_password_command::ChangePasswordCommand;
 use crate::commands::setup_command::SetupCommand;
 use crate::notifications::crashed_notification::CrashNotifier;
+use crate::notifications::restart_notification::RestartNotifier;
 use crossbeam_channel::{unbounded, Receiver, RecvError, Sender};
 use masq_lib::messages::{
-    FromMessageBody, UiNewPasswordBroadcast, UiNodeCrashedBroadcast, UiSetupBroadcast,
+    FromMessageBody, UiNewPasswordBroadcast, UiNodeCrashedBroadcast,
+    UiNodeRestartAbandonedBroadcast, UiNodeRestartedBroadcast, UiNodeRestartingBroadcast,
+    UiSetupBroadcast,
 };
 use masq_lib::ui_gateway::MessageBody;
 use std::fmt::Debug;
@@ -70,6 +73,14 @@ impl BroadcastHandlerReal {
                     SetupCommand::handle_broadcast(body, stdout);
                 } else if let Ok((body, _)) = UiNodeCrashedBroadcast::fmb(message_body.clone()) {
                     CrashNotifier::handle_broadcast(body, stdout);
+                } else if let Ok((body, _)) = UiNodeRestartingBroadcast::fmb(message_body.clone()) {
+                    RestartNotifier::handle_restarting(body, stdout);
+                } else if let Ok((body, _)) = UiNodeRestartedBroadcast::fmb(message_body.clone()) {
+                    RestartNotifier::handle_restarted(body, stdout);
+                } else if let Ok((body, _)) =
+                    UiNodeRestartAbandonedBroadcast::fmb(message_body.clone())
+                {
+                    RestartNotifier::handle_abandoned(body, stdout);
                 } else if let Ok((_, _)) = UiNewPasswordBroadcast::fmb(message_body.clone()) {
                     ChangePasswordCommand::handle_broadcast(stdout);
                 } else {
@@ -100,11 +111,17 @@ pub trait StreamFactory: Send + Debug {
 }
 
 #[derive(Clone, PartialEq, Debug)]
-pub struct StreamFactoryReal {}
+pub struct StreamFactoryReal {
+    stdout_is_reserved: bool,
+}
 
 impl StreamFactory for StreamFactoryReal {
     fn make(&self) -> (Box<dyn Write>, Box<dyn Write>) {
-        (Box::new(std::io::stdout()), Box::new(std::io::stderr()))
+        if self.stdout_is_reserved {
+            (Box::new(std::io::stderr()), Box::new(std::io::stderr()))
+        } else {
+            (Box::new(std::io::stdout()), Box::new(std::io::stderr()))
+        }
     }
 }
 
@@ -116,7 +133,16 @@ impl Default for StreamFactoryReal {
 
 impl StreamFactoryReal {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            stdout_is_reserved: false,
+        }
+    }
+
+    // For when stdout carries nothing but --json output: broadcasts go to stderr instead.
+    pub fn new_stderr_only() -> Self {
+        Self {
+            stdout_is_reserved: true,
+        }
     }
 }
 
@@ -125,7 +151,9 @@ mod tests {
     use super::*;
     use crate::test_utils::mocks::TestStreamFactory;
     use masq_lib::messages::UiSetupBroadcast;
-    use masq_lib::messages::{CrashReason, ToMessageBody, UiNodeCrashedBroadcast};
+    use masq_lib::messages::{
+        CrashReason, ToMessageBody, UiNodeCrashedBroadcast, UiNodeRestartedBroadcast,
+    };
     use masq_lib::ui_gateway::MessagePath;
 
     #[test]
@@ -189,6 +217,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn broadcast_of_restarted_triggers_correct_handler() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new().start(Box::new(factory));
+        let message = UiNodeRestartedBroadcast {
+            new_process_id: 2345,
+            redirect_ui_port: 5432,
+            attempt: 1,
+        }
+        .tmb(0);
+
+        subject.send(message);
+
+        let stdout = handle.stdout_so_far();
+        assert_eq!(
+            stdout,
+            "\nThe Daemon restarted the Node as process 2345, listening for UIs on port 5432 (attempt 1).\n\nmasq> ".to_string()
+        );
+        assert_eq!(
+            handle.stderr_so_far(),
+            "".to_string(),
+            "stderr: '{}'",
+            stdout
+        );
+    }
+
     #[test]
     fn broadcast_of_new_password_triggers_correct_handler() {
         let (factory, handle) = TestStreamFactory::new();
@@ -1,9 +1,9 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
+use crate::communications::ui_stream::UiStream;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use masq_lib::ui_gateway::MessageBody;
 use masq_lib::ui_traffic_converter::UiTrafficConverter;
-use std::net::TcpStream;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use websocket::receiver::Reader;
@@ -42,7 +42,7 @@ impl ClientListener {
 
     pub fn start(
         &self,
-        listener_half: Reader<TcpStream>,
+        listener_half: Reader<UiStream>,
         message_body_tx: Sender<Result<MessageBody, ClientListenerError>>,
     ) {
         let thread = ClientListenerThread::new(listener_half, message_body_tx);
@@ -72,13 +72,13 @@ impl ClientListener {
 }
 
 struct ClientListenerThread {
-    listener_half: Reader<TcpStream>,
+    listener_half: Reader<UiStream>,
     message_body_tx: Sender<Result<MessageBody, ClientListenerError>>,
 }
 
 impl ClientListenerThread {
     pub fn new(
-        listener_half: Reader<TcpStream>,
+        listener_half: Reader<UiStream>,
         message_body_tx: Sender<Result<MessageBody, ClientListenerError>>,
     ) -> Self {
         Self {
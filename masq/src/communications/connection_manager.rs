@@ -5,6 +5,7 @@ use crate::communications::broadcast_handler::{
 };
 use crate::communications::client_listener_thread::{ClientListener, ClientListenerError};
 use crate::communications::node_conversation::{NodeConversation, NodeConversationTermination};
+use crate::communications::ui_stream::UiStream;
 use crossbeam_channel::{unbounded, RecvTimeoutError};
 use crossbeam_channel::{Receiver, RecvError, Sender};
 use masq_lib::messages::{CrashReason, FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast};
@@ -13,10 +14,10 @@ use masq_lib::ui_gateway::{MessageBody, MessagePath};
 use masq_lib::ui_traffic_converter::UiTrafficConverter;
 use masq_lib::utils::localhost;
 use std::collections::{HashMap, HashSet};
-use std::net::TcpStream;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
+use websocket::header::{Authorization, Bearer, Headers};
 use websocket::sender::Writer;
 use websocket::sync::Client;
 use websocket::ws::sender::Sender as WsSender;
@@ -63,6 +64,9 @@ pub struct ConnectionManager {
     conversation_return_rx: Receiver<NodeConversation>,
     redirect_response_rx: Receiver<Result<(), ClientListenerError>>,
     active_port_response_rx: Receiver<Option<u16>>,
+    ui_host: String,
+    wss: bool,
+    ui_token_opt: Option<String>,
 }
 
 impl Default for ConnectionManager {
@@ -78,9 +82,27 @@ impl ConnectionManager {
             conversation_return_rx: unbounded().1,
             redirect_response_rx: unbounded().1,
             active_port_response_rx: unbounded().1,
+            ui_host: localhost().to_string(),
+            wss: false,
+            ui_token_opt: None,
         }
     }
 
+    pub fn ui_host(mut self, ui_host: &str) -> Self {
+        self.ui_host = ui_host.to_string();
+        self
+    }
+
+    pub fn wss(mut self, wss: bool) -> Self {
+        self.wss = wss;
+        self
+    }
+
+    pub fn ui_token(mut self, ui_token_opt: Option<String>) -> Self {
+        self.ui_token_opt = ui_token_opt;
+        self
+    }
+
     pub fn connect(
         &mut self,
         port: u16,
@@ -89,7 +111,14 @@ impl ConnectionManager {
     ) -> Result<(), ClientListenerError> {
         let (demand_tx, demand_rx) = unbounded();
         let (listener_to_manager_tx, listener_to_manager_rx) = unbounded();
-        let talker_half = make_client_listener(port, listener_to_manager_tx, timeout_millis)?;
+        let talker_half = make_client_listener(
+            &self.ui_host,
+            port,
+            self.wss,
+            &self.ui_token_opt,
+            listener_to_manager_tx,
+            timeout_millis,
+        )?;
         let (conversation_return_tx, conversation_return_rx) = unbounded();
         let (redirect_order_tx, redirect_order_rx) = unbounded();
         let (redirect_response_tx, redirect_response_rx) = unbounded();
@@ -117,6 +146,9 @@ impl ConnectionManager {
             redirect_order_rx,
             redirect_response_tx,
             active_port_response_tx,
+            ui_host: self.ui_host.clone(),
+            wss: self.wss,
+            ui_token_opt: self.ui_token_opt.clone(),
         };
         ConnectionManagerThread::start(inner);
         Ok(())
@@ -153,14 +185,25 @@ impl ConnectionManager {
 }
 
 fn make_client_listener(
+    ui_host: &str,
     port: u16,
+    wss: bool,
+    ui_token_opt: &Option<String>,
     listener_to_manager_tx: Sender<Result<MessageBody, ClientListenerError>>,
     timeout_millis: u64,
-) -> Result<Writer<TcpStream>, ClientListenerError> {
-    let url = format!("ws://{}:{}", localhost(), port);
+) -> Result<Writer<UiStream>, ClientListenerError> {
+    let scheme = if wss { "wss" } else { "ws" };
+    let url = format!("{}://{}:{}", scheme, ui_host, port);
     let builder = ClientBuilder::new(url.as_str()).expect("Bad URL");
-    let result = builder.add_protocol(NODE_UI_PROTOCOL);
-    let result = match connect_insecure_timeout(result, timeout_millis) {
+    let mut result = builder.add_protocol(NODE_UI_PROTOCOL);
+    if let Some(token) = ui_token_opt {
+        let mut headers = Headers::new();
+        headers.set(Authorization(Bearer {
+            token: token.clone(),
+        }));
+        result = result.custom_headers(&headers);
+    }
+    let result = match connect_timeout(result, ui_host, port, wss, timeout_millis) {
         Err(RecvTimeoutError::Disconnected) => return Err(ClientListenerError::Closed),
         Err(RecvTimeoutError::Timeout) => return Err(ClientListenerError::Timeout),
         Ok(r) => r,
@@ -175,13 +218,20 @@ fn make_client_listener(
     Ok(talker_half)
 }
 
-fn connect_insecure_timeout(
+fn connect_timeout(
     mut builder: ClientBuilder<'static>,
+    ui_host: &str,
+    port: u16,
+    wss: bool,
     timeout_millis: u64,
-) -> Result<WebSocketResult<Client<TcpStream>>, RecvTimeoutError> {
+) -> Result<WebSocketResult<Client<UiStream>>, RecvTimeoutError> {
     let (tx, rx) = unbounded();
+    let ui_host = ui_host.to_string();
     thread::spawn(move || {
-        let result = builder.connect_insecure();
+        let result = match UiStream::connect(&ui_host, port, wss) {
+            Ok(stream) => builder.connect_on(stream),
+            Err(e) => Err(e.into()),
+        };
         let _ = tx.send(result);
     });
     rx.recv_timeout(Duration::from_millis(timeout_millis))
@@ -199,11 +249,14 @@ struct CmsInner {
     conversations_to_manager_tx: Sender<OutgoingMessageType>,
     conversations_to_manager_rx: Receiver<OutgoingMessageType>,
     listener_to_manager_rx: Receiver<Result<MessageBody, ClientListenerError>>,
-    talker_half: Writer<TcpStream>,
+    talker_half: Writer<UiStream>,
     broadcast_handle: Box<dyn BroadcastHandle>,
     redirect_order_rx: Receiver<RedirectOrder>,
     redirect_response_tx: Sender<Result<(), ClientListenerError>>,
     active_port_response_tx: Sender<Option<u16>>,
+    ui_host: String,
+    wss: bool,
+    ui_token_opt: Option<String>,
 }
 
 pub struct ConnectionManagerThread {}
@@ -376,7 +429,10 @@ impl ConnectionManagerThread {
         };
         let (listener_to_manager_tx, listener_to_manager_rx) = unbounded();
         let talker_half = match make_client_listener(
+            &inner.ui_host,
             redirect_order.port,
+            inner.wss,
+            &inner.ui_token_opt,
             listener_to_manager_tx,
             redirect_order.timeout_millis,
         ) {
@@ -447,7 +503,10 @@ impl ConnectionManagerThread {
         let (listener_to_manager_tx, listener_to_manager_rx) = unbounded();
         inner.listener_to_manager_rx = listener_to_manager_rx;
         match make_client_listener(
+            &inner.ui_host,
             inner.active_port.expect("Active port disappeared!"),
+            inner.wss,
+            &inner.ui_token_opt,
             listener_to_manager_tx,
             FALLBACK_TIMEOUT_MILLIS,
         ) {
@@ -557,6 +616,8 @@ mod tests {
     use masq_lib::test_utils::utils::is_running_under_github_actions;
     use masq_lib::utils::{find_free_port, running_test};
     use std::hash::Hash;
+    use std::io::Read;
+    use std::net::SocketAddr;
     use std::sync::{Arc, Mutex};
     use std::thread;
     use std::time::Duration;
@@ -1455,6 +1516,69 @@ mod tests {
         let _ = stop_handle.stop();
     }
 
+    #[test]
+    fn make_client_listener_presents_ui_token_when_it_has_one() {
+        let port = find_free_port();
+        let listener = std::net::TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+        let handle = thread::spawn(move || {
+            make_client_listener(
+                &localhost().to_string(),
+                port,
+                false,
+                &Some("booga".to_string()),
+                unbounded().0,
+                500,
+            )
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(1000)))
+            .unwrap();
+        let mut buf = [0u8; 4096];
+
+        let len = stream.read(&mut buf).unwrap();
+
+        let request = String::from_utf8_lossy(&buf[..len]).to_string();
+        assert!(
+            request.contains("Authorization: Bearer booga\r\n"),
+            "{}",
+            request
+        );
+        drop(stream);
+        assert!(handle.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn make_client_listener_starts_a_tls_handshake_when_told_to_use_wss() {
+        let port = find_free_port();
+        let listener = std::net::TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+        let handle = thread::spawn(move || {
+            make_client_listener(
+                &localhost().to_string(),
+                port,
+                true,
+                &Some("booga".to_string()),
+                unbounded().0,
+                500,
+            )
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(1000)))
+            .unwrap();
+        let mut buf = [0u8; 4096];
+
+        let len = stream.read(&mut buf).unwrap();
+
+        assert!(len > 0);
+        assert_eq!(buf[0], 0x16); // TLS handshake record: a ClientHello, not an HTTP request
+        drop(stream);
+        assert_eq!(
+            handle.join().unwrap().err(),
+            Some(ClientListenerError::Broken)
+        );
+    }
+
     fn make_inner() -> CmsInner {
         CmsInner {
             active_port: Some(0),
@@ -1473,10 +1597,13 @@ mod tests {
             redirect_order_rx: unbounded().1,
             redirect_response_tx: unbounded().0,
             active_port_response_tx: unbounded().0,
+            ui_host: localhost().to_string(),
+            wss: false,
+            ui_token_opt: None,
         }
     }
 
-    pub fn make_broken_talker_half() -> Writer<TcpStream> {
+    pub fn make_broken_talker_half() -> Writer<UiStream> {
         let port = find_free_port();
         let server = MockWebSocketsServer::new(port);
         let stop_handle = server.start();
@@ -0,0 +1,153 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
+
+use native_tls::{TlsConnector, TlsStream};
+use std::io;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use websocket::stream::sync::{AsTcpStream, Splittable};
+
+// How long a read on a TLS connection may hold the stream before it lets a write through.
+const SECURE_READ_POLL_MILLIS: u64 = 10;
+
+// The connection to the Daemon or Node. A TLS stream can't be cloned the way a TcpStream can, so
+// the listener and talker halves of a secure connection share it, and reads give it up regularly.
+pub enum UiStream {
+    Plain(TcpStream),
+    Secure {
+        tls: Arc<Mutex<TlsStream<TcpStream>>>,
+        tcp: TcpStream,
+    },
+}
+
+impl UiStream {
+    pub fn connect(host: &str, port: u16, wss: bool) -> io::Result<UiStream> {
+        let tcp = TcpStream::connect((host, port))?;
+        if !wss {
+            return Ok(UiStream::Plain(tcp));
+        }
+        let connector = TlsConnector::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let tls = connector
+            .connect(host, tcp)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        UiStream::secure(tls)
+    }
+
+    fn secure(tls: TlsStream<TcpStream>) -> io::Result<UiStream> {
+        let tcp = tls.get_ref().try_clone()?;
+        tcp.set_read_timeout(Some(Duration::from_millis(SECURE_READ_POLL_MILLIS)))?;
+        Ok(UiStream::Secure {
+            tls: Arc::new(Mutex::new(tls)),
+            tcp,
+        })
+    }
+}
+
+impl Read for UiStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            UiStream::Plain(tcp) => tcp.read(buf),
+            UiStream::Secure { tls, .. } => loop {
+                let result = tls.lock().expect("TLS stream poisoned").read(buf);
+                match result {
+                    Err(e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut =>
+                    {
+                        thread::sleep(Duration::from_millis(1))
+                    }
+                    result => return result,
+                }
+            },
+        }
+    }
+}
+
+impl Write for UiStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            UiStream::Plain(tcp) => tcp.write(buf),
+            UiStream::Secure { tls, .. } => tls.lock().expect("TLS stream poisoned").write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            UiStream::Plain(tcp) => tcp.flush(),
+            UiStream::Secure { tls, .. } => tls.lock().expect("TLS stream poisoned").flush(),
+        }
+    }
+}
+
+impl Splittable for UiStream {
+    type Reader = UiStream;
+    type Writer = UiStream;
+
+    fn split(self) -> io::Result<(UiStream, UiStream)> {
+        match self {
+            UiStream::Plain(tcp) => Ok((UiStream::Plain(tcp.try_clone()?), UiStream::Plain(tcp))),
+            UiStream::Secure { tls, tcp } => Ok((
+                UiStream::Secure {
+                    tls: tls.clone(),
+                    tcp: tcp.try_clone()?,
+                },
+                UiStream::Secure { tls, tcp },
+            )),
+        }
+    }
+}
+
+impl AsTcpStream for UiStream {
+    fn as_tcp(&self) -> &TcpStream {
+        match self {
+            UiStream::Plain(tcp) => tcp,
+            UiStream::Secure { tcp, .. } => tcp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::utils::{find_free_port, localhost};
+    use std::net::{SocketAddr, TcpListener};
+
+    #[test]
+    fn plain_stream_splits_into_halves_on_the_same_connection() {
+        let port = find_free_port();
+        let listener = TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+        let subject = UiStream::connect(&localhost().to_string(), port, false).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        let (mut reader, mut writer) = subject.split().unwrap();
+
+        writer.write_all(b"booga").unwrap();
+        let mut buf = [0u8; 5];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"booga");
+        server.write_all(b"agoob").unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"agoob");
+        assert_eq!(
+            reader.as_tcp().peer_addr().unwrap(),
+            writer.as_tcp().peer_addr().unwrap()
+        );
+    }
+
+    #[test]
+    fn secure_stream_fails_to_connect_to_a_server_that_does_not_speak_tls() {
+        let port = find_free_port();
+        let listener = TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+        let handle = thread::spawn(move || {
+            let (mut server, _) = listener.accept().unwrap();
+            let _ = server.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+        });
+
+        let result = UiStream::connect(&localhost().to_string(), port, true);
+
+        assert!(result.is_err());
+        handle.join().unwrap();
+    }
+}
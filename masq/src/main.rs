@@ -6,13 +6,18 @@ use masq_cli_lib::command_processor::{
     CommandProcessor, CommandProcessorFactory, CommandProcessorFactoryReal,
 };
 use masq_cli_lib::communications::broadcast_handler::StreamFactoryReal;
+use masq_cli_lib::json_output;
 use masq_cli_lib::utils::{BufReadFactory, BufReadFactoryReal};
 use masq_lib::command;
 use masq_lib::command::{Command, StdStreams};
 use masq_lib::short_writeln;
+use std::collections::HashMap;
 use std::io;
 use std::io::BufRead;
 
+// Options that come before the subcommand but aren't followed by a value
+const VALUELESS_OPTIONS: &[&str] = &["--json", "--wss"];
+
 fn main() {
     let mut streams: StdStreams<'_> = StdStreams {
         stdin: &mut io::stdin(),
@@ -34,7 +39,17 @@ struct Main {
 
 impl command::Command for Main {
     fn go(&mut self, streams: &mut StdStreams<'_>, args: &[String]) -> u8 {
-        let broadcast_stream_factory = StreamFactoryReal::new();
+        let (options, subcommand_opt) = Self::split_args(args);
+        if let Some(None) = options.get("--script") {
+            short_writeln!(streams.stderr, "--script needs the path of a file to run");
+            return 1;
+        }
+        let json = options.contains_key("--json");
+        let broadcast_stream_factory = if json {
+            StreamFactoryReal::new_stderr_only()
+        } else {
+            StreamFactoryReal::new()
+        };
         let mut command_processor = match self
             .processor_factory
             .make(Box::new(broadcast_stream_factory), args)
@@ -45,14 +60,25 @@ impl command::Command for Main {
                 return 1;
             }
         };
-        let result = match Self::extract_subcommand(args) {
-            Some(command_parts) => {
-                match self.handle_command(&mut *command_processor, command_parts, streams.stderr) {
+        let result = match (options.get("--script"), subcommand_opt) {
+            (Some(Some(_)), Some(_)) => {
+                short_writeln!(
+                    streams.stderr,
+                    "Either give masq a command or a --script to run, not both"
+                );
+                1
+            }
+            (Some(Some(path)), None) => {
+                let keep_going = options.get("--on-error") == Some(&Some("continue".to_string()));
+                self.go_script(&mut *command_processor, path, json, keep_going, streams)
+            }
+            (_, Some(command_parts)) => {
+                match self.handle_command(&mut *command_processor, command_parts, json, streams) {
                     Ok(_) => 0,
                     Err(_) => 1,
                 }
             }
-            None => self.go_interactive(&mut *command_processor, streams),
+            (_, None) => self.go_interactive(&mut *command_processor, json, streams),
         };
         command_processor.close();
         result
@@ -68,16 +94,31 @@ impl Main {
         }
     }
 
-    fn extract_subcommand(args: &[String]) -> Option<Vec<String>> {
-        let args_vec: Vec<String> = args.to_vec();
-        for idx in 1..args_vec.len() {
-            let one = &args_vec[idx - 1];
-            let two = &args_vec[idx];
-            if !one.starts_with("--") && !two.starts_with("--") {
-                return Some(args_vec.into_iter().skip(idx).collect());
+    // Separates the options that come before the subcommand, if there is one, from the subcommand
+    // and everything after it.
+    fn split_args(args: &[String]) -> (HashMap<String, Option<String>>, Option<Vec<String>>) {
+        let mut options = HashMap::new();
+        let mut idx = 1;
+        while idx < args.len() {
+            let arg = &args[idx];
+            if !arg.starts_with("--") {
+                return (options, Some(args[idx..].to_vec()));
+            }
+            if let Some(equals) = arg.find('=') {
+                options.insert(
+                    arg[..equals].to_string(),
+                    Some(arg[(equals + 1)..].to_string()),
+                );
+                idx += 1;
+            } else if VALUELESS_OPTIONS.contains(&arg.as_str()) {
+                options.insert(arg.clone(), None);
+                idx += 1;
+            } else {
+                options.insert(arg.clone(), args.get(idx + 1).cloned());
+                idx += 2;
             }
         }
-        None
+        (options, None)
     }
 
     fn accept_subcommand(stdin: &mut dyn BufRead) -> Result<Option<Vec<String>>, std::io::Error> {
@@ -116,6 +157,7 @@ impl Main {
     fn go_interactive(
         &self,
         processor: &mut dyn CommandProcessor,
+        json: bool,
         streams: &mut StdStreams<'_>,
     ) -> u8 {
         let mut line_reader = self.buf_read_factory.make();
@@ -134,7 +176,7 @@ impl Main {
             if args[0] == "exit" {
                 break;
             }
-            match self.handle_command(processor, args, streams.stderr) {
+            match self.handle_command(processor, args, json, streams) {
                 Ok(_) => (),
                 Err(_) => continue,
             }
@@ -142,25 +184,87 @@ impl Main {
         0
     }
 
+    fn go_script(
+        &self,
+        processor: &mut dyn CommandProcessor,
+        path: &str,
+        json: bool,
+        keep_going: bool,
+        streams: &mut StdStreams<'_>,
+    ) -> u8 {
+        let script = match self.buf_read_factory.make_script(path) {
+            Ok(script) => script,
+            Err(e) => {
+                short_writeln!(streams.stderr, "Can't read script '{}': {}", path, e);
+                return 1;
+            }
+        };
+        let mut exit_code = 0;
+        for line in script.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    short_writeln!(streams.stderr, "Can't read script '{}': {}", path, e);
+                    return 1;
+                }
+            };
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            let args = Self::split_quoted_line(line);
+            if args.is_empty() {
+                continue;
+            }
+            if args[0] == "exit" {
+                break;
+            }
+            if self.handle_command(processor, args, json, streams).is_err() {
+                exit_code = 1;
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+        exit_code
+    }
+
     fn handle_command(
         &self,
         processor: &mut dyn CommandProcessor,
         command_parts: Vec<String>,
-        stderr: &mut dyn io::Write,
+        json: bool,
+        streams: &mut StdStreams<'_>,
     ) -> Result<(), ()> {
-        let command = match self.command_factory.make(command_parts) {
+        let command = match self.command_factory.make(command_parts.clone()) {
             Ok(c) => c,
-            Err(UnrecognizedSubcommand(msg)) => {
-                short_writeln!(stderr, "Unrecognized command: '{}'", msg);
-                return Err(());
-            }
-            Err(CommandSyntax(msg)) => {
-                short_writeln!(stderr, "{}", msg);
+            Err(e) => {
+                let msg = match e {
+                    UnrecognizedSubcommand(msg) => format!("Unrecognized command: '{}'", msg),
+                    CommandSyntax(msg) => msg,
+                };
+                if json {
+                    short_writeln!(
+                        streams.stdout,
+                        "{}",
+                        json_output::rejected_command(&command_parts, &msg)
+                    );
+                } else {
+                    short_writeln!(streams.stderr, "{}", msg);
+                }
                 return Err(());
             }
         };
+        if json {
+            let captured = processor.process_captured(command);
+            short_writeln!(
+                streams.stdout,
+                "{}",
+                json_output::executed_command(&command_parts, &captured)
+            );
+            return captured.result.map_err(|_| ());
+        }
         if let Err(e) = processor.process(command) {
-            short_writeln!(stderr, "{}", e);
+            short_writeln!(streams.stderr, "{}", e);
             Err(())
         } else {
             Ok(())
@@ -174,6 +278,7 @@ mod tests {
     use masq_cli_lib::command_context::CommandContext;
     use masq_cli_lib::command_context::ContextError::Other;
     use masq_cli_lib::command_factory::CommandFactoryError;
+    use masq_cli_lib::command_processor::CapturedExecution;
     use masq_cli_lib::commands::commands_common;
     use masq_cli_lib::commands::commands_common::CommandError;
     use masq_cli_lib::commands::commands_common::CommandError::Transmission;
@@ -182,7 +287,7 @@ mod tests {
         MockCommand,
     };
     use masq_lib::messages::ToMessageBody;
-    use masq_lib::messages::UiShutdownRequest;
+    use masq_lib::messages::{UiShutdownRequest, UiShutdownResponse, NODE_NOT_RUNNING_ERROR};
     use masq_lib::test_utils::fake_stream_holder::{ByteArrayReader, FakeStreamHolder};
     use std::cell::RefCell;
     use std::io::ErrorKind;
@@ -190,21 +295,56 @@ mod tests {
 
     struct BufReadFactoryMock {
         interactive: RefCell<Option<ByteArrayReader>>,
+        make_script_params: Arc<Mutex<Vec<String>>>,
+        make_script_results: RefCell<Vec<Result<ByteArrayReader, io::Error>>>,
     }
 
     impl BufReadFactory for BufReadFactoryMock {
         fn make(&self) -> Box<dyn BufRead> {
             Box::new(self.interactive.borrow_mut().take().unwrap())
         }
+
+        fn make_script(&self, path: &str) -> Result<Box<dyn BufRead>, io::Error> {
+            self.make_script_params
+                .lock()
+                .unwrap()
+                .push(path.to_string());
+            self.make_script_results
+                .borrow_mut()
+                .remove(0)
+                .map(|reader| Box::new(reader) as Box<dyn BufRead>)
+        }
     }
 
     impl BufReadFactoryMock {
         pub fn new() -> BufReadFactoryMock {
             BufReadFactoryMock {
                 interactive: RefCell::new(None),
+                make_script_params: Arc::new(Mutex::new(vec![])),
+                make_script_results: RefCell::new(vec![]),
             }
         }
 
+        pub fn make_script_params(
+            mut self,
+            params: &Arc<Mutex<Vec<String>>>,
+        ) -> BufReadFactoryMock {
+            self.make_script_params = params.clone();
+            self
+        }
+
+        pub fn make_script_result(self, script: &str) -> BufReadFactoryMock {
+            self.make_script_results
+                .borrow_mut()
+                .push(Ok(ByteArrayReader::new(script.as_bytes())));
+            self
+        }
+
+        pub fn make_script_error(self, error: io::Error) -> BufReadFactoryMock {
+            self.make_script_results.borrow_mut().push(Err(error));
+            self
+        }
+
         pub fn make_interactive_result(self, input: &str) -> BufReadFactoryMock {
             self.make_interactive_reader(ByteArrayReader::new(input.as_bytes()))
         }
@@ -652,4 +792,353 @@ mod tests {
             "Can't connect to Daemon or Node (ConnectionProblem(\"booga\")). Probably this means the Daemon isn't running.\n".to_string()
         );
     }
+
+    fn make_captured_execution(result: Result<(), CommandError>) -> CapturedExecution {
+        CapturedExecution {
+            result,
+            responses: vec![UiShutdownResponse {}.tmb(1)],
+            stdout: "Shutdown order acknowledged\n".to_string(),
+            stderr: "".to_string(),
+        }
+    }
+
+    fn make_script_main(
+        script: &str,
+        command_factory: CommandFactoryMock,
+        processor: CommandProcessorMock,
+    ) -> Main {
+        Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor))),
+            ),
+            buf_read_factory: Box::new(BufReadFactoryMock::new().make_script_result(script)),
+        }
+    }
+
+    #[test]
+    fn split_args_separates_options_from_the_subcommand() {
+        let args: Vec<String> = vec![
+            "masq",
+            "--json",
+            "--ui-port",
+            "5333",
+            "--wss",
+            "--on-error=continue",
+            "setup",
+            "--chain",
+            "ropsten",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let (options, subcommand_opt) = Main::split_args(&args);
+
+        assert_eq!(options.len(), 4);
+        assert_eq!(options.get("--json"), Some(&None));
+        assert_eq!(options.get("--ui-port"), Some(&Some("5333".to_string())));
+        assert_eq!(options.get("--wss"), Some(&None));
+        assert_eq!(
+            options.get("--on-error"),
+            Some(&Some("continue".to_string()))
+        );
+        assert_eq!(
+            subcommand_opt,
+            Some(vec![
+                "setup".to_string(),
+                "--chain".to_string(),
+                "ropsten".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn json_mode_reports_a_command_as_a_json_object() {
+        let command_factory = CommandFactoryMock::new()
+            .make_result(Ok(Box::new(FakeCommand::new("shutdown command"))));
+        let process_captured_params_arc = Arc::new(Mutex::new(vec![]));
+        let processor = CommandProcessorMock::new()
+            .process_captured_params(&process_captured_params_arc)
+            .process_captured_result(make_captured_execution(Ok(())));
+        let mut subject = Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor))),
+            ),
+            buf_read_factory: Box::new(BufReadFactoryMock::new()),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--json".to_string(),
+                "shutdown".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 0);
+        assert_eq!(process_captured_params_arc.lock().unwrap().len(), 1);
+        assert_eq!(
+            stream_holder.stdout.get_string(),
+            format!(
+                "{}\n",
+                json_output::executed_command(
+                    &["shutdown".to_string()],
+                    &make_captured_execution(Ok(()))
+                )
+            )
+        );
+        assert_eq!(stream_holder.stderr.get_string(), "".to_string());
+    }
+
+    #[test]
+    fn json_mode_reports_rejected_and_failed_commands_as_json_objects() {
+        let command_factory = CommandFactoryMock::new()
+            .make_result(Err(UnrecognizedSubcommand("booga".to_string())))
+            .make_result(Ok(Box::new(FakeCommand::new("shutdown command"))));
+        let failure = Err(CommandError::Payload(
+            NODE_NOT_RUNNING_ERROR,
+            "Node is not running".to_string(),
+        ));
+        let processor =
+            CommandProcessorMock::new().process_captured_result(make_captured_execution(failure));
+        let mut subject = Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor))),
+            ),
+            buf_read_factory: Box::new(
+                BufReadFactoryMock::new().make_interactive_result("booga\nshutdown\nexit\n"),
+            ),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &["masq".to_string(), "--json".to_string()],
+        );
+
+        assert_eq!(result, 0);
+        let expected_failure = make_captured_execution(Err(CommandError::Payload(
+            NODE_NOT_RUNNING_ERROR,
+            "Node is not running".to_string(),
+        )));
+        assert_eq!(
+            stream_holder.stdout.get_string(),
+            format!(
+                "{}\n{}\n",
+                json_output::rejected_command(
+                    &["booga".to_string()],
+                    "Unrecognized command: 'booga'"
+                ),
+                json_output::executed_command(&["shutdown".to_string()], &expected_failure)
+            )
+        );
+        assert_eq!(stream_holder.stderr.get_string(), "".to_string());
+    }
+
+    #[test]
+    fn script_mode_runs_every_command_until_exit() {
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let command_factory = CommandFactoryMock::new()
+            .make_params(&make_params_arc)
+            .make_result(Ok(Box::new(FakeCommand::new("setup command"))))
+            .make_result(Ok(Box::new(FakeCommand::new("start command"))));
+        let processor = CommandProcessorMock::new()
+            .process_result(Ok(()))
+            .process_result(Ok(()));
+        let make_script_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut subject = Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor))),
+            ),
+            buf_read_factory: Box::new(
+                BufReadFactoryMock::new()
+                    .make_script_params(&make_script_params_arc)
+                    .make_script_result(
+                        "# Start a zero-hop Node\nsetup --neighborhood-mode \"zero-hop\"\n\n  \nstart\nexit\nshutdown\n",
+                    ),
+            ),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--script".to_string(),
+                "commands.txt".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 0);
+        assert_eq!(
+            *make_script_params_arc.lock().unwrap(),
+            vec!["commands.txt".to_string()]
+        );
+        assert_eq!(
+            *make_params_arc.lock().unwrap(),
+            vec![
+                vec![
+                    "setup".to_string(),
+                    "--neighborhood-mode".to_string(),
+                    "zero-hop".to_string()
+                ],
+                vec!["start".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn script_mode_stops_at_the_first_failure() {
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let command_factory = CommandFactoryMock::new()
+            .make_params(&make_params_arc)
+            .make_result(Ok(Box::new(FakeCommand::new("setup command"))))
+            .make_result(Ok(Box::new(FakeCommand::new("start command"))));
+        let processor = CommandProcessorMock::new()
+            .process_result(Ok(()))
+            .process_result(Err(Transmission("Booga!".to_string())));
+        let mut subject = make_script_main("setup\nstart\nshutdown\n", command_factory, processor);
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--script".to_string(),
+                "commands.txt".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(make_params_arc.lock().unwrap().len(), 2);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "Transmission problem: Booga!\n".to_string()
+        );
+    }
+
+    #[test]
+    fn script_mode_continues_past_failures_when_told_to_but_still_fails() {
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let command_factory = CommandFactoryMock::new()
+            .make_params(&make_params_arc)
+            .make_result(Err(CommandSyntax("Booga!".to_string())))
+            .make_result(Ok(Box::new(FakeCommand::new("start command"))))
+            .make_result(Ok(Box::new(FakeCommand::new("shutdown command"))));
+        let processor = CommandProcessorMock::new()
+            .process_result(Err(Transmission("Wibble!".to_string())))
+            .process_result(Ok(()));
+        let mut subject = make_script_main(
+            "setup --booga\nstart\nshutdown\n",
+            command_factory,
+            processor,
+        );
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--script".to_string(),
+                "commands.txt".to_string(),
+                "--on-error".to_string(),
+                "continue".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(make_params_arc.lock().unwrap().len(), 3);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "Booga!\nTransmission problem: Wibble!\n".to_string()
+        );
+    }
+
+    #[test]
+    fn script_mode_complains_about_a_script_it_cannot_read() {
+        let close_params_arc = Arc::new(Mutex::new(vec![]));
+        let processor = CommandProcessorMock::new().close_params(&close_params_arc);
+        let mut subject = Main {
+            command_factory: Box::new(CommandFactoryMock::new()),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor))),
+            ),
+            buf_read_factory: Box::new(
+                BufReadFactoryMock::new()
+                    .make_script_error(io::Error::new(ErrorKind::NotFound, "No such file")),
+            ),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--script".to_string(),
+                "nope.txt".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "Can't read script 'nope.txt': No such file\n".to_string()
+        );
+        assert_eq!(close_params_arc.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn script_mode_without_a_path_is_a_usage_error() {
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut subject = Main {
+            command_factory: Box::new(CommandFactoryMock::new()),
+            processor_factory: Box::new(
+                CommandProcessorFactoryMock::new().make_params(&make_params_arc),
+            ),
+            buf_read_factory: Box::new(BufReadFactoryMock::new()),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &["masq".to_string(), "--script".to_string()],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "--script needs the path of a file to run\n".to_string()
+        );
+        assert_eq!(stream_holder.stdout.get_string(), String::new());
+        assert!(make_params_arc.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn script_mode_will_not_also_run_a_command_from_the_command_line() {
+        let processor = CommandProcessorMock::new();
+        let mut subject = make_script_main("start\n", CommandFactoryMock::new(), processor);
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "masq".to_string(),
+                "--script".to_string(),
+                "commands.txt".to_string(),
+                "shutdown".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "Either give masq a command or a --script to run, not both\n".to_string()
+        );
+    }
 }
@@ -1,3 +1,4 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
 pub mod crashed_notification;
+pub mod restart_notification;
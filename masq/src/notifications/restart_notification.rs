@@ -0,0 +1,107 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use masq_lib::messages::{
+    UiNodeRestartAbandonedBroadcast, UiNodeRestartedBroadcast, UiNodeRestartingBroadcast,
+};
+use masq_lib::short_writeln;
+use std::io::Write;
+
+pub struct RestartNotifier {}
+
+impl RestartNotifier {
+    pub fn handle_restarting(response: UiNodeRestartingBroadcast, stdout: &mut dyn Write) {
+        short_writeln!(
+            stdout,
+            "\nThe Daemon will restart the Node that was process {} in {}ms (attempt {}).\n",
+            response.crashed_process_id,
+            response.delay_ms,
+            response.attempt
+        );
+        Self::prompt(stdout);
+    }
+
+    pub fn handle_restarted(response: UiNodeRestartedBroadcast, stdout: &mut dyn Write) {
+        short_writeln!(
+            stdout,
+            "\nThe Daemon restarted the Node as process {}, listening for UIs on port {} (attempt {}).\n",
+            response.new_process_id,
+            response.redirect_ui_port,
+            response.attempt
+        );
+        Self::prompt(stdout);
+    }
+
+    pub fn handle_abandoned(response: UiNodeRestartAbandonedBroadcast, stdout: &mut dyn Write) {
+        short_writeln!(
+            stdout,
+            "\nThe Node has been restarted {} times in {} minutes; the Daemon won't restart it again. Use start to start it.\n",
+            response.restarts,
+            response.window_minutes
+        );
+        Self::prompt(stdout);
+    }
+
+    fn prompt(stdout: &mut dyn Write) {
+        write!(stdout, "masq> ").expect("write! failed");
+        stdout.flush().expect("flush failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::test_utils::fake_stream_holder::ByteArrayWriter;
+
+    #[test]
+    pub fn handles_restarting() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartingBroadcast {
+            crashed_process_id: 12345,
+            attempt: 2,
+            delay_ms: 2000,
+        };
+
+        RestartNotifier::handle_restarting(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Daemon will restart the Node that was process 12345 in 2000ms (attempt 2).\n\nmasq> "
+                .to_string()
+        );
+    }
+
+    #[test]
+    pub fn handles_restarted() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartedBroadcast {
+            new_process_id: 23456,
+            redirect_ui_port: 5432,
+            attempt: 2,
+        };
+
+        RestartNotifier::handle_restarted(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Daemon restarted the Node as process 23456, listening for UIs on port 5432 (attempt 2).\n\nmasq> "
+                .to_string()
+        );
+    }
+
+    #[test]
+    pub fn handles_abandoned() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartAbandonedBroadcast {
+            restarts: 5,
+            window_minutes: 60,
+        };
+
+        RestartNotifier::handle_abandoned(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Node has been restarted 5 times in 60 minutes; the Daemon won't restart it again. Use start to start it.\n\nmasq> "
+                .to_string()
+        );
+    }
+}
@@ -5,7 +5,12 @@ use crate::commands::change_password_command::{
 use crate::commands::check_password_command::check_password_subcommand;
 use crate::commands::crash_command::crash_subcommand;
 use crate::commands::descriptor_command::descriptor_subcommand;
+use crate::commands::financials_command::financials_subcommand;
 use crate::commands::generate_wallets_command::generate_wallets_subcommand;
+use crate::commands::issue_token_command::issue_token_subcommand;
+use crate::commands::neighborhood_command::neighborhood_subcommand;
+use crate::commands::profile_command::profile_subcommand;
+use crate::commands::rotate_keys_command::rotate_keys_subcommand;
 use crate::commands::setup_command::setup_subcommand;
 use crate::commands::shutdown_command::shutdown_subcommand;
 use crate::commands::start_command::start_subcommand;
@@ -13,6 +18,26 @@ use crate::commands::wallet_addresses::wallet_addresses_subcommand;
 use clap::{App, AppSettings, Arg};
 use lazy_static::lazy_static;
 use masq_lib::constants::{DEFAULT_UI_PORT, HIGHEST_USABLE_PORT, LOWEST_USABLE_INSECURE_PORT};
+use masq_lib::utils::localhost;
+
+const JSON_HELP: &str =
+    "Instead of the usual text, write one JSON object per command on a line of its own: the command, whether it \
+     succeeded, the responses it got from the Daemon or Node, what it would have printed, and any error.";
+const SCRIPT_HELP: &str =
+    "Run the commands in this file, one per line, instead of a single command or an interactive session. Blank \
+     lines and lines starting with # are skipped. masq exits with status 0 if every command succeeded and 1 if not.";
+const ON_ERROR_HELP: &str =
+    "What --script should do when a command fails: stop there, or continue with the rest of the file.";
+const UI_HOST_HELP: &str =
+    "The name or IP address of the machine whose Daemon or Node masq should talk to. Anything other than \
+     localhost is only reachable if that Node was started with --ui-remote-address, and then you need --wss \
+     and --ui-token as well.";
+const WSS_HELP: &str =
+    "Connect over TLS (wss://) instead of plain WebSockets. The Node's --ui-remote-address only accepts TLS \
+     connections, and its certificate must be one this machine trusts.";
+const UI_TOKEN_HELP: &str =
+    "If the Node was started with --ui-auth on, specify a token the Daemon issued here so that the Node \
+     will talk to masq. Get one with the issue-token command.";
 
 lazy_static! {
     static ref UI_PORT_HELP: String = format!(
@@ -21,6 +46,7 @@ lazy_static! {
         DEFAULT_UI_PORT, LOWEST_USABLE_INSECURE_PORT, HIGHEST_USABLE_PORT
     );
     static ref DEFAULT_UI_PORT_STRING: String = format!("{}", DEFAULT_UI_PORT);
+    static ref DEFAULT_UI_HOST_STRING: String = localhost().to_string();
 }
 
 pub fn app_head() -> App<'static, 'static> {
@@ -48,12 +74,50 @@ pub fn app() -> App<'static, 'static> {
                 .validator(validate_ui_port)
                 .help(UI_PORT_HELP.as_str()),
         )
+        .arg(
+            Arg::with_name("ui-host")
+                .long("ui-host")
+                .value_name("UI-HOST")
+                .takes_value(true)
+                .default_value(DEFAULT_UI_HOST_STRING.as_str())
+                .help(UI_HOST_HELP),
+        )
+        .arg(Arg::with_name("wss").long("wss").help(WSS_HELP))
+        .arg(
+            Arg::with_name("ui-token")
+                .long("ui-token")
+                .value_name("UI-TOKEN")
+                .takes_value(true)
+                .help(UI_TOKEN_HELP),
+        )
+        .arg(Arg::with_name("json").long("json").help(JSON_HELP))
+        .arg(
+            Arg::with_name("script")
+                .long("script")
+                .value_name("FILE")
+                .takes_value(true)
+                .help(SCRIPT_HELP),
+        )
+        .arg(
+            Arg::with_name("on-error")
+                .long("on-error")
+                .value_name("ON-ERROR")
+                .takes_value(true)
+                .possible_values(&["stop", "continue"])
+                .default_value("stop")
+                .help(ON_ERROR_HELP),
+        )
         .subcommand(set_password_subcommand())
         .subcommand(change_password_subcommand())
         .subcommand(check_password_subcommand())
         .subcommand(crash_subcommand())
         .subcommand(descriptor_subcommand())
+        .subcommand(financials_subcommand())
         .subcommand(generate_wallets_subcommand())
+        .subcommand(issue_token_subcommand())
+        .subcommand(neighborhood_subcommand())
+        .subcommand(profile_subcommand())
+        .subcommand(rotate_keys_subcommand())
         .subcommand(setup_subcommand())
         .subcommand(start_subcommand())
         .subcommand(shutdown_subcommand())
@@ -1,16 +1,17 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
+use crate::communications::ui_stream::UiStream;
 use masq_lib::messages::NODE_UI_PROTOCOL;
 use masq_lib::utils::localhost;
-use std::net::TcpStream;
 use websocket::sync::Client;
 use websocket::ClientBuilder;
 
-pub fn make_client(port: u16) -> Client<TcpStream> {
+pub fn make_client(port: u16) -> Client<UiStream> {
     let builder =
         ClientBuilder::new(format!("ws://{}:{}", localhost(), port).as_str()).expect("Bad URL");
+    let stream = UiStream::connect(&localhost().to_string(), port, false).unwrap();
     builder
         .add_protocol(NODE_UI_PROTOCOL)
-        .connect_insecure()
+        .connect_on(stream)
         .unwrap()
 }
@@ -2,7 +2,7 @@
 
 use crate::command_context::{CommandContext, ContextError};
 use crate::command_factory::{CommandFactory, CommandFactoryError};
-use crate::command_processor::{CommandProcessor, CommandProcessorFactory};
+use crate::command_processor::{CapturedExecution, CommandProcessor, CommandProcessorFactory};
 use crate::commands::commands_common::CommandError::Transmission;
 use crate::commands::commands_common::{Command, CommandError};
 use crate::communications::broadcast_handler::StreamFactory;
@@ -161,6 +161,8 @@ impl CommandContextMock {
 pub struct CommandProcessorMock {
     process_params: Arc<Mutex<Vec<Box<dyn Command>>>>,
     process_results: RefCell<Vec<Result<(), CommandError>>>,
+    process_captured_params: Arc<Mutex<Vec<Box<dyn Command>>>>,
+    process_captured_results: RefCell<Vec<CapturedExecution>>,
     close_params: Arc<Mutex<Vec<()>>>,
 }
 
@@ -170,6 +172,11 @@ impl CommandProcessor for CommandProcessorMock {
         self.process_results.borrow_mut().remove(0)
     }
 
+    fn process_captured(&mut self, command: Box<dyn Command>) -> CapturedExecution {
+        self.process_captured_params.lock().unwrap().push(command);
+        self.process_captured_results.borrow_mut().remove(0)
+    }
+
     fn close(&mut self) {
         self.close_params.lock().unwrap().push(());
     }
@@ -190,6 +197,16 @@ impl CommandProcessorMock {
         self
     }
 
+    pub fn process_captured_params(mut self, params: &Arc<Mutex<Vec<Box<dyn Command>>>>) -> Self {
+        self.process_captured_params = params.clone();
+        self
+    }
+
+    pub fn process_captured_result(self, result: CapturedExecution) -> Self {
+        self.process_captured_results.borrow_mut().push(result);
+        self
+    }
+
     pub fn close_params(mut self, params: &Arc<Mutex<Vec<()>>>) -> Self {
         self.close_params = params.clone();
         self
@@ -1,12 +1,15 @@
 // Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
 use crate::line_reader::LineReader;
-use std::io::BufRead;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
 
 pub const MASQ_PROMPT: &str = "masq> ";
 
 pub trait BufReadFactory {
     fn make(&self) -> Box<dyn BufRead>;
+    fn make_script(&self, path: &str) -> Result<Box<dyn BufRead>, io::Error>;
 }
 
 pub struct BufReadFactoryReal {}
@@ -15,6 +18,10 @@ impl BufReadFactory for BufReadFactoryReal {
     fn make(&self) -> Box<dyn BufRead> {
         Box::new(LineReader::new())
     }
+
+    fn make_script(&self, path: &str) -> Result<Box<dyn BufRead>, io::Error> {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
 }
 
 impl Default for BufReadFactoryReal {
@@ -5,6 +5,10 @@ pub const HTTP_PORT: u16 = 80;
 pub const TLS_PORT: u16 = 443;
 pub const DEFAULT_CHAIN_NAME: &str = "mainnet";
 pub const DEFAULT_GAS_PRICE: &str = "1";
+pub const DEFAULT_CONFIRMATIONS: u64 = 12;
+pub const DEFAULT_PAYMENT_CURVES: &str = "86400|86400|10000000|1000000000|2592000|10000000";
+pub const DEFAULT_ROUTING_STRATEGY: &str = "cheapest";
+pub const DEFAULT_MALEFACTOR_BAN_DURATION_HOURS: u64 = 24;
 pub const LOWEST_USABLE_INSECURE_PORT: u16 = 1025;
 pub const HIGHEST_USABLE_PORT: u16 = 65535;
 pub const DEFAULT_UI_PORT: u16 = 5333;
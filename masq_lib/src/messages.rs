@@ -21,6 +21,8 @@ pub const NODE_ALREADY_RUNNING_ERROR: u64 = 0x8000_0000_0000_0003;
 pub const UNMARSHAL_ERROR: u64 = 0x8000_0000_0000_0004;
 pub const SETUP_ERROR: u64 = 0x8000_0000_0000_0005;
 pub const TIMEOUT_ERROR: u64 = 0x8000_0000_0000_0006;
+pub const UNAUTHORIZED_ERROR: u64 = 0x8000_0000_0000_0007;
+pub const PROFILE_ERROR: u64 = 0x8000_0000_0000_0008;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum UiMessageError {
@@ -361,6 +363,16 @@ impl From<UiSetupBroadcast> for UiSetupInner {
     }
 }
 
+impl From<UiProfileLoadResponse> for UiSetupInner {
+    fn from(input: UiProfileLoadResponse) -> Self {
+        Self {
+            running: false,
+            values: input.values,
+            errors: input.errors,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct UiStartOrder {}
 conversation_message!(UiStartOrder, "start");
@@ -391,6 +403,34 @@ pub struct UiNodeCrashedBroadcast {
 }
 fire_and_forget_message!(UiNodeCrashedBroadcast, "crashed");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiNodeRestartingBroadcast {
+    #[serde(rename = "crashedProcessId")]
+    pub crashed_process_id: u32,
+    pub attempt: u32,
+    #[serde(rename = "delayMs")]
+    pub delay_ms: u64,
+}
+fire_and_forget_message!(UiNodeRestartingBroadcast, "restarting");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiNodeRestartedBroadcast {
+    #[serde(rename = "newProcessId")]
+    pub new_process_id: u32,
+    #[serde(rename = "redirectUiPort")]
+    pub redirect_ui_port: u16,
+    pub attempt: u32,
+}
+fire_and_forget_message!(UiNodeRestartedBroadcast, "restarted");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiNodeRestartAbandonedBroadcast {
+    pub restarts: u32,
+    #[serde(rename = "windowMinutes")]
+    pub window_minutes: u64,
+}
+fire_and_forget_message!(UiNodeRestartAbandonedBroadcast, "restartAbandoned");
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct UiRedirect {
     pub port: u16,
@@ -401,6 +441,81 @@ pub struct UiRedirect {
 }
 fire_and_forget_message!(UiRedirect, "redirect");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiIssueTokenRequest {
+    #[serde(rename = "currentTokenOpt")]
+    pub current_token_opt: Option<String>,
+}
+conversation_message!(UiIssueTokenRequest, "issueToken");
+
+// The first token for a data directory isn't sent back at all: it's written to a file that only the
+// Node's real user can read, and the response says where.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiIssueTokenResponse {
+    #[serde(rename = "tokenOpt")]
+    pub token_opt: Option<String>,
+    #[serde(rename = "tokenFileOpt")]
+    pub token_file_opt: Option<String>,
+}
+conversation_message!(UiIssueTokenResponse, "issueToken");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileDeleteRequest {
+    pub name: String,
+}
+conversation_message!(UiProfileDeleteRequest, "profileDelete");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileDeleteResponse {}
+conversation_message!(UiProfileDeleteResponse, "profileDelete");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileListRequest {}
+conversation_message!(UiProfileListRequest, "profileList");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileListResponse {
+    pub profiles: Vec<String>,
+}
+conversation_message!(UiProfileListResponse, "profileList");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileLoadRequest {
+    pub name: String,
+}
+conversation_message!(UiProfileLoadRequest, "profileLoad");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiProfileLoadResponse {
+    pub values: Vec<UiSetupResponseValue>,
+    pub errors: Vec<(String, String)>,
+}
+conversation_message!(UiProfileLoadResponse, "profileLoad");
+impl UiProfileLoadResponse {
+    pub fn new(
+        values: HashMap<String, UiSetupResponseValue>,
+        errors: ConfiguratorError,
+    ) -> UiProfileLoadResponse {
+        let inner = UiSetupResponse::new(false, values, errors);
+        UiProfileLoadResponse {
+            values: inner.values,
+            errors: inner.errors,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileSaveRequest {
+    pub name: String,
+}
+conversation_message!(UiProfileSaveRequest, "profileSave");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiProfileSaveResponse {
+    pub saved: Vec<String>,
+}
+conversation_message!(UiProfileSaveResponse, "profileSave");
+
 ///////////////////////////////////////////////////////////////////
 // These messages are sent to or by both the Daemon and the Node
 ///////////////////////////////////////////////////////////////////
@@ -462,6 +577,8 @@ pub struct UiConfigurationResponse {
     pub clandestine_port: u16,
     #[serde(rename = "gasPrice")]
     pub gas_price: u64,
+    #[serde(rename = "paymentCurves")]
+    pub payment_curves: UiPaymentCurves,
     #[serde(rename = "mnemonicSeedOpt")]
     pub mnemonic_seed_opt: Option<String>,
     #[serde(rename = "consumingWalletDerivationPathOpt")]
@@ -475,6 +592,22 @@ pub struct UiConfigurationResponse {
 }
 conversation_message!(UiConfigurationResponse, "configuration");
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiPaymentCurves {
+    #[serde(rename = "paymentSuggestedAfterSec")]
+    pub payment_suggested_after_sec: i64,
+    #[serde(rename = "paymentGraceBeforeBanSec")]
+    pub payment_grace_before_ban_sec: i64,
+    #[serde(rename = "permanentDebtAllowedGwub")]
+    pub permanent_debt_allowed_gwub: i64,
+    #[serde(rename = "balanceToDecreaseFromGwub")]
+    pub balance_to_decrease_from_gwub: i64,
+    #[serde(rename = "balanceDecreasesForSec")]
+    pub balance_decreases_for_sec: i64,
+    #[serde(rename = "unbanWhenBalanceBelowGwub")]
+    pub unban_when_balance_below_gwub: i64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UiDescriptorRequest {}
 conversation_message!(UiDescriptorRequest, "descriptor");
@@ -554,10 +687,82 @@ pub struct UiGenerateWalletsResponse {
 }
 conversation_message!(UiGenerateWalletsResponse, "generateWallets");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNeighborhoodRequest {
+    #[serde(rename = "dotGraph")]
+    pub dot_graph: bool,
+}
+conversation_message!(UiNeighborhoodRequest, "neighborhood");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiRatePack {
+    #[serde(rename = "routingByteRate")]
+    pub routing_byte_rate: u64,
+    #[serde(rename = "routingServiceRate")]
+    pub routing_service_rate: u64,
+    #[serde(rename = "exitByteRate")]
+    pub exit_byte_rate: u64,
+    #[serde(rename = "exitServiceRate")]
+    pub exit_service_rate: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNeighborhoodNode {
+    #[serde(rename = "publicKey")]
+    pub public_key: String,
+    #[serde(rename = "nodeAddrOpt")]
+    pub node_addr_opt: Option<String>,
+    pub version: u32,
+    #[serde(rename = "ratePack")]
+    pub rate_pack: UiRatePack,
+    #[serde(rename = "acceptsConnections")]
+    pub accepts_connections: bool,
+    #[serde(rename = "routesData")]
+    pub routes_data: bool,
+    pub desirable: bool,
+    pub neighbors: Vec<String>,
+    #[serde(rename = "hopsAwayOpt")]
+    pub hops_away_opt: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNeighborhoodResponse {
+    #[serde(rename = "ourPublicKey")]
+    pub our_public_key: String,
+    pub nodes: Vec<UiNeighborhoodNode>,
+    #[serde(rename = "dotGraphOpt")]
+    pub dot_graph_opt: Option<String>,
+}
+conversation_message!(UiNeighborhoodResponse, "neighborhood");
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UiNewPasswordBroadcast {}
 fire_and_forget_message!(UiNewPasswordBroadcast, "newPassword");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNodePerformanceRequest {}
+conversation_message!(UiNodePerformanceRequest, "nodePerformance");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNodePerformance {
+    #[serde(rename = "publicKey")]
+    pub public_key: String,
+    pub desirable: bool,
+    pub successes: u32,
+    pub failures: u32,
+    pub exits: u32,
+    #[serde(rename = "dnsFailures")]
+    pub dns_failures: u32,
+    #[serde(rename = "latencyMsOpt")]
+    pub latency_ms_opt: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiNodePerformanceResponse {
+    pub nodes: Vec<UiNodePerformance>,
+}
+conversation_message!(UiNodePerformanceResponse, "nodePerformance");
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct UiRecoverWalletsRequest {
     #[serde(rename = "dbPassword")]
@@ -579,6 +784,20 @@ conversation_message!(UiRecoverWalletsRequest, "recoverWallet");
 pub struct UiRecoverWalletsResponse {}
 conversation_message!(UiRecoverWalletsResponse, "recoverWallet");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiRotateKeysRequest {
+    #[serde(rename = "dbPassword")]
+    pub db_password: String,
+}
+conversation_message!(UiRotateKeysRequest, "rotateKeys");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct UiRotateKeysResponse {
+    #[serde(rename = "publicKey")]
+    pub public_key: String,
+}
+conversation_message!(UiRotateKeysResponse, "rotateKeys");
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct UiShutdownRequest {}
 conversation_message!(UiShutdownRequest, "shutdown");
@@ -1,11 +1,17 @@
 use crate::constants::{
-    DEFAULT_CHAIN_NAME, DEFAULT_GAS_PRICE, DEFAULT_UI_PORT, HIGHEST_USABLE_PORT,
-    LOWEST_USABLE_INSECURE_PORT,
+    DEFAULT_CHAIN_NAME, DEFAULT_CONFIRMATIONS, DEFAULT_GAS_PRICE,
+    DEFAULT_MALEFACTOR_BAN_DURATION_HOURS, DEFAULT_PAYMENT_CURVES, DEFAULT_UI_PORT,
+    HIGHEST_USABLE_PORT, HTTP_PORT, LOWEST_USABLE_INSECURE_PORT, TLS_PORT,
 };
 use crate::crash_point::CrashPoint;
 use clap::{App, Arg};
 use lazy_static::lazy_static;
 
+pub const BATCH_PAYMENTS_HELP: &str =
+    "Whether MASQ Node should combine the payments it makes during one scan into as few transactions \
+     as the blockchain allows. On chains with a batch payment contract this saves gas, but the contract \
+     must first have been approved to spend tokens from your consuming wallet; without enough approval, \
+     payments are made one at a time as usual. Choose on or off [default: off]";
 pub const BLOCKCHAIN_SERVICE_HELP: &str =
     "The Ethereum client you wish to use to provide Blockchain \
      exit services from your MASQ Node (e.g. http://localhost:8545, \
@@ -34,6 +40,16 @@ pub const DB_PASSWORD_HELP: &str =
     "A password or phrase to decrypt the encrypted material in the database, to include your \
      mnemonic seed (if applicable) and your list of previous neighbors. If you don't provide this \
      password, none of the encrypted data in your database will be used.";
+pub const DNS_BYPASS_HELP: &str =
+    "Comma-separated list of domains that should resolve normally instead of through the MASQ Network, such as \
+     LAN hosts, banks, or software-update servers. A plain domain covers itself and all its subdomains; a pattern \
+     with '*' must match the whole host name, with '*' standing for any run of characters \
+     (e.g. lan,mybank.com,updates*.example.com)";
+pub const DNS_BYPASS_SERVERS_HELP: &str =
+    "IP addresses of the DNS servers that resolve the domains in --dns-bypass, along with any look-ups for record \
+     types other than A and AAAA (MX, TXT, SRV, and so on). If you don't specify them on Linux, MASQ Node uses \
+     the DNS servers that the dns_utility commented out of /etc/resolv.conf when it subverted DNS. On macOS and \
+     Windows, --dns-bypass needs them. Without this or --dns-bypass, no DNS query is sent to any server.";
 pub const DNS_SERVERS_HELP: &str =
     "IP addresses of DNS Servers for host name look-up while providing exit \
      services for other MASQ Nodes (e.g. 1.0.0.1,1.1.1.1,8.8.8.8,9.9.9.9, etc.)";
@@ -42,6 +58,27 @@ pub const EARNING_WALLET_HELP: &str =
      (case-insensitive). If you already have a derivation-path earning wallet, don't supply this. \
      If you have supplied an earning wallet address before, either don't supply it again or be \
      careful to supply exactly the same one you supplied before.";
+pub const EXIT_EXCLUDE_HELP: &str =
+    "Comma-separated list of exit Nodes your Node must never use for your traffic, by IP address, CIDR range, or \
+     two-letter country code: for example, US,CN,203.0.113.0/24. Country codes need --geoip-database.";
+pub const EXIT_REQUIRE_HELP: &str =
+    "Comma-separated list of the only exit Nodes your Node may use for your traffic, by IP address, CIDR range, or \
+     two-letter country code: for example, DE,NL,2001:db8::/32. An exit Node that matches any of them qualifies \
+     unless --exit-exclude rules it out. Country codes need --geoip-database.";
+pub const EXIT_SERVICE_POLICY_HELP: &str =
+    "Comma-separated list of rules deciding which destinations your Node will connect to when it serves as an \
+     exit Node for others: for example, reject *:6660-6669,accept *.example.com:443,reject 203.0.113.0/24. \
+     The first matching rule decides. Your rules come before the built-in ones, which reject loopback, private, \
+     link-local and multicast addresses and port 25. In zero-hop mode, only your rules apply.";
+pub const FEE_STRATEGY_HELP: &str =
+    "How MASQ Node decides what to offer for gas when it pays other Nodes. With fixed, it always offers \
+     --gas-price. With oracle, it asks the blockchain what recent blocks have charged and offers that, using \
+     EIP-1559 fees where the chain supports them; --gas-price is then used only when the blockchain won't say. \
+     Choose fixed or oracle [default: fixed]";
+pub const GEOIP_DATABASE_HELP: &str =
+    "Path to an offline IP-to-country database, used to apply country codes in --exit-require and --exit-exclude \
+     in place of the DB-IP \"IP to Country Lite\" database bundled into release builds. It must be a CSV file, \
+     gzipped if its name ends in .gz, with one range per line in the form first_ip,last_ip,country_code.";
 pub const IP_ADDRESS_HELP: &str = "The public IP address of your MASQ Node: that is, the IPv4 \
      address at which other Nodes can contact yours. If you're running your Node behind \
      a router, this will be the IP address of the router. If this IP address starts with 192.168 or 10.0, \
@@ -53,6 +90,10 @@ pub const LOG_LEVEL_HELP: &str =
      generates a lot of log traffic. This will both consume your disk space and degrade your Node's performance. \
      You should probably not specify a level higher than the default unless you have security concerns about \
      persistent logs being kept on your computer: if your Node crashes, it's good to know why.";
+pub const MAX_FEE_PER_GAS_HELP: &str =
+    "The most MASQ Node will ever offer per unit of gas when it pays other Nodes, in Gwei, whatever --fee-strategy \
+     suggests. With --fee-strategy oracle, this keeps a sudden spike in the blockchain's fees from emptying your \
+     consuming wallet. Must be at least 1. If left unspecified, there's no cap.";
 pub const NEIGHBORS_HELP: &str = "One or more Node descriptors for running Nodes in the MASQ \
      Network to which you'd like your Node to connect on startup. A Node descriptor looks like \
      this:\n\ngBviQbjOS3e5ReFQCvIhUM3i02d1zPleo1iXg/EN6zQ:86.75.30.9:5542 (initial ':' for testnet) and\n\
@@ -80,6 +121,34 @@ pub const NEIGHBORHOOD_MODE_HELP: &str = "This configures the way the Node relat
      standard means that your Node will operate fully unconstrained, both originating and accepting \
      connections, both consuming and providing services, and when you operate behind a router, it \
      requires that you forward your clandestine port through that router to your Node's machine.";
+pub const ROUTING_STRATEGY_HELP: &str =
+    "How MASQ Node chooses among the routes available for your traffic. With cheapest, it picks the route whose \
+     routing and exit charges add up to the least, with a penalty for each extra hop, among the routes with the \
+     fewest Nodes that keep letting you down. With random, it picks any route whose exit Node hasn't let you \
+     down recently, which spreads your traffic across the Network. With reliable, it picks the route with the \
+     fewest Nodes that have let you down recently, then the cheapest of those. Choose cheapest, random, or \
+     reliable. If you don't specify one, your Node uses the strategy it used last time [default: cheapest]";
+pub const SOCKS_PORT_HELP: &str =
+    "A port on localhost where the Node will accept SOCKS5 connections, such as 1080. Point an application's \
+     SOCKS proxy setting here and its traffic will go through the MASQ Network with the hostname and port it \
+     asked for, without any changes to your system DNS. Any TCP protocol can be carried this way; UDP can't. \
+     It must differ from --clandestine-port and --ui-port. Without this, the Node has no SOCKS listener.";
+pub const UI_AUTH_HELP: &str =
+    "Whether user interfaces must present a token before the Node will talk to them. With on, every UI \
+     connection must carry a token issued by the Daemon; ask the Daemon for one with masq issue-token and hand \
+     it to masq with --ui-token. The first token is written to the ui-token file in your data directory, where \
+     only you can read it; later ones are issued only to a UI that presents a token it already has. Tokens are \
+     kept, hashed, in your data directory. [default: off]";
+pub const UI_REMOTE_ADDRESS_HELP: &str =
+    "An additional IP address and port, such as 0.0.0.0:5334, where the Node will accept user interfaces from \
+     other machines. Connections to this address always use TLS and always need a token, so you must also \
+     specify --ui-auth on and --ui-tls-identity. Reach it with masq --ui-host <host> --ui-port <port> --wss. \
+     Without this, the Node accepts user interfaces only on localhost.";
+pub const UI_TLS_IDENTITY_HELP: &str =
+    "The path to a PKCS#12 (.p12 or .pfx) file holding the certificate and private key the Node presents to \
+     user interfaces connecting to --ui-remote-address.";
+pub const UI_TLS_PASSWORD_HELP: &str =
+    "The password that unlocks the --ui-tls-identity file. Leave it out if the file isn't password-protected.";
 pub const REAL_USER_HELP: &str =
     "The user whose identity Node will assume when dropping privileges after bootstrapping. Since Node refuses to \
      run with root privilege after bootstrapping, you might want to use this if you start the Node as root, or if \
@@ -102,10 +171,31 @@ lazy_static! {
          Must be between {} and {} [default: last used port]",
         LOWEST_USABLE_INSECURE_PORT, HIGHEST_USABLE_PORT
     );
+    pub static ref CONFIRMATIONS_HELP: String = format!(
+        "How many blocks must be mined on top of the block containing a payment to your earning wallet \
+         before MASQ Node credits it. Payments in blocks that are orphaned by a chain reorganization \
+         before then are never credited; payments already credited from an orphaned block are taken back. \
+         [default: {}]",
+        DEFAULT_CONFIRMATIONS
+    );
+    pub static ref MALEFACTOR_BAN_DURATION_HELP: String = format!(
+        "How many hours MASQ Node refuses to deal with another Node after catching it sending malformed or \
+         forged Gossip. The ban covers both the offender's public key and its IP address, and it survives \
+         restarts. Must be at least 1. [default: {}]",
+        DEFAULT_MALEFACTOR_BAN_DURATION_HOURS
+    );
     pub static ref GAS_PRICE_HELP: String = format!(
        "The Gas Price is the amount of Gwei you will pay per unit of gas used in a transaction. \
        If left unspecified, MASQ Node will use the previously stored value (Default {}). Valid range is 1-99 Gwei.",
        DEFAULT_GAS_PRICE);
+    pub static ref PAYMENT_CURVES_HELP: String = format!(
+       "The thresholds the Accountant uses to decide when to pay the Nodes you owe and when to ban the Nodes \
+       that owe you. Six whole numbers separated by '|': payment-suggested-after-sec|payment-grace-before-ban-sec|\
+       permanent-debt-allowed-gwub|balance-to-decrease-from-gwub|balance-decreases-for-sec|unban-when-balance-below-gwub. \
+       balance-decreases-for-sec must be greater than payment-suggested-after-sec. Exit-heavy Nodes may want to \
+       tolerate more debt than consume-only Nodes. If left unspecified, MASQ Node will use the previously stored \
+       value (Default {}).",
+       DEFAULT_PAYMENT_CURVES);
 }
 
 // These Args are needed in more than one clap schema. To avoid code duplication, they're defined here and referred
@@ -202,6 +292,16 @@ pub fn ui_port_arg(help: &str) -> Arg {
 
 pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
     head.arg(
+        Arg::with_name("batch-payments")
+            .long("batch-payments")
+            .value_name("BATCH-PAYMENTS")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["on", "off"])
+            .case_insensitive(true)
+            .help(BATCH_PAYMENTS_HELP),
+    )
+    .arg(
         Arg::with_name("blockchain-service-url")
             .long("blockchain-service-url")
             .empty_values(false)
@@ -220,6 +320,15 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .help(&CLANDESTINE_PORT_HELP),
     )
     .arg(config_file_arg())
+    .arg(
+        Arg::with_name("confirmations")
+            .long("confirmations")
+            .value_name("CONFIRMATIONS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_confirmations)
+            .help(&CONFIRMATIONS_HELP),
+    )
     .arg(
         Arg::with_name("consuming-private-key")
             .long("consuming-private-key")
@@ -241,6 +350,24 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
     )
     .arg(data_directory_arg())
     .arg(db_password_arg(DB_PASSWORD_HELP))
+    .arg(
+        Arg::with_name("dns-bypass")
+            .long("dns-bypass")
+            .value_name("DNS-BYPASS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_dns_bypass)
+            .help(DNS_BYPASS_HELP),
+    )
+    .arg(
+        Arg::with_name("dns-bypass-servers")
+            .long("dns-bypass-servers")
+            .value_name("DNS-BYPASS-SERVERS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_ip_addresses)
+            .help(DNS_BYPASS_SERVERS_HELP),
+    )
     .arg(
         Arg::with_name("dns-servers")
             .long("dns-servers")
@@ -255,6 +382,33 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
         common_validators::validate_ethereum_address,
     ))
     .arg(chain_arg())
+    .arg(
+        Arg::with_name("exit-exclude")
+            .long("exit-exclude")
+            .value_name("EXIT-RULES")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_exit_rules)
+            .help(EXIT_EXCLUDE_HELP),
+    )
+    .arg(
+        Arg::with_name("exit-require")
+            .long("exit-require")
+            .value_name("EXIT-RULES")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_exit_rules)
+            .help(EXIT_REQUIRE_HELP),
+    )
+    .arg(
+        Arg::with_name("exit-service-policy")
+            .long("exit-service-policy")
+            .value_name("EXIT-SERVICE-RULES")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_exit_service_rules)
+            .help(EXIT_SERVICE_POLICY_HELP),
+    )
     .arg(
         Arg::with_name("fake-public-key")
             .long("fake-public-key")
@@ -263,6 +417,16 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .max_values(1)
             .hidden(true),
     )
+    .arg(
+        Arg::with_name("fee-strategy")
+            .long("fee-strategy")
+            .value_name("FEE-STRATEGY")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["fixed", "oracle"])
+            .case_insensitive(true)
+            .help(FEE_STRATEGY_HELP),
+    )
     .arg(
         Arg::with_name("gas-price")
             .long("gas-price")
@@ -272,6 +436,14 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .validator(common_validators::validate_gas_price)
             .help(&GAS_PRICE_HELP),
     )
+    .arg(
+        Arg::with_name("geoip-database")
+            .long("geoip-database")
+            .value_name("GEOIP-DATABASE")
+            .min_values(0)
+            .max_values(1)
+            .help(GEOIP_DATABASE_HELP),
+    )
     .arg(
         Arg::with_name("ip")
             .long("ip")
@@ -291,6 +463,24 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .case_insensitive(true)
             .help(LOG_LEVEL_HELP),
     )
+    .arg(
+        Arg::with_name("malefactor-ban-duration")
+            .long("malefactor-ban-duration")
+            .value_name("HOURS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_malefactor_ban_duration)
+            .help(&MALEFACTOR_BAN_DURATION_HELP),
+    )
+    .arg(
+        Arg::with_name("max-fee-per-gas")
+            .long("max-fee-per-gas")
+            .value_name("GWEI")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_max_fee_per_gas)
+            .help(MAX_FEE_PER_GAS_HELP),
+    )
     .arg(
         Arg::with_name("neighborhood-mode")
             .long("neighborhood-mode")
@@ -308,13 +498,76 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .min_values(0)
             .help(NEIGHBORS_HELP),
     )
+    .arg(
+        Arg::with_name("payment-curves")
+            .long("payment-curves")
+            .value_name("PAYMENT-CURVES")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_payment_curves)
+            .help(&PAYMENT_CURVES_HELP),
+    )
+    .arg(
+        Arg::with_name("routing-strategy")
+            .long("routing-strategy")
+            .value_name("ROUTING-STRATEGY")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["cheapest", "random", "reliable"])
+            .case_insensitive(true)
+            .help(ROUTING_STRATEGY_HELP),
+    )
+    .arg(
+        Arg::with_name("socks-port")
+            .long("socks-port")
+            .value_name("SOCKS-PORT")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_socks_port)
+            .help(SOCKS_PORT_HELP),
+    )
+    .arg(
+        Arg::with_name("ui-auth")
+            .long("ui-auth")
+            .value_name("UI-AUTH")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["on", "off"])
+            .case_insensitive(true)
+            .help(UI_AUTH_HELP),
+    )
+    .arg(
+        Arg::with_name("ui-remote-address")
+            .long("ui-remote-address")
+            .value_name("IP:PORT")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_socket_address)
+            .help(UI_REMOTE_ADDRESS_HELP),
+    )
+    .arg(
+        Arg::with_name("ui-tls-identity")
+            .long("ui-tls-identity")
+            .value_name("FILE-PATH")
+            .min_values(0)
+            .max_values(1)
+            .help(UI_TLS_IDENTITY_HELP),
+    )
+    .arg(
+        Arg::with_name("ui-tls-password")
+            .long("ui-tls-password")
+            .value_name("PASSWORD")
+            .min_values(0)
+            .max_values(1)
+            .help(UI_TLS_PASSWORD_HELP),
+    )
     .arg(real_user_arg())
 }
 
 pub mod common_validators {
     use crate::constants::LOWEST_USABLE_INSECURE_PORT;
     use regex::Regex;
-    use std::net::IpAddr;
+    use std::net::{IpAddr, SocketAddr};
     use std::str::FromStr;
     use tiny_hderive::bip44::DerivationPath;
 
@@ -367,6 +620,173 @@ pub mod common_validators {
         }
     }
 
+    pub fn validate_confirmations(confirmations: String) -> Result<(), String> {
+        match confirmations.parse::<u64>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(confirmations),
+        }
+    }
+
+    pub fn validate_malefactor_ban_duration(hours: String) -> Result<(), String> {
+        match hours.parse::<u64>() {
+            Ok(h) if h > 0 => Ok(()),
+            _ => Err(hours),
+        }
+    }
+
+    pub fn validate_max_fee_per_gas(gwei: String) -> Result<(), String> {
+        match gwei.parse::<u64>() {
+            Ok(g) if g > 0 => Ok(()),
+            _ => Err(gwei),
+        }
+    }
+
+    pub fn validate_dns_bypass(patterns: String) -> Result<(), String> {
+        let pattern_is_valid = |pattern: &str| {
+            pattern
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '*')
+        };
+        if patterns
+            .split(',')
+            .map(|pattern| pattern.trim())
+            .all(pattern_is_valid)
+        {
+            Ok(())
+        } else {
+            Err(patterns)
+        }
+    }
+
+    fn ip_range_is_valid(range: &str) -> bool {
+        let mut pieces = range.splitn(2, '/');
+        let max_prefix_len = match IpAddr::from_str(pieces.next().expect("splitn is broken")) {
+            Ok(IpAddr::V4(_)) => 32,
+            Ok(IpAddr::V6(_)) => 128,
+            Err(_) => return false,
+        };
+        match pieces.next() {
+            None => true,
+            Some(prefix_len) => {
+                matches!(prefix_len.parse::<u8>(), Ok(len) if len <= max_prefix_len)
+            }
+        }
+    }
+
+    // Each rule is a two-letter country code, an IP address, or a CIDR range.
+    pub fn validate_exit_rules(rules: String) -> Result<(), String> {
+        let rule_is_valid = |rule: &str| {
+            (rule.len() == 2 && rule.chars().all(|c| c.is_ascii_alphabetic()))
+                || ip_range_is_valid(rule)
+        };
+        if rules
+            .split(',')
+            .map(|rule| rule.trim())
+            .filter(|rule| !rule.is_empty())
+            .all(rule_is_valid)
+        {
+            Ok(())
+        } else {
+            Err(rules)
+        }
+    }
+
+    // Each rule is accept or reject, then a destination, then optionally a colon and a port or port
+    // range. A destination is *, an IP address or CIDR range (IPv6 in brackets), a host name, or
+    // *. and a domain.
+    pub fn validate_exit_service_rules(rules: String) -> Result<(), String> {
+        let ports_are_valid = |ports: &str| {
+            if ports == "*" {
+                return true;
+            }
+            let mut pieces = ports.splitn(2, '-');
+            let low = pieces.next().expect("splitn is broken").parse::<u16>();
+            let high = pieces.next().map(|high| high.parse::<u16>());
+            match (low, high) {
+                (Ok(low), None) => low > 0,
+                (Ok(low), Some(Ok(high))) => low > 0 && high >= low,
+                _ => false,
+            }
+        };
+        let host_is_valid = |host: &str| {
+            let host = host.strip_prefix("*.").unwrap_or(host);
+            host.split('.').all(|label| {
+                !label.is_empty()
+                    && label
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+        };
+        let rule_is_valid = |rule: &str| {
+            let mut words = rule.split_whitespace();
+            let action = words.next().map(|word| word.to_lowercase());
+            if action != Some("accept".to_string()) && action != Some("reject".to_string()) {
+                return false;
+            }
+            let destination = match (words.next(), words.next()) {
+                (Some(destination), None) => destination,
+                _ => return false,
+            };
+            if let Some(bracketed) = destination.strip_prefix('[') {
+                let mut pieces = bracketed.splitn(2, ']');
+                let address = pieces.next().expect("splitn is broken");
+                let rest = match pieces.next() {
+                    Some(rest) => rest,
+                    None => return false,
+                };
+                let (prefix, ports_opt) = match rest.find(':') {
+                    Some(colon) => (&rest[..colon], Some(&rest[colon + 1..])),
+                    None => (rest, None),
+                };
+                return address.contains(':')
+                    && ip_range_is_valid(&format!("{}{}", address, prefix))
+                    && ports_opt.map(ports_are_valid).unwrap_or(true);
+            }
+            let mut pieces = destination.splitn(2, ':');
+            let target = pieces.next().expect("splitn is broken");
+            let ports_valid = pieces.next().map(ports_are_valid).unwrap_or(true);
+            let target_valid = if target == "*" {
+                true
+            } else if target
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.' || c == '/')
+            {
+                ip_range_is_valid(target)
+            } else {
+                host_is_valid(target)
+            };
+            ports_valid && target_valid
+        };
+        if rules
+            .split(',')
+            .map(|rule| rule.trim())
+            .filter(|rule| !rule.is_empty())
+            .all(rule_is_valid)
+        {
+            Ok(())
+        } else {
+            Err(rules)
+        }
+    }
+
+    pub fn validate_payment_curves(curves: String) -> Result<(), String> {
+        let values = curves
+            .split('|')
+            .map(|value| value.parse::<i64>())
+            .collect::<Result<Vec<i64>, _>>();
+        match values {
+            Ok(ref values) if values.len() == 6 && values.iter().all(|value| *value >= 0) => {
+                // balance-decreases-for-sec must come after payment-suggested-after-sec
+                if values[4] > values[0] {
+                    Ok(())
+                } else {
+                    Err(curves)
+                }
+            }
+            _ => Err(curves),
+        }
+    }
+
     pub fn validate_earning_wallet(value: String) -> Result<(), String> {
         validate_ethereum_address(value.clone()).or_else(|_| validate_derivation_path(value))
     }
@@ -420,6 +840,21 @@ pub mod common_validators {
         }
     }
 
+    pub fn validate_socks_port(port: String) -> Result<(), String> {
+        match str::parse::<u16>(&port) {
+            Ok(0) | Ok(HTTP_PORT) | Ok(TLS_PORT) => Err(port),
+            Ok(_) => Ok(()),
+            Err(_) => Err(port),
+        }
+    }
+
+    pub fn validate_socket_address(address: String) -> Result<(), String> {
+        match SocketAddr::from_str(&address) {
+            Ok(socket_addr) if socket_addr.port() != 0 => Ok(()),
+            _ => Err(address),
+        }
+    }
+
     pub fn validate_ui_port(port: String) -> Result<(), String> {
         match str::parse::<u16>(&port) {
             Ok(port_number) if port_number < LOWEST_USABLE_INSECURE_PORT => Err(port),
@@ -472,6 +907,7 @@ impl ConfiguratorError {
 
 #[cfg(test)]
 mod tests {
+    use crate::constants::DEFAULT_PAYMENT_CURVES;
     use crate::shared_schema::common_validators;
 
     #[test]
@@ -518,6 +954,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_socket_address_given_invalid_input() {
+        assert_eq!(
+            common_validators::validate_socket_address(String::from("1.2.3.4")),
+            Err(String::from("1.2.3.4"))
+        );
+        assert_eq!(
+            common_validators::validate_socket_address(String::from("1.2.3.4:0")),
+            Err(String::from("1.2.3.4:0"))
+        );
+    }
+
+    #[test]
+    fn validate_socket_address_given_valid_input() {
+        assert_eq!(
+            common_validators::validate_socket_address(String::from("0.0.0.0:5334")),
+            Ok(())
+        );
+        assert_eq!(
+            common_validators::validate_socket_address(String::from("[::1]:5334")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validate_socks_port_rejects_zero_garbage_and_proxy_ports() {
+        ["0", "booga", "80", "443", "65536"]
+            .iter()
+            .for_each(|port| {
+                assert_eq!(
+                    common_validators::validate_socks_port(port.to_string()),
+                    Err(port.to_string())
+                )
+            });
+        assert_eq!(
+            common_validators::validate_socks_port(String::from("1080")),
+            Ok(())
+        );
+    }
+
     #[test]
     fn validate_ui_port_complains_about_non_numeric_ui_port() {
         let result = common_validators::validate_ui_port(String::from("booga"));
@@ -575,6 +1051,48 @@ mod tests {
         assert_eq!(Ok(()), result);
     }
 
+    #[test]
+    fn validate_confirmations_accepts_zero() {
+        let result = common_validators::validate_confirmations("0".to_string());
+
+        assert_eq!(Ok(()), result);
+    }
+
+    #[test]
+    fn validate_confirmations_rejects_negative_numbers() {
+        let result = common_validators::validate_confirmations("-1".to_string());
+
+        assert_eq!(Err(String::from("-1")), result);
+    }
+
+    #[test]
+    fn validate_malefactor_ban_duration_accepts_positive_hours() {
+        let result = common_validators::validate_malefactor_ban_duration("72".to_string());
+
+        assert_eq!(Ok(()), result);
+    }
+
+    #[test]
+    fn validate_malefactor_ban_duration_rejects_zero() {
+        let result = common_validators::validate_malefactor_ban_duration("0".to_string());
+
+        assert_eq!(Err(String::from("0")), result);
+    }
+
+    #[test]
+    fn validate_max_fee_per_gas_accepts_positive_gwei() {
+        let result = common_validators::validate_max_fee_per_gas("150".to_string());
+
+        assert_eq!(Ok(()), result);
+    }
+
+    #[test]
+    fn validate_max_fee_per_gas_rejects_zero() {
+        let result = common_validators::validate_max_fee_per_gas("0".to_string());
+
+        assert_eq!(Err(String::from("0")), result);
+    }
+
     #[test]
     fn validate_gas_price_zero() {
         let result = common_validators::validate_gas_price("0".to_string());
@@ -626,4 +1144,107 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(Err(String::from("0x0")), result);
     }
+
+    #[test]
+    fn validate_dns_bypass_accepts_domains_and_wildcards() {
+        let result = common_validators::validate_dns_bypass(
+            "lan, MyBank.com,updates*.example.com,*.local.".to_string(),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_dns_bypass_rejects_things_that_are_not_domains() {
+        let result = common_validators::validate_dns_bypass("lan,http://mybank.com".to_string());
+
+        assert_eq!(result, Err("lan,http://mybank.com".to_string()));
+    }
+
+    #[test]
+    fn validate_exit_rules_accepts_countries_addresses_and_ranges() {
+        let result = common_validators::validate_exit_rules(
+            "us, 1.2.3.4,10.0.0.0/8,2001:db8::/32".to_string(),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_exit_rules_rejects_nonsense() {
+        let country = common_validators::validate_exit_rules("USA".to_string());
+        let prefix = common_validators::validate_exit_rules("DE,10.0.0.0/33".to_string());
+        let address = common_validators::validate_exit_rules("10.0.0/8".to_string());
+
+        assert_eq!(country, Err("USA".to_string()));
+        assert_eq!(prefix, Err("DE,10.0.0.0/33".to_string()));
+        assert_eq!(address, Err("10.0.0/8".to_string()));
+    }
+
+    #[test]
+    fn validate_exit_service_rules_accepts_all_kinds_of_rules() {
+        let result = common_validators::validate_exit_service_rules(
+            "reject 10.0.0.0/8, ACCEPT *.example.com:443,reject [fe80::]/10:*, reject [::1],\
+             reject *:6660-6669,accept localhost, reject 1.2.3.4:25"
+                .to_string(),
+        );
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_exit_service_rules_rejects_nonsense() {
+        let bad_rules = vec![
+            "allow 1.2.3.4",
+            "reject",
+            "reject 1.2.3.4 5.6.7.8",
+            "reject *:0",
+            "reject *:80-79",
+            "reject fe80::/10",
+            "reject [fe80::]/129",
+            "reject [1.2.3.4]",
+            "reject 10.0.0.0/33",
+            "reject exa$mple.com",
+            "reject http://example.com",
+        ];
+
+        bad_rules.into_iter().for_each(|rule| {
+            assert_eq!(
+                common_validators::validate_exit_service_rules(format!("accept *:443,{}", rule)),
+                Err(format!("accept *:443,{}", rule)),
+                "{}",
+                rule
+            )
+        });
+    }
+
+    #[test]
+    fn validate_payment_curves_accepts_default() {
+        let result = common_validators::validate_payment_curves(DEFAULT_PAYMENT_CURVES.to_string());
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_payment_curves_rejects_wrong_number_of_values() {
+        let result = common_validators::validate_payment_curves("1|2|3|4|5".to_string());
+
+        assert_eq!(result, Err("1|2|3|4|5".to_string()));
+    }
+
+    #[test]
+    fn validate_payment_curves_rejects_negative_and_non_numeric_values() {
+        let negative = common_validators::validate_payment_curves("1|2|-3|4|5|6".to_string());
+        let non_numeric = common_validators::validate_payment_curves("1|2|3|booga|5|6".to_string());
+
+        assert_eq!(negative, Err("1|2|-3|4|5|6".to_string()));
+        assert_eq!(non_numeric, Err("1|2|3|booga|5|6".to_string()));
+    }
+
+    #[test]
+    fn validate_payment_curves_rejects_decrease_that_ends_before_it_starts() {
+        let result = common_validators::validate_payment_curves("5|2|3|4|5|6".to_string());
+
+        assert_eq!(result, Err("5|2|3|4|5|6".to_string()));
+    }
 }
@@ -125,6 +125,7 @@ pub struct NodeStartupConfig {
     pub blockchain_service_url_opt: Option<String>,
     pub chain_opt: Option<String>,
     pub db_password_opt: Option<String>,
+    pub exit_service_policy_opt: Option<String>,
 }
 
 impl Default for NodeStartupConfig {
@@ -152,6 +153,7 @@ impl NodeStartupConfig {
             blockchain_service_url_opt: None,
             chain_opt: Some(TEST_DEFAULT_CHAIN_NAME.to_string()),
             db_password_opt: Some("password".to_string()),
+            exit_service_policy_opt: None,
         }
     }
 
@@ -207,6 +209,10 @@ impl NodeStartupConfig {
             args.push("--db-password".to_string());
             args.push(db_password.to_string());
         }
+        if let Some(ref exit_service_policy) = self.exit_service_policy_opt {
+            args.push("--exit-service-policy".to_string());
+            args.push(exit_service_policy.to_string());
+        }
         args
     }
 
@@ -376,6 +382,7 @@ pub struct NodeStartupConfigBuilder {
     blockchain_service_url: Option<String>,
     chain: Option<String>,
     db_password: Option<String>,
+    exit_service_policy: Option<String>,
 }
 
 impl NodeStartupConfigBuilder {
@@ -397,6 +404,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url: None,
             chain: Some(TEST_DEFAULT_CHAIN_NAME.to_string()),
             db_password: None,
+            exit_service_policy: None,
         }
     }
 
@@ -422,6 +430,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url: None,
             chain: Some(TEST_DEFAULT_CHAIN_NAME.to_string()),
             db_password: Some("password".to_string()),
+            exit_service_policy: None,
         }
     }
 
@@ -447,6 +456,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url: None,
             chain: Some(TEST_DEFAULT_CHAIN_NAME.to_string()),
             db_password: Some("password".to_string()),
+            exit_service_policy: None,
         }
     }
 
@@ -468,6 +478,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url: None,
             chain: Some(TEST_DEFAULT_CHAIN_NAME.to_string()),
             db_password: Some("password".to_string()),
+            exit_service_policy: None,
         }
     }
 
@@ -489,6 +500,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url: config.blockchain_service_url_opt.clone(),
             chain: config.chain_opt.clone(),
             db_password: config.db_password_opt.clone(),
+            exit_service_policy: config.exit_service_policy_opt.clone(),
         }
     }
 
@@ -600,6 +612,11 @@ impl NodeStartupConfigBuilder {
         self
     }
 
+    pub fn exit_service_policy(mut self, value: &str) -> Self {
+        self.exit_service_policy = Some(value.to_string());
+        self
+    }
+
     pub fn build(self) -> NodeStartupConfig {
         NodeStartupConfig {
             neighborhood_mode: self.neighborhood_mode,
@@ -618,6 +635,7 @@ impl NodeStartupConfigBuilder {
             blockchain_service_url_opt: self.blockchain_service_url,
             chain_opt: self.chain,
             db_password_opt: self.db_password,
+            exit_service_policy_opt: self.exit_service_policy,
         }
     }
 }
@@ -1288,6 +1306,7 @@ mod tests {
             blockchain_service_url_opt: None,
             chain_opt: None,
             db_password_opt: Some("booga".to_string()),
+            exit_service_policy_opt: Some("accept 172.18.0.0/16".to_string()),
         };
         let neighborhood_mode = "standard".to_string();
         let ip_addr = IpAddr::from_str("1.2.3.4").unwrap();
@@ -1344,7 +1363,11 @@ mod tests {
             result.fake_public_key_opt,
             Some(PublicKey::new(&[1, 2, 3, 4]))
         );
-        assert_eq!(result.db_password_opt, Some("booga".to_string()))
+        assert_eq!(result.db_password_opt, Some("booga".to_string()));
+        assert_eq!(
+            result.exit_service_policy_opt,
+            Some("accept 172.18.0.0/16".to_string())
+        )
     }
 
     #[test]
@@ -200,12 +200,10 @@ impl MultinodeGossip for Introduction {
     }
 
     fn render(&self) -> Gossip_0v1 {
-        Gossip_0v1 {
-            node_records: vec![
-                GossipNodeRecord::from(self.introducer.clone()),
-                GossipNodeRecord::from(self.introducee.clone()),
-            ],
-        }
+        Gossip_0v1::new(vec![
+            GossipNodeRecord::from(self.introducer.clone()),
+            GossipNodeRecord::from(self.introducee.clone()),
+        ])
     }
 }
 
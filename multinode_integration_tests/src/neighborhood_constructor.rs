@@ -61,9 +61,29 @@ pub fn construct_neighborhood(
     NeighborhoodDatabase,
     MASQRealNode,
     HashMap<PublicKey, MASQMockNode>,
+) {
+    construct_neighborhood_from(
+        cluster,
+        model_db,
+        additional_keys_to_mock,
+        NodeStartupConfigBuilder::standard(),
+    )
+}
+
+// Like construct_neighborhood, but the real Node starts from config_builder instead of a plain
+// standard configuration.
+pub fn construct_neighborhood_from(
+    cluster: &mut MASQNodeCluster,
+    model_db: NeighborhoodDatabase,
+    additional_keys_to_mock: Vec<&PublicKey>,
+    config_builder: NodeStartupConfigBuilder,
+) -> (
+    NeighborhoodDatabase,
+    MASQRealNode,
+    HashMap<PublicKey, MASQMockNode>,
 ) {
     let real_node = cluster.start_real_node(
-        NodeStartupConfigBuilder::standard()
+        config_builder
             .fake_public_key(model_db.root().public_key())
             .consuming_wallet_info(make_consuming_wallet_info(
                 model_db.root().public_key().to_string().as_str(),
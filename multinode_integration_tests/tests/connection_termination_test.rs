@@ -6,9 +6,11 @@ use multinode_integration_tests_lib::masq_mock_node::MASQMockNode;
 use multinode_integration_tests_lib::masq_node::{MASQNode, MASQNodeUtils, PortSelector};
 use multinode_integration_tests_lib::masq_node_cluster::MASQNodeCluster;
 use multinode_integration_tests_lib::masq_node_server::MASQNodeServer;
-use multinode_integration_tests_lib::masq_real_node::MASQRealNode;
+use multinode_integration_tests_lib::masq_real_node::{MASQRealNode, NodeStartupConfigBuilder};
 use multinode_integration_tests_lib::multinode_gossip::{parse_gossip, GossipType};
-use multinode_integration_tests_lib::neighborhood_constructor::construct_neighborhood;
+use multinode_integration_tests_lib::neighborhood_constructor::{
+    construct_neighborhood, construct_neighborhood_from,
+};
 use node_lib::blockchain::blockchain_interface::contract_address;
 use node_lib::hopper::live_cores_package::LiveCoresPackage;
 use node_lib::json_masquerader::JsonMasquerader;
@@ -35,6 +37,9 @@ use std::time::Duration;
 const HTTP_REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: booga.com\r\n\r\n";
 const HTTP_RESPONSE: &[u8] =
     b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Type: text/plain\r\n\r\nbooga";
+// The servers the exit Node talks to in these tests are on the Docker network, which the default
+// exit service rules refuse as a private range.
+const DOCKER_NETWORK_POLICY: &str = "accept 172.18.0.0/16";
 
 #[test]
 // Given: Originating Node is real_node; exit Node is fictional Node with exit_key.
@@ -262,7 +267,12 @@ fn create_neighborhood(cluster: &mut MASQNodeCluster) -> (MASQRealNode, MASQMock
     db.add_node(mock_node.clone()).unwrap();
     db.add_node(fictional_node_1.clone()).unwrap();
     db.add_node(fictional_node_2.clone()).unwrap();
-    let (_, masq_real_node, mut node_map) = construct_neighborhood(cluster, db, vec![]);
+    let (_, masq_real_node, mut node_map) = construct_neighborhood_from(
+        cluster,
+        db,
+        vec![],
+        NodeStartupConfigBuilder::standard().exit_service_policy(DOCKER_NETWORK_POLICY),
+    );
     let masq_mock_node = node_map.remove(mock_node.public_key()).unwrap();
     (
         masq_real_node,
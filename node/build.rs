@@ -0,0 +1,22 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Bundles the GeoIP database that geoip/fetch_dbip_country_lite.sh downloads. Without it, the Node is
+// built with an empty one, and country codes in exit rules need --geoip-database.
+fn main() {
+    let source = Path::new("geoip").join("dbip-country-lite.csv.gz");
+    let target = PathBuf::from(env::var("OUT_DIR").expect("Cargo didn't set OUT_DIR"))
+        .join("bundled-geoip.csv.gz");
+    println!("cargo:rerun-if-changed=geoip");
+    if source.exists() {
+        fs::copy(&source, &target).expect("Couldn't copy bundled GeoIP database");
+    } else {
+        println!(
+            "cargo:warning=No {:?}: building without a bundled GeoIP database",
+            source
+        );
+        fs::write(&target, b"").expect("Couldn't write empty GeoIP database");
+    }
+}
@@ -3,5 +3,6 @@
 CI_DIR="$( cd "$( dirname "$0" )" && pwd )"
 
 pushd "$CI_DIR/.."
+geoip/fetch_dbip_country_lite.sh
 cargo build --all --lib --bins --tests --release --verbose
 popd
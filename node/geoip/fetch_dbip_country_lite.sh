@@ -0,0 +1,27 @@
+#!/bin/bash -ev
+# Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+# Downloads DB-IP's "IP to Country Lite" database into this directory, where the Node build picks it
+# up and bundles it into the Node. See README.md here for its license.
+GEOIP_DIR="$( cd "$( dirname "$0" )" && pwd )"
+DATABASE="$GEOIP_DIR/dbip-country-lite.csv.gz"
+
+function month() {
+  # DB-IP publishes each month's database early in that month, so fall back to last month's.
+  if [[ "$1" == "0" ]]; then
+    date -u +%Y-%m
+  else
+    date -u -d "-$1 month" +%Y-%m 2>/dev/null || date -u -v-"$1"m +%Y-%m
+  fi
+}
+
+for months_ago in 0 1; do
+  URL="https://download.db-ip.com/free/dbip-country-lite-$(month $months_ago).csv.gz"
+  if curl --fail --location --silent --show-error --output "$DATABASE.part" "$URL"; then
+    gzip --test "$DATABASE.part"
+    mv "$DATABASE.part" "$DATABASE"
+    exit 0
+  fi
+done
+rm -f "$DATABASE.part"
+echo "Couldn't download the DB-IP IP to Country Lite database"
+exit 1
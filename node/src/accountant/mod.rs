@@ -1,15 +1,21 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 pub mod payable_dao;
+pub mod payment_history_dao;
 pub mod receivable_dao;
 
 #[cfg(test)]
 pub mod test_utils;
 
 use crate::accountant::payable_dao::{PayableAccount, PayableDaoFactory, Payment};
+use crate::accountant::payment_history_dao::{
+    PaymentHistoryDao, PaymentHistoryDaoFactory, PaymentStatus,
+};
 use crate::accountant::receivable_dao::{ReceivableAccount, ReceivableDaoFactory};
 use crate::banned_dao::{BannedDao, BannedDaoFactory};
-use crate::blockchain::blockchain_bridge::RetrieveTransactions;
+use crate::blockchain::blockchain_bridge::{
+    RequestTransactionReceipts, RetrieveTransactions, RetrievedTransactions, TransactionReceipts,
+};
 use crate::blockchain::blockchain_interface::{BlockchainError, Transaction};
 use crate::bootstrapper::BootstrapperConfig;
 use crate::db_config::config_dao::ConfigDaoFactory;
@@ -40,21 +46,26 @@ use lazy_static::lazy_static;
 use masq_lib::messages::UiMessageError::UnexpectedMessage;
 use masq_lib::messages::{FromMessageBody, ToMessageBody, UiFinancialsRequest, UiMessageError};
 use masq_lib::messages::{UiFinancialsResponse, UiPayableAccount, UiReceivableAccount};
+use masq_lib::shared_schema::common_validators;
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
 use payable_dao::PayableDao;
 use receivable_dao::ReceivableDao;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::thread;
 use std::time::{Duration, SystemTime};
+use web3::types::{TransactionReceipt, U256};
 
 pub const CRASH_KEY: &str = "ACCOUNTANT";
 pub const DEFAULT_PAYABLE_SCAN_INTERVAL: u64 = 3600; // one hour
 pub const DEFAULT_PAYMENT_RECEIVED_SCAN_INTERVAL: u64 = 3600; // one hour
+pub const PENDING_TRANSACTION_TIMEOUT: u64 = 21_600; // six hours
 
 const SECONDS_PER_DAY: i64 = 86_400;
 
 lazy_static! {
-    pub static ref PAYMENT_CURVES: PaymentCurves = PaymentCurves {
+    pub static ref DEFAULT_PAYMENT_CURVES: PaymentCurves = PaymentCurves {
         payment_suggested_after_sec: SECONDS_PER_DAY,
         payment_grace_before_ban_sec: SECONDS_PER_DAY,
         permanent_debt_allowed_gwub: 10_000_000,
@@ -64,6 +75,14 @@ lazy_static! {
     };
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum PendingPaymentStatus {
+    Confirmed,
+    Failed,
+    Dropped,
+    StillPending,
+}
+
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum PaymentError {
     SignConversion(u64),
@@ -89,18 +108,61 @@ impl PaymentCurves {
     }
 }
 
+impl FromStr for PaymentCurves {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        common_validators::validate_payment_curves(s.to_string())
+            .map_err(|curves| format!("Invalid payment curves: {}", curves))?;
+        let values = s
+            .split('|')
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("Payment curves validation failed")
+            })
+            .collect::<Vec<i64>>();
+        Ok(PaymentCurves {
+            payment_suggested_after_sec: values[0],
+            payment_grace_before_ban_sec: values[1],
+            permanent_debt_allowed_gwub: values[2],
+            balance_to_decrease_from_gwub: values[3],
+            balance_decreases_for_sec: values[4],
+            unban_when_balance_below_gwub: values[5],
+        })
+    }
+}
+
+impl Display for PaymentCurves {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|{}",
+            self.payment_suggested_after_sec,
+            self.payment_grace_before_ban_sec,
+            self.permanent_debt_allowed_gwub,
+            self.balance_to_decrease_from_gwub,
+            self.balance_decreases_for_sec,
+            self.unban_when_balance_below_gwub
+        )
+    }
+}
+
 pub struct Accountant {
     config: AccountantConfig,
     consuming_wallet: Option<Wallet>,
     earning_wallet: Wallet,
     payable_dao: Box<dyn PayableDao>,
     receivable_dao: Box<dyn ReceivableDao>,
+    payment_history_dao: Box<dyn PaymentHistoryDao>,
     banned_dao: Box<dyn BannedDao>,
     persistent_configuration: Box<dyn PersistentConfiguration>,
     report_accounts_payable_sub: Option<Recipient<ReportAccountsPayable>>,
     retrieve_transactions_sub: Option<Recipient<RetrieveTransactions>>,
     report_new_payments_sub: Option<Recipient<ReceivedPayments>>,
     report_sent_payments_sub: Option<Recipient<SentPayments>>,
+    request_transaction_receipts_sub: Option<Recipient<RequestTransactionReceipts>>,
+    report_transaction_receipts_sub: Option<Recipient<ReportTransactionReceipts>>,
     ui_message_sub: Option<Recipient<NodeToUiMessage>>,
     logger: Logger,
 }
@@ -111,7 +173,10 @@ impl Actor for Accountant {
 
 #[derive(Debug, Eq, Message, PartialEq)]
 pub struct ReceivedPayments {
+    orphaned_blocks: Vec<u64>,
+    new_start_block: u64,
     payments: Vec<Transaction>,
+    more_to_scan: bool,
 }
 
 #[derive(Debug, Eq, Message, PartialEq)]
@@ -119,6 +184,12 @@ pub struct SentPayments {
     pub payments: Vec<Result<Payment, BlockchainError>>,
 }
 
+#[derive(Debug, Message, PartialEq)]
+pub struct ReportTransactionReceipts {
+    pub payments_with_receipts: Vec<(Payment, Option<TransactionReceipt>)>,
+    pub mined_nonce_count_opt: Option<U256>,
+}
+
 impl Handler<BindMessage> for Accountant {
     type Result = ();
 
@@ -135,6 +206,7 @@ impl Handler<StartMessage> for Accountant {
         self.handle_start_message();
 
         ctx.run_interval(self.config.payable_scan_interval, |accountant, _ctx| {
+            accountant.scan_for_pending_payments();
             accountant.scan_for_payables();
         });
 
@@ -164,6 +236,14 @@ impl Handler<SentPayments> for Accountant {
     }
 }
 
+impl Handler<ReportTransactionReceipts> for Accountant {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReportTransactionReceipts, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_transaction_receipts(msg);
+    }
+}
+
 impl Handler<ReportRoutingServiceProvidedMessage> for Accountant {
     type Result = ();
 
@@ -225,6 +305,7 @@ impl Accountant {
         config: &BootstrapperConfig,
         payable_dao_factory: Box<dyn PayableDaoFactory>,
         receivable_dao_factory: Box<dyn ReceivableDaoFactory>,
+        payment_history_dao_factory: Box<dyn PaymentHistoryDaoFactory>,
         banned_dao_factory: Box<dyn BannedDaoFactory>,
         config_dao_factory: Box<dyn ConfigDaoFactory>,
     ) -> Accountant {
@@ -234,6 +315,7 @@ impl Accountant {
             earning_wallet: config.earning_wallet.clone(),
             payable_dao: payable_dao_factory.make(),
             receivable_dao: receivable_dao_factory.make(),
+            payment_history_dao: payment_history_dao_factory.make(),
             banned_dao: banned_dao_factory.make(),
             persistent_configuration: Box::new(PersistentConfigurationReal::new(
                 config_dao_factory.make(),
@@ -242,6 +324,8 @@ impl Accountant {
             retrieve_transactions_sub: None,
             report_new_payments_sub: None,
             report_sent_payments_sub: None,
+            request_transaction_receipts_sub: None,
+            report_transaction_receipts_sub: None,
             ui_message_sub: None,
             logger: Logger::new("Accountant"),
         }
@@ -265,6 +349,7 @@ impl Accountant {
                 .recipient::<ReportExitServiceConsumedMessage>(),
             report_new_payments: addr.clone().recipient::<ReceivedPayments>(),
             report_sent_payments: addr.clone().recipient::<SentPayments>(),
+            report_transaction_receipts: addr.clone().recipient::<ReportTransactionReceipts>(),
             ui_message_sub: addr.clone().recipient::<NodeFromUiMessage>(),
         }
     }
@@ -277,7 +362,7 @@ impl Accountant {
             .payable_dao
             .non_pending_payables()
             .into_iter()
-            .filter(Accountant::should_pay)
+            .filter(|payable| Accountant::should_pay(payable, &self.config.payment_curves))
             .collect::<Vec<PayableAccount>>();
 
         if !payables.is_empty() {
@@ -312,12 +397,81 @@ impl Accountant {
         }
     }
 
+    fn scan_for_pending_payments(&mut self) {
+        debug!(self.logger, "Scanning for pending payments");
+        let future_logger = self.logger.clone();
+
+        let pending_payments = self.payable_dao.pending_payments();
+        if pending_payments.is_empty() {
+            return;
+        }
+
+        let report_transaction_receipts = self.report_transaction_receipts_sub.clone();
+        let future = self
+            .request_transaction_receipts_sub
+            .as_ref()
+            .expect("BlockchainBridge is unbound")
+            .send(RequestTransactionReceipts {
+                pending_payments: pending_payments.clone(),
+            })
+            .then(move |receipts_possibly| match receipts_possibly {
+                Ok(receipts) => {
+                    report_transaction_receipts
+                        .expect("Accountant is unbound")
+                        .try_send(Self::report_transaction_receipts(
+                            pending_payments,
+                            receipts,
+                            &future_logger,
+                        ))
+                        .expect("Accountant is dead");
+                    Ok(())
+                }
+                Err(e) => {
+                    error!(
+                        future_logger,
+                        "Unable to send RequestTransactionReceipts: {:?}", e
+                    );
+                    thread::sleep(Duration::from_secs(1));
+                    panic!("Unable to send RequestTransactionReceipts: {:?}", e);
+                }
+            });
+        actix::spawn(future);
+    }
+
+    // A payment whose receipt couldn't be fetched stays pending until the next scan
+    fn report_transaction_receipts(
+        pending_payments: Vec<Payment>,
+        receipts: TransactionReceipts,
+        logger: &Logger,
+    ) -> ReportTransactionReceipts {
+        let payments_with_receipts = pending_payments
+            .into_iter()
+            .zip(receipts.receipts.into_iter())
+            .flat_map(|(payment, receipt_result)| match receipt_result {
+                Ok(receipt_opt) => Some((payment, receipt_opt)),
+                Err(e) => {
+                    warning!(
+                        logger,
+                        "Unable to retrieve receipt for transaction {:#x} from Blockchain Bridge: {:?}",
+                        payment.transaction,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+        ReportTransactionReceipts {
+            payments_with_receipts,
+            mined_nonce_count_opt: receipts.mined_nonce_count_opt,
+        }
+    }
+
     fn scan_for_delinquencies(&mut self) {
         debug!(self.logger, "Scanning for delinquencies");
 
         let now = SystemTime::now();
         self.receivable_dao
-            .new_delinquencies(now, &PAYMENT_CURVES)
+            .new_delinquencies(now, &self.config.payment_curves)
             .into_iter()
             .for_each(|account| {
                 self.banned_dao.ban(&account.wallet);
@@ -332,7 +486,7 @@ impl Accountant {
             });
 
         self.receivable_dao
-            .paid_delinquencies(&PAYMENT_CURVES)
+            .paid_delinquencies(&self.config.payment_curves)
             .into_iter()
             .for_each(|account| {
                 self.banned_dao.unban(&account.wallet);
@@ -380,19 +534,28 @@ impl Accountant {
             .send(RetrieveTransactions {
                 start_block,
                 recipient: self.earning_wallet.clone(),
+                credited_blocks: self.receivable_dao.recent_credited_blocks(),
             })
             .then(move |transactions_possibly| match transactions_possibly {
-                Ok(Ok(ref vec)) if vec.is_empty() => {
-                    debug!(future_logger, "No payments detected");
-                    Ok(())
-                }
-                Ok(Ok(transactions)) => {
-                    future_report_new_payments_sub
-                        .expect("Accountant is unbound")
-                        .try_send(ReceivedPayments {
-                            payments: transactions,
-                        })
-                        .expect("Accountant is dead.");
+                Ok(Ok(retrieved)) => {
+                    if retrieved.transactions.is_empty() {
+                        debug!(future_logger, "No payments detected");
+                    }
+                    if !retrieved.transactions.is_empty()
+                        || !retrieved.orphaned_blocks.is_empty()
+                        || retrieved.new_start_block != start_block
+                        || retrieved.more_to_scan
+                    {
+                        future_report_new_payments_sub
+                            .expect("Accountant is unbound")
+                            .try_send(ReceivedPayments {
+                                orphaned_blocks: retrieved.orphaned_blocks,
+                                new_start_block: retrieved.new_start_block,
+                                payments: retrieved.transactions,
+                                more_to_scan: retrieved.more_to_scan,
+                            })
+                            .expect("Accountant is dead.");
+                    }
                     Ok(())
                 }
                 Ok(Err(e)) => {
@@ -424,32 +587,63 @@ impl Accountant {
         (balance, age)
     }
 
-    fn should_pay(payable: &PayableAccount) -> bool {
+    fn pending_payment_status(
+        payment: &Payment,
+        receipt_opt: Option<&TransactionReceipt>,
+        mined_nonce_count_opt: Option<U256>,
+        now: SystemTime,
+    ) -> PendingPaymentStatus {
+        match receipt_opt {
+            Some(receipt) => match receipt.status {
+                Some(status) if status.is_zero() => PendingPaymentStatus::Failed,
+                // Receipts from before the Byzantium fork carry no status; being mined is enough
+                _ => PendingPaymentStatus::Confirmed,
+            },
+            None => match now.duration_since(payment.timestamp) {
+                Ok(age) if age.as_secs() >= PENDING_TRANSACTION_TIMEOUT => {
+                    match (payment.nonce_opt, mined_nonce_count_opt) {
+                        // Another transaction has used the nonce, so this one can never be mined
+                        (Some(nonce), Some(mined_nonce_count)) if mined_nonce_count > nonce => {
+                            PendingPaymentStatus::Dropped
+                        }
+                        // Sent before nonces were recorded; the timeout is all there is to go on
+                        (None, _) => PendingPaymentStatus::Dropped,
+                        // Paying again now could pay twice if the transaction is mined later
+                        _ => PendingPaymentStatus::StillPending,
+                    }
+                }
+                _ => PendingPaymentStatus::StillPending,
+            },
+        }
+    }
+
+    fn should_pay(payable: &PayableAccount, payment_curves: &PaymentCurves) -> bool {
         // TODO: This calculation should be done in the database, if possible
         let time_since_last_paid = SystemTime::now()
             .duration_since(payable.last_paid_timestamp)
             .expect("Internal error")
             .as_secs();
 
-        if time_since_last_paid <= PAYMENT_CURVES.payment_suggested_after_sec as u64 {
+        if time_since_last_paid <= payment_curves.payment_suggested_after_sec as u64 {
             return false;
         }
 
-        if payable.balance <= PAYMENT_CURVES.permanent_debt_allowed_gwub {
+        if payable.balance <= payment_curves.permanent_debt_allowed_gwub {
             return false;
         }
 
-        let threshold = Accountant::calculate_payout_threshold(time_since_last_paid);
+        let threshold =
+            Accountant::calculate_payout_threshold(time_since_last_paid, payment_curves);
         payable.balance as f64 > threshold
     }
 
-    fn calculate_payout_threshold(x: u64) -> f64 {
-        let m = -((PAYMENT_CURVES.balance_to_decrease_from_gwub as f64
-            - PAYMENT_CURVES.permanent_debt_allowed_gwub as f64)
-            / (PAYMENT_CURVES.balance_decreases_for_sec as f64
-                - PAYMENT_CURVES.payment_suggested_after_sec as f64));
-        let b = PAYMENT_CURVES.balance_to_decrease_from_gwub as f64
-            - m * PAYMENT_CURVES.payment_suggested_after_sec as f64;
+    fn calculate_payout_threshold(x: u64, payment_curves: &PaymentCurves) -> f64 {
+        let m = -((payment_curves.balance_to_decrease_from_gwub as f64
+            - payment_curves.permanent_debt_allowed_gwub as f64)
+            / (payment_curves.balance_decreases_for_sec as f64
+                - payment_curves.payment_suggested_after_sec as f64));
+        let b = payment_curves.balance_to_decrease_from_gwub as f64
+            - m * payment_curves.payment_suggested_after_sec as f64;
         m * x as f64 + b
     }
 
@@ -529,21 +723,67 @@ impl Accountant {
             Some(msg.peer_actors.blockchain_bridge.retrieve_transactions);
         self.report_new_payments_sub = Some(msg.peer_actors.accountant.report_new_payments);
         self.report_sent_payments_sub = Some(msg.peer_actors.accountant.report_sent_payments);
+        self.request_transaction_receipts_sub = Some(
+            msg.peer_actors
+                .blockchain_bridge
+                .request_transaction_receipts,
+        );
+        self.report_transaction_receipts_sub =
+            Some(msg.peer_actors.accountant.report_transaction_receipts);
         self.ui_message_sub = Some(msg.peer_actors.ui_gateway.node_to_ui_message_sub);
 
         info!(self.logger, "Accountant bound");
     }
 
     fn handle_start_message(&mut self) {
+        self.scan_for_pending_payments();
         self.scan_for_payables();
         self.scan_for_received_payments();
         self.scan_for_delinquencies();
     }
 
     fn handle_received_payments(&mut self, received_payments: ReceivedPayments) {
-        self.receivable_dao
-            .as_mut()
-            .more_money_received(received_payments.payments);
+        if !received_payments.orphaned_blocks.is_empty() {
+            warning!(
+                self.logger,
+                "Reversing payments credited from orphaned blocks {:?}",
+                received_payments.orphaned_blocks
+            );
+            if let Err(PaymentError::SignConversion(_)) = self
+                .payment_history_dao
+                .received_payments_reversed(&received_payments.orphaned_blocks)
+            {
+                error!(
+                    self.logger,
+                    "Overflow error trying to record reversal of payments from blocks {:?}",
+                    received_payments.orphaned_blocks
+                )
+            }
+            self.receivable_dao
+                .as_mut()
+                .reverse_orphaned_payments(&received_payments.orphaned_blocks);
+        }
+        received_payments.payments.iter().for_each(|payment| {
+            if let Err(PaymentError::SignConversion(_)) =
+                self.payment_history_dao.payment_received(payment)
+            {
+                error!(
+                    self.logger,
+                    "Overflow error trying to record payment of {} received from {} (transaction {:#x}). Skipping",
+                    payment.gwei_amount,
+                    payment.from,
+                    payment.transaction_hash
+                )
+            }
+        });
+        self.receivable_dao.as_mut().more_money_received(
+            received_payments.payments,
+            received_payments.new_start_block,
+        );
+        // The start block is checkpointed; go on to the next window without waiting for the timer
+        if received_payments.more_to_scan {
+            self.scan_for_received_payments();
+        }
     }
 
     fn handle_sent_payments(&mut self, sent_payments: SentPayments) {
@@ -551,7 +791,12 @@ impl Accountant {
             .payments
             .iter()
             .for_each(|payment| match payment {
-                Ok(payment) => match self.payable_dao.as_mut().payment_sent(payment) {
+                Ok(payment) => match self
+                    .payable_dao
+                    .as_mut()
+                    .payment_sent(payment)
+                    .and_then(|_| self.payment_history_dao.payment_sent(payment))
+                {
                     Ok(()) => (),
                     Err(PaymentError::SignConversion(_)) => error! (
                         self.logger,
@@ -569,6 +814,103 @@ impl Accountant {
             })
     }
 
+    fn handle_transaction_receipts(&mut self, msg: ReportTransactionReceipts) {
+        let now = SystemTime::now();
+        let mined_nonce_count_opt = msg.mined_nonce_count_opt;
+        msg.payments_with_receipts
+            .into_iter()
+            .for_each(|(payment, receipt_opt)| {
+                let status = Self::pending_payment_status(
+                    &payment,
+                    receipt_opt.as_ref(),
+                    mined_nonce_count_opt,
+                    now,
+                );
+                let block_number_opt = receipt_opt
+                    .as_ref()
+                    .and_then(|receipt| receipt.block_number)
+                    .map(|block_number| block_number.low_u64());
+                let result = match status {
+                    PendingPaymentStatus::Confirmed => {
+                        info!(
+                            self.logger,
+                            "Payment of {} Gwei to {} confirmed (transaction {:#x})",
+                            payment.amount,
+                            payment.to,
+                            payment.transaction
+                        );
+                        self.payable_dao
+                            .payment_confirmed(
+                                &payment.to,
+                                payment.amount,
+                                now,
+                                payment.transaction,
+                            )
+                            .and_then(|_| {
+                                self.payment_history_dao.sent_payment_status_changed(
+                                    &payment,
+                                    PaymentStatus::Confirmed,
+                                    block_number_opt,
+                                )
+                            })
+                    }
+                    PendingPaymentStatus::Failed => {
+                        warning!(
+                            self.logger,
+                            "Payment of {} Gwei to {} failed on the blockchain (transaction {:#x}); debt will be paid again",
+                            payment.amount,
+                            payment.to,
+                            payment.transaction
+                        );
+                        self.payable_dao.payment_failed(&payment).and_then(|_| {
+                            self.payment_history_dao.sent_payment_status_changed(
+                                &payment,
+                                PaymentStatus::Failed,
+                                block_number_opt,
+                            )
+                        })
+                    }
+                    PendingPaymentStatus::Dropped => {
+                        warning!(
+                            self.logger,
+                            "Payment of {} Gwei to {} was not mined within {} sec (transaction {:#x}); debt will be paid again",
+                            payment.amount,
+                            payment.to,
+                            PENDING_TRANSACTION_TIMEOUT,
+                            payment.transaction
+                        );
+                        self.payable_dao.payment_failed(&payment).and_then(|_| {
+                            self.payment_history_dao.sent_payment_status_changed(
+                                &payment,
+                                PaymentStatus::Dropped,
+                                None,
+                            )
+                        })
+                    }
+                    PendingPaymentStatus::StillPending => {
+                        debug!(
+                            self.logger,
+                            "Payment of {} Gwei to {} is still pending (transaction {:#x})",
+                            payment.amount,
+                            payment.to,
+                            payment.transaction
+                        );
+                        Ok(())
+                    }
+                };
+                if let Err(PaymentError::SignConversion(_)) = result {
+                    error!(
+                        self.logger,
+                        "Overflow error trying to record {:?} payment of {} to earning wallet {} (transaction {:#x}). Skipping",
+                        status,
+                        payment.amount,
+                        payment.to,
+                        payment.transaction
+                    )
+                }
+            })
+    }
+
     fn handle_report_routing_service_provided_message(
         &mut self,
         msg: ReportRoutingServiceProvidedMessage,
@@ -722,10 +1064,12 @@ pub fn jackass_unsigned_to_signed(unsigned: u64) -> Result<i64, PaymentError> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::accountant::payment_history_dao::PaymentRecord;
     use crate::accountant::receivable_dao::{ReceivableAccount, ReceivableDaoFactory};
     use crate::accountant::test_utils::make_receivable_account;
     use crate::blockchain::blockchain_interface::BlockchainError;
     use crate::blockchain::blockchain_interface::Transaction;
+    use crate::blockchain::test_utils::make_transaction_receipt;
     use crate::database::dao_utils::from_time_t;
     use crate::database::dao_utils::to_time_t;
     use crate::db_config::config_dao::ConfigDao;
@@ -767,6 +1111,11 @@ pub mod tests {
         non_pending_payables_results: RefCell<Vec<Vec<PayableAccount>>>,
         payment_sent_parameters: Arc<Mutex<Vec<Payment>>>,
         payment_sent_results: RefCell<Vec<Result<(), PaymentError>>>,
+        payment_confirmed_parameters: Arc<Mutex<Vec<(Wallet, u64, SystemTime, H256)>>>,
+        payment_confirmed_results: RefCell<Vec<Result<(), PaymentError>>>,
+        payment_failed_parameters: Arc<Mutex<Vec<Payment>>>,
+        payment_failed_results: RefCell<Vec<Result<(), PaymentError>>>,
+        pending_payments_results: RefCell<Vec<Vec<Payment>>>,
         top_records_parameters: Arc<Mutex<Vec<(u64, u64)>>>,
         top_records_results: RefCell<Vec<Vec<PayableAccount>>>,
         total_results: RefCell<Vec<u64>>,
@@ -791,12 +1140,26 @@ pub mod tests {
 
         fn payment_confirmed(
             &self,
-            _wallet: &Wallet,
-            _amount: u64,
-            _confirmation_noticed_timestamp: SystemTime,
-            _transaction_hash: H256,
+            wallet: &Wallet,
+            amount: u64,
+            confirmation_noticed_timestamp: SystemTime,
+            transaction_hash: H256,
         ) -> Result<(), PaymentError> {
-            unimplemented!("SC-925: TODO")
+            self.payment_confirmed_parameters.lock().unwrap().push((
+                wallet.clone(),
+                amount,
+                confirmation_noticed_timestamp,
+                transaction_hash,
+            ));
+            self.payment_confirmed_results.borrow_mut().remove(0)
+        }
+
+        fn payment_failed(&self, payment: &Payment) -> Result<(), PaymentError> {
+            self.payment_failed_parameters
+                .lock()
+                .unwrap()
+                .push(payment.clone());
+            self.payment_failed_results.borrow_mut().remove(0)
         }
 
         fn account_status(&self, wallet: &Wallet) -> Option<PayableAccount> {
@@ -815,6 +1178,14 @@ pub mod tests {
             }
         }
 
+        fn pending_payments(&self) -> Vec<Payment> {
+            if self.pending_payments_results.borrow().is_empty() {
+                vec![]
+            } else {
+                self.pending_payments_results.borrow_mut().remove(0)
+            }
+        }
+
         fn top_records(&self, minimum_amount: u64, maximum_age: u64) -> Vec<PayableAccount> {
             self.top_records_parameters
                 .lock()
@@ -861,6 +1232,34 @@ pub mod tests {
             self
         }
 
+        fn payment_confirmed_parameters(
+            mut self,
+            parameters: &Arc<Mutex<Vec<(Wallet, u64, SystemTime, H256)>>>,
+        ) -> Self {
+            self.payment_confirmed_parameters = parameters.clone();
+            self
+        }
+
+        fn payment_confirmed_result(self, result: Result<(), PaymentError>) -> Self {
+            self.payment_confirmed_results.borrow_mut().push(result);
+            self
+        }
+
+        fn payment_failed_parameters(mut self, parameters: &Arc<Mutex<Vec<Payment>>>) -> Self {
+            self.payment_failed_parameters = parameters.clone();
+            self
+        }
+
+        fn payment_failed_result(self, result: Result<(), PaymentError>) -> Self {
+            self.payment_failed_results.borrow_mut().push(result);
+            self
+        }
+
+        fn pending_payments_result(self, result: Vec<Payment>) -> Self {
+            self.pending_payments_results.borrow_mut().push(result);
+            self
+        }
+
         fn top_records_parameters(mut self, parameters: &Arc<Mutex<Vec<(u64, u64)>>>) -> Self {
             self.top_records_parameters = parameters.clone();
             self
@@ -909,8 +1308,10 @@ pub mod tests {
         account_status_results: RefCell<Vec<Option<ReceivableAccount>>>,
         more_money_receivable_parameters: Arc<Mutex<Vec<(Wallet, u64)>>>,
         more_money_receivable_results: RefCell<Vec<Result<(), PaymentError>>>,
-        more_money_received_parameters: Arc<Mutex<Vec<Vec<Transaction>>>>,
+        more_money_received_parameters: Arc<Mutex<Vec<(Vec<Transaction>, u64)>>>,
         more_money_received_results: RefCell<Vec<Result<(), PaymentError>>>,
+        recent_credited_blocks_results: RefCell<Vec<Vec<(u64, H256)>>>,
+        reverse_orphaned_payments_parameters: Arc<Mutex<Vec<Vec<u64>>>>,
         receivables_results: RefCell<Vec<Vec<ReceivableAccount>>>,
         new_delinquencies_parameters: Arc<Mutex<Vec<(SystemTime, PaymentCurves)>>>,
         new_delinquencies_results: RefCell<Vec<Vec<ReceivableAccount>>>,
@@ -930,11 +1331,26 @@ pub mod tests {
             self.more_money_receivable_results.borrow_mut().remove(0)
         }
 
-        fn more_money_received(&mut self, transactions: Vec<Transaction>) {
+        fn more_money_received(&mut self, transactions: Vec<Transaction>, new_start_block: u64) {
             self.more_money_received_parameters
                 .lock()
                 .unwrap()
-                .push(transactions);
+                .push((transactions, new_start_block));
+        }
+
+        fn recent_credited_blocks(&self) -> Vec<(u64, H256)> {
+            if self.recent_credited_blocks_results.borrow().is_empty() {
+                vec![]
+            } else {
+                self.recent_credited_blocks_results.borrow_mut().remove(0)
+            }
+        }
+
+        fn reverse_orphaned_payments(&mut self, block_numbers: &[u64]) {
+            self.reverse_orphaned_payments_parameters
+                .lock()
+                .unwrap()
+                .push(block_numbers.to_vec());
         }
 
         fn account_status(&self, wallet: &Wallet) -> Option<ReceivableAccount> {
@@ -1011,7 +1427,7 @@ pub mod tests {
 
         fn more_money_received_parameters(
             mut self,
-            parameters: &Arc<Mutex<Vec<Vec<Transaction>>>>,
+            parameters: &Arc<Mutex<Vec<(Vec<Transaction>, u64)>>>,
         ) -> Self {
             self.more_money_received_parameters = parameters.clone();
             self
@@ -1022,6 +1438,21 @@ pub mod tests {
             self
         }
 
+        fn recent_credited_blocks_result(self, result: Vec<(u64, H256)>) -> Self {
+            self.recent_credited_blocks_results
+                .borrow_mut()
+                .push(result);
+            self
+        }
+
+        fn reverse_orphaned_payments_parameters(
+            mut self,
+            parameters: &Arc<Mutex<Vec<Vec<u64>>>>,
+        ) -> Self {
+            self.reverse_orphaned_payments_parameters = parameters.clone();
+            self
+        }
+
         fn new_delinquencies_parameters(
             mut self,
             parameters: &Arc<Mutex<Vec<(SystemTime, PaymentCurves)>>>,
@@ -1090,6 +1521,123 @@ pub mod tests {
         }
     }
 
+    #[derive(Debug, Default)]
+    pub struct PaymentHistoryDaoMock {
+        payment_sent_parameters: Arc<Mutex<Vec<Payment>>>,
+        payment_received_parameters: Arc<Mutex<Vec<Transaction>>>,
+        sent_payment_status_changed_parameters:
+            Arc<Mutex<Vec<(Payment, PaymentStatus, Option<u64>)>>>,
+        received_payments_reversed_parameters: Arc<Mutex<Vec<Vec<u64>>>>,
+    }
+
+    impl PaymentHistoryDao for PaymentHistoryDaoMock {
+        fn payment_sent(&self, payment: &Payment) -> Result<(), PaymentError> {
+            self.payment_sent_parameters
+                .lock()
+                .unwrap()
+                .push(payment.clone());
+            Ok(())
+        }
+
+        fn payment_received(&self, transaction: &Transaction) -> Result<(), PaymentError> {
+            self.payment_received_parameters
+                .lock()
+                .unwrap()
+                .push(transaction.clone());
+            Ok(())
+        }
+
+        fn sent_payment_status_changed(
+            &self,
+            payment: &Payment,
+            status: PaymentStatus,
+            block_number_opt: Option<u64>,
+        ) -> Result<(), PaymentError> {
+            self.sent_payment_status_changed_parameters
+                .lock()
+                .unwrap()
+                .push((payment.clone(), status, block_number_opt));
+            Ok(())
+        }
+
+        fn received_payments_reversed(&self, block_numbers: &[u64]) -> Result<(), PaymentError> {
+            self.received_payments_reversed_parameters
+                .lock()
+                .unwrap()
+                .push(block_numbers.to_vec());
+            Ok(())
+        }
+
+        fn records_for(&self, _counterparty: &Wallet) -> Vec<PaymentRecord> {
+            unimplemented!()
+        }
+
+        fn records_between(&self, _from: SystemTime, _to: SystemTime) -> Vec<PaymentRecord> {
+            unimplemented!()
+        }
+    }
+
+    impl PaymentHistoryDaoMock {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn payment_sent_parameters(mut self, parameters: &Arc<Mutex<Vec<Payment>>>) -> Self {
+            self.payment_sent_parameters = parameters.clone();
+            self
+        }
+
+        pub fn payment_received_parameters(
+            mut self,
+            parameters: &Arc<Mutex<Vec<Transaction>>>,
+        ) -> Self {
+            self.payment_received_parameters = parameters.clone();
+            self
+        }
+
+        pub fn sent_payment_status_changed_parameters(
+            mut self,
+            parameters: &Arc<Mutex<Vec<(Payment, PaymentStatus, Option<u64>)>>>,
+        ) -> Self {
+            self.sent_payment_status_changed_parameters = parameters.clone();
+            self
+        }
+
+        pub fn received_payments_reversed_parameters(
+            mut self,
+            parameters: &Arc<Mutex<Vec<Vec<u64>>>>,
+        ) -> Self {
+            self.received_payments_reversed_parameters = parameters.clone();
+            self
+        }
+    }
+
+    pub struct PaymentHistoryDaoFactoryMock {
+        called: Rc<RefCell<bool>>,
+        mock: RefCell<Option<PaymentHistoryDaoMock>>,
+    }
+
+    impl PaymentHistoryDaoFactory for PaymentHistoryDaoFactoryMock {
+        fn make(&self) -> Box<dyn PaymentHistoryDao> {
+            *self.called.borrow_mut() = true;
+            Box::new(self.mock.borrow_mut().take().unwrap())
+        }
+    }
+
+    impl PaymentHistoryDaoFactoryMock {
+        fn new(mock: PaymentHistoryDaoMock) -> Self {
+            Self {
+                called: Rc::new(RefCell::new(false)),
+                mock: RefCell::new(Some(mock)),
+            }
+        }
+
+        fn called(mut self, called: &Rc<RefCell<bool>>) -> Self {
+            self.called = called.clone();
+            self
+        }
+    }
+
     #[derive(Debug, Default)]
     struct BannedDaoMock {
         ban_list_parameters: Arc<Mutex<Vec<()>>>,
@@ -1202,6 +1750,10 @@ pub mod tests {
         let receivable_dao = ReceivableDaoMock::new();
         let receivable_dao_factory =
             ReceivableDaoFactoryMock::new(receivable_dao).called(&receivable_dao_factory_called);
+        let payment_history_dao_factory_called = Rc::new(RefCell::new(false));
+        let payment_history_dao = PaymentHistoryDaoMock::new();
+        let payment_history_dao_factory = PaymentHistoryDaoFactoryMock::new(payment_history_dao)
+            .called(&payment_history_dao_factory_called);
         let banned_dao_factory_called = Rc::new(RefCell::new(false));
         let banned_dao = BannedDaoMock::new();
         let banned_dao_factory =
@@ -1215,12 +1767,17 @@ pub mod tests {
             &config,
             Box::new(payable_dao_factory),
             Box::new(receivable_dao_factory),
+            Box::new(payment_history_dao_factory),
             Box::new(banned_dao_factory),
             Box::new(config_dao_factory),
         );
 
         assert_eq!(payable_dao_factory_called.as_ref(), &RefCell::new(true));
         assert_eq!(receivable_dao_factory_called.as_ref(), &RefCell::new(true));
+        assert_eq!(
+            payment_history_dao_factory_called.as_ref(),
+            &RefCell::new(true)
+        );
         assert_eq!(banned_dao_factory_called.as_ref(), &RefCell::new(true));
         assert_eq!(config_dao_factory_called.as_ref(), &RefCell::new(true));
     }
@@ -1267,6 +1824,7 @@ pub mod tests {
                 AccountantConfig {
                     payable_scan_interval: Duration::from_millis(10_000),
                     payment_received_scan_interval: Duration::from_millis(10_000),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("some_wallet_address"),
             )),
@@ -1352,6 +1910,7 @@ pub mod tests {
                 AccountantConfig {
                     payable_scan_interval: Duration::from_millis(10_000),
                     payment_received_scan_interval: Duration::from_millis(10_000),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("some_wallet_address"),
             )),
@@ -1397,11 +1956,12 @@ pub mod tests {
 
         let system = System::new("accountant_calls_payable_dao_payment_sent_when_sent_payments");
 
-        let accountant = make_subject(
+        let mut accountant = make_subject(
             Some(bc_from_ac_plus_earning_wallet(
                 AccountantConfig {
                     payable_scan_interval: Duration::from_millis(100),
                     payment_received_scan_interval: Duration::from_secs(10_000),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("some_wallet_address"),
             )),
@@ -1422,6 +1982,10 @@ pub mod tests {
         let send_payments = SentPayments {
             payments: vec![Ok(expected_payment.clone())],
         };
+        let history_payment_sent_parameters = Arc::new(Mutex::new(vec![]));
+        accountant.payment_history_dao = Box::new(
+            PaymentHistoryDaoMock::new().payment_sent_parameters(&history_payment_sent_parameters),
+        );
 
         let subject = accountant.start();
 
@@ -1436,6 +2000,8 @@ pub mod tests {
 
         expected_payment.timestamp = actual.timestamp;
         assert_eq!(actual, &expected_payment);
+        let history_payment_sent_parameters = history_payment_sent_parameters.lock().unwrap();
+        assert_eq!(*history_payment_sent_parameters, vec![expected_payment]);
     }
 
     #[test]
@@ -1450,6 +2016,7 @@ pub mod tests {
                 AccountantConfig {
                     payable_scan_interval: Duration::from_millis(100),
                     payment_received_scan_interval: Duration::from_secs(10_000),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("some_wallet_address"),
             )),
@@ -1486,7 +2053,7 @@ pub mod tests {
         let expected_wallet = make_wallet("blah");
         let expected_wallet_inner = expected_wallet.clone();
         let expected_amount =
-            u64::try_from(PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000).unwrap();
+            u64::try_from(DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000).unwrap();
 
         let expected_pending_payment_transaction = H256::from("transaction_hash".keccak256());
         let expected_pending_payment_transaction_inner =
@@ -1495,9 +2062,9 @@ pub mod tests {
         let payable_dao = PayableDaoMock::new()
             .non_pending_payables_result(vec![PayableAccount {
                 wallet: expected_wallet.clone(),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
                 ),
                 pending_payment_transaction: None,
             }])
@@ -1509,7 +2076,7 @@ pub mod tests {
                 expected_amount,
                 expected_pending_payment_transaction_inner,
             ))]))
-            .retrieve_transactions_response(Ok(vec![]));
+            .retrieve_transactions_response(Ok(RetrievedTransactions::default()));
 
         let (accountant_mock, accountant_mock_awaiter, accountant_recording_arc) = make_recorder();
 
@@ -1527,6 +2094,7 @@ pub mod tests {
                     AccountantConfig {
                         payable_scan_interval: Duration::from_millis(100),
                         payment_received_scan_interval: Duration::from_secs(10_000),
+                        payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                     },
                     earning_wallet.clone(),
                 )),
@@ -1575,16 +2143,16 @@ pub mod tests {
         let payable_dao = PayableDaoMock::new()
             .non_pending_payables_result(vec![PayableAccount {
                 wallet: expected_wallet.clone(),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
                 ),
                 pending_payment_transaction: None,
             }])
             .non_pending_payables_result(vec![]);
 
         let blockchain_bridge = Recorder::new()
-            .retrieve_transactions_response(Ok(vec![]))
+            .retrieve_transactions_response(Ok(RetrievedTransactions::default()))
             .report_accounts_payable_response(Err("Failed to send transaction".to_string()));
 
         let (accountant_mock, _, accountant_recording_arc) = make_recorder();
@@ -1603,6 +2171,7 @@ pub mod tests {
                     AccountantConfig {
                         payable_scan_interval: Duration::from_millis(100),
                         payment_received_scan_interval: Duration::from_secs(10_000),
+                        payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                     },
                     earning_wallet.clone(),
                 )),
@@ -1627,18 +2196,285 @@ pub mod tests {
         assert_eq!(0, accountant_recording.len());
     }
 
+    #[test]
+    fn accountant_requests_receipts_for_pending_payments_and_reports_them() {
+        init_test_logging();
+        let earning_wallet = make_wallet("earner3000");
+        let unreachable_payment = Payment::new(
+            make_wallet("unreachable"),
+            4321,
+            H256::from("unreachable_hash".keccak256()),
+        );
+        let pending_payment = Payment::new(
+            make_wallet("creditor"),
+            1234,
+            H256::from("transaction_hash".keccak256()),
+        );
+        let pending_payments_inner = vec![unreachable_payment.clone(), pending_payment.clone()];
+        let receipt = make_transaction_receipt(pending_payment.transaction, Some(1));
+        let blockchain_bridge = Recorder::new()
+            .request_transaction_receipts_response(TransactionReceipts {
+                receipts: vec![Err(BlockchainError::QueryFailed), Ok(Some(receipt.clone()))],
+                mined_nonce_count_opt: Some(U256::from(3)),
+            })
+            .retrieve_transactions_response(Ok(RetrievedTransactions::default()));
+        let blockchain_bridge_awaiter = blockchain_bridge.get_awaiter();
+        let blockchain_bridge_recording_arc = blockchain_bridge.get_recording();
+        let (accountant_mock, accountant_awaiter, accountant_recording_arc) = make_recorder();
+
+        thread::spawn(move || {
+            let system =
+                System::new("accountant_requests_receipts_for_pending_payments_and_reports_them");
+            let payable_dao = PayableDaoMock::new()
+                .pending_payments_result(pending_payments_inner)
+                .non_pending_payables_result(vec![]);
+            let peer_actors = peer_actors_builder()
+                .blockchain_bridge(blockchain_bridge)
+                .accountant(accountant_mock)
+                .build();
+            let subject = make_subject(
+                Some(bc_from_ac_plus_earning_wallet(
+                    AccountantConfig {
+                        payable_scan_interval: Duration::from_secs(10_000),
+                        payment_received_scan_interval: Duration::from_secs(10_000),
+                        payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
+                    },
+                    earning_wallet,
+                )),
+                Some(payable_dao),
+                None,
+                None,
+                None,
+            );
+            let subject_addr = subject.start();
+            let subject_subs = Accountant::make_subs_from(&subject_addr);
+
+            send_bind_message!(subject_subs, peer_actors);
+            send_start_message!(subject_subs);
+
+            system.run();
+        });
+
+        blockchain_bridge_awaiter.await_message_count(1);
+        let blockchain_bridge_recording = blockchain_bridge_recording_arc.lock().unwrap();
+        assert_eq!(
+            blockchain_bridge_recording.get_record::<RequestTransactionReceipts>(0),
+            &RequestTransactionReceipts {
+                pending_payments: vec![unreachable_payment.clone(), pending_payment.clone()]
+            }
+        );
+        accountant_awaiter.await_message_count(1);
+        let accountant_recording = accountant_recording_arc.lock().unwrap();
+        assert_eq!(
+            accountant_recording.get_record::<ReportTransactionReceipts>(0),
+            &ReportTransactionReceipts {
+                payments_with_receipts: vec![(pending_payment, Some(receipt))],
+                mined_nonce_count_opt: Some(U256::from(3)),
+            }
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: Accountant: Unable to retrieve receipt for transaction {:#x} from Blockchain Bridge: QueryFailed",
+            unreachable_payment.transaction
+        ));
+    }
+
+    #[test]
+    fn accountant_does_not_request_receipts_when_nothing_is_pending() {
+        let blockchain_bridge =
+            Recorder::new().retrieve_transactions_response(Ok(RetrievedTransactions::default()));
+        let blockchain_bridge_awaiter = blockchain_bridge.get_awaiter();
+        let blockchain_bridge_recording_arc = blockchain_bridge.get_recording();
+
+        thread::spawn(move || {
+            let system =
+                System::new("accountant_does_not_request_receipts_when_nothing_is_pending");
+            let payable_dao = PayableDaoMock::new()
+                .pending_payments_result(vec![])
+                .non_pending_payables_result(vec![]);
+            let peer_actors = peer_actors_builder()
+                .blockchain_bridge(blockchain_bridge)
+                .build();
+            let subject = make_subject(None, Some(payable_dao), None, None, None);
+            let subject_addr = subject.start();
+            let subject_subs = Accountant::make_subs_from(&subject_addr);
+
+            send_bind_message!(subject_subs, peer_actors);
+            send_start_message!(subject_subs);
+
+            system.run();
+        });
+
+        blockchain_bridge_awaiter.await_message_count(1);
+        let blockchain_bridge_recording = blockchain_bridge_recording_arc.lock().unwrap();
+        assert_eq!(blockchain_bridge_recording.len(), 1);
+        blockchain_bridge_recording.get_record::<RetrieveTransactions>(0);
+    }
+
+    #[test]
+    fn accountant_settles_pending_payments_according_to_their_receipts() {
+        init_test_logging();
+        let payment_confirmed_parameters_arc = Arc::new(Mutex::new(vec![]));
+        let payment_failed_parameters_arc = Arc::new(Mutex::new(vec![]));
+        let payable_dao = PayableDaoMock::new()
+            .payment_confirmed_parameters(&payment_confirmed_parameters_arc)
+            .payment_confirmed_result(Ok(()))
+            .payment_failed_parameters(&payment_failed_parameters_arc)
+            .payment_failed_result(Ok(()))
+            .payment_failed_result(Ok(()));
+        let sent_payment_status_changed_parameters_arc = Arc::new(Mutex::new(vec![]));
+        let payment_history_dao = PaymentHistoryDaoMock::new()
+            .sent_payment_status_changed_parameters(&sent_payment_status_changed_parameters_arc);
+        let system = System::new("accountant_settles_pending_payments_according_to_their_receipts");
+        let mut subject = make_subject(None, Some(payable_dao), None, None, None);
+        subject.payment_history_dao = Box::new(payment_history_dao);
+        let subject_addr = subject.start();
+        let confirmed = Payment::new(
+            make_wallet("confirmed"),
+            100,
+            H256::from_uint(&U256::from(1)),
+        );
+        let failed = Payment::new(make_wallet("failed"), 200, H256::from_uint(&U256::from(2)));
+        let mut dropped =
+            Payment::new(make_wallet("dropped"), 300, H256::from_uint(&U256::from(3)))
+                .with_nonce(U256::from(6));
+        dropped.timestamp =
+            SystemTime::now().sub(Duration::from_secs(PENDING_TRANSACTION_TIMEOUT + 1));
+        let pending = Payment::new(make_wallet("pending"), 400, H256::from_uint(&U256::from(4)));
+        let mut overdue =
+            Payment::new(make_wallet("overdue"), 500, H256::from_uint(&U256::from(5)))
+                .with_nonce(U256::from(7));
+        overdue.timestamp =
+            SystemTime::now().sub(Duration::from_secs(PENDING_TRANSACTION_TIMEOUT + 1));
+        let mut confirmed_receipt = make_transaction_receipt(confirmed.transaction, Some(1));
+        confirmed_receipt.block_number = Some(42.into());
+        let mut failed_receipt = make_transaction_receipt(failed.transaction, Some(0));
+        failed_receipt.block_number = Some(43.into());
+
+        subject_addr
+            .try_send(ReportTransactionReceipts {
+                payments_with_receipts: vec![
+                    (confirmed.clone(), Some(confirmed_receipt)),
+                    (failed.clone(), Some(failed_receipt)),
+                    (dropped.clone(), None),
+                    (pending.clone(), None),
+                    (overdue.clone(), None),
+                ],
+                mined_nonce_count_opt: Some(U256::from(7)),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let payment_confirmed_parameters = payment_confirmed_parameters_arc.lock().unwrap();
+        assert_eq!(payment_confirmed_parameters.len(), 1);
+        let (wallet, amount, _, transaction) = &payment_confirmed_parameters[0];
+        assert_eq!(wallet, &confirmed.to);
+        assert_eq!(*amount, 100);
+        assert_eq!(transaction, &confirmed.transaction);
+        let payment_failed_parameters = payment_failed_parameters_arc.lock().unwrap();
+        assert_eq!(
+            *payment_failed_parameters,
+            vec![failed.clone(), dropped.clone()]
+        );
+        let sent_payment_status_changed_parameters =
+            sent_payment_status_changed_parameters_arc.lock().unwrap();
+        assert_eq!(
+            *sent_payment_status_changed_parameters,
+            vec![
+                (confirmed.clone(), PaymentStatus::Confirmed, Some(42)),
+                (failed, PaymentStatus::Failed, Some(43)),
+                (dropped, PaymentStatus::Dropped, None),
+            ]
+        );
+        let tlh = TestLogHandler::new();
+        tlh.exists_log_containing(&format!(
+            "INFO: Accountant: Payment of 100 Gwei to {} confirmed (transaction {:#x})",
+            confirmed.to, confirmed.transaction
+        ));
+        tlh.exists_log_containing(&format!(
+            "WARN: Accountant: Payment of 200 Gwei to {} failed on the blockchain",
+            make_wallet("failed")
+        ));
+        tlh.exists_log_containing(&format!(
+            "WARN: Accountant: Payment of 300 Gwei to {} was not mined within {} sec",
+            make_wallet("dropped"),
+            PENDING_TRANSACTION_TIMEOUT
+        ));
+        tlh.exists_log_containing(&format!(
+            "DEBUG: Accountant: Payment of 400 Gwei to {} is still pending",
+            pending.to
+        ));
+        tlh.exists_log_containing(&format!(
+            "DEBUG: Accountant: Payment of 500 Gwei to {} is still pending",
+            overdue.to
+        ));
+    }
+
+    #[test]
+    fn pending_payment_status_treats_receipts_without_status_as_confirmed() {
+        let payment = Payment::new(make_wallet("booga"), 100, H256::from_uint(&U256::from(1)));
+        let receipt = make_transaction_receipt(payment.transaction, None);
+
+        let result =
+            Accountant::pending_payment_status(&payment, Some(&receipt), None, SystemTime::now());
+
+        assert_eq!(result, PendingPaymentStatus::Confirmed);
+    }
+
+    #[test]
+    fn pending_payment_status_drops_an_overdue_payment_only_when_its_nonce_is_used() {
+        let mut payment = Payment::new(make_wallet("booga"), 100, H256::from_uint(&U256::from(1)))
+            .with_nonce(U256::from(5));
+        let now = SystemTime::now();
+        payment.timestamp = now.sub(Duration::from_secs(PENDING_TRANSACTION_TIMEOUT + 1));
+        let status_with_count = |mined_nonce_count_opt: Option<u64>| {
+            Accountant::pending_payment_status(
+                &payment,
+                None,
+                mined_nonce_count_opt.map(U256::from),
+                now,
+            )
+        };
+
+        assert_eq!(status_with_count(Some(6)), PendingPaymentStatus::Dropped);
+        assert_eq!(
+            status_with_count(Some(5)),
+            PendingPaymentStatus::StillPending
+        );
+        assert_eq!(status_with_count(None), PendingPaymentStatus::StillPending);
+    }
+
+    #[test]
+    fn pending_payment_status_drops_an_overdue_payment_without_a_nonce() {
+        let mut payment = Payment::new(make_wallet("booga"), 100, H256::from_uint(&U256::from(1)));
+        let now = SystemTime::now();
+        payment.timestamp = now.sub(Duration::from_secs(PENDING_TRANSACTION_TIMEOUT + 1));
+
+        let result = Accountant::pending_payment_status(&payment, None, Some(U256::from(0)), now);
+
+        assert_eq!(result, PendingPaymentStatus::Dropped);
+    }
+
     #[test]
     fn accountant_payment_received_scan_timer_triggers_scanning_for_payments() {
         let paying_wallet = make_wallet("wallet0");
         let earning_wallet = make_wallet("earner3000");
         let amount = 42u64;
+        let block_hash = H256::from("block 7".keccak256());
         let expected_transactions = vec![Transaction {
             block_number: 7u64,
+            block_hash,
+            transaction_hash: H256::from("transaction 7".keccak256()),
             from: paying_wallet.clone(),
             gwei_amount: amount,
         }];
         let blockchain_bridge =
-            Recorder::new().retrieve_transactions_response(Ok(expected_transactions.clone()));
+            Recorder::new().retrieve_transactions_response(Ok(RetrievedTransactions {
+                orphaned_blocks: vec![4],
+                new_start_block: 8,
+                transactions: expected_transactions.clone(),
+                more_to_scan: false,
+            }));
         let blockchain_bridge_awaiter = blockchain_bridge.get_awaiter();
         let blockchain_bridge_recording = blockchain_bridge.get_recording();
         let (accountant_mock, accountant_awaiter, accountant_recording_arc) = make_recorder();
@@ -1646,6 +2482,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(10_000),
                 payment_received_scan_interval: Duration::from_millis(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -1656,6 +2493,7 @@ pub mod tests {
             );
             let payable_dao = PayableDaoMock::new().non_pending_payables_result(vec![]);
             let receivable_dao = ReceivableDaoMock::new()
+                .recent_credited_blocks_result(vec![(4, block_hash)])
                 .new_delinquencies_result(vec![])
                 .paid_delinquencies_result(vec![]);
             let config_mock = PersistentConfigurationMock::new().start_block_result(Ok(Some(5)));
@@ -1687,6 +2525,7 @@ pub mod tests {
             &RetrieveTransactions {
                 start_block: 5u64,
                 recipient: earning_wallet,
+                credited_blocks: vec![(4, block_hash)],
             },
             retrieve_transactions_message
         );
@@ -1697,7 +2536,10 @@ pub mod tests {
             received_payments_recording.get_record::<ReceivedPayments>(0);
         assert_eq!(
             &ReceivedPayments {
-                payments: expected_transactions
+                orphaned_blocks: vec![4],
+                new_start_block: 8,
+                payments: expected_transactions,
+                more_to_scan: false,
             },
             received_payments_message
         );
@@ -1707,7 +2549,13 @@ pub mod tests {
     fn accountant_logs_if_no_transactions_were_detected() {
         init_test_logging();
         let earning_wallet = make_wallet("earner3000");
-        let blockchain_bridge = Recorder::new().retrieve_transactions_response(Ok(vec![]));
+        let blockchain_bridge =
+            Recorder::new().retrieve_transactions_response(Ok(RetrievedTransactions {
+                orphaned_blocks: vec![],
+                new_start_block: 5,
+                transactions: vec![],
+                more_to_scan: false,
+            }));
         let blockchain_bridge_awaiter = blockchain_bridge.get_awaiter();
         let blockchain_bridge_recording = blockchain_bridge.get_recording();
         let (accountant_mock, _, accountant_recording_arc) = make_recorder();
@@ -1715,6 +2563,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(10_000),
                 payment_received_scan_interval: Duration::from_millis(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -1755,6 +2604,7 @@ pub mod tests {
             &RetrieveTransactions {
                 start_block: 5u64,
                 recipient: earning_wallet,
+                credited_blocks: vec![],
             },
             retrieve_transactions_message
         );
@@ -1776,6 +2626,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(10_000),
                 payment_received_scan_interval: Duration::from_millis(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -1825,19 +2676,29 @@ pub mod tests {
         let gwei_amount = 42u64;
         let expected_payment = Transaction {
             block_number: 7u64,
+            block_hash: H256::from("block 7".keccak256()),
+            transaction_hash: H256::from("transaction 7".keccak256()),
             from: wallet.clone(),
             gwei_amount,
         };
+        let reverse_orphaned_payments_params_arc = Arc::new(Mutex::new(vec![]));
         let more_money_received_params_arc = Arc::new(Mutex::new(vec![]));
         let receivable_dao = ReceivableDaoMock::new()
+            .reverse_orphaned_payments_parameters(&reverse_orphaned_payments_params_arc)
             .more_money_received_parameters(&more_money_received_params_arc)
             .more_money_received_result(Ok(()))
             .more_money_received_result(Ok(()));
-        let accountant = make_subject(
+        let payment_received_params_arc = Arc::new(Mutex::new(vec![]));
+        let received_payments_reversed_params_arc = Arc::new(Mutex::new(vec![]));
+        let payment_history_dao = PaymentHistoryDaoMock::new()
+            .payment_received_parameters(&payment_received_params_arc)
+            .received_payments_reversed_parameters(&received_payments_reversed_params_arc);
+        let mut accountant = make_subject(
             Some(bc_from_ac_plus_earning_wallet(
                 AccountantConfig {
                     payable_scan_interval: Duration::from_secs(10_000),
                     payment_received_scan_interval: Duration::from_secs(10_000),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 earning_wallet.clone(),
             )),
@@ -1847,20 +2708,37 @@ pub mod tests {
             None,
         );
 
+        accountant.payment_history_dao = Box::new(payment_history_dao);
+
         let system = System::new("accountant_receives_new_payments_to_the_receivables_dao");
         let subject = accountant.start();
 
         subject
             .try_send(ReceivedPayments {
+                orphaned_blocks: vec![3, 4],
+                new_start_block: 8,
                 payments: vec![expected_payment.clone(), expected_payment.clone()],
+                more_to_scan: false,
             })
             .expect("unexpected actix error");
         System::current().stop();
         system.run();
+        let reverse_orphaned_payments_params = reverse_orphaned_payments_params_arc.lock().unwrap();
+        assert_eq!(*reverse_orphaned_payments_params, vec![vec![3, 4]]);
+        let received_payments_reversed_params =
+            received_payments_reversed_params_arc.lock().unwrap();
+        assert_eq!(*received_payments_reversed_params, vec![vec![3, 4]]);
+        let payment_received_params = payment_received_params_arc.lock().unwrap();
+        assert_eq!(
+            *payment_received_params,
+            vec![expected_payment.clone(), expected_payment.clone()]
+        );
         let more_money_received_params = more_money_received_params_arc.lock().unwrap();
         assert_eq!(1, more_money_received_params.len());
 
-        let more_money_received_params = more_money_received_params.get(0).unwrap();
+        let (more_money_received_params, new_start_block) =
+            more_money_received_params.get(0).unwrap();
+        assert_eq!(*new_start_block, 8);
         assert_eq!(2, more_money_received_params.len());
 
         let first_payment = more_money_received_params.get(0).unwrap();
@@ -1871,12 +2749,82 @@ pub mod tests {
         assert_eq!(gwei_amount, second_payment.gwei_amount);
     }
 
+    #[test]
+    fn accountant_scans_the_next_window_right_away_when_more_blocks_remain() {
+        let earning_wallet = make_wallet("earner3000");
+        let blockchain_bridge =
+            Recorder::new().retrieve_transactions_response(Ok(RetrievedTransactions {
+                orphaned_blocks: vec![],
+                new_start_block: 10_008,
+                transactions: vec![],
+                more_to_scan: false,
+            }));
+        let blockchain_bridge_awaiter = blockchain_bridge.get_awaiter();
+        let blockchain_bridge_recording = blockchain_bridge.get_recording();
+        let more_money_received_params_arc = Arc::new(Mutex::new(vec![]));
+        let more_money_received_params_arc_inner = more_money_received_params_arc.clone();
+        let config = bc_from_ac_plus_earning_wallet(
+            AccountantConfig {
+                payable_scan_interval: Duration::from_secs(10_000),
+                payment_received_scan_interval: Duration::from_secs(10_000),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
+            },
+            earning_wallet.clone(),
+        );
+
+        thread::spawn(move || {
+            let system =
+                System::new("accountant_scans_the_next_window_right_away_when_more_blocks_remain");
+            let receivable_dao = ReceivableDaoMock::new()
+                .more_money_received_parameters(&more_money_received_params_arc_inner);
+            let config_mock = PersistentConfigurationMock::new().start_block_result(Ok(Some(8)));
+            let subject = make_subject(
+                Some(config),
+                Some(PayableDaoMock::new()),
+                Some(receivable_dao),
+                None,
+                Some(config_mock),
+            );
+            let peer_actors = peer_actors_builder()
+                .blockchain_bridge(blockchain_bridge)
+                .build();
+            let subject_addr: Addr<Accountant> = subject.start();
+            let subject_subs = Accountant::make_subs_from(&subject_addr);
+            send_bind_message!(subject_subs, peer_actors);
+
+            subject_subs
+                .report_new_payments
+                .try_send(ReceivedPayments {
+                    orphaned_blocks: vec![],
+                    new_start_block: 8,
+                    payments: vec![],
+                    more_to_scan: true,
+                })
+                .unwrap();
+
+            system.run();
+        });
+
+        blockchain_bridge_awaiter.await_message_count(1);
+        let more_money_received_params = more_money_received_params_arc.lock().unwrap();
+        assert_eq!(*more_money_received_params, vec![(vec![], 8)]);
+        let retrieve_transactions_recording = blockchain_bridge_recording.lock().unwrap();
+        assert_eq!(
+            retrieve_transactions_recording.get_record::<RetrieveTransactions>(0),
+            &RetrieveTransactions {
+                start_block: 8,
+                recipient: earning_wallet,
+                credited_blocks: vec![],
+            }
+        );
+    }
+
     #[test]
     fn accountant_payable_scan_timer_triggers_scanning_for_payables() {
         init_test_logging();
         let (blockchain_bridge, blockchain_bridge_awaiter, _) = make_recorder();
         let blockchain_bridge = blockchain_bridge
-            .retrieve_transactions_response(Ok(vec![]))
+            .retrieve_transactions_response(Ok(RetrievedTransactions::default()))
             .report_accounts_payable_response(Ok(vec![]));
 
         thread::spawn(move || {
@@ -1886,6 +2834,7 @@ pub mod tests {
                 AccountantConfig {
                     payable_scan_interval: Duration::from_millis(100),
                     payment_received_scan_interval: Duration::from_secs(100),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("hi"),
             );
@@ -1893,17 +2842,17 @@ pub mod tests {
             // slightly above minimum balance, to the right of the curve (time intersection)
             let account0 = PayableAccount {
                 wallet: make_wallet("wallet0"),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub + 1,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
                 ),
                 pending_payment_transaction: None,
             };
             let account1 = PayableAccount {
                 wallet: make_wallet("wallet1"),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub + 2,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 2,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 12,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 12,
                 ),
                 pending_payment_transaction: None,
             };
@@ -1937,6 +2886,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(1000),
                 payment_received_scan_interval: Duration::from_secs(1000),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("buy"),
             make_wallet("hi"),
@@ -1970,6 +2920,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(1000),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("mine"),
         );
@@ -1978,27 +2929,27 @@ pub mod tests {
             // below minimum balance, to the right of time intersection (inside buffer zone)
             PayableAccount {
                 wallet: make_wallet("wallet0"),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub - 1,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub - 1,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
                 ),
                 pending_payment_transaction: None,
             },
             // above balance intersection, to the left of minimum time (inside buffer zone)
             PayableAccount {
                 wallet: make_wallet("wallet1"),
-                balance: PAYMENT_CURVES.balance_to_decrease_from_gwub + 1,
+                balance: DEFAULT_PAYMENT_CURVES.balance_to_decrease_from_gwub + 1,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.payment_suggested_after_sec + 10,
+                    now - DEFAULT_PAYMENT_CURVES.payment_suggested_after_sec + 10,
                 ),
                 pending_payment_transaction: None,
             },
             // above minimum balance, to the right of minimum time (not in buffer zone, below the curve)
             PayableAccount {
                 wallet: make_wallet("wallet2"),
-                balance: PAYMENT_CURVES.balance_to_decrease_from_gwub - 1000,
+                balance: DEFAULT_PAYMENT_CURVES.balance_to_decrease_from_gwub - 1000,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.payment_suggested_after_sec - 1,
+                    now - DEFAULT_PAYMENT_CURVES.payment_suggested_after_sec - 1,
                 ),
                 pending_payment_transaction: None,
             },
@@ -2032,6 +2983,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_millis(100),
                 payment_received_scan_interval: Duration::from_millis(1_000),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("mine"),
         );
@@ -2040,18 +2992,18 @@ pub mod tests {
             // slightly above minimum balance, to the right of the curve (time intersection)
             PayableAccount {
                 wallet: make_wallet("wallet0"),
-                balance: PAYMENT_CURVES.permanent_debt_allowed_gwub + 1,
+                balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.balance_decreases_for_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
                 ),
                 pending_payment_transaction: None,
             },
             // slightly above the curve (balance intersection), to the right of minimum time
             PayableAccount {
                 wallet: make_wallet("wallet1"),
-                balance: PAYMENT_CURVES.balance_to_decrease_from_gwub + 1,
+                balance: DEFAULT_PAYMENT_CURVES.balance_to_decrease_from_gwub + 1,
                 last_paid_timestamp: from_time_t(
-                    now - PAYMENT_CURVES.payment_suggested_after_sec - 10,
+                    now - DEFAULT_PAYMENT_CURVES.payment_suggested_after_sec - 10,
                 ),
                 pending_payment_transaction: None,
             },
@@ -2062,7 +3014,7 @@ pub mod tests {
         let (mut blockchain_bridge, blockchain_bridge_awaiter, blockchain_bridge_recordings_arc) =
             make_recorder();
         blockchain_bridge = blockchain_bridge
-            .retrieve_transactions_response(Ok(vec![]))
+            .retrieve_transactions_response(Ok(RetrievedTransactions::default()))
             .report_accounts_payable_response(Ok(vec![]));
 
         thread::spawn(move || {
@@ -2095,13 +3047,15 @@ pub mod tests {
     fn payment_received_scan_triggers_scan_for_delinquencies() {
         let ban_parameters_arc = Arc::new(Mutex::new(vec![]));
         let ban_parameters_arc_inner = ban_parameters_arc.clone();
-        let blockchain_bridge = Recorder::new().retrieve_transactions_response(Ok(vec![]));
+        let blockchain_bridge =
+            Recorder::new().retrieve_transactions_response(Ok(RetrievedTransactions::default()));
         thread::spawn(move || {
             let system = System::new("payment_received_scan_triggers_scan_for_delinquencies");
             let config = bc_from_ac_plus_earning_wallet(
                 AccountantConfig {
                     payable_scan_interval: Duration::from_secs(10_000),
                     payment_received_scan_interval: Duration::from_millis(100),
+                    payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
                 },
                 make_wallet("hi"),
             );
@@ -2171,10 +3125,19 @@ pub mod tests {
     #[test]
     fn scan_for_delinquencies_triggers_bans_and_unbans() {
         init_test_logging();
+        let payment_curves = PaymentCurves {
+            payment_suggested_after_sec: 1000,
+            payment_grace_before_ban_sec: 1001,
+            permanent_debt_allowed_gwub: 1002,
+            balance_to_decrease_from_gwub: 1003,
+            balance_decreases_for_sec: 1004,
+            unban_when_balance_below_gwub: 1005,
+        };
         let config = bc_from_ac_plus_earning_wallet(
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(1000),
+                payment_curves: payment_curves.clone(),
             },
             make_wallet("mine"),
         );
@@ -2208,10 +3171,10 @@ pub mod tests {
 
         let new_delinquencies_parameters: MutexGuard<Vec<(SystemTime, PaymentCurves)>> =
             new_delinquencies_parameters_arc.lock().unwrap();
-        assert_eq!(PAYMENT_CURVES.clone(), new_delinquencies_parameters[0].1);
+        assert_eq!(payment_curves, new_delinquencies_parameters[0].1);
         let paid_delinquencies_parameters: MutexGuard<Vec<PaymentCurves>> =
             paid_delinquencies_parameters_arc.lock().unwrap();
-        assert_eq!(PAYMENT_CURVES.clone(), paid_delinquencies_parameters[0]);
+        assert_eq!(payment_curves, paid_delinquencies_parameters[0]);
         let ban_parameters = ban_parameters_arc.lock().unwrap();
         assert!(ban_parameters.contains(&newly_banned_1.wallet));
         assert!(ban_parameters.contains(&newly_banned_2.wallet));
@@ -2227,6 +3190,44 @@ pub mod tests {
         tlh.exists_log_matching("INFO: Accountant: Wallet 0x00000000000000000077616c6c6574343536376e \\(balance: 4567 MASQ, age: \\d+ sec\\) is no longer delinquent: unbanned");
     }
 
+    #[test]
+    fn should_pay_uses_the_supplied_payment_curves() {
+        let now = to_time_t(SystemTime::now());
+        let payable = PayableAccount {
+            wallet: make_wallet("booga"),
+            balance: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
+            last_paid_timestamp: from_time_t(
+                now - DEFAULT_PAYMENT_CURVES.balance_decreases_for_sec - 10,
+            ),
+            pending_payment_transaction: None,
+        };
+        let tolerant_curves = PaymentCurves {
+            permanent_debt_allowed_gwub: DEFAULT_PAYMENT_CURVES.permanent_debt_allowed_gwub + 1000,
+            ..DEFAULT_PAYMENT_CURVES.clone()
+        };
+
+        assert!(Accountant::should_pay(&payable, &DEFAULT_PAYMENT_CURVES));
+        assert!(!Accountant::should_pay(&payable, &tolerant_curves));
+    }
+
+    #[test]
+    fn default_payment_curves_match_the_default_setting() {
+        let result = PaymentCurves::from_str(masq_lib::constants::DEFAULT_PAYMENT_CURVES).unwrap();
+
+        assert_eq!(result, DEFAULT_PAYMENT_CURVES.clone());
+        assert_eq!(
+            DEFAULT_PAYMENT_CURVES.to_string(),
+            masq_lib::constants::DEFAULT_PAYMENT_CURVES
+        );
+    }
+
+    #[test]
+    fn payment_curves_from_str_rejects_bad_syntax() {
+        let result = PaymentCurves::from_str("1|2|3");
+
+        assert_eq!(result, Err("Invalid payment curves: 1|2|3".to_string()));
+    }
+
     #[test]
     fn report_routing_service_provided_message_is_received() {
         init_test_logging();
@@ -2234,6 +3235,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("hi"),
         );
@@ -2288,6 +3290,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             consuming_wallet.clone(),
             make_wallet("our earning wallet"),
@@ -2341,6 +3344,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -2392,6 +3396,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("hi"),
         );
@@ -2439,6 +3444,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             consuming_wallet.clone(),
             make_wallet("the earning wallet"),
@@ -2483,6 +3489,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -2525,6 +3532,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("hi"),
         );
@@ -2579,6 +3587,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             consuming_wallet.clone(),
             make_wallet("my earning wallet"),
@@ -2632,6 +3641,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -2683,6 +3693,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             make_wallet("hi"),
         );
@@ -2731,6 +3742,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             consuming_wallet.clone(),
             make_wallet("own earning wallet"),
@@ -2775,6 +3787,7 @@ pub mod tests {
             AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             earning_wallet.clone(),
         );
@@ -2950,6 +3963,9 @@ pub mod tests {
             &config_opt.unwrap_or(BootstrapperConfig::new()),
             Box::new(payable_dao_factory),
             Box::new(receivable_dao_factory),
+            Box::new(PaymentHistoryDaoFactoryMock::new(
+                PaymentHistoryDaoMock::new(),
+            )),
             Box::new(banned_dao_factory),
             Box::new(ConfigDaoFactoryMock::new(ConfigDaoMock::new())),
         );
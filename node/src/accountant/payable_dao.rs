@@ -9,7 +9,7 @@ use rusqlite::{Error, OptionalExtension, NO_PARAMS};
 use serde_json::{self, json};
 use std::fmt::Debug;
 use std::time::SystemTime;
-use web3::types::H256;
+use web3::types::{H256, U256};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct PayableAccount {
@@ -25,6 +25,8 @@ pub struct Payment {
     pub amount: u64,
     pub timestamp: SystemTime,
     pub transaction: H256,
+    // Payments sent before nonces were recorded have none
+    pub nonce_opt: Option<U256>,
 }
 
 impl Payment {
@@ -34,6 +36,14 @@ impl Payment {
             amount,
             timestamp: SystemTime::now(),
             transaction,
+            nonce_opt: None,
+        }
+    }
+
+    pub fn with_nonce(self, nonce: U256) -> Self {
+        Self {
+            nonce_opt: Some(nonce),
+            ..self
         }
     }
 }
@@ -51,10 +61,14 @@ pub trait PayableDao: Debug + Send {
         transaction_hash: H256,
     ) -> Result<(), PaymentError>;
 
+    fn payment_failed(&self, payment: &Payment) -> Result<(), PaymentError>;
+
     fn account_status(&self, wallet: &Wallet) -> Option<PayableAccount>;
 
     fn non_pending_payables(&self) -> Vec<PayableAccount>;
 
+    fn pending_payments(&self) -> Vec<Payment>;
+
     fn top_records(&self, minimum_amount: u64, maximum_age: u64) -> Vec<PayableAccount>;
 
     fn total(&self) -> u64;
@@ -91,6 +105,7 @@ impl PayableDao for PayableDaoReal {
             signed_amount,
             payment.timestamp,
             payment.transaction,
+            payment.nonce_opt,
         ) {
             Ok(_) => Ok(()),
             Err(e) => panic!("Database is corrupt: {}", e),
@@ -99,13 +114,28 @@ impl PayableDao for PayableDaoReal {
 
     fn payment_confirmed(
         &self,
-        _wallet: &Wallet,
+        wallet: &Wallet,
         amount: u64,
-        _confirmation_noticed_timestamp: SystemTime,
-        _transaction_hash: H256,
+        confirmation_noticed_timestamp: SystemTime,
+        transaction_hash: H256,
     ) -> Result<(), PaymentError> {
         let _signed_amount = jackass_unsigned_to_signed(amount)?;
-        unimplemented!("SC-925: TODO")
+        match self.try_clear_pending_transaction(
+            wallet,
+            confirmation_noticed_timestamp,
+            transaction_hash,
+        ) {
+            Ok(_) => Ok(()),
+            Err(e) => panic!("Database is corrupt: {}", e),
+        }
+    }
+
+    fn payment_failed(&self, payment: &Payment) -> Result<(), PaymentError> {
+        let signed_amount = jackass_unsigned_to_signed(payment.amount)?;
+        match self.try_restore_balance(&payment.to, signed_amount, payment.transaction) {
+            Ok(_) => Ok(()),
+            Err(e) => panic!("Database is corrupt: {}", e),
+        }
     }
 
     fn account_status(&self, wallet: &Wallet) -> Option<PayableAccount> {
@@ -170,6 +200,48 @@ impl PayableDao for PayableDaoReal {
         .collect()
     }
 
+    fn pending_payments(&self) -> Vec<Payment> {
+        let mut stmt = self.conn
+            .prepare("select wallet_address, pending_payment_amount, last_paid_timestamp, pending_payment_transaction, pending_payment_nonce from payable where pending_payment_transaction is not null")
+            .expect("Internal error");
+
+        stmt.query_map(NO_PARAMS, |row| {
+            let wallet_result: Result<Wallet, rusqlite::Error> = row.get(0);
+            let amount_result: Result<i64, rusqlite::Error> = row.get(1);
+            let last_paid_timestamp_result = row.get(2);
+            let transaction_result: Result<String, rusqlite::Error> = row.get(3);
+            let nonce_result: Result<Option<i64>, rusqlite::Error> = row.get(4);
+            match (
+                wallet_result,
+                amount_result,
+                last_paid_timestamp_result,
+                transaction_result,
+                nonce_result,
+            ) {
+                (
+                    Ok(wallet),
+                    Ok(amount),
+                    Ok(last_paid_timestamp),
+                    Ok(transaction),
+                    Ok(nonce_opt),
+                ) => Ok(Payment {
+                    to: wallet,
+                    amount: amount as u64,
+                    timestamp: dao_utils::from_time_t(last_paid_timestamp),
+                    transaction: match serde_json::from_value(json!(transaction)) {
+                        Ok(transaction) => transaction,
+                        Err(e) => panic!("{:?}", e),
+                    },
+                    nonce_opt: nonce_opt.map(|nonce| U256::from(nonce as u64)),
+                }),
+                _ => panic!("Database is corrupt: PAYABLE table columns and/or types"),
+            }
+        })
+        .expect("Database is corrupt")
+        .flatten()
+        .collect()
+    }
+
     fn top_records(&self, minimum_amount: u64, maximum_age: u64) -> Vec<PayableAccount> {
         let min_amt = jackass_unsigned_to_signed(minimum_amount).unwrap_or(0x7FFF_FFFF_FFFF_FFFF);
         let max_age = jackass_unsigned_to_signed(maximum_age).unwrap_or(0x7FFF_FFFF_FFFF_FFFF);
@@ -285,16 +357,66 @@ impl PayableDaoReal {
         amount: i64,
         last_paid_timestamp: SystemTime,
         transaction_hash: H256,
+        nonce_opt: Option<U256>,
     ) -> Result<bool, String> {
         let mut stmt = self
             .conn
-            .prepare("insert into payable (balance, last_paid_timestamp, pending_payment_transaction, wallet_address) values (0 - :balance, :last_paid, :transaction, :address) on conflict (wallet_address) do update set balance = balance - :balance, last_paid_timestamp = :last_paid, pending_payment_transaction = :transaction where wallet_address = :address")
+            .prepare("insert into payable (balance, last_paid_timestamp, pending_payment_transaction, pending_payment_amount, pending_payment_nonce, wallet_address) values (0 - :balance, :last_paid, :transaction, :balance, :nonce, :address) on conflict (wallet_address) do update set balance = balance - :balance, last_paid_timestamp = :last_paid, pending_payment_transaction = :transaction, pending_payment_amount = :balance, pending_payment_nonce = :nonce where wallet_address = :address")
             .expect("Internal error");
+        let nonce_opt = nonce_opt.map(|nonce| nonce.low_u64() as i64);
         let params: &[(&str, &dyn ToSql)] = &[
             (":balance", &amount),
             (":last_paid", &dao_utils::to_time_t(last_paid_timestamp)),
             (":transaction", &format!("{:#x}", &transaction_hash)),
+            (":nonce", &nonce_opt),
+            (":address", &wallet),
+        ];
+        match stmt.execute_named(params) {
+            Ok(0) => Ok(false),
+            Ok(_) => Ok(true),
+            Err(e) => Err(format!("{}", e)),
+        }
+    }
+
+    fn try_clear_pending_transaction(
+        &self,
+        wallet: &Wallet,
+        confirmation_noticed_timestamp: SystemTime,
+        transaction_hash: H256,
+    ) -> Result<bool, String> {
+        let mut stmt = self
+            .conn
+            .prepare("update payable set last_paid_timestamp = :last_paid, pending_payment_transaction = null, pending_payment_amount = null, pending_payment_nonce = null where wallet_address = :address and pending_payment_transaction = :transaction")
+            .expect("Internal error");
+        let params: &[(&str, &dyn ToSql)] = &[
+            (
+                ":last_paid",
+                &dao_utils::to_time_t(confirmation_noticed_timestamp),
+            ),
+            (":address", &wallet),
+            (":transaction", &format!("{:#x}", &transaction_hash)),
+        ];
+        match stmt.execute_named(params) {
+            Ok(0) => Ok(false),
+            Ok(_) => Ok(true),
+            Err(e) => Err(format!("{}", e)),
+        }
+    }
+
+    fn try_restore_balance(
+        &self,
+        wallet: &Wallet,
+        amount: i64,
+        transaction_hash: H256,
+    ) -> Result<bool, String> {
+        let mut stmt = self
+            .conn
+            .prepare("update payable set balance = balance + :balance, pending_payment_transaction = null, pending_payment_amount = null, pending_payment_nonce = null where wallet_address = :address and pending_payment_transaction = :transaction")
+            .expect("Internal error");
+        let params: &[(&str, &dyn ToSql)] = &[
+            (":balance", &amount),
             (":address", &wallet),
+            (":transaction", &format!("{:#x}", &transaction_hash)),
         ];
         match stmt.execute_named(params) {
             Ok(0) => Ok(false),
@@ -513,6 +635,141 @@ mod tests {
         assert_eq!(result, Err(PaymentError::SignConversion(std::u64::MAX)))
     }
 
+    #[test]
+    fn payment_confirmed_clears_the_pending_transaction() {
+        let home_dir = ensure_node_home_directory_exists(
+            "payable_dao",
+            "payment_confirmed_clears_the_pending_transaction",
+        );
+        let wallet = make_wallet("booga");
+        let subject = PayableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let payment = Payment::new(wallet.clone(), 1234, H256::from_uint(&U256::from(1)));
+        subject.more_money_payable(&wallet, 2345).unwrap();
+        subject.payment_sent(&payment).unwrap();
+        let confirmation_timestamp = from_time_t(1_234_567_890);
+
+        subject
+            .payment_confirmed(
+                &wallet,
+                payment.amount,
+                confirmation_timestamp,
+                payment.transaction,
+            )
+            .unwrap();
+
+        assert_eq!(
+            subject.account_status(&wallet).unwrap(),
+            PayableAccount {
+                wallet,
+                balance: 1111,
+                last_paid_timestamp: confirmation_timestamp,
+                pending_payment_transaction: None,
+            }
+        );
+        assert_eq!(subject.pending_payments(), vec![]);
+    }
+
+    #[test]
+    fn payment_confirmed_leaves_a_different_pending_transaction_alone() {
+        let home_dir = ensure_node_home_directory_exists(
+            "payable_dao",
+            "payment_confirmed_leaves_a_different_pending_transaction_alone",
+        );
+        let wallet = make_wallet("booga");
+        let subject = PayableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let payment = Payment::new(wallet.clone(), 1234, H256::from_uint(&U256::from(1)));
+        subject.payment_sent(&payment).unwrap();
+
+        subject
+            .payment_confirmed(
+                &wallet,
+                payment.amount,
+                SystemTime::now(),
+                H256::from_uint(&U256::from(2)),
+            )
+            .unwrap();
+
+        let status = subject.account_status(&wallet).unwrap();
+        assert_eq!(
+            status.pending_payment_transaction,
+            Some(H256::from_uint(&U256::from(1)))
+        );
+    }
+
+    #[test]
+    fn payment_failed_restores_the_balance_and_clears_the_pending_transaction() {
+        let home_dir = ensure_node_home_directory_exists(
+            "payable_dao",
+            "payment_failed_restores_the_balance_and_clears_the_pending_transaction",
+        );
+        let wallet = make_wallet("booga");
+        let subject = PayableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let payment = Payment::new(wallet.clone(), 1234, H256::from_uint(&U256::from(1)));
+        subject.more_money_payable(&wallet, 1234).unwrap();
+        subject.payment_sent(&payment).unwrap();
+        subject.more_money_payable(&wallet, 100).unwrap();
+
+        subject.payment_failed(&payment).unwrap();
+
+        let status = subject.account_status(&wallet).unwrap();
+        assert_eq!(status.balance, 1334);
+        assert_eq!(status.pending_payment_transaction, None);
+        assert_eq!(subject.non_pending_payables().len(), 1);
+    }
+
+    #[test]
+    fn payment_failed_works_for_overflow() {
+        let home_dir =
+            ensure_node_home_directory_exists("payable_dao", "payment_failed_works_for_overflow");
+        let wallet = make_wallet("booga");
+        let subject = PayableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let payment = Payment::new(wallet, std::u64::MAX, H256::from_uint(&U256::from(1)));
+
+        let result = subject.payment_failed(&payment);
+
+        assert_eq!(result, Err(PaymentError::SignConversion(std::u64::MAX)))
+    }
+
+    #[test]
+    fn pending_payments_returns_payments_awaiting_confirmation() {
+        let home_dir = ensure_node_home_directory_exists(
+            "payable_dao",
+            "pending_payments_returns_payments_awaiting_confirmation",
+        );
+        let subject = PayableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let mut payment = Payment::new(make_wallet("foo"), 1234, H256::from_uint(&U256::from(1)))
+            .with_nonce(U256::from(7));
+        payment.timestamp = from_time_t(1_000_000_000);
+        subject.payment_sent(&payment).unwrap();
+        subject
+            .more_money_payable(&make_wallet("bar"), 4321)
+            .unwrap();
+
+        let result = subject.pending_payments();
+
+        assert_eq!(result, vec![payment]);
+    }
+
     #[test]
     fn payable_account_status_works_when_account_doesnt_exist() {
         let home_dir = ensure_node_home_directory_exists(
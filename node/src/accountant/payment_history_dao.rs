@@ -0,0 +1,480 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai). All rights reserved.
+use crate::accountant::payable_dao::Payment;
+use crate::accountant::{jackass_unsigned_to_signed, PaymentError};
+use crate::blockchain::blockchain_interface::Transaction;
+use crate::database::connection_wrapper::ConnectionWrapper;
+use crate::database::dao_utils;
+use crate::database::dao_utils::DaoFactoryReal;
+use crate::sub_lib::wallet::Wallet;
+use rusqlite::types::ToSql;
+use rusqlite::Row;
+use serde_json::{self, json};
+use std::fmt::Debug;
+use std::time::SystemTime;
+use web3::types::H256;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PaymentDirection {
+    Sent,
+    Received,
+}
+
+impl PaymentDirection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PaymentDirection::Sent => "sent",
+            PaymentDirection::Received => "received",
+        }
+    }
+
+    fn from_column(s: &str) -> Option<Self> {
+        match s {
+            "sent" => Some(PaymentDirection::Sent),
+            "received" => Some(PaymentDirection::Received),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PaymentStatus {
+    // Sent, but not yet mined
+    Pending,
+    Confirmed,
+    // Mined, but reverted
+    Failed,
+    // Never mined; the debt is owed again
+    Dropped,
+    // Received in a block that was later orphaned
+    Reversed,
+}
+
+impl PaymentStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Confirmed => "confirmed",
+            PaymentStatus::Failed => "failed",
+            PaymentStatus::Dropped => "dropped",
+            PaymentStatus::Reversed => "reversed",
+        }
+    }
+
+    fn from_column(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(PaymentStatus::Pending),
+            "confirmed" => Some(PaymentStatus::Confirmed),
+            "failed" => Some(PaymentStatus::Failed),
+            "dropped" => Some(PaymentStatus::Dropped),
+            "reversed" => Some(PaymentStatus::Reversed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentRecord {
+    pub direction: PaymentDirection,
+    pub transaction_hash: H256,
+    pub counterparty: Wallet,
+    pub amount: u64,
+    pub block_number_opt: Option<u64>,
+    pub status: PaymentStatus,
+    pub timestamp: SystemTime,
+}
+
+pub trait PaymentHistoryDao: Debug + Send {
+    fn payment_sent(&self, payment: &Payment) -> Result<(), PaymentError>;
+
+    fn payment_received(&self, transaction: &Transaction) -> Result<(), PaymentError>;
+
+    fn sent_payment_status_changed(
+        &self,
+        payment: &Payment,
+        status: PaymentStatus,
+        block_number_opt: Option<u64>,
+    ) -> Result<(), PaymentError>;
+
+    fn received_payments_reversed(&self, block_numbers: &[u64]) -> Result<(), PaymentError>;
+
+    fn records_for(&self, counterparty: &Wallet) -> Vec<PaymentRecord>;
+
+    fn records_between(&self, from: SystemTime, to: SystemTime) -> Vec<PaymentRecord>;
+}
+
+pub trait PaymentHistoryDaoFactory {
+    fn make(&self) -> Box<dyn PaymentHistoryDao>;
+}
+
+impl PaymentHistoryDaoFactory for DaoFactoryReal {
+    fn make(&self) -> Box<dyn PaymentHistoryDao> {
+        Box::new(PaymentHistoryDaoReal::new(self.make_connection()))
+    }
+}
+
+#[derive(Debug)]
+pub struct PaymentHistoryDaoReal {
+    conn: Box<dyn ConnectionWrapper>,
+}
+
+impl PaymentHistoryDao for PaymentHistoryDaoReal {
+    fn payment_sent(&self, payment: &Payment) -> Result<(), PaymentError> {
+        let signed_amount = jackass_unsigned_to_signed(payment.amount)?;
+        let params: &[(&str, &dyn ToSql)] = &[
+            (":direction", &PaymentDirection::Sent.as_str()),
+            (":transaction_hash", &format!("{:#x}", payment.transaction)),
+            (":wallet_address", &payment.to),
+            (":amount", &signed_amount),
+            (":block_number", &None::<i64>),
+            (":status", &PaymentStatus::Pending.as_str()),
+            (":timestamp", &dao_utils::to_time_t(payment.timestamp)),
+        ];
+        self.upsert(params)
+    }
+
+    fn payment_received(&self, transaction: &Transaction) -> Result<(), PaymentError> {
+        let signed_amount = jackass_unsigned_to_signed(transaction.gwei_amount)?;
+        let block_number = jackass_unsigned_to_signed(transaction.block_number)?;
+        // A payment that comes back in a new block after a reorganization is confirmed again
+        let params: &[(&str, &dyn ToSql)] = &[
+            (":direction", &PaymentDirection::Received.as_str()),
+            (
+                ":transaction_hash",
+                &format!("{:#x}", transaction.transaction_hash),
+            ),
+            (":wallet_address", &transaction.from),
+            (":amount", &signed_amount),
+            (":block_number", &Some(block_number)),
+            (":status", &PaymentStatus::Confirmed.as_str()),
+            (":timestamp", &dao_utils::now_time_t()),
+        ];
+        self.upsert(params)
+    }
+
+    fn sent_payment_status_changed(
+        &self,
+        payment: &Payment,
+        status: PaymentStatus,
+        block_number_opt: Option<u64>,
+    ) -> Result<(), PaymentError> {
+        let block_number_opt = match block_number_opt {
+            Some(block_number) => Some(jackass_unsigned_to_signed(block_number)?),
+            None => None,
+        };
+        let mut stmt = self
+            .conn
+            .prepare("update payment_history set status = :status, block_number = :block_number where direction = :direction and transaction_hash = :transaction_hash and wallet_address = :wallet_address")
+            .expect("Internal error");
+        let params: &[(&str, &dyn ToSql)] = &[
+            (":status", &status.as_str()),
+            (":block_number", &block_number_opt),
+            (":direction", &PaymentDirection::Sent.as_str()),
+            (":transaction_hash", &format!("{:#x}", payment.transaction)),
+            (":wallet_address", &payment.to),
+        ];
+        match stmt.execute_named(params) {
+            Ok(_) => Ok(()),
+            Err(e) => panic!("Database is corrupt: {}", e),
+        }
+    }
+
+    fn received_payments_reversed(&self, block_numbers: &[u64]) -> Result<(), PaymentError> {
+        let mut stmt = self
+            .conn
+            .prepare("update payment_history set status = :status where direction = :direction and block_number = :block_number")
+            .expect("Internal error");
+        for block_number in block_numbers {
+            let signed_block_number = jackass_unsigned_to_signed(*block_number)?;
+            let params: &[(&str, &dyn ToSql)] = &[
+                (":status", &PaymentStatus::Reversed.as_str()),
+                (":direction", &PaymentDirection::Received.as_str()),
+                (":block_number", &signed_block_number),
+            ];
+            if let Err(e) = stmt.execute_named(params) {
+                panic!("Database is corrupt: {}", e)
+            }
+        }
+        Ok(())
+    }
+
+    fn records_for(&self, counterparty: &Wallet) -> Vec<PaymentRecord> {
+        let mut stmt = self
+            .conn
+            .prepare("select direction, transaction_hash, wallet_address, amount, block_number, status, timestamp from payment_history where wallet_address = ? order by timestamp, rowid")
+            .expect("Internal error");
+        stmt.query_map(&[counterparty], Self::row_to_record)
+            .expect("Database is corrupt")
+            .flatten()
+            .collect()
+    }
+
+    fn records_between(&self, from: SystemTime, to: SystemTime) -> Vec<PaymentRecord> {
+        let mut stmt = self
+            .conn
+            .prepare("select direction, transaction_hash, wallet_address, amount, block_number, status, timestamp from payment_history where timestamp >= ? and timestamp <= ? order by timestamp, rowid")
+            .expect("Internal error");
+        let params: &[&dyn ToSql] = &[&dao_utils::to_time_t(from), &dao_utils::to_time_t(to)];
+        stmt.query_map(params, Self::row_to_record)
+            .expect("Database is corrupt")
+            .flatten()
+            .collect()
+    }
+}
+
+impl PaymentHistoryDaoReal {
+    pub fn new(conn: Box<dyn ConnectionWrapper>) -> PaymentHistoryDaoReal {
+        PaymentHistoryDaoReal { conn }
+    }
+
+    fn upsert(&self, params: &[(&str, &dyn ToSql)]) -> Result<(), PaymentError> {
+        let mut stmt = self
+            .conn
+            .prepare("insert into payment_history (direction, transaction_hash, wallet_address, amount, block_number, status, timestamp) values (:direction, :transaction_hash, :wallet_address, :amount, :block_number, :status, :timestamp) on conflict (direction, transaction_hash, wallet_address) do update set amount = :amount, block_number = :block_number, status = :status, timestamp = :timestamp")
+            .expect("Internal error");
+        match stmt.execute_named(params) {
+            Ok(_) => Ok(()),
+            Err(e) => panic!("Database is corrupt: {}", e),
+        }
+    }
+
+    fn row_to_record(row: &Row) -> rusqlite::Result<PaymentRecord> {
+        let direction_result: Result<String, rusqlite::Error> = row.get(0);
+        let transaction_hash_result: Result<String, rusqlite::Error> = row.get(1);
+        let wallet_result: Result<Wallet, rusqlite::Error> = row.get(2);
+        let amount_result: Result<i64, rusqlite::Error> = row.get(3);
+        let block_number_result: Result<Option<i64>, rusqlite::Error> = row.get(4);
+        let status_result: Result<String, rusqlite::Error> = row.get(5);
+        let timestamp_result: Result<i64, rusqlite::Error> = row.get(6);
+        match (
+            direction_result,
+            transaction_hash_result,
+            wallet_result,
+            amount_result,
+            block_number_result,
+            status_result,
+            timestamp_result,
+        ) {
+            (
+                Ok(direction),
+                Ok(transaction_hash),
+                Ok(counterparty),
+                Ok(amount),
+                Ok(block_number_opt),
+                Ok(status),
+                Ok(timestamp),
+            ) => Ok(PaymentRecord {
+                direction: PaymentDirection::from_column(&direction).unwrap_or_else(|| {
+                    panic!("Database is corrupt: payment direction '{}'", direction)
+                }),
+                transaction_hash: match serde_json::from_value(json!(transaction_hash)) {
+                    Ok(transaction_hash) => transaction_hash,
+                    Err(e) => panic!("{:?}", e),
+                },
+                counterparty,
+                amount: amount as u64,
+                block_number_opt: block_number_opt.map(|block_number| block_number as u64),
+                status: PaymentStatus::from_column(&status)
+                    .unwrap_or_else(|| panic!("Database is corrupt: payment status '{}'", status)),
+                timestamp: dao_utils::from_time_t(timestamp),
+            }),
+            _ => panic!("Database is corrupt: PAYMENT_HISTORY table columns and/or types"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::dao_utils::from_time_t;
+    use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
+    use crate::test_utils::make_wallet;
+    use ethereum_types::BigEndianHash;
+    use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, DEFAULT_CHAIN_ID};
+    use web3::types::U256;
+
+    fn make_subject(test_name: &str) -> PaymentHistoryDaoReal {
+        let home_dir = ensure_node_home_directory_exists("payment_history_dao", test_name);
+        PaymentHistoryDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        )
+    }
+
+    fn make_payment(to: &Wallet, amount: u64, transaction: u64, timestamp: i64) -> Payment {
+        Payment {
+            to: to.clone(),
+            amount,
+            timestamp: from_time_t(timestamp),
+            transaction: H256::from_uint(&U256::from(transaction)),
+            nonce_opt: None,
+        }
+    }
+
+    fn make_transaction(from: &Wallet, gwei_amount: u64, block_number: u64) -> Transaction {
+        Transaction {
+            block_number,
+            block_hash: H256::from_uint(&U256::from(block_number)),
+            transaction_hash: H256::from_uint(&U256::from(block_number + 1000)),
+            from: from.clone(),
+            gwei_amount,
+        }
+    }
+
+    #[test]
+    fn sent_payment_is_recorded_as_pending_and_follows_its_receipt() {
+        let subject = make_subject("sent_payment_is_recorded_as_pending_and_follows_its_receipt");
+        let wallet = make_wallet("creditor");
+        let confirmed = make_payment(&wallet, 1234, 1, 1_000);
+        let dropped = make_payment(&wallet, 2345, 2, 1_001);
+
+        subject.payment_sent(&confirmed).unwrap();
+        subject.payment_sent(&dropped).unwrap();
+        subject
+            .sent_payment_status_changed(&confirmed, PaymentStatus::Confirmed, Some(42))
+            .unwrap();
+        subject
+            .sent_payment_status_changed(&dropped, PaymentStatus::Dropped, None)
+            .unwrap();
+
+        assert_eq!(
+            subject.records_for(&wallet),
+            vec![
+                PaymentRecord {
+                    direction: PaymentDirection::Sent,
+                    transaction_hash: confirmed.transaction,
+                    counterparty: wallet.clone(),
+                    amount: 1234,
+                    block_number_opt: Some(42),
+                    status: PaymentStatus::Confirmed,
+                    timestamp: from_time_t(1_000),
+                },
+                PaymentRecord {
+                    direction: PaymentDirection::Sent,
+                    transaction_hash: dropped.transaction,
+                    counterparty: wallet.clone(),
+                    amount: 2345,
+                    block_number_opt: None,
+                    status: PaymentStatus::Dropped,
+                    timestamp: from_time_t(1_001),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn payments_sent_in_one_batch_are_recorded_separately() {
+        let subject = make_subject("payments_sent_in_one_batch_are_recorded_separately");
+        let first_wallet = make_wallet("first");
+        let second_wallet = make_wallet("second");
+        let first = make_payment(&first_wallet, 1234, 1, 1_000);
+        let second = make_payment(&second_wallet, 2345, 1, 1_000);
+
+        subject.payment_sent(&first).unwrap();
+        subject.payment_sent(&second).unwrap();
+        subject
+            .sent_payment_status_changed(&second, PaymentStatus::Failed, Some(42))
+            .unwrap();
+
+        let first_records = subject.records_for(&first_wallet);
+        let second_records = subject.records_for(&second_wallet);
+        assert_eq!(first_records.len(), 1);
+        assert_eq!(first_records[0].status, PaymentStatus::Pending);
+        assert_eq!(second_records.len(), 1);
+        assert_eq!(second_records[0].status, PaymentStatus::Failed);
+        assert_eq!(second_records[0].block_number_opt, Some(42));
+    }
+
+    #[test]
+    fn received_payments_from_orphaned_blocks_are_marked_reversed() {
+        let subject = make_subject("received_payments_from_orphaned_blocks_are_marked_reversed");
+        let wallet = make_wallet("debtor");
+        let kept = make_transaction(&wallet, 1234, 10);
+        let orphaned = make_transaction(&wallet, 2345, 11);
+        // A sent payment mined in the same block is ours, and orphaning doesn't reverse it here
+        let sent = make_payment(&wallet, 3456, 3, 1_000);
+        subject.payment_sent(&sent).unwrap();
+        subject
+            .sent_payment_status_changed(&sent, PaymentStatus::Confirmed, Some(11))
+            .unwrap();
+
+        subject.payment_received(&kept).unwrap();
+        subject.payment_received(&orphaned).unwrap();
+        subject.received_payments_reversed(&[11]).unwrap();
+
+        let records = subject.records_for(&wallet);
+        let status_of = |hash: H256| {
+            records
+                .iter()
+                .find(|record| record.transaction_hash == hash)
+                .unwrap()
+                .status
+        };
+        assert_eq!(records.len(), 3);
+        assert_eq!(status_of(kept.transaction_hash), PaymentStatus::Confirmed);
+        assert_eq!(
+            status_of(orphaned.transaction_hash),
+            PaymentStatus::Reversed
+        );
+        assert_eq!(status_of(sent.transaction), PaymentStatus::Confirmed);
+    }
+
+    #[test]
+    fn received_payment_mined_again_after_reversal_is_confirmed_in_its_new_block() {
+        let subject = make_subject(
+            "received_payment_mined_again_after_reversal_is_confirmed_in_its_new_block",
+        );
+        let wallet = make_wallet("debtor");
+        let original = make_transaction(&wallet, 1234, 10);
+        let remined = Transaction {
+            block_number: 12,
+            block_hash: H256::from_uint(&U256::from(12)),
+            ..original.clone()
+        };
+        subject.payment_received(&original).unwrap();
+        subject.received_payments_reversed(&[10]).unwrap();
+
+        subject.payment_received(&remined).unwrap();
+
+        let records = subject.records_for(&wallet);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].direction, PaymentDirection::Received);
+        assert_eq!(records[0].transaction_hash, original.transaction_hash);
+        assert_eq!(records[0].amount, 1234);
+        assert_eq!(records[0].block_number_opt, Some(12));
+        assert_eq!(records[0].status, PaymentStatus::Confirmed);
+    }
+
+    #[test]
+    fn records_between_finds_payments_in_the_time_range() {
+        let subject = make_subject("records_between_finds_payments_in_the_time_range");
+        let early = make_payment(&make_wallet("early"), 1, 1, 1_000);
+        let middle = make_payment(&make_wallet("middle"), 2, 2, 2_000);
+        let late = make_payment(&make_wallet("late"), 3, 3, 3_000);
+        subject.payment_sent(&late).unwrap();
+        subject.payment_sent(&early).unwrap();
+        subject.payment_sent(&middle).unwrap();
+
+        let result = subject.records_between(from_time_t(1_000), from_time_t(2_000));
+
+        assert_eq!(
+            result
+                .into_iter()
+                .map(|record| record.counterparty)
+                .collect::<Vec<Wallet>>(),
+            vec![early.to, middle.to]
+        );
+    }
+
+    #[test]
+    fn payment_sent_complains_about_an_amount_too_large_to_record() {
+        let subject = make_subject("payment_sent_complains_about_an_amount_too_large_to_record");
+        let wallet = make_wallet("creditor");
+        let payment = make_payment(&wallet, std::u64::MAX, 1, 1_000);
+
+        let result = subject.payment_sent(&payment);
+
+        assert_eq!(result, Err(PaymentError::SignConversion(std::u64::MAX)));
+        assert_eq!(subject.records_for(&wallet), vec![]);
+    }
+}
@@ -4,15 +4,21 @@ use crate::blockchain::blockchain_interface::Transaction;
 use crate::database::connection_wrapper::ConnectionWrapper;
 use crate::database::dao_utils;
 use crate::database::dao_utils::{to_time_t, DaoFactoryReal};
-use crate::db_config::config_dao::{ConfigDaoWrite, ConfigDaoWriteableReal};
+use crate::db_config::config_dao::{ConfigDaoRead, ConfigDaoWrite, ConfigDaoWriteableReal};
 use crate::db_config::persistent_configuration::PersistentConfigError;
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::wallet::Wallet;
 use indoc::indoc;
 use rusqlite::named_params;
 use rusqlite::types::{ToSql, Type};
-use rusqlite::{OptionalExtension, Row, NO_PARAMS};
+use rusqlite::{OptionalExtension, Row, Transaction as SqlTransaction, NO_PARAMS};
+use serde_json::json;
 use std::time::SystemTime;
+use web3::types::H256;
+
+// How many blocks behind the start block credited payments are remembered, in case their blocks
+// are orphaned
+const CREDITED_BLOCKS_KEPT: u64 = 128;
 
 #[derive(Debug, PartialEq)]
 pub enum ReceivableDaoError {
@@ -42,7 +48,14 @@ pub struct ReceivableAccount {
 pub trait ReceivableDao: Send {
     fn more_money_receivable(&self, wallet: &Wallet, amount: u64) -> Result<(), PaymentError>;
 
-    fn more_money_received(&mut self, transactions: Vec<Transaction>);
+    // Credits the payments and records that the blockchain has been scanned up to new_start_block
+    fn more_money_received(&mut self, transactions: Vec<Transaction>, new_start_block: u64);
+
+    // Recently credited blocks, with the hashes they had when they were credited
+    fn recent_credited_blocks(&self) -> Vec<(u64, H256)>;
+
+    // Takes back the payments credited from these blocks and rescans from the earliest of them
+    fn reverse_orphaned_payments(&mut self, block_numbers: &[u64]);
 
     fn account_status(&self, wallet: &Wallet) -> Option<ReceivableAccount>;
 
@@ -93,8 +106,8 @@ impl ReceivableDao for ReceivableDaoReal {
         }
     }
 
-    fn more_money_received(&mut self, payments: Vec<Transaction>) {
-        self.try_multi_insert_payment(&payments)
+    fn more_money_received(&mut self, payments: Vec<Transaction>, new_start_block: u64) {
+        self.try_multi_insert_payment(&payments, new_start_block)
             .unwrap_or_else(|e| {
                 let mut report_lines =
                     vec![format!("{:10} {:42} {:18}", "Block #", "Wallet", "Amount")];
@@ -115,6 +128,41 @@ impl ReceivableDao for ReceivableDaoReal {
             })
     }
 
+    fn recent_credited_blocks(&self) -> Vec<(u64, H256)> {
+        let mut stmt = self
+            .conn
+            .prepare("select distinct block_number, block_hash from received_payment order by block_number")
+            .expect("Internal error");
+        stmt.query_map(NO_PARAMS, |row| {
+            let block_number_result: Result<i64, rusqlite::Error> = row.get(0);
+            let block_hash_result: Result<String, rusqlite::Error> = row.get(1);
+            match (block_number_result, block_hash_result) {
+                (Ok(block_number), Ok(block_hash)) => Ok((
+                    block_number as u64,
+                    match serde_json::from_value(json!(block_hash)) {
+                        Ok(hash) => hash,
+                        Err(e) => panic!("{:?}", e),
+                    },
+                )),
+                _ => panic!("Database is corrupt: RECEIVED_PAYMENT table columns and/or types"),
+            }
+        })
+        .expect("Database is corrupt")
+        .flatten()
+        .collect()
+    }
+
+    fn reverse_orphaned_payments(&mut self, block_numbers: &[u64]) {
+        if let Err(e) = self.try_reverse_payments(block_numbers) {
+            error!(
+                self.logger,
+                "Reversing payments from orphaned blocks {:?} failed, rolling back: {:?}",
+                block_numbers,
+                e
+            );
+        }
+    }
+
     fn account_status(&self, wallet: &Wallet) -> Option<ReceivableAccount> {
         let mut stmt = self
             .conn
@@ -314,20 +362,15 @@ impl ReceivableDaoReal {
     fn try_multi_insert_payment(
         &mut self,
         payments: &[Transaction],
+        new_start_block: u64,
     ) -> Result<(), ReceivableDaoError> {
         let tx = match self.conn.transaction() {
             Ok(t) => t,
             Err(e) => return Err(ReceivableDaoError::Other(e.to_string())),
         };
 
-        let block_number = payments
-            .iter()
-            .map(|t| t.block_number)
-            .max()
-            .ok_or_else(|| "no payments given".to_string())?;
-
         let mut writer = ConfigDaoWriteableReal::new(tx);
-        match writer.set("start_block", Some(block_number.to_string())) {
+        match writer.set("start_block", Some(new_start_block.to_string())) {
             Ok(_) => (),
             Err(e) => return Err(ReceivableDaoError::Other(format!("{:?}", e))),
         }
@@ -350,9 +393,19 @@ impl ReceivableDaoReal {
                     }
                 };
                 let params: &[&dyn ToSql] = &[&gwei_amount, &timestamp, &transaction.from];
-                stmt.execute(params).map_err(|e| e.to_string())?;
+                if stmt.execute(params).map_err(|e| e.to_string())? > 0 {
+                    Self::remember_payment(&tx, transaction, gwei_amount)?;
+                }
             }
         }
+        tx.execute(
+            "delete from received_payment where block_number < ?",
+            &[
+                jackass_unsigned_to_signed(new_start_block.saturating_sub(CREDITED_BLOCKS_KEPT))
+                    .unwrap_or(0x7FFF_FFFF_FFFF_FFFF),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
         match tx.commit() {
             // Error response is untested here, because without a mockable Transaction, it's untestable.
             Err(e) => Err(ReceivableDaoError::Other(format!("{:?}", e))),
@@ -360,6 +413,69 @@ impl ReceivableDaoReal {
         }
     }
 
+    fn remember_payment(
+        tx: &SqlTransaction,
+        transaction: &Transaction,
+        gwei_amount: i64,
+    ) -> Result<(), ReceivableDaoError> {
+        let block_number = jackass_unsigned_to_signed(transaction.block_number)
+            .map_err(|e| ReceivableDaoError::Other(format!("Block number too large: {:?}", e)))?;
+        let params: &[&dyn ToSql] = &[
+            &block_number,
+            &format!("{:#x}", transaction.block_hash),
+            &transaction.from,
+            &gwei_amount,
+        ];
+        tx.execute(
+            "insert into received_payment (block_number, block_hash, wallet_address, amount) values (?, ?, ?, ?)",
+            params,
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn try_reverse_payments(&mut self, block_numbers: &[u64]) -> Result<(), ReceivableDaoError> {
+        let earliest_block = match block_numbers.iter().min() {
+            Some(block_number) => *block_number,
+            None => return Ok(()),
+        };
+        let tx = match self.conn.transaction() {
+            Ok(t) => t,
+            Err(e) => return Err(ReceivableDaoError::Other(e.to_string())),
+        };
+        for block_number in block_numbers {
+            let block_number = jackass_unsigned_to_signed(*block_number).map_err(|e| {
+                ReceivableDaoError::Other(format!("Block number too large: {:?}", e))
+            })?;
+            tx.execute(
+                "update receivable set balance = balance + (select coalesce(sum(p.amount), 0) from received_payment p where p.block_number = ?1 and p.wallet_address = receivable.wallet_address) where wallet_address in (select wallet_address from received_payment where block_number = ?1)",
+                &[&block_number],
+            )
+            .map_err(|e| e.to_string())?;
+            tx.execute(
+                "delete from received_payment where block_number = ?",
+                &[&block_number],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        let mut writer = ConfigDaoWriteableReal::new(tx);
+        let start_block_opt = writer
+            .get("start_block")
+            .map_err(|e| ReceivableDaoError::Other(format!("{:?}", e)))?
+            .value_opt
+            .and_then(|value| value.parse::<u64>().ok());
+        match start_block_opt {
+            Some(start_block) if start_block <= earliest_block => (),
+            _ => writer
+                .set("start_block", Some(earliest_block.to_string()))
+                .map_err(|e| ReceivableDaoError::Other(format!("{:?}", e)))?,
+        }
+        writer
+            .commit()
+            .map_err(|e| ReceivableDaoError::Other(format!("{:?}", e)))
+    }
+
     fn row_to_account(row: &Row) -> rusqlite::Result<ReceivableAccount> {
         let wallet: Result<Wallet, rusqlite::Error> = row.get(0);
         let balance_result = row.get(1);
@@ -391,9 +507,11 @@ mod tests {
     use crate::test_utils::logging;
     use crate::test_utils::logging::TestLogHandler;
     use crate::test_utils::{assert_contains, make_wallet};
+    use ethereum_types::BigEndianHash;
     use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, DEFAULT_CHAIN_ID};
     use rusqlite::NO_PARAMS;
     use rusqlite::{Connection, Error, OpenFlags};
+    use web3::types::U256;
 
     #[test]
     fn conversion_from_pce_works() {
@@ -427,11 +545,13 @@ mod tests {
         );
         let payments = vec![Transaction {
             block_number: 42u64,
+            block_hash: H256::from_uint(&U256::from(42)),
+            transaction_hash: H256::zero(),
             from: make_wallet("some_address"),
             gwei_amount: 18446744073709551615,
         }];
 
-        let result = subject.try_multi_insert_payment(&payments.as_slice());
+        let result = subject.try_multi_insert_payment(&payments.as_slice(), 43);
 
         assert_eq!(
             result,
@@ -458,11 +578,13 @@ mod tests {
 
         let payments = vec![Transaction {
             block_number: 42u64,
+            block_hash: H256::from_uint(&U256::from(42)),
+            transaction_hash: H256::zero(),
             from: make_wallet("some_address"),
             gwei_amount: 18446744073709551615,
         }];
 
-        let result = subject.try_multi_insert_payment(&payments.as_slice());
+        let result = subject.try_multi_insert_payment(&payments.as_slice(), 43);
 
         assert_eq!(
             result,
@@ -490,11 +612,13 @@ mod tests {
 
         let payments = vec![Transaction {
             block_number: 42u64,
+            block_hash: H256::from_uint(&U256::from(42)),
+            transaction_hash: H256::zero(),
             from: make_wallet("some_address"),
             gwei_amount: 18446744073709551615,
         }];
 
-        let _ = subject.try_multi_insert_payment(payments.as_slice());
+        let _ = subject.try_multi_insert_payment(payments.as_slice(), 43);
     }
 
     #[test]
@@ -616,15 +740,19 @@ mod tests {
                     from: debtor1.clone(),
                     gwei_amount: 1200u64,
                     block_number: 35u64,
+                    block_hash: H256::from_uint(&U256::from(35)),
+                    transaction_hash: H256::zero(),
                 },
                 Transaction {
                     from: debtor2.clone(),
                     gwei_amount: 2300u64,
                     block_number: 57u64,
+                    block_hash: H256::from_uint(&U256::from(57)),
+                    transaction_hash: H256::zero(),
                 },
             ];
 
-            subject.more_money_received(transactions);
+            subject.more_money_received(transactions, 70);
             (
                 subject.account_status(&debtor1).unwrap(),
                 subject.account_status(&debtor2).unwrap(),
@@ -650,7 +778,14 @@ mod tests {
         );
         let persistent_config = PersistentConfigurationReal::new(Box::new(config_dao));
         let start_block = persistent_config.start_block().unwrap().unwrap();
-        assert_eq!(57u64, start_block);
+        assert_eq!(70u64, start_block);
+        assert_eq!(
+            subject.recent_credited_blocks(),
+            vec![
+                (35, H256::from_uint(&U256::from(35))),
+                (57, H256::from_uint(&U256::from(57)))
+            ]
+        );
     }
 
     #[test]
@@ -671,12 +806,15 @@ mod tests {
                 from: debtor.clone(),
                 gwei_amount: 2300u64,
                 block_number: 33u64,
+                block_hash: H256::from_uint(&U256::from(33)),
+                transaction_hash: H256::zero(),
             }];
-            subject.more_money_received(transactions);
+            subject.more_money_received(transactions, 34);
             subject.account_status(&debtor)
         };
 
         assert!(status.is_none());
+        assert!(subject.recent_credited_blocks().is_empty());
     }
 
     #[test]
@@ -689,22 +827,28 @@ mod tests {
         let payments = vec![
             Transaction {
                 block_number: 1234567890,
+                block_hash: H256::from_uint(&U256::from(1234567890)),
+                transaction_hash: H256::zero(),
                 from: Wallet::new("0xAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
                 gwei_amount: 123456789123456789,
             },
             Transaction {
                 block_number: 2345678901,
+                block_hash: H256::from_uint(&U256::from(2345678901)),
+                transaction_hash: H256::zero(),
                 from: Wallet::new("0xBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB"),
                 gwei_amount: 234567891234567891,
             },
             Transaction {
                 block_number: 3456789012,
+                block_hash: H256::from_uint(&U256::from(3456789012)),
+                transaction_hash: H256::zero(),
                 from: Wallet::new("0xCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCCC"),
                 gwei_amount: 345678912345678912,
             },
         ];
 
-        receivable_dao.more_money_received(payments);
+        receivable_dao.more_money_received(payments, 3456789013);
 
         TestLogHandler::new().exists_log_containing(&format!(
             "ERROR: ReceivableDaoReal: Payment reception failed, rolling back: Other(\"Query is not read-only\")\n\
@@ -717,24 +861,121 @@ mod tests {
     }
 
     #[test]
-    fn more_money_received_logs_when_no_payment_are_given() {
-        logging::init_test_logging();
+    fn more_money_received_advances_start_block_without_payments() {
+        let home_dir = ensure_node_home_directory_exists(
+            "receivable_dao",
+            "more_money_received_advances_start_block_without_payments",
+        );
+        let mut subject = ReceivableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+
+        subject.more_money_received(vec![], 1234);
+
+        let config_dao = ConfigDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        let persistent_config = PersistentConfigurationReal::new(Box::new(config_dao));
+        assert_eq!(persistent_config.start_block().unwrap(), Some(1234));
+    }
+
+    #[test]
+    fn more_money_received_forgets_credited_blocks_too_old_to_be_orphaned() {
+        let home_dir = ensure_node_home_directory_exists(
+            "receivable_dao",
+            "more_money_received_forgets_credited_blocks_too_old_to_be_orphaned",
+        );
+        let debtor = make_wallet("debtor");
+        let mut subject = ReceivableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        subject.more_money_receivable(&debtor, 1000).unwrap();
+        let payment = |block_number: u64| Transaction {
+            block_number,
+            block_hash: H256::from_uint(&U256::from(block_number)),
+            transaction_hash: H256::zero(),
+            from: debtor.clone(),
+            gwei_amount: 10,
+        };
+        subject.more_money_received(vec![payment(100), payment(200)], 201);
+
+        subject.more_money_received(vec![payment(220)], 100 + CREDITED_BLOCKS_KEPT + 1);
+
+        assert_eq!(
+            subject.recent_credited_blocks(),
+            vec![
+                (200, H256::from_uint(&U256::from(200))),
+                (220, H256::from_uint(&U256::from(220)))
+            ]
+        );
+    }
 
+    #[test]
+    fn reverse_orphaned_payments_takes_back_payments_and_rewinds_start_block() {
         let home_dir = ensure_node_home_directory_exists(
             "receivable_dao",
-            "more_money_received_logs_when_no_payment_are_given",
+            "reverse_orphaned_payments_takes_back_payments_and_rewinds_start_block",
+        );
+        let debtor1 = make_wallet("debtor1");
+        let debtor2 = make_wallet("debtor2");
+        let mut subject = ReceivableDaoReal::new(
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap(),
+        );
+        subject.more_money_receivable(&debtor1, 1000).unwrap();
+        subject.more_money_receivable(&debtor2, 2000).unwrap();
+        let payment = |block_number: u64, from: &Wallet, gwei_amount: u64| Transaction {
+            block_number,
+            block_hash: H256::from_uint(&U256::from(block_number)),
+            transaction_hash: H256::zero(),
+            from: from.clone(),
+            gwei_amount,
+        };
+        subject.more_money_received(
+            vec![
+                payment(40, &debtor1, 100),
+                payment(41, &debtor1, 200),
+                payment(41, &debtor2, 300),
+                payment(42, &debtor2, 400),
+            ],
+            50,
         );
 
-        let mut receivable_dao = ReceivableDaoReal::new(
+        subject.reverse_orphaned_payments(&[41, 42]);
+
+        assert_eq!(subject.account_status(&debtor1).unwrap().balance, 900);
+        assert_eq!(subject.account_status(&debtor2).unwrap().balance, 2000);
+        assert_eq!(
+            subject.recent_credited_blocks(),
+            vec![(40, H256::from_uint(&U256::from(40)))]
+        );
+        let config_dao = ConfigDaoReal::new(
             DbInitializerReal::new()
                 .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
                 .unwrap(),
         );
+        let persistent_config = PersistentConfigurationReal::new(Box::new(config_dao));
+        assert_eq!(persistent_config.start_block().unwrap(), Some(41));
+    }
+
+    #[test]
+    fn reverse_orphaned_payments_logs_when_transaction_fails() {
+        logging::init_test_logging();
+        let conn_mock =
+            ConnectionWrapperMock::default().transaction_result(Err(Error::InvalidQuery));
+        let mut subject = ReceivableDaoReal::new(Box::new(conn_mock));
 
-        receivable_dao.more_money_received(vec![]);
+        subject.reverse_orphaned_payments(&[41, 42]);
 
         TestLogHandler::new().exists_log_containing(
-            "ERROR: ReceivableDaoReal: Payment reception failed, rolling back: Other(\"no payments given\")",
+            "ERROR: ReceivableDaoReal: Reversing payments from orphaned blocks [41, 42] failed, rolling back: Other(\"Query is not read-only\")",
         );
     }
 
@@ -17,6 +17,7 @@ use crate::blockchain::blockchain_bridge::BlockchainBridge;
 use crate::blockchain::blockchain_interface::{
     BlockchainInterface, BlockchainInterfaceClandestine, BlockchainInterfaceNonClandestine,
 };
+use crate::blockchain::fee_strategy::make_fee_strategy;
 use crate::database::dao_utils::DaoFactoryReal;
 use crate::database::db_initializer::{
     connection_or_panic, DbInitializer, DbInitializerReal, DATABASE_FILE,
@@ -100,6 +101,7 @@ impl ActorSystemFactoryReal {
             } else {
                 Some(0)
             },
+            config.socks_port_opt,
         );
         let proxy_client_subs = actor_factory.make_and_start_proxy_client(ProxyClientConfig {
             cryptde: main_cryptde,
@@ -111,6 +113,7 @@ impl ActorSystemFactoryReal {
                 .clone()
                 .exit_service_rate,
             exit_byte_rate: config.neighborhood_config.mode.rate_pack().exit_byte_rate,
+            exit_service_policy: config.exit_service_policy.clone(),
         });
         let hopper_subs = actor_factory.make_and_start_hopper(HopperConfig {
             main_cryptde,
@@ -202,6 +205,7 @@ pub trait ActorFactory: Send {
         alias_cryptde: &'static dyn CryptDE,
         is_decentralized: bool,
         consuming_wallet_balance: Option<i64>,
+        socks_port_opt: Option<u16>,
     ) -> ProxyServerSubs;
     fn make_and_start_hopper(&self, config: HopperConfig) -> HopperSubs;
     fn make_and_start_neighborhood(
@@ -253,6 +257,7 @@ impl ActorFactory for ActorFactoryReal {
         alias_cryptde: &'static dyn CryptDE,
         is_decentralized: bool,
         consuming_wallet_balance: Option<i64>,
+        socks_port_opt: Option<u16>,
     ) -> ProxyServerSubs {
         let addr: Addr<ProxyServer> = Arbiter::start(move |_| {
             ProxyServer::new(
@@ -260,6 +265,7 @@ impl ActorFactory for ActorFactoryReal {
                 alias_cryptde,
                 is_decentralized,
                 consuming_wallet_balance,
+                socks_port_opt,
             )
         });
         ProxyServer::make_subs_from(&addr)
@@ -300,6 +306,11 @@ impl ActorFactory for ActorFactoryReal {
             config.blockchain_bridge_config.chain_id,
             false,
         );
+        let payment_history_dao_factory = DaoFactoryReal::new(
+            data_directory,
+            config.blockchain_bridge_config.chain_id,
+            false,
+        );
         let banned_dao_factory = DaoFactoryReal::new(
             data_directory,
             config.blockchain_bridge_config.chain_id,
@@ -321,6 +332,7 @@ impl ActorFactory for ActorFactoryReal {
                 &cloned_config,
                 Box::new(payable_dao_factory),
                 Box::new(receivable_dao_factory),
+                Box::new(payment_history_dao_factory),
                 Box::new(banned_dao_factory),
                 Box::new(config_dao_factory),
             )
@@ -360,13 +372,17 @@ impl ActorFactory for ActorFactoryReal {
         let blockchain_interface: Box<dyn BlockchainInterface> = {
             match blockchain_service_url {
                 Some(url) => match Http::new(&url) {
-                    Ok((event_loop_handle, transport)) => {
-                        Box::new(BlockchainInterfaceNonClandestine::new(
+                    Ok((event_loop_handle, transport)) => Box::new(
+                        BlockchainInterfaceNonClandestine::new(
                             transport,
                             event_loop_handle,
                             config.blockchain_bridge_config.chain_id,
+                        )
+                        .with_fee_strategy(make_fee_strategy(
+                            config.blockchain_bridge_config.fee_strategy,
                         ))
-                    }
+                        .with_max_fee_per_gas(config.blockchain_bridge_config.max_fee_per_gas_opt),
+                    ),
                     Err(e) => panic!("Invalid blockchain node URL: {:?}", e),
                 },
                 None => Box::new(BlockchainInterfaceClandestine::new(
@@ -411,8 +427,11 @@ impl ActorFactory for ActorFactoryReal {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::accountant::{ReceivedPayments, SentPayments};
-    use crate::blockchain::blockchain_bridge::RetrieveTransactions;
+    use crate::accountant::{
+        ReceivedPayments, ReportTransactionReceipts, SentPayments, DEFAULT_PAYMENT_CURVES,
+    };
+    use crate::blockchain::blockchain_bridge::{RequestTransactionReceipts, RetrieveTransactions};
+    use crate::blockchain::fee_strategy::FeeStrategyKind;
     use crate::bootstrapper::{Bootstrapper, RealUser};
     use crate::database::connection_wrapper::ConnectionWrapper;
     use crate::database::db_initializer::test_utils::DbInitializerMock;
@@ -429,6 +448,8 @@ mod tests {
     use crate::sub_lib::configurator::NewPasswordMessage;
     use crate::sub_lib::cryptde::PlainData;
     use crate::sub_lib::dispatcher::{InboundClientData, StreamShutdownMsg};
+    use crate::sub_lib::exit_policy::ExitPolicy;
+    use crate::sub_lib::exit_service_policy::ExitServicePolicy;
     use crate::sub_lib::hopper::IncipientCoresPackage;
     use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
     use crate::sub_lib::neighborhood::RouteQueryMessage;
@@ -436,11 +457,11 @@ mod tests {
         DispatcherNodeQueryMessage, GossipFailure_0v1, NodeRecordMetadataMessage,
     };
     use crate::sub_lib::neighborhood::{NeighborhoodConfig, NodeQueryMessage};
-    use crate::sub_lib::neighborhood::{NeighborhoodMode, RemoveNeighborMessage};
+    use crate::sub_lib::neighborhood::{NeighborhoodMode, RemoveNeighborMessage, RoutingStrategy};
     use crate::sub_lib::node_addr::NodeAddr;
     use crate::sub_lib::peer_actors::StartMessage;
     use crate::sub_lib::proxy_client::{
-        ClientResponsePayload_0v1, DnsResolveFailure_0v1, InboundServerData,
+        ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1, InboundServerData,
     };
     use crate::sub_lib::proxy_server::{
         AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1,
@@ -448,7 +469,7 @@ mod tests {
     use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
     use crate::sub_lib::stream_handler_pool::DispatcherNodeQueryResponse;
     use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
-    use crate::sub_lib::ui_gateway::UiGatewayConfig;
+    use crate::sub_lib::ui_gateway::{UiGatewayConfig, UiSecurity};
     use crate::test_utils::recorder::Recorder;
     use crate::test_utils::recorder::Recording;
     use crate::test_utils::{alias_cryptde, rate_pack};
@@ -517,6 +538,7 @@ mod tests {
             alias_cryptde: &'a dyn CryptDE,
             is_decentralized: bool,
             consuming_wallet_balance: Option<i64>,
+            socks_port_opt: Option<u16>,
         ) -> ProxyServerSubs {
             self.parameters
                 .proxy_server_params
@@ -527,6 +549,7 @@ mod tests {
                     alias_cryptde,
                     is_decentralized,
                     consuming_wallet_balance,
+                    socks_port_opt,
                 ));
             let addr: Addr<Recorder> = ActorFactoryMock::start_recorder(&self.proxy_server);
             ProxyServerSubs {
@@ -538,6 +561,9 @@ mod tests {
                 dns_failure_from_hopper: addr
                     .clone()
                     .recipient::<ExpiredCoresPackage<DnsResolveFailure_0v1>>(),
+                exit_refusal_from_hopper: addr
+                    .clone()
+                    .recipient::<ExpiredCoresPackage<ExitRefusal_0v1>>(),
                 add_return_route: recipient!(addr, AddReturnRouteMessage),
                 add_route: recipient!(addr, AddRouteMessage),
                 stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
@@ -622,6 +648,7 @@ mod tests {
                     .recipient::<ReportExitServiceConsumedMessage>(),
                 report_new_payments: recipient!(addr, ReceivedPayments),
                 report_sent_payments: recipient!(addr, SentPayments),
+                report_transaction_receipts: recipient!(addr, ReportTransactionReceipts),
                 ui_message_sub: addr.clone().recipient::<NodeFromUiMessage>(),
             }
         }
@@ -668,6 +695,7 @@ mod tests {
                     .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
                 inbound_server_data: recipient!(addr, InboundServerData),
                 dns_resolve_failed: recipient!(addr, DnsResolveFailure_0v1),
+                exit_refused: recipient!(addr, ExitRefusal_0v1),
             }
         }
 
@@ -686,6 +714,9 @@ mod tests {
                 bind: recipient!(addr, BindMessage),
                 report_accounts_payable: addr.clone().recipient::<ReportAccountsPayable>(),
                 retrieve_transactions: addr.clone().recipient::<RetrieveTransactions>(),
+                request_transaction_receipts: addr
+                    .clone()
+                    .recipient::<RequestTransactionReceipts>(),
                 ui_sub: addr.clone().recipient::<NodeFromUiMessage>(),
             }
         }
@@ -720,8 +751,17 @@ mod tests {
     #[derive(Clone)]
     struct Parameters<'a> {
         proxy_client_params: Arc<Mutex<Option<ProxyClientConfig>>>,
-        proxy_server_params:
-            Arc<Mutex<Option<(&'a dyn CryptDE, &'a dyn CryptDE, bool, Option<i64>)>>>,
+        proxy_server_params: Arc<
+            Mutex<
+                Option<(
+                    &'a dyn CryptDE,
+                    &'a dyn CryptDE,
+                    bool,
+                    Option<i64>,
+                    Option<u16>,
+                )>,
+            >,
+        >,
         hopper_params: Arc<Mutex<Option<HopperConfig>>>,
         neighborhood_params: Arc<Mutex<Option<(&'a dyn CryptDE, BootstrapperConfig)>>>,
         accountant_params: Arc<Mutex<Option<(BootstrapperConfig, PathBuf)>>>,
@@ -809,6 +849,10 @@ mod tests {
             blockchain_service_url: Some("http://λ:8545".to_string()),
             chain_id: DEFAULT_CHAIN_ID,
             gas_price: 1,
+            batch_payments: false,
+            fee_strategy: FeeStrategyKind::Fixed,
+            max_fee_per_gas_opt: None,
+            confirmations: 0,
         };
         let mut config = BootstrapperConfig::new();
         config.blockchain_bridge_config = bbconfig;
@@ -828,18 +872,25 @@ mod tests {
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             clandestine_discriminator_factories: Vec::new(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_security: UiSecurity::default(),
                 node_descriptor: String::from("uninitialized"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
                 blockchain_service_url: None,
                 chain_id: DEFAULT_CHAIN_ID,
                 gas_price: 1,
+                batch_payments: false,
+                fee_strategy: FeeStrategyKind::Fixed,
+                max_fee_per_gas_opt: None,
+                confirmations: 0,
             },
             port_configurations: HashMap::new(),
+            socks_port_opt: None,
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -855,6 +906,11 @@ mod tests {
                     rate_pack(100),
                 ),
             },
+            routing_strategy: RoutingStrategy::default(),
+            exit_policy: ExitPolicy::default(),
+            exit_service_policy: ExitServicePolicy::default(),
+            geoip_database_opt: None,
+            malefactor_ban_duration: Duration::from_secs(3600),
         };
         Bootstrapper::pub_initialize_cryptdes_for_testing(
             &Some(main_cryptde().clone()),
@@ -893,18 +949,25 @@ mod tests {
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             clandestine_discriminator_factories: Vec::new(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_security: UiSecurity::default(),
                 node_descriptor: String::from("NODE-DESCRIPTOR"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
                 blockchain_service_url: None,
                 chain_id: DEFAULT_CHAIN_ID,
                 gas_price: 1,
+                batch_payments: false,
+                fee_strategy: FeeStrategyKind::Fixed,
+                max_fee_per_gas_opt: None,
+                confirmations: 0,
             },
             port_configurations: HashMap::new(),
+            socks_port_opt: Some(1080),
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -916,6 +979,13 @@ mod tests {
             neighborhood_config: NeighborhoodConfig {
                 mode: NeighborhoodMode::ZeroHop,
             },
+            routing_strategy: RoutingStrategy::default(),
+            exit_policy: ExitPolicy::default(),
+            exit_service_policy: ExitServicePolicy::new(
+                ExitServicePolicy::parse_rules("reject *:22").unwrap(),
+            ),
+            geoip_database_opt: None,
+            malefactor_ban_duration: Duration::from_secs(3600),
         };
         let (tx, rx) = mpsc::channel();
         let system = System::new("MASQNode");
@@ -947,11 +1017,16 @@ mod tests {
         assert_eq!(proxy_client_config.exit_service_rate, 0);
         assert_eq!(proxy_client_config.exit_byte_rate, 0);
         assert_eq!(proxy_client_config.dns_servers, config.dns_servers);
+        assert_eq!(
+            proxy_client_config.exit_service_policy,
+            config.exit_service_policy
+        );
         let (
             actual_main_cryptde,
             actual_alias_cryptde,
             actual_is_decentralized,
             consuming_wallet_balance,
+            socks_port_opt,
         ) = Parameters::get(parameters.proxy_server_params);
         check_cryptde(actual_main_cryptde);
         check_cryptde(actual_alias_cryptde);
@@ -961,6 +1036,7 @@ mod tests {
         );
         assert_eq!(actual_is_decentralized, false);
         assert_eq!(consuming_wallet_balance, Some(0));
+        assert_eq!(socks_port_opt, Some(1080));
         let (cryptde, neighborhood_config) = Parameters::get(parameters.neighborhood_params);
         check_cryptde(cryptde);
         assert_eq!(
@@ -981,6 +1057,10 @@ mod tests {
                 blockchain_service_url: None,
                 chain_id: DEFAULT_CHAIN_ID,
                 gas_price: 1,
+                batch_payments: false,
+                fee_strategy: FeeStrategyKind::Fixed,
+                max_fee_per_gas_opt: None,
+                confirmations: 0,
             }
         );
         assert_eq!(
@@ -1003,18 +1083,25 @@ mod tests {
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             clandestine_discriminator_factories: Vec::new(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_security: UiSecurity::default(),
                 node_descriptor: String::from("NODE-DESCRIPTOR"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
                 blockchain_service_url: None,
                 chain_id: DEFAULT_CHAIN_ID,
                 gas_price: 1,
+                batch_payments: false,
+                fee_strategy: FeeStrategyKind::Fixed,
+                max_fee_per_gas_opt: None,
+                confirmations: 0,
             },
             port_configurations: HashMap::new(),
+            socks_port_opt: None,
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -1030,6 +1117,11 @@ mod tests {
                     rate_pack(100),
                 ),
             },
+            routing_strategy: RoutingStrategy::default(),
+            exit_policy: ExitPolicy::default(),
+            exit_service_policy: ExitServicePolicy::default(),
+            geoip_database_opt: None,
+            malefactor_ban_duration: Duration::from_secs(3600),
         };
         let (tx, _) = mpsc::channel();
         let system = System::new("MASQNode");
@@ -1044,7 +1136,8 @@ mod tests {
 
         System::current().stop();
         system.run();
-        let (_, _, _, consuming_wallet_balance) = Parameters::get(parameters.proxy_server_params);
+        let (_, _, _, consuming_wallet_balance, _) =
+            Parameters::get(parameters.proxy_server_params);
         assert_eq!(consuming_wallet_balance, None);
     }
 
@@ -1,8 +1,8 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
-use crate::accountant::payable_dao::Payment;
+use crate::accountant::payable_dao::{PayableAccount, Payment};
 use crate::blockchain::blockchain_interface::{
-    BlockchainError, BlockchainInterface, BlockchainResult, Transaction,
+    to_wei, BlockchainError, BlockchainInterface, BlockchainResult, Receipt, Transaction,
 };
 use crate::bootstrapper::BootstrapperConfig;
 use crate::db_config::persistent_configuration::PersistentConfiguration;
@@ -22,12 +22,61 @@ use masq_lib::crash_point::CrashPoint;
 use masq_lib::messages::{FromMessageBody, UiCrashRequest};
 use masq_lib::ui_gateway::NodeFromUiMessage;
 use std::convert::TryFrom;
+use web3::types::{H256, U256};
 
 pub const CRASH_KEY: &str = "BLOCKCHAINBRIDGE";
+// The most blocks one eth_getLogs query asks about; a blockchain service may insist on fewer
+const MAX_LOG_WINDOW: u64 = 10_000;
+// How many windows in a row must be scanned without complaint before the window is doubled again,
+// so that a blockchain service that was only briefly overloaded doesn't keep scans small for good
+const LOG_WINDOW_REGROWTH_SCANS: u32 = 10;
+
+// Hands out consecutive nonces for the transactions of one payment scan, so that the chain is asked
+// only once per scan. After a failed send it forgets what it knew and asks the chain again.
+#[derive(Debug, Default)]
+struct NonceTracker {
+    next_nonce_opt: Option<U256>,
+}
+
+impl NonceTracker {
+    fn reset(&mut self) {
+        self.next_nonce_opt = None
+    }
+
+    fn send<F>(
+        &mut self,
+        blockchain_interface: &dyn BlockchainInterface,
+        consuming_wallet: &Wallet,
+        send: F,
+    ) -> BlockchainResult<(U256, H256)>
+    where
+        F: FnOnce(U256) -> BlockchainResult<H256>,
+    {
+        let nonce = match self.next_nonce_opt {
+            Some(nonce) => nonce,
+            None => blockchain_interface.get_transaction_count(consuming_wallet)?,
+        };
+        match send(nonce) {
+            Ok(hash) => {
+                self.next_nonce_opt = Some(nonce + U256::one());
+                Ok((nonce, hash))
+            }
+            Err(e) => {
+                self.reset();
+                Err(e)
+            }
+        }
+    }
+}
 
 pub struct BlockchainBridge {
     consuming_wallet: Option<Wallet>,
     blockchain_interface: Box<dyn BlockchainInterface>,
+    nonce_tracker: NonceTracker,
+    batch_payments: bool,
+    confirmations: u64,
+    log_window: u64,
+    windows_since_shrink: u32,
     logger: Logger,
     persistent_config: Box<dyn PersistentConfiguration>,
     set_consuming_wallet_subs: Option<Vec<Recipient<SetConsumingWalletMessage>>>,
@@ -66,10 +115,23 @@ impl Handler<BindMessage> for BlockchainBridge {
 pub struct RetrieveTransactions {
     pub start_block: u64,
     pub recipient: Wallet,
+    // Blocks that recently credited payments, with the hashes they had then
+    pub credited_blocks: Vec<(u64, H256)>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RetrievedTransactions {
+    // Credited blocks that are no longer on the chain; their payments must be reversed
+    pub orphaned_blocks: Vec<u64>,
+    // Where the next scan should begin
+    pub new_start_block: u64,
+    pub transactions: Vec<Transaction>,
+    // The confirmed blocks didn't fit in one window; the next scan shouldn't wait
+    pub more_to_scan: bool,
 }
 
 impl Message for RetrieveTransactions {
-    type Result = Result<Vec<Transaction>, BlockchainError>;
+    type Result = Result<RetrievedTransactions, BlockchainError>;
 }
 
 impl Handler<RetrieveTransactions> for BlockchainBridge {
@@ -80,10 +142,37 @@ impl Handler<RetrieveTransactions> for BlockchainBridge {
         msg: RetrieveTransactions,
         _ctx: &mut Self::Context,
     ) -> <Self as Handler<RetrieveTransactions>>::Result {
-        MessageResult(
-            self.blockchain_interface
-                .retrieve_transactions(msg.start_block, &msg.recipient),
-        )
+        MessageResult(self.retrieve_transactions(msg))
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RequestTransactionReceipts {
+    pub pending_payments: Vec<Payment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionReceipts {
+    // One for each pending payment, in the same order; a failure to fetch one doesn't affect the others
+    pub receipts: Vec<Receipt>,
+    // How many transactions from the consuming wallet were in mined blocks before any receipt was
+    // fetched, or None if the blockchain service couldn't say
+    pub mined_nonce_count_opt: Option<U256>,
+}
+
+impl Message for RequestTransactionReceipts {
+    type Result = TransactionReceipts;
+}
+
+impl Handler<RequestTransactionReceipts> for BlockchainBridge {
+    type Result = MessageResult<RequestTransactionReceipts>;
+
+    fn handle(
+        &mut self,
+        msg: RequestTransactionReceipts,
+        _ctx: &mut Self::Context,
+    ) -> <Self as Handler<RequestTransactionReceipts>>::Result {
+        MessageResult(self.fetch_transaction_receipts(&msg.pending_payments))
     }
 }
 
@@ -95,39 +184,8 @@ impl Handler<ReportAccountsPayable> for BlockchainBridge {
         msg: ReportAccountsPayable,
         _ctx: &mut Self::Context,
     ) -> <Self as Handler<ReportAccountsPayable>>::Result {
-        MessageResult(match self.consuming_wallet.as_ref() {
-            Some(consuming_wallet) => Ok(msg
-                .accounts
-                .iter()
-                .map(|payable| {
-                    match self
-                        .blockchain_interface
-                        .get_transaction_count(&consuming_wallet)
-                    {
-                        Ok(nonce) => {
-                            match self.blockchain_interface.send_transaction(
-                                &consuming_wallet,
-                                &payable.wallet,
-                                u64::try_from(payable.balance).unwrap_or_else(|_| {
-                                    panic!("Lost payable amount precision: {}", payable.balance)
-                                }),
-                                nonce,
-                                self.persistent_config.gas_price().unwrap().unwrap(),
-                            ) {
-                                Ok(hash) => Ok(Payment::new(
-                                    payable.wallet.clone(),
-                                    u64::try_from(payable.balance).unwrap_or_else(|_| {
-                                        panic!("Lost payable amount precision: {}", payable.balance)
-                                    }),
-                                    hash,
-                                )),
-                                Err(e) => Err(e),
-                            }
-                        }
-                        Err(e) => Err(e),
-                    }
-                })
-                .collect::<Vec<BlockchainResult<Payment>>>()),
+        MessageResult(match self.consuming_wallet.clone() {
+            Some(consuming_wallet) => Ok(self.pay_accounts(&consuming_wallet, &msg.accounts)),
             None => Err(String::from("No consuming wallet specified")),
         })
     }
@@ -152,6 +210,11 @@ impl BlockchainBridge {
         BlockchainBridge {
             consuming_wallet: config.consuming_wallet.clone(),
             blockchain_interface,
+            nonce_tracker: NonceTracker::default(),
+            batch_payments: config.blockchain_bridge_config.batch_payments,
+            confirmations: config.blockchain_bridge_config.confirmations,
+            log_window: MAX_LOG_WINDOW,
+            windows_since_shrink: 0,
             logger: Logger::new("BlockchainBridge"),
             persistent_config,
             set_consuming_wallet_subs: None,
@@ -164,9 +227,287 @@ impl BlockchainBridge {
             bind: recipient!(addr, BindMessage),
             report_accounts_payable: recipient!(addr, ReportAccountsPayable),
             retrieve_transactions: recipient!(addr, RetrieveTransactions),
+            request_transaction_receipts: recipient!(addr, RequestTransactionReceipts),
             ui_sub: recipient!(addr, NodeFromUiMessage),
         }
     }
+
+    fn retrieve_transactions(
+        &mut self,
+        msg: RetrieveTransactions,
+    ) -> Result<RetrievedTransactions, BlockchainError> {
+        let orphaned_blocks = self.find_orphaned_blocks(&msg.credited_blocks)?;
+        let start_block = orphaned_blocks
+            .iter()
+            .fold(msg.start_block, |min, block| min.min(*block));
+        let latest_block = self.blockchain_interface.get_block_number()?;
+        // Blocks this close to the head of the chain may yet be orphaned; leave them for a later scan
+        let end_block = match latest_block.checked_sub(self.confirmations) {
+            Some(end_block) if end_block >= start_block => end_block,
+            _ => {
+                debug!(
+                    self.logger,
+                    "Latest block is {}; nothing from block {} has {} confirmations yet",
+                    latest_block,
+                    start_block,
+                    self.confirmations
+                );
+                return Ok(RetrievedTransactions {
+                    orphaned_blocks,
+                    new_start_block: start_block,
+                    transactions: vec![],
+                    more_to_scan: false,
+                });
+            }
+        };
+        let (window_end, transactions) =
+            self.retrieve_window(start_block, end_block, &msg.recipient)?;
+        if window_end < end_block {
+            debug!(
+                self.logger,
+                "Scanned blocks {} to {}; {} confirmed blocks remain",
+                start_block,
+                window_end,
+                end_block - window_end
+            );
+        }
+        Ok(RetrievedTransactions {
+            orphaned_blocks,
+            new_start_block: window_end + 1,
+            transactions,
+            more_to_scan: window_end < end_block,
+        })
+    }
+
+    // Retrieves transactions from as much of the range as fits in one window, halving the window
+    // for as long as the blockchain service complains that it's too large, and doubling it again
+    // once the service has been happy with it for a while
+    fn retrieve_window(
+        &mut self,
+        start_block: u64,
+        end_block: u64,
+        recipient: &Wallet,
+    ) -> Result<(u64, Vec<Transaction>), BlockchainError> {
+        loop {
+            let window_end = end_block.min(start_block + self.log_window - 1);
+            match self.blockchain_interface.retrieve_transactions(
+                start_block,
+                window_end,
+                recipient,
+            ) {
+                Ok(transactions) => {
+                    self.regrow_log_window();
+                    return Ok((window_end, transactions));
+                }
+                Err(BlockchainError::TooManyResults) if window_end > start_block => {
+                    self.log_window = ((window_end - start_block + 1) / 2).max(1);
+                    self.windows_since_shrink = 0;
+                    info!(
+                        self.logger,
+                        "Blockchain service refused blocks {} to {}; asking for {} blocks at a time",
+                        start_block,
+                        window_end,
+                        self.log_window
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn regrow_log_window(&mut self) {
+        if self.log_window >= MAX_LOG_WINDOW {
+            return;
+        }
+        self.windows_since_shrink += 1;
+        if self.windows_since_shrink >= LOG_WINDOW_REGROWTH_SCANS {
+            self.log_window = (self.log_window * 2).min(MAX_LOG_WINDOW);
+            self.windows_since_shrink = 0;
+            debug!(
+                self.logger,
+                "Asking the blockchain service for {} blocks at a time again", self.log_window
+            );
+        }
+    }
+
+    // Each block names its parent, so once a credited block is found still in place, every block
+    // below it is too. Checks from the newest credited block down and stops at the first that
+    // hasn't moved, which in the usual case where nothing was orphaned costs a single query.
+    fn find_orphaned_blocks(
+        &self,
+        credited_blocks: &[(u64, H256)],
+    ) -> Result<Vec<u64>, BlockchainError> {
+        let mut newest_first = credited_blocks.to_vec();
+        newest_first.sort_by(|a, b| b.0.cmp(&a.0));
+        let mut orphaned_blocks = vec![];
+        for (block_number, credited_hash) in newest_first {
+            match self.blockchain_interface.get_block_hash(block_number)? {
+                Some(hash) if hash == credited_hash => break,
+                hash_opt => {
+                    warning!(
+                        self.logger,
+                        "Block {} that credited payments as {:?} is now {:?}; its payments will be reversed",
+                        block_number,
+                        credited_hash,
+                        hash_opt
+                    );
+                    orphaned_blocks.push(block_number)
+                }
+            }
+        }
+        orphaned_blocks.reverse();
+        Ok(orphaned_blocks)
+    }
+
+    fn fetch_transaction_receipts(&self, pending_payments: &[Payment]) -> TransactionReceipts {
+        // The count comes first: a transaction mined after it was taken will show up as a receipt
+        let mined_nonce_count_opt = match self.consuming_wallet.as_ref() {
+            Some(consuming_wallet) => match self
+                .blockchain_interface
+                .get_mined_transaction_count(consuming_wallet)
+            {
+                Ok(count) => Some(count),
+                Err(e) => {
+                    warning!(
+                        self.logger,
+                        "Couldn't count mined transactions from {}: {}",
+                        consuming_wallet,
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        TransactionReceipts {
+            receipts: pending_payments
+                .iter()
+                .map(|payment| {
+                    self.blockchain_interface
+                        .get_transaction_receipt(payment.transaction)
+                })
+                .collect(),
+            mined_nonce_count_opt,
+        }
+    }
+
+    fn pay_accounts(
+        &mut self,
+        consuming_wallet: &Wallet,
+        accounts: &[PayableAccount],
+    ) -> Vec<BlockchainResult<Payment>> {
+        self.nonce_tracker.reset();
+        let gas_price = self.persistent_config.gas_price().unwrap().unwrap();
+        let batch_size = if self.batch_payments {
+            self.blockchain_interface.max_batch_size().max(1)
+        } else {
+            1
+        };
+        let mut results = vec![];
+        for batch in accounts.chunks(batch_size) {
+            if batch.len() > 1 && self.batch_is_affordable(consuming_wallet, batch) {
+                results.extend(self.pay_batch(consuming_wallet, batch, gas_price))
+            } else {
+                results.extend(
+                    batch
+                        .iter()
+                        .map(|payable| self.pay_single(consuming_wallet, payable, gas_price))
+                        .collect::<Vec<BlockchainResult<Payment>>>(),
+                )
+            }
+        }
+        results
+    }
+
+    fn batch_is_affordable(&self, consuming_wallet: &Wallet, batch: &[PayableAccount]) -> bool {
+        let total = batch.iter().fold(U256::zero(), |acc, payable| {
+            acc + to_wei(payable_amount(payable))
+        });
+        match self
+            .blockchain_interface
+            .get_batch_allowance(consuming_wallet)
+        {
+            Ok(allowance) if allowance >= total => true,
+            Ok(allowance) => {
+                warning!(
+                    self.logger,
+                    "Batch payment contract may move only {} wei from {}, but {} payables need {} wei; paying them one at a time",
+                    allowance,
+                    consuming_wallet,
+                    batch.len(),
+                    total
+                );
+                false
+            }
+            Err(e) => {
+                warning!(
+                    self.logger,
+                    "Couldn't check batch payment allowance for {}: {}; paying {} payables one at a time",
+                    consuming_wallet,
+                    e,
+                    batch.len()
+                );
+                false
+            }
+        }
+    }
+
+    fn pay_single(
+        &mut self,
+        consuming_wallet: &Wallet,
+        payable: &PayableAccount,
+        gas_price: u64,
+    ) -> BlockchainResult<Payment> {
+        let amount = payable_amount(payable);
+        let blockchain_interface = self.blockchain_interface.as_ref();
+        self.nonce_tracker
+            .send(blockchain_interface, consuming_wallet, |nonce| {
+                blockchain_interface.send_transaction(
+                    consuming_wallet,
+                    &payable.wallet,
+                    amount,
+                    nonce,
+                    gas_price,
+                )
+            })
+            .map(|(nonce, hash)| {
+                Payment::new(payable.wallet.clone(), amount, hash).with_nonce(nonce)
+            })
+    }
+
+    fn pay_batch(
+        &mut self,
+        consuming_wallet: &Wallet,
+        batch: &[PayableAccount],
+        gas_price: u64,
+    ) -> Vec<BlockchainResult<Payment>> {
+        let payments = batch
+            .iter()
+            .map(|payable| (payable.wallet.clone(), payable_amount(payable)))
+            .collect::<Vec<(Wallet, u64)>>();
+        let blockchain_interface = self.blockchain_interface.as_ref();
+        match self
+            .nonce_tracker
+            .send(blockchain_interface, consuming_wallet, |nonce| {
+                blockchain_interface.send_batch_transaction(
+                    consuming_wallet,
+                    &payments,
+                    nonce,
+                    gas_price,
+                )
+            }) {
+            Ok((nonce, hash)) => payments
+                .into_iter()
+                .map(|(wallet, amount)| Ok(Payment::new(wallet, amount, hash).with_nonce(nonce)))
+                .collect(),
+            Err(e) => payments.iter().map(|_| Err(e.clone())).collect(),
+        }
+    }
+}
+
+fn payable_amount(payable: &PayableAccount) -> u64 {
+    u64::try_from(payable.balance)
+        .unwrap_or_else(|_| panic!("Lost payable amount precision: {}", payable.balance))
 }
 
 #[cfg(test)]
@@ -175,9 +516,10 @@ mod tests {
     use crate::accountant::payable_dao::PayableAccount;
     use crate::blockchain::bip32::Bip32ECKeyPair;
     use crate::blockchain::blockchain_interface::{
-        contract_address, Balance, BlockchainError, BlockchainResult, Nonce, Transaction,
+        contract_address, Balance, BlockchainError, BlockchainResult, Nonce, Receipt, Transaction,
         Transactions,
     };
+    use crate::blockchain::test_utils::make_transaction_receipt;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
     use crate::test_utils::persistent_configuration_mock::PersistentConfigurationMock;
@@ -260,13 +602,25 @@ mod tests {
 
     #[derive(Debug, Default)]
     struct BlockchainInterfaceMock {
-        pub retrieve_transactions_parameters: Arc<Mutex<Vec<(u64, Wallet)>>>,
+        pub retrieve_transactions_parameters: Arc<Mutex<Vec<(u64, u64, Wallet)>>>,
         pub retrieve_transactions_results: RefCell<Vec<BlockchainResult<Vec<Transaction>>>>,
+        pub get_block_number_results: RefCell<Vec<BlockchainResult<u64>>>,
+        pub get_block_hash_parameters: Arc<Mutex<Vec<u64>>>,
+        pub get_block_hash_results: RefCell<Vec<BlockchainResult<Option<H256>>>>,
         pub send_transaction_parameters: Arc<Mutex<Vec<(Wallet, Wallet, u64, U256, u64)>>>,
         pub send_transaction_results: RefCell<Vec<BlockchainResult<H256>>>,
+        pub send_batch_transaction_parameters:
+            Arc<Mutex<Vec<(Wallet, Vec<(Wallet, u64)>, U256, u64)>>>,
+        pub send_batch_transaction_results: RefCell<Vec<BlockchainResult<H256>>>,
+        pub max_batch_size_results: RefCell<Vec<usize>>,
+        pub get_batch_allowance_results: RefCell<Vec<Balance>>,
         pub contract_address_results: RefCell<Vec<Address>>,
         pub get_transaction_count_parameters: Arc<Mutex<Vec<Wallet>>>,
         pub get_transaction_count_results: RefCell<Vec<BlockchainResult<U256>>>,
+        pub get_mined_transaction_count_parameters: Arc<Mutex<Vec<Wallet>>>,
+        pub get_mined_transaction_count_results: RefCell<Vec<BlockchainResult<U256>>>,
+        pub get_transaction_receipt_parameters: Arc<Mutex<Vec<H256>>>,
+        pub get_transaction_receipt_results: RefCell<Vec<Receipt>>,
     }
 
     impl BlockchainInterfaceMock {
@@ -278,11 +632,38 @@ mod tests {
             self
         }
 
+        fn get_block_number_result(self, result: BlockchainResult<u64>) -> Self {
+            self.get_block_number_results.borrow_mut().push(result);
+            self
+        }
+
+        fn get_block_hash_result(self, result: BlockchainResult<Option<H256>>) -> Self {
+            self.get_block_hash_results.borrow_mut().push(result);
+            self
+        }
+
         fn send_transaction_result(self, result: BlockchainResult<H256>) -> Self {
             self.send_transaction_results.borrow_mut().push(result);
             self
         }
 
+        fn send_batch_transaction_result(self, result: BlockchainResult<H256>) -> Self {
+            self.send_batch_transaction_results
+                .borrow_mut()
+                .push(result);
+            self
+        }
+
+        fn max_batch_size_result(self, result: usize) -> Self {
+            self.max_batch_size_results.borrow_mut().push(result);
+            self
+        }
+
+        fn get_batch_allowance_result(self, result: Balance) -> Self {
+            self.get_batch_allowance_results.borrow_mut().push(result);
+            self
+        }
+
         fn contract_address_result(self, address: Address) -> Self {
             self.contract_address_results.borrow_mut().push(address);
             self
@@ -292,6 +673,20 @@ mod tests {
             self.get_transaction_count_results.borrow_mut().push(result);
             self
         }
+
+        fn get_mined_transaction_count_result(self, result: BlockchainResult<U256>) -> Self {
+            self.get_mined_transaction_count_results
+                .borrow_mut()
+                .push(result);
+            self
+        }
+
+        fn get_transaction_receipt_result(self, result: Receipt) -> Self {
+            self.get_transaction_receipt_results
+                .borrow_mut()
+                .push(result);
+            self
+        }
     }
 
     impl BlockchainInterface for BlockchainInterfaceMock {
@@ -299,12 +694,30 @@ mod tests {
             self.contract_address_results.borrow_mut().remove(0)
         }
 
-        fn retrieve_transactions(&self, start_block: u64, recipient: &Wallet) -> Transactions {
-            self.retrieve_transactions_parameters
+        fn retrieve_transactions(
+            &self,
+            start_block: u64,
+            end_block: u64,
+            recipient: &Wallet,
+        ) -> Transactions {
+            self.retrieve_transactions_parameters.lock().unwrap().push((
+                start_block,
+                end_block,
+                recipient.clone(),
+            ));
+            self.retrieve_transactions_results.borrow_mut().remove(0)
+        }
+
+        fn get_block_number(&self) -> BlockchainResult<u64> {
+            self.get_block_number_results.borrow_mut().remove(0)
+        }
+
+        fn get_block_hash(&self, block_number: u64) -> BlockchainResult<Option<H256>> {
+            self.get_block_hash_parameters
                 .lock()
                 .unwrap()
-                .push((start_block, recipient.clone()));
-            self.retrieve_transactions_results.borrow_mut().remove(0)
+                .push(block_number);
+            self.get_block_hash_results.borrow_mut().remove(0)
         }
 
         fn send_transaction(
@@ -325,6 +738,33 @@ mod tests {
             self.send_transaction_results.borrow_mut().remove(0)
         }
 
+        fn send_batch_transaction(
+            &self,
+            consuming_wallet: &Wallet,
+            payments: &[(Wallet, u64)],
+            nonce: U256,
+            gas_price: u64,
+        ) -> BlockchainResult<H256> {
+            self.send_batch_transaction_parameters
+                .lock()
+                .unwrap()
+                .push((
+                    consuming_wallet.clone(),
+                    payments.to_vec(),
+                    nonce,
+                    gas_price,
+                ));
+            self.send_batch_transaction_results.borrow_mut().remove(0)
+        }
+
+        fn max_batch_size(&self) -> usize {
+            self.max_batch_size_results.borrow_mut().remove(0)
+        }
+
+        fn get_batch_allowance(&self, _owner: &Wallet) -> Balance {
+            self.get_batch_allowance_results.borrow_mut().remove(0)
+        }
+
         fn get_eth_balance(&self, _address: &Wallet) -> Balance {
             unimplemented!()
         }
@@ -340,6 +780,24 @@ mod tests {
                 .push(wallet.clone());
             self.get_transaction_count_results.borrow_mut().remove(0)
         }
+
+        fn get_mined_transaction_count(&self, wallet: &Wallet) -> Nonce {
+            self.get_mined_transaction_count_parameters
+                .lock()
+                .unwrap()
+                .push(wallet.clone());
+            self.get_mined_transaction_count_results
+                .borrow_mut()
+                .remove(0)
+        }
+
+        fn get_transaction_receipt(&self, hash: H256) -> Receipt {
+            self.get_transaction_receipt_parameters
+                .lock()
+                .unwrap()
+                .push(hash);
+            self.get_transaction_receipt_results.borrow_mut().remove(0)
+        }
     }
 
     #[test]
@@ -348,19 +806,22 @@ mod tests {
         let block_no = 37;
         let expected_results = vec![Transaction {
             block_number: 42u64,
+            block_hash: H256::from("block 42".keccak256()),
+            transaction_hash: H256::from("transaction 42".keccak256()),
             from: make_wallet("some_address"),
             gwei_amount: 21,
         }];
         let result = Ok(expected_results.clone());
         let wallet = make_wallet("smelly");
         let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_number_result(Ok(112))
             .retrieve_transactions_result(result)
             .contract_address_result(contract_address(DEFAULT_CHAIN_ID));
         let retrieve_transactions_parameters = blockchain_interface_mock
             .retrieve_transactions_parameters
             .clone();
         let subject = BlockchainBridge::new(
-            &bc_from_wallet(None),
+            &bc_with_confirmations(12),
             Box::new(blockchain_interface_mock),
             Box::new(PersistentConfigurationMock::default()),
         );
@@ -369,15 +830,444 @@ mod tests {
         let request = addr.send(RetrieveTransactions {
             start_block: block_no,
             recipient: wallet.clone(),
+            credited_blocks: vec![],
         });
         System::current().stop();
         system.run();
 
         let retrieve_transactions_parameters = retrieve_transactions_parameters.lock().unwrap();
-        assert_eq!((block_no, wallet), retrieve_transactions_parameters[0]);
+        assert_eq!((block_no, 100, wallet), retrieve_transactions_parameters[0]);
 
         let result = request.wait().unwrap().unwrap();
-        assert_eq!(expected_results, result);
+        assert_eq!(
+            result,
+            RetrievedTransactions {
+                orphaned_blocks: vec![],
+                new_start_block: 101,
+                transactions: expected_results,
+                more_to_scan: false,
+            }
+        );
+    }
+
+    #[test]
+    fn retrieve_transactions_waits_for_blocks_to_gather_enough_confirmations() {
+        let system =
+            System::new("retrieve_transactions_waits_for_blocks_to_gather_enough_confirmations");
+        let blockchain_interface_mock =
+            BlockchainInterfaceMock::default().get_block_number_result(Ok(48));
+        let retrieve_transactions_parameters = blockchain_interface_mock
+            .retrieve_transactions_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(12),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RetrieveTransactions {
+            start_block: 37,
+            recipient: make_wallet("smelly"),
+            credited_blocks: vec![],
+        });
+        System::current().stop();
+        system.run();
+
+        assert!(retrieve_transactions_parameters.lock().unwrap().is_empty());
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(
+            result,
+            RetrievedTransactions {
+                orphaned_blocks: vec![],
+                new_start_block: 37,
+                transactions: vec![],
+                more_to_scan: false,
+            }
+        );
+    }
+
+    #[test]
+    fn retrieve_transactions_rescans_from_orphaned_blocks() {
+        init_test_logging();
+        let system = System::new("retrieve_transactions_rescans_from_orphaned_blocks");
+        let hash_30 = H256::from("block 30".keccak256());
+        let hash_33 = H256::from("block 33".keccak256());
+        let hash_35 = H256::from("block 35".keccak256());
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_hash_result(Ok(None))
+            .get_block_hash_result(Ok(Some(H256::from("other 33".keccak256()))))
+            .get_block_hash_result(Ok(Some(hash_30)))
+            .get_block_number_result(Ok(60))
+            .retrieve_transactions_result(Ok(vec![]));
+        let get_block_hash_parameters = blockchain_interface_mock.get_block_hash_parameters.clone();
+        let retrieve_transactions_parameters = blockchain_interface_mock
+            .retrieve_transactions_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(12),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+        let wallet = make_wallet("smelly");
+
+        let request = addr.send(RetrieveTransactions {
+            start_block: 37,
+            recipient: wallet.clone(),
+            credited_blocks: vec![(30, hash_30), (33, hash_33), (35, hash_35)],
+        });
+        System::current().stop();
+        system.run();
+
+        assert_eq!(*get_block_hash_parameters.lock().unwrap(), vec![35, 33, 30]);
+        assert_eq!(
+            *retrieve_transactions_parameters.lock().unwrap(),
+            vec![(33, 48, wallet)]
+        );
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(
+            result,
+            RetrievedTransactions {
+                orphaned_blocks: vec![33, 35],
+                new_start_block: 49,
+                transactions: vec![],
+                more_to_scan: false,
+            }
+        );
+        TestLogHandler::new()
+            .exists_log_containing("WARN: BlockchainBridge: Block 33 that credited payments as");
+    }
+
+    #[test]
+    fn retrieve_transactions_checks_only_the_newest_credited_block_when_it_hasnt_moved() {
+        let system = System::new(
+            "retrieve_transactions_checks_only_the_newest_credited_block_when_it_hasnt_moved",
+        );
+        let hash_30 = H256::from("block 30".keccak256());
+        let hash_33 = H256::from("block 33".keccak256());
+        let hash_35 = H256::from("block 35".keccak256());
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_hash_result(Ok(Some(hash_35)))
+            .get_block_number_result(Ok(60))
+            .retrieve_transactions_result(Ok(vec![]));
+        let get_block_hash_parameters = blockchain_interface_mock.get_block_hash_parameters.clone();
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(12),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RetrieveTransactions {
+            start_block: 37,
+            recipient: make_wallet("smelly"),
+            credited_blocks: vec![(30, hash_30), (33, hash_33), (35, hash_35)],
+        });
+        System::current().stop();
+        system.run();
+
+        assert_eq!(*get_block_hash_parameters.lock().unwrap(), vec![35]);
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(result.orphaned_blocks, Vec::<u64>::new());
+        assert_eq!(result.new_start_block, 49);
+    }
+
+    #[test]
+    fn retrieve_transactions_scans_one_window_at_a_time_and_shrinks_it_when_told_to() {
+        init_test_logging();
+        let system = System::new(
+            "retrieve_transactions_scans_one_window_at_a_time_and_shrinks_it_when_told_to",
+        );
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_number_result(Ok(100_000))
+            .retrieve_transactions_result(Err(BlockchainError::TooManyResults))
+            .retrieve_transactions_result(Err(BlockchainError::TooManyResults))
+            .retrieve_transactions_result(Ok(vec![]))
+            .get_block_number_result(Ok(100_000))
+            .retrieve_transactions_result(Ok(vec![]));
+        let retrieve_transactions_parameters = blockchain_interface_mock
+            .retrieve_transactions_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(0),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+        let wallet = make_wallet("smelly");
+
+        let first_request = addr.send(RetrieveTransactions {
+            start_block: 1,
+            recipient: wallet.clone(),
+            credited_blocks: vec![],
+        });
+        let second_request = addr.send(RetrieveTransactions {
+            start_block: 2_501,
+            recipient: wallet.clone(),
+            credited_blocks: vec![],
+        });
+        System::current().stop();
+        system.run();
+
+        assert_eq!(
+            *retrieve_transactions_parameters.lock().unwrap(),
+            vec![
+                (1, 10_000, wallet.clone()),
+                (1, 5_000, wallet.clone()),
+                (1, 2_500, wallet.clone()),
+                (2_501, 5_000, wallet),
+            ]
+        );
+        let first_result = first_request.wait().unwrap().unwrap();
+        assert_eq!(first_result.new_start_block, 2_501);
+        assert!(first_result.more_to_scan);
+        let second_result = second_request.wait().unwrap().unwrap();
+        assert_eq!(second_result.new_start_block, 5_001);
+        assert!(second_result.more_to_scan);
+        TestLogHandler::new().exists_log_containing(
+            "INFO: BlockchainBridge: Blockchain service refused blocks 1 to 5000; asking for 2500 blocks at a time",
+        );
+    }
+
+    #[test]
+    fn retrieve_window_regrows_the_window_after_enough_successful_scans() {
+        let blockchain_interface_mock = (0..=LOG_WINDOW_REGROWTH_SCANS)
+            .fold(BlockchainInterfaceMock::default(), |mock, _| {
+                mock.retrieve_transactions_result(Ok(vec![]))
+            });
+        let retrieve_transactions_parameters = blockchain_interface_mock
+            .retrieve_transactions_parameters
+            .clone();
+        let mut subject = BlockchainBridge::new(
+            &bc_with_confirmations(0),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        subject.log_window = 2_500;
+        let wallet = make_wallet("smelly");
+
+        (0..=LOG_WINDOW_REGROWTH_SCANS).for_each(|_| {
+            subject.retrieve_window(1, 100_000, &wallet).unwrap();
+        });
+
+        let parameters = retrieve_transactions_parameters.lock().unwrap();
+        assert_eq!(parameters.len(), LOG_WINDOW_REGROWTH_SCANS as usize + 1);
+        assert!(parameters[..LOG_WINDOW_REGROWTH_SCANS as usize]
+            .iter()
+            .all(|(start, end, _)| (*start, *end) == (1, 2_500)));
+        assert_eq!(
+            parameters[LOG_WINDOW_REGROWTH_SCANS as usize],
+            (1, 5_000, wallet)
+        );
+        assert_eq!(subject.log_window, 5_000);
+    }
+
+    #[test]
+    fn retrieve_transactions_gives_up_when_a_single_block_has_too_many_results() {
+        let system =
+            System::new("retrieve_transactions_gives_up_when_a_single_block_has_too_many_results");
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_number_result(Ok(38))
+            .retrieve_transactions_result(Err(BlockchainError::TooManyResults))
+            .retrieve_transactions_result(Err(BlockchainError::TooManyResults));
+        let retrieve_transactions_parameters = blockchain_interface_mock
+            .retrieve_transactions_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(0),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+        let wallet = make_wallet("smelly");
+
+        let request = addr.send(RetrieveTransactions {
+            start_block: 37,
+            recipient: wallet.clone(),
+            credited_blocks: vec![],
+        });
+        System::current().stop();
+        system.run();
+
+        assert_eq!(
+            *retrieve_transactions_parameters.lock().unwrap(),
+            vec![(37, 38, wallet.clone()), (37, 37, wallet)]
+        );
+        let result = request.wait().unwrap();
+        assert_eq!(result, Err(BlockchainError::TooManyResults));
+    }
+
+    #[test]
+    fn retrieve_transactions_fails_if_block_hashes_cannot_be_checked() {
+        let system = System::new("retrieve_transactions_fails_if_block_hashes_cannot_be_checked");
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_block_hash_result(Err(BlockchainError::QueryFailed));
+        let subject = BlockchainBridge::new(
+            &bc_with_confirmations(12),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RetrieveTransactions {
+            start_block: 37,
+            recipient: make_wallet("smelly"),
+            credited_blocks: vec![(30, H256::from("block 30".keccak256()))],
+        });
+        System::current().stop();
+        system.run();
+
+        let result = request.wait().unwrap();
+        assert_eq!(result, Err(BlockchainError::QueryFailed));
+    }
+
+    #[test]
+    fn request_transaction_receipts_fetches_a_receipt_for_each_pending_payment() {
+        let system =
+            System::new("request_transaction_receipts_fetches_a_receipt_for_each_pending_payment");
+        let receipt =
+            make_transaction_receipt(H256::from("sometransactionhash".keccak256()), Some(1));
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_transaction_receipt_result(Ok(Some(receipt.clone())))
+            .get_transaction_receipt_result(Ok(None));
+        let get_transaction_receipt_parameters = blockchain_interface_mock
+            .get_transaction_receipt_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(None),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RequestTransactionReceipts {
+            pending_payments: vec![
+                Payment::new(
+                    make_wallet("blah"),
+                    42,
+                    H256::from("sometransactionhash".keccak256()),
+                ),
+                Payment::new(
+                    make_wallet("foo"),
+                    21,
+                    H256::from("someothertransactionhash".keccak256()),
+                ),
+            ],
+        });
+        System::current().stop();
+        system.run();
+
+        let result = request.wait().unwrap();
+        assert_eq!(
+            result,
+            TransactionReceipts {
+                receipts: vec![Ok(Some(receipt)), Ok(None)],
+                mined_nonce_count_opt: None,
+            }
+        );
+        assert_eq!(
+            *get_transaction_receipt_parameters.lock().unwrap(),
+            vec![
+                H256::from("sometransactionhash".keccak256()),
+                H256::from("someothertransactionhash".keccak256())
+            ]
+        );
+    }
+
+    #[test]
+    fn request_transaction_receipts_reports_a_blockchain_error_for_its_own_payment_only() {
+        let system = System::new(
+            "request_transaction_receipts_reports_a_blockchain_error_for_its_own_payment_only",
+        );
+        let consuming_wallet = make_paying_wallet(b"consuming");
+        let receipt =
+            make_transaction_receipt(H256::from("someothertransactionhash".keccak256()), Some(1));
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_mined_transaction_count_result(Ok(U256::from(5)))
+            .get_transaction_receipt_result(Err(BlockchainError::QueryFailed))
+            .get_transaction_receipt_result(Ok(Some(receipt.clone())));
+        let get_mined_transaction_count_parameters = blockchain_interface_mock
+            .get_mined_transaction_count_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(Some(consuming_wallet.clone())),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RequestTransactionReceipts {
+            pending_payments: vec![
+                Payment::new(
+                    make_wallet("blah"),
+                    42,
+                    H256::from("sometransactionhash".keccak256()),
+                ),
+                Payment::new(
+                    make_wallet("foo"),
+                    21,
+                    H256::from("someothertransactionhash".keccak256()),
+                ),
+            ],
+        });
+        System::current().stop();
+        system.run();
+
+        let result = request.wait().unwrap();
+        assert_eq!(
+            result,
+            TransactionReceipts {
+                receipts: vec![Err(BlockchainError::QueryFailed), Ok(Some(receipt))],
+                mined_nonce_count_opt: Some(U256::from(5)),
+            }
+        );
+        assert_eq!(
+            *get_mined_transaction_count_parameters.lock().unwrap(),
+            vec![consuming_wallet]
+        );
+    }
+
+    #[test]
+    fn request_transaction_receipts_fetches_receipts_even_if_the_mined_transactions_cant_be_counted(
+    ) {
+        init_test_logging();
+        let system = System::new(
+            "request_transaction_receipts_fetches_receipts_even_if_the_mined_transactions_cant_be_counted",
+        );
+        let consuming_wallet = make_paying_wallet(b"consuming");
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_mined_transaction_count_result(Err(BlockchainError::QueryFailed))
+            .get_transaction_receipt_result(Ok(None));
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(Some(consuming_wallet.clone())),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default()),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(RequestTransactionReceipts {
+            pending_payments: vec![Payment::new(
+                make_wallet("blah"),
+                42,
+                H256::from("sometransactionhash".keccak256()),
+            )],
+        });
+        System::current().stop();
+        system.run();
+
+        let result = request.wait().unwrap();
+        assert_eq!(
+            result,
+            TransactionReceipts {
+                receipts: vec![Ok(None)],
+                mined_nonce_count_opt: None,
+            }
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: BlockchainBridge: Couldn't count mined transactions from {}: ",
+            consuming_wallet
+        ));
     }
 
     #[test]
@@ -387,7 +1277,6 @@ mod tests {
 
         let blockchain_interface_mock = BlockchainInterfaceMock::default()
             .get_transaction_count_result(Ok(U256::from(1)))
-            .get_transaction_count_result(Ok(U256::from(2)))
             .send_transaction_result(Ok(H256::from("sometransactionhash".keccak256())))
             .send_transaction_result(Ok(H256::from("someothertransactionhash".keccak256())))
             .contract_address_result(contract_address(DEFAULT_CHAIN_ID));
@@ -454,7 +1343,8 @@ mod tests {
             make_wallet("blah"),
             42,
             H256::from("sometransactionhash".keccak256()),
-        );
+        )
+        .with_nonce(U256::from(1));
 
         if let Ok(zero) = result.clone().get(0).unwrap().clone() {
             assert!(
@@ -478,7 +1368,8 @@ mod tests {
             make_wallet("foo"),
             21,
             H256::from("someothertransactionhash".keccak256()),
-        );
+        )
+        .with_nonce(U256::from(2));
 
         if let Ok(one) = result.clone().get(1).unwrap().clone() {
             assert!(
@@ -501,13 +1392,298 @@ mod tests {
         assert_eq!(result[1], Ok(expected_payment_1));
 
         assert_eq!(
-            transaction_count_parameters.lock().unwrap()[0],
-            consuming_wallet.clone(),
+            *transaction_count_parameters.lock().unwrap(),
+            vec![consuming_wallet.clone()],
+        );
+    }
+
+    fn make_payable(name: &str, balance: i64) -> PayableAccount {
+        PayableAccount {
+            wallet: make_wallet(name),
+            balance,
+            last_paid_timestamp: SystemTime::now(),
+            pending_payment_transaction: None,
+        }
+    }
+
+    fn payment_summary(
+        result: &BlockchainResult<Payment>,
+    ) -> BlockchainResult<(Wallet, u64, H256)> {
+        result
+            .as_ref()
+            .map(|payment| (payment.to.clone(), payment.amount, payment.transaction))
+            .map_err(|e| e.clone())
+    }
+
+    fn nonces_sent(
+        send_parameters: &Arc<Mutex<Vec<(Wallet, Wallet, u64, U256, u64)>>>,
+    ) -> Vec<U256> {
+        send_parameters
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, _, _, nonce, _)| *nonce)
+            .collect()
+    }
+
+    #[test]
+    fn report_accounts_payable_asks_the_chain_for_a_nonce_again_after_a_failed_send() {
+        let system = System::new(
+            "report_accounts_payable_asks_the_chain_for_a_nonce_again_after_a_failed_send",
+        );
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_transaction_count_result(Ok(U256::from(5)))
+            .get_transaction_count_result(Ok(U256::from(9)))
+            .send_transaction_result(Ok(H256::from("first".keccak256())))
+            .send_transaction_result(Err(BlockchainError::TransactionFailed(
+                "nonce too low".to_string(),
+            )))
+            .send_transaction_result(Ok(H256::from("third".keccak256())));
+        let send_parameters = blockchain_interface_mock
+            .send_transaction_parameters
+            .clone();
+        let transaction_count_parameters = blockchain_interface_mock
+            .get_transaction_count_parameters
+            .clone();
+        let consuming_wallet = make_paying_wallet(b"somewallet");
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(Some(consuming_wallet.clone())),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(ReportAccountsPayable {
+            accounts: vec![
+                make_payable("first", 10),
+                make_payable("second", 20),
+                make_payable("third", 30),
+            ],
+        });
+
+        System::current().stop();
+        system.run();
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(
+            result.iter().map(payment_summary).collect::<Vec<_>>(),
+            vec![
+                Ok((make_wallet("first"), 10, H256::from("first".keccak256()))),
+                Err(BlockchainError::TransactionFailed(
+                    "nonce too low".to_string()
+                )),
+                Ok((make_wallet("third"), 30, H256::from("third".keccak256()))),
+            ]
+        );
+        assert_eq!(
+            nonces_sent(&send_parameters),
+            vec![U256::from(5), U256::from(6), U256::from(9)]
+        );
+        assert_eq!(transaction_count_parameters.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn report_accounts_payable_retries_the_nonce_query_for_the_next_payable_if_it_fails() {
+        let system = System::new(
+            "report_accounts_payable_retries_the_nonce_query_for_the_next_payable_if_it_fails",
+        );
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_transaction_count_result(Err(BlockchainError::QueryFailed))
+            .get_transaction_count_result(Ok(U256::from(3)))
+            .send_transaction_result(Ok(H256::from("second".keccak256())));
+        let send_parameters = blockchain_interface_mock
+            .send_transaction_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(Some(make_paying_wallet(b"somewallet"))),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(ReportAccountsPayable {
+            accounts: vec![make_payable("first", 10), make_payable("second", 20)],
+        });
+
+        System::current().stop();
+        system.run();
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(
+            result.iter().map(payment_summary).collect::<Vec<_>>(),
+            vec![
+                Err(BlockchainError::QueryFailed),
+                Ok((make_wallet("second"), 20, H256::from("second".keccak256()))),
+            ]
+        );
+        assert_eq!(nonces_sent(&send_parameters), vec![U256::from(3)]);
+    }
+
+    #[test]
+    fn report_accounts_payable_asks_the_chain_for_a_fresh_nonce_for_every_scan() {
+        let system =
+            System::new("report_accounts_payable_asks_the_chain_for_a_fresh_nonce_for_every_scan");
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .get_transaction_count_result(Ok(U256::from(1)))
+            .get_transaction_count_result(Ok(U256::from(7)))
+            .send_transaction_result(Ok(H256::from("first".keccak256())))
+            .send_transaction_result(Ok(H256::from("second".keccak256())));
+        let send_parameters = blockchain_interface_mock
+            .send_transaction_parameters
+            .clone();
+        let subject = BlockchainBridge::new(
+            &bc_from_wallet(Some(make_paying_wallet(b"somewallet"))),
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let first_request = addr.send(ReportAccountsPayable {
+            accounts: vec![make_payable("first", 10)],
+        });
+        let second_request = addr.send(ReportAccountsPayable {
+            accounts: vec![make_payable("second", 20)],
+        });
+
+        System::current().stop();
+        system.run();
+        first_request.wait().unwrap().unwrap();
+        second_request.wait().unwrap().unwrap();
+        assert_eq!(
+            nonces_sent(&send_parameters),
+            vec![U256::from(1), U256::from(7)]
+        );
+    }
+
+    #[test]
+    fn report_accounts_payable_batches_payments_when_configured_to() {
+        let system = System::new("report_accounts_payable_batches_payments_when_configured_to");
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .max_batch_size_result(2)
+            .get_batch_allowance_result(Ok(to_wei(1000)))
+            .get_transaction_count_result(Ok(U256::from(4)))
+            .send_batch_transaction_result(Ok(H256::from("batch".keccak256())))
+            .send_transaction_result(Ok(H256::from("single".keccak256())));
+        let send_batch_parameters = blockchain_interface_mock
+            .send_batch_transaction_parameters
+            .clone();
+        let send_parameters = blockchain_interface_mock
+            .send_transaction_parameters
+            .clone();
+        let consuming_wallet = make_paying_wallet(b"somewallet");
+        let mut config = bc_from_wallet(Some(consuming_wallet.clone()));
+        config.blockchain_bridge_config.batch_payments = true;
+        let subject = BlockchainBridge::new(
+            &config,
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(ReportAccountsPayable {
+            accounts: vec![
+                make_payable("first", 10),
+                make_payable("second", 20),
+                make_payable("third", 30),
+            ],
+        });
+
+        System::current().stop();
+        system.run();
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(
+            result.iter().map(payment_summary).collect::<Vec<_>>(),
+            vec![
+                Ok((make_wallet("first"), 10, H256::from("batch".keccak256()))),
+                Ok((make_wallet("second"), 20, H256::from("batch".keccak256()))),
+                Ok((make_wallet("third"), 30, H256::from("single".keccak256()))),
+            ]
+        );
+        assert_eq!(
+            *send_batch_parameters.lock().unwrap(),
+            vec![(
+                consuming_wallet,
+                vec![(make_wallet("first"), 10), (make_wallet("second"), 20)],
+                U256::from(4),
+                3
+            )]
+        );
+        assert_eq!(nonces_sent(&send_parameters), vec![U256::from(5)]);
+    }
+
+    #[test]
+    fn report_accounts_payable_reports_a_failed_batch_for_every_payable_in_it() {
+        let system =
+            System::new("report_accounts_payable_reports_a_failed_batch_for_every_payable_in_it");
+        let error = BlockchainError::TransactionFailed("out of gas".to_string());
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .max_batch_size_result(30)
+            .get_batch_allowance_result(Ok(to_wei(1000)))
+            .get_transaction_count_result(Ok(U256::from(4)))
+            .send_batch_transaction_result(Err(error.clone()));
+        let mut config = bc_from_wallet(Some(make_paying_wallet(b"somewallet")));
+        config.blockchain_bridge_config.batch_payments = true;
+        let subject = BlockchainBridge::new(
+            &config,
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(ReportAccountsPayable {
+            accounts: vec![make_payable("first", 10), make_payable("second", 20)],
+        });
+
+        System::current().stop();
+        system.run();
+        let result = request.wait().unwrap().unwrap();
+        assert_eq!(result, vec![Err(error.clone()), Err(error)]);
+    }
+
+    #[test]
+    fn report_accounts_payable_pays_one_at_a_time_if_the_batch_contract_may_not_spend_enough() {
+        init_test_logging();
+        let system = System::new(
+            "report_accounts_payable_pays_one_at_a_time_if_the_batch_contract_may_not_spend_enough",
         );
+        let blockchain_interface_mock = BlockchainInterfaceMock::default()
+            .max_batch_size_result(30)
+            .get_batch_allowance_result(Ok(to_wei(29)))
+            .get_transaction_count_result(Ok(U256::from(4)))
+            .send_transaction_result(Ok(H256::from("first".keccak256())))
+            .send_transaction_result(Ok(H256::from("second".keccak256())));
+        let send_batch_parameters = blockchain_interface_mock
+            .send_batch_transaction_parameters
+            .clone();
+        let send_parameters = blockchain_interface_mock
+            .send_transaction_parameters
+            .clone();
+        let consuming_wallet = make_paying_wallet(b"somewallet");
+        let mut config = bc_from_wallet(Some(consuming_wallet.clone()));
+        config.blockchain_bridge_config.batch_payments = true;
+        let subject = BlockchainBridge::new(
+            &config,
+            Box::new(blockchain_interface_mock),
+            Box::new(PersistentConfigurationMock::default().gas_price_result(Ok(Some(3)))),
+        );
+        let addr: Addr<BlockchainBridge> = subject.start();
+
+        let request = addr.send(ReportAccountsPayable {
+            accounts: vec![make_payable("first", 10), make_payable("second", 20)],
+        });
+
+        System::current().stop();
+        system.run();
+        request.wait().unwrap().unwrap();
+        assert!(send_batch_parameters.lock().unwrap().is_empty());
         assert_eq!(
-            transaction_count_parameters.lock().unwrap()[1],
-            consuming_wallet.clone(),
+            nonces_sent(&send_parameters),
+            vec![U256::from(4), U256::from(5)]
         );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: BlockchainBridge: Batch payment contract may move only {} wei from {}, but 2 payables need {} wei; paying them one at a time",
+            to_wei(29),
+            consuming_wallet,
+            to_wei(30)
+        ));
     }
 
     #[test]
@@ -666,4 +1842,10 @@ mod tests {
         bc.consuming_wallet = consuming_wallet;
         bc
     }
+
+    fn bc_with_confirmations(confirmations: u64) -> BootstrapperConfig {
+        let mut config = bc_from_wallet(None);
+        config.blockchain_bridge_config.confirmations = confirmations;
+        config
+    }
 }
@@ -1,17 +1,21 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
-use crate::blockchain::raw_transaction::RawTransaction;
+use crate::blockchain::fee_strategy::{FeeStrategy, FixedFeeStrategy, TransactionFees};
+use crate::blockchain::raw_transaction::{Eip1559RawTransaction, RawTransaction};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::wallet::Wallet;
 use actix::Message;
 use futures::{future, Future};
 use masq_lib::constants::DEFAULT_CHAIN_NAME;
+use serde_json::json;
 use std::convert::{From, TryFrom, TryInto};
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use web3::contract::{Contract, Options};
 use web3::transports::EventLoopHandle;
-use web3::types::{Address, BlockNumber, Bytes, FilterBuilder, Log, H256, U256};
+use web3::types::{
+    Address, BlockId, BlockNumber, Bytes, FilterBuilder, Log, TransactionReceipt, H256, U256,
+};
 use web3::{Transport, Web3};
 
 // SHRD (Ropsten)
@@ -64,6 +68,18 @@ pub const CONTRACT_CREATION_BLOCK: [u64; 5] = [
     RINKEBY_TESTNET_CONTRACT_CREATION_BLOCK,
 ];
 
+// Disperse (https://disperse.app), which pays several recipients from one transaction. It's deployed
+// at the same address on every public chain we support; there's none on the dev chain.
+pub const DISPERSE_CONTRACT_ADDRESS: Address = Address {
+    0: [
+        0xD1, 0x52, 0xf5, 0x49, 0x54, 0x50, 0x93, 0x34, 0x7A, 0x16, 0x2D, 0xce, 0x21, 0x0e, 0x72,
+        0x93, 0xf1, 0x45, 0x21, 0x50,
+    ],
+};
+
+// Keeps the gas limit of a batch transaction comfortably below the block gas limit
+pub const MAX_PAYMENTS_PER_BATCH: usize = 30;
+
 pub const CHAIN_NAMES: [&str; 5] = ["", "mainnet", "dev", "ropsten", "rinkeby"];
 
 pub fn contract_address(chain_id: u8) -> Address {
@@ -73,6 +89,13 @@ pub fn contract_address(chain_id: u8) -> Address {
     }
 }
 
+pub fn batch_contract_address(chain_id: u8) -> Option<Address> {
+    match chain_id {
+        1u8 | 3u8 | 4u8 => Some(DISPERSE_CONTRACT_ADDRESS),
+        _ => None,
+    }
+}
+
 pub fn chain_name(chain_id: u8) -> &'static str {
     match chain_id {
         1u8 | 2u8 | 3u8 | 4u8 => CHAIN_NAMES[usize::from(chain_id)],
@@ -104,7 +127,7 @@ pub fn contract_creation_block_from_chain_id(chain_id: u8) -> u64 {
     }
 }
 
-pub const CONTRACT_ABI: &str = r#"[{"constant":true,"inputs":[{"name":"owner","type":"address"}],"name":"balanceOf","outputs":[{"name":"","type":"uint256"}],"payable":false,"stateMutability":"view","type":"function"},{"constant":false,"inputs":[{"name":"to","type":"address"},{"name":"value","type":"uint256"}],"name":"transfer","outputs":[{"name":"","type":"bool"}],"payable":false,"stateMutability":"nonpayable","type":"function"}]"#;
+pub const CONTRACT_ABI: &str = r#"[{"constant":true,"inputs":[{"name":"owner","type":"address"}],"name":"balanceOf","outputs":[{"name":"","type":"uint256"}],"payable":false,"stateMutability":"view","type":"function"},{"constant":false,"inputs":[{"name":"to","type":"address"},{"name":"value","type":"uint256"}],"name":"transfer","outputs":[{"name":"","type":"bool"}],"payable":false,"stateMutability":"nonpayable","type":"function"},{"constant":true,"inputs":[{"name":"owner","type":"address"},{"name":"spender","type":"address"}],"name":"allowance","outputs":[{"name":"","type":"uint256"}],"payable":false,"stateMutability":"view","type":"function"}]"#;
 
 const TRANSACTION_LITERAL: H256 = H256 {
     0: [
@@ -115,10 +138,32 @@ const TRANSACTION_LITERAL: H256 = H256 {
 };
 
 const TRANSFER_METHOD_ID: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+// JSON-RPC error code Infura uses for eth_getLogs queries that would return too many results.
+// Infura also uses it when it's rate-limiting, which a smaller block range won't help.
+const LIMIT_EXCEEDED_ERROR_CODE: i64 = -32005;
+// How other blockchain services word their refusal of an eth_getLogs block range too large for them
+const LOG_RANGE_ERROR_MESSAGES: [&str; 6] = [
+    "query returned more than",   // Geth, Erigon
+    "log response size exceeded", // Alchemy
+    "block range is too wide",    // Ankr, Polygon
+    "exceed maximum block range", // BNB Chain, NodeReal
+    "block range limit exceeded", // Chainstack
+    "eth_getlogs is limited to",  // QuickNode
+];
+
+// disperseTokenSimple(address,address[],uint256[]): unlike disperseToken, which gathers the whole
+// batch into the contract first, this has the token move each payment straight from the payer, so
+// the recipient sees the consuming wallet, not the contract, as the sender of its Transfer log.
+const DISPERSE_TOKEN_SIMPLE_METHOD_ID: [u8; 4] = [0x51, 0xba, 0x16, 0x2c];
+
+// Headroom added to eth_estimateGas, since the state a call runs against can change before it's mined
+const GAS_ESTIMATE_MARGIN_PERCENT: u64 = 20;
 
 #[derive(Clone, Debug, Eq, Message, PartialEq)]
 pub struct Transaction {
     pub block_number: u64,
+    pub block_hash: H256,
+    pub transaction_hash: H256,
     pub from: Wallet,
     pub gwei_amount: u64,
 }
@@ -139,6 +184,8 @@ pub enum BlockchainError {
     InvalidAddress,
     InvalidResponse,
     QueryFailed,
+    // The blockchain service won't return logs for a block range this large
+    TooManyResults,
     TransactionFailed(String),
 }
 
@@ -152,11 +199,23 @@ pub type BlockchainResult<T> = Result<T, BlockchainError>;
 pub type Balance = BlockchainResult<web3::types::U256>;
 pub type Nonce = BlockchainResult<web3::types::U256>;
 pub type Transactions = BlockchainResult<Vec<Transaction>>;
+pub type Receipt = BlockchainResult<Option<TransactionReceipt>>;
 
 pub trait BlockchainInterface {
     fn contract_address(&self) -> Address;
 
-    fn retrieve_transactions(&self, start_block: u64, recipient: &Wallet) -> Transactions;
+    // Both ends of the block range are inclusive
+    fn retrieve_transactions(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        recipient: &Wallet,
+    ) -> Transactions;
+
+    fn get_block_number(&self) -> BlockchainResult<u64>;
+
+    // None if the chain has no block with that number (any more)
+    fn get_block_hash(&self, block_number: u64) -> BlockchainResult<Option<H256>>;
 
     fn send_transaction(
         &self,
@@ -167,6 +226,20 @@ pub trait BlockchainInterface {
         gas_price: u64,
     ) -> BlockchainResult<H256>;
 
+    fn send_batch_transaction(
+        &self,
+        consuming_wallet: &Wallet,
+        payments: &[(Wallet, u64)],
+        nonce: U256,
+        gas_price: u64,
+    ) -> BlockchainResult<H256>;
+
+    // How many payments send_batch_transaction can carry; 1 means this chain can't batch
+    fn max_batch_size(&self) -> usize;
+
+    // How much of the consuming wallet's tokens the batch contract is allowed to move
+    fn get_batch_allowance(&self, owner: &Wallet) -> Balance;
+
     fn get_eth_balance(&self, address: &Wallet) -> Balance;
 
     fn get_token_balance(&self, address: &Wallet) -> Balance;
@@ -179,6 +252,11 @@ pub trait BlockchainInterface {
     }
 
     fn get_transaction_count(&self, address: &Wallet) -> Nonce;
+
+    // Counts only transactions in mined blocks, where get_transaction_count counts pending ones too
+    fn get_mined_transaction_count(&self, address: &Wallet) -> Nonce;
+
+    fn get_transaction_receipt(&self, hash: H256) -> Receipt;
 }
 
 // TODO: This probably should go away
@@ -207,12 +285,27 @@ impl BlockchainInterface for BlockchainInterfaceClandestine {
         contract_address(self.chain_id)
     }
 
-    fn retrieve_transactions(&self, _start_block: u64, _recipient: &Wallet) -> Transactions {
+    fn retrieve_transactions(
+        &self,
+        _start_block: u64,
+        _end_block: u64,
+        _recipient: &Wallet,
+    ) -> Transactions {
         let msg = "Can't retrieve transactions clandestinely yet".to_string();
         error!(self.logger, "{}", &msg);
         Err(BlockchainError::TransactionFailed(msg))
     }
 
+    fn get_block_number(&self) -> BlockchainResult<u64> {
+        error!(self.logger, "Can't get block number clandestinely yet");
+        Err(BlockchainError::QueryFailed)
+    }
+
+    fn get_block_hash(&self, _block_number: u64) -> BlockchainResult<Option<H256>> {
+        error!(self.logger, "Can't get block hash clandestinely yet");
+        Err(BlockchainError::QueryFailed)
+    }
+
     fn send_transaction(
         &self,
         _consuming_wallet: &Wallet,
@@ -226,6 +319,27 @@ impl BlockchainInterface for BlockchainInterfaceClandestine {
         Err(BlockchainError::TransactionFailed(msg))
     }
 
+    fn send_batch_transaction(
+        &self,
+        _consuming_wallet: &Wallet,
+        _payments: &[(Wallet, u64)],
+        _nonce: U256,
+        _gas_price: u64,
+    ) -> BlockchainResult<H256> {
+        let msg = "Can't send transactions clandestinely yet".to_string();
+        error!(self.logger, "{}", &msg);
+        Err(BlockchainError::TransactionFailed(msg))
+    }
+
+    fn max_batch_size(&self) -> usize {
+        1
+    }
+
+    fn get_batch_allowance(&self, _owner: &Wallet) -> Balance {
+        error!(self.logger, "Can't get batch allowance clandestinely yet",);
+        Ok(0.into())
+    }
+
     fn get_eth_balance(&self, _address: &Wallet) -> Balance {
         error!(self.logger, "Can't get eth balance clandestinely yet",);
         Ok(0.into())
@@ -239,6 +353,18 @@ impl BlockchainInterface for BlockchainInterfaceClandestine {
     fn get_transaction_count(&self, _address: &Wallet) -> Nonce {
         unimplemented!()
     }
+
+    fn get_mined_transaction_count(&self, _address: &Wallet) -> Nonce {
+        let msg = "Can't get transaction count clandestinely yet".to_string();
+        error!(self.logger, "{}", &msg);
+        Err(BlockchainError::QueryFailed)
+    }
+
+    fn get_transaction_receipt(&self, _hash: H256) -> Receipt {
+        let msg = "Can't get transaction receipt clandestinely yet".to_string();
+        error!(self.logger, "{}", &msg);
+        Err(BlockchainError::QueryFailed)
+    }
 }
 
 pub struct BlockchainInterfaceNonClandestine<T: Transport + Debug> {
@@ -248,6 +374,8 @@ pub struct BlockchainInterfaceNonClandestine<T: Transport + Debug> {
     _event_loop_handle: EventLoopHandle,
     web3: Web3<T>,
     contract: Contract<T>,
+    fee_strategy: Box<dyn FeeStrategy<T>>,
+    max_fee_per_gas_opt: Option<U256>,
 }
 
 const GWEI: U256 = U256([1_000_000_000u64, 0, 0, 0]);
@@ -256,6 +384,29 @@ pub fn to_gwei(wei: U256) -> Option<u64> {
     u64::try_from(wei / GWEI).ok()
 }
 
+fn log_query_error(error: web3::Error) -> BlockchainError {
+    let message = match &error {
+        web3::Error::Rpc(rpc_error) => rpc_error.message.to_lowercase(),
+        web3::Error::Transport(message) => message.to_lowercase(),
+        _ => return BlockchainError::QueryFailed,
+    };
+    let limit_exceeded = match &error {
+        web3::Error::Rpc(rpc_error) => {
+            rpc_error.code.code() == LIMIT_EXCEEDED_ERROR_CODE && !message.contains("rate limit")
+        }
+        _ => false,
+    };
+    if limit_exceeded
+        || LOG_RANGE_ERROR_MESSAGES
+            .iter()
+            .any(|known| message.contains(known))
+    {
+        BlockchainError::TooManyResults
+    } else {
+        BlockchainError::QueryFailed
+    }
+}
+
 pub fn to_wei(gwub: u64) -> U256 {
     let subgwei = U256::from(gwub);
     subgwei.full_mul(GWEI).try_into().expect("Internal Error")
@@ -269,11 +420,17 @@ where
         contract_address(self.chain_id)
     }
 
-    fn retrieve_transactions(&self, start_block: u64, recipient: &Wallet) -> Transactions {
+    fn retrieve_transactions(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        recipient: &Wallet,
+    ) -> Transactions {
         debug!(
             self.logger,
-            "Retrieving transactions from start block: {} for: {} chain_id: {} contract: {:#x}",
+            "Retrieving transactions from blocks {} to {} for: {} chain_id: {} contract: {:#x}",
             start_block,
+            end_block,
             recipient,
             self.chain_id,
             self.contract_address()
@@ -281,7 +438,7 @@ where
         let filter = FilterBuilder::default()
             .address(vec![self.contract_address()])
             .from_block(BlockNumber::Number(start_block))
-            .to_block(BlockNumber::Latest)
+            .to_block(BlockNumber::Number(end_block))
             .topics(
                 Some(vec![TRANSACTION_LITERAL]),
                 None,
@@ -304,30 +461,56 @@ where
                         } else {
                             let transactions = logs
                                 .iter()
-                                .filter_map(|log: &Log| match log.block_number {
-                                    Some(block_number) => {
-                                        let amount: U256 = U256::from(log.data.0.as_slice());
-                                        let gwei_amount = to_gwei(amount);
-                                        gwei_amount.map(|gwei_amount| Transaction {
-                                            block_number: u64::try_from(block_number)
-                                                .expect("Internal Error"), // TODO: back to testing for overflow
-                                            from: Wallet::from(log.topics[1]),
-                                            gwei_amount,
-                                        })
+                                .filter_map(|log: &Log| {
+                                    match (log.block_number, log.block_hash, log.transaction_hash) {
+                                        (
+                                            Some(block_number),
+                                            Some(block_hash),
+                                            Some(transaction_hash),
+                                        ) => {
+                                            let amount: U256 = U256::from(log.data.0.as_slice());
+                                            let gwei_amount = to_gwei(amount);
+                                            gwei_amount.map(|gwei_amount| Transaction {
+                                                block_number: u64::try_from(block_number)
+                                                    .expect("Internal Error"), // TODO: back to testing for overflow
+                                                block_hash,
+                                                transaction_hash,
+                                                from: Wallet::from(log.topics[1]),
+                                                gwei_amount,
+                                            })
+                                        }
+                                        _ => None,
                                     }
-                                    None => None,
                                 })
                                 .collect();
                             debug!(logger, "Retrieved transactions: {:?}", transactions);
                             Ok(transactions)
                         }
                     }
-                    Err(_) => Err(BlockchainError::QueryFailed),
+                    Err(e) => Err(log_query_error(e)),
                 })
             })
             .wait()
     }
 
+    fn get_block_number(&self) -> BlockchainResult<u64> {
+        self.web3
+            .eth()
+            .block_number()
+            .map(|block_number| block_number.as_u64())
+            .map_err(|_| BlockchainError::QueryFailed)
+            .wait()
+    }
+
+    fn get_block_hash(&self, block_number: u64) -> BlockchainResult<Option<H256>> {
+        self.web3
+            .eth()
+            .block(BlockId::Number(BlockNumber::Number(block_number)))
+            .map(|block_opt| block_opt.and_then(|block| block.hash))
+            .map_err(|_| BlockchainError::QueryFailed)
+            .wait()
+    }
+
     fn send_transaction(
         &self,
         consuming_wallet: &Wallet,
@@ -349,39 +532,72 @@ where
         data[0..4].copy_from_slice(&TRANSFER_METHOD_ID);
         data[16..36].copy_from_slice(&recipient.address().0[..]);
         to_wei(amount).to_big_endian(&mut data[36..68]);
-        let gas_limit = ethereum_types::U256::try_from(
-            data.iter()
-                .fold(55_000u64, |acc, v| acc + if v == &0u8 { 4 } else { 68 }),
+        self.send_contract_call(
+            consuming_wallet,
+            self.contract_address(),
+            data.to_vec(),
+            55_000,
+            nonce,
+            gas_price,
         )
-        .expect("Internal error");
+    }
 
-        let converted_nonce = serde_json::from_value::<ethereum_types::U256>(
-            serde_json::to_value(nonce).expect("Internal error"),
-        )
-        .expect("Internal error");
-        let gas_price = serde_json::from_value::<ethereum_types::U256>(
-            serde_json::to_value(to_wei(gas_price)).expect("Internal error"),
-        )
-        .expect("Internal error");
-
-        let tx = RawTransaction {
-            nonce: converted_nonce,
-            to: Some(ethereum_types::Address {
-                0: self.contract_address().0,
-            }),
-            value: ethereum_types::U256::zero(),
-            gas_price,
-            gas_limit,
-            data: data.to_vec(),
+    fn send_batch_transaction(
+        &self,
+        consuming_wallet: &Wallet,
+        payments: &[(Wallet, u64)],
+        nonce: U256,
+        gas_price: u64,
+    ) -> BlockchainResult<H256> {
+        let batch_contract = match batch_contract_address(self.chain_id) {
+            Some(address) => address,
+            None => {
+                return Err(BlockchainError::TransactionFailed(format!(
+                    "No batch payment contract on chain {}",
+                    chain_name(self.chain_id)
+                )))
+            }
         };
-        match self
-            .web3
-            .eth()
-            .send_raw_transaction(Bytes(tx.sign(&consuming_wallet, self.chain_id)))
-            .wait()
-        {
-            Ok(result) => Ok(result),
-            Err(e) => Err(BlockchainError::TransactionFailed(format!("{:?}", e))),
+        debug!(
+            self.logger,
+            "Sending batch transaction for {} payments from {}: (chain_id: {} contract: {:#x})",
+            payments.len(),
+            consuming_wallet,
+            self.chain_id,
+            batch_contract
+        );
+        let data = disperse_token_simple_data(self.contract_address(), payments);
+        self.send_contract_call(
+            consuming_wallet,
+            batch_contract,
+            data,
+            55_000 + 50_000 * payments.len() as u64,
+            nonce,
+            gas_price,
+        )
+    }
+
+    fn max_batch_size(&self) -> usize {
+        match batch_contract_address(self.chain_id) {
+            Some(_) => MAX_PAYMENTS_PER_BATCH,
+            None => 1,
+        }
+    }
+
+    fn get_batch_allowance(&self, owner: &Wallet) -> Balance {
+        match batch_contract_address(self.chain_id) {
+            Some(spender) => self
+                .contract
+                .query(
+                    "allowance",
+                    (owner.address(), spender),
+                    None,
+                    Options::with(|_| {}),
+                    None,
+                )
+                .map_err(|_| BlockchainError::QueryFailed)
+                .wait(),
+            None => Ok(0.into()),
         }
     }
 
@@ -413,6 +629,22 @@ where
             .map_err(|_| BlockchainError::QueryFailed)
             .wait()
     }
+
+    fn get_mined_transaction_count(&self, wallet: &Wallet) -> Nonce {
+        self.web3
+            .eth()
+            .transaction_count(wallet.address(), Some(BlockNumber::Latest))
+            .map_err(|_| BlockchainError::QueryFailed)
+            .wait()
+    }
+
+    fn get_transaction_receipt(&self, hash: H256) -> Receipt {
+        self.web3
+            .eth()
+            .transaction_receipt(hash)
+            .map_err(|_| BlockchainError::QueryFailed)
+            .wait()
+    }
 }
 
 impl<T> BlockchainInterfaceNonClandestine<T>
@@ -433,92 +665,171 @@ where
             _event_loop_handle: event_loop_handle,
             web3,
             contract,
+            fee_strategy: Box::new(FixedFeeStrategy {}),
+            max_fee_per_gas_opt: None,
         }
     }
+
+    pub fn with_fee_strategy(mut self, fee_strategy: Box<dyn FeeStrategy<T>>) -> Self {
+        self.fee_strategy = fee_strategy;
+        self
+    }
+
+    // max_fee_per_gas_opt is in Gwei, like the configured gas price
+    pub fn with_max_fee_per_gas(mut self, max_fee_per_gas_opt: Option<u64>) -> Self {
+        self.max_fee_per_gas_opt = max_fee_per_gas_opt.map(to_wei);
+        self
+    }
+
+    fn send_contract_call(
+        &self,
+        consuming_wallet: &Wallet,
+        to: Address,
+        data: Vec<u8>,
+        base_gas: u64,
+        nonce: U256,
+        gas_price: u64,
+    ) -> BlockchainResult<H256> {
+        let gas_limit = to_ethereum_u256(self.gas_limit(consuming_wallet, to, &data, base_gas));
+        let fees = self.fee_strategy.fees(&self.web3, gas_price);
+        let fees = match self.max_fee_per_gas_opt {
+            Some(max_fee_per_gas) => fees.capped_at(max_fee_per_gas),
+            None => fees,
+        };
+
+        let nonce = to_ethereum_u256(nonce);
+        let to = Some(ethereum_types::Address { 0: to.0 });
+        let value = ethereum_types::U256::zero();
+        let signed_tx = match fees {
+            TransactionFees::Legacy { gas_price } => RawTransaction {
+                nonce,
+                to,
+                value,
+                gas_price: to_ethereum_u256(gas_price),
+                gas_limit,
+                data,
+            }
+            .sign(&consuming_wallet, self.chain_id),
+            TransactionFees::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Eip1559RawTransaction {
+                nonce,
+                to,
+                value,
+                max_fee_per_gas: to_ethereum_u256(max_fee_per_gas),
+                max_priority_fee_per_gas: to_ethereum_u256(max_priority_fee_per_gas),
+                gas_limit,
+                data,
+            }
+            .sign(&consuming_wallet, self.chain_id),
+        };
+        match self
+            .web3
+            .eth()
+            .send_raw_transaction(Bytes(signed_tx))
+            .wait()
+        {
+            Ok(result) => Ok(result),
+            Err(e) => Err(BlockchainError::TransactionFailed(format!("{:?}", e))),
+        }
+    }
+
+    // Asks the blockchain how much gas the call will burn, with some headroom for the state to
+    // change before it's mined; if the blockchain won't say, falls back to a fixed limit computed
+    // from the size of the call.
+    fn gas_limit(
+        &self,
+        consuming_wallet: &Wallet,
+        to: Address,
+        data: &[u8],
+        base_gas: u64,
+    ) -> U256 {
+        let fixed_limit = U256::from(
+            data.iter()
+                .fold(base_gas, |acc, v| acc + if v == &0u8 { 4 } else { 68 }),
+        );
+        let request = json!({
+            "from": consuming_wallet.address(),
+            "to": to,
+            "data": Bytes(data.to_vec()),
+        });
+        match self
+            .web3
+            .transport()
+            .execute("eth_estimateGas", vec![request])
+            .wait()
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|response| {
+                serde_json::from_value::<U256>(response).map_err(|e| e.to_string())
+            }) {
+            Ok(estimate) => estimate * (100 + GAS_ESTIMATE_MARGIN_PERCENT) / 100,
+            Err(e) => {
+                warning!(
+                    self.logger,
+                    "Couldn't estimate gas ({}); using a limit of {}",
+                    e,
+                    fixed_limit
+                );
+                fixed_limit
+            }
+        }
+    }
+}
+
+fn to_ethereum_u256(value: U256) -> ethereum_types::U256 {
+    serde_json::from_value::<ethereum_types::U256>(
+        serde_json::to_value(value).expect("Internal error"),
+    )
+    .expect("Internal error")
+}
+
+fn disperse_token_simple_data(token: Address, payments: &[(Wallet, u64)]) -> Vec<u8> {
+    let count = payments.len();
+    let mut data = vec![0u8; 4 + 32 * (5 + 2 * count)];
+    data[0..4].copy_from_slice(&DISPERSE_TOKEN_SIMPLE_METHOD_ID);
+    let word = |index: usize| 4 + 32 * index;
+    data[word(0) + 12..word(1)].copy_from_slice(&token.0[..]);
+    let recipients_offset = 3;
+    let values_offset = recipients_offset + 1 + count;
+    U256::from(32 * recipients_offset).to_big_endian(&mut data[word(1)..word(2)]);
+    U256::from(32 * values_offset).to_big_endian(&mut data[word(2)..word(3)]);
+    U256::from(count)
+        .to_big_endian(&mut data[word(recipients_offset)..word(recipients_offset + 1)]);
+    U256::from(count).to_big_endian(&mut data[word(values_offset)..word(values_offset + 1)]);
+    payments
+        .iter()
+        .enumerate()
+        .for_each(|(index, (recipient, amount))| {
+            let recipient_word = word(recipients_offset + 1 + index);
+            data[recipient_word + 12..recipient_word + 32]
+                .copy_from_slice(&recipient.address().0[..]);
+            let value_word = word(values_offset + 1 + index);
+            to_wei(*amount).to_big_endian(&mut data[value_word..value_word + 32]);
+        });
+    data
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blockchain::fee_strategy::{make_fee_strategy, FeeStrategyKind};
+    use crate::blockchain::test_utils::TestTransport;
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils::{await_value, make_paying_wallet, make_wallet};
     use ethereum_types::BigEndianHash;
     use ethsign_crypto::Keccak256;
-    use jsonrpc_core as rpc;
     use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
     use masq_lib::utils::find_free_port;
+    use rustc_hex::FromHex;
     use serde_json::json;
     use serde_json::Value;
     use simple_server::Server;
-    use std::cell::RefCell;
-    use std::collections::VecDeque;
     use std::net::Ipv4Addr;
-    use std::rc::Rc;
     use std::str::FromStr;
     use std::sync::mpsc;
     use std::thread;
-    use web3::{transports::Http, Error, RequestId, Transport};
-
-    #[derive(Debug, Default, Clone)]
-    pub struct TestTransport {
-        asserted: usize,
-        requests: Rc<RefCell<Vec<(String, Vec<rpc::Value>)>>>,
-        responses: Rc<RefCell<VecDeque<rpc::Value>>>,
-    }
-
-    impl Transport for TestTransport {
-        type Out = web3::Result<rpc::Value>;
-
-        fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
-            let request = web3::helpers::build_request(1, method, params.clone());
-            self.requests.borrow_mut().push((method.into(), params));
-            (self.requests.borrow().len(), request)
-        }
-
-        fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
-            match self.responses.borrow_mut().pop_front() {
-                Some(response) => Box::new(futures::finished(response)),
-                None => {
-                    println!("Unexpected request (id: {:?}): {:?}", id, request);
-                    Box::new(futures::failed(Error::Unreachable))
-                }
-            }
-        }
-    }
-
-    impl TestTransport {
-        pub fn add_response(&mut self, value: rpc::Value) {
-            self.responses.borrow_mut().push_back(value);
-        }
-
-        pub fn assert_request(&mut self, method: &str, params: &[String]) {
-            let idx = self.asserted;
-            self.asserted += 1;
-
-            let (m, p) = self
-                .requests
-                .borrow()
-                .get(idx)
-                .expect("Expected result.")
-                .clone();
-            assert_eq!(&m, method);
-            let p: Vec<String> = p
-                .into_iter()
-                .map(|p| serde_json::to_string(&p).unwrap())
-                .collect();
-            assert_eq!(p, params);
-        }
-
-        pub fn assert_no_more_requests(&mut self) {
-            let requests = self.requests.borrow();
-            assert_eq!(
-                self.asserted,
-                requests.len(),
-                "Expected no more requests, got: {:?}",
-                &requests[self.asserted..]
-            );
-        }
-    }
+    use web3::transports::Http;
 
     fn make_fake_event_loop_handle() -> EventLoopHandle {
         Http::new("http://86.75.30.9").unwrap().0
@@ -549,6 +860,7 @@ mod tests {
         let result = subject
             .retrieve_transactions(
                 42,
+                4_974_200,
                 &Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap(),
             )
             .unwrap();
@@ -558,9 +870,19 @@ mod tests {
             format!("\"0x000000000000000000000000{}\"", &to[2..]),
             body["params"][0]["topics"][2].to_string(),
         );
+        assert_eq!(body["params"][0]["fromBlock"].to_string(), "\"0x2a\"");
+        assert_eq!(body["params"][0]["toBlock"].to_string(), "\"0x4be6f8\"");
         assert_eq!(
             vec![Transaction {
                 block_number: 4_974_179u64,
+                block_hash: H256::from_str(
+                    "1a24b9169cbaec3f6effa1f600b70c7ab9e8e86db44062b49132a4415d26732a"
+                )
+                .unwrap(),
+                transaction_hash: H256::from_str(
+                    "955cec6ac4f832911ab894ce16aa22c3003f46deff3f7165b32700d2f5ff0681"
+                )
+                .unwrap(),
                 from: Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap(),
                 gwei_amount: 4_503_599u64,
             }],
@@ -578,8 +900,11 @@ mod tests {
         let subject =
             BlockchainInterfaceNonClandestine::new(transport, event_loop_handle, DEFAULT_CHAIN_ID);
 
-        let result = subject
-            .retrieve_transactions(42, &Wallet::new("0x3f69f9efd4f2592fd70beecd9dce71c472fc"));
+        let result = subject.retrieve_transactions(
+            42,
+            100,
+            &Wallet::new("0x3f69f9efd4f2592fd70beecd9dce71c472fc"),
+        );
 
         assert_eq!(
             BlockchainError::InvalidAddress,
@@ -609,6 +934,7 @@ mod tests {
 
         let result = subject.retrieve_transactions(
             42,
+            4_974_200,
             &Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap(),
         );
 
@@ -641,6 +967,7 @@ mod tests {
 
         let result = subject.retrieve_transactions(
             42,
+            4_974_200,
             &Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap(),
         );
 
@@ -671,12 +998,71 @@ mod tests {
 
         let result = subject.retrieve_transactions(
             42,
+            4_974_200,
             &Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap(),
         );
 
         assert_eq!(Ok(vec![]), result);
     }
 
+    #[test]
+    fn blockchain_interface_non_clandestine_retrieve_transactions_reports_too_many_results_only_for_oversized_ranges(
+    ) {
+        let mut transport = TestTransport::default();
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32005),
+            message: "query returned more than 10000 results".to_string(),
+            data: None,
+        });
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range".to_string(),
+            data: None,
+        });
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "header not found".to_string(),
+            data: None,
+        });
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32005),
+            message: "daily request count exceeded, request rate limited".to_string(),
+            data: None,
+        });
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "request timed out".to_string(),
+            data: None,
+        });
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "exceeded the allowed number of requests per second".to_string(),
+            data: None,
+        });
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+        let wallet = Wallet::from_str("0x3f69f9efd4f2592fd70be8c32ecd9dce71c472fc").unwrap();
+
+        let results = (0..6)
+            .map(|_| subject.retrieve_transactions(42, 100_000, &wallet))
+            .collect::<Vec<Transactions>>();
+
+        assert_eq!(
+            results,
+            vec![
+                Err(BlockchainError::TooManyResults),
+                Err(BlockchainError::TooManyResults),
+                Err(BlockchainError::QueryFailed),
+                Err(BlockchainError::QueryFailed),
+                Err(BlockchainError::QueryFailed),
+                Err(BlockchainError::QueryFailed),
+            ]
+        );
+    }
+
     #[test]
     fn blockchain_interface_non_clandestine_can_retrieve_eth_balance_of_a_wallet() {
         let port = find_free_port();
@@ -880,7 +1266,11 @@ mod tests {
     #[test]
     fn blockchain_interface_non_clandestine_can_transfer_tokens() {
         let mut transport = TestTransport::default();
-
+        transport.add_error_response(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::MethodNotFound,
+            message: "the method eth_estimateGas does not exist".to_string(),
+            data: None,
+        });
         transport.add_response(json!(
             "0x0000000000000000000000000000000000000000000000000000000000000001"
         ));
@@ -899,11 +1289,127 @@ mod tests {
             2u64,
         );
 
+        transport.assert_request(
+            "eth_estimateGas",
+            &[String::from(
+                r#"{"data":"0xa9059cbb00000000000000000000000000000000000000000000000000626c61683132330000000000000000000000000000000000000000000000000000082f79cd9000","from":"0x5c361ba8d82fcf0e5538b2a823e9d457a2296725","to":"0x384dec25e03f94931767ce4c3556168468ba24c3"}"#,
+            )],
+        );
         transport.assert_request("eth_sendRawTransaction", &[String::from(r#""0xf8a801847735940082dbe894384dec25e03f94931767ce4c3556168468ba24c380b844a9059cbb00000000000000000000000000000000000000000000000000626c61683132330000000000000000000000000000000000000000000000000000082f79cd900029a0b8e83e714af8bf1685b496912ee4aeff7007ba0f4c29ae50f513bc71ce6a18f4a06a923088306b4ee9cbfcdc62c9b396385f9b1c380134bf046d6c9ae47dea6578""#)]);
         transport.assert_no_more_requests();
         assert_eq!(result, Ok(H256::from_uint(&U256::from(1))));
     }
 
+    #[test]
+    fn blockchain_interface_non_clandestine_uses_the_estimated_gas_with_headroom() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x5208"));
+        transport.add_response(json!(
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.send_transaction(
+            &make_paying_wallet(b"gdasgsa"),
+            &make_wallet("blah123"),
+            9000,
+            U256::from(1),
+            2u64,
+        );
+
+        assert_eq!(result, Ok(H256::from_uint(&U256::from(1))));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "eth_estimateGas".to_string());
+        assert_eq!(requests[1].0, "eth_sendRawTransaction".to_string());
+        let raw_transaction = requests[1].1[0].as_str().unwrap().to_string();
+        // nonce 1, gas price 2 Gwei, gas limit 21000 + 20%
+        assert!(
+            raw_transaction.contains("01847735940082627094"),
+            "{} doesn't have a gas limit of 25200",
+            raw_transaction
+        );
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_never_offers_more_than_the_max_fee_per_gas() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x5208"));
+        transport.add_response(json!(
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        )
+        .with_max_fee_per_gas(Some(1));
+
+        let result = subject.send_transaction(
+            &make_paying_wallet(b"gdasgsa"),
+            &make_wallet("blah123"),
+            9000,
+            U256::from(1),
+            2u64,
+        );
+
+        assert_eq!(result, Ok(H256::from_uint(&U256::from(1))));
+        let requests = transport.requests();
+        let raw_transaction = requests[1].1[0].as_str().unwrap().to_string();
+        // nonce 1, gas price 1 Gwei rather than the configured 2
+        assert!(
+            raw_transaction.contains("01843b9aca0082627094"),
+            "{} doesn't have a gas price of 1 Gwei",
+            raw_transaction
+        );
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_sends_eip1559_transaction_when_fee_strategy_says_so() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x5208"));
+        transport.add_response(json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]]
+        }));
+        transport.add_response(json!(
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        )
+        .with_fee_strategy(make_fee_strategy(FeeStrategyKind::Oracle));
+
+        let result = subject.send_transaction(
+            &make_paying_wallet(b"gdasgsa"),
+            &make_wallet("blah123"),
+            9000,
+            U256::from(1),
+            2u64,
+        );
+
+        assert_eq!(result, Ok(H256::from_uint(&U256::from(1))));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].0, "eth_estimateGas".to_string());
+        assert_eq!(requests[1].0, "eth_feeHistory".to_string());
+        assert_eq!(requests[2].0, "eth_sendRawTransaction".to_string());
+        let raw_transaction = requests[2].1[0].as_str().unwrap().to_string();
+        assert!(
+            raw_transaction.starts_with("0x02"),
+            "{} is not a type 2 transaction",
+            raw_transaction
+        );
+    }
+
     #[test]
     fn blockchain_interface_non_clandestine_can_fetch_nonce() {
         let mut transport = TestTransport::default();
@@ -931,6 +1437,135 @@ mod tests {
         assert_eq!(result, Ok(U256::from(1)));
     }
 
+    #[test]
+    fn blockchain_interface_non_clandestine_can_fetch_mined_transaction_count() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!(
+            "0x0000000000000000000000000000000000000000000000000000000000000003"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.get_mined_transaction_count(&make_paying_wallet(b"gdasgsa"));
+
+        transport.assert_request(
+            "eth_getTransactionCount",
+            &[
+                String::from(r#""0x5c361ba8d82fcf0e5538b2a823e9d457a2296725""#),
+                String::from(r#""latest""#),
+            ],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(result, Ok(U256::from(3)));
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_can_fetch_transaction_receipt() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!({
+            "transactionHash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+            "transactionIndex": "0x0",
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000002",
+            "blockNumber": "0x7",
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "contractAddress": null,
+            "logs": [],
+            "status": "0x1",
+            "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
+        }));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject
+            .get_transaction_receipt(H256::from_uint(&U256::from(1)))
+            .unwrap()
+            .unwrap();
+
+        transport.assert_request(
+            "eth_getTransactionReceipt",
+            &[String::from(
+                r#""0x0000000000000000000000000000000000000000000000000000000000000001""#,
+            )],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(result.transaction_hash, H256::from_uint(&U256::from(1)));
+        assert_eq!(result.block_number, Some(7.into()));
+        assert_eq!(result.status, Some(1.into()));
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_can_fetch_block_number() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x4be663"));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.get_block_number();
+
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, Ok(4_974_179));
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_returns_none_for_a_block_the_chain_does_not_have() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!(null));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.get_block_hash(4_974_179);
+
+        transport.assert_request(
+            "eth_getBlockByNumber",
+            &[String::from(r#""0x4be663""#), String::from("false")],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn blockchain_interface_clandestine_cannot_fetch_block_hashes() {
+        let subject = BlockchainInterfaceClandestine::new(DEFAULT_CHAIN_ID);
+
+        assert_eq!(
+            subject.get_block_hash(4_974_179),
+            Err(BlockchainError::QueryFailed)
+        );
+        assert_eq!(
+            subject.get_block_number(),
+            Err(BlockchainError::QueryFailed)
+        );
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_returns_none_for_unknown_transaction_receipt() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!(null));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.get_transaction_receipt(H256::from_uint(&U256::from(1)));
+
+        assert_eq!(result, Ok(None));
+    }
+
     #[test]
     fn to_gwei_truncates_units_smaller_than_gwei() {
         assert_eq!(Some(1), to_gwei(U256::from(1_999_999_999)));
@@ -974,4 +1609,184 @@ mod tests {
             "transfer(address,uint256)".keccak256()[0..4]
         );
     }
+
+    #[test]
+    fn hash_the_batch_contract_disperse_token_simple_function_signature() {
+        assert_eq!(
+            DISPERSE_TOKEN_SIMPLE_METHOD_ID,
+            "disperseTokenSimple(address,address[],uint256[])".keccak256()[0..4]
+        );
+    }
+
+    #[test]
+    fn disperse_token_simple_data_encodes_token_recipients_and_values() {
+        let payments = vec![
+            (
+                Wallet::from_str("0x1111111111111111111111111111111111111111").unwrap(),
+                5,
+            ),
+            (
+                Wallet::from_str("0x2222222222222222222222222222222222222222").unwrap(),
+                7,
+            ),
+        ];
+
+        let result = disperse_token_simple_data(contract_address(DEFAULT_CHAIN_ID), &payments);
+
+        let expected: Vec<u8> = vec![
+            "51ba162c",
+            "000000000000000000000000384dec25e03f94931767ce4c3556168468ba24c3",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "00000000000000000000000000000000000000000000000000000000000000c0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000001111111111111111111111111111111111111111",
+            "0000000000000000000000002222222222222222222222222222222222222222",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "000000000000000000000000000000000000000000000000000000012a05f200",
+            "00000000000000000000000000000000000000000000000000000001a13b8600",
+        ]
+        .join("")
+        .from_hex()
+        .unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn recipient_of_a_batch_payment_credits_the_consuming_wallet() {
+        // What the token logs when disperseTokenSimple has it transferFrom the payer to a recipient
+        let consuming_wallet = make_paying_wallet(b"consuming");
+        let recipient = make_wallet("recipient");
+        let payer_topic: H256 = consuming_wallet.address().into();
+        let recipient_topic: H256 = recipient.address().into();
+        let mut transport = TestTransport::default();
+        transport.add_response(json!([{
+            "address": format!("{:#x}", contract_address(DEFAULT_CHAIN_ID)),
+            "blockHash": "0x1a24b9169cbaec3f6effa1f600b70c7ab9e8e86db44062b49132a4415d26732a",
+            "blockNumber": "0x4be663",
+            "data": format!("0x{:064x}", 2_000_000_000u64),
+            "logIndex": "0x0",
+            "removed": false,
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                payer_topic,
+                recipient_topic,
+            ],
+            "transactionHash": "0x955cec6ac4f832911ab894ce16aa22c3003f46deff3f7165b32700d2f5ff0681",
+            "transactionIndex": "0x0",
+        }]));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject
+            .retrieve_transactions(42, 4_974_200, &recipient)
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].from, consuming_wallet);
+        assert_eq!(result[0].gwei_amount, 2);
+    }
+
+    #[test]
+    fn batch_contract_exists_only_on_public_chains() {
+        assert_eq!(batch_contract_address(1), Some(DISPERSE_CONTRACT_ADDRESS));
+        assert_eq!(batch_contract_address(2), None);
+        assert_eq!(batch_contract_address(3), Some(DISPERSE_CONTRACT_ADDRESS));
+        assert_eq!(batch_contract_address(4), Some(DISPERSE_CONTRACT_ADDRESS));
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_reports_max_batch_size_for_its_chain() {
+        let public = BlockchainInterfaceNonClandestine::new(
+            TestTransport::default(),
+            make_fake_event_loop_handle(),
+            3,
+        );
+        let dev = BlockchainInterfaceNonClandestine::new(
+            TestTransport::default(),
+            make_fake_event_loop_handle(),
+            2,
+        );
+
+        assert_eq!(public.max_batch_size(), MAX_PAYMENTS_PER_BATCH);
+        assert_eq!(dev.max_batch_size(), 1);
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_can_send_batch_transaction() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x1d4c0"));
+        transport.add_response(json!(
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.send_batch_transaction(
+            &make_paying_wallet(b"gdasgsa"),
+            &[
+                (make_wallet("blah123"), 9000),
+                (make_wallet("blah456"), 1000),
+            ],
+            U256::from(1),
+            2u64,
+        );
+
+        assert_eq!(result, Ok(H256::from_uint(&U256::from(1))));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "eth_estimateGas".to_string());
+        assert_eq!(requests[1].0, "eth_sendRawTransaction".to_string());
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_refuses_to_send_batch_transaction_without_batch_contract(
+    ) {
+        let mut transport = TestTransport::default();
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            2,
+        );
+
+        let result = subject.send_batch_transaction(
+            &make_paying_wallet(b"gdasgsa"),
+            &[(make_wallet("blah123"), 9000)],
+            U256::from(1),
+            2u64,
+        );
+
+        assert_eq!(
+            result,
+            Err(BlockchainError::TransactionFailed(
+                "No batch payment contract on chain dev".to_string()
+            ))
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn blockchain_interface_non_clandestine_can_fetch_batch_allowance() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!(
+            "0x00000000000000000000000000000000000000000000000000000000000003e8"
+        ));
+        let subject = BlockchainInterfaceNonClandestine::new(
+            transport.clone(),
+            make_fake_event_loop_handle(),
+            DEFAULT_CHAIN_ID,
+        );
+
+        let result = subject.get_batch_allowance(&make_paying_wallet(b"gdasgsa"));
+
+        assert_eq!(result, Ok(U256::from(1000)));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0, "eth_call".to_string());
+    }
 }
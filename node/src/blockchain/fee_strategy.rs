@@ -0,0 +1,346 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai). All rights reserved.
+
+use crate::blockchain::blockchain_interface::to_wei;
+use crate::sub_lib::logger::Logger;
+use futures::Future;
+use serde_derive::Deserialize;
+use serde_json::json;
+use std::fmt::Debug;
+use web3::types::U256;
+use web3::{Transport, Web3};
+
+// How many recent blocks the oracle looks at to choose a priority fee
+const FEE_HISTORY_BLOCKS: u64 = 10;
+// Which percentile of the priority fees paid in those blocks the oracle offers
+const PRIORITY_FEE_PERCENTILE: f64 = 50.0;
+// Offered when the recent blocks paid no priority fees at all
+const MIN_PRIORITY_FEE_GWEI: u64 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeStrategyKind {
+    Fixed,
+    Oracle,
+}
+
+impl Default for FeeStrategyKind {
+    fn default() -> Self {
+        FeeStrategyKind::Fixed
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionFees {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl TransactionFees {
+    // Never offers more than max_fee_per_gas per unit of gas, whatever the strategy came up with
+    pub fn capped_at(self, max_fee_per_gas: U256) -> Self {
+        match self {
+            TransactionFees::Legacy { gas_price } => TransactionFees::Legacy {
+                gas_price: gas_price.min(max_fee_per_gas),
+            },
+            TransactionFees::Eip1559 {
+                max_fee_per_gas: offered_max_fee,
+                max_priority_fee_per_gas,
+            } => TransactionFees::Eip1559 {
+                max_fee_per_gas: offered_max_fee.min(max_fee_per_gas),
+                max_priority_fee_per_gas: max_priority_fee_per_gas.min(max_fee_per_gas),
+            },
+        }
+    }
+}
+
+pub trait FeeStrategy<T: Transport>: Debug {
+    // configured_gas_price is in Gwei, as stored in the persistent configuration
+    fn fees(&self, web3: &Web3<T>, configured_gas_price: u64) -> TransactionFees;
+}
+
+pub fn make_fee_strategy<T: Transport + Debug>(kind: FeeStrategyKind) -> Box<dyn FeeStrategy<T>> {
+    match kind {
+        FeeStrategyKind::Fixed => Box::new(FixedFeeStrategy {}),
+        FeeStrategyKind::Oracle => Box::new(OracleFeeStrategy::default()),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FixedFeeStrategy {}
+
+impl<T: Transport> FeeStrategy<T> for FixedFeeStrategy {
+    fn fees(&self, _web3: &Web3<T>, configured_gas_price: u64) -> TransactionFees {
+        TransactionFees::Legacy {
+            gas_price: to_wei(configured_gas_price),
+        }
+    }
+}
+
+// Asks the chain what it's charging right now: eth_feeHistory where the chain knows EIP-1559,
+// eth_gasPrice where it doesn't, and the configured gas price if the chain won't say.
+#[derive(Debug)]
+pub struct OracleFeeStrategy {
+    logger: Logger,
+}
+
+impl Default for OracleFeeStrategy {
+    fn default() -> Self {
+        Self {
+            logger: Logger::new("FeeStrategy"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeHistory {
+    base_fee_per_gas: Vec<U256>,
+    #[serde(default)]
+    reward: Vec<Vec<U256>>,
+}
+
+impl<T: Transport> FeeStrategy<T> for OracleFeeStrategy {
+    fn fees(&self, web3: &Web3<T>, configured_gas_price: u64) -> TransactionFees {
+        match self.eip1559_fees(web3) {
+            Ok(fees) => return fees,
+            Err(e) => debug!(
+                self.logger,
+                "No fee history ({}); asking for the legacy gas price", e
+            ),
+        }
+        match web3.eth().gas_price().wait() {
+            Ok(gas_price) => TransactionFees::Legacy { gas_price },
+            Err(e) => {
+                warning!(
+                    self.logger,
+                    "Couldn't get the gas price from the blockchain ({:?}); using the configured {} Gwei",
+                    e,
+                    configured_gas_price
+                );
+                TransactionFees::Legacy {
+                    gas_price: to_wei(configured_gas_price),
+                }
+            }
+        }
+    }
+}
+
+impl OracleFeeStrategy {
+    fn eip1559_fees<T: Transport>(&self, web3: &Web3<T>) -> Result<TransactionFees, String> {
+        let response = web3
+            .transport()
+            .execute(
+                "eth_feeHistory",
+                vec![
+                    json!(format!("{:#x}", FEE_HISTORY_BLOCKS)),
+                    json!("latest"),
+                    json!([PRIORITY_FEE_PERCENTILE]),
+                ],
+            )
+            .wait()
+            .map_err(|e| format!("{:?}", e))?;
+        let history = serde_json::from_value::<FeeHistory>(response)
+            .map_err(|e| format!("unexpected response: {}", e))?;
+        // The last entry is the base fee of the block that hasn't been mined yet
+        let base_fee = match history.base_fee_per_gas.last() {
+            Some(base_fee) if !base_fee.is_zero() => *base_fee,
+            _ => return Err("no base fee".to_string()),
+        };
+        let max_priority_fee_per_gas = Self::median_priority_fee(&history.reward);
+        // Leaves room for the base fee to double before the transaction stops being minable
+        let max_fee_per_gas = base_fee * 2 + max_priority_fee_per_gas;
+        debug!(
+            self.logger,
+            "Base fee {} wei; offering max fee {} wei with priority fee {} wei",
+            base_fee,
+            max_fee_per_gas,
+            max_priority_fee_per_gas
+        );
+        Ok(TransactionFees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    fn median_priority_fee(rewards: &[Vec<U256>]) -> U256 {
+        let mut fees = rewards
+            .iter()
+            .filter_map(|block_rewards| block_rewards.first().cloned())
+            .filter(|fee| !fee.is_zero())
+            .collect::<Vec<U256>>();
+        if fees.is_empty() {
+            return to_wei(MIN_PRIORITY_FEE_GWEI);
+        }
+        fees.sort();
+        fees[fees.len() / 2]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::test_utils::TestTransport;
+    use crate::test_utils::logging::{init_test_logging, TestLogHandler};
+
+    #[test]
+    fn capped_fees_never_exceed_the_cap() {
+        let legacy = TransactionFees::Legacy {
+            gas_price: to_wei(30),
+        };
+        let eip1559 = TransactionFees::Eip1559 {
+            max_fee_per_gas: to_wei(40),
+            max_priority_fee_per_gas: to_wei(25),
+        };
+
+        assert_eq!(
+            legacy.capped_at(to_wei(20)),
+            TransactionFees::Legacy {
+                gas_price: to_wei(20)
+            }
+        );
+        assert_eq!(
+            eip1559.capped_at(to_wei(20)),
+            TransactionFees::Eip1559 {
+                max_fee_per_gas: to_wei(20),
+                max_priority_fee_per_gas: to_wei(20),
+            }
+        );
+        assert_eq!(
+            eip1559.capped_at(to_wei(50)),
+            TransactionFees::Eip1559 {
+                max_fee_per_gas: to_wei(40),
+                max_priority_fee_per_gas: to_wei(25),
+            }
+        );
+    }
+
+    #[test]
+    fn fixed_fee_strategy_uses_the_configured_gas_price() {
+        let mut transport = TestTransport::default();
+        let web3 = Web3::new(transport.clone());
+        let subject = FixedFeeStrategy {};
+
+        let result = subject.fees(&web3, 5);
+
+        assert_eq!(
+            result,
+            TransactionFees::Legacy {
+                gas_price: to_wei(5)
+            }
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn oracle_fee_strategy_offers_eip1559_fees_from_fee_history() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x4a817c800", "0x2540be400"],
+            "gasUsedRatio": [0.5, 0.9],
+            "reward": [["0x3b9aca00"], ["0x77359400"], ["0x0"], ["0xb2d05e00"]]
+        }));
+        let web3 = Web3::new(transport.clone());
+        let subject = OracleFeeStrategy::default();
+
+        let result = subject.fees(&web3, 5);
+
+        assert_eq!(
+            result,
+            TransactionFees::Eip1559 {
+                max_fee_per_gas: to_wei(10) * 2 + to_wei(2),
+                max_priority_fee_per_gas: to_wei(2),
+            }
+        );
+        transport.assert_request(
+            "eth_feeHistory",
+            &[
+                r#""0xa""#.to_string(),
+                r#""latest""#.to_string(),
+                "[50.0]".to_string(),
+            ],
+        );
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn oracle_fee_strategy_offers_a_minimum_priority_fee_when_blocks_paid_none() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00"],
+            "gasUsedRatio": [],
+            "reward": [["0x0"]]
+        }));
+        let web3 = Web3::new(transport.clone());
+        let subject = OracleFeeStrategy::default();
+
+        let result = subject.fees(&web3, 5);
+
+        assert_eq!(
+            result,
+            TransactionFees::Eip1559 {
+                max_fee_per_gas: to_wei(1) * 2 + to_wei(MIN_PRIORITY_FEE_GWEI),
+                max_priority_fee_per_gas: to_wei(MIN_PRIORITY_FEE_GWEI),
+            }
+        );
+    }
+
+    #[test]
+    fn oracle_fee_strategy_uses_legacy_gas_price_on_chains_without_base_fee() {
+        let mut transport = TestTransport::default();
+        transport.add_response(json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x0", "0x0"],
+            "gasUsedRatio": [0.5]
+        }));
+        transport.add_response(json!("0x2540be400"));
+        let web3 = Web3::new(transport.clone());
+        let subject = OracleFeeStrategy::default();
+
+        let result = subject.fees(&web3, 5);
+
+        assert_eq!(
+            result,
+            TransactionFees::Legacy {
+                gas_price: to_wei(10)
+            }
+        );
+        transport.assert_request(
+            "eth_feeHistory",
+            &[
+                r#""0xa""#.to_string(),
+                r#""latest""#.to_string(),
+                "[50.0]".to_string(),
+            ],
+        );
+        transport.assert_request("eth_gasPrice", &[]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn oracle_fee_strategy_falls_back_to_configured_gas_price_when_the_chain_wont_say() {
+        init_test_logging();
+        let transport = TestTransport::default();
+        let web3 = Web3::new(transport.clone());
+        let subject = OracleFeeStrategy::default();
+
+        let result = subject.fees(&web3, 5);
+
+        assert_eq!(
+            result,
+            TransactionFees::Legacy {
+                gas_price: to_wei(5)
+            }
+        );
+        let tlh = TestLogHandler::new();
+        tlh.exists_log_containing(
+            "WARN: FeeStrategy: Couldn't get the gas price from the blockchain",
+        );
+        tlh.exists_log_containing("using the configured 5 Gwei");
+    }
+}
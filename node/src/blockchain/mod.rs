@@ -3,6 +3,7 @@ pub mod bip32;
 pub mod bip39;
 pub mod blockchain_bridge;
 pub mod blockchain_interface;
+pub mod fee_strategy;
 pub mod payer;
 pub mod raw_transaction;
 pub mod signature;
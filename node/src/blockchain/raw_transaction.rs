@@ -66,6 +66,81 @@ impl RawTransaction {
     }
 }
 
+/// Description of an EIP-1559 (type 2) Transaction, whose fee is a base fee burned by the
+/// chain plus a priority fee for the miner, both capped by `max_fee_per_gas`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Eip1559RawTransaction {
+    /// Nonce
+    pub nonce: U256,
+    /// Recipient (None when contract creation)
+    pub to: Option<Address>,
+    /// Transfered value
+    pub value: U256,
+    /// Most the sender will pay per unit of gas, base fee included
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: U256,
+    /// Most the sender will pay the miner per unit of gas
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: U256,
+    /// Gas limit
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: U256,
+    /// Input data
+    pub data: Vec<u8>,
+}
+
+const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+
+impl Eip1559RawTransaction {
+    /// Signs and returns the typed transaction envelope: the type byte followed by the RLP
+    pub fn sign(&self, wallet: &Wallet, chain_id: u8) -> Vec<u8> {
+        let hash = self.tx_hash(chain_id);
+        let sig = match wallet.sign(&hash) {
+            Ok(s) => s,
+            Err(e) => panic!("{:?}", e),
+        };
+        let mut tx = RlpStream::new();
+        tx.begin_unbounded_list();
+        self.encode(&mut tx, chain_id);
+        tx.append(&sig.v);
+        tx.append(&U256::from(&sig.r[..]));
+        tx.append(&U256::from(&sig.s[..]));
+        tx.finalize_unbounded_list();
+        Self::envelope(tx)
+    }
+
+    fn tx_hash(&self, chain_id: u8) -> Vec<u8> {
+        let mut hash = RlpStream::new();
+        hash.begin_unbounded_list();
+        self.encode(&mut hash, chain_id);
+        hash.finalize_unbounded_list();
+        Self::envelope(hash).keccak256().to_vec()
+    }
+
+    fn encode(&self, s: &mut RlpStream, chain_id: u8) {
+        s.append(&U256::from(chain_id));
+        s.append(&self.nonce);
+        s.append(&self.max_priority_fee_per_gas);
+        s.append(&self.max_fee_per_gas);
+        s.append(&self.gas_limit);
+        if let Some(ref t) = self.to {
+            s.append(t);
+        } else {
+            s.append(&vec![]);
+        }
+        s.append(&self.value);
+        s.append(&self.data);
+        // access list
+        s.begin_list(0);
+    }
+
+    fn envelope(stream: RlpStream) -> Vec<u8> {
+        let mut result = vec![EIP1559_TRANSACTION_TYPE];
+        result.extend(stream.out());
+        result
+    }
+}
+
 fn ecdsa_sign(hash: &dyn AsRef<[u8]>, wallet: &Wallet, chain_id: u8) -> Signature {
     match wallet.sign(&hash) {
         Ok(s) => Signature {
@@ -82,6 +157,9 @@ mod tests {
     use super::*;
     use crate::blockchain::bip32::Bip32ECKeyPair;
     use ethereum_types::H256;
+    use rlp::Rlp;
+    use rustc_hex::FromHex;
+    use std::str::FromStr;
 
     #[derive(Deserialize)]
     struct Signing {
@@ -186,4 +264,60 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn eip1559_transaction_is_a_signed_type_2_envelope() {
+        let wallet = Wallet::from(
+            Bip32ECKeyPair::from_raw_secret(
+                &"0fde24c464a9c55a83a164ec8f31888921549da2401a1af3cd79cccf5685421a"
+                    .from_hex::<Vec<u8>>()
+                    .unwrap(),
+            )
+            .unwrap(),
+        );
+        let subject = Eip1559RawTransaction {
+            nonce: U256::from(7),
+            to: Some(Address::from_str("8D75959f1E61EC2571aa72798237101F084DE63a").unwrap()),
+            value: U256::zero(),
+            max_fee_per_gas: U256::from(40_000_000_000u64),
+            max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            gas_limit: U256::from(60_000),
+            data: vec![169, 5, 156, 187],
+        };
+
+        let result = subject.sign(&wallet, 1);
+
+        assert_eq!(result[0], 0x02);
+        let rlp = Rlp::new(&result[1..]);
+        assert_eq!(rlp.item_count().unwrap(), 12);
+        assert_eq!(rlp.val_at::<U256>(0).unwrap(), U256::from(1));
+        assert_eq!(rlp.val_at::<U256>(1).unwrap(), U256::from(7));
+        assert_eq!(rlp.val_at::<U256>(2).unwrap(), U256::from(2_000_000_000u64));
+        assert_eq!(
+            rlp.val_at::<U256>(3).unwrap(),
+            U256::from(40_000_000_000u64)
+        );
+        assert_eq!(rlp.val_at::<U256>(4).unwrap(), U256::from(60_000));
+        assert_eq!(rlp.val_at::<Address>(5).unwrap(), subject.to.unwrap());
+        assert_eq!(rlp.val_at::<U256>(6).unwrap(), U256::zero());
+        assert_eq!(rlp.val_at::<Vec<u8>>(7).unwrap(), vec![169, 5, 156, 187]);
+        assert_eq!(rlp.at(8).unwrap().item_count().unwrap(), 0);
+        let mut unsigned = RlpStream::new_list(9);
+        (0..9).for_each(|index| {
+            unsigned.append_raw(rlp.at(index).unwrap().as_raw(), 1);
+        });
+        let mut preimage = vec![0x02];
+        preimage.extend(unsigned.out());
+        let mut r = [0u8; 32];
+        rlp.val_at::<U256>(10).unwrap().to_big_endian(&mut r);
+        let mut s = [0u8; 32];
+        rlp.val_at::<U256>(11).unwrap().to_big_endian(&mut s);
+        let signature = Signature {
+            v: rlp.val_at::<u8>(9).unwrap(),
+            r,
+            s,
+        };
+        let signer = signature.recover(&preimage.keccak256()).unwrap();
+        assert_eq!(signer.address(), &wallet.address().0);
+    }
 }
@@ -1,6 +1,13 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 use bip39::{Language, Mnemonic, Seed};
+use jsonrpc_core as rpc;
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use web3::types::{TransactionReceipt, H256};
+use web3::{Error, RequestId, Transport};
 
 pub fn make_meaningless_phrase() -> String {
     "phrase donate agent satoshi burst end company pear obvious achieve depth advice".to_string()
@@ -10,3 +17,92 @@ pub fn make_meaningless_seed() -> Seed {
     let mnemonic = Mnemonic::from_phrase(make_meaningless_phrase(), Language::English).unwrap();
     Seed::new(&mnemonic, "passphrase")
 }
+
+pub fn make_transaction_receipt(
+    transaction_hash: H256,
+    status_opt: Option<u64>,
+) -> TransactionReceipt {
+    serde_json::from_value(json!({
+        "transactionHash": transaction_hash,
+        "transactionIndex": "0x0",
+        "blockHash": null,
+        "blockNumber": null,
+        "cumulativeGasUsed": "0x0",
+        "gasUsed": null,
+        "contractAddress": null,
+        "logs": [],
+        "status": status_opt.map(|status| format!("{:#x}", status)),
+        "logsBloom": format!("0x{}", "0".repeat(512)),
+    }))
+    .expect("Bad transaction receipt")
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TestTransport {
+    asserted: usize,
+    requests: Rc<RefCell<Vec<(String, Vec<rpc::Value>)>>>,
+    responses: Rc<RefCell<VecDeque<Result<rpc::Value, rpc::Error>>>>,
+}
+
+impl Transport for TestTransport {
+    type Out = web3::Result<rpc::Value>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let request = web3::helpers::build_request(1, method, params.clone());
+        self.requests.borrow_mut().push((method.into(), params));
+        (self.requests.borrow().len(), request)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        match self.responses.borrow_mut().pop_front() {
+            Some(Ok(response)) => Box::new(futures::finished(response)),
+            Some(Err(error)) => Box::new(futures::failed(Error::Rpc(error))),
+            None => {
+                println!("Unexpected request (id: {:?}): {:?}", id, request);
+                Box::new(futures::failed(Error::Unreachable))
+            }
+        }
+    }
+}
+
+impl TestTransport {
+    pub fn add_response(&mut self, value: rpc::Value) {
+        self.responses.borrow_mut().push_back(Ok(value));
+    }
+
+    pub fn add_error_response(&mut self, error: rpc::Error) {
+        self.responses.borrow_mut().push_back(Err(error));
+    }
+
+    pub fn assert_request(&mut self, method: &str, params: &[String]) {
+        let idx = self.asserted;
+        self.asserted += 1;
+
+        let (m, p) = self
+            .requests
+            .borrow()
+            .get(idx)
+            .expect("Expected result.")
+            .clone();
+        assert_eq!(&m, method);
+        let p: Vec<String> = p
+            .into_iter()
+            .map(|p| serde_json::to_string(&p).unwrap())
+            .collect();
+        assert_eq!(p, params);
+    }
+
+    pub fn requests(&self) -> Vec<(String, Vec<rpc::Value>)> {
+        self.requests.borrow().clone()
+    }
+
+    pub fn assert_no_more_requests(&mut self) {
+        let requests = self.requests.borrow();
+        assert_eq!(
+            self.asserted,
+            requests.len(),
+            "Expected no more requests, got: {:?}",
+            &requests[self.asserted..]
+        );
+    }
+}
@@ -1,20 +1,24 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
-use crate::accountant::{DEFAULT_PAYABLE_SCAN_INTERVAL, DEFAULT_PAYMENT_RECEIVED_SCAN_INTERVAL};
+use crate::accountant::{
+    DEFAULT_PAYABLE_SCAN_INTERVAL, DEFAULT_PAYMENT_CURVES, DEFAULT_PAYMENT_RECEIVED_SCAN_INTERVAL,
+};
 use crate::actor_system_factory::ActorFactoryReal;
 use crate::actor_system_factory::ActorSystemFactory;
 use crate::actor_system_factory::ActorSystemFactoryReal;
 use crate::blockchain::blockchain_interface::chain_id_from_name;
+use crate::blockchain::fee_strategy::FeeStrategyKind;
 use crate::crash_test_dummy::CrashTestDummy;
 use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::config_dao::ConfigDaoReal;
 use crate::db_config::persistent_configuration::{
-    PersistentConfiguration, PersistentConfigurationReal,
+    NodeKeys, PersistentConfiguration, PersistentConfigurationReal,
 };
 use crate::discriminator::DiscriminatorFactory;
 use crate::json_discriminator_factory::JsonDiscriminatorFactory;
 use crate::listener_handler::ListenerHandler;
 use crate::listener_handler::ListenerHandlerFactory;
 use crate::listener_handler::ListenerHandlerFactoryReal;
+use crate::neighborhood::geoip::GeoIpDatabase;
 use crate::node_configurator::node_configurator_standard::{
     NodeConfiguratorStandardPrivileged, NodeConfiguratorStandardUnprivileged,
 };
@@ -24,21 +28,26 @@ use crate::server_initializer::LoggerInitializerWrapper;
 use crate::sub_lib::accountant;
 use crate::sub_lib::accountant::AccountantConfig;
 use crate::sub_lib::blockchain_bridge::BlockchainBridgeConfig;
-use crate::sub_lib::cryptde::CryptDE;
+use crate::sub_lib::cryptde::{CryptDE, PlainData};
 use crate::sub_lib::cryptde_null::CryptDENull;
 use crate::sub_lib::cryptde_real::CryptDEReal;
+use crate::sub_lib::exit_policy::ExitPolicy;
+use crate::sub_lib::exit_service_policy::ExitServicePolicy;
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::neighborhood::NodeDescriptor;
-use crate::sub_lib::neighborhood::{NeighborhoodConfig, NeighborhoodMode};
+use crate::sub_lib::neighborhood::{NeighborhoodConfig, NeighborhoodMode, RoutingStrategy};
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::socket_server::SocketServer;
-use crate::sub_lib::ui_gateway::UiGatewayConfig;
+use crate::sub_lib::ui_gateway::{UiGatewayConfig, UiSecurity};
 use crate::sub_lib::wallet::Wallet;
 use futures::try_ready;
 use itertools::Itertools;
 use log::LevelFilter;
 use masq_lib::command::StdStreams;
-use masq_lib::constants::{DEFAULT_CHAIN_NAME, DEFAULT_UI_PORT};
+use masq_lib::constants::{
+    DEFAULT_CHAIN_NAME, DEFAULT_CONFIRMATIONS, DEFAULT_MALEFACTOR_BAN_DURATION_HOURS,
+    DEFAULT_UI_PORT,
+};
 use masq_lib::crash_point::CrashPoint;
 use masq_lib::shared_schema::ConfiguratorError;
 use std::collections::HashMap;
@@ -48,6 +57,7 @@ use std::fmt::{Debug, Display, Error, Formatter};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::vec::Vec;
 use tokio::prelude::stream::futures_unordered::FuturesUnordered;
@@ -294,6 +304,7 @@ pub struct BootstrapperConfig {
     pub ui_gateway_config: UiGatewayConfig,
     pub blockchain_bridge_config: BlockchainBridgeConfig,
     pub port_configurations: HashMap<u16, PortConfiguration>,
+    pub socks_port_opt: Option<u16>,
     pub data_directory: PathBuf,
     pub main_cryptde_null_opt: Option<CryptDENull>,
     pub alias_cryptde_null_opt: Option<CryptDENull>,
@@ -305,6 +316,11 @@ pub struct BootstrapperConfig {
     pub consuming_wallet: Option<Wallet>,
     pub earning_wallet: Wallet,
     pub neighborhood_config: NeighborhoodConfig,
+    pub routing_strategy: RoutingStrategy,
+    pub exit_policy: ExitPolicy,
+    pub exit_service_policy: ExitServicePolicy,
+    pub geoip_database_opt: Option<Arc<GeoIpDatabase>>,
+    pub malefactor_ban_duration: Duration,
 }
 
 impl Default for BootstrapperConfig {
@@ -324,19 +340,26 @@ impl BootstrapperConfig {
                 payment_received_scan_interval: Duration::from_secs(
                     DEFAULT_PAYMENT_RECEIVED_SCAN_INTERVAL,
                 ),
+                payment_curves: DEFAULT_PAYMENT_CURVES.clone(),
             },
             crash_point: CrashPoint::None,
             clandestine_discriminator_factories: vec![],
             ui_gateway_config: UiGatewayConfig {
                 ui_port: DEFAULT_UI_PORT,
+                ui_security: UiSecurity::default(),
                 node_descriptor: String::from(""),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
                 blockchain_service_url: None,
                 chain_id: 3u8, /*DEFAULT_CHAIN_ID*/
                 gas_price: 1,
+                batch_payments: false,
+                fee_strategy: FeeStrategyKind::Fixed,
+                max_fee_per_gas_opt: None,
+                confirmations: DEFAULT_CONFIRMATIONS,
             },
             port_configurations: HashMap::new(),
+            socks_port_opt: None,
             data_directory: PathBuf::new(),
             main_cryptde_null_opt: None,
             alias_cryptde_null_opt: None,
@@ -350,13 +373,34 @@ impl BootstrapperConfig {
             neighborhood_config: NeighborhoodConfig {
                 mode: NeighborhoodMode::ZeroHop,
             },
+            routing_strategy: RoutingStrategy::default(),
+            exit_policy: ExitPolicy::default(),
+            exit_service_policy: ExitServicePolicy::default(),
+            geoip_database_opt: None,
+            malefactor_ban_duration: Duration::from_secs(
+                DEFAULT_MALEFACTOR_BAN_DURATION_HOURS * 3600,
+            ),
         }
     }
 
     pub fn merge_unprivileged(&mut self, unprivileged: BootstrapperConfig) {
         self.blockchain_bridge_config.gas_price = unprivileged.blockchain_bridge_config.gas_price;
+        self.blockchain_bridge_config.batch_payments =
+            unprivileged.blockchain_bridge_config.batch_payments;
+        self.blockchain_bridge_config.fee_strategy =
+            unprivileged.blockchain_bridge_config.fee_strategy;
+        self.blockchain_bridge_config.max_fee_per_gas_opt =
+            unprivileged.blockchain_bridge_config.max_fee_per_gas_opt;
+        self.blockchain_bridge_config.confirmations =
+            unprivileged.blockchain_bridge_config.confirmations;
+        self.accountant_config.payment_curves = unprivileged.accountant_config.payment_curves;
         self.clandestine_port_opt = unprivileged.clandestine_port_opt;
         self.neighborhood_config = unprivileged.neighborhood_config;
+        self.routing_strategy = unprivileged.routing_strategy;
+        self.exit_policy = unprivileged.exit_policy;
+        self.exit_service_policy = unprivileged.exit_service_policy;
+        self.geoip_database_opt = unprivileged.geoip_database_opt;
+        self.malefactor_ban_duration = unprivileged.malefactor_ban_duration;
         self.earning_wallet = unprivileged.earning_wallet;
         self.consuming_wallet = unprivileged.consuming_wallet;
         self.db_password_opt = unprivileged.db_password_opt;
@@ -435,11 +479,7 @@ impl SocketServer<BootstrapperConfig> for Bootstrapper {
             .configure(&args.to_vec(), streams)?;
         self.config.merge_unprivileged(unprivileged_config);
         self.set_up_clandestine_port();
-        let (cryptde_ref, _) = Bootstrapper::initialize_cryptdes(
-            &self.config.main_cryptde_null_opt,
-            &self.config.alias_cryptde_null_opt,
-            self.config.blockchain_bridge_config.chain_id,
-        );
+        let (cryptde_ref, _) = self.establish_cryptdes()?;
         self.config.ui_gateway_config.node_descriptor = Bootstrapper::report_local_descriptor(
             cryptde_ref,
             self.config.neighborhood_config.mode.node_addr_opt(),
@@ -501,6 +541,78 @@ impl Bootstrapper {
         (main_cryptde_ref(), alias_cryptde_ref())
     }
 
+    // Without a database password the keys can't be stored securely, so the Node gets a new
+    // identity every time it starts, just as it does with --fake-public-key.
+    fn establish_cryptdes(
+        &self,
+    ) -> Result<(&'static dyn CryptDE, &'static dyn CryptDE), ConfiguratorError> {
+        let chain_id = self.config.blockchain_bridge_config.chain_id;
+        match (
+            &self.config.main_cryptde_null_opt,
+            &self.config.db_password_opt,
+        ) {
+            (None, Some(db_password)) => {
+                let mut persistent_config = self.make_persistent_config();
+                let (main_cryptde, alias_cryptde) =
+                    Self::establish_node_keys(&mut persistent_config, db_password, chain_id)?;
+                unsafe {
+                    MAIN_CRYPTDE_BOX_OPT = Some(Box::new(main_cryptde));
+                    ALIAS_CRYPTDE_BOX_OPT = Some(Box::new(alias_cryptde));
+                }
+                Ok((main_cryptde_ref(), alias_cryptde_ref()))
+            }
+            _ => Ok(Self::initialize_cryptdes(
+                &self.config.main_cryptde_null_opt,
+                &self.config.alias_cryptde_null_opt,
+                chain_id,
+            )),
+        }
+    }
+
+    fn establish_node_keys(
+        persistent_config: &mut dyn PersistentConfiguration,
+        db_password: &str,
+        chain_id: u8,
+    ) -> Result<(CryptDEReal, CryptDEReal), ConfiguratorError> {
+        // A wrong password is the user's mistake, not a sign of a corrupt database
+        match persistent_config.check_password(Some(db_password.to_string())) {
+            Ok(true) => (),
+            Ok(false) => {
+                return Err(ConfiguratorError::required(
+                    "db-password",
+                    "Incorrect password: can't read the Node's keys",
+                ))
+            }
+            Err(pce) => return Err(pce.into_configurator_error("db-password")),
+        }
+        match persistent_config.node_keys(db_password) {
+            Ok(Some(node_keys)) => {
+                let from_key_material = |key_material: &PlainData| {
+                    CryptDEReal::from_key_material(key_material, chain_id).unwrap_or_else(|e| {
+                        panic!("Database is corrupt: node keys are invalid: {}", e)
+                    })
+                };
+                Ok((
+                    from_key_material(&node_keys.main),
+                    from_key_material(&node_keys.alias),
+                ))
+            }
+            Ok(None) => {
+                let main_cryptde = CryptDEReal::new(chain_id);
+                let alias_cryptde = CryptDEReal::new(chain_id);
+                let node_keys = NodeKeys {
+                    main: main_cryptde.key_material(),
+                    alias: alias_cryptde.key_material(),
+                };
+                match persistent_config.set_node_keys(&node_keys, db_password) {
+                    Ok(_) => Ok((main_cryptde, alias_cryptde)),
+                    Err(pce) => panic!("Database is corrupt: error setting node keys: {:?}", pce),
+                }
+            }
+            Err(pce) => panic!("Database is corrupt: error reading node keys: {:?}", pce),
+        }
+    }
+
     fn report_local_descriptor(
         cryptde: &dyn CryptDE,
         node_addr_opt: Option<NodeAddr>,
@@ -532,15 +644,7 @@ impl Bootstrapper {
         if let NeighborhoodMode::Standard(node_addr, neighbor_configs, rate_pack) =
             &self.config.neighborhood_config.mode
         {
-            let conn = DbInitializerReal::new()
-                .initialize(
-                    &self.config.data_directory,
-                    self.config.blockchain_bridge_config.chain_id,
-                    true,
-                )
-                .expect("Cannot initialize database");
-            let config_dao = ConfigDaoReal::new(conn);
-            let mut persistent_config = PersistentConfigurationReal::new(Box::new(config_dao));
+            let mut persistent_config = self.make_persistent_config();
             let clandestine_port = self.establish_clandestine_port(&mut persistent_config);
             let mut listener_handler = self.listener_handler_factory.make();
             listener_handler
@@ -566,6 +670,18 @@ impl Bootstrapper {
             .push(Box::new(JsonDiscriminatorFactory::new()));
     }
 
+    fn make_persistent_config(&self) -> PersistentConfigurationReal {
+        let conn = DbInitializerReal::new()
+            .initialize(
+                &self.config.data_directory,
+                self.config.blockchain_bridge_config.chain_id,
+                true,
+            )
+            .expect("Cannot initialize database");
+        let config_dao = ConfigDaoReal::new(conn);
+        PersistentConfigurationReal::new(Box::new(config_dao))
+    }
+
     fn establish_clandestine_port(
         &self,
         persistent_config: &mut dyn PersistentConfiguration,
@@ -595,6 +711,7 @@ impl Bootstrapper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::accountant::PaymentCurves;
     use crate::actor_system_factory::ActorFactory;
     use crate::blockchain::blockchain_interface::chain_id_from_name;
     use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
@@ -1119,6 +1236,62 @@ mod tests {
         assert_eq!(config.blockchain_bridge_config.gas_price, 11);
     }
 
+    #[test]
+    fn initialize_as_unprivileged_sets_payment_settings() {
+        let _lock = INITIALIZATION.lock();
+        let data_dir = ensure_node_home_directory_exists(
+            "bootstrapper",
+            "initialize_as_unprivileged_sets_payment_settings",
+        );
+        let mut config = BootstrapperConfig::new();
+        config.data_directory = data_dir.clone();
+        let mut subject = BootstrapperBuilder::new()
+            .add_listener_handler(Box::new(
+                ListenerHandlerNull::new(vec![]).bind_port_result(Ok(())),
+            ))
+            .config(config)
+            .build();
+
+        subject
+            .initialize_as_unprivileged(
+                &[
+                    "MASQNode".to_string(),
+                    String::from("--data-directory"),
+                    data_dir.to_str().unwrap().to_string(),
+                    String::from("--ip"),
+                    String::from("1.2.3.4"),
+                    String::from("--batch-payments"),
+                    String::from("on"),
+                    String::from("--fee-strategy"),
+                    String::from("oracle"),
+                    String::from("--max-fee-per-gas"),
+                    String::from("150"),
+                    String::from("--confirmations"),
+                    String::from("3"),
+                    String::from("--payment-curves"),
+                    String::from("1|2|3|4|5|6"),
+                ],
+                &mut FakeStreamHolder::new().streams(),
+            )
+            .unwrap();
+
+        let config = subject.config;
+        assert!(config.blockchain_bridge_config.batch_payments);
+        assert_eq!(
+            config.blockchain_bridge_config.fee_strategy,
+            FeeStrategyKind::Oracle
+        );
+        assert_eq!(
+            config.blockchain_bridge_config.max_fee_per_gas_opt,
+            Some(150)
+        );
+        assert_eq!(config.blockchain_bridge_config.confirmations, 3);
+        assert_eq!(
+            config.accountant_config.payment_curves,
+            PaymentCurves::from_str("1|2|3|4|5|6").unwrap()
+        );
+    }
+
     #[test]
     fn initialize_with_clandestine_port_produces_expected_clandestine_discriminator_factories_vector(
     ) {
@@ -1833,6 +2006,163 @@ mod tests {
         let _ = subject.establish_clandestine_port(&mut persistent_config);
     }
 
+    #[test]
+    fn establish_node_keys_loads_existing_keys() {
+        let main_cryptde = CryptDEReal::new(DEFAULT_CHAIN_ID);
+        let alias_cryptde = CryptDEReal::new(DEFAULT_CHAIN_ID);
+        let node_keys_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .node_keys_params(&node_keys_params_arc)
+            .node_keys_result(Ok(Some(NodeKeys {
+                main: main_cryptde.key_material(),
+                alias: alias_cryptde.key_material(),
+            })));
+
+        let (main_result, alias_result) =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID)
+                .unwrap();
+
+        assert_eq!(main_result.public_key(), main_cryptde.public_key());
+        assert_eq!(alias_result.public_key(), alias_cryptde.public_key());
+        let node_keys_params = node_keys_params_arc.lock().unwrap();
+        assert_eq!(*node_keys_params, vec!["password".to_string()]);
+    }
+
+    #[test]
+    fn establish_node_keys_generates_and_stores_keys_if_there_are_none() {
+        let set_node_keys_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .node_keys_result(Ok(None))
+            .set_node_keys_params(&set_node_keys_params_arc)
+            .set_node_keys_result(Ok(()));
+
+        let (main_result, alias_result) =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID)
+                .unwrap();
+
+        assert_ne!(main_result.public_key(), alias_result.public_key());
+        let set_node_keys_params = set_node_keys_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_node_keys_params,
+            vec![(
+                NodeKeys {
+                    main: main_result.key_material(),
+                    alias: alias_result.key_material(),
+                },
+                "password".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Database is corrupt: error reading node keys: PasswordError")]
+    fn establish_node_keys_handles_error_reading_keys() {
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .node_keys_result(Err(PersistentConfigError::PasswordError));
+
+        let _ =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID);
+    }
+
+    #[test]
+    #[should_panic(expected = "Database is corrupt: error setting node keys: TransactionError")]
+    fn establish_node_keys_handles_error_setting_keys() {
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .node_keys_result(Ok(None))
+            .set_node_keys_result(Err(PersistentConfigError::TransactionError));
+
+        let _ =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Database is corrupt: node keys are invalid: Key material must be 96 bytes, not 3"
+    )]
+    fn establish_node_keys_handles_invalid_keys() {
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .node_keys_result(Ok(Some(NodeKeys {
+                main: PlainData::new(&[1, 2, 3]),
+                alias: PlainData::new(&[4, 5, 6]),
+            })));
+
+        let _ =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID);
+    }
+
+    #[test]
+    fn establish_node_keys_keeps_the_same_identity_across_restarts() {
+        let data_dir = ensure_node_home_directory_exists(
+            "bootstrapper",
+            "establish_node_keys_keeps_the_same_identity_across_restarts",
+        );
+        let make_persistent_config = || {
+            let conn = DbInitializerReal::new()
+                .initialize(&data_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap();
+            PersistentConfigurationReal::new(Box::new(ConfigDaoReal::new(conn)))
+        };
+        let mut persistent_config = make_persistent_config();
+        persistent_config.change_password(None, "password").unwrap();
+        let (first_main, first_alias) =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID)
+                .unwrap();
+        let mut persistent_config = make_persistent_config();
+
+        let (second_main, second_alias) =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID)
+                .unwrap();
+
+        assert_eq!(second_main.public_key(), first_main.public_key());
+        assert_eq!(second_alias.public_key(), first_alias.public_key());
+    }
+
+    #[test]
+    fn establish_node_keys_rejects_the_wrong_password() {
+        let data_dir = ensure_node_home_directory_exists(
+            "bootstrapper",
+            "establish_node_keys_rejects_the_wrong_password",
+        );
+        let conn = DbInitializerReal::new()
+            .initialize(&data_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+        let mut persistent_config =
+            PersistentConfigurationReal::new(Box::new(ConfigDaoReal::new(conn)));
+        persistent_config.change_password(None, "password").unwrap();
+        Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID)
+            .unwrap();
+
+        let result =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "wrong", DEFAULT_CHAIN_ID);
+
+        assert_eq!(
+            result.err(),
+            Some(ConfiguratorError::required(
+                "db-password",
+                "Incorrect password: can't read the Node's keys"
+            ))
+        );
+    }
+
+    #[test]
+    fn establish_node_keys_handles_error_checking_the_password() {
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Err(PersistentConfigError::NotPresent));
+
+        let result =
+            Bootstrapper::establish_node_keys(&mut persistent_config, "password", DEFAULT_CHAIN_ID);
+
+        assert_eq!(
+            result.err(),
+            Some(PersistentConfigError::NotPresent.into_configurator_error("db-password"))
+        );
+    }
+
     #[test]
     fn real_user_null() {
         let subject = RealUser::null();
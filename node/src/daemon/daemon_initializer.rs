@@ -2,6 +2,7 @@
 
 use crate::bootstrapper::RealUser;
 use crate::daemon::launcher::LauncherReal;
+use crate::daemon::restart_policy::RestartPolicy;
 use crate::daemon::{
     ChannelFactory, ChannelFactoryReal, Daemon, DaemonBindMessage, Launcher, Recipients,
 };
@@ -9,7 +10,7 @@ use crate::node_configurator::node_configurator_initialization::InitializationCo
 use crate::node_configurator::{port_is_busy, DirsWrapper};
 use crate::server_initializer::LoggerInitializerWrapper;
 use crate::sub_lib::main_tools::main_with_args;
-use crate::sub_lib::ui_gateway::UiGatewayConfig;
+use crate::sub_lib::ui_gateway::{UiGatewayConfig, UiSecurity};
 use crate::ui_gateway::UiGateway;
 use actix::{Actor, System, SystemRunner};
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -19,20 +20,33 @@ use masq_lib::command::{Command, StdStreams};
 use std::collections::HashMap;
 
 pub trait RecipientsFactory {
-    fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients;
+    fn make(
+        &self,
+        launcher: Box<dyn Launcher>,
+        ui_port: u16,
+        restart_policy_opt: Option<RestartPolicy>,
+    ) -> Recipients;
 }
 
 #[derive(Default)]
 pub struct RecipientsFactoryReal {}
 
 impl RecipientsFactory for RecipientsFactoryReal {
-    fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients {
+    fn make(
+        &self,
+        launcher: Box<dyn Launcher>,
+        ui_port: u16,
+        restart_policy_opt: Option<RestartPolicy>,
+    ) -> Recipients {
         let ui_gateway_addr = UiGateway::new(&UiGatewayConfig {
             ui_port,
+            ui_security: UiSecurity::default(),
             node_descriptor: "".to_string(), // irrelevant; field should be removed
         })
         .start();
-        let daemon_addr = Daemon::new(launcher).start();
+        let daemon_addr = Daemon::new(launcher)
+            .with_restart_policy(restart_policy_opt)
+            .start();
         Recipients {
             ui_gateway_from_sub: ui_gateway_addr.clone().recipient(),
             ui_gateway_to_sub: ui_gateway_addr.clone().recipient(),
@@ -134,9 +148,11 @@ impl DaemonInitializer {
 
     fn bind(&mut self, sender: Sender<HashMap<String, String>>) -> u8 {
         let launcher = LauncherReal::new(sender);
-        let recipients = self
-            .recipients_factory
-            .make(Box::new(launcher), self.config.ui_port);
+        let recipients = self.recipients_factory.make(
+            Box::new(launcher),
+            self.config.ui_port,
+            self.config.restart_policy_opt.clone(),
+        );
         let bind_message = DaemonBindMessage {
             to_ui_message_recipient: recipients.ui_gateway_to_sub,
             from_ui_message_recipient: recipients.ui_gateway_from_sub,
@@ -179,15 +195,26 @@ mod tests {
     use std::iter::FromIterator;
     use std::net::{SocketAddr, TcpListener};
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    type RecipientsFactoryMakeParams = Vec<(Box<dyn Launcher>, u16, Option<RestartPolicy>)>;
 
     struct RecipientsFactoryMock {
-        make_params: Arc<Mutex<Vec<(Box<dyn Launcher>, u16)>>>,
+        make_params: Arc<Mutex<RecipientsFactoryMakeParams>>,
         make_results: RefCell<Vec<Recipients>>,
     }
 
     impl RecipientsFactory for RecipientsFactoryMock {
-        fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients {
-            self.make_params.lock().unwrap().push((launcher, ui_port));
+        fn make(
+            &self,
+            launcher: Box<dyn Launcher>,
+            ui_port: u16,
+            restart_policy_opt: Option<RestartPolicy>,
+        ) -> Recipients {
+            self.make_params
+                .lock()
+                .unwrap()
+                .push((launcher, ui_port, restart_policy_opt));
             self.make_results.borrow_mut().remove(0)
         }
     }
@@ -200,6 +227,11 @@ mod tests {
             }
         }
 
+        fn make_params(mut self, params: &Arc<Mutex<RecipientsFactoryMakeParams>>) -> Self {
+            self.make_params = params.clone();
+            self
+        }
+
         fn make_result(self, result: Recipients) -> Self {
             self.make_results.borrow_mut().push(result);
             self
@@ -281,9 +313,16 @@ mod tests {
             .data_dir_result(Some(data_dir));
         let logger_initializer_wrapper = LoggerInitializerWrapperMock::new();
         let port = find_free_port();
-        let config = InitializationConfig { ui_port: port };
+        let restart_policy = RestartPolicy::new(3, Duration::from_secs(600));
+        let config = InitializationConfig {
+            ui_port: port,
+            restart_policy_opt: Some(restart_policy.clone()),
+        };
         let channel_factory = ChannelFactoryMock::new();
-        let addr_factory = RecipientsFactoryMock::new().make_result(recipients);
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let addr_factory = RecipientsFactoryMock::new()
+            .make_params(&make_params_arc)
+            .make_result(recipients);
         let rerunner = RerunnerMock::new();
         let mut subject = DaemonInitializer::new(
             &dirs_wrapper,
@@ -304,6 +343,10 @@ mod tests {
         let _ = daemon_recording.get_record::<DaemonBindMessage>(0);
         assert_eq!(ui_gateway_recording.len(), 1);
         assert_eq!(daemon_recording.len(), 1);
+        let make_params = make_params_arc.lock().unwrap();
+        assert_eq!(make_params.len(), 1);
+        assert_eq!(make_params[0].1, port);
+        assert_eq!(make_params[0].2, Some(restart_policy));
     }
 
     #[test]
@@ -319,7 +362,10 @@ mod tests {
             .data_dir_result(Some(data_dir));
         let logger_initializer_wrapper = LoggerInitializerWrapperMock::new();
         let port = find_free_port();
-        let config = InitializationConfig { ui_port: port };
+        let config = InitializationConfig {
+            ui_port: port,
+            restart_policy_opt: None,
+        };
         let (sender, receiver) = unbounded();
         let channel_factory = ChannelFactoryMock::new();
         let addr_factory = RecipientsFactoryMock::new();
@@ -370,7 +416,10 @@ mod tests {
         let mut subject = DaemonInitializer::new(
             &dirs_wrapper,
             Box::new(logger_initializer_wrapper),
-            InitializationConfig { ui_port: port },
+            InitializationConfig {
+                ui_port: port,
+                restart_policy_opt: None,
+            },
             Box::new(ChannelFactoryMock::new()),
             Box::new(RecipientsFactoryMock::new()),
             Box::new(RerunnerMock::new()),
@@ -8,7 +8,7 @@ use masq_lib::messages::NODE_UI_PROTOCOL;
 use std::thread;
 use std::time::Duration;
 use sysinfo::{ProcessExt, ProcessStatus, Signal, SystemExt};
-use websocket::ClientBuilder;
+use websocket::{ClientBuilder, WebSocketError};
 
 // Note: if the INTERVALs are half the DELAYs or greater, the tests below will need to change,
 // because they depend on being able to fail twice and still succeed.
@@ -34,7 +34,12 @@ impl VerifierTools for VerifierToolsReal {
             Ok(builder) => builder.add_protocol(NODE_UI_PROTOCOL),
             Err(e) => panic!(format!("{:?}", e)),
         };
-        builder.connect_insecure().is_ok()
+        // A Node that demands UI tokens refuses our token-less upgrade, but it's up and answering.
+        match builder.connect_insecure() {
+            Ok(_) => true,
+            Err(WebSocketError::IoError(_)) => false,
+            Err(_) => true,
+        }
     }
 
     fn process_is_running(&self, process_id: u32) -> bool {
@@ -351,6 +356,24 @@ mod tests {
         assert_eq!(result, true);
     }
 
+    #[test]
+    fn can_connect_to_ui_gateway_counts_refused_upgrade_as_success() {
+        let port = find_free_port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let mut server = Server::bind(SocketAddr::new(localhost(), port)).unwrap();
+            tx.send(()).unwrap();
+            let upgrade = server.accept().expect("Couldn't accept connection");
+            let _ = upgrade.reject();
+        });
+        let subject = VerifierToolsReal::new();
+        rx.recv().unwrap();
+
+        let result = subject.can_connect_to_ui_gateway(port);
+
+        assert_eq!(result, true);
+    }
+
     #[test]
     fn can_connect_to_ui_gateway_handles_failure() {
         let port = find_free_port();
@@ -4,32 +4,48 @@ pub mod crash_notification;
 pub mod daemon_initializer;
 pub mod launch_verifier;
 mod launcher;
+pub mod restart_policy;
+mod setup_profiles;
 mod setup_reporter;
 
 #[cfg(test)]
 mod mocks;
 
+use crate::bootstrapper::RealUser;
 use crate::daemon::crash_notification::CrashNotification;
 use crate::daemon::launch_verifier::{VerifierTools, VerifierToolsReal};
+use crate::daemon::restart_policy::{RestartDecision, RestartPolicy, RestartSupervisor};
+use crate::daemon::setup_profiles::SetupProfiles;
 use crate::daemon::setup_reporter::{SetupCluster, SetupReporter, SetupReporterReal};
+use crate::node_configurator::{DirsWrapper, RealDirsWrapper};
+use crate::privilege_drop::{PrivilegeDropper, PrivilegeDropperReal};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
+use crate::ui_gateway::ui_tokens::UiTokens;
 use actix::Recipient;
-use actix::{Actor, Context, Handler, Message};
+use actix::{Actor, AsyncContext, Context, Handler, Message};
 use crossbeam_channel::{Receiver, Sender};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use masq_lib::messages::UiSetupResponseValueStatus::{Configured, Set};
 use masq_lib::messages::{
-    FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast, UiRedirect, UiSetupBroadcast,
-    UiSetupRequest, UiSetupResponse, UiSetupResponseValue, UiStartOrder, UiStartResponse,
-    NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
+    FromMessageBody, ToMessageBody, UiIssueTokenRequest, UiIssueTokenResponse,
+    UiNodeCrashedBroadcast, UiNodeRestartAbandonedBroadcast, UiNodeRestartedBroadcast,
+    UiNodeRestartingBroadcast, UiProfileDeleteRequest, UiProfileDeleteResponse,
+    UiProfileListRequest, UiProfileListResponse, UiProfileLoadRequest, UiProfileLoadResponse,
+    UiProfileSaveRequest, UiProfileSaveResponse, UiRedirect, UiSetupBroadcast, UiSetupRequest,
+    UiSetupRequestValue, UiSetupResponse, UiSetupResponseValue, UiStartOrder, UiStartResponse,
+    NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR, PROFILE_ERROR,
+    SETUP_ERROR, UNAUTHORIZED_ERROR,
 };
 use masq_lib::shared_schema::ConfiguratorError;
 use masq_lib::ui_gateway::MessagePath::{Conversation, FireAndForget};
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{MessageBody, MessageTarget, NodeFromUiMessage, NodeToUiMessage};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 pub struct Recipients {
     ui_gateway_from_sub: Recipient<NodeFromUiMessage>,
@@ -44,6 +60,7 @@ lazy_static! {
         vec![
             ("db-password".to_string(), 16),
             ("consuming-private-key".to_string(), 64),
+            ("ui-tls-password".to_string(), 16),
         ]
         .into_iter()
         .collect()
@@ -91,15 +108,25 @@ pub struct DaemonBindMessage {
     pub crash_notification_recipient: Recipient<CrashNotification>, // the Daemon itself, for crash notifications
 }
 
+#[derive(Message, PartialEq, Clone, Debug)]
+struct RestartNodeMessage {
+    crashed_process_id: u32,
+    attempt: u32,
+}
+
 pub struct Daemon {
     launcher: Box<dyn Launcher>,
     params: SetupCluster,
+    last_launch_params: HashMap<String, String>,
+    restart_supervisor_opt: Option<RestartSupervisor>,
     ui_gateway_sub: Option<Recipient<NodeToUiMessage>>,
     crash_notification_sub: Option<Recipient<CrashNotification>>,
     node_process_id: Option<u32>,
     node_ui_port: Option<u16>,
     verifier_tools: Box<dyn VerifierTools>,
     setup_reporter: Box<dyn SetupReporter>,
+    setup_profiles: SetupProfiles,
+    privilege_dropper: Box<dyn PrivilegeDropper>,
     logger: Logger,
 }
 
@@ -132,6 +159,16 @@ impl Handler<NodeFromUiMessage> for Daemon {
             self.handle_setup(client_id, context_id, setup_request);
         } else if let Ok((_, context_id)) = UiStartOrder::fmb(msg.body.clone()) {
             self.handle_start_order(client_id, context_id);
+        } else if let Ok((request, context_id)) = UiIssueTokenRequest::fmb(msg.body.clone()) {
+            self.handle_issue_token(client_id, context_id, request);
+        } else if let Ok((request, context_id)) = UiProfileSaveRequest::fmb(msg.body.clone()) {
+            self.handle_profile_save(client_id, context_id, request);
+        } else if let Ok((_, context_id)) = UiProfileListRequest::fmb(msg.body.clone()) {
+            self.handle_profile_list(client_id, context_id);
+        } else if let Ok((request, context_id)) = UiProfileLoadRequest::fmb(msg.body.clone()) {
+            self.handle_profile_load(client_id, context_id, request);
+        } else if let Ok((request, context_id)) = UiProfileDeleteRequest::fmb(msg.body.clone()) {
+            self.handle_profile_delete(client_id, context_id, request);
         } else {
             self.handle_unexpected_message(client_id, msg.body);
         }
@@ -142,28 +179,56 @@ impl Handler<NodeFromUiMessage> for Daemon {
 impl Handler<CrashNotification> for Daemon {
     type Result = ();
 
-    fn handle(&mut self, msg: CrashNotification, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: CrashNotification, ctx: &mut Self::Context) -> Self::Result {
         debug!(&self.logger, "Handling CrashNotification");
-        self.handle_crash_notification(msg);
+        if let Some((restart_msg, delay)) = self.handle_crash_notification(msg) {
+            ctx.notify_later(restart_msg, delay);
+        }
         debug!(&self.logger, "CrashNotification handled");
     }
 }
 
+impl Handler<RestartNodeMessage> for Daemon {
+    type Result = ();
+
+    fn handle(&mut self, msg: RestartNodeMessage, ctx: &mut Self::Context) -> Self::Result {
+        debug!(&self.logger, "Handling RestartNodeMessage");
+        if let Some((restart_msg, delay)) = self.handle_restart(msg) {
+            ctx.notify_later(restart_msg, delay);
+        }
+        debug!(&self.logger, "RestartNodeMessage handled");
+    }
+}
+
 impl Daemon {
     pub fn new(launcher: Box<dyn Launcher>) -> Daemon {
         Daemon {
             launcher,
             params: HashMap::new(),
+            last_launch_params: HashMap::new(),
+            restart_supervisor_opt: None,
             ui_gateway_sub: None,
             crash_notification_sub: None,
             node_process_id: None,
             node_ui_port: None,
             verifier_tools: Box::new(VerifierToolsReal::new()),
             setup_reporter: Box::new(SetupReporterReal::new()),
+            setup_profiles: SetupProfiles::new(
+                &RealDirsWrapper {}
+                    .data_dir()
+                    .expect("No data directory")
+                    .join("MASQ"),
+            ),
+            privilege_dropper: Box::new(PrivilegeDropperReal::new()),
             logger: Logger::new("Daemon"),
         }
     }
 
+    pub fn with_restart_policy(mut self, restart_policy_opt: Option<RestartPolicy>) -> Daemon {
+        self.restart_supervisor_opt = restart_policy_opt.map(RestartSupervisor::new);
+        self
+    }
+
     fn handle_setup(&mut self, client_id: u64, context_id: u64, payload: UiSetupRequest) {
         if self.port_if_node_is_running().is_some() {
             let body =
@@ -205,14 +270,11 @@ impl Daemon {
                 },
             ),
             None => match self.launcher.launch(
-                self.params
-                    .iter()
-                    .filter(|(_, v)| v.status == Set || v.status == Configured)
-                    .map(|(k, v)| (k.to_string(), v.value.to_string()))
-                    .collect(),
+                self.launch_params(),
                 self.crash_notification_sub.clone().expect("Daemon unbound"),
             ) {
                 Ok(Some(success)) => {
+                    self.last_launch_params = self.launch_params();
                     self.node_process_id = Some(success.new_process_id);
                     self.node_ui_port = Some(success.redirect_ui_port);
                     self.respond_to_ui(
@@ -237,6 +299,227 @@ impl Daemon {
         }
     }
 
+    // The first token for a data directory goes into a file only the real user can read; after
+    // that, only a UI that already holds a token may get another one.
+    fn handle_issue_token(
+        &mut self,
+        client_id: u64,
+        context_id: u64,
+        payload: UiIssueTokenRequest,
+    ) {
+        let error_body = |code: u64, message: String| MessageBody {
+            opcode: "issueToken".to_string(),
+            path: Conversation(context_id),
+            payload: Err((code, message)),
+        };
+        let data_directory = match self.params.get("data-directory") {
+            Some(value) if !value.value.is_empty() => PathBuf::from(&value.value),
+            _ => {
+                return self.respond_to_ui(
+                    client_id,
+                    error_body(
+                        SETUP_ERROR,
+                        "Cannot issue UI token: data directory is not yet known".to_string(),
+                    ),
+                )
+            }
+        };
+        let tokens = UiTokens::new(&data_directory);
+        if tokens.is_empty() {
+            return self.issue_first_token(client_id, context_id, &tokens);
+        }
+        let authorized = payload
+            .current_token_opt
+            .map(|token| tokens.is_valid(&token))
+            .unwrap_or(false);
+        if !authorized {
+            warning!(
+                &self.logger,
+                "Refusing to issue UI token to UI {}: no valid current token",
+                client_id
+            );
+            return self.respond_to_ui(
+                client_id,
+                error_body(
+                    UNAUTHORIZED_ERROR,
+                    "Cannot issue UI token: a valid current token is required".to_string(),
+                ),
+            );
+        }
+        let body = match tokens.issue() {
+            Ok(token) => {
+                info!(
+                    &self.logger,
+                    "Issued UI token to UI {} in {:?}",
+                    client_id,
+                    tokens.path()
+                );
+                UiIssueTokenResponse {
+                    token_opt: Some(token),
+                    token_file_opt: None,
+                }
+                .tmb(context_id)
+            }
+            Err(e) => error_body(SETUP_ERROR, format!("Cannot issue UI token: {}", e)),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    // The Daemon usually runs as root, so the file it writes the first token into has to be handed
+    // over to the user who will run masq.
+    fn issue_first_token(&mut self, client_id: u64, context_id: u64, tokens: &UiTokens) {
+        let body = match tokens.issue_first() {
+            Ok(token_path) => {
+                if let Some(real_user) = self.real_user_opt() {
+                    self.privilege_dropper.chown(&token_path, &real_user);
+                }
+                info!(
+                    &self.logger,
+                    "Wrote first UI token into {:?} for UI {}", token_path, client_id
+                );
+                UiIssueTokenResponse {
+                    token_opt: None,
+                    token_file_opt: Some(token_path.to_string_lossy().to_string()),
+                }
+                .tmb(context_id)
+            }
+            Err(e) => MessageBody {
+                opcode: "issueToken".to_string(),
+                path: Conversation(context_id),
+                payload: Err((SETUP_ERROR, format!("Cannot issue UI token: {}", e))),
+            },
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn real_user_opt(&self) -> Option<RealUser> {
+        self.params
+            .get("real-user")
+            .and_then(|value| RealUser::from_str(&value.value).ok())
+            .filter(|real_user| real_user.uid_opt.is_some() && real_user.gid_opt.is_some())
+    }
+
+    // Secrets are never written into profiles; whatever the setup holds for them is kept when a
+    // profile is loaded.
+    fn handle_profile_save(
+        &mut self,
+        client_id: u64,
+        context_id: u64,
+        payload: UiProfileSaveRequest,
+    ) {
+        let values: BTreeMap<String, String> = self
+            .launch_params()
+            .into_iter()
+            .filter(|(name, _)| !CENSORABLES.contains_key(name))
+            .collect();
+        let body = match self.setup_profiles.save(&payload.name, &values) {
+            Ok(_) => {
+                info!(&self.logger, "Saved setup profile '{}'", payload.name);
+                UiProfileSaveResponse {
+                    saved: values.keys().cloned().collect(),
+                }
+                .tmb(context_id)
+            }
+            Err(e) => Self::profile_error_body("profileSave", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn handle_profile_list(&mut self, client_id: u64, context_id: u64) {
+        let body = match self.setup_profiles.list() {
+            Ok(profiles) => UiProfileListResponse { profiles }.tmb(context_id),
+            Err(e) => Self::profile_error_body("profileList", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn handle_profile_load(
+        &mut self,
+        client_id: u64,
+        context_id: u64,
+        payload: UiProfileLoadRequest,
+    ) {
+        if self.port_if_node_is_running().is_some() {
+            return self.respond_to_ui(
+                client_id,
+                MessageBody {
+                    opcode: "profileLoad".to_string(),
+                    path: Conversation(context_id),
+                    payload: Err((
+                        NODE_ALREADY_RUNNING_ERROR,
+                        "Cannot load profile: Node is running".to_string(),
+                    )),
+                },
+            );
+        }
+        let profile = match self.setup_profiles.load(&payload.name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                return self.respond_to_ui(
+                    client_id,
+                    Self::profile_error_body("profileLoad", context_id, e),
+                )
+            }
+        };
+        let mut incoming_setup = self
+            .params
+            .values()
+            .filter(|value| value.status == Set)
+            .filter(|value| !profile.contains_key(&value.name))
+            .filter(|value| !CENSORABLES.contains_key(&value.name))
+            .map(|value| UiSetupRequestValue::clear(&value.name))
+            .collect::<Vec<UiSetupRequestValue>>();
+        incoming_setup.extend(
+            profile
+                .iter()
+                .map(|(name, value)| UiSetupRequestValue::new(name, value)),
+        );
+        let (new_setup, errors) = match self
+            .setup_reporter
+            .get_modified_setup(self.params.clone(), incoming_setup)
+        {
+            Ok(setup) => (setup, ConfiguratorError::new(vec![])),
+            Err((lame_cluster, errors)) => (lame_cluster, errors),
+        };
+        let changed = Self::compare_setup_clusters(&self.params, &new_setup).is_err();
+        self.params = new_setup;
+        info!(&self.logger, "Loaded setup profile '{}'", payload.name);
+        self.respond_to_ui(
+            client_id,
+            UiProfileLoadResponse::new(self.censored_params(), errors.clone()).tmb(context_id),
+        );
+        if changed {
+            self.send_ui_message(
+                UiSetupBroadcast::new(false, self.censored_params(), errors).tmb(0),
+                MessageTarget::AllExcept(client_id),
+            );
+        }
+    }
+
+    fn handle_profile_delete(
+        &mut self,
+        client_id: u64,
+        context_id: u64,
+        payload: UiProfileDeleteRequest,
+    ) {
+        let body = match self.setup_profiles.delete(&payload.name) {
+            Ok(_) => {
+                info!(&self.logger, "Deleted setup profile '{}'", payload.name);
+                UiProfileDeleteResponse {}.tmb(context_id)
+            }
+            Err(e) => Self::profile_error_body("profileDelete", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn profile_error_body(opcode: &str, context_id: u64, message: String) -> MessageBody {
+        MessageBody {
+            opcode: opcode.to_string(),
+            path: Conversation(context_id),
+            payload: Err((PROFILE_ERROR, message)),
+        }
+    }
+
     fn handle_unexpected_message(&mut self, client_id: u64, body: MessageBody) {
         match self.port_if_node_is_running() {
             Some(port) => {
@@ -268,7 +551,10 @@ impl Daemon {
         }
     }
 
-    fn handle_crash_notification(&mut self, msg: CrashNotification) {
+    fn handle_crash_notification(
+        &mut self,
+        msg: CrashNotification,
+    ) -> Option<(RestartNodeMessage, Duration)> {
         if self.node_ui_port.is_some() || self.node_process_id.is_some() {
             self.node_process_id = None;
             self.node_ui_port = None;
@@ -280,6 +566,109 @@ impl Daemon {
                 .tmb(0),
                 MessageTarget::AllClients,
             );
+            // A Node that exits cleanly was told to shut down; it didn't crash.
+            if msg.exit_code != Some(0) {
+                return self.schedule_restart(msg.process_id);
+            }
+        }
+        None
+    }
+
+    fn handle_restart(
+        &mut self,
+        msg: RestartNodeMessage,
+    ) -> Option<(RestartNodeMessage, Duration)> {
+        if self.port_if_node_is_running().is_some() {
+            info!(
+                &self.logger,
+                "Node was started before restart attempt {} was due; not restarting it",
+                msg.attempt
+            );
+            return None;
+        }
+        match self.launcher.launch(
+            self.last_launch_params.clone(),
+            self.crash_notification_sub.clone().expect("Daemon unbound"),
+        ) {
+            Ok(Some(success)) => {
+                info!(
+                    &self.logger,
+                    "Restarted Node as process {} on attempt {}",
+                    success.new_process_id,
+                    msg.attempt
+                );
+                self.node_process_id = Some(success.new_process_id);
+                self.node_ui_port = Some(success.redirect_ui_port);
+                self.send_ui_message(
+                    UiNodeRestartedBroadcast {
+                        new_process_id: success.new_process_id,
+                        redirect_ui_port: success.redirect_ui_port,
+                        attempt: msg.attempt,
+                    }
+                    .tmb(0),
+                    MessageTarget::AllClients,
+                );
+                None
+            }
+            Ok(None) => None,
+            Err(s) => {
+                error!(
+                    &self.logger,
+                    "Could not restart Node on attempt {}: {}", msg.attempt, s
+                );
+                self.schedule_restart(msg.crashed_process_id)
+            }
+        }
+    }
+
+    fn schedule_restart(
+        &mut self,
+        crashed_process_id: u32,
+    ) -> Option<(RestartNodeMessage, Duration)> {
+        let supervisor = self.restart_supervisor_opt.as_mut()?;
+        let window = supervisor.policy().window;
+        match supervisor.decide(Instant::now()) {
+            RestartDecision::Restart { attempt, delay } => {
+                info!(
+                    &self.logger,
+                    "Restarting Node in {}ms (attempt {})",
+                    delay.as_millis(),
+                    attempt
+                );
+                self.send_ui_message(
+                    UiNodeRestartingBroadcast {
+                        crashed_process_id,
+                        attempt,
+                        delay_ms: delay.as_millis() as u64,
+                    }
+                    .tmb(0),
+                    MessageTarget::AllClients,
+                );
+                Some((
+                    RestartNodeMessage {
+                        crashed_process_id,
+                        attempt,
+                    },
+                    delay,
+                ))
+            }
+            RestartDecision::GiveUp { restarts } => {
+                warning!(
+                    &self.logger,
+                    "Node has been restarted {} times in {} minutes; leaving it down",
+                    restarts,
+                    window.as_secs() / 60
+                );
+                self.send_ui_message(
+                    UiNodeRestartAbandonedBroadcast {
+                        restarts,
+                        window_minutes: window.as_secs() / 60,
+                    }
+                    .tmb(0),
+                    MessageTarget::AllClients,
+                );
+                None
+            }
         }
     }
 
@@ -300,6 +689,14 @@ impl Daemon {
         }
     }
 
+    fn launch_params(&self) -> HashMap<String, String> {
+        self.params
+            .iter()
+            .filter(|(_, v)| v.status == Set || v.status == Configured)
+            .map(|(k, v)| (k.to_string(), v.value.to_string()))
+            .collect()
+    }
+
     fn send_node_is_not_running_error(&self, client_id: u64, err_opcode: String) {
         error!(
             &self.logger,
@@ -427,14 +824,16 @@ mod tests {
     use crate::daemon::mocks::VerifierToolsMock;
     use crate::daemon::setup_reporter::{setup_cluster_from, SetupCluster};
     use crate::daemon::LaunchSuccess;
+    use crate::server_initializer::test_utils::PrivilegeDropperMock;
     use crate::test_utils::recorder::{make_recorder, Recorder};
+    use crate::ui_gateway::ui_tokens::FIRST_UI_TOKEN_FILENAME;
     use actix::System;
     use masq_lib::messages::UiSetupResponseValueStatus::{Blank, Required, Set};
     use masq_lib::messages::{
-        CrashReason, UiFinancialsRequest, UiNodeCrashedBroadcast, UiRedirect, UiSetupBroadcast,
-        UiSetupRequest, UiSetupRequestValue, UiSetupResponse, UiSetupResponseValue,
-        UiSetupResponseValueStatus, UiShutdownRequest, UiStartOrder, UiStartResponse,
-        NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
+        CrashReason, UiFinancialsRequest, UiIssueTokenResponse, UiNodeCrashedBroadcast, UiRedirect,
+        UiSetupBroadcast, UiSetupRequest, UiSetupRequestValue, UiSetupResponse,
+        UiSetupResponseValue, UiSetupResponseValueStatus, UiShutdownRequest, UiStartOrder,
+        UiStartResponse, NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
     };
     use masq_lib::shared_schema::ConfiguratorError;
     use masq_lib::test_utils::environment_guard::{ClapGuard, EnvironmentGuard};
@@ -515,7 +914,7 @@ mod tests {
             }
         }
 
-        fn _get_modified_setup_params(
+        fn get_modified_setup_params(
             mut self,
             params: &Arc<Mutex<Vec<(SetupCluster, Vec<UiSetupRequestValue>)>>>,
         ) -> Self {
@@ -1567,4 +1966,647 @@ mod tests {
         let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
         assert_eq!(ui_gateway_recording.len(), 0);
     }
+
+    fn make_restart_policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            window: Duration::from_secs(3600),
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(1000),
+        }
+    }
+
+    fn assert_broadcast<T: ToMessageBody>(record: &NodeToUiMessage, expected: T) {
+        assert_eq!(record.target, MessageTarget::AllClients);
+        assert_eq!(record.body, expected.tmb(0));
+    }
+
+    #[test]
+    fn crash_notification_with_restart_policy_broadcasts_and_schedules_restart() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()))
+            .with_restart_policy(Some(make_restart_policy(2)));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(12345);
+
+        let result = subject.handle_crash_notification(CrashNotification {
+            process_id: 12345,
+            exit_code: Some(101),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(
+            result,
+            Some((
+                RestartNodeMessage {
+                    crashed_process_id: 12345,
+                    attempt: 1,
+                },
+                Duration::from_millis(10)
+            ))
+        );
+        assert_eq!(subject.node_process_id, None);
+        assert_eq!(subject.node_ui_port, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(0),
+            UiNodeCrashedBroadcast {
+                process_id: 12345,
+                crash_reason: CrashReason::Unrecognized(String::new()),
+            },
+        );
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(1),
+            UiNodeRestartingBroadcast {
+                crashed_process_id: 12345,
+                attempt: 1,
+                delay_ms: 10,
+            },
+        );
+        assert_eq!(ui_gateway_recording.len(), 2);
+    }
+
+    #[test]
+    fn clean_exit_is_not_restarted() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()))
+            .with_restart_policy(Some(make_restart_policy(2)));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(12345);
+
+        let result = subject.handle_crash_notification(CrashNotification {
+            process_id: 12345,
+            exit_code: Some(0),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 1);
+    }
+
+    #[test]
+    fn crash_notification_without_restart_policy_schedules_nothing() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(12345);
+
+        let result = subject.handle_crash_notification(CrashNotification {
+            process_id: 12345,
+            exit_code: Some(101),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 1);
+    }
+
+    #[test]
+    fn restart_relaunches_node_with_last_launch_params_and_broadcasts() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let (daemon, _, _) = make_recorder();
+        let launch_params_arc = Arc::new(Mutex::new(vec![]));
+        let launcher = LauncherMock::new()
+            .launch_params(&launch_params_arc)
+            .launch_result(Ok(Some(LaunchSuccess {
+                new_process_id: 2345,
+                redirect_ui_port: 5432,
+            })))
+            .launch_result(Ok(Some(LaunchSuccess {
+                new_process_id: 3456,
+                redirect_ui_port: 6543,
+            })));
+        let system = System::new("test");
+        let mut subject =
+            Daemon::new(Box::new(launcher)).with_restart_policy(Some(make_restart_policy(2)));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.crash_notification_sub = Some(daemon.start().recipient());
+        subject.params.insert(
+            "db-password".to_string(),
+            UiSetupResponseValue::new("db-password", "goober", Set),
+        );
+        subject.handle_start_order(1234, 4321);
+        let (restart_msg, _) = subject
+            .handle_crash_notification(CrashNotification {
+                process_id: 2345,
+                exit_code: Some(101),
+                stderr: None,
+            })
+            .unwrap();
+        subject.params.insert(
+            "db-password".to_string(),
+            UiSetupResponseValue::new("db-password", "changed", Set),
+        );
+
+        let result = subject.handle_restart(restart_msg);
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, None);
+        assert_eq!(subject.node_process_id, Some(3456));
+        assert_eq!(subject.node_ui_port, Some(6543));
+        let expected_params: HashMap<String, String> =
+            HashMap::from_iter(vec![("db-password".to_string(), "goober".to_string())]);
+        let launch_params = launch_params_arc.lock().unwrap();
+        assert_eq!(
+            launch_params.iter().map(|x| &x.0).collect::<Vec<_>>(),
+            vec![&expected_params, &expected_params]
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(3),
+            UiNodeRestartedBroadcast {
+                new_process_id: 3456,
+                redirect_ui_port: 6543,
+                attempt: 1,
+            },
+        );
+        assert_eq!(ui_gateway_recording.len(), 4);
+    }
+
+    #[test]
+    fn failed_restarts_back_off_and_eventually_give_up() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let (daemon, _, _) = make_recorder();
+        let launcher = LauncherMock::new()
+            .launch_result(Err("booga".to_string()))
+            .launch_result(Err("booga".to_string()));
+        let system = System::new("test");
+        let mut subject =
+            Daemon::new(Box::new(launcher)).with_restart_policy(Some(make_restart_policy(2)));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.crash_notification_sub = Some(daemon.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(12345);
+        let first_restart = subject.handle_crash_notification(CrashNotification {
+            process_id: 12345,
+            exit_code: None,
+            stderr: Some("panic".to_string()),
+        });
+
+        let second_restart = subject.handle_restart(first_restart.unwrap().0);
+        let third_restart = subject.handle_restart(second_restart.clone().unwrap().0);
+
+        System::current().stop();
+        system.run();
+        assert_eq!(
+            second_restart,
+            Some((
+                RestartNodeMessage {
+                    crashed_process_id: 12345,
+                    attempt: 2,
+                },
+                Duration::from_millis(20)
+            ))
+        );
+        assert_eq!(third_restart, None);
+        assert_eq!(subject.node_process_id, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(1),
+            UiNodeRestartingBroadcast {
+                crashed_process_id: 12345,
+                attempt: 1,
+                delay_ms: 10,
+            },
+        );
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(2),
+            UiNodeRestartingBroadcast {
+                crashed_process_id: 12345,
+                attempt: 2,
+                delay_ms: 20,
+            },
+        );
+        assert_broadcast(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(3),
+            UiNodeRestartAbandonedBroadcast {
+                restarts: 2,
+                window_minutes: 60,
+            },
+        );
+        assert_eq!(ui_gateway_recording.len(), 4);
+    }
+
+    #[test]
+    fn restart_is_skipped_if_node_was_started_in_the_meantime() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let launch_params_arc = Arc::new(Mutex::new(vec![]));
+        let launcher = LauncherMock::new().launch_params(&launch_params_arc);
+        let system = System::new("test");
+        let mut subject =
+            Daemon::new(Box::new(launcher)).with_restart_policy(Some(make_restart_policy(2)));
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.verifier_tools = Box::new(VerifierToolsMock::new().process_is_running_result(true));
+        subject.node_ui_port = Some(4444);
+        subject.node_process_id = Some(3333);
+
+        let result = subject.handle_restart(RestartNodeMessage {
+            crashed_process_id: 12345,
+            attempt: 1,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, None);
+        assert_eq!(subject.node_process_id, Some(3333));
+        assert!(launch_params_arc.lock().unwrap().is_empty());
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 0);
+    }
+
+    #[test]
+    fn writes_first_ui_token_into_a_file_for_the_real_user() {
+        let data_dir = ensure_node_home_directory_exists(
+            "daemon",
+            "writes_first_ui_token_into_a_file_for_the_real_user",
+        );
+        let chown_params_arc = Arc::new(Mutex::new(vec![]));
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.params = setup_cluster_from(vec![
+            ("data-directory", data_dir.to_str().unwrap(), Set),
+            ("real-user", "123:456:/home/booga", Set),
+        ]);
+        subject.privilege_dropper =
+            Box::new(PrivilegeDropperMock::new().chown_params(&chown_params_arc));
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiIssueTokenRequest {
+                    current_token_opt: None,
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording
+            .get_record::<NodeToUiMessage>(0)
+            .clone();
+        assert_eq!(record.target, ClientId(1234));
+        let (response, context_id) = UiIssueTokenResponse::fmb(record.body).unwrap();
+        assert_eq!(context_id, 4321);
+        let token_path = data_dir.join(FIRST_UI_TOKEN_FILENAME);
+        assert_eq!(
+            response,
+            UiIssueTokenResponse {
+                token_opt: None,
+                token_file_opt: Some(token_path.to_string_lossy().to_string()),
+            }
+        );
+        let token = std::fs::read_to_string(&token_path).unwrap();
+        assert!(UiTokens::new(&data_dir).is_valid(token.trim_end()));
+        assert_eq!(
+            *chown_params_arc.lock().unwrap(),
+            vec![(
+                token_path,
+                RealUser::new(Some(123), Some(456), Some(PathBuf::from("/home/booga")))
+            )]
+        );
+    }
+
+    #[test]
+    fn issues_later_ui_tokens_only_to_token_holders() {
+        let data_dir = ensure_node_home_directory_exists(
+            "daemon",
+            "issues_later_ui_tokens_only_to_token_holders",
+        );
+        let tokens = UiTokens::new(&data_dir);
+        let first_token = tokens.issue().unwrap();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.params =
+            setup_cluster_from(vec![("data-directory", data_dir.to_str().unwrap(), Set)]);
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+        let request = |current_token_opt: Option<&str>, context_id: u64| NodeFromUiMessage {
+            client_id: 1234,
+            body: UiIssueTokenRequest {
+                current_token_opt: current_token_opt.map(|token| token.to_string()),
+            }
+            .tmb(context_id),
+        };
+
+        subject_addr.try_send(request(None, 1)).unwrap();
+        subject_addr.try_send(request(Some("booga"), 2)).unwrap();
+        subject_addr
+            .try_send(request(Some(&first_token), 3))
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let refusal = (
+            UNAUTHORIZED_ERROR,
+            "Cannot issue UI token: a valid current token is required".to_string(),
+        );
+        (0..2).for_each(|index| {
+            let record = ui_gateway_recording
+                .get_record::<NodeToUiMessage>(index)
+                .clone();
+            assert_eq!(&record.body.opcode, "issueToken");
+            assert_eq!(record.body.payload.err().unwrap(), refusal);
+        });
+        let record = ui_gateway_recording
+            .get_record::<NodeToUiMessage>(2)
+            .clone();
+        let (response, context_id) = UiIssueTokenResponse::fmb(record.body).unwrap();
+        assert_eq!(context_id, 3);
+        assert_eq!(response.token_file_opt, None);
+        let token = response.token_opt.unwrap();
+        assert_ne!(token, first_token);
+        assert!(tokens.is_valid(&first_token));
+        assert!(tokens.is_valid(&token));
+    }
+
+    #[test]
+    fn refuses_to_issue_ui_token_before_data_directory_is_known() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let subject = Daemon::new(Box::new(LauncherMock::new()));
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiIssueTokenRequest {
+                    current_token_opt: None,
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording
+            .get_record::<NodeToUiMessage>(0)
+            .clone();
+        assert_eq!(record.target, ClientId(1234));
+        assert_eq!(
+            record.body.payload.err().unwrap(),
+            (
+                SETUP_ERROR,
+                "Cannot issue UI token: data directory is not yet known".to_string()
+            )
+        );
+    }
+
+    fn send_to_daemon_and_collect_responses(
+        subject: Daemon,
+        bodies: Vec<MessageBody>,
+    ) -> Vec<NodeToUiMessage> {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+
+        bodies.into_iter().for_each(|body| {
+            subject_addr
+                .try_send(NodeFromUiMessage {
+                    client_id: 1234,
+                    body,
+                })
+                .unwrap()
+        });
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        (0..ui_gateway_recording.len())
+            .map(|index| {
+                ui_gateway_recording
+                    .get_record::<NodeToUiMessage>(index)
+                    .clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn profile_save_keeps_specified_values_but_not_secrets_and_profile_list_shows_it() {
+        let home_dir = ensure_node_home_directory_exists(
+            "daemon",
+            "profile_save_keeps_specified_values_but_not_secrets_and_profile_list_shows_it",
+        );
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.setup_profiles = SetupProfiles::new(&home_dir);
+        subject.params = setup_cluster_from(vec![
+            ("chain", "mainnet", Set),
+            ("log-level", "info", Configured),
+            ("db-password", "secret", Set),
+            (
+                "data-directory",
+                "/home/booga",
+                UiSetupResponseValueStatus::Default,
+            ),
+            ("neighbors", "", Blank),
+        ]);
+
+        let records = send_to_daemon_and_collect_responses(
+            subject,
+            vec![
+                UiProfileSaveRequest {
+                    name: "mainnet".to_string(),
+                }
+                .tmb(4321),
+                UiProfileListRequest {}.tmb(4322),
+            ],
+        );
+
+        assert_eq!(records[0].target, ClientId(1234));
+        assert_eq!(
+            records[0].body,
+            UiProfileSaveResponse {
+                saved: vec!["chain".to_string(), "log-level".to_string()],
+            }
+            .tmb(4321)
+        );
+        assert_eq!(
+            records[1].body,
+            UiProfileListResponse {
+                profiles: vec!["mainnet".to_string()],
+            }
+            .tmb(4322)
+        );
+        assert_eq!(
+            SetupProfiles::new(&home_dir).load("mainnet"),
+            Ok(BTreeMap::from_iter(vec![
+                ("chain".to_string(), "mainnet".to_string()),
+                ("log-level".to_string(), "info".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn profile_load_replaces_set_values_keeps_secrets_and_broadcasts_new_setup() {
+        let home_dir = ensure_node_home_directory_exists(
+            "daemon",
+            "profile_load_replaces_set_values_keeps_secrets_and_broadcasts_new_setup",
+        );
+        let setup_profiles = SetupProfiles::new(&home_dir);
+        setup_profiles
+            .save(
+                "testing",
+                &BTreeMap::from_iter(vec![
+                    ("chain".to_string(), "ropsten".to_string()),
+                    ("log-level".to_string(), "trace".to_string()),
+                ]),
+            )
+            .unwrap();
+        let existing_setup = setup_cluster_from(vec![
+            ("chain", "mainnet", Set),
+            ("neighbors", "masq://booga", Set),
+            ("db-password", "secret", Set),
+        ]);
+        let new_setup = setup_cluster_from(vec![
+            ("chain", "ropsten", Set),
+            ("log-level", "trace", Set),
+            ("db-password", "secret", Set),
+        ]);
+        let get_modified_setup_params_arc = Arc::new(Mutex::new(vec![]));
+        let setup_reporter = SetupReporterMock::new()
+            .get_modified_setup_params(&get_modified_setup_params_arc)
+            .get_modified_setup_result(Ok(new_setup));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.setup_profiles = setup_profiles;
+        subject.setup_reporter = Box::new(setup_reporter);
+        subject.params = existing_setup.clone();
+
+        let records = send_to_daemon_and_collect_responses(
+            subject,
+            vec![UiProfileLoadRequest {
+                name: "testing".to_string(),
+            }
+            .tmb(4321)],
+        );
+
+        let get_modified_setup_params = get_modified_setup_params_arc.lock().unwrap();
+        assert_eq!(
+            *get_modified_setup_params,
+            vec![(
+                existing_setup,
+                vec![
+                    UiSetupRequestValue::clear("neighbors"),
+                    UiSetupRequestValue::new("chain", "ropsten"),
+                    UiSetupRequestValue::new("log-level", "trace"),
+                ]
+            )]
+        );
+        let expected_setup = setup_cluster_from(vec![
+            ("chain", "ropsten", Set),
+            ("log-level", "trace", Set),
+            ("db-password", "****************", Set),
+        ]);
+        assert_eq!(records[0].target, ClientId(1234));
+        assert_eq!(
+            records[0].body,
+            UiProfileLoadResponse::new(expected_setup.clone(), ConfiguratorError::new(vec![]))
+                .tmb(4321)
+        );
+        assert_eq!(records[1].target, AllExcept(1234));
+        assert_eq!(
+            records[1].body,
+            UiSetupBroadcast::new(false, expected_setup, ConfiguratorError::new(vec![])).tmb(0)
+        );
+        assert_eq!(records.len(), 2);
+    }
+
+    #[test]
+    fn profile_load_is_refused_while_node_is_running() {
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.verifier_tools = Box::new(VerifierToolsMock::new().process_is_running_result(true));
+        subject.node_process_id = Some(12345);
+        subject.node_ui_port = Some(5432);
+
+        let records = send_to_daemon_and_collect_responses(
+            subject,
+            vec![UiProfileLoadRequest {
+                name: "testing".to_string(),
+            }
+            .tmb(4321)],
+        );
+
+        assert_eq!(
+            records[0].body,
+            MessageBody {
+                opcode: "profileLoad".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    NODE_ALREADY_RUNNING_ERROR,
+                    "Cannot load profile: Node is running".to_string()
+                )),
+            }
+        );
+    }
+
+    #[test]
+    fn profile_delete_removes_profile_and_missing_profiles_are_reported() {
+        let home_dir = ensure_node_home_directory_exists(
+            "daemon",
+            "profile_delete_removes_profile_and_missing_profiles_are_reported",
+        );
+        let setup_profiles = SetupProfiles::new(&home_dir);
+        setup_profiles.save("doomed", &BTreeMap::new()).unwrap();
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        subject.setup_profiles = setup_profiles;
+
+        let records = send_to_daemon_and_collect_responses(
+            subject,
+            vec![
+                UiProfileDeleteRequest {
+                    name: "doomed".to_string(),
+                }
+                .tmb(4321),
+                UiProfileDeleteRequest {
+                    name: "doomed".to_string(),
+                }
+                .tmb(4322),
+                UiProfileLoadRequest {
+                    name: "doomed".to_string(),
+                }
+                .tmb(4323),
+            ],
+        );
+
+        let profile_error = |opcode: &str, context_id: u64| MessageBody {
+            opcode: opcode.to_string(),
+            path: MessagePath::Conversation(context_id),
+            payload: Err((PROFILE_ERROR, "No profile named 'doomed'".to_string())),
+        };
+        assert_eq!(records[0].body, UiProfileDeleteResponse {}.tmb(4321));
+        assert_eq!(records[1].body, profile_error("profileDelete", 4322));
+        assert_eq!(records[2].body, profile_error("profileLoad", 4323));
+        assert_eq!(SetupProfiles::new(&home_dir).list(), Ok(vec![]));
+    }
 }
@@ -0,0 +1,163 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+pub const DEFAULT_RESTART_WINDOW_MINUTES: u64 = 60;
+pub const INITIAL_RESTART_DELAY_MS: u64 = 1_000;
+pub const MAX_RESTART_DELAY_MS: u64 = 300_000;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RestartPolicy {
+    pub max_restarts: u32,
+    pub window: Duration,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RestartPolicy {
+    pub fn new(max_restarts: u32, window: Duration) -> Self {
+        Self {
+            max_restarts,
+            window,
+            initial_delay: Duration::from_millis(INITIAL_RESTART_DELAY_MS),
+            max_delay: Duration::from_millis(MAX_RESTART_DELAY_MS),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RestartDecision {
+    Restart { attempt: u32, delay: Duration },
+    GiveUp { restarts: u32 },
+}
+
+// Remembers when the Daemon decided to restart the Node, so that a Node that keeps crashing is
+// restarted ever more slowly and, after too many restarts in the window, not at all.
+pub struct RestartSupervisor {
+    policy: RestartPolicy,
+    restart_times: VecDeque<Instant>,
+}
+
+impl RestartSupervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            restart_times: VecDeque::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &RestartPolicy {
+        &self.policy
+    }
+
+    pub fn decide(&mut self, now: Instant) -> RestartDecision {
+        while let Some(earliest) = self.restart_times.front() {
+            if now.duration_since(*earliest) >= self.policy.window {
+                self.restart_times.pop_front();
+            } else {
+                break;
+            }
+        }
+        let restarts = self.restart_times.len() as u32;
+        if restarts >= self.policy.max_restarts {
+            return RestartDecision::GiveUp { restarts };
+        }
+        self.restart_times.push_back(now);
+        RestartDecision::Restart {
+            attempt: restarts + 1,
+            delay: self.delay_for(restarts),
+        }
+    }
+
+    fn delay_for(&self, previous_restarts: u32) -> Duration {
+        2u32.checked_pow(previous_restarts)
+            .and_then(|factor| self.policy.initial_delay.checked_mul(factor))
+            .map(|delay| delay.min(self.policy.max_delay))
+            .unwrap_or(self.policy.max_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_policy(max_restarts: u32, window_secs: u64) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            window: Duration::from_secs(window_secs),
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        }
+    }
+
+    #[test]
+    fn new_uses_default_delays() {
+        let result = RestartPolicy::new(3, Duration::from_secs(60));
+
+        assert_eq!(
+            result,
+            RestartPolicy {
+                max_restarts: 3,
+                window: Duration::from_secs(60),
+                initial_delay: Duration::from_millis(INITIAL_RESTART_DELAY_MS),
+                max_delay: Duration::from_millis(MAX_RESTART_DELAY_MS),
+            }
+        );
+    }
+
+    #[test]
+    fn delay_doubles_with_each_restart_up_to_maximum_then_supervisor_gives_up() {
+        let mut subject = RestartSupervisor::new(make_policy(6, 3600));
+        let now = Instant::now();
+
+        let results = (0..7).map(|_| subject.decide(now)).collect::<Vec<_>>();
+
+        let restart = |attempt: u32, millis: u64| RestartDecision::Restart {
+            attempt,
+            delay: Duration::from_millis(millis),
+        };
+        assert_eq!(
+            results,
+            vec![
+                restart(1, 100),
+                restart(2, 200),
+                restart(3, 400),
+                restart(4, 800),
+                restart(5, 1000),
+                restart(6, 1000),
+                RestartDecision::GiveUp { restarts: 6 },
+            ]
+        );
+    }
+
+    #[test]
+    fn restarts_outside_the_window_are_forgotten() {
+        let mut subject = RestartSupervisor::new(make_policy(2, 60));
+        let start = Instant::now();
+        subject.decide(start);
+        subject.decide(start + Duration::from_secs(30));
+
+        let give_up = subject.decide(start + Duration::from_secs(59));
+        let restart = subject.decide(start + Duration::from_secs(60));
+
+        assert_eq!(give_up, RestartDecision::GiveUp { restarts: 2 });
+        assert_eq!(
+            restart,
+            RestartDecision::Restart {
+                attempt: 2,
+                delay: Duration::from_millis(200),
+            }
+        );
+    }
+
+    #[test]
+    fn zero_max_restarts_never_restarts() {
+        let mut subject = RestartSupervisor::new(make_policy(0, 60));
+
+        let result = subject.decide(Instant::now());
+
+        assert_eq!(result, RestartDecision::GiveUp { restarts: 0 });
+    }
+}
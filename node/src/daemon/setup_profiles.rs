@@ -0,0 +1,209 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+pub const SETUP_PROFILES_DIRECTORY: &str = "profiles";
+const PROFILE_EXTENSION: &str = "json";
+const MAX_PROFILE_NAME_LENGTH: usize = 64;
+
+// Named sets of setup values the Daemon keeps on disk, one JSON file of names and values per
+// profile, so that a whole setup can be put back in one step.
+pub struct SetupProfiles {
+    directory: PathBuf,
+}
+
+impl SetupProfiles {
+    pub fn new(daemon_directory: &Path) -> Self {
+        Self {
+            directory: daemon_directory.join(SETUP_PROFILES_DIRECTORY),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<String>, String> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Couldn't list profiles: {}", e)),
+        };
+        let mut names = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(PROFILE_EXTENSION))
+            .filter_map(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .map(|stem| stem.to_string())
+            })
+            .filter(|name| Self::validate_name(name).is_ok())
+            .collect::<Vec<String>>();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn save(&self, name: &str, values: &BTreeMap<String, String>) -> Result<(), String> {
+        let path = self.path_for(name)?;
+        let json = serde_json::to_string_pretty(values).expect("Serialization problem");
+        fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(&path, json))
+            .map_err(|e| format!("Couldn't save profile '{}': {}", name, e))
+    }
+
+    pub fn load(&self, name: &str) -> Result<BTreeMap<String, String>, String> {
+        let path = self.path_for(name)?;
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                return Err(format!("No profile named '{}'", name))
+            }
+            Err(e) => return Err(format!("Couldn't read profile '{}': {}", name, e)),
+        };
+        serde_json::from_str(&json).map_err(|e| format!("Profile '{}' is corrupt: {}", name, e))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        let path = self.path_for(name)?;
+        match fs::remove_file(&path) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                Err(format!("No profile named '{}'", name))
+            }
+            Err(e) => Err(format!("Couldn't delete profile '{}': {}", name, e)),
+        }
+    }
+
+    fn path_for(&self, name: &str) -> Result<PathBuf, String> {
+        Self::validate_name(name)?;
+        Ok(self
+            .directory
+            .join(format!("{}.{}", name, PROFILE_EXTENSION)))
+    }
+
+    fn validate_name(name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || name.len() > MAX_PROFILE_NAME_LENGTH
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            Err(format!(
+                "Profile name '{}' must be 1 to {} letters, digits, '-' or '_'",
+                name, MAX_PROFILE_NAME_LENGTH
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+    use std::iter::FromIterator;
+
+    fn make_values(pairs: Vec<(&str, &str)>) -> BTreeMap<String, String> {
+        BTreeMap::from_iter(
+            pairs
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+    }
+
+    #[test]
+    fn saved_profiles_can_be_listed_loaded_and_deleted() {
+        let home_dir = ensure_node_home_directory_exists(
+            "setup_profiles",
+            "saved_profiles_can_be_listed_loaded_and_deleted",
+        );
+        let subject = SetupProfiles::new(&home_dir);
+        let mainnet = make_values(vec![("chain", "mainnet"), ("log-level", "info")]);
+        let zero_hop = make_values(vec![("neighborhood-mode", "zero-hop")]);
+        assert_eq!(subject.list(), Ok(vec![]));
+
+        subject.save("zero-hop", &zero_hop).unwrap();
+        subject.save("mainnet", &mainnet).unwrap();
+
+        assert_eq!(
+            subject.list(),
+            Ok(vec!["mainnet".to_string(), "zero-hop".to_string()])
+        );
+        assert_eq!(subject.load("mainnet"), Ok(mainnet));
+        assert_eq!(subject.delete("zero-hop"), Ok(()));
+        assert_eq!(subject.list(), Ok(vec!["mainnet".to_string()]));
+        assert_eq!(
+            subject.load("zero-hop"),
+            Err("No profile named 'zero-hop'".to_string())
+        );
+        assert_eq!(
+            subject.delete("zero-hop"),
+            Err("No profile named 'zero-hop'".to_string())
+        );
+    }
+
+    #[test]
+    fn saving_over_an_existing_profile_replaces_it() {
+        let home_dir = ensure_node_home_directory_exists(
+            "setup_profiles",
+            "saving_over_an_existing_profile_replaces_it",
+        );
+        let subject = SetupProfiles::new(&home_dir);
+        subject
+            .save("testing", &make_values(vec![("chain", "ropsten")]))
+            .unwrap();
+        let replacement = make_values(vec![("chain", "dev"), ("log-level", "trace")]);
+
+        subject.save("testing", &replacement).unwrap();
+
+        assert_eq!(subject.load("testing"), Ok(replacement));
+    }
+
+    #[test]
+    fn names_that_could_escape_the_profiles_directory_are_rejected() {
+        let home_dir = ensure_node_home_directory_exists(
+            "setup_profiles",
+            "names_that_could_escape_the_profiles_directory_are_rejected",
+        );
+        let subject = SetupProfiles::new(&home_dir);
+
+        vec!["", "../evil", "a/b", "with space", &"x".repeat(65)]
+            .into_iter()
+            .for_each(|name| {
+                let expected = Err(format!(
+                    "Profile name '{}' must be 1 to 64 letters, digits, '-' or '_'",
+                    name
+                ));
+                assert_eq!(subject.save(name, &BTreeMap::new()), expected);
+                assert_eq!(
+                    subject.load(name),
+                    expected.clone().map(|_| BTreeMap::new())
+                );
+                assert_eq!(subject.delete(name), expected);
+            });
+        assert_eq!(subject.list(), Ok(vec![]));
+    }
+
+    #[test]
+    fn corrupt_profile_is_reported() {
+        let home_dir =
+            ensure_node_home_directory_exists("setup_profiles", "corrupt_profile_is_reported");
+        let subject = SetupProfiles::new(&home_dir);
+        fs::create_dir_all(home_dir.join(SETUP_PROFILES_DIRECTORY)).unwrap();
+        fs::write(
+            home_dir.join(SETUP_PROFILES_DIRECTORY).join("broken.json"),
+            "not json",
+        )
+        .unwrap();
+
+        let result = subject.load("broken");
+
+        let message = result.unwrap_err();
+        assert!(
+            message.starts_with("Profile 'broken' is corrupt: "),
+            "{}",
+            message
+        );
+    }
+}
@@ -1,17 +1,20 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
 use crate::blockchain::blockchain_interface::{chain_id_from_name, chain_name_from_id};
+use crate::blockchain::fee_strategy::FeeStrategyKind;
 use crate::bootstrapper::BootstrapperConfig;
 use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
+use crate::neighborhood::geoip::GeoIpDatabase;
 use crate::node_configurator::node_configurator_standard::standard::{
     privileged_parse_args, unprivileged_parse_args,
 };
 use crate::node_configurator::{
     app_head, data_directory_from_context, determine_config_file_path, DirsWrapper, RealDirsWrapper,
 };
+use crate::sub_lib::exit_policy::{ExitPolicy, ExitRule};
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::utils::make_new_multi_config;
 use crate::test_utils::main_cryptde;
@@ -496,6 +499,27 @@ fn is_required_for_blockchain(params: &SetupCluster) -> bool {
     !matches! (params.get("neighborhood-mode"), Some(nhm) if &nhm.value == "zero-hop")
 }
 
+struct BatchPayments {}
+impl ValueRetriever for BatchPayments {
+    fn value_name(&self) -> &'static str {
+        "batch-payments"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        let value = if bootstrapper_config.blockchain_bridge_config.batch_payments {
+            "on"
+        } else {
+            "off"
+        };
+        Some((value.to_string(), Default))
+    }
+}
+
 struct BlockchainServiceUrl {}
 impl ValueRetriever for BlockchainServiceUrl {
     fn value_name(&self) -> &'static str {
@@ -563,6 +587,28 @@ impl ValueRetriever for ConfigFile {
     }
 }
 
+struct Confirmations {}
+impl ValueRetriever for Confirmations {
+    fn value_name(&self) -> &'static str {
+        "confirmations"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((
+            bootstrapper_config
+                .blockchain_bridge_config
+                .confirmations
+                .to_string(),
+            Default,
+        ))
+    }
+}
+
 struct ConsumingPrivateKey {}
 impl ValueRetriever for ConsumingPrivateKey {
     fn value_name(&self) -> &'static str {
@@ -635,6 +681,26 @@ impl ValueRetriever for DbPassword {
     }
 }
 
+struct DnsBypass {}
+impl ValueRetriever for DnsBypass {
+    fn value_name(&self) -> &'static str {
+        "dns-bypass"
+    }
+}
+
+struct DnsBypassServers {}
+impl ValueRetriever for DnsBypassServers {
+    fn value_name(&self) -> &'static str {
+        "dns-bypass-servers"
+    }
+
+    // Only on Linux can the Node find the DNS servers the dns_utility saved when it subverted DNS.
+    fn is_required(&self, params: &SetupCluster) -> bool {
+        cfg!(not(target_os = "linux"))
+            && matches!(params.get("dns-bypass"), Some(dns_bypass) if !dns_bypass.value.trim().is_empty())
+    }
+}
+
 struct DnsServers {}
 impl ValueRetriever for DnsServers {
     fn value_name(&self) -> &'static str {
@@ -662,6 +728,47 @@ impl ValueRetriever for EarningWallet {
     }
 }
 
+struct ExitExclude {}
+impl ValueRetriever for ExitExclude {
+    fn value_name(&self) -> &'static str {
+        "exit-exclude"
+    }
+}
+
+struct ExitRequire {}
+impl ValueRetriever for ExitRequire {
+    fn value_name(&self) -> &'static str {
+        "exit-require"
+    }
+}
+
+struct ExitServicePolicy {}
+impl ValueRetriever for ExitServicePolicy {
+    fn value_name(&self) -> &'static str {
+        "exit-service-policy"
+    }
+}
+
+struct FeeStrategy {}
+impl ValueRetriever for FeeStrategy {
+    fn value_name(&self) -> &'static str {
+        "fee-strategy"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        let value = match bootstrapper_config.blockchain_bridge_config.fee_strategy {
+            FeeStrategyKind::Fixed => "fixed",
+            FeeStrategyKind::Oracle => "oracle",
+        };
+        Some((value.to_string(), Default))
+    }
+}
+
 struct GasPrice {}
 impl ValueRetriever for GasPrice {
     fn value_name(&self) -> &'static str {
@@ -688,6 +795,29 @@ impl ValueRetriever for GasPrice {
     }
 }
 
+struct GeoipDatabase {}
+impl ValueRetriever for GeoipDatabase {
+    fn value_name(&self) -> &'static str {
+        "geoip-database"
+    }
+
+    // Country codes need a database, but the one bundled into the Node will do if there is one.
+    fn is_required(&self, params: &SetupCluster) -> bool {
+        !GeoIpDatabase::is_bundled()
+            && ["exit-require", "exit-exclude"].iter().any(|name| {
+                params
+                    .get(*name)
+                    .and_then(|rules| ExitPolicy::parse_rules(&rules.value).ok())
+                    .map(|rules| {
+                        rules
+                            .iter()
+                            .any(|rule| matches!(rule, ExitRule::Country(_)))
+                    })
+                    .unwrap_or(false)
+            })
+    }
+}
+
 struct Ip {}
 impl ValueRetriever for Ip {
     fn value_name(&self) -> &'static str {
@@ -723,6 +853,30 @@ impl ValueRetriever for LogLevel {
     }
 }
 
+struct MalefactorBanDuration {}
+impl ValueRetriever for MalefactorBanDuration {
+    fn value_name(&self) -> &'static str {
+        "malefactor-ban-duration"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        let hours = bootstrapper_config.malefactor_ban_duration.as_secs() / 3600;
+        Some((hours.to_string(), Default))
+    }
+}
+
+struct MaxFeePerGas {}
+impl ValueRetriever for MaxFeePerGas {
+    fn value_name(&self) -> &'static str {
+        "max-fee-per-gas"
+    }
+}
+
 struct NeighborhoodMode {}
 impl ValueRetriever for NeighborhoodMode {
     fn value_name(&self) -> &'static str {
@@ -781,6 +935,28 @@ impl ValueRetriever for Neighbors {
     }
 }
 
+struct PaymentCurves {}
+impl ValueRetriever for PaymentCurves {
+    fn value_name(&self) -> &'static str {
+        "payment-curves"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((
+            bootstrapper_config
+                .accountant_config
+                .payment_curves
+                .to_string(),
+            Default,
+        ))
+    }
+}
+
 struct RealUser {
     #[allow(dead_code)]
     dirs_wrapper: Box<dyn DirsWrapper>,
@@ -824,25 +1000,111 @@ impl RealUser {
     }
 }
 
+struct RoutingStrategy {}
+impl ValueRetriever for RoutingStrategy {
+    fn value_name(&self) -> &'static str {
+        "routing-strategy"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((bootstrapper_config.routing_strategy.to_string(), Default))
+    }
+}
+
+struct SocksPort {}
+impl ValueRetriever for SocksPort {
+    fn value_name(&self) -> &'static str {
+        "socks-port"
+    }
+}
+
+struct UiAuth {}
+impl ValueRetriever for UiAuth {
+    fn value_name(&self) -> &'static str {
+        "ui-auth"
+    }
+
+    fn computed_default(
+        &self,
+        _bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some(("off".to_string(), Default))
+    }
+}
+
+struct UiRemoteAddress {}
+impl ValueRetriever for UiRemoteAddress {
+    fn value_name(&self) -> &'static str {
+        "ui-remote-address"
+    }
+}
+
+struct UiTlsIdentity {}
+impl ValueRetriever for UiTlsIdentity {
+    fn value_name(&self) -> &'static str {
+        "ui-tls-identity"
+    }
+
+    // Remote UI connections always use TLS.
+    fn is_required(&self, params: &SetupCluster) -> bool {
+        params
+            .get("ui-remote-address")
+            .map(|address| !address.value.is_empty())
+            .unwrap_or(false)
+    }
+}
+
+struct UiTlsPassword {}
+impl ValueRetriever for UiTlsPassword {
+    fn value_name(&self) -> &'static str {
+        "ui-tls-password"
+    }
+}
+
 fn value_retrievers(dirs_wrapper: &dyn DirsWrapper) -> Vec<Box<dyn ValueRetriever>> {
     vec![
+        Box::new(BatchPayments {}),
         Box::new(BlockchainServiceUrl {}),
         Box::new(Chain {}),
         Box::new(ClandestinePort {}),
         Box::new(ConfigFile {}),
+        Box::new(Confirmations {}),
         Box::new(ConsumingPrivateKey {}),
         Box::new(CrashPoint {}),
         Box::new(DataDirectory::new(dirs_wrapper)),
         Box::new(DbPassword {}),
+        Box::new(DnsBypass {}),
+        Box::new(DnsBypassServers {}),
         Box::new(DnsServers {}),
         Box::new(EarningWallet {}),
+        Box::new(ExitExclude {}),
+        Box::new(ExitRequire {}),
+        Box::new(ExitServicePolicy {}),
+        Box::new(FeeStrategy {}),
         Box::new(GasPrice {}),
+        Box::new(GeoipDatabase {}),
         Box::new(Ip {}),
         Box::new(LogLevel {}),
+        Box::new(MalefactorBanDuration {}),
+        Box::new(MaxFeePerGas {}),
         Box::new(NeighborhoodMode {}),
         Box::new(Neighbors {}),
+        Box::new(PaymentCurves {}),
         #[cfg(not(target_os = "windows"))]
         Box::new(RealUser::new(dirs_wrapper)),
+        Box::new(RoutingStrategy {}),
+        Box::new(SocksPort {}),
+        Box::new(UiAuth {}),
+        Box::new(UiRemoteAddress {}),
+        Box::new(UiTlsIdentity {}),
+        Box::new(UiTlsPassword {}),
     ]
 }
 
@@ -862,6 +1124,7 @@ mod tests {
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils::assert_string_contains;
     use crate::test_utils::persistent_configuration_mock::PersistentConfigurationMock;
+    use masq_lib::constants::DEFAULT_PAYMENT_CURVES;
     use masq_lib::messages::UiSetupResponseValueStatus::{Blank, Configured, Required, Set};
     use masq_lib::test_utils::environment_guard::{ClapGuard, EnvironmentGuard};
     use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, TEST_DEFAULT_CHAIN_NAME};
@@ -872,6 +1135,7 @@ mod tests {
     use std::net::IpAddr;
     use std::str::FromStr;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     #[test]
     fn everything_in_defaults_is_properly_constructed() {
@@ -919,6 +1183,9 @@ mod tests {
             )
             .unwrap();
         config.set_gas_price(1234567890).unwrap();
+        config
+            .set_payment_curves(&crate::accountant::PaymentCurves::from_str("1|2|3|4|5|6").unwrap())
+            .unwrap();
         let neighbor1 = NodeDescriptor {
             encryption_public_key: PublicKey::new(b"ABCD"),
             mainnet: true,
@@ -954,25 +1221,37 @@ mod tests {
             .unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             ("blockchain-service-url", "", Required),
             ("chain", DEFAULT_CHAIN_NAME, Default),
             ("clandestine-port", "1234", Default),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             ("consuming-private-key", "", Blank),
             ("crash-point", "", Blank),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "1.1.1.1", Default),
             ("earning-wallet", "", Blank),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "1234567890", Default),
+            ("geoip-database", "", Blank),
             ("ip", "4.3.2.1", Set),
             ("log-level", "warn", Default),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "standard", Default),
             (
                 "neighbors",
                 "QUJDRA@1.2.3.4:1234,RUZHSA@5.6.7.8:5678",
                 Configured,
             ),
+            ("payment-curves", "1|2|3|4|5|6", Default),
             #[cfg(not(target_os = "windows"))]
             (
                 "real-user",
@@ -981,6 +1260,12 @@ mod tests {
                     .to_string(),
                 Default,
             ),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1019,6 +1304,7 @@ mod tests {
             ("log-level", "error", Set),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("payment-curves", "10|20|30|40|50|60", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
         ]);
@@ -1027,23 +1313,41 @@ mod tests {
         let result = subject.get_modified_setup(existing_setup, vec![]).unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             ("blockchain-service-url", "https://example.com", Set),
             ("chain", TEST_DEFAULT_CHAIN_NAME, Set),
             ("clandestine-port", "1234", Set),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             ("consuming-private-key", "0011223344556677001122334455667700112233445566770011223344556677", Set),
             ("crash-point", "Message", Set),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "8.8.8.8", Set),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Set),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "50", Set),
+            ("geoip-database", "", Blank),
             ("ip", "4.3.2.1", Set),
             ("log-level", "error", Set),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("payment-curves", "10|20|30|40|50|60", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1076,6 +1380,7 @@ mod tests {
             ("log-level", "error"),
             ("neighborhood-mode", "originate-only"),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("payment-curves", "10|20|30|40|50|60"),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga"),
         ].into_iter()
@@ -1088,23 +1393,41 @@ mod tests {
             .unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             ("blockchain-service-url", "https://example.com", Set),
             ("chain", TEST_DEFAULT_CHAIN_NAME, Set),
             ("clandestine-port", "1234", Set),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             ("consuming-private-key", "0011223344556677001122334455667700112233445566770011223344556677", Set),
             ("crash-point", "Message", Set),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "8.8.8.8", Set),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Set),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "50", Set),
+            ("geoip-database", "", Blank),
             ("ip", "4.3.2.1", Set),
             ("log-level", "error", Set),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("payment-curves", "10|20|30|40|50|60", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1138,6 +1461,7 @@ mod tests {
             ("MASQ_LOG_LEVEL", "error"),
             ("MASQ_NEIGHBORHOOD_MODE", "originate-only"),
             ("MASQ_NEIGHBORS", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("MASQ_PAYMENT_CURVES", "10|20|30|40|50|60"),
             #[cfg(not(target_os = "windows"))]
             ("MASQ_REAL_USER", "9999:9999:booga"),
         ].into_iter()
@@ -1148,23 +1472,41 @@ mod tests {
         let result = subject.get_modified_setup(HashMap::new(), params).unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             ("blockchain-service-url", "https://example.com", Configured),
             ("chain", TEST_DEFAULT_CHAIN_NAME, Configured),
             ("clandestine-port", "1234", Configured),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             ("consuming-private-key", "0011223344556677001122334455667700112233445566770011223344556677", Configured),
             ("crash-point", "Error", Configured),
             ("data-directory", home_dir.to_str().unwrap(), Configured),
             ("db-password", "password", Configured),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "8.8.8.8", Configured),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Configured),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "50", Configured),
+            ("geoip-database", "", Blank),
             ("ip", "4.3.2.1", Configured),
             ("log-level", "error", Configured),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "originate-only", Configured),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Configured),
+            ("payment-curves", "10|20|30|40|50|60", Configured),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Configured),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1250,6 +1592,7 @@ mod tests {
         let result = subject.get_modified_setup(existing_setup, params).unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             (
                 "blockchain-service-url",
                 "https://www.ropsten.com",
@@ -1258,6 +1601,7 @@ mod tests {
             ("chain", TEST_DEFAULT_CHAIN_NAME, Set),
             ("clandestine-port", "8877", Configured),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             (
                 "consuming-private-key",
                 "FFEEDDCCBBAA99887766554433221100FFEEDDCCBBAA99887766554433221100",
@@ -1270,17 +1614,27 @@ mod tests {
                 Default,
             ),
             ("db-password", TEST_DEFAULT_CHAIN_NAME, Configured),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "8.7.6.5", Configured),
             (
                 "earning-wallet",
                 "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
                 Configured,
             ),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "88", Configured),
+            ("geoip-database", "", Blank),
             ("ip", "", Blank),
             ("log-level", "debug", Configured),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "zero-hop", Configured),
             ("neighbors", "", Blank),
+            ("payment-curves", DEFAULT_PAYMENT_CURVES, Default),
             #[cfg(not(target_os = "windows"))]
             (
                 "real-user",
@@ -1289,6 +1643,12 @@ mod tests {
                     .to_string(),
                 Default,
             ),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1327,6 +1687,7 @@ mod tests {
             ("MASQ_LOG_LEVEL", "error"),
             ("MASQ_NEIGHBORHOOD_MODE", "originate-only"),
             ("MASQ_NEIGHBORS", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("MASQ_PAYMENT_CURVES", "10|20|30|40|50|60"),
             #[cfg(not(target_os = "windows"))]
             ("MASQ_REAL_USER", "9999:9999:booga"),
         ].into_iter()
@@ -1345,6 +1706,7 @@ mod tests {
             "ip",
             "neighborhood-mode",
             "neighbors",
+            "payment-curves",
             #[cfg(not(target_os = "windows"))]
             "real-user",
         ]
@@ -1376,6 +1738,7 @@ mod tests {
                 "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:9.10.11.12:9101",
                 Set,
             ),
+            ("payment-curves", "6|5|4|3|20|1", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "6666:6666:agoob", Set),
         ]);
@@ -1384,27 +1747,45 @@ mod tests {
         let result = subject.get_modified_setup(existing_setup, params).unwrap();
 
         let expected_result = vec![
+            ("batch-payments", "off", Default),
             ("blockchain-service-url", "https://example.com", Configured),
             ("chain", TEST_DEFAULT_CHAIN_NAME, Configured),
             ("clandestine-port", "1234", Configured),
             ("config-file", "config.toml", Default),
+            ("confirmations", "12", Default),
             ("consuming-private-key", "0011223344556677001122334455667700112233445566770011223344556677", Configured),
             ("crash-point", "Panic", Configured),
             ("data-directory", home_dir.to_str().unwrap(), Configured),
             ("db-password", "password", Configured),
+            ("dns-bypass", "", Blank),
+            ("dns-bypass-servers", "", Blank),
             ("dns-servers", "8.8.8.8", Configured),
             (
                 "earning-wallet",
                 "0x0123456789012345678901234567890123456789",
                 Configured,
             ),
+            ("exit-exclude", "", Blank),
+            ("exit-require", "", Blank),
+            ("exit-service-policy", "", Blank),
+            ("fee-strategy", "fixed", Default),
             ("gas-price", "50", Configured),
+            ("geoip-database", "", Blank),
             ("ip", "4.3.2.1", Configured),
             ("log-level", "error", Configured),
+            ("malefactor-ban-duration", "24", Default),
+            ("max-fee-per-gas", "", Blank),
             ("neighborhood-mode", "originate-only", Configured),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Configured),
+            ("payment-curves", "10|20|30|40|50|60", Configured),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Configured),
+            ("routing-strategy", "cheapest", Default),
+            ("socks-port", "", Blank),
+            ("ui-auth", "off", Default),
+            ("ui-remote-address", "", Blank),
+            ("ui-tls-identity", "", Blank),
+            ("ui-tls-password", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1497,6 +1878,7 @@ mod tests {
             ("log-level", "warn", Default),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "", Blank),
+            ("payment-curves", DEFAULT_PAYMENT_CURVES, Default),
             (
                 "real-user",
                 &crate::bootstrapper::RealUser::new(None, None, None)
@@ -2260,6 +2642,149 @@ mod tests {
         );
     }
 
+    #[test]
+    fn batch_payments_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        let subject = BatchPayments {};
+
+        let off_result = subject.computed_default(&bootstrapper_config, &None, &None);
+        bootstrapper_config.blockchain_bridge_config.batch_payments = true;
+        let on_result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(off_result, Some(("off".to_string(), Default)));
+        assert_eq!(on_result, Some(("on".to_string(), Default)));
+    }
+
+    #[test]
+    fn fee_strategy_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        let subject = FeeStrategy {};
+
+        let fixed_result = subject.computed_default(&bootstrapper_config, &None, &None);
+        bootstrapper_config.blockchain_bridge_config.fee_strategy = FeeStrategyKind::Oracle;
+        let oracle_result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(fixed_result, Some(("fixed".to_string(), Default)));
+        assert_eq!(oracle_result, Some(("oracle".to_string(), Default)));
+    }
+
+    #[test]
+    fn max_fee_per_gas_has_no_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        bootstrapper_config
+            .blockchain_bridge_config
+            .max_fee_per_gas_opt = Some(150);
+        let subject = MaxFeePerGas {};
+
+        let result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn confirmations_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        bootstrapper_config.blockchain_bridge_config.confirmations = 3;
+        let subject = Confirmations {};
+
+        let result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(result, Some(("3".to_string(), Default)));
+    }
+
+    #[test]
+    fn routing_strategy_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        bootstrapper_config.routing_strategy =
+            crate::sub_lib::neighborhood::RoutingStrategy::Reliable;
+        let subject = RoutingStrategy {};
+
+        let result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(result, Some(("reliable".to_string(), Default)));
+    }
+
+    #[test]
+    fn malefactor_ban_duration_computed_default() {
+        let mut bootstrapper_config = BootstrapperConfig::new();
+        bootstrapper_config.malefactor_ban_duration = Duration::from_secs(72 * 3600);
+        let subject = MalefactorBanDuration {};
+
+        let result = subject.computed_default(&bootstrapper_config, &None, &None);
+
+        assert_eq!(result, Some(("72".to_string(), Default)));
+    }
+
+    #[test]
+    fn exit_rules_and_geoip_database_have_no_computed_default() {
+        let bootstrapper_config = BootstrapperConfig::new();
+
+        assert_eq!(
+            ExitExclude {}.computed_default(&bootstrapper_config, &None, &None),
+            None
+        );
+        assert_eq!(
+            ExitRequire {}.computed_default(&bootstrapper_config, &None, &None),
+            None
+        );
+        assert_eq!(
+            GeoipDatabase {}.computed_default(&bootstrapper_config, &None, &None),
+            None
+        );
+    }
+
+    #[test]
+    fn geoip_database_is_required_only_for_country_exit_rules_without_a_bundled_one() {
+        let ranges_only = setup_cluster_from(vec![
+            ("exit-require", "10.0.0.0/8", Set),
+            ("exit-exclude", "10.2.0.0/16", Set),
+        ]);
+        let country_required = setup_cluster_from(vec![("exit-require", "de,10.0.0.0/8", Set)]);
+        let country_excluded = setup_cluster_from(vec![("exit-exclude", "ru", Set)]);
+
+        assert_eq!(GeoipDatabase {}.is_required(&HashMap::new()), false);
+        assert_eq!(GeoipDatabase {}.is_required(&ranges_only), false);
+        assert_eq!(
+            GeoipDatabase {}.is_required(&country_required),
+            !GeoIpDatabase::is_bundled()
+        );
+        assert_eq!(
+            GeoipDatabase {}.is_required(&country_excluded),
+            !GeoIpDatabase::is_bundled()
+        );
+    }
+
+    #[test]
+    fn dns_bypass_servers_are_required_for_dns_bypass_except_on_linux() {
+        let params = setup_cluster_from(vec![("dns-bypass", "lan,mybank.com", Set)]);
+
+        assert_eq!(DnsBypass {}.is_required(&params), false);
+        assert_eq!(DnsBypassServers {}.is_required(&HashMap::new()), false);
+        assert_eq!(
+            DnsBypassServers {}.is_required(&params),
+            !cfg!(target_os = "linux")
+        );
+    }
+
+    #[test]
+    fn ui_auth_computed_default() {
+        let subject = UiAuth {};
+
+        let result = subject.computed_default(&BootstrapperConfig::new(), &None, &None);
+
+        assert_eq!(result, Some(("off".to_string(), Default)))
+    }
+
+    #[test]
+    fn ui_tls_identity_is_required_only_for_remote_ui_access() {
+        let remote = setup_cluster_from(vec![("ui-remote-address", "0.0.0.0:5334", Set)]);
+        let blank = setup_cluster_from(vec![("ui-remote-address", "", Blank)]);
+
+        assert_eq!(UiTlsIdentity {}.is_required(&remote), true);
+        assert_eq!(UiTlsIdentity {}.is_required(&blank), false);
+        assert_eq!(UiTlsIdentity {}.is_required(&HashMap::new()), false);
+    }
+
     #[test]
     fn ip_requirements() {
         verify_requirements(
@@ -2307,6 +2832,9 @@ mod tests {
         assert_eq!(DbPassword {}.is_required(&params), true);
         assert_eq!(DnsServers {}.is_required(&params), true);
         assert_eq!(EarningWallet {}.is_required(&params), false);
+        assert_eq!(ExitExclude {}.is_required(&params), false);
+        assert_eq!(ExitRequire {}.is_required(&params), false);
+        assert_eq!(ExitServicePolicy {}.is_required(&params), false);
         assert_eq!(GasPrice {}.is_required(&params), true);
         assert_eq!(Ip {}.is_required(&params), true);
         assert_eq!(LogLevel {}.is_required(&params), true);
@@ -2316,5 +2844,10 @@ mod tests {
             crate::daemon::setup_reporter::RealUser::default().is_required(&params),
             false
         );
+        assert_eq!(SocksPort {}.is_required(&params), false);
+        assert_eq!(UiAuth {}.is_required(&params), false);
+        assert_eq!(UiRemoteAddress {}.is_required(&params), false);
+        assert_eq!(UiTlsIdentity {}.is_required(&params), false);
+        assert_eq!(UiTlsPassword {}.is_required(&params), false);
     }
 }
@@ -172,6 +172,7 @@ mod tests {
     use crate::sub_lib::neighborhood::NodeDescriptor;
     use crate::test_utils::{main_cryptde, ArgsBuilder};
     use bip39::{Language, MnemonicType, Seed};
+    use masq_lib::constants::{DEFAULT_PAYMENT_CURVES, DEFAULT_ROUTING_STRATEGY};
     use masq_lib::test_utils::environment_guard::ClapGuard;
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
     use masq_lib::test_utils::utils::{
@@ -212,6 +213,8 @@ mod tests {
            "exampleEncrypted": null,
            "gasPrice": "1",
            "pastNeighbors": null,
+           "paymentCurves": DEFAULT_PAYMENT_CURVES,
+           "routingStrategy": DEFAULT_ROUTING_STRATEGY,
            "schemaVersion": CURRENT_SCHEMA_VERSION,
            "seed": null,
            "startBlock": &contract_creation_block_from_chain_id(chain_id_from_name(TEST_DEFAULT_CHAIN_NAME)).to_string(),
@@ -3,9 +3,11 @@ use crate::blockchain::blockchain_interface::{
     chain_name_from_id, contract_creation_block_from_chain_id,
 };
 use crate::database::connection_wrapper::{ConnectionWrapper, ConnectionWrapperReal};
+use crate::database::db_migrations::DbMigrator;
 use crate::db_config::secure_config_layer::EXAMPLE_ENCRYPTED;
 use masq_lib::constants::{
-    DEFAULT_GAS_PRICE, HIGHEST_RANDOM_CLANDESTINE_PORT, LOWEST_USABLE_INSECURE_PORT,
+    DEFAULT_GAS_PRICE, DEFAULT_PAYMENT_CURVES, DEFAULT_ROUTING_STRATEGY,
+    HIGHEST_RANDOM_CLANDESTINE_PORT, LOWEST_USABLE_INSECURE_PORT,
 };
 use rand::prelude::*;
 use rusqlite::Error::InvalidColumnType;
@@ -19,12 +21,43 @@ use std::path::PathBuf;
 use tokio::net::TcpListener;
 
 pub const DATABASE_FILE: &str = "node-data.db";
-pub const CURRENT_SCHEMA_VERSION: &str = "0.0.10";
+pub const CURRENT_SCHEMA_VERSION: &str = "0.0.18";
+
+// Payments credited from recent blocks, kept so that they can be reversed if their block is orphaned
+pub const RECEIVED_PAYMENT_TABLE_SQL: &str = "create table received_payment (
+    block_number integer not null,
+    block_hash text not null,
+    wallet_address text not null,
+    amount integer not null
+)";
+
+// Every payment sent or received, kept for auditing after the payable and receivable balances move on
+pub const PAYMENT_HISTORY_TABLE_SQL: &str = "create table payment_history (
+    direction text not null,
+    transaction_hash text not null,
+    wallet_address text not null,
+    amount integer not null,
+    block_number integer null,
+    status text not null,
+    timestamp integer not null,
+    unique (direction, transaction_hash, wallet_address)
+)";
+
+// Nodes banned for sending malformed or forged Gossip, by public key, IP address, or both
+pub const MALEFACTOR_TABLE_SQL: &str = "create table malefactor (
+    public_key blob null,
+    ip_address text null,
+    reason text not null,
+    banned_at integer not null,
+    expires_at integer not null
+)";
 
 #[derive(Debug, PartialEq)]
 pub enum InitializationError {
     Nonexistent,
     IncompatibleVersion(String),
+    BackupFailed(String),
+    MigrationFailed(String),
     SqliteError(rusqlite::Error),
 }
 
@@ -38,7 +71,9 @@ pub trait DbInitializer {
 }
 
 #[derive(Default)]
-pub struct DbInitializerReal {}
+pub struct DbInitializerReal {
+    migrator: DbMigrator,
+}
 
 impl DbInitializer for DbInitializerReal {
     fn initialize(
@@ -56,10 +91,14 @@ impl DbInitializer for DbInitializerReal {
         flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
         let database_file_path = &path.join(DATABASE_FILE);
         match Connection::open_with_flags(database_file_path, flags) {
-            Ok(conn) => {
+            Ok(mut conn) => {
                 eprintln!("Opened existing database at {:?}", database_file_path);
                 let config = self.extract_configurations(&conn);
-                match self.check_version(config.get("schema_version")) {
+                match self.update_schema_if_necessary(
+                    database_file_path,
+                    &mut conn,
+                    config.get("schema_version"),
+                ) {
                     Ok(_) => Ok(Box::new(ConnectionWrapperReal::new(conn))),
                     Err(e) => Err(e),
                 }
@@ -121,6 +160,9 @@ impl DbInitializerReal {
         self.initialize_config(conn, chain_id)?;
         self.create_payable_table(conn)?;
         self.create_receivable_table(conn)?;
+        self.create_received_payment_table(conn)?;
+        self.create_payment_history_table(conn)?;
+        self.create_malefactor_table(conn)?;
         self.create_banned_table(conn)
     }
 
@@ -200,7 +242,22 @@ impl DbInitializerReal {
             false,
             "gas price",
         );
+        Self::set_config_value(
+            conn,
+            "payment_curves",
+            Some(DEFAULT_PAYMENT_CURVES),
+            false,
+            "payment curves",
+        );
+        Self::set_config_value(
+            conn,
+            "routing_strategy",
+            Some(DEFAULT_ROUTING_STRATEGY),
+            false,
+            "routing strategy",
+        );
         Self::set_config_value(conn, "past_neighbors", None, true, "past neighbors");
+        Self::set_config_value(conn, "node_keys", None, true, "node keys");
         Ok(())
     }
 
@@ -211,7 +268,9 @@ impl DbInitializerReal {
                 wallet_address text primary key,
                 balance integer not null,
                 last_paid_timestamp integer not null,
-                pending_payment_transaction text null
+                pending_payment_transaction text null,
+                pending_payment_amount integer null,
+                pending_payment_nonce integer null
             )",
             NO_PARAMS,
         )
@@ -243,6 +302,27 @@ impl DbInitializerReal {
         Ok(())
     }
 
+    #[allow(clippy::unnecessary_wraps)]
+    fn create_received_payment_table(&self, conn: &Connection) -> Result<(), InitializationError> {
+        conn.execute(RECEIVED_PAYMENT_TABLE_SQL, NO_PARAMS)
+            .expect("Can't create received_payment table");
+        Ok(())
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn create_payment_history_table(&self, conn: &Connection) -> Result<(), InitializationError> {
+        conn.execute(PAYMENT_HISTORY_TABLE_SQL, NO_PARAMS)
+            .expect("Can't create payment_history table");
+        Ok(())
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    fn create_malefactor_table(&self, conn: &Connection) -> Result<(), InitializationError> {
+        conn.execute(MALEFACTOR_TABLE_SQL, NO_PARAMS)
+            .expect("Can't create malefactor table");
+        Ok(())
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     fn create_banned_table(&self, conn: &Connection) -> Result<(), InitializationError> {
         conn.execute(
@@ -273,6 +353,43 @@ impl DbInitializerReal {
         .collect::<HashMap<String, Option<String>>>()
     }
 
+    fn update_schema_if_necessary(
+        &self,
+        database_file_path: &PathBuf,
+        conn: &mut Connection,
+        version: Option<&Option<String>>,
+    ) -> Result<(), InitializationError> {
+        match version {
+            Some(Some(v_ref))
+                if *v_ref != CURRENT_SCHEMA_VERSION
+                    && self.migrator.can_migrate(v_ref, CURRENT_SCHEMA_VERSION) =>
+            {
+                Self::back_up_database(database_file_path, v_ref)?;
+                self.migrator.migrate(conn, v_ref, CURRENT_SCHEMA_VERSION)
+            }
+            _ => self.check_version(version),
+        }
+    }
+
+    fn back_up_database(
+        database_file_path: &PathBuf,
+        version: &str,
+    ) -> Result<(), InitializationError> {
+        let backup_file_path =
+            database_file_path.with_file_name(format!("{}.{}.bak", DATABASE_FILE, version));
+        eprintln!(
+            "Backing up database at {:?} to {:?}",
+            database_file_path, backup_file_path
+        );
+        match fs::copy(database_file_path, &backup_file_path) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(InitializationError::BackupFailed(format!(
+                "Can't copy {:?} to {:?}: {}",
+                database_file_path, backup_file_path, e
+            ))),
+        }
+    }
+
     fn check_version(&self, version: Option<&Option<String>>) -> Result<(), InitializationError> {
         match version {
             None => Err(InitializationError::IncompatibleVersion(format!(
@@ -511,7 +628,7 @@ mod tests {
         flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
         let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
 
-        let mut stmt = conn.prepare ("select wallet_address, balance, last_paid_timestamp, pending_payment_transaction from payable").unwrap ();
+        let mut stmt = conn.prepare ("select wallet_address, balance, last_paid_timestamp, pending_payment_transaction, pending_payment_amount from payable").unwrap ();
         let mut payable_contents = stmt.query_map(NO_PARAMS, |_| Ok(42)).unwrap();
         assert!(payable_contents.next().is_none());
     }
@@ -539,6 +656,77 @@ mod tests {
         assert!(receivable_contents.next().is_none());
     }
 
+    #[test]
+    fn db_initialize_creates_received_payment_table() {
+        let home_dir = ensure_node_home_directory_does_not_exist(
+            "db_initializer",
+            "db_initialize_creates_received_payment_table",
+        );
+        let subject = DbInitializerReal::new();
+
+        subject
+            .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+
+        let mut stmt = conn
+            .prepare(
+                "select block_number, block_hash, wallet_address, amount from received_payment",
+            )
+            .unwrap();
+        let mut received_payment_contents = stmt.query_map(NO_PARAMS, |_| Ok(())).unwrap();
+        assert!(received_payment_contents.next().is_none());
+    }
+
+    #[test]
+    fn db_initialize_creates_payment_history_table() {
+        let home_dir = ensure_node_home_directory_does_not_exist(
+            "db_initializer",
+            "db_initialize_creates_payment_history_table",
+        );
+        let subject = DbInitializerReal::new();
+
+        subject
+            .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+
+        let mut stmt = conn
+            .prepare("select direction, transaction_hash, wallet_address, amount, block_number, status, timestamp from payment_history")
+            .unwrap();
+        let mut payment_history_contents = stmt.query_map(NO_PARAMS, |_| Ok(())).unwrap();
+        assert!(payment_history_contents.next().is_none());
+    }
+
+    #[test]
+    fn db_initialize_creates_malefactor_table() {
+        let home_dir = ensure_node_home_directory_does_not_exist(
+            "db_initializer",
+            "db_initialize_creates_malefactor_table",
+        );
+        let subject = DbInitializerReal::new();
+
+        subject
+            .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+
+        let mut stmt = conn
+            .prepare("select public_key, ip_address, reason, banned_at, expires_at from malefactor")
+            .unwrap();
+        let mut malefactor_contents = stmt.query_map(NO_PARAMS, |_| Ok(())).unwrap();
+        assert!(malefactor_contents.next().is_none());
+    }
+
     #[test]
     fn db_initialize_creates_banned_table() {
         let home_dir = ensure_node_home_directory_does_not_exist(
@@ -612,8 +800,19 @@ mod tests {
         verify(&mut config_vec, "earning_wallet_address", None);
         verify(&mut config_vec, EXAMPLE_ENCRYPTED, None);
         verify(&mut config_vec, "gas_price", Some(DEFAULT_GAS_PRICE));
+        verify(&mut config_vec, "node_keys", None);
         verify(&mut config_vec, "past_neighbors", None);
+        verify(
+            &mut config_vec,
+            "payment_curves",
+            Some(DEFAULT_PAYMENT_CURVES),
+        );
         verify(&mut config_vec, "preexisting", Some("yes")); // makes sure we just created this database
+        verify(
+            &mut config_vec,
+            "routing_strategy",
+            Some(DEFAULT_ROUTING_STRATEGY),
+        );
         verify(
             &mut config_vec,
             "schema_version",
@@ -695,6 +894,134 @@ mod tests {
         );
     }
 
+    #[test]
+    fn existing_database_with_an_old_version_is_backed_up_and_migrated() {
+        let home_dir = ensure_node_home_directory_exists(
+            "db_initializer",
+            "existing_database_with_an_old_version_is_backed_up_and_migrated",
+        );
+        {
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap();
+            let mut flags = OpenFlags::empty();
+            flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
+            let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+            conn.execute("drop table payable", NO_PARAMS).unwrap();
+            conn.execute("drop table received_payment", NO_PARAMS)
+                .unwrap();
+            conn.execute("drop table payment_history", NO_PARAMS)
+                .unwrap();
+            conn.execute("drop table malefactor", NO_PARAMS).unwrap();
+            conn.execute(
+                "create table payable (
+                    wallet_address text primary key,
+                    balance integer not null,
+                    last_paid_timestamp integer not null,
+                    pending_payment_transaction text null
+                )",
+                NO_PARAMS,
+            )
+            .unwrap();
+            conn.execute(
+                "insert into payable (wallet_address, balance, last_paid_timestamp, pending_payment_transaction) values ('0x1111111111111111111111111111111111111111', 10, 0, '0x1234')",
+                NO_PARAMS,
+            )
+            .unwrap();
+            conn.execute(
+                "delete from config where name in ('payment_curves', 'routing_strategy', 'node_keys')",
+                NO_PARAMS,
+            )
+            .unwrap();
+            conn.execute(
+                "update config set value = '0.0.10' where name = 'schema_version'",
+                NO_PARAMS,
+            )
+            .unwrap();
+        }
+        let subject = DbInitializerReal::new();
+
+        let result = subject.initialize(&home_dir, DEFAULT_CHAIN_ID, true);
+
+        assert!(result.is_ok());
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+        let config_map = subject.extract_configurations(&conn);
+        assert_eq!(
+            config_map.get("schema_version"),
+            Some(&Some(CURRENT_SCHEMA_VERSION.to_string()))
+        );
+        let pending_payment_amount: Option<i64> = conn
+            .query_row(
+                "select pending_payment_amount from payable",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(pending_payment_amount, Some(0));
+        assert_eq!(
+            config_map.get("payment_curves"),
+            Some(&Some(DEFAULT_PAYMENT_CURVES.to_string()))
+        );
+        assert_eq!(
+            config_map.get("routing_strategy"),
+            Some(&Some(DEFAULT_ROUTING_STRATEGY.to_string()))
+        );
+        assert_eq!(config_map.get("node_keys"), Some(&None));
+        let backup_conn = Connection::open_with_flags(
+            &home_dir.join(format!("{}.0.0.10.bak", DATABASE_FILE)),
+            flags,
+        )
+        .unwrap();
+        let backup_config_map = subject.extract_configurations(&backup_conn);
+        assert_eq!(
+            backup_config_map.get("schema_version"),
+            Some(&Some("0.0.10".to_string()))
+        );
+    }
+
+    #[test]
+    fn existing_database_that_cannot_be_migrated_is_left_alone() {
+        let home_dir = ensure_node_home_directory_exists(
+            "db_initializer",
+            "existing_database_that_cannot_be_migrated_is_left_alone",
+        );
+        {
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap();
+            let mut flags = OpenFlags::empty();
+            flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
+            let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+            conn.execute(
+                "update config set value = '0.0.10' where name = 'schema_version'",
+                NO_PARAMS,
+            )
+            .unwrap();
+        }
+        let subject = DbInitializerReal::new();
+
+        let result = subject.initialize(&home_dir, DEFAULT_CHAIN_ID, true);
+
+        match result.err().unwrap() {
+            InitializationError::MigrationFailed(msg) => assert!(
+                msg.starts_with("Migration from 0.0.10 to 0.0.11 failed: "),
+                "{}",
+                msg
+            ),
+            x => panic!("Expected MigrationFailed, got {:?}", x),
+        }
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+        let config_map = subject.extract_configurations(&conn);
+        assert_eq!(
+            config_map.get("schema_version"),
+            Some(&Some("0.0.10".to_string()))
+        );
+    }
+
     #[test]
     fn choose_clandestine_port_chooses_different_unused_ports_each_time() {
         let _listeners = (0..10)
@@ -0,0 +1,599 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai). All rights reserved.
+use crate::database::db_initializer::{
+    InitializationError, MALEFACTOR_TABLE_SQL, PAYMENT_HISTORY_TABLE_SQL,
+    RECEIVED_PAYMENT_TABLE_SQL,
+};
+use masq_lib::constants::{DEFAULT_PAYMENT_CURVES, DEFAULT_ROUTING_STRATEGY};
+use rusqlite::{Connection, Transaction, NO_PARAMS};
+use std::fmt::Debug;
+
+pub trait DatabaseMigration: Debug + Send {
+    fn old_version(&self) -> &'static str;
+    fn new_version(&self) -> &'static str;
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()>;
+}
+
+#[derive(Debug)]
+pub struct DbMigrator {
+    steps: Vec<Box<dyn DatabaseMigration>>,
+}
+
+impl Default for DbMigrator {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(Migrate_0_0_10_to_0_0_11),
+            Box::new(Migrate_0_0_11_to_0_0_12),
+            Box::new(Migrate_0_0_12_to_0_0_13),
+            Box::new(Migrate_0_0_13_to_0_0_14),
+            Box::new(Migrate_0_0_14_to_0_0_15),
+            Box::new(Migrate_0_0_15_to_0_0_16),
+            Box::new(Migrate_0_0_16_to_0_0_17),
+            Box::new(Migrate_0_0_17_to_0_0_18),
+        ])
+    }
+}
+
+impl DbMigrator {
+    pub fn new(steps: Vec<Box<dyn DatabaseMigration>>) -> Self {
+        Self { steps }
+    }
+
+    pub fn can_migrate(&self, from_version: &str, to_version: &str) -> bool {
+        self.plan(from_version, to_version).is_some()
+    }
+
+    pub fn migrate(
+        &self,
+        conn: &mut Connection,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<(), InitializationError> {
+        let steps = match self.plan(from_version, to_version) {
+            Some(steps) => steps,
+            None => {
+                return Err(InitializationError::IncompatibleVersion(format!(
+                    "Need {}, found {}",
+                    to_version, from_version
+                )))
+            }
+        };
+        let transaction = conn
+            .transaction()
+            .map_err(InitializationError::SqliteError)?;
+        for step in steps {
+            eprintln!(
+                "Migrating database from version {} to {}",
+                step.old_version(),
+                step.new_version()
+            );
+            step.migrate(&transaction)
+                .and_then(|_| {
+                    transaction
+                        .execute(
+                            "update config set value = ? where name = 'schema_version'",
+                            &[step.new_version()],
+                        )
+                        .map(|_| ())
+                })
+                .map_err(|e| {
+                    InitializationError::MigrationFailed(format!(
+                        "Migration from {} to {} failed: {}",
+                        step.old_version(),
+                        step.new_version(),
+                        e
+                    ))
+                })?;
+        }
+        transaction
+            .commit()
+            .map_err(InitializationError::SqliteError)
+    }
+
+    fn plan(&self, from_version: &str, to_version: &str) -> Option<Vec<&dyn DatabaseMigration>> {
+        let mut steps = vec![];
+        let mut version = from_version;
+        while version != to_version {
+            let step = self
+                .steps
+                .iter()
+                .find(|step| step.old_version() == version)?;
+            if steps.len() >= self.steps.len() {
+                return None;
+            }
+            version = step.new_version();
+            steps.push(step.as_ref());
+        }
+        Some(steps)
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_10_to_0_0_11;
+
+impl DatabaseMigration for Migrate_0_0_10_to_0_0_11 {
+    fn old_version(&self) -> &'static str {
+        "0.0.10"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.11"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(
+            "alter table payable add column pending_payment_amount integer null",
+            NO_PARAMS,
+        )?;
+        // The amounts of payments sent before this version were never recorded; if one of them
+        // fails, there's nothing to restore.
+        transaction.execute(
+            "update payable set pending_payment_amount = 0 where pending_payment_transaction is not null",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_11_to_0_0_12;
+
+impl DatabaseMigration for Migrate_0_0_11_to_0_0_12 {
+    fn old_version(&self) -> &'static str {
+        "0.0.11"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.12"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(
+            "insert into config (name, value, encrypted) values ('payment_curves', ?, 0)",
+            &[DEFAULT_PAYMENT_CURVES],
+        )?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_12_to_0_0_13;
+
+impl DatabaseMigration for Migrate_0_0_12_to_0_0_13 {
+    fn old_version(&self) -> &'static str {
+        "0.0.12"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.13"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(RECEIVED_PAYMENT_TABLE_SQL, NO_PARAMS)?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_13_to_0_0_14;
+
+impl DatabaseMigration for Migrate_0_0_13_to_0_0_14 {
+    fn old_version(&self) -> &'static str {
+        "0.0.13"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.14"
+    }
+
+    // Payments made before this version are not in the ledger; their traces remain in payable and receivable
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(PAYMENT_HISTORY_TABLE_SQL, NO_PARAMS)?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_14_to_0_0_15;
+
+impl DatabaseMigration for Migrate_0_0_14_to_0_0_15 {
+    fn old_version(&self) -> &'static str {
+        "0.0.14"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.15"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(
+            "insert into config (name, value, encrypted) values ('routing_strategy', ?, 0)",
+            &[DEFAULT_ROUTING_STRATEGY],
+        )?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_15_to_0_0_16;
+
+impl DatabaseMigration for Migrate_0_0_15_to_0_0_16 {
+    fn old_version(&self) -> &'static str {
+        "0.0.15"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.16"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(
+            "insert into config (name, value, encrypted) values ('node_keys', null, 1)",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_16_to_0_0_17;
+
+impl DatabaseMigration for Migrate_0_0_16_to_0_0_17 {
+    fn old_version(&self) -> &'static str {
+        "0.0.16"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.17"
+    }
+
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(MALEFACTOR_TABLE_SQL, NO_PARAMS)?;
+        Ok(())
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+struct Migrate_0_0_17_to_0_0_18;
+
+impl DatabaseMigration for Migrate_0_0_17_to_0_0_18 {
+    fn old_version(&self) -> &'static str {
+        "0.0.17"
+    }
+
+    fn new_version(&self) -> &'static str {
+        "0.0.18"
+    }
+
+    // Payments pending from before this version keep a null nonce
+    fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+        transaction.execute(
+            "alter table payable add column pending_payment_nonce integer null",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Error;
+
+    #[derive(Debug)]
+    struct DatabaseMigrationMock {
+        old_version: &'static str,
+        new_version: &'static str,
+        sql: &'static str,
+    }
+
+    impl DatabaseMigration for DatabaseMigrationMock {
+        fn old_version(&self) -> &'static str {
+            self.old_version
+        }
+
+        fn new_version(&self) -> &'static str {
+            self.new_version
+        }
+
+        fn migrate(&self, transaction: &Transaction) -> rusqlite::Result<()> {
+            transaction.execute(self.sql, NO_PARAMS).map(|_| ())
+        }
+    }
+
+    fn step(
+        old_version: &'static str,
+        new_version: &'static str,
+        sql: &'static str,
+    ) -> Box<dyn DatabaseMigration> {
+        Box::new(DatabaseMigrationMock {
+            old_version,
+            new_version,
+            sql,
+        })
+    }
+
+    fn make_connection(version: &str) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "create table config (name text not null, value text, encrypted integer not null)",
+            NO_PARAMS,
+        )
+        .unwrap();
+        conn.execute(
+            "insert into config (name, value, encrypted) values ('schema_version', ?, 0)",
+            &[version],
+        )
+        .unwrap();
+        conn.execute("create table booga (value integer)", NO_PARAMS)
+            .unwrap();
+        conn
+    }
+
+    fn schema_version(conn: &Connection) -> String {
+        conn.query_row(
+            "select value from config where name = 'schema_version'",
+            NO_PARAMS,
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn booga_values(conn: &Connection) -> Vec<i64> {
+        let mut stmt = conn
+            .prepare("select value from booga order by value")
+            .unwrap();
+        stmt.query_map(NO_PARAMS, |row| row.get(0))
+            .unwrap()
+            .map(|value| value.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn migrate_applies_steps_in_order_and_updates_the_schema_version() {
+        let mut conn = make_connection("1.0");
+        let subject = DbMigrator::new(vec![
+            step("1.1", "1.2", "insert into booga (value) values (2)"),
+            step("1.0", "1.1", "insert into booga (value) values (1)"),
+            step("1.2", "1.3", "insert into booga (value) values (3)"),
+        ]);
+
+        let result = subject.migrate(&mut conn, "1.0", "1.2");
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(booga_values(&conn), vec![1, 2]);
+        assert_eq!(schema_version(&conn), "1.2".to_string());
+    }
+
+    #[test]
+    fn migrate_rolls_everything_back_if_a_step_fails() {
+        let mut conn = make_connection("1.0");
+        let subject = DbMigrator::new(vec![
+            step("1.0", "1.1", "insert into booga (value) values (1)"),
+            step("1.1", "1.2", "insert into nonexistent (value) values (2)"),
+        ]);
+
+        let result = subject.migrate(&mut conn, "1.0", "1.2");
+
+        match result {
+            Err(InitializationError::MigrationFailed(msg)) => assert!(
+                msg.starts_with("Migration from 1.1 to 1.2 failed: "),
+                "{}",
+                msg
+            ),
+            x => panic!("Expected MigrationFailed, got {:?}", x),
+        }
+        assert_eq!(booga_values(&conn), Vec::<i64>::new());
+        assert_eq!(schema_version(&conn), "1.0".to_string());
+    }
+
+    #[test]
+    fn migrate_complains_when_there_is_no_path_to_the_target_version() {
+        let mut conn = make_connection("0.9");
+        let subject = DbMigrator::new(vec![step(
+            "1.0",
+            "1.1",
+            "insert into booga (value) values (1)",
+        )]);
+
+        let result = subject.migrate(&mut conn, "0.9", "1.1");
+
+        assert_eq!(
+            result,
+            Err(InitializationError::IncompatibleVersion(
+                "Need 1.1, found 0.9".to_string()
+            ))
+        );
+        assert_eq!(schema_version(&conn), "0.9".to_string());
+    }
+
+    #[test]
+    fn can_migrate_does_not_loop_forever_on_cyclic_steps() {
+        let subject = DbMigrator::new(vec![
+            step("1.0", "1.1", "select 1"),
+            step("1.1", "1.0", "select 1"),
+        ]);
+
+        assert!(!subject.can_migrate("1.0", "2.0"));
+        assert!(subject.can_migrate("1.0", "1.1"));
+        assert!(subject.can_migrate("1.1", "1.1"));
+    }
+
+    #[test]
+    fn migrate_0_0_10_to_0_0_11_adds_pending_payment_amount() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "create table payable (
+                wallet_address text primary key,
+                balance integer not null,
+                last_paid_timestamp integer not null,
+                pending_payment_transaction text null
+            )",
+            NO_PARAMS,
+        )
+        .unwrap();
+        conn.execute(
+            "insert into payable (wallet_address, balance, last_paid_timestamp, pending_payment_transaction) values ('0x1111111111111111111111111111111111111111', 10, 0, '0x1234'), ('0x2222222222222222222222222222222222222222', 20, 0, null)",
+            NO_PARAMS,
+        )
+        .unwrap();
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_10_to_0_0_11.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let mut stmt = conn
+            .prepare("select pending_payment_amount from payable order by wallet_address")
+            .unwrap();
+        let amounts = stmt
+            .query_map(NO_PARAMS, |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<Option<i64>>, Error>>()
+            .unwrap();
+        assert_eq!(amounts, vec![Some(0), None]);
+    }
+
+    #[test]
+    fn migrate_0_0_11_to_0_0_12_adds_default_payment_curves() {
+        let mut conn = make_connection("0.0.11");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_11_to_0_0_12.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let payment_curves: String = conn
+            .query_row(
+                "select value from config where name = 'payment_curves'",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(payment_curves, DEFAULT_PAYMENT_CURVES.to_string());
+    }
+
+    #[test]
+    fn migrate_0_0_12_to_0_0_13_adds_received_payment_table() {
+        let mut conn = make_connection("0.0.12");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_12_to_0_0_13.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let count: i64 = conn
+            .query_row("select count(*) from received_payment", NO_PARAMS, |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn migrate_0_0_13_to_0_0_14_adds_payment_history_table() {
+        let mut conn = make_connection("0.0.13");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_13_to_0_0_14.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let count: i64 = conn
+            .query_row("select count(*) from payment_history", NO_PARAMS, |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn migrate_0_0_14_to_0_0_15_adds_default_routing_strategy() {
+        let mut conn = make_connection("0.0.14");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_14_to_0_0_15.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let routing_strategy: String = conn
+            .query_row(
+                "select value from config where name = 'routing_strategy'",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(routing_strategy, DEFAULT_ROUTING_STRATEGY.to_string());
+    }
+
+    #[test]
+    fn migrate_0_0_15_to_0_0_16_adds_empty_encrypted_node_keys() {
+        let mut conn = make_connection("0.0.15");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_15_to_0_0_16.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let (value, encrypted): (Option<String>, i64) = conn
+            .query_row(
+                "select value, encrypted from config where name = 'node_keys'",
+                NO_PARAMS,
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(value, None);
+        assert_eq!(encrypted, 1);
+    }
+
+    #[test]
+    fn migrate_0_0_16_to_0_0_17_adds_malefactor_table() {
+        let mut conn = make_connection("0.0.16");
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_16_to_0_0_17.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let count: i64 = conn
+            .query_row("select count(*) from malefactor", NO_PARAMS, |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn migrate_0_0_17_to_0_0_18_adds_pending_payment_nonce() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "create table payable (
+                wallet_address text primary key,
+                balance integer not null,
+                last_paid_timestamp integer not null,
+                pending_payment_transaction text null,
+                pending_payment_amount integer null
+            )",
+            NO_PARAMS,
+        )
+        .unwrap();
+        conn.execute(
+            "insert into payable (wallet_address, balance, last_paid_timestamp, pending_payment_transaction, pending_payment_amount) values ('0x1111111111111111111111111111111111111111', 10, 0, '0x1234', 10)",
+            NO_PARAMS,
+        )
+        .unwrap();
+        let transaction = conn.transaction().unwrap();
+
+        Migrate_0_0_17_to_0_0_18.migrate(&transaction).unwrap();
+
+        transaction.commit().unwrap();
+        let nonce: Option<i64> = conn
+            .query_row(
+                "select pending_payment_nonce from payable",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(nonce, None);
+    }
+}
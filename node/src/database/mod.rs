@@ -3,3 +3,4 @@ pub mod config_dumper;
 pub mod connection_wrapper;
 pub mod dao_utils;
 pub mod db_initializer;
+pub mod db_migrations;
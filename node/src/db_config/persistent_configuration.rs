@@ -1,4 +1,5 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::accountant::PaymentCurves;
 use crate::blockchain::bip32::Bip32ECKeyPair;
 use crate::blockchain::bip39::Bip39;
 use crate::database::connection_wrapper::ConnectionWrapper;
@@ -8,7 +9,7 @@ use crate::db_config::typed_config_layer::{
     decode_bytes, decode_u64, encode_bytes, encode_u64, TypedConfigLayerError,
 };
 use crate::sub_lib::cryptde::PlainData;
-use crate::sub_lib::neighborhood::NodeDescriptor;
+use crate::sub_lib::neighborhood::{NodeDescriptor, RoutingStrategy};
 use crate::sub_lib::wallet::Wallet;
 use bip39::{Language, MnemonicType};
 use masq_lib::constants::{HIGHEST_USABLE_PORT, LOWEST_USABLE_INSECURE_PORT};
@@ -28,6 +29,8 @@ pub enum PersistentConfigError {
     BadMnemonicSeed(PlainData),
     BadDerivationPathFormat(String),
     BadAddressFormat(String),
+    BadPaymentCurvesFormat(String),
+    BadRoutingStrategyFormat(String),
     Collision(String),
 }
 
@@ -67,6 +70,13 @@ impl PersistentConfigError {
     }
 }
 
+// Key material for the main and alias CryptDEs; see CryptDEReal::key_material()
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeKeys {
+    pub main: PlainData,
+    pub alias: PlainData,
+}
+
 pub trait PersistentConfiguration {
     fn current_schema_version(&self) -> String;
     fn check_password(
@@ -82,6 +92,13 @@ pub trait PersistentConfiguration {
     fn set_clandestine_port(&mut self, port: u16) -> Result<(), PersistentConfigError>;
     fn gas_price(&self) -> Result<Option<u64>, PersistentConfigError>;
     fn set_gas_price(&mut self, gas_price: u64) -> Result<(), PersistentConfigError>;
+    fn payment_curves(&self) -> Result<Option<PaymentCurves>, PersistentConfigError>;
+    fn set_payment_curves(&mut self, curves: &PaymentCurves) -> Result<(), PersistentConfigError>;
+    fn routing_strategy(&self) -> Result<Option<RoutingStrategy>, PersistentConfigError>;
+    fn set_routing_strategy(
+        &mut self,
+        strategy: RoutingStrategy,
+    ) -> Result<(), PersistentConfigError>;
     fn mnemonic_seed(&self, db_password: &str) -> Result<Option<PlainData>, PersistentConfigError>;
     fn mnemonic_seed_exists(&self) -> Result<bool, PersistentConfigError>;
     // WARNING: Actors should get consuming-wallet information from their startup config, not from here
@@ -108,6 +125,12 @@ pub trait PersistentConfiguration {
         node_descriptors_opt: Option<Vec<NodeDescriptor>>,
         db_password: &str,
     ) -> Result<(), PersistentConfigError>;
+    fn node_keys(&self, db_password: &str) -> Result<Option<NodeKeys>, PersistentConfigError>;
+    fn set_node_keys(
+        &mut self,
+        node_keys: &NodeKeys,
+        db_password: &str,
+    ) -> Result<(), PersistentConfigError>;
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError>;
     fn set_start_block(&mut self, value: u64) -> Result<(), PersistentConfigError>;
 }
@@ -195,6 +218,41 @@ impl PersistentConfiguration for PersistentConfigurationReal {
         Ok(writer.commit()?)
     }
 
+    fn payment_curves(&self) -> Result<Option<PaymentCurves>, PersistentConfigError> {
+        match self.dao.get("payment_curves")?.value_opt {
+            None => Ok(None),
+            Some(curves) => match PaymentCurves::from_str(&curves) {
+                Ok(curves) => Ok(Some(curves)),
+                Err(_) => Err(PersistentConfigError::BadPaymentCurvesFormat(curves)),
+            },
+        }
+    }
+
+    fn set_payment_curves(&mut self, curves: &PaymentCurves) -> Result<(), PersistentConfigError> {
+        let mut writer = self.dao.start_transaction()?;
+        writer.set("payment_curves", Some(curves.to_string()))?;
+        Ok(writer.commit()?)
+    }
+
+    fn routing_strategy(&self) -> Result<Option<RoutingStrategy>, PersistentConfigError> {
+        match self.dao.get("routing_strategy")?.value_opt {
+            None => Ok(None),
+            Some(strategy) => match RoutingStrategy::from_str(&strategy) {
+                Ok(strategy) => Ok(Some(strategy)),
+                Err(_) => Err(PersistentConfigError::BadRoutingStrategyFormat(strategy)),
+            },
+        }
+    }
+
+    fn set_routing_strategy(
+        &mut self,
+        strategy: RoutingStrategy,
+    ) -> Result<(), PersistentConfigError> {
+        let mut writer = self.dao.start_transaction()?;
+        writer.set("routing_strategy", Some(strategy.to_string()))?;
+        Ok(writer.commit()?)
+    }
+
     fn mnemonic_seed(&self, db_password: &str) -> Result<Option<PlainData>, PersistentConfigError> {
         Ok(decode_bytes(self.scl.decrypt(
             self.dao.get("seed")?,
@@ -341,6 +399,52 @@ impl PersistentConfiguration for PersistentConfigurationReal {
         Ok(writer.commit()?)
     }
 
+    fn node_keys(&self, db_password: &str) -> Result<Option<NodeKeys>, PersistentConfigError> {
+        let bytes_opt = decode_bytes(self.scl.decrypt(
+            self.dao.get("node_keys")?,
+            Some(db_password.to_string()),
+            &self.dao,
+        )?)?;
+        match bytes_opt {
+            None => Ok(None),
+            Some(bytes) => {
+                let (main, alias) = serde_cbor::de::from_slice::<(Vec<u8>, Vec<u8>)>(
+                    &bytes.as_slice(),
+                )
+                .expect("Can't continue; node keys configuration is corrupt and cannot be deserialized.");
+                Ok(Some(NodeKeys {
+                    main: PlainData::from(main),
+                    alias: PlainData::from(alias),
+                }))
+            }
+        }
+    }
+
+    fn set_node_keys(
+        &mut self,
+        node_keys: &NodeKeys,
+        db_password: &str,
+    ) -> Result<(), PersistentConfigError> {
+        let plain_data = PlainData::new(
+            &serde_cbor::ser::to_vec(&(
+                node_keys.main.as_slice().to_vec(),
+                node_keys.alias.as_slice().to_vec(),
+            ))
+            .expect("Serialization failed"),
+        );
+        let mut writer = self.dao.start_transaction()?;
+        writer.set(
+            "node_keys",
+            self.scl.encrypt(
+                "node_keys",
+                encode_bytes(Some(plain_data))?,
+                Some(db_password.to_string()),
+                &writer,
+            )?,
+        )?;
+        Ok(writer.commit()?)
+    }
+
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError> {
         Ok(decode_u64(self.dao.get("start_block")?.value_opt)?)
     }
@@ -1416,6 +1520,150 @@ mod tests {
         )
     }
 
+    #[test]
+    fn payment_curves_success() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "payment_curves",
+            Some("1|2|3|4|5|6"),
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.payment_curves();
+
+        assert_eq!(
+            result,
+            Ok(Some(PaymentCurves {
+                payment_suggested_after_sec: 1,
+                payment_grace_before_ban_sec: 2,
+                permanent_debt_allowed_gwub: 3,
+                balance_to_decrease_from_gwub: 4,
+                balance_decreases_for_sec: 5,
+                unban_when_balance_below_gwub: 6,
+            }))
+        );
+    }
+
+    #[test]
+    fn payment_curves_when_absent() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "payment_curves",
+            None,
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.payment_curves();
+
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn payment_curves_complains_about_bad_format() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "payment_curves",
+            Some("1|2|booga"),
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.payment_curves();
+
+        assert_eq!(
+            result,
+            Err(PersistentConfigError::BadPaymentCurvesFormat(
+                "1|2|booga".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn set_payment_curves_succeeds() {
+        let set_params_arc = Arc::new(Mutex::new(vec![]));
+        let writer = Box::new(
+            ConfigDaoWriteableMock::new()
+                .set_params(&set_params_arc)
+                .set_result(Ok(()))
+                .commit_result(Ok(())),
+        );
+        let config_dao = Box::new(ConfigDaoMock::new().start_transaction_result(Ok(writer)));
+        let mut subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.set_payment_curves(&PaymentCurves {
+            payment_suggested_after_sec: 1,
+            payment_grace_before_ban_sec: 2,
+            permanent_debt_allowed_gwub: 3,
+            balance_to_decrease_from_gwub: 4,
+            balance_decreases_for_sec: 5,
+            unban_when_balance_below_gwub: 6,
+        });
+
+        assert_eq!(result, Ok(()));
+        let set_params = set_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_params,
+            vec![(
+                "payment_curves".to_string(),
+                Some("1|2|3|4|5|6".to_string())
+            )]
+        )
+    }
+
+    #[test]
+    fn routing_strategy_success() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "routing_strategy",
+            Some("reliable"),
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.routing_strategy();
+
+        assert_eq!(result, Ok(Some(RoutingStrategy::Reliable)));
+    }
+
+    #[test]
+    fn routing_strategy_complains_about_bad_format() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "routing_strategy",
+            Some("booga"),
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.routing_strategy();
+
+        assert_eq!(
+            result,
+            Err(PersistentConfigError::BadRoutingStrategyFormat(
+                "booga".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn set_routing_strategy_succeeds() {
+        let set_params_arc = Arc::new(Mutex::new(vec![]));
+        let writer = Box::new(
+            ConfigDaoWriteableMock::new()
+                .set_params(&set_params_arc)
+                .set_result(Ok(()))
+                .commit_result(Ok(())),
+        );
+        let config_dao = Box::new(ConfigDaoMock::new().start_transaction_result(Ok(writer)));
+        let mut subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.set_routing_strategy(RoutingStrategy::Random);
+
+        assert_eq!(result, Ok(()));
+        let set_params = set_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_params,
+            vec![("routing_strategy".to_string(), Some("random".to_string()))]
+        )
+    }
+
     #[test]
     fn past_neighbors_success() {
         let example = "Aside from that, Mrs. Lincoln, how was the play?".as_bytes();
@@ -1505,4 +1753,112 @@ mod tests {
         assert_eq!(actual_node_descriptors, node_descriptors);
         assert_eq!(set_params.len(), 1);
     }
+
+    #[test]
+    fn node_keys_success() {
+        let example = "Aside from that, Mrs. Lincoln, how was the play?".as_bytes();
+        let example_encrypted = Bip39::encrypt_bytes(&example, "password").unwrap();
+        let node_keys = NodeKeys {
+            main: PlainData::new(&[1, 2, 3, 4]),
+            alias: PlainData::new(&[5, 6, 7, 8]),
+        };
+        let node_keys_bytes = PlainData::new(
+            &serde_cbor::ser::to_vec(&(vec![1u8, 2, 3, 4], vec![5u8, 6, 7, 8])).unwrap(),
+        );
+        let node_keys_string = encode_bytes(Some(node_keys_bytes)).unwrap().unwrap();
+        let node_keys_enc = Bip39::encrypt_bytes(&node_keys_string.as_bytes(), "password").unwrap();
+        let get_params_arc = Arc::new(Mutex::new(vec![]));
+        let config_dao = Box::new(
+            ConfigDaoMock::new()
+                .get_params(&get_params_arc)
+                .get_result(Ok(ConfigDaoRecord::new(
+                    "node_keys",
+                    Some(&node_keys_enc),
+                    true,
+                )))
+                .get_result(Ok(ConfigDaoRecord::new(
+                    EXAMPLE_ENCRYPTED,
+                    Some(&example_encrypted),
+                    true,
+                ))),
+        );
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.node_keys("password").unwrap();
+
+        assert_eq!(result, Some(node_keys));
+        let get_params = get_params_arc.lock().unwrap();
+        assert_eq!(
+            *get_params,
+            vec!["node_keys".to_string(), EXAMPLE_ENCRYPTED.to_string()]
+        );
+    }
+
+    #[test]
+    fn node_keys_handles_missing_keys() {
+        let example = "Aside from that, Mrs. Lincoln, how was the play?".as_bytes();
+        let example_encrypted = Bip39::encrypt_bytes(&example, "password").unwrap();
+        let config_dao = Box::new(
+            ConfigDaoMock::new()
+                .get_result(Ok(ConfigDaoRecord::new("node_keys", None, true)))
+                .get_result(Ok(ConfigDaoRecord::new(
+                    EXAMPLE_ENCRYPTED,
+                    Some(&example_encrypted),
+                    true,
+                ))),
+        );
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.node_keys("password").unwrap();
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn set_node_keys_success() {
+        let example = "Aside from that, Mrs. Lincoln, how was the play?".as_bytes();
+        let example_encrypted = Bip39::encrypt_bytes(&example, "password").unwrap();
+        let node_keys = NodeKeys {
+            main: PlainData::new(&[1, 2, 3, 4]),
+            alias: PlainData::new(&[5, 6, 7, 8]),
+        };
+        let set_params_arc = Arc::new(Mutex::new(vec![]));
+        let writer = Box::new(
+            ConfigDaoWriteableMock::new()
+                .get_result(Ok(ConfigDaoRecord::new(
+                    EXAMPLE_ENCRYPTED,
+                    Some(&example_encrypted),
+                    true,
+                )))
+                .get_result(Ok(ConfigDaoRecord::new(
+                    "node_keys",
+                    Some("irrelevant"),
+                    true,
+                )))
+                .set_params(&set_params_arc)
+                .set_result(Ok(()))
+                .commit_result(Ok(())),
+        );
+        let config_dao = Box::new(ConfigDaoMock::new().start_transaction_result(Ok(writer)));
+        let mut subject = PersistentConfigurationReal::new(config_dao);
+
+        subject.set_node_keys(&node_keys, "password").unwrap();
+
+        let set_params = set_params_arc.lock().unwrap();
+        assert_eq!(set_params[0].0, "node_keys".to_string());
+        let encrypted_serialized_node_keys = set_params[0].1.clone().unwrap();
+        let encoded_serialized_node_keys =
+            Bip39::decrypt_bytes(&encrypted_serialized_node_keys, "password").unwrap();
+        let serialized_node_keys = decode_bytes(Some(
+            String::from_utf8(encoded_serialized_node_keys.into()).unwrap(),
+        ))
+        .unwrap()
+        .unwrap();
+        let (main, alias) =
+            serde_cbor::de::from_slice::<(Vec<u8>, Vec<u8>)>(&serialized_node_keys.as_slice())
+                .unwrap();
+        assert_eq!(main, vec![1, 2, 3, 4]);
+        assert_eq!(alias, vec![5, 6, 7, 8]);
+        assert_eq!(set_params.len(), 1);
+    }
 }
@@ -0,0 +1,138 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use masq_lib::utils::localhost;
+use regex::Regex;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+// Domains whose queries go to the original DNS servers instead of being answered with localhost.
+// A pattern containing '*' must match the whole host name, with '*' standing for any run of
+// characters; any other pattern matches the domain it names and all of that domain's subdomains.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DnsBypassList {
+    patterns: Vec<String>,
+}
+
+impl DnsBypassList {
+    pub fn new(patterns: &str) -> Self {
+        DnsBypassList {
+            patterns: patterns
+                .split(',')
+                .map(Self::normalize)
+                .filter(|pattern| !pattern.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, hostname: &str) -> bool {
+        let hostname = Self::normalize(hostname);
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains('*') {
+                glob_matches(pattern.as_bytes(), hostname.as_bytes())
+            } else {
+                hostname == *pattern || hostname.ends_with(&format!(".{}", pattern))
+            }
+        })
+    }
+
+    fn normalize(name: &str) -> String {
+        name.trim().trim_end_matches('.').to_lowercase()
+    }
+}
+
+fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut last_star_opt: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            last_star_opt = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = last_star_opt {
+            // Let the last star swallow one more character and try again from there.
+            last_star_opt = Some((star_p, star_n + 1));
+            p = star_p + 1;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+// When the dns_utility subverts DNS on Linux, it comments out the existing nameserver directives in
+// /etc/resolv.conf and adds its own for 127.0.0.1; the commented-out ones are the original servers.
+// Forwarding to 127.0.0.1 would just bring the query back to us, so that one never counts.
+pub fn saved_resolvers_from_resolv_conf(contents: &str) -> Vec<IpAddr> {
+    let regex = Regex::new(r"(?m)^\s*#\s*nameserver\s+([^\s#]+)").expect("Regex syntax error");
+    regex
+        .captures_iter(contents)
+        .filter_map(|captures| IpAddr::from_str(&captures[1]).ok())
+        .filter(|ip_addr| *ip_addr != localhost())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_patterns_match_domain_and_subdomains() {
+        let subject = DnsBypassList::new("lan, MyBank.com.,,");
+
+        assert!(subject.matches("lan"));
+        assert!(subject.matches("printer.lan"));
+        assert!(subject.matches("www.mybank.com"));
+        assert!(subject.matches("MYBANK.COM."));
+        assert!(!subject.matches("notmybank.com"));
+        assert!(!subject.matches("mybank.com.evil.net"));
+        assert!(!subject.is_empty());
+    }
+
+    #[test]
+    fn wildcard_patterns_must_match_whole_hostname() {
+        let subject = DnsBypassList::new("updates*.example.com,*.local");
+
+        assert!(subject.matches("updates.example.com"));
+        assert!(subject.matches("updates2.eu.example.com"));
+        assert!(!subject.matches("www.example.com"));
+        assert!(subject.matches("printer.local"));
+        assert!(!subject.matches("local"));
+    }
+
+    #[test]
+    fn empty_list_matches_nothing() {
+        let subject = DnsBypassList::new(" , ");
+
+        assert!(subject.is_empty());
+        assert!(!subject.matches("lan"));
+    }
+
+    #[test]
+    fn saved_resolvers_are_the_commented_out_nameservers() {
+        let contents = "# Generated by NetworkManager\n\
+                        search home\n\
+                        #nameserver 192.168.1.1\n\
+                        # nameserver 2001:db8::53 # ISP\n\
+                        #nameserver 127.0.0.53\n\
+                        #nameserver 127.0.0.1\n\
+                        nameserver 127.0.0.1\n\
+                        nameserver 8.8.8.8\n";
+
+        let result = saved_resolvers_from_resolv_conf(contents);
+
+        assert_eq!(
+            result,
+            vec![
+                IpAddr::from_str("192.168.1.1").unwrap(),
+                IpAddr::from_str("2001:db8::53").unwrap(),
+                IpAddr::from_str("127.0.0.53").unwrap(),
+            ]
+        );
+    }
+}
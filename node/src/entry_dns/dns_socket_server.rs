@@ -7,15 +7,59 @@ use tokio::prelude::Async;
 use tokio::prelude::Future;
 
 const DNS_PORT: u16 = 53;
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
 
+use crate::entry_dns::dns_bypass::{saved_resolvers_from_resolv_conf, DnsBypassList};
+use crate::entry_dns::dns_tcp_server::DnsTcpServer;
+use crate::entry_dns::negative_cache::NegativeCache;
+use crate::entry_dns::packet_facade::{PacketFacade, Query};
 use crate::entry_dns::processing;
+use crate::node_configurator::node_configurator_standard::app;
+use crate::node_configurator::node_configurator_standard::standard::make_service_mode_multi_config;
+use crate::node_configurator::RealDirsWrapper;
 use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperReal;
 use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
+use clap::value_t;
+use masq_lib::multi_config::MultiConfig;
 use masq_lib::shared_schema::ConfiguratorError;
 use masq_lib::utils::localhost;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// Only on Linux does the dns_utility leave the original DNS servers somewhere we can read them: in
+// comments in /etc/resolv.conf. Elsewhere, bypassed queries go only to --dns-bypass-servers.
+#[cfg(target_os = "linux")]
+fn saved_resolvers_path_opt() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/resolv.conf"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn saved_resolvers_path_opt() -> Option<PathBuf> {
+    None
+}
+
+struct PendingForward {
+    client_addr: SocketAddr,
+    tcp_connection_id_opt: Option<u64>,
+    server_addr: SocketAddr,
+    transaction_id: u16,
+    questions: Vec<(String, u16, u16)>,
+    timestamp: Instant,
+}
 
 pub struct DnsSocketServer {
     socket_wrapper: Box<dyn UdpSocketWrapperTrait>,
+    tcp_server: DnsTcpServer,
+    upstream_socket_wrapper: Box<dyn UdpSocketWrapperTrait>,
+    resolv_conf_path_opt: Option<PathBuf>,
+    bypass_list: DnsBypassList,
+    upstream_servers: Vec<SocketAddr>,
+    pending_forwards: HashMap<u16, PendingForward>,
+    negative_cache: NegativeCache,
     buf: [u8; 65536],
 }
 
@@ -25,10 +69,13 @@ impl Future for DnsSocketServer {
 
     fn poll(&mut self) -> Result<Async<<Self as Future>::Item>, <Self as Future>::Error> {
         let logger = Logger::new("EntryDnsServer");
+        self.serve_tcp(&logger)?;
         loop {
+            let relayed = self.relay_upstream_response(&logger)?;
             let mut buffer = self.buf;
             let (len, socket_addr) = match self.socket_wrapper.recv_from(&mut buffer) {
                 Ok(Async::Ready((len, socket_addr))) => (len, socket_addr),
+                Ok(Async::NotReady) if relayed => continue,
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Err(e) => {
                     error!(
@@ -38,6 +85,12 @@ impl Future for DnsSocketServer {
                     return Err(());
                 }
             };
+            if !self.upstream_servers.is_empty()
+                && processing::should_forward(&mut buffer, len, &self.bypass_list)
+            {
+                self.forward_upstream(&mut buffer[0..len], socket_addr, None, &logger)?;
+                continue;
+            }
             let response_length = processing::process(&mut buffer, len, &socket_addr, &logger);
             if let Err(e) = self
                 .socket_wrapper
@@ -64,16 +117,23 @@ impl SocketServer<()> for DnsSocketServer {
         self.socket_wrapper
             .bind(socket_addr)
             .unwrap_or_else(|e| panic!("Cannot bind socket to {:?}: {:?}", socket_addr, e));
+        self.tcp_server
+            .bind(socket_addr)
+            .unwrap_or_else(|e| panic!("Cannot bind listener to {:?}: {:?}", socket_addr, e));
         Ok(())
     }
 
     fn initialize_as_unprivileged(
         &mut self,
-        _args: &[String],
-        _streams: &mut StdStreams<'_>,
+        args: &[String],
+        streams: &mut StdStreams<'_>,
     ) -> Result<(), ConfiguratorError> {
         self.buf = [0; 65536];
-        Ok(())
+        // The Bootstrapper parses the same arguments, and it reports any problems with them.
+        match make_service_mode_multi_config(&RealDirsWrapper {}, &app(), args, streams) {
+            Ok(multi_config) => self.configure_upstream(&multi_config),
+            Err(_) => Ok(()),
+        }
     }
 }
 
@@ -81,9 +141,270 @@ impl DnsSocketServer {
     pub fn new() -> DnsSocketServer {
         DnsSocketServer {
             socket_wrapper: Box::new(UdpSocketWrapperReal::new()),
+            tcp_server: DnsTcpServer::new(),
+            upstream_socket_wrapper: Box::new(UdpSocketWrapperReal::new()),
+            resolv_conf_path_opt: saved_resolvers_path_opt(),
+            bypass_list: DnsBypassList::default(),
+            upstream_servers: vec![],
+            pending_forwards: HashMap::new(),
+            negative_cache: NegativeCache::new(),
             buf: [0; 65536],
         }
     }
+
+    // Nothing leaves this machine unless one of the bypass parameters asks for it; otherwise only
+    // address queries are answered, as before.
+    fn configure_upstream(&mut self, multi_config: &MultiConfig) -> Result<(), ConfiguratorError> {
+        let bypass_opt = value_m!(multi_config, "dns-bypass", String);
+        let bypass_servers_opt = value_m!(multi_config, "dns-bypass-servers", String);
+        if bypass_opt.is_none() && bypass_servers_opt.is_none() {
+            return Ok(());
+        }
+        self.bypass_list = DnsBypassList::new(&bypass_opt.unwrap_or_default());
+        let server_ips = match bypass_servers_opt {
+            Some(server_ips) => server_ips
+                .split(',')
+                .filter_map(|ip_str| IpAddr::from_str(ip_str.trim()).ok())
+                .collect::<Vec<IpAddr>>(),
+            None => match &self.resolv_conf_path_opt {
+                Some(resolv_conf_path) => fs::read_to_string(resolv_conf_path)
+                    .map(|contents| saved_resolvers_from_resolv_conf(&contents))
+                    .unwrap_or_default(),
+                None => vec![],
+            },
+        };
+        // One upstream socket can only talk to servers of one address family.
+        let ipv4 = match server_ips.first() {
+            Some(ip_addr) => ip_addr.is_ipv4(),
+            // Without upstream servers, only address queries can be answered, as before.
+            None if self.bypass_list.is_empty() => return Ok(()),
+            None if self.resolv_conf_path_opt.is_none() => return Err(ConfiguratorError::required(
+                "dns-bypass-servers",
+                "--dns-bypass needs --dns-bypass-servers on this platform",
+            )),
+            None => return Err(ConfiguratorError::required(
+                "dns-bypass-servers",
+                "--dns-bypass needs DNS servers to send bypassed queries to, and none were saved when DNS was subverted",
+            )),
+        };
+        self.upstream_servers = server_ips
+            .into_iter()
+            .filter(|ip_addr| ip_addr.is_ipv4() == ipv4)
+            .map(|ip_addr| SocketAddr::new(ip_addr, DNS_PORT))
+            .collect();
+        let bind_addr = if ipv4 {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+        } else {
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)
+        };
+        match self.upstream_socket_wrapper.bind(bind_addr) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(ConfiguratorError::required(
+                "dns-bypass-servers",
+                &format!("Couldn't open a socket to the DNS servers: {}", e),
+            )),
+        }
+    }
+
+    // Requests over TCP are answered just like those over UDP; only the way back is different.
+    fn serve_tcp(&mut self, logger: &Logger) -> Result<(), ()> {
+        for request in self.tcp_server.poll_requests(logger) {
+            let mut buffer = self.buf;
+            let len = request.message.len();
+            buffer[0..len].copy_from_slice(&request.message);
+            if !self.upstream_servers.is_empty()
+                && processing::should_forward(&mut buffer, len, &self.bypass_list)
+            {
+                self.forward_upstream(
+                    &mut buffer[0..len],
+                    request.peer_addr,
+                    Some(request.connection_id),
+                    logger,
+                )?;
+                continue;
+            }
+            let response_length = processing::process(&mut buffer, len, &request.peer_addr, logger);
+            self.tcp_server.send_response(
+                request.connection_id,
+                &buffer[0..response_length],
+                logger,
+            );
+        }
+        Ok(())
+    }
+
+    // The query keeps its contents but gets a fresh random transaction ID, so that an answer can
+    // only be matched to it by someone who saw the query go out. Questions the upstream servers
+    // have recently said don't exist are answered from the negative cache instead.
+    fn forward_upstream(
+        &mut self,
+        request: &mut [u8],
+        client_addr: SocketAddr,
+        tcp_connection_id_opt: Option<u64>,
+        logger: &Logger,
+    ) -> Result<(), ()> {
+        let length = request.len();
+        let queries = PacketFacade::new(request, length)
+            .get_queries()
+            .unwrap_or_default();
+        if let Some(rcode) = self.negative_cache.lookup(&queries) {
+            let response_length =
+                processing::make_negative_response(&mut PacketFacade::new(request, length), rcode);
+            trace!(logger, "{} answered from negative cache", client_addr);
+            return self.send_to_client(
+                &request[0..response_length],
+                client_addr,
+                tcp_connection_id_opt,
+                logger,
+            );
+        }
+        self.pending_forwards
+            .retain(|_, pending| pending.timestamp.elapsed() < FORWARD_TIMEOUT);
+        let upstream_id = loop {
+            let candidate = rand::random::<u16>();
+            if !self.pending_forwards.contains_key(&candidate) {
+                break candidate;
+            }
+        };
+        let mut facade = PacketFacade::new(request, length);
+        let transaction_id = facade
+            .get_transaction_id()
+            .expect("Forwarded query has no header");
+        facade.set_transaction_id(upstream_id);
+        let server_addr = self.upstream_servers[upstream_id as usize % self.upstream_servers.len()];
+        match self.upstream_socket_wrapper.send_to(request, server_addr) {
+            Ok(_) => {
+                self.pending_forwards.insert(
+                    upstream_id,
+                    PendingForward {
+                        client_addr,
+                        tcp_connection_id_opt,
+                        server_addr,
+                        transaction_id,
+                        questions: Self::question_keys(&queries),
+                        timestamp: Instant::now(),
+                    },
+                );
+            }
+            Err(e) => warning!(
+                logger,
+                "Couldn't forward query from {} to {}: {}",
+                client_addr,
+                server_addr,
+                e
+            ),
+        }
+        Ok(())
+    }
+
+    // Answers from the upstream servers go back to the client unchanged except for the transaction
+    // ID, so their TTLs are the servers' own. An answer that can't be parsed, or that doesn't
+    // answer the questions that were asked, becomes a SERVFAIL.
+    fn relay_upstream_response(&mut self, logger: &Logger) -> Result<bool, ()> {
+        if self.pending_forwards.is_empty() {
+            return Ok(false);
+        }
+        let mut buffer = self.buf;
+        let (len, server_addr) = match self.upstream_socket_wrapper.recv_from(&mut buffer) {
+            Ok(Async::Ready((len, server_addr))) => (len, server_addr),
+            Ok(Async::NotReady) => return Ok(false),
+            Err(e) => {
+                warning!(logger, "Error receiving from DNS server: {}", e);
+                return Ok(false);
+            }
+        };
+        let mut facade = PacketFacade::new(&mut buffer, len);
+        let pending = match facade.get_transaction_id() {
+            Some(upstream_id)
+                if self
+                    .pending_forwards
+                    .get(&upstream_id)
+                    .map(|pending| pending.server_addr == server_addr)
+                    .unwrap_or(false) =>
+            {
+                self.pending_forwards
+                    .remove(&upstream_id)
+                    .expect("Pending forward disappeared")
+            }
+            _ => {
+                warning!(
+                    logger,
+                    "Discarding unexpected DNS response from {}",
+                    server_addr
+                );
+                return Ok(true);
+            }
+        };
+        facade.set_transaction_id(pending.transaction_id);
+        let well_formed = match facade.get_queries() {
+            Some(queries) => {
+                Self::question_keys(&queries) == pending.questions
+                    && facade.get_answers().is_some()
+                    && facade.get_authorities().is_some()
+            }
+            None => false,
+        };
+        let response_length = if well_formed {
+            self.negative_cache.record(&facade);
+            len
+        } else {
+            warning!(
+                logger,
+                "DNS server {} sent an unusable response for {}",
+                server_addr,
+                pending.client_addr
+            );
+            processing::make_server_failure(&mut facade)
+        };
+        trace!(
+            logger,
+            "{}ns: {} forwarded to {}",
+            pending.timestamp.elapsed().as_nanos(),
+            pending.client_addr,
+            server_addr
+        );
+        self.send_to_client(
+            &buffer[0..response_length],
+            pending.client_addr,
+            pending.tcp_connection_id_opt,
+            logger,
+        )?;
+        Ok(true)
+    }
+
+    fn send_to_client(
+        &mut self,
+        response: &[u8],
+        client_addr: SocketAddr,
+        tcp_connection_id_opt: Option<u64>,
+        logger: &Logger,
+    ) -> Result<(), ()> {
+        if let Some(connection_id) = tcp_connection_id_opt {
+            self.tcp_server
+                .send_response(connection_id, response, logger);
+            return Ok(());
+        }
+        match self.socket_wrapper.send_to(response, client_addr) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!(logger, "Unrecoverable error sending to UdpSocket: {}", e);
+                Err(())
+            }
+        }
+    }
+
+    fn question_keys(queries: &[Query]) -> Vec<(String, u16, u16)> {
+        queries
+            .iter()
+            .map(|query| {
+                (
+                    query.get_query_name().to_lowercase(),
+                    query.get_query_type(),
+                    query.get_query_class(),
+                )
+            })
+            .collect()
+    }
 }
 
 impl Default for DnsSocketServer {
@@ -96,10 +417,17 @@ impl Default for DnsSocketServer {
 mod tests {
     use super::super::packet_facade::PacketFacade;
     use super::*;
+    use crate::sub_lib::stream_connector::ConnectionInfo;
     use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
+    use crate::test_utils::little_tcp_server::LittleTcpServer;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
+    use crate::test_utils::stream_connector_mock::StreamConnectorMock;
+    use crate::test_utils::tokio_wrapper_mocks::{
+        ReadHalfWrapperMock, TokioListenerWrapperMock, WriteHalfWrapperMock,
+    };
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
+    use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, node_home_directory};
     use std::borrow::Borrow;
     use std::borrow::BorrowMut;
     use std::clone::Clone;
@@ -107,16 +435,20 @@ mod tests {
     use std::io;
     use std::io::Error;
     use std::io::ErrorKind;
+    use std::net::TcpStream as StdTcpStream;
     use std::ops::DerefMut;
     use std::str::FromStr;
     use std::sync::Arc;
     use std::sync::Mutex;
     use tokio;
-    use trust_dns::op::ResponseCode;
+    use tokio::net::TcpStream;
+    use tokio::reactor::Handle;
+    use trust_dns::op::{OpCode, ResponseCode};
+    use trust_dns::rr::{DNSClass, RecordType};
 
     struct UdpSocketWrapperMockGuts {
         log: Vec<String>,
-        buf: [u8; 12],
+        buf: Vec<u8>,
     }
 
     #[derive(Clone)]
@@ -155,36 +487,21 @@ mod tests {
             let guts_ref = unwrapped_guts.borrow_mut();
             let guts: &mut UdpSocketWrapperMockGuts = guts_ref.deref_mut();
             guts.log.push(format!("send_to (buf, {:?})", addr));
-            UdpSocketWrapperMock::copy(&mut guts.buf, buf);
+            guts.buf = buf.to_vec();
             self.send_to_results.lock().unwrap().borrow_mut().remove(0)
         }
     }
 
     impl UdpSocketWrapperMock {
         fn new(buf: &[u8]) -> UdpSocketWrapperMock {
-            assert_eq!(
-                buf.len() <= 12,
-                true,
-                "Mock accepts buffer of up to 12 bytes, not {}",
-                buf.len()
-            );
-            let result = UdpSocketWrapperMock {
+            UdpSocketWrapperMock {
                 guts: Arc::new(Mutex::new(UdpSocketWrapperMockGuts {
                     log: Vec::new(),
-                    buf: [0; 12],
+                    buf: buf.to_vec(),
                 })),
                 recv_from_results: Arc::new(Mutex::new(vec![])),
                 send_to_results: Arc::new(Mutex::new(vec![])),
-            };
-            result
-                .guts
-                .lock()
-                .unwrap()
-                .borrow_mut()
-                .deref_mut()
-                .buf
-                .copy_from_slice(&buf);
-            result
+            }
         }
 
         fn copy(destination: &mut [u8], source: &[u8]) {
@@ -198,7 +515,11 @@ mod tests {
     #[test]
     fn uses_standard_dns_port() {
         let socket_wrapper = make_socket_wrapper_mock();
+        let listener = TokioListenerWrapperMock::new().bind_result(Ok(()));
+        let listener_log = listener.log.clone();
         let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.tcp_server =
+            DnsTcpServer::new_for_test(Box::new(listener), Box::new(StreamConnectorMock::new()));
 
         subject
             .initialize_as_privileged(&[], &mut FakeStreamHolder::new().streams())
@@ -207,7 +528,8 @@ mod tests {
         let unwrapped_guts = socket_wrapper.guts.lock().unwrap();
         let borrowed_guts = unwrapped_guts.borrow();
         let log = &borrowed_guts.log;
-        assert_eq!(log[0], "bind ('127.0.0.1:53')")
+        assert_eq!(log[0], "bind ('127.0.0.1:53')");
+        assert_eq!(listener_log.dump(), vec!["bind (V4(127.0.0.1:53))"]);
     }
 
     #[test]
@@ -265,7 +587,10 @@ mod tests {
             let mut subject = make_instrumented_subject(socket_wrapper.clone());
 
             subject
-                .initialize_as_unprivileged(&[], &mut holder.streams())
+                .initialize_as_unprivileged(
+                    &make_args("serves_multiple_requests_then_short_circuit_on_error", &[]),
+                    &mut holder.streams(),
+                )
                 .unwrap();
             tokio::run(subject);
 
@@ -313,7 +638,10 @@ mod tests {
         let mut subject = make_instrumented_subject(socket_wrapper.clone());
 
         subject
-            .initialize_as_unprivileged(&[], &mut holder.streams())
+            .initialize_as_unprivileged(
+                &make_args("poll_handles_error_receiving_from_udp_socket_wrapper", &[]),
+                &mut holder.streams(),
+            )
             .unwrap();
 
         let result = subject.poll();
@@ -347,7 +675,10 @@ mod tests {
         let mut subject = make_instrumented_subject(socket_wrapper.clone());
 
         subject
-            .initialize_as_unprivileged(&[], &mut holder.streams())
+            .initialize_as_unprivileged(
+                &make_args("poll_handles_error_sending_to_udp_socket_wrapper", &[]),
+                &mut holder.streams(),
+            )
             .unwrap();
 
         let result = subject.poll();
@@ -359,16 +690,601 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bypassed_query_is_forwarded_upstream_and_answer_is_relayed_back() {
+        init_test_logging();
+        let query = make_query(0x1234, "printer.lan", RecordType::A);
+        let client_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let server_addr = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let socket_wrapper = Box::new(UdpSocketWrapperMock::new(&query));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), client_addr))));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        // The upstream mock answers with whatever it was sent, which is good enough for relaying.
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        upstream_socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        upstream_socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), server_addr))));
+        let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.bypass_list = DnsBypassList::new("lan");
+        subject.upstream_servers = vec![server_addr];
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        let client_guts = socket_wrapper.guts.lock().unwrap();
+        assert_eq!(
+            client_guts.log,
+            vec![
+                format!("recv_from (Ok(Ready(({}, {}))))", query.len(), client_addr),
+                format!("send_to (buf, {})", client_addr),
+                String::from("recv_from (Err(Kind(BrokenPipe)))"),
+            ]
+        );
+        assert_eq!(client_guts.buf, query);
+        let upstream_guts = upstream_socket_wrapper.guts.lock().unwrap();
+        assert_eq!(
+            upstream_guts.log,
+            vec![
+                format!("send_to (buf, {})", server_addr),
+                format!("recv_from (Ok(Ready(({}, {}))))", query.len(), server_addr),
+            ]
+        );
+        assert_eq!(&upstream_guts.buf[2..], &query[2..]);
+        assert!(subject.pending_forwards.is_empty());
+    }
+
+    #[test]
+    fn response_from_unexpected_server_is_discarded() {
+        init_test_logging();
+        let query = make_query(0x1234, "printer.lan", RecordType::A);
+        let client_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let socket_wrapper = Box::new(UdpSocketWrapperMock::new(&query));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), client_addr))));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        upstream_socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        upstream_socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((
+                query.len(),
+                SocketAddr::from_str("6.6.6.6:53").unwrap(),
+            ))));
+        let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.bypass_list = DnsBypassList::new("lan");
+        subject.upstream_servers = vec![SocketAddr::from_str("192.168.1.1:53").unwrap()];
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        assert_eq!(
+            socket_wrapper.guts.lock().unwrap().log,
+            vec![
+                format!("recv_from (Ok(Ready(({}, {}))))", query.len(), client_addr),
+                String::from("recv_from (Err(Kind(BrokenPipe)))"),
+            ]
+        );
+        assert_eq!(subject.pending_forwards.len(), 1);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: EntryDnsServer: Discarding unexpected DNS response from 6.6.6.6:53",
+        );
+    }
+
+    #[test]
+    fn non_address_query_is_forwarded_upstream_without_bypass_list() {
+        let query = make_query(0x1234, "example.com", RecordType::MX);
+        let client_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let server_addr = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let socket_wrapper = Box::new(UdpSocketWrapperMock::new(&query));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), client_addr))));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        upstream_socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        upstream_socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::NotReady));
+        let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.upstream_servers = vec![server_addr];
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        let upstream_guts = upstream_socket_wrapper.guts.lock().unwrap();
+        assert_eq!(
+            upstream_guts.log,
+            vec![
+                format!("send_to (buf, {})", server_addr),
+                String::from("recv_from (Ok(NotReady))"),
+            ]
+        );
+        assert_eq!(&upstream_guts.buf[2..], &query[2..]);
+        assert_eq!(subject.pending_forwards.len(), 1);
+    }
+
+    #[test]
+    fn cached_negative_answer_is_served_without_forwarding() {
+        let query = make_query(0x1234, "nowhere.example.com", RecordType::TXT);
+        let client_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let socket_wrapper = Box::new(UdpSocketWrapperMock::new(&query));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), client_addr))));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.upstream_servers = vec![SocketAddr::from_str("192.168.1.1:53").unwrap()];
+        let mut nxdomain = make_query(0x4321, "nowhere.example.com", RecordType::TXT);
+        nxdomain.resize(200, 0);
+        {
+            let mut facade = PacketFacade::new(&mut nxdomain, query.len());
+            facade.set_query(false);
+            facade.set_rcode(ResponseCode::NXDomain.low());
+            let mut soa_rdata = vec![0xC0, 0x0C, 0xC0, 0x0C];
+            soa_rdata.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4]);
+            soa_rdata.extend_from_slice(&300u32.to_be_bytes());
+            facade.add_authority(
+                "example.com",
+                RecordType::SOA.into(),
+                DNSClass::IN.into(),
+                900,
+                &soa_rdata,
+            );
+            subject.negative_cache.record(&facade);
+        }
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        assert!(upstream_socket_wrapper.guts.lock().unwrap().log.is_empty());
+        let mut response = socket_wrapper.guts.lock().unwrap().buf.clone();
+        let length = response.len();
+        let facade = PacketFacade::new(&mut response, length);
+        assert_eq!(facade.get_transaction_id(), Some(0x1234));
+        assert_eq!(facade.is_query(), Some(false));
+        assert_eq!(facade.get_rcode(), Some(ResponseCode::NXDomain.low()));
+        let queries = facade.get_queries().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].get_query_name(), "nowhere.example.com");
+        assert_eq!(facade.get_answers().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn unusable_upstream_response_becomes_server_failure() {
+        init_test_logging();
+        let query = make_query(0x1234, "example.com", RecordType::SRV);
+        let client_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let server_addr = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let socket_wrapper = Box::new(UdpSocketWrapperMock::new(&query));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), client_addr))));
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(12)));
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        upstream_socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        // Cut off in the middle of the question
+        upstream_socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((15, server_addr))));
+        let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.upstream_servers = vec![server_addr];
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        let mut response = socket_wrapper.guts.lock().unwrap().buf.clone();
+        assert_eq!(response.len(), 12);
+        let facade = PacketFacade::new(&mut response, 12);
+        assert_eq!(facade.get_transaction_id(), Some(0x1234));
+        assert_eq!(facade.get_rcode(), Some(ResponseCode::ServFail.low()));
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: EntryDnsServer: DNS server 192.168.1.1:53 sent an unusable response for {}",
+            client_addr
+        ));
+    }
+
+    #[test]
+    fn tcp_query_is_answered_with_length_prefix() {
+        let query = make_query(0x1234, "example.com", RecordType::A);
+        let mut tcp_request = (query.len() as u16).to_be_bytes().to_vec();
+        tcp_request.extend_from_slice(&query);
+        let poll_write_params_arc = Arc::new(Mutex::new(vec![]));
+        let server = LittleTcpServer::start();
+        let mut subject = make_instrumented_subject(make_failing_socket_wrapper_mock());
+        subject.tcp_server = make_tcp_server_with_connection(
+            &server,
+            ReadHalfWrapperMock::new()
+                .poll_read_ok(tcp_request)
+                .poll_read_result(vec![], Ok(Async::NotReady)),
+            WriteHalfWrapperMock::new()
+                .poll_write_params(&poll_write_params_arc)
+                .poll_write_result(Ok(Async::NotReady)),
+        );
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        let poll_write_params = poll_write_params_arc.lock().unwrap();
+        assert_eq!(poll_write_params.len(), 1);
+        let written = &poll_write_params[0];
+        let length = u16::from_be_bytes([written[0], written[1]]) as usize;
+        assert_eq!(written.len(), length + 2);
+        let mut response = written[2..].to_vec();
+        let facade = PacketFacade::new(&mut response, length);
+        assert_eq!(facade.get_transaction_id(), Some(0x1234));
+        assert_eq!(facade.is_query(), Some(false));
+        let answers = facade.get_answers().unwrap();
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0].get_rdata(), &[127, 0, 0, 1]);
+    }
+
+    #[test]
+    fn tcp_query_can_be_forwarded_upstream_and_answered_over_tcp() {
+        let query = make_query(0x1234, "example.com", RecordType::MX);
+        let mut tcp_request = (query.len() as u16).to_be_bytes().to_vec();
+        tcp_request.extend_from_slice(&query);
+        let server_addr = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let poll_write_params_arc = Arc::new(Mutex::new(vec![]));
+        let server = LittleTcpServer::start();
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        upstream_socket_wrapper
+            .send_to_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready(query.len())));
+        upstream_socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Ok(Async::Ready((query.len(), server_addr))));
+        let mut subject = make_instrumented_subject(make_failing_socket_wrapper_mock());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.upstream_servers = vec![server_addr];
+        subject.tcp_server = make_tcp_server_with_connection(
+            &server,
+            ReadHalfWrapperMock::new()
+                .poll_read_ok(tcp_request.clone())
+                .poll_read_result(vec![], Ok(Async::NotReady)),
+            WriteHalfWrapperMock::new()
+                .poll_write_params(&poll_write_params_arc)
+                .poll_write_ok(tcp_request.len()),
+        );
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        assert_eq!(
+            upstream_socket_wrapper.guts.lock().unwrap().log,
+            vec![
+                format!("send_to (buf, {})", server_addr),
+                format!("recv_from (Ok(Ready(({}, {}))))", query.len(), server_addr),
+            ]
+        );
+        // The upstream mock answers with whatever it was sent.
+        assert_eq!(*poll_write_params_arc.lock().unwrap(), vec![tcp_request]);
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_configures_bypass_from_parameters() {
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        let args = make_args(
+            "initialize_as_unprivileged_configures_bypass_from_parameters",
+            &[
+                ("--dns-bypass", "lan,*.mybank.com"),
+                ("--dns-bypass-servers", "192.168.1.1,2001:db8::53,10.0.0.53"),
+            ],
+        );
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(subject.bypass_list, DnsBypassList::new("lan,*.mybank.com"));
+        assert_eq!(
+            subject.upstream_servers,
+            vec![
+                SocketAddr::from_str("192.168.1.1:53").unwrap(),
+                SocketAddr::from_str("10.0.0.53:53").unwrap(),
+            ]
+        );
+        assert_eq!(
+            upstream_socket_wrapper.guts.lock().unwrap().log,
+            vec![String::from("bind ('0.0.0.0:0')")]
+        );
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_falls_back_to_servers_saved_in_resolv_conf() {
+        let test_name = "initialize_as_unprivileged_falls_back_to_servers_saved_in_resolv_conf";
+        let args = make_args(test_name, &[("--dns-bypass", "lan")]);
+        let resolv_conf_path =
+            node_home_directory("dns_socket_server", test_name).join("resolv.conf");
+        std::fs::write(
+            &resolv_conf_path,
+            "#nameserver 192.168.1.1\nnameserver 127.0.0.1\n",
+        )
+        .unwrap();
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.resolv_conf_path_opt = Some(resolv_conf_path);
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            subject.upstream_servers,
+            vec![SocketAddr::from_str("192.168.1.1:53").unwrap()]
+        );
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_complains_when_there_is_nowhere_to_send_bypassed_queries() {
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        let args = make_args(
+            "initialize_as_unprivileged_complains_when_there_is_nowhere_to_send_bypassed_queries",
+            &[("--dns-bypass", "lan")],
+        );
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "dns-bypass-servers",
+                "--dns-bypass needs DNS servers to send bypassed queries to, and none were saved when DNS was subverted"
+            ))
+        );
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_demands_bypass_servers_where_none_are_saved() {
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.resolv_conf_path_opt = None;
+        let args = make_args(
+            "initialize_as_unprivileged_demands_bypass_servers_where_none_are_saved",
+            &[("--dns-bypass", "lan")],
+        );
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "dns-bypass-servers",
+                "--dns-bypass needs --dns-bypass-servers on this platform"
+            ))
+        );
+    }
+
+    #[test]
+    fn saved_resolvers_are_read_from_resolv_conf_only_on_linux() {
+        let result = saved_resolvers_path_opt();
+
+        if cfg!(target_os = "linux") {
+            assert_eq!(result, Some(PathBuf::from("/etc/resolv.conf")));
+        } else {
+            assert_eq!(result, None);
+        }
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_configures_upstream_servers_without_bypass_list() {
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        let args = make_args(
+            "initialize_as_unprivileged_configures_upstream_servers_without_bypass_list",
+            &[("--dns-bypass-servers", "2001:db8::53")],
+        );
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(result, Ok(()));
+        assert!(subject.bypass_list.is_empty());
+        assert_eq!(
+            subject.upstream_servers,
+            vec![SocketAddr::from_str("[2001:db8::53]:53").unwrap()]
+        );
+        assert_eq!(
+            upstream_socket_wrapper.guts.lock().unwrap().log,
+            vec![String::from("bind ('[::]:0')")]
+        );
+    }
+
+    #[test]
+    fn initialize_as_unprivileged_leaves_bypass_off_by_default() {
+        let test_name = "initialize_as_unprivileged_leaves_bypass_off_by_default";
+        let args = make_args(test_name, &[]);
+        let resolv_conf_path =
+            node_home_directory("dns_socket_server", test_name).join("resolv.conf");
+        std::fs::write(
+            &resolv_conf_path,
+            "#nameserver 192.168.1.1\nnameserver 127.0.0.1\n",
+        )
+        .unwrap();
+        let upstream_socket_wrapper = Box::new(UdpSocketWrapperMock::new(&[]));
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.upstream_socket_wrapper = upstream_socket_wrapper.clone();
+        subject.resolv_conf_path_opt = Some(resolv_conf_path);
+
+        let result =
+            subject.initialize_as_unprivileged(&args, &mut FakeStreamHolder::new().streams());
+
+        assert_eq!(result, Ok(()));
+        assert!(subject.bypass_list.is_empty());
+        assert!(subject.upstream_servers.is_empty());
+        assert!(upstream_socket_wrapper.guts.lock().unwrap().log.is_empty());
+    }
+
     fn make_socket_wrapper_mock() -> Box<UdpSocketWrapperMock> {
         Box::new(UdpSocketWrapperMock::new(&[
             0x12, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
         ]))
     }
 
+    fn make_failing_socket_wrapper_mock() -> Box<UdpSocketWrapperMock> {
+        let socket_wrapper = make_socket_wrapper_mock();
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        socket_wrapper
+    }
+
+    fn make_tcp_server_with_connection(
+        server: &LittleTcpServer,
+        reader: ReadHalfWrapperMock,
+        writer: WriteHalfWrapperMock,
+    ) -> DnsTcpServer {
+        let peer_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        let std_stream = StdTcpStream::connect(server.socket_addr()).unwrap();
+        let stream = TcpStream::from_std(std_stream, &Handle::default()).unwrap();
+        let listener = TokioListenerWrapperMock::new()
+            .bind_result(Ok(()))
+            .poll_accept_results(vec![
+                Ok(Async::Ready((stream, peer_addr))),
+                Ok(Async::NotReady),
+            ]);
+        let stream_connector =
+            StreamConnectorMock::new().split_stream_result(Some(ConnectionInfo {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                local_addr: SocketAddr::from_str("127.0.0.1:53").unwrap(),
+                peer_addr,
+            }));
+        let mut tcp_server =
+            DnsTcpServer::new_for_test(Box::new(listener), Box::new(stream_connector));
+        tcp_server
+            .bind(SocketAddr::from_str("127.0.0.1:53").unwrap())
+            .unwrap();
+        tcp_server
+    }
+
     fn make_instrumented_subject(socket_wrapper: Box<UdpSocketWrapperMock>) -> DnsSocketServer {
         DnsSocketServer {
             socket_wrapper,
+            tcp_server: DnsTcpServer::new(),
+            upstream_socket_wrapper: make_socket_wrapper_mock(),
+            resolv_conf_path_opt: Some(PathBuf::from("/nonexistent/resolv.conf")),
+            bypass_list: DnsBypassList::default(),
+            upstream_servers: vec![],
+            pending_forwards: HashMap::new(),
+            negative_cache: NegativeCache::new(),
             buf: [0; 65536],
         }
     }
+
+    fn make_args(test_name: &str, params: &[(&str, &str)]) -> Vec<String> {
+        let home_dir = ensure_node_home_directory_exists("dns_socket_server", test_name);
+        params.iter().fold(
+            vec![
+                "MASQNode".to_string(),
+                "--data-directory".to_string(),
+                home_dir.to_string_lossy().to_string(),
+            ],
+            |mut args, (name, value)| {
+                args.push(name.to_string());
+                args.push(value.to_string());
+                args
+            },
+        )
+    }
+
+    fn make_query(transaction_id: u16, name: &str, record_type: RecordType) -> Vec<u8> {
+        let mut buf: [u8; 100] = [0; 100];
+        let length = {
+            let mut facade = PacketFacade::new(&mut buf, 12);
+            facade.set_transaction_id(transaction_id);
+            facade.set_query(true);
+            facade.set_opcode(OpCode::Query.into());
+            facade.add_query(name, record_type.into(), DNSClass::IN.into());
+            facade.get_length()
+        };
+        buf[0..length].to_vec()
+    }
 }
@@ -0,0 +1,616 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::sub_lib::logger::Logger;
+use crate::sub_lib::stream_connector::StreamConnector;
+use crate::sub_lib::stream_connector::StreamConnectorReal;
+use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
+use crate::sub_lib::tokio_wrappers::TokioListenerWrapper;
+use crate::sub_lib::tokio_wrappers::TokioListenerWrapperReal;
+use crate::sub_lib::tokio_wrappers::WriteHalfWrapper;
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::prelude::Async;
+use tokio::prelude::Future;
+use tokio::timer::Delay;
+
+const MAX_CONNECTIONS: usize = 64;
+// Longer than the time a forwarded query may wait for its answer, so that a client waiting on one
+// isn't cut off before it's either answered or given up on.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15);
+const LENGTH_PREFIX_SIZE: usize = 2;
+// Room for one message of the largest size the length prefix can describe. A client that sends
+// more than that before it's answered, or doesn't read its answers, is cut off.
+const MAX_BUFFER_SIZE: usize = LENGTH_PREFIX_SIZE + u16::MAX as usize;
+
+#[derive(Debug, PartialEq)]
+pub struct DnsTcpRequest {
+    pub connection_id: u64,
+    pub peer_addr: SocketAddr,
+    pub message: Vec<u8>,
+}
+
+struct DnsTcpConnection {
+    peer_addr: SocketAddr,
+    reader: Box<dyn ReadHalfWrapper>,
+    writer: Box<dyn WriteHalfWrapper>,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    unanswered: usize,
+    closed_by_peer: bool,
+    last_activity: Instant,
+}
+
+impl DnsTcpConnection {
+    fn read_requests(
+        &mut self,
+        connection_id: u64,
+        requests: &mut Vec<DnsTcpRequest>,
+    ) -> io::Result<()> {
+        let mut buf = [0u8; 4096];
+        while !self.closed_by_peer {
+            // Every complete message has been taken out, so there's always room for one more byte.
+            let room = (MAX_BUFFER_SIZE - self.incoming.len()).min(buf.len());
+            match self.reader.poll_read(&mut buf[0..room])? {
+                Async::NotReady => break,
+                Async::Ready(0) => self.closed_by_peer = true,
+                Async::Ready(len) => {
+                    self.last_activity = Instant::now();
+                    self.incoming.extend_from_slice(&buf[0..len]);
+                    while let Some(message) = self.take_message() {
+                        self.unanswered += 1;
+                        requests.push(DnsTcpRequest {
+                            connection_id,
+                            peer_addr: self.peer_addr,
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Every DNS message on a TCP connection is preceded by its length as a two-byte big-endian
+    // integer (RFC 1035 section 4.2.2).
+    fn take_message(&mut self) -> Option<Vec<u8>> {
+        if self.incoming.len() < LENGTH_PREFIX_SIZE {
+            return None;
+        }
+        let end =
+            LENGTH_PREFIX_SIZE + u16::from_be_bytes([self.incoming[0], self.incoming[1]]) as usize;
+        if self.incoming.len() < end {
+            return None;
+        }
+        let message = self.incoming[LENGTH_PREFIX_SIZE..end].to_vec();
+        self.incoming.drain(0..end);
+        Some(message)
+    }
+
+    fn write_responses(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.writer.poll_write(&self.outgoing)? {
+                Async::NotReady => break,
+                Async::Ready(0) => return Err(io::Error::from(ErrorKind::WriteZero)),
+                Async::Ready(len) => {
+                    self.last_activity = Instant::now();
+                    self.outgoing.drain(0..len);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.closed_by_peer && self.unanswered == 0 && self.outgoing.is_empty()
+    }
+}
+
+// Accepts DNS clients that use TCP, which they do when a UDP answer comes back truncated or when
+// they expect answers too large for UDP. Requests are handed back to the DnsSocketServer, which
+// answers them the same way it answers requests that arrive over UDP.
+pub struct DnsTcpServer {
+    listener: Box<dyn TokioListenerWrapper>,
+    stream_connector: Box<dyn StreamConnector>,
+    listening: bool,
+    connections: HashMap<u64, DnsTcpConnection>,
+    next_connection_id: u64,
+    max_connections: usize,
+    idle_timeout: Duration,
+    idle_timer_opt: Option<Delay>,
+}
+
+impl Default for DnsTcpServer {
+    fn default() -> Self {
+        DnsTcpServer {
+            listener: Box::new(TokioListenerWrapperReal::new()),
+            stream_connector: Box::new(StreamConnectorReal {}),
+            listening: false,
+            connections: HashMap::new(),
+            next_connection_id: 0,
+            max_connections: MAX_CONNECTIONS,
+            idle_timeout: IDLE_TIMEOUT,
+            idle_timer_opt: None,
+        }
+    }
+}
+
+impl DnsTcpServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[cfg(test)]
+    pub fn new_for_test(
+        listener: Box<dyn TokioListenerWrapper>,
+        stream_connector: Box<dyn StreamConnector>,
+    ) -> Self {
+        DnsTcpServer {
+            listener,
+            stream_connector,
+            ..Self::default()
+        }
+    }
+
+    pub fn bind(&mut self, addr: SocketAddr) -> io::Result<()> {
+        self.listener.bind(addr)?;
+        self.listening = true;
+        Ok(())
+    }
+
+    // Everything is polled until it's NotReady, so that the task will be woken when any of it
+    // has more to offer.
+    pub fn poll_requests(&mut self, logger: &Logger) -> Vec<DnsTcpRequest> {
+        self.close_idle_connections(logger);
+        self.accept_connections(logger);
+        let mut requests = vec![];
+        let mut failed_connection_ids = vec![];
+        for (connection_id, connection) in self.connections.iter_mut() {
+            if let Err(e) = connection
+                .read_requests(*connection_id, &mut requests)
+                .and_then(|_| connection.write_responses())
+            {
+                debug!(
+                    logger,
+                    "Dropping DNS connection from {}: {}", connection.peer_addr, e
+                );
+                failed_connection_ids.push(*connection_id);
+            }
+        }
+        failed_connection_ids.iter().for_each(|connection_id| {
+            self.connections.remove(connection_id);
+        });
+        requests.retain(|request| self.connections.contains_key(&request.connection_id));
+        self.connections
+            .retain(|_, connection| !connection.is_finished());
+        self.schedule_idle_check(logger);
+        requests
+    }
+
+    // If the client has gone away in the meantime, the response is quietly dropped.
+    pub fn send_response(&mut self, connection_id: u64, response: &[u8], logger: &Logger) {
+        let connection = match self.connections.get_mut(&connection_id) {
+            Some(connection) => connection,
+            None => return,
+        };
+        connection.unanswered = connection.unanswered.saturating_sub(1);
+        if response.len() > u16::MAX as usize {
+            error!(
+                logger,
+                "Can't send {}-byte DNS response to {} over TCP",
+                response.len(),
+                connection.peer_addr
+            );
+        } else if connection.outgoing.len() + LENGTH_PREFIX_SIZE + response.len() > MAX_BUFFER_SIZE
+        {
+            debug!(
+                logger,
+                "Dropping DNS connection from {}: it isn't reading its responses",
+                connection.peer_addr
+            );
+            self.connections.remove(&connection_id);
+            return;
+        } else {
+            connection
+                .outgoing
+                .extend_from_slice(&(response.len() as u16).to_be_bytes());
+            connection.outgoing.extend_from_slice(response);
+        }
+        match connection.write_responses() {
+            Ok(()) if connection.is_finished() => {
+                self.connections.remove(&connection_id);
+            }
+            Ok(()) => (),
+            Err(e) => {
+                debug!(
+                    logger,
+                    "Dropping DNS connection from {}: {}", connection.peer_addr, e
+                );
+                self.connections.remove(&connection_id);
+            }
+        }
+    }
+
+    fn accept_connections(&mut self, logger: &Logger) {
+        if !self.listening {
+            return;
+        }
+        loop {
+            match self.listener.poll_accept() {
+                Ok(Async::Ready((stream, peer_addr))) => {
+                    if self.connections.len() >= self.max_connections {
+                        // Dropping the stream closes it.
+                        warning!(
+                            logger,
+                            "Refusing DNS connection from {}: already serving {} connections",
+                            peer_addr,
+                            self.max_connections
+                        );
+                        continue;
+                    }
+                    let connection_info = match self.stream_connector.split_stream(stream, logger) {
+                        Some(connection_info) => connection_info,
+                        None => continue,
+                    };
+                    self.connections.insert(
+                        self.next_connection_id,
+                        DnsTcpConnection {
+                            peer_addr,
+                            reader: connection_info.reader,
+                            writer: connection_info.writer,
+                            incoming: vec![],
+                            outgoing: vec![],
+                            unanswered: 0,
+                            closed_by_peer: false,
+                            last_activity: Instant::now(),
+                        },
+                    );
+                    self.next_connection_id = self.next_connection_id.wrapping_add(1);
+                }
+                Ok(Async::NotReady) => return,
+                Err(e) => {
+                    warning!(logger, "Could not accept DNS connection: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    // Idle connections would otherwise only be noticed when some other traffic woke the task, so a
+    // timer wakes it when the connection that has been quiet longest is due to be closed.
+    fn schedule_idle_check(&mut self, logger: &Logger) {
+        loop {
+            let idle_timeout = self.idle_timeout;
+            let deadline = match self
+                .connections
+                .values()
+                .map(|connection| connection.last_activity + idle_timeout)
+                .min()
+            {
+                Some(deadline) => deadline,
+                None => {
+                    self.idle_timer_opt = None;
+                    return;
+                }
+            };
+            let idle_timer = self
+                .idle_timer_opt
+                .get_or_insert_with(|| Delay::new(deadline));
+            idle_timer.reset(deadline);
+            match idle_timer.poll() {
+                Ok(Async::NotReady) => return,
+                Ok(Async::Ready(())) => self.close_idle_connections(logger),
+                Err(e) => {
+                    debug!(logger, "Can't time out idle DNS connections: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn close_idle_connections(&mut self, logger: &Logger) {
+        let idle_timeout = self.idle_timeout;
+        self.connections.retain(|_, connection| {
+            let idle = connection.last_activity.elapsed() >= idle_timeout;
+            if idle {
+                debug!(
+                    logger,
+                    "Closing idle DNS connection from {}", connection.peer_addr
+                );
+            }
+            !idle
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sub_lib::stream_connector::ConnectionInfo;
+    use crate::test_utils::little_tcp_server::LittleTcpServer;
+    use crate::test_utils::logging::init_test_logging;
+    use crate::test_utils::logging::TestLogHandler;
+    use crate::test_utils::stream_connector_mock::StreamConnectorMock;
+    use crate::test_utils::tokio_wrapper_mocks::ReadHalfWrapperMock;
+    use crate::test_utils::tokio_wrapper_mocks::TokioListenerWrapperMock;
+    use crate::test_utils::tokio_wrapper_mocks::WriteHalfWrapperMock;
+    use std::net::TcpStream as StdTcpStream;
+    use std::str::FromStr;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tokio::net::TcpStream;
+    use tokio::prelude::future::poll_fn;
+    use tokio::reactor::Handle;
+
+    fn make_connection(
+        reader: ReadHalfWrapperMock,
+        writer: WriteHalfWrapperMock,
+    ) -> DnsTcpConnection {
+        DnsTcpConnection {
+            peer_addr: SocketAddr::from_str("127.0.0.1:4567").unwrap(),
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            incoming: vec![],
+            outgoing: vec![],
+            unanswered: 0,
+            closed_by_peer: false,
+            last_activity: Instant::now(),
+        }
+    }
+
+    fn make_stream(server: &LittleTcpServer) -> TcpStream {
+        let std_stream = StdTcpStream::connect(server.socket_addr()).unwrap();
+        TcpStream::from_std(std_stream, &Handle::default()).unwrap()
+    }
+
+    #[test]
+    fn reassembles_length_prefixed_requests_split_across_reads() {
+        let reader = ReadHalfWrapperMock::new()
+            .poll_read_ok(vec![0, 5, 1, 2])
+            .poll_read_ok(vec![3, 4, 5, 0, 2, 9])
+            .poll_read_ok(vec![9, 0])
+            .poll_read_result(vec![], Ok(Async::NotReady));
+        let mut subject = DnsTcpServer::new();
+        subject
+            .connections
+            .insert(7, make_connection(reader, WriteHalfWrapperMock::new()));
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        let peer_addr = SocketAddr::from_str("127.0.0.1:4567").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                DnsTcpRequest {
+                    connection_id: 7,
+                    peer_addr,
+                    message: vec![1, 2, 3, 4, 5],
+                },
+                DnsTcpRequest {
+                    connection_id: 7,
+                    peer_addr,
+                    message: vec![9, 9],
+                },
+            ]
+        );
+        let connection = subject.connections.get(&7).unwrap();
+        assert_eq!(connection.incoming, vec![0]);
+        assert_eq!(connection.unanswered, 2);
+    }
+
+    #[test]
+    fn reads_no_more_than_the_largest_request_at_once() {
+        let reader = ReadHalfWrapperMock::new()
+            .poll_read_ok(vec![0xAA; 7])
+            .poll_read_result(vec![], Ok(Async::NotReady));
+        let mut connection = make_connection(reader, WriteHalfWrapperMock::new());
+        connection.incoming = vec![0xFF, 0xFF];
+        connection.incoming.extend(vec![0xAA; MAX_BUFFER_SIZE - 9]);
+        let mut subject = DnsTcpServer::new();
+        subject.connections.insert(7, connection);
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].message, vec![0xAA; u16::MAX as usize]);
+        assert!(subject.connections.get(&7).unwrap().incoming.is_empty());
+    }
+
+    #[test]
+    fn connection_that_does_not_read_its_responses_is_dropped() {
+        init_test_logging();
+        let reader = ReadHalfWrapperMock::new().poll_read_result(vec![], Ok(Async::NotReady));
+        let writer = WriteHalfWrapperMock::new().poll_write_result(Ok(Async::NotReady));
+        let mut connection = make_connection(reader, writer);
+        connection.outgoing = vec![0; MAX_BUFFER_SIZE - 3];
+        connection.unanswered = 2;
+        let mut subject = DnsTcpServer::new();
+        subject.connections.insert(7, connection);
+
+        subject.send_response(7, &[1, 2], &Logger::new("DnsTcpServer"));
+
+        assert!(subject.connections.is_empty());
+        TestLogHandler::new().exists_log_containing(
+            "DEBUG: DnsTcpServer: Dropping DNS connection from 127.0.0.1:4567: it isn't reading its responses",
+        );
+    }
+
+    #[test]
+    fn responses_are_sent_with_length_prefix_and_leftovers_are_sent_later() {
+        let poll_write_params_arc = Arc::new(Mutex::new(vec![]));
+        let reader = ReadHalfWrapperMock::new().poll_read_result(vec![], Ok(Async::NotReady));
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_params(&poll_write_params_arc)
+            .poll_write_ok(3)
+            .poll_write_result(Ok(Async::NotReady))
+            .poll_write_ok(2);
+        let mut subject = DnsTcpServer::new();
+        subject
+            .connections
+            .insert(7, make_connection(reader, writer));
+        subject.connections.get_mut(&7).unwrap().unanswered = 1;
+
+        subject.send_response(7, &[1, 2, 3], &Logger::new("test"));
+        subject.send_response(8, &[4, 5, 6], &Logger::new("test"));
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert!(result.is_empty());
+        assert_eq!(
+            *poll_write_params_arc.lock().unwrap(),
+            vec![vec![0, 3, 1, 2, 3], vec![2, 3], vec![2, 3]]
+        );
+        let connection = subject.connections.get(&7).unwrap();
+        assert!(connection.outgoing.is_empty());
+        assert_eq!(connection.unanswered, 0);
+    }
+
+    #[test]
+    fn connection_closed_by_peer_stays_open_until_its_requests_are_answered() {
+        let reader = ReadHalfWrapperMock::new()
+            .poll_read_ok(vec![0, 1, 42, 0, 0])
+            .poll_read_ok(vec![]);
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_ok(3)
+            .poll_write_ok(2);
+        let mut subject = DnsTcpServer::new();
+        subject
+            .connections
+            .insert(7, make_connection(reader, writer));
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert_eq!(result.len(), 2);
+        assert!(subject.connections.contains_key(&7));
+
+        subject.send_response(7, &[42], &Logger::new("test"));
+
+        assert!(subject.connections.contains_key(&7));
+
+        subject.send_response(7, &[], &Logger::new("test"));
+
+        assert!(subject.connections.is_empty());
+    }
+
+    #[test]
+    fn connections_that_fail_are_dropped_with_their_requests() {
+        let reader = ReadHalfWrapperMock::new()
+            .poll_read_ok(vec![0, 1, 42])
+            .poll_read_result(vec![], Err(io::Error::from(ErrorKind::ConnectionReset)));
+        let mut subject = DnsTcpServer::new();
+        subject
+            .connections
+            .insert(7, make_connection(reader, WriteHalfWrapperMock::new()));
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert!(result.is_empty());
+        assert!(subject.connections.is_empty());
+    }
+
+    #[test]
+    fn idle_connections_are_closed() {
+        let mut subject = DnsTcpServer::new();
+        let mut connection =
+            make_connection(ReadHalfWrapperMock::new(), WriteHalfWrapperMock::new());
+        connection.last_activity = Instant::now() - Duration::from_secs(16);
+        subject.connections.insert(7, connection);
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert!(result.is_empty());
+        assert!(subject.connections.is_empty());
+    }
+
+    #[test]
+    fn idle_connections_are_closed_without_any_other_traffic() {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut subject = DnsTcpServer::new();
+            subject.idle_timeout = Duration::from_millis(50);
+            let reader = ReadHalfWrapperMock::new()
+                .poll_read_result(vec![], Ok(Async::NotReady))
+                .poll_read_result(vec![], Ok(Async::NotReady))
+                .poll_read_result(vec![], Ok(Async::NotReady));
+            subject
+                .connections
+                .insert(7, make_connection(reader, WriteHalfWrapperMock::new()));
+            let logger = Logger::new("test");
+            tokio::run(poll_fn(move || {
+                subject.poll_requests(&logger);
+                if subject.connections.is_empty() {
+                    tx.send(()).unwrap();
+                    Ok(Async::Ready(()))
+                } else {
+                    Ok(Async::NotReady)
+                }
+            }));
+        });
+
+        let result = rx.recv_timeout(Duration::from_secs(5));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn accepts_connections_up_to_the_limit() {
+        init_test_logging();
+        let server = LittleTcpServer::start();
+        let listener = TokioListenerWrapperMock::new()
+            .bind_result(Ok(()))
+            .poll_accept_results(vec![
+                Ok(Async::Ready((
+                    make_stream(&server),
+                    SocketAddr::from_str("127.0.0.1:1111").unwrap(),
+                ))),
+                Ok(Async::Ready((
+                    make_stream(&server),
+                    SocketAddr::from_str("127.0.0.1:2222").unwrap(),
+                ))),
+                Ok(Async::NotReady),
+            ]);
+        let listener_log = listener.log.clone();
+        let stream_connector =
+            StreamConnectorMock::new().split_stream_result(Some(ConnectionInfo {
+                reader: Box::new(
+                    ReadHalfWrapperMock::new().poll_read_result(vec![], Ok(Async::NotReady)),
+                ),
+                writer: Box::new(WriteHalfWrapperMock::new()),
+                local_addr: SocketAddr::from_str("127.0.0.1:53").unwrap(),
+                peer_addr: SocketAddr::from_str("127.0.0.1:1111").unwrap(),
+            }));
+        let mut subject = DnsTcpServer::new();
+        subject.listener = Box::new(listener);
+        subject.stream_connector = Box::new(stream_connector);
+        subject.max_connections = 1;
+        subject
+            .bind(SocketAddr::from_str("127.0.0.1:53").unwrap())
+            .unwrap();
+
+        let result = subject.poll_requests(&Logger::new("DnsTcpServer"));
+
+        assert!(result.is_empty());
+        assert_eq!(
+            listener_log.dump(),
+            vec![String::from("bind (V4(127.0.0.1:53))")]
+        );
+        assert_eq!(subject.connections.len(), 1);
+        assert_eq!(
+            subject.connections.get(&0).unwrap().peer_addr,
+            SocketAddr::from_str("127.0.0.1:1111").unwrap()
+        );
+        TestLogHandler::new().exists_log_containing(
+            "WARN: DnsTcpServer: Refusing DNS connection from 127.0.0.1:2222: already serving 1 connections",
+        );
+    }
+
+    #[test]
+    fn does_not_accept_connections_before_binding() {
+        let mut subject = DnsTcpServer::new();
+
+        let result = subject.poll_requests(&Logger::new("test"));
+
+        assert!(result.is_empty());
+    }
+}
@@ -2,5 +2,8 @@
 
 #[macro_use]
 pub mod packet_facade; // public only so that it can be used by the integration test
+pub mod dns_bypass;
 pub mod dns_socket_server;
+mod dns_tcp_server;
+mod negative_cache;
 mod processing;
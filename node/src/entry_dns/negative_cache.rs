@@ -0,0 +1,236 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use super::packet_facade::{PacketFacade, Query};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+use trust_dns::op::ResponseCode;
+use trust_dns::rr::RecordType;
+
+// RFC 2308 recommends not caching negative answers for more than a few hours.
+const MAX_NEGATIVE_TTL_SECS: u32 = 3 * 3600;
+const MAX_ENTRIES: usize = 10_000;
+
+type QuestionKey = (String, u16, u16);
+
+// Remembers NXDOMAIN and NODATA answers from the upstream DNS servers, so that names and record
+// types that don't exist aren't asked about again until the answer's negative TTL runs out.
+// As RFC 2308 requires, an answer is only cached if it comes with an SOA record for its TTL.
+#[derive(Default)]
+pub struct NegativeCache {
+    entries: HashMap<QuestionKey, (u8, Instant)>,
+}
+
+impl NegativeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lookup(&mut self, queries: &[Query]) -> Option<u8> {
+        let key = Self::key_for(queries)?;
+        match self.entries.get(&key) {
+            Some((rcode, expiration)) if *expiration > Instant::now() => Some(*rcode),
+            Some(_) => {
+                self.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn record(&mut self, response: &PacketFacade) {
+        let key = match response.get_queries() {
+            Some(queries) => match Self::key_for(&queries) {
+                Some(key) => key,
+                None => return,
+            },
+            None => return,
+        };
+        let rcode = response.get_rcode().unwrap_or(0xFF);
+        let answer_count = response.get_answers().map(|answers| answers.len());
+        let negative = rcode == ResponseCode::NXDomain.low()
+            || (rcode == ResponseCode::NoError.low() && answer_count == Some(0));
+        if !negative {
+            self.entries.remove(&key);
+            return;
+        }
+        let ttl_opt = response.get_authorities().and_then(|authorities| {
+            authorities
+                .iter()
+                .filter(|record| record.get_resource_type() == u16::from(RecordType::SOA))
+                .filter_map(|soa| {
+                    // The SOA's MINIMUM field is the last four bytes of its rdata.
+                    let rdata = soa.get_rdata();
+                    let minimum_bytes = rdata.get(rdata.len().checked_sub(4)?..)?;
+                    let minimum = u32::from_be_bytes(<[u8; 4]>::try_from(minimum_bytes).ok()?);
+                    Some(soa.get_time_to_live().min(minimum))
+                })
+                .min()
+        });
+        let ttl = match ttl_opt {
+            Some(ttl) if ttl > 0 => ttl.min(MAX_NEGATIVE_TTL_SECS),
+            _ => return,
+        };
+        if self.entries.len() >= MAX_ENTRIES {
+            let now = Instant::now();
+            self.entries.retain(|_, (_, expiration)| *expiration > now);
+            if self.entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        self.entries.insert(
+            key,
+            (rcode, Instant::now() + Duration::from_secs(u64::from(ttl))),
+        );
+    }
+
+    fn key_for(queries: &[Query]) -> Option<QuestionKey> {
+        match queries {
+            [query] => Some((
+                query.get_query_name().to_lowercase(),
+                query.get_query_type(),
+                query.get_query_class(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trust_dns::rr::DNSClass;
+
+    fn make_response(
+        buf: &mut [u8],
+        rcode: ResponseCode,
+        answer: bool,
+        soa_ttl_opt: Option<(u32, u32)>,
+    ) -> usize {
+        let mut facade = PacketFacade::new(buf, 12);
+        facade.set_query(false);
+        facade.set_rcode(rcode.low());
+        facade.add_query(
+            "Nowhere.example.com",
+            RecordType::MX.into(),
+            DNSClass::IN.into(),
+        );
+        if answer {
+            facade.add_answer(
+                "nowhere.example.com",
+                RecordType::MX.into(),
+                DNSClass::IN.into(),
+                300,
+                &[0x00, 0x0A, 0xC0, 0x0C],
+            );
+        }
+        if let Some((ttl, minimum)) = soa_ttl_opt {
+            let mut rdata = vec![0xC0, 0x0C, 0xC0, 0x0C];
+            rdata.extend_from_slice(&[0; 16]);
+            rdata.extend_from_slice(&minimum.to_be_bytes());
+            facade.add_authority(
+                "example.com",
+                RecordType::SOA.into(),
+                DNSClass::IN.into(),
+                ttl,
+                &rdata,
+            );
+        }
+        facade.get_length()
+    }
+
+    fn queries_for(name: &str, record_type: RecordType) -> Vec<Query> {
+        vec![Query::new_for_test(
+            name.to_string(),
+            record_type.into(),
+            DNSClass::IN.into(),
+            0,
+        )]
+    }
+
+    #[test]
+    fn nxdomain_and_nodata_answers_with_soa_are_cached() {
+        let mut subject = NegativeCache::new();
+        let mut nxdomain_buf: [u8; 200] = [0; 200];
+        let length = make_response(
+            &mut nxdomain_buf,
+            ResponseCode::NXDomain,
+            false,
+            Some((900, 300)),
+        );
+
+        subject.record(&PacketFacade::new(&mut nxdomain_buf, length));
+
+        assert_eq!(
+            subject.lookup(&queries_for("nowhere.EXAMPLE.com", RecordType::MX)),
+            Some(ResponseCode::NXDomain.low())
+        );
+        assert_eq!(
+            subject.lookup(&queries_for("nowhere.example.com", RecordType::TXT)),
+            None
+        );
+        let mut nodata_buf: [u8; 200] = [0; 200];
+        let length = make_response(
+            &mut nodata_buf,
+            ResponseCode::NoError,
+            false,
+            Some((60, 300)),
+        );
+
+        subject.record(&PacketFacade::new(&mut nodata_buf, length));
+
+        assert_eq!(
+            subject.lookup(&queries_for("nowhere.example.com", RecordType::MX)),
+            Some(ResponseCode::NoError.low())
+        );
+    }
+
+    #[test]
+    fn negative_answers_without_soa_or_with_zero_ttl_are_not_cached() {
+        let mut subject = NegativeCache::new();
+        let mut buf: [u8; 200] = [0; 200];
+        let length = make_response(&mut buf, ResponseCode::NXDomain, false, None);
+        subject.record(&PacketFacade::new(&mut buf, length));
+        let mut buf: [u8; 200] = [0; 200];
+        let length = make_response(&mut buf, ResponseCode::NXDomain, false, Some((0, 300)));
+        subject.record(&PacketFacade::new(&mut buf, length));
+
+        let result = subject.lookup(&queries_for("nowhere.example.com", RecordType::MX));
+
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn positive_answer_replaces_cached_negative_answer() {
+        let mut subject = NegativeCache::new();
+        let mut buf: [u8; 200] = [0; 200];
+        let length = make_response(&mut buf, ResponseCode::NXDomain, false, Some((900, 300)));
+        subject.record(&PacketFacade::new(&mut buf, length));
+        let mut buf: [u8; 200] = [0; 200];
+        let length = make_response(&mut buf, ResponseCode::NoError, true, None);
+
+        subject.record(&PacketFacade::new(&mut buf, length));
+
+        assert_eq!(
+            subject.lookup(&queries_for("nowhere.example.com", RecordType::MX)),
+            None
+        );
+    }
+
+    #[test]
+    fn expired_answers_are_forgotten() {
+        let mut subject = NegativeCache::new();
+        subject.entries.insert(
+            (
+                "nowhere.example.com".to_string(),
+                RecordType::MX.into(),
+                DNSClass::IN.into(),
+            ),
+            (ResponseCode::NXDomain.low(), Instant::now()),
+        );
+
+        let result = subject.lookup(&queries_for("nowhere.example.com", RecordType::MX));
+
+        assert_eq!(result, None);
+        assert!(subject.entries.is_empty());
+    }
+}
@@ -3,6 +3,8 @@ use std::cmp::max;
 use std::ops::Add;
 use std::str::from_utf8;
 
+const COMPRESSION_FLAGS: usize = 0xC0;
+
 macro_rules! try_opt {
     ($e:expr) => {
         match $e {
@@ -570,12 +572,23 @@ impl<'a> PacketFacade<'a> {
             if length == 0x00 {
                 return Some(local_offset + 1);
             }
+            if length & COMPRESSION_FLAGS == COMPRESSION_FLAGS {
+                // A compression pointer ends the sequence where it stands.
+                return if local_offset + 2 > buflen {
+                    None
+                } else {
+                    Some(local_offset + 2)
+                };
+            }
             local_offset += length + 1;
         }
     }
 
+    // Answers from real DNS servers use compression: a sequence may end with a two-byte pointer
+    // to the rest of the name somewhere earlier in the packet.
     fn extract_string_seq(buf: &[u8], offset: usize, buflen: usize) -> Option<(String, usize)> {
         let mut local_offset = offset;
+        let mut sequence_end_opt: Option<usize> = None;
         let mut result = String::from("");
         loop {
             if local_offset >= buflen {
@@ -583,7 +596,18 @@ impl<'a> PacketFacade<'a> {
             }
             let length = buf[local_offset] as usize;
             if length == 0x00 {
-                return Some((result, local_offset + 1));
+                return Some((result, sequence_end_opt.unwrap_or(local_offset + 1)));
+            }
+            if length & COMPRESSION_FLAGS == COMPRESSION_FLAGS {
+                let pointer = try_opt!(PacketFacade::u16_from(buf, local_offset, buflen));
+                let target = (pointer & 0x3FFF) as usize;
+                // Pointing only backward guarantees that the name can't loop forever.
+                if target >= local_offset {
+                    return None;
+                }
+                sequence_end_opt = sequence_end_opt.or(Some(local_offset + 2));
+                local_offset = target;
+                continue;
             }
             if !result.is_empty() {
                 result = result.add(".");
@@ -1003,6 +1027,66 @@ mod tests {
         assert_eq!(subject.get_length(), length);
     }
 
+    #[test]
+    fn can_access_answers_with_compressed_names() {
+        let mut buf: [u8; 500] = [0; 500];
+        let length = {
+            let mut adder = ByteHandle::new(&mut buf, 4);
+            adder.add_bytes(&[0x00, 0x01]); // 1 query
+            adder.add_bytes(&[0x00, 0x02]); // 2 answers
+            adder.skip_bytes(4);
+
+            adder.add_bytes(&[0x03, 0x77, 0x77, 0x77]); // www (offset 12)
+            adder.add_bytes(&[0x06, 0x64, 0x6F, 0x6D, 0x61, 0x69, 0x6E]); // domain (offset 16)
+            adder.add_bytes(&[0x03, 0x63, 0x6F, 0x6D, 0x00]); // com [end]
+            adder.add_bytes(&[0x00, 0x0F, 0x00, 0x01]); // type MX, class IN
+
+            adder.add_bytes(&[0xC0, 0x0C]); // pointer to www.domain.com
+            adder.add_bytes(&[0x00, 0x0F, 0x00, 0x01]); // type MX, class IN
+            adder.add_bytes(&[0x00, 0x00, 0x0E, 0x10]); // time to live
+            adder.add_bytes(&[0x00, 0x02]); // rdata length
+            adder.add_bytes(&[0xC0, 0x10]); // rdata: pointer to domain.com
+
+            adder.add_bytes(&[0x04, 0x6D, 0x61, 0x69, 0x6C]); // mail
+            adder.add_bytes(&[0xC0, 0x10]); // pointer to domain.com
+            adder.add_bytes(&[0x00, 0x01, 0x00, 0x01]); // type A, class IN
+            adder.add_bytes(&[0x00, 0x00, 0x0E, 0x10]); // time to live
+            adder.add_bytes(&[0x00, 0x04]); // rdata length
+            adder.add_bytes(&[0x01, 0x02, 0x03, 0x04]); // rdata
+
+            adder.get_offset()
+        };
+
+        let subject = PacketFacade::new(&mut buf, length);
+
+        let records = subject.get_answers().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].get_name(), "www.domain.com");
+        assert_eq!(records[0].get_rdata(), u8vec(&[0xC0, 0x10]));
+        assert_eq!(records[1].get_name(), "mail.domain.com");
+        assert_eq!(records[1].get_rdata(), u8vec(&[0x01, 0x02, 0x03, 0x04]));
+        assert_eq!(subject.find_answers_end(), Some(length));
+    }
+
+    #[test]
+    fn rejects_compression_pointer_that_does_not_point_backward() {
+        let mut buf: [u8; 500] = [0; 500];
+        let length = {
+            let mut adder = ByteHandle::new(&mut buf, 4);
+            adder.add_bytes(&[0x00, 0x01]); // 1 query
+            adder.skip_bytes(6);
+
+            adder.add_bytes(&[0xC0, 0x0C]); // pointer to itself
+            adder.add_bytes(&[0x00, 0x01, 0x00, 0x01]); // type A, class IN
+
+            adder.get_offset()
+        };
+
+        let subject = PacketFacade::new(&mut buf, length);
+
+        assert_eq!(subject.get_queries().is_none(), true);
+    }
+
     #[test]
     fn can_access_authorities() {
         let mut buf: [u8; 500] = [0; 500];
@@ -1,4 +1,5 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use super::dns_bypass::DnsBypassList;
 use super::packet_facade::PacketFacade;
 use super::packet_facade::Query;
 use super::packet_facade::ResourceRecord;
@@ -36,6 +37,48 @@ pub fn process(buf: &mut [u8], length: usize, addr: &SocketAddr, logger: &Logger
     response_size
 }
 
+// A request goes to the upstream DNS servers only if it's a well-formed query and every question
+// in it either names a domain on the bypass list or asks for something other than an address;
+// anything else is answered here.
+pub fn should_forward(buf: &mut [u8], length: usize, bypass_list: &DnsBypassList) -> bool {
+    let facade = PacketFacade::new(buf, length);
+    let forwardable = |query: &Query| {
+        bypass_list.matches(&query.get_query_name())
+            || (query.get_query_class() == u16::from(DNSClass::IN)
+                && !matches!(
+                    RecordType::from(query.get_query_type()),
+                    RecordType::A | RecordType::AAAA
+                ))
+    };
+    match (facade.get_opcode(), facade.get_queries()) {
+        (Some(opcode), Some(queries)) if opcode == u8::from(OpCode::Query) => {
+            !queries.is_empty() && queries.iter().all(forwardable)
+        }
+        _ => false,
+    }
+}
+
+// Turns a request into a response that carries only its questions and the response code.
+pub fn make_negative_response(facade: &mut PacketFacade, rcode: u8) -> usize {
+    let queries = match facade.get_queries() {
+        Some(queries) => queries,
+        None => return make_format_error(facade),
+    };
+    make_error(facade, rcode);
+    queries.iter().for_each(|query| {
+        facade.add_query(
+            query.get_query_name(),
+            query.get_query_type(),
+            query.get_query_class(),
+        );
+    });
+    facade.get_length()
+}
+
+pub fn make_server_failure(facade: &mut PacketFacade<'_>) -> usize {
+    make_error(facade, ResponseCode::ServFail.low())
+}
+
 fn make_response(mut facade: &mut PacketFacade) -> usize {
     match facade.get_opcode() {
         None => return make_format_error(facade),
@@ -355,6 +398,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_forward_only_queries_that_are_all_bypassed_or_not_for_addresses() {
+        let bypass_list = DnsBypassList::new("lan,mybank.com");
+        let check_typed = |opcode: OpCode, names: &[(&str, RecordType)], list: &DnsBypassList| {
+            let mut buf: [u8; 500] = [0; 500];
+            let length = {
+                let mut facade = PacketFacade::new(&mut buf, 500);
+                facade.set_transaction_id(0x1234);
+                facade.set_query(true);
+                facade.set_opcode(opcode.into());
+                names.iter().for_each(|(name, record_type)| {
+                    facade.add_query(name, (*record_type).into(), DNSClass::IN.into());
+                });
+                facade.get_length()
+            };
+            should_forward(&mut buf, length, list)
+        };
+        let check = |opcode: OpCode, names: &[&str], list: &DnsBypassList| {
+            let typed_names: Vec<(&str, RecordType)> =
+                names.iter().map(|name| (*name, RecordType::A)).collect();
+            check_typed(opcode, &typed_names, list)
+        };
+
+        assert!(check(OpCode::Query, &["printer.lan"], &bypass_list));
+        assert!(check(
+            OpCode::Query,
+            &["printer.lan", "www.mybank.com"],
+            &bypass_list
+        ));
+        assert!(!check(
+            OpCode::Query,
+            &["printer.lan", "www.example.com"],
+            &bypass_list
+        ));
+        assert!(!check(OpCode::Query, &[], &bypass_list));
+        assert!(!check(OpCode::Status, &["printer.lan"], &bypass_list));
+        assert!(!check(
+            OpCode::Query,
+            &["printer.lan"],
+            &DnsBypassList::default()
+        ));
+        assert!(check_typed(
+            OpCode::Query,
+            &[("example.com", RecordType::MX)],
+            &DnsBypassList::default()
+        ));
+        assert!(check_typed(
+            OpCode::Query,
+            &[
+                ("printer.lan", RecordType::A),
+                ("example.com", RecordType::TXT)
+            ],
+            &bypass_list
+        ));
+        assert!(!check_typed(
+            OpCode::Query,
+            &[
+                ("example.com", RecordType::AAAA),
+                ("example.com", RecordType::TXT)
+            ],
+            &bypass_list
+        ));
+    }
+
+    #[test]
+    fn make_negative_response_keeps_only_the_questions() {
+        let mut buf: [u8; 500] = [0; 500];
+        let req_length = {
+            let mut request = PacketFacade::new(&mut buf, 12);
+            request.set_transaction_id(0x4321);
+            request.set_query(true);
+            request.set_recursion_desired(true);
+            request.set_opcode(OpCode::Query.into());
+            request.add_query(
+                "nowhere.example.com",
+                RecordType::MX.into(),
+                DNSClass::IN.into(),
+            );
+            request.add_additional("", RecordType::OPT.into(), 4096, 0, &[]);
+            request.get_length()
+        };
+
+        let rsp_length = {
+            let mut facade = PacketFacade::new(&mut buf, req_length);
+            make_negative_response(&mut facade, ResponseCode::NXDomain.low())
+        };
+
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.get_transaction_id(), Some(0x4321));
+        assert_eq!(response.is_query(), Some(false));
+        assert_eq!(response.is_recursion_desired(), Some(true));
+        assert_eq!(response.is_recursion_available(), Some(true));
+        assert_eq!(response.get_rcode(), Some(ResponseCode::NXDomain.low()));
+        let queries = response.get_queries().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].get_query_name(), "nowhere.example.com");
+        assert_eq!(queries[0].get_query_type(), u16::from(RecordType::MX));
+        assert_eq!(response.get_answers().unwrap().len(), 0);
+        assert_eq!(response.get_additionals().unwrap().len(), 0);
+    }
+
     #[test]
     fn write_log_produces_correct_text() {
         init_test_logging();
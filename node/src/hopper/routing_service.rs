@@ -10,7 +10,7 @@ use crate::sub_lib::hopper::{ExpiredCoresPackage, HopperSubs, MessageType};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::neighborhood::{GossipFailure_0v1, NeighborhoodSubs};
 use crate::sub_lib::proxy_client::{
-    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ProxyClientSubs,
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1, ProxyClientSubs,
 };
 use crate::sub_lib::proxy_server::{ClientRequestPayload_0v1, ProxyServerSubs};
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
@@ -357,6 +357,26 @@ impl RoutingService {
                     ))
                     .expect("ProxyServer is dead")
             }
+            (Component::ProxyServer, MessageType::ExitRefused(vd)) => {
+                let refusal = match ExitRefusal_0v1::try_from(vd) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        error!(self.logger, "Received unmigratable ExitRefused: {:?}", e);
+                        return;
+                    }
+                };
+                self.routing_service_subs
+                    .proxy_server_subs
+                    .exit_refusal_from_hopper
+                    .try_send(ExpiredCoresPackage::new(
+                        expired_package.immediate_neighbor,
+                        expired_package.paying_wallet,
+                        expired_package.remaining_route,
+                        refusal,
+                        expired_package.payload_len,
+                    ))
+                    .expect("ProxyServer is dead")
+            }
             (Component::Neighborhood, MessageType::Gossip(vd)) => {
                 let gossip = match Gossip_0v1::try_from(vd) {
                     Ok(g) => g,
@@ -594,6 +614,64 @@ mod tests {
         assert_eq!(dns_resolve_failure, message.payload);
     }
 
+    #[test]
+    fn exit_refusals_are_reported_to_the_proxy_server() {
+        let main_cryptde = main_cryptde();
+        let alias_cryptde = alias_cryptde();
+        let route = route_to_proxy_server(&main_cryptde.public_key(), main_cryptde);
+        let stream_key = make_meaningless_stream_key();
+        let exit_refusal = ExitRefusal_0v1::new(stream_key);
+        let lcp = LiveCoresPackage::new(
+            route,
+            encodex(
+                alias_cryptde,
+                &alias_cryptde.public_key(),
+                &MessageType::ExitRefused(VersionedData::new(
+                    &crate::sub_lib::migrations::exit_refusal::MIGRATIONS,
+                    &exit_refusal.clone(),
+                )),
+            )
+            .unwrap(),
+        );
+        let data_enc = encodex(main_cryptde, &main_cryptde.public_key(), &lcp).unwrap();
+        let inbound_client_data = InboundClientData {
+            peer_addr: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            reception_port: None,
+            sequence_number: None,
+            last_data: false,
+            is_clandestine: false,
+            data: data_enc.into(),
+        };
+        let (proxy_server, _, proxy_server_recording) = make_recorder();
+
+        let system = System::new("exit_refusals_are_reported_to_the_proxy_server");
+        let peer_actors = peer_actors_builder().proxy_server(proxy_server).build();
+        let subject = RoutingService::new(
+            main_cryptde,
+            alias_cryptde,
+            RoutingServiceSubs {
+                proxy_client_subs: peer_actors.proxy_client,
+                proxy_server_subs: peer_actors.proxy_server,
+                neighborhood_subs: peer_actors.neighborhood,
+                hopper_subs: peer_actors.hopper,
+                to_dispatcher: peer_actors.dispatcher.from_dispatcher_client,
+                to_accountant_routing: peer_actors.accountant.report_routing_service_provided,
+            },
+            100,
+            200,
+            false,
+        );
+
+        subject.route(inbound_client_data);
+
+        System::current().stop();
+        system.run();
+
+        let recordings = proxy_server_recording.lock().unwrap();
+        let message = recordings.get_record::<ExpiredCoresPackage<ExitRefusal_0v1>>(0);
+        assert_eq!(exit_refusal, message.payload);
+    }
+
     #[test]
     fn logs_and_ignores_message_that_cannot_be_deserialized() {
         init_test_logging();
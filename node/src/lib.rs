@@ -31,6 +31,7 @@ pub mod json_discriminator_factory;
 pub mod json_framer;
 pub mod json_masquerader;
 mod listener_handler;
+mod malefactor_dao;
 pub mod masquerader;
 pub mod neighborhood;
 pub mod node_configurator;
@@ -40,6 +41,7 @@ pub mod proxy_client;
 pub mod proxy_server;
 pub mod run_modes;
 pub mod server_initializer;
+pub mod socks_discriminator_factory;
 pub mod stream_handler_pool;
 mod stream_messages;
 mod stream_reader;
@@ -138,16 +138,15 @@ mod tests {
     use crate::node_test_utils::NullDiscriminatorFactory;
     use crate::test_utils::little_tcp_server::LittleTcpServer;
     use crate::test_utils::logging::init_test_logging;
-    use crate::test_utils::logging::TestLog;
     use crate::test_utils::logging::TestLogHandler;
     use crate::test_utils::recorder::make_recorder;
     use crate::test_utils::recorder::Recorder;
     use crate::test_utils::stream_connector_mock::StreamConnectorMock;
+    use crate::test_utils::tokio_wrapper_mocks::TokioListenerWrapperMock;
     use actix::Actor;
     use actix::Addr;
     use actix::System;
     use masq_lib::utils::{find_free_port, localhost};
-    use std::cell::RefCell;
     use std::io::Error;
     use std::io::ErrorKind;
     use std::net;
@@ -162,50 +161,6 @@ mod tests {
     use tokio::net::TcpStream;
     use tokio::reactor::Handle;
 
-    struct TokioListenerWrapperMock {
-        log: Arc<TestLog>,
-        bind_results: Vec<io::Result<()>>,
-        poll_accept_results: RefCell<Vec<io::Result<Async<(TcpStream, SocketAddr)>>>>,
-    }
-
-    impl TokioListenerWrapperMock {
-        fn new() -> TokioListenerWrapperMock {
-            TokioListenerWrapperMock {
-                log: Arc::new(TestLog::new()),
-                bind_results: vec![],
-                poll_accept_results: RefCell::new(vec![]),
-            }
-        }
-    }
-
-    impl TokioListenerWrapper for TokioListenerWrapperMock {
-        fn bind(&mut self, addr: SocketAddr) -> io::Result<()> {
-            self.log.log(format!("bind ({:?})", addr));
-            self.bind_results.remove(0)
-        }
-
-        fn poll_accept(&mut self) -> io::Result<Async<(TcpStream, SocketAddr)>> {
-            self.poll_accept_results.borrow_mut().remove(0)
-        }
-    }
-
-    impl TokioListenerWrapperMock {
-        pub fn bind_result(mut self, result: io::Result<()>) -> TokioListenerWrapperMock {
-            self.bind_results.push(result);
-            self
-        }
-
-        pub fn poll_accept_results(
-            self,
-            result_vec: Vec<Result<Async<(TcpStream, SocketAddr)>, io::Error>>,
-        ) -> TokioListenerWrapperMock {
-            result_vec
-                .into_iter()
-                .for_each(|result| self.poll_accept_results.borrow_mut().push(result));
-            self
-        }
-    }
-
     #[test]
     #[should_panic(expected = "TcpListener not initialized - bind to a SocketAddr")]
     fn panics_if_tried_to_run_without_initializing() {
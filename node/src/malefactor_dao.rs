@@ -0,0 +1,335 @@
+// Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::database::connection_wrapper::ConnectionWrapper;
+use crate::database::dao_utils::{from_time_t, now_time_t, to_time_t, DaoFactoryReal};
+use crate::sub_lib::cryptde::PublicKey;
+use lazy_static::lazy_static;
+use rusqlite::{Row, ToSql};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+lazy_static! {
+    pub static ref MALEFACTOR_CACHE: MalefactorCache = MalefactorCache::default();
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Malefactor {
+    pub public_key_opt: Option<PublicKey>,
+    pub ip_address_opt: Option<IpAddr>,
+    pub reason: String,
+    pub banned_at: SystemTime,
+    pub expires_at: SystemTime,
+}
+
+impl Malefactor {
+    pub fn new(
+        public_key_opt: Option<PublicKey>,
+        ip_address_opt: Option<IpAddr>,
+        reason: String,
+        ban_duration: Duration,
+    ) -> Self {
+        // whole seconds, so that what comes back out of the database is what went in
+        let banned_at = from_time_t(now_time_t());
+        Self {
+            public_key_opt,
+            ip_address_opt,
+            reason,
+            banned_at,
+            expires_at: banned_at + ban_duration,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MalefactorCache {
+    keys: RwLock<HashMap<PublicKey, SystemTime>>,
+    ip_addresses: RwLock<HashMap<IpAddr, SystemTime>>,
+}
+
+impl MalefactorCache {
+    pub fn insert(&self, malefactor: &Malefactor) {
+        if let Some(public_key) = &malefactor.public_key_opt {
+            Self::extend(&self.keys, public_key, malefactor.expires_at);
+        }
+        if let Some(ip_address) = &malefactor.ip_address_opt {
+            Self::extend(&self.ip_addresses, ip_address, malefactor.expires_at);
+        }
+    }
+
+    pub fn is_banned_key(&self, public_key: &PublicKey) -> bool {
+        Self::is_banned(&self.keys, public_key)
+    }
+
+    pub fn is_banned_ip(&self, ip_address: &IpAddr) -> bool {
+        Self::is_banned(&self.ip_addresses, ip_address)
+    }
+
+    fn extend<K: Hash + Eq + Clone>(
+        map: &RwLock<HashMap<K, SystemTime>>,
+        key: &K,
+        expires_at: SystemTime,
+    ) {
+        let mut map = map.write().expect("Failed to insert ban into cache");
+        let expiration = map.entry(key.clone()).or_insert(expires_at);
+        if *expiration < expires_at {
+            *expiration = expires_at;
+        }
+    }
+
+    fn is_banned<K: Hash + Eq>(map: &RwLock<HashMap<K, SystemTime>>, key: &K) -> bool {
+        match map
+            .read()
+            .expect("Failed to read from malefactor cache")
+            .get(key)
+        {
+            Some(expires_at) => *expires_at > SystemTime::now(),
+            None => false,
+        }
+    }
+}
+
+pub trait MalefactorDao: Send {
+    fn active_bans(&self) -> Vec<Malefactor>;
+    fn ban(&self, malefactor: &Malefactor);
+}
+
+pub trait MalefactorDaoFactory {
+    fn make(&self) -> Box<dyn MalefactorDao>;
+}
+
+impl MalefactorDaoFactory for DaoFactoryReal {
+    fn make(&self) -> Box<dyn MalefactorDao> {
+        Box::new(MalefactorDaoReal::new(self.make_connection()))
+    }
+}
+
+pub struct MalefactorDaoReal {
+    conn: Box<dyn ConnectionWrapper>,
+}
+
+impl MalefactorDaoReal {
+    pub fn new(conn: Box<dyn ConnectionWrapper>) -> Self {
+        Self { conn }
+    }
+
+    fn row_to_malefactor(row: &Row) -> rusqlite::Result<Malefactor> {
+        let public_key_opt: Option<Vec<u8>> = row.get(0)?;
+        let ip_address_opt: Option<String> = row.get(1)?;
+        let banned_at: i64 = row.get(3)?;
+        let expires_at: i64 = row.get(4)?;
+        Ok(Malefactor {
+            public_key_opt: public_key_opt.map(|bytes| PublicKey::new(&bytes)),
+            ip_address_opt: ip_address_opt.map(|ip_address| {
+                IpAddr::from_str(&ip_address).unwrap_or_else(|_| {
+                    panic!(
+                        "Database is corrupt: malefactor IP address '{}' is invalid",
+                        ip_address
+                    )
+                })
+            }),
+            reason: row.get(2)?,
+            banned_at: from_time_t(banned_at),
+            expires_at: from_time_t(expires_at),
+        })
+    }
+}
+
+impl MalefactorDao for MalefactorDaoReal {
+    fn active_bans(&self) -> Vec<Malefactor> {
+        let mut stmt = self
+            .conn
+            .prepare("select public_key, ip_address, reason, banned_at, expires_at from malefactor where expires_at > ?")
+            .expect("Failed to prepare a statement");
+        let params: &[&dyn ToSql] = &[&now_time_t()];
+        stmt.query_map(params, Self::row_to_malefactor)
+            .expect("Couldn't retrieve malefactor list: database corrupt")
+            .flatten()
+            .collect()
+    }
+
+    fn ban(&self, malefactor: &Malefactor) {
+        let params: &[&dyn ToSql] = &[&now_time_t()];
+        if let Err(e) = self
+            .conn
+            .prepare("delete from malefactor where expires_at <= ?")
+            .and_then(|mut stmt| stmt.execute(params))
+        {
+            panic!(
+                "Could not clear expired malefactor bans because of database corruption: {}",
+                e
+            )
+        }
+        let mut stmt = self
+            .conn
+            .prepare("insert into malefactor (public_key, ip_address, reason, banned_at, expires_at) values (?, ?, ?, ?, ?)")
+            .expect("Failed to prepare a statement");
+        let params: &[&dyn ToSql] = &[
+            &malefactor
+                .public_key_opt
+                .as_ref()
+                .map(|public_key| public_key.as_slice().to_vec()),
+            &malefactor
+                .ip_address_opt
+                .map(|ip_address| ip_address.to_string()),
+            &malefactor.reason,
+            &to_time_t(malefactor.banned_at),
+            &to_time_t(malefactor.expires_at),
+        ];
+        match stmt.execute(params) {
+            Ok(_) => MALEFACTOR_CACHE.insert(malefactor),
+            Err(e) => panic!(
+                "Could not ban malefactor {:?} because of database corruption: {}",
+                malefactor, e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
+    use masq_lib::test_utils::utils::{
+        ensure_node_home_directory_does_not_exist, ensure_node_home_directory_exists,
+        DEFAULT_CHAIN_ID,
+    };
+    use rusqlite::NO_PARAMS;
+
+    fn make_subject(test_name: &str) -> MalefactorDaoReal {
+        let home_dir = ensure_node_home_directory_does_not_exist("malefactor_dao", test_name);
+        let conn = DbInitializerReal::new()
+            .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+        MalefactorDaoReal::new(conn)
+    }
+
+    #[test]
+    fn ban_records_malefactor_in_database_and_cache() {
+        let subject = make_subject("ban_records_malefactor_in_database_and_cache");
+        let public_key = PublicKey::new(b"ban_records_malefactor");
+        let ip_address = IpAddr::from_str("10.11.12.1").unwrap();
+        let malefactor = Malefactor::new(
+            Some(public_key.clone()),
+            Some(ip_address),
+            "Forged Gossip".to_string(),
+            Duration::from_secs(3600),
+        );
+
+        subject.ban(&malefactor);
+
+        assert_eq!(subject.active_bans(), vec![malefactor.clone()]);
+        assert!(MALEFACTOR_CACHE.is_banned_key(&public_key));
+        assert!(MALEFACTOR_CACHE.is_banned_ip(&ip_address));
+        assert_eq!(
+            malefactor.expires_at,
+            malefactor.banned_at + Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn ban_can_record_malefactor_known_only_by_ip_address() {
+        let subject = make_subject("ban_can_record_malefactor_known_only_by_ip_address");
+        let ip_address = IpAddr::from_str("10.11.12.2").unwrap();
+        let malefactor = Malefactor::new(
+            None,
+            Some(ip_address),
+            "Non-deserializable Gossip".to_string(),
+            Duration::from_secs(3600),
+        );
+
+        subject.ban(&malefactor);
+
+        assert_eq!(subject.active_bans(), vec![malefactor]);
+        assert!(MALEFACTOR_CACHE.is_banned_ip(&ip_address));
+    }
+
+    #[test]
+    fn ban_purges_expired_bans_and_active_bans_ignores_them() {
+        let subject = make_subject("ban_purges_expired_bans_and_active_bans_ignores_them");
+        subject
+            .conn
+            .prepare("insert into malefactor (public_key, ip_address, reason, banned_at, expires_at) values (null, '10.11.12.3', 'Old news', 1000, 2000)")
+            .unwrap()
+            .execute(NO_PARAMS)
+            .unwrap();
+        assert_eq!(subject.active_bans(), vec![]);
+        let malefactor = Malefactor::new(
+            None,
+            Some(IpAddr::from_str("10.11.12.4").unwrap()),
+            "Fresh news".to_string(),
+            Duration::from_secs(3600),
+        );
+
+        subject.ban(&malefactor);
+
+        let count: i64 = subject
+            .conn
+            .prepare("select count(*) from malefactor")
+            .unwrap()
+            .query_row(NO_PARAMS, |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert!(!MALEFACTOR_CACHE.is_banned_ip(&IpAddr::from_str("10.11.12.3").unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to prepare a statement")]
+    fn active_bans_panics_when_table_doesnt_exist() {
+        let home_dir = ensure_node_home_directory_exists(
+            "malefactor_dao",
+            "active_bans_panics_when_table_doesnt_exist",
+        );
+        let conn = DbInitializerReal::new()
+            .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+            .unwrap();
+        conn.prepare("drop table malefactor")
+            .unwrap()
+            .execute(NO_PARAMS)
+            .unwrap();
+        let subject = MalefactorDaoReal::new(conn);
+
+        subject.active_bans();
+    }
+
+    #[test]
+    fn cache_keeps_the_later_expiration_for_a_repeat_offender() {
+        let public_key = PublicKey::new(b"repeat_offender");
+        let long_ban = Malefactor::new(
+            Some(public_key.clone()),
+            None,
+            "First offense".to_string(),
+            Duration::from_secs(3600),
+        );
+        let mut expired_ban = long_ban.clone();
+        expired_ban.expires_at = expired_ban.banned_at - Duration::from_secs(1);
+        let subject = MalefactorCache::default();
+
+        subject.insert(&long_ban);
+        subject.insert(&expired_ban);
+
+        assert!(subject.is_banned_key(&public_key));
+    }
+
+    #[test]
+    fn cache_does_not_ban_after_expiration() {
+        let public_key = PublicKey::new(b"expired_offender");
+        let ip_address = IpAddr::from_str("10.11.12.5").unwrap();
+        let mut malefactor = Malefactor::new(
+            Some(public_key.clone()),
+            Some(ip_address),
+            "Ancient history".to_string(),
+            Duration::from_secs(0),
+        );
+        malefactor.expires_at = malefactor.banned_at - Duration::from_secs(1);
+        let subject = MalefactorCache::default();
+
+        subject.insert(&malefactor);
+
+        assert!(!subject.is_banned_key(&public_key));
+        assert!(!subject.is_banned_ip(&ip_address));
+        assert!(!subject.is_banned_key(&PublicKey::new(b"never_banned")));
+    }
+}
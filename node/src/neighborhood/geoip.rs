@@ -0,0 +1,241 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::sub_lib::exit_policy::CountryFinder;
+use flate2::read::GzDecoder;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::io::Read;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+// DB-IP's "IP to Country Lite" database (CC BY 4.0, https://db-ip.com), gzipped, if the build found
+// one in node/geoip; otherwise empty. See node/geoip/README.md.
+const BUNDLED_DATABASE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/bundled-geoip.csv.gz"));
+
+// An offline IP-to-country table, read from a CSV file with one range per line in the form
+// first_ip,last_ip,country_code: the layout of the freely-available "IP to Country Lite" databases.
+// Lines starting with '#' are ignored, and files whose names end in .gz are decompressed first.
+pub struct GeoIpDatabase {
+    ranges: Vec<(IpAddr, IpAddr, String)>,
+}
+
+impl Debug for GeoIpDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "GeoIpDatabase {{ {} ranges }}", self.ranges.len())
+    }
+}
+
+impl CountryFinder for GeoIpDatabase {
+    fn country_of(&self, ip_addr: &IpAddr) -> Option<String> {
+        let candidate_count = self
+            .ranges
+            .partition_point(|(first_ip, _, _)| first_ip <= ip_addr);
+        if candidate_count == 0 {
+            return None;
+        }
+        let (_, last_ip, country) = &self.ranges[candidate_count - 1];
+        if ip_addr <= last_ip {
+            Some(country.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl GeoIpDatabase {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("Couldn't read GeoIP database {:?}: {}", path, e))?;
+        let text = if path.extension().map(|ext| ext == "gz").unwrap_or(false) {
+            Self::gunzip(&bytes)
+        } else {
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("Couldn't read GeoIP database {:?}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("GeoIP database {:?} is corrupt: {}", path, e))
+    }
+
+    pub fn is_bundled() -> bool {
+        !BUNDLED_DATABASE.is_empty()
+    }
+
+    // None if this Node was built without a GeoIP database.
+    pub fn bundled() -> Result<Option<Self>, String> {
+        Self::from_gzipped(BUNDLED_DATABASE)
+            .map_err(|e| format!("Bundled GeoIP database is corrupt: {}", e))
+    }
+
+    fn from_gzipped(bytes: &[u8]) -> Result<Option<Self>, String> {
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        Self::parse(&Self::gunzip(bytes)?).map(Some)
+    }
+
+    fn gunzip(bytes: &[u8]) -> Result<String, String> {
+        let mut text = String::new();
+        GzDecoder::new(bytes)
+            .read_to_string(&mut text)
+            .map_err(|e| e.to_string())?;
+        Ok(text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut ranges = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_number, line)| {
+                Self::parse_line(line).map_err(|e| format!("line {}: {}", line_number, e))
+            })
+            .collect::<Result<Vec<(IpAddr, IpAddr, String)>, String>>()?;
+        ranges.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(GeoIpDatabase { ranges })
+    }
+
+    fn parse_line(line: &str) -> Result<(IpAddr, IpAddr, String), String> {
+        let fields = line
+            .split(',')
+            .map(|field| field.trim().trim_matches('"'))
+            .collect::<Vec<&str>>();
+        if fields.len() != 3 {
+            return Err(format!("expected 3 fields, found {}", fields.len()));
+        }
+        let parse_ip = |field: &str| {
+            IpAddr::from_str(field).map_err(|_| format!("'{}' is not an IP address", field))
+        };
+        let first_ip = parse_ip(fields[0])?;
+        let last_ip = parse_ip(fields[1])?;
+        if first_ip.is_ipv4() != last_ip.is_ipv4() || last_ip < first_ip {
+            return Err(format!("{} to {} is not a range", first_ip, last_ip));
+        }
+        Ok((first_ip, last_ip, fields[2].to_uppercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+    use std::io::Write;
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn country_of_finds_the_range_containing_the_address() {
+        let subject = GeoIpDatabase::parse(
+            "# first,last,country\n\
+             5.0.0.0,5.255.255.255,fr\n\
+             \"1.0.0.0\",\"1.0.0.255\",\"AU\"\n\
+             2001:db8::,2001:db8:ffff:ffff:ffff:ffff:ffff:ffff,DE\n",
+        )
+        .unwrap();
+
+        assert_eq!(subject.country_of(&ip("1.0.0.1")), Some("AU".to_string()));
+        assert_eq!(subject.country_of(&ip("5.6.7.8")), Some("FR".to_string()));
+        assert_eq!(
+            subject.country_of(&ip("2001:db8::1")),
+            Some("DE".to_string())
+        );
+        assert_eq!(subject.country_of(&ip("0.255.255.255")), None);
+        assert_eq!(subject.country_of(&ip("1.0.1.0")), None);
+        assert_eq!(subject.country_of(&ip("6.0.0.0")), None);
+    }
+
+    #[test]
+    fn parse_complains_about_bad_lines() {
+        let result = GeoIpDatabase::parse("1.0.0.0,1.0.0.255,AU\n1.0.1.0,booga,CN\n");
+
+        assert_eq!(
+            result.err(),
+            Some("line 2: 'booga' is not an IP address".to_string())
+        );
+        assert_eq!(
+            GeoIpDatabase::parse("1.0.0.255,1.0.0.0,AU").err(),
+            Some("line 1: 1.0.0.255 to 1.0.0.0 is not a range".to_string())
+        );
+        assert_eq!(
+            GeoIpDatabase::parse("1.0.0.0,AU").err(),
+            Some("line 1: expected 3 fields, found 2".to_string())
+        );
+    }
+
+    #[test]
+    fn load_reads_database_from_file() {
+        let home_dir = ensure_node_home_directory_exists("geoip", "load_reads_database_from_file");
+        let path = home_dir.join("geoip.csv");
+        fs::write(&path, "1.0.0.0,1.0.0.255,AU\n").unwrap();
+
+        let subject = GeoIpDatabase::load(&path).unwrap();
+
+        assert_eq!(subject.country_of(&ip("1.0.0.7")), Some("AU".to_string()));
+        assert_eq!(
+            format!("{:?}", subject),
+            "GeoIpDatabase { 1 ranges }".to_string()
+        );
+    }
+
+    #[test]
+    fn load_complains_about_missing_file() {
+        let home_dir =
+            ensure_node_home_directory_exists("geoip", "load_complains_about_missing_file");
+        let path = home_dir.join("nonexistent.csv");
+
+        let result = GeoIpDatabase::load(&path);
+
+        match result {
+            Err(e) => assert!(e.starts_with("Couldn't read GeoIP database"), "{}", e),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn load_decompresses_gzipped_database() {
+        let home_dir =
+            ensure_node_home_directory_exists("geoip", "load_decompresses_gzipped_database");
+        let path = home_dir.join("dbip-country-lite.csv.gz");
+        fs::write(&path, gzip("1.0.0.0,1.0.0.255,AU\n")).unwrap();
+
+        let subject = GeoIpDatabase::load(&path).unwrap();
+
+        assert_eq!(subject.country_of(&ip("1.0.0.7")), Some("AU".to_string()));
+    }
+
+    #[test]
+    fn from_gzipped_finds_no_database_in_nothing() {
+        let empty = GeoIpDatabase::from_gzipped(&[]).unwrap();
+        let database = GeoIpDatabase::from_gzipped(&gzip("5.0.0.0,5.255.255.255,FR\n"))
+            .unwrap()
+            .unwrap();
+        let garbage = GeoIpDatabase::from_gzipped(b"booga");
+
+        assert!(empty.is_none());
+        assert_eq!(database.country_of(&ip("5.6.7.8")), Some("FR".to_string()));
+        assert!(garbage.is_err());
+    }
+
+    #[test]
+    fn bundled_database_is_there_only_if_the_build_found_one() {
+        let result = GeoIpDatabase::bundled().unwrap();
+
+        match result {
+            Some(database) => {
+                assert!(GeoIpDatabase::is_bundled());
+                assert_eq!(database.country_of(&ip("8.8.8.8")), Some("US".to_string()));
+            }
+            None => assert!(!GeoIpDatabase::is_bundled()),
+        }
+    }
+}
@@ -136,6 +136,11 @@ impl GossipNodeRecord {
 #[allow(non_camel_case_types)]
 pub struct Gossip_0v1 {
     pub node_records: Vec<GossipNodeRecord>,
+    // The IP address the sender saw Gossip from the target come from, when no Node in the
+    // sender's database claims that address. Nodes behind NAT routers learn their public IP
+    // addresses this way.
+    #[serde(default)]
+    pub observed_ip_opt: Option<IpAddr>,
 }
 
 impl Into<MessageType> for Gossip_0v1 {
@@ -305,7 +310,10 @@ impl DotRenderable for SrcDestEdgeRenderable {
 
 impl Gossip_0v1 {
     pub fn new(node_records: Vec<GossipNodeRecord>) -> Self {
-        Self { node_records }
+        Self {
+            node_records,
+            observed_ip_opt: None,
+        }
     }
 
     // Pass in:
@@ -693,6 +701,7 @@ Length: 4 (0x4) bytes
                 GossipNodeRecord::from((&db, target_node.public_key(), true)),
                 neighbor_gnr,
             ],
+            observed_ip_opt: None,
         };
 
         let result = gossip.to_dot_graph(&source_node, &target_node);
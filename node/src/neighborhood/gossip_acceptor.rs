@@ -567,7 +567,7 @@ impl GossipHandler for IntroductionHandler {
             let (introducer, introducee) = Self::identify_players(agrs, gossip_source)
                 .expect("Introduction not properly qualified");
             let introducer_key = introducer.inner.public_key.clone();
-            match self.update_database(database, cryptde, introducer) {
+            match self.update_database(database, cryptde, introducer, gossip_source) {
                 Ok(_) => (),
                 Err(e) => {
                     return GossipAcceptanceResult::Ban(format!(
@@ -718,6 +718,7 @@ impl IntroductionHandler {
         database: &mut NeighborhoodDatabase,
         cryptde: &dyn CryptDE,
         introducer: AccessibleGossipRecord,
+        gossip_source: SocketAddr,
     ) -> Result<bool, String> {
         let introducer_key = introducer.inner.public_key.clone();
         match database.node_by_key_mut(&introducer_key) {
@@ -730,7 +731,22 @@ impl IntroductionHandler {
                         existing_introducer_ref.version(),
                         introducer.inner.version
                     );
-                    existing_introducer_ref.update(introducer)?;
+                    let sender_key_opt = GossipAcceptorReal::gossip_sender_key(
+                        database,
+                        std::slice::from_ref(&introducer),
+                        gossip_source,
+                    );
+                    GossipAcceptorReal::relocate_if_moved(
+                        database,
+                        &introducer,
+                        sender_key_opt.as_ref(),
+                        gossip_source,
+                        &self.logger,
+                    );
+                    database
+                        .node_by_key_mut(&introducer_key)
+                        .expect("Introducer disappeared")
+                        .update(introducer)?;
                 } else {
                     debug!(
                         self.logger,
@@ -848,7 +864,8 @@ impl GossipHandler for StandardGossipHandler {
     ) -> GossipAcceptanceResult {
         let mut db_changed =
             self.identify_and_add_non_introductory_new_nodes(database, &agrs, gossip_source);
-        db_changed = self.identify_and_update_obsolete_nodes(database, agrs) || db_changed;
+        db_changed =
+            self.identify_and_update_obsolete_nodes(database, agrs, gossip_source) || db_changed;
         db_changed = self.handle_root_node(cryptde, database, gossip_source) || db_changed;
         // If no Nodes need updating, return ::Ignored and don't change the database.
         // Otherwise, return ::Accepted.
@@ -902,11 +919,20 @@ impl StandardGossipHandler {
         &self,
         database: &mut NeighborhoodDatabase,
         agrs: Vec<AccessibleGossipRecord>,
+        gossip_source: SocketAddr,
     ) -> bool {
+        let sender_key_opt = GossipAcceptorReal::gossip_sender_key(database, &agrs, gossip_source);
         let change_flags: Vec<bool> = agrs
             .into_iter()
             .flat_map(|agr| match database.node_by_key(&agr.inner.public_key) {
                 Some(existing_node) if agr.inner.version > existing_node.version() => {
+                    GossipAcceptorReal::relocate_if_moved(
+                        database,
+                        &agr,
+                        sender_key_opt.as_ref(),
+                        gossip_source,
+                        &self.logger,
+                    );
                     Some(self.update_database_record(database, agr))
                 }
                 _ => None,
@@ -1081,6 +1107,86 @@ impl<'a> GossipAcceptorReal<'a> {
         }
     }
 
+    // A Node whose public IP address has changed re-debuts to its neighbors from its new address,
+    // with a newer version of its record signed by its own key. The NodeAddr itself isn't signed:
+    // signing it would reveal addresses that Gossip deliberately withholds. So only the sender of
+    // the Gossip can move its own NodeAddr, and only to the IP address the Gossip came from.
+    fn relocate_if_moved(
+        database: &mut NeighborhoodDatabase,
+        agr: &AccessibleGossipRecord,
+        sender_key_opt: Option<&PublicKey>,
+        gossip_source: SocketAddr,
+        logger: &Logger,
+    ) {
+        let public_key = &agr.inner.public_key;
+        let old_node_addr = match database.node_by_key(public_key) {
+            Some(existing_node) if agr.inner.version > existing_node.version() => {
+                match existing_node.node_addr_opt() {
+                    Some(node_addr) => node_addr,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        let new_node_addr = match &agr.node_addr_opt {
+            Some(node_addr)
+                if node_addr != &old_node_addr && node_addr.ip_addr() == gossip_source.ip() =>
+            {
+                node_addr
+            }
+            _ => return,
+        };
+        if sender_key_opt != Some(public_key) {
+            warning!(
+                logger,
+                "Node {} can't move from {} to {}: the Gossip came from another Node",
+                public_key,
+                old_node_addr,
+                new_node_addr
+            );
+            return;
+        }
+        match database.relocate_node(public_key, new_node_addr) {
+            Ok(_) => info!(
+                logger,
+                "Node {} has moved from {} to {}", public_key, old_node_addr, new_node_addr
+            ),
+            Err(e) => warning!(
+                logger,
+                "Node {} can't move from {} to {}: {:?}",
+                public_key,
+                old_node_addr,
+                new_node_addr,
+                e
+            ),
+        }
+    }
+
+    // Gossip doesn't say who sent it. The sender is the Node already known at the source address
+    // or, if no Node is known there, the one record in the Gossip that claims that address.
+    fn gossip_sender_key(
+        database: &NeighborhoodDatabase,
+        agrs: &[AccessibleGossipRecord],
+        gossip_source: SocketAddr,
+    ) -> Option<PublicKey> {
+        if let Some(node) = database.node_by_ip(&gossip_source.ip()) {
+            return Some(node.public_key().clone());
+        }
+        let claimants = agrs
+            .iter()
+            .filter(|agr| {
+                agr.node_addr_opt
+                    .as_ref()
+                    .map(|node_addr| node_addr.ip_addr() == gossip_source.ip())
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<&AccessibleGossipRecord>>();
+        match claimants.as_slice() {
+            [sender] => Some(sender.inner.public_key.clone()),
+            _ => None,
+        }
+    }
+
     fn make_debut_triple(
         database: &NeighborhoodDatabase,
         debut_target: &AccessibleGossipRecord,
@@ -1615,7 +1721,7 @@ mod tests {
     }
 
     #[test]
-    fn introduction_that_tries_to_change_immutable_characteristics_of_introducer_is_suspicious() {
+    fn introduction_from_introducer_that_has_moved_relocates_introducer() {
         let (gossip, gossip_source) = make_introduction(2345, 3456);
         let dest_root = make_node_record(7878, true);
         let mut dest_db = db_from_node(&dest_root);
@@ -1623,24 +1729,76 @@ mod tests {
         let subject = IntroductionHandler::new(Logger::new("test"));
         let agrs: Vec<AccessibleGossipRecord> = gossip.try_into().unwrap();
         let introducer_key = &agrs[0].inner.public_key;
-        dest_db.add_node(NodeRecord::from(&agrs[0])).unwrap();
+        let mut introducer_before_move = NodeRecord::from(&agrs[0]);
+        introducer_before_move.force_node_addr(&NodeAddr::from(
+            &SocketAddr::from_str("4.5.6.7:4567").unwrap(),
+        ));
+        dest_db.add_node(introducer_before_move).unwrap();
         dest_db
             .node_by_key_mut(introducer_key)
             .unwrap()
             .set_version(0);
+        dest_db.resign_node(introducer_key);
+
+        let qualifies_result = subject.qualifies(&dest_db, &agrs, gossip_source);
+        let handle_result = subject.handle(&cryptde, &mut dest_db, agrs.clone(), gossip_source);
+
+        assert_eq!(qualifies_result, Qualification::Matched);
+        assert_eq!(
+            handle_result,
+            GossipAcceptanceResult::Reply(
+                GossipBuilder::new(&dest_db)
+                    .node(dest_db.root().public_key(), true)
+                    .build(),
+                agrs[1].inner.public_key.clone(),
+                agrs[1].node_addr_opt.clone().unwrap(),
+            )
+        );
+        let introducer_after_gossip = dest_db.node_by_key(introducer_key).unwrap();
+        assert_eq!(
+            introducer_after_gossip.node_addr_opt(),
+            agrs[0].node_addr_opt
+        );
+        assert_eq!(introducer_after_gossip.version(), agrs[0].inner.version);
+        assert_eq!(
+            dest_db
+                .node_by_ip(&gossip_source.ip())
+                .unwrap()
+                .public_key(),
+            introducer_key
+        );
+        assert_eq!(
+            dest_db.node_by_ip(&IpAddr::from_str("4.5.6.7").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn introduction_that_moves_introducer_onto_another_nodes_address_is_suspicious() {
+        let (gossip, gossip_source) = make_introduction(2345, 3456);
+        let dest_root = make_node_record(7878, true);
+        let mut dest_db = db_from_node(&dest_root);
+        let cryptde = CryptDENull::from(dest_db.root().public_key(), DEFAULT_CHAIN_ID);
+        let subject = IntroductionHandler::new(Logger::new("test"));
+        let agrs: Vec<AccessibleGossipRecord> = gossip.try_into().unwrap();
+        let introducer_key = &agrs[0].inner.public_key;
+        let mut squatter = make_node_record(5678, false);
+        squatter.force_node_addr(&NodeAddr::new(&gossip_source.ip(), &[5678]));
+        dest_db.add_node(squatter).unwrap();
+        let mut introducer_before_move = NodeRecord::from(&agrs[0]);
+        introducer_before_move.force_node_addr(&NodeAddr::from(
+            &SocketAddr::from_str("4.5.6.7:4567").unwrap(),
+        ));
+        dest_db.add_node(introducer_before_move).unwrap();
         dest_db
             .node_by_key_mut(introducer_key)
             .unwrap()
-            .force_node_addr(&NodeAddr::from(
-                &SocketAddr::from_str("4.5.6.7:4567").unwrap(),
-            ));
+            .set_version(0);
         dest_db.resign_node(introducer_key);
         let introducer_before_gossip = dest_db.node_by_key(introducer_key).unwrap().clone();
 
-        let qualifies_result = subject.qualifies(&dest_db, &agrs, gossip_source);
         let handle_result = subject.handle(&cryptde, &mut dest_db, agrs.clone(), gossip_source);
 
-        assert_eq!(qualifies_result, Qualification::Matched);
         assert_eq!(
             handle_result,
             GossipAcceptanceResult::Ban(format!("Introducer {} tried changing immutable characteristic: Updating a NodeRecord must not change its node_addr_opt: 4.5.6.7:4567 -> 2.3.4.5:2345", introducer_key)),
@@ -2804,6 +2962,171 @@ mod tests {
         );
     }
 
+    #[test]
+    fn standard_gossip_from_neighbor_that_has_moved_relocates_it() {
+        let dest_root = make_node_record(1234, true);
+        let mut dest_db = db_from_node(&dest_root);
+        let neighbor = make_node_record(2345, true);
+        dest_db.add_node(neighbor.clone()).unwrap();
+        dest_db.add_arbitrary_full_neighbor(dest_root.public_key(), neighbor.public_key());
+        let mut moved_neighbor = dest_db.node_by_key(neighbor.public_key()).unwrap().clone();
+        moved_neighbor.increment_version();
+        moved_neighbor.resign();
+        let new_node_addr = NodeAddr::new(&IpAddr::from_str("3.4.5.6").unwrap(), &[2345]);
+        let mut agr = AccessibleGossipRecord::from(&moved_neighbor);
+        agr.node_addr_opt = Some(new_node_addr.clone());
+        let subject = GossipAcceptorReal::new(main_cryptde());
+
+        let result = subject.handle(
+            &mut dest_db,
+            vec![agr],
+            SocketAddr::from_str("3.4.5.6:2345").unwrap(),
+        );
+
+        assert_eq!(result, GossipAcceptanceResult::Accepted);
+        let neighbor_after_gossip = dest_db.node_by_key(neighbor.public_key()).unwrap();
+        assert_eq!(
+            neighbor_after_gossip.node_addr_opt(),
+            Some(new_node_addr.clone())
+        );
+        assert_eq!(neighbor_after_gossip.version(), moved_neighbor.version());
+        assert_eq!(
+            dest_db
+                .node_by_ip(&new_node_addr.ip_addr())
+                .unwrap()
+                .public_key(),
+            neighbor.public_key()
+        );
+        assert_eq!(
+            dest_db.node_by_ip(&neighbor.node_addr_opt().unwrap().ip_addr()),
+            None
+        );
+    }
+
+    #[test]
+    fn standard_gossip_cannot_move_a_node_unless_it_comes_from_the_new_address() {
+        let dest_root = make_node_record(1234, true);
+        let mut dest_db = db_from_node(&dest_root);
+        let relayer = make_node_record(2345, true);
+        let neighbor = make_node_record(3456, true);
+        dest_db.add_node(relayer.clone()).unwrap();
+        dest_db.add_node(neighbor.clone()).unwrap();
+        dest_db.add_arbitrary_full_neighbor(dest_root.public_key(), relayer.public_key());
+        dest_db.add_arbitrary_full_neighbor(dest_root.public_key(), neighbor.public_key());
+        let mut moved_neighbor = dest_db.node_by_key(neighbor.public_key()).unwrap().clone();
+        moved_neighbor.increment_version();
+        moved_neighbor.resign();
+        let mut agr = AccessibleGossipRecord::from(&moved_neighbor);
+        agr.node_addr_opt = Some(NodeAddr::new(
+            &IpAddr::from_str("5.6.7.8").unwrap(),
+            &[3456],
+        ));
+        let relayer_agr =
+            AccessibleGossipRecord::from(dest_db.node_by_key(relayer.public_key()).unwrap());
+        let subject = GossipAcceptorReal::new(main_cryptde());
+
+        subject.handle(
+            &mut dest_db,
+            vec![relayer_agr, agr],
+            relayer.node_addr_opt().unwrap().into(),
+        );
+
+        let neighbor_after_gossip = dest_db.node_by_key(neighbor.public_key()).unwrap();
+        assert_eq!(
+            neighbor_after_gossip.node_addr_opt(),
+            neighbor.node_addr_opt()
+        );
+        assert_eq!(
+            neighbor_after_gossip.version(),
+            moved_neighbor.version() - 1
+        );
+        assert_eq!(
+            dest_db.node_by_ip(&IpAddr::from_str("5.6.7.8").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn standard_gossip_cannot_move_another_node_to_the_senders_address() {
+        let dest_root = make_node_record(1234, true);
+        let mut dest_db = db_from_node(&dest_root);
+        let relayer = make_node_record(2345, true);
+        let neighbor = make_node_record(3456, true);
+        dest_db.add_node(relayer.clone()).unwrap();
+        dest_db.add_node(neighbor.clone()).unwrap();
+        dest_db.add_arbitrary_full_neighbor(dest_root.public_key(), relayer.public_key());
+        dest_db.add_arbitrary_full_neighbor(dest_root.public_key(), neighbor.public_key());
+        let mut moved_neighbor = dest_db.node_by_key(neighbor.public_key()).unwrap().clone();
+        moved_neighbor.increment_version();
+        moved_neighbor.resign();
+        let mut agr = AccessibleGossipRecord::from(&moved_neighbor);
+        agr.node_addr_opt = relayer.node_addr_opt();
+        let subject = GossipAcceptorReal::new(main_cryptde());
+
+        subject.handle(
+            &mut dest_db,
+            vec![agr],
+            relayer.node_addr_opt().unwrap().into(),
+        );
+
+        let neighbor_after_gossip = dest_db.node_by_key(neighbor.public_key()).unwrap();
+        assert_eq!(
+            neighbor_after_gossip.node_addr_opt(),
+            neighbor.node_addr_opt()
+        );
+        assert_eq!(
+            dest_db
+                .node_by_ip(&relayer.node_addr_opt().unwrap().ip_addr())
+                .unwrap()
+                .public_key(),
+            relayer.public_key()
+        );
+    }
+
+    #[test]
+    fn gossip_sender_key_prefers_the_node_already_known_at_the_source() {
+        let root = make_node_record(1234, true);
+        let mut db = db_from_node(&root);
+        let known = make_node_record(2345, true);
+        db.add_node(known.clone()).unwrap();
+        let mut claimant = AccessibleGossipRecord::from(&make_node_record(3456, true));
+        claimant.node_addr_opt = known.node_addr_opt();
+
+        let result = GossipAcceptorReal::gossip_sender_key(
+            &db,
+            &[claimant],
+            known.node_addr_opt().unwrap().into(),
+        );
+
+        assert_eq!(result, Some(known.public_key().clone()));
+    }
+
+    #[test]
+    fn gossip_sender_key_is_the_lone_claimant_of_an_unknown_source() {
+        let root = make_node_record(1234, true);
+        let db = db_from_node(&root);
+        let claimant = make_node_record(2345, true);
+        let bystander = make_node_record(3456, true);
+        let agrs = vec![
+            AccessibleGossipRecord::from(&bystander),
+            AccessibleGossipRecord::from(&claimant),
+        ];
+
+        let result = GossipAcceptorReal::gossip_sender_key(
+            &db,
+            &agrs,
+            claimant.node_addr_opt().unwrap().into(),
+        );
+        let unclaimed_result = GossipAcceptorReal::gossip_sender_key(
+            &db,
+            &agrs,
+            SocketAddr::from_str("9.8.7.6:5432").unwrap(),
+        );
+
+        assert_eq!(result, Some(claimant.public_key().clone()));
+        assert_eq!(unclaimed_result, None);
+    }
+
     #[test]
     fn make_debut_triple_doesnt_like_record_with_no_node_addr() {
         let root_node = make_node_record(1234, true);
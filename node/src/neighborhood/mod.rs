@@ -1,6 +1,7 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 mod dot_graph;
+pub mod geoip;
 pub mod gossip;
 pub mod gossip_acceptor;
 #[cfg(not(feature = "expose_test_privates"))]
@@ -9,13 +10,16 @@ mod gossip_producer;
 pub mod gossip_producer;
 pub mod neighborhood_database;
 pub mod node_record;
+pub mod public_ip_detector;
 
 use crate::blockchain::blockchain_interface::{chain_id_from_name, contract_address};
 use crate::bootstrapper::BootstrapperConfig;
+use crate::database::dao_utils::DaoFactoryReal;
 use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
+use crate::malefactor_dao::{Malefactor, MalefactorDao, MalefactorDaoFactory, MALEFACTOR_CACHE};
 use crate::neighborhood::gossip::{DotGossipEndpoint, GossipNodeRecord, Gossip_0v1};
 use crate::neighborhood::gossip_acceptor::GossipAcceptanceResult;
 use crate::neighborhood::node_record::NodeRecordInner_0v1;
@@ -24,12 +28,14 @@ use crate::sub_lib::configurator::NewPasswordMessage;
 use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::cryptde::{CryptDE, CryptData, PlainData};
 use crate::sub_lib::dispatcher::{Component, StreamShutdownMsg};
+use crate::sub_lib::exit_policy::{CountryFinder, ExitPolicy};
 use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
 use crate::sub_lib::hopper::{IncipientCoresPackage, MessageType};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::neighborhood::ExpectedService;
 use crate::sub_lib::neighborhood::ExpectedServices;
 use crate::sub_lib::neighborhood::NeighborhoodSubs;
+use crate::sub_lib::neighborhood::NewPublicIp;
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::neighborhood::NodeQueryMessage;
 use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
@@ -37,6 +43,7 @@ use crate::sub_lib::neighborhood::NodeRecordMetadataMessage;
 use crate::sub_lib::neighborhood::RemoveNeighborMessage;
 use crate::sub_lib::neighborhood::RouteQueryMessage;
 use crate::sub_lib::neighborhood::RouteQueryResponse;
+use crate::sub_lib::neighborhood::RoutingStrategy;
 use crate::sub_lib::neighborhood::{DispatcherNodeQueryMessage, GossipFailure_0v1};
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
@@ -54,24 +61,41 @@ use actix::Handler;
 use actix::MessageResult;
 use actix::Recipient;
 use actix::{Actor, System};
+use geoip::GeoIpDatabase;
 use gossip_acceptor::GossipAcceptor;
 use gossip_acceptor::GossipAcceptorReal;
 use gossip_producer::GossipProducer;
 use gossip_producer::GossipProducerReal;
 use itertools::Itertools;
 use masq_lib::constants::DEFAULT_CHAIN_NAME;
-use masq_lib::messages::FromMessageBody;
-use masq_lib::messages::UiShutdownRequest;
-use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
+use masq_lib::messages::{FromMessageBody, ToMessageBody};
+use masq_lib::messages::{UiNeighborhoodNode, UiNeighborhoodRequest, UiNeighborhoodResponse};
+use masq_lib::messages::{UiNodePerformance, UiNodePerformanceRequest, UiNodePerformanceResponse};
+use masq_lib::messages::{UiRatePack, UiShutdownRequest};
+use masq_lib::ui_gateway::{MessageBody, MessageTarget, NodeFromUiMessage, NodeToUiMessage};
 use masq_lib::utils::exit_process;
 use neighborhood_database::NeighborhoodDatabase;
-use node_record::NodeRecord;
+use node_record::{NodePerformance, NodeRecord};
+use public_ip_detector::{PublicIpDetector, PublicIpDetectorReal};
+use rand::Rng;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub const CRASH_KEY: &str = "NEIGHBORHOOD";
+// Nominal payload size used to compare the byte-rate charges of candidate routes
+pub const ROUTE_COST_PAYLOAD_BYTES: u64 = 1_000;
+// Charge-equivalent added for every hop in a candidate route, so that long routes are
+// not chosen merely because their Nodes are slightly cheaper
+pub const ROUTE_HOP_PENALTY: u64 = 1_000;
+pub const PUBLIC_IP_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Number of neighbors that must see a Node behind a NAT router at the same new address before
+// it moves there (fewer if it doesn't have this many neighbors)
+pub const OBSERVED_IP_CONFIRMATIONS: usize = 2;
 
 pub struct Neighborhood {
     cryptde: &'static dyn CryptDE,
@@ -79,7 +103,7 @@ pub struct Neighborhood {
     hopper_no_lookup: Option<Recipient<NoLookupIncipientCoresPackage>>,
     is_connected: bool,
     connected_signal: Option<Recipient<StartMessage>>,
-    _to_ui_message_sub: Option<Recipient<NodeToUiMessage>>,
+    node_to_ui_sub: Option<Recipient<NodeToUiMessage>>,
     gossip_acceptor: Box<dyn GossipAcceptor>,
     gossip_producer: Box<dyn GossipProducer>,
     neighborhood_database: NeighborhoodDatabase,
@@ -90,9 +114,24 @@ pub struct Neighborhood {
     data_directory: PathBuf,
     persistent_config_opt: Option<Box<dyn PersistentConfiguration>>,
     db_password_opt: Option<String>,
+    routing_strategy: RoutingStrategy,
+    exit_policy: ExitPolicy,
+    geoip_database_opt: Option<Arc<GeoIpDatabase>>,
+    malefactor_dao_opt: Option<Box<dyn MalefactorDao>>,
+    malefactor_ban_duration: Duration,
+    public_ip_detector: Box<dyn PublicIpDetector>,
+    public_ip_check_interval: Duration,
+    observed_public_ips: HashMap<PublicKey, IpAddr>,
     logger: Logger,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PublicIpMonitor {
+    LocalInterface,
+    Neighbors,
+    Unavailable,
+}
+
 impl Actor for Neighborhood {
     type Context = Context<Self>;
 }
@@ -105,14 +144,30 @@ impl Handler<BindMessage> for Neighborhood {
         self.hopper = Some(msg.peer_actors.hopper.from_hopper_client);
         self.hopper_no_lookup = Some(msg.peer_actors.hopper.from_hopper_client_no_lookup);
         self.connected_signal = Some(msg.peer_actors.accountant.start);
+        self.node_to_ui_sub = Some(msg.peer_actors.ui_gateway.node_to_ui_message_sub);
     }
 }
 
 impl Handler<StartMessage> for Neighborhood {
     type Result = ();
 
-    fn handle(&mut self, _msg: StartMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, _msg: StartMessage, ctx: &mut Self::Context) -> Self::Result {
         self.handle_start_message();
+        match self.public_ip_monitor() {
+            PublicIpMonitor::LocalInterface => {
+                ctx.run_interval(self.public_ip_check_interval, |neighborhood, ctx| {
+                    if let Some(new_ip) = neighborhood.detect_new_public_ip() {
+                        ctx.notify(NewPublicIp { new_ip });
+                    }
+                });
+            }
+            PublicIpMonitor::Neighbors => {
+                ctx.run_interval(self.public_ip_check_interval, |neighborhood, _ctx| {
+                    neighborhood.gossip_to_neighbors()
+                });
+            }
+            PublicIpMonitor::Unavailable => (),
+        }
     }
 }
 
@@ -246,17 +301,19 @@ impl Handler<RemoveNeighborMessage> for Neighborhood {
     }
 }
 
+impl Handler<NewPublicIp> for Neighborhood {
+    type Result = ();
+
+    fn handle(&mut self, msg: NewPublicIp, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_new_public_ip(msg.new_ip);
+    }
+}
+
 impl Handler<NodeRecordMetadataMessage> for Neighborhood {
     type Result = ();
 
     fn handle(&mut self, msg: NodeRecordMetadataMessage, _ctx: &mut Self::Context) -> Self::Result {
-        match msg {
-            NodeRecordMetadataMessage::Desirable(public_key, desirable) => {
-                if let Some(node_record) = self.neighborhood_database.node_by_key_mut(&public_key) {
-                    node_record.set_desirable(desirable);
-                };
-            }
-        };
+        self.handle_node_record_metadata_message(msg);
     }
 }
 
@@ -273,8 +330,12 @@ impl Handler<NodeFromUiMessage> for Neighborhood {
 
     fn handle(&mut self, msg: NodeFromUiMessage, _ctx: &mut Self::Context) -> Self::Result {
         let client_id = msg.client_id;
-        if let Ok((body, _)) = UiShutdownRequest::fmb(msg.body) {
+        if let Ok((body, _)) = UiShutdownRequest::fmb(msg.body.clone()) {
             self.handle_shutdown_order(client_id, body);
+        } else if let Ok((body, context_id)) = UiNeighborhoodRequest::fmb(msg.body.clone()) {
+            self.handle_neighborhood_request(client_id, context_id, body);
+        } else if let Ok((_, context_id)) = UiNodePerformanceRequest::fmb(msg.body) {
+            self.handle_node_performance_request(client_id, context_id);
         }
     }
 }
@@ -366,7 +427,7 @@ impl Neighborhood {
             hopper: None,
             hopper_no_lookup: None,
             connected_signal: None,
-            _to_ui_message_sub: None,
+            node_to_ui_sub: None,
             is_connected: false,
             gossip_acceptor,
             gossip_producer,
@@ -378,6 +439,14 @@ impl Neighborhood {
             data_directory: config.data_directory.clone(),
             persistent_config_opt: None,
             db_password_opt: config.db_password_opt.clone(),
+            routing_strategy: config.routing_strategy,
+            exit_policy: config.exit_policy.clone(),
+            geoip_database_opt: config.geoip_database_opt.clone(),
+            malefactor_dao_opt: None,
+            malefactor_ban_duration: config.malefactor_ban_duration,
+            public_ip_detector: Box::new(PublicIpDetectorReal::default()),
+            public_ip_check_interval: PUBLIC_IP_CHECK_INTERVAL,
+            observed_public_ips: HashMap::new(),
             logger: Logger::new("Neighborhood"),
         }
     }
@@ -404,9 +473,110 @@ impl Neighborhood {
 
     fn handle_start_message(&mut self) {
         self.connect_database();
+        self.load_malefactor_bans();
         self.send_debut_gossip();
     }
 
+    // The local interface address only stands in for the public IP address if the two agreed at
+    // startup. Behind a NAT router, public IP changes can't be seen from here, so the Node gossips
+    // to its neighbors regularly and they report the address its Gossip comes from.
+    fn public_ip_monitor(&self) -> PublicIpMonitor {
+        let root = self.neighborhood_database.root();
+        let public_ip = match root.node_addr_opt() {
+            Some(node_addr) if root.accepts_connections() => node_addr.ip_addr(),
+            _ => return PublicIpMonitor::Unavailable,
+        };
+        match self.public_ip_detector.detect(public_ip) {
+            Some(local_ip) if local_ip == public_ip => {
+                info!(
+                    self.logger,
+                    "Monitoring public IP address {} for changes", public_ip
+                );
+                PublicIpMonitor::LocalInterface
+            }
+            Some(local_ip) => {
+                info!(
+                    self.logger,
+                    "Local IP address {} is not public IP address {}; neighbors will report changes to the public IP address",
+                    local_ip,
+                    public_ip
+                );
+                PublicIpMonitor::Neighbors
+            }
+            None => {
+                warning!(
+                    self.logger,
+                    "Could not determine local IP address; neighbors will report changes to public IP address {}",
+                    public_ip
+                );
+                PublicIpMonitor::Neighbors
+            }
+        }
+    }
+
+    fn detect_new_public_ip(&self) -> Option<IpAddr> {
+        let public_ip = self.neighborhood_database.root().node_addr_opt()?.ip_addr();
+        self.public_ip_detector
+            .detect(public_ip)
+            .filter(|local_ip| *local_ip != public_ip)
+    }
+
+    fn handle_new_public_ip(&mut self, new_ip: IpAddr) {
+        let old_node_addr = match self.neighborhood_database.root().node_addr_opt() {
+            Some(node_addr) if node_addr.ip_addr() != new_ip => node_addr,
+            _ => return,
+        };
+        let new_node_addr = NodeAddr::new(&new_ip, &old_node_addr.ports());
+        let root_key = self.neighborhood_database.root().public_key().clone();
+        if let Err(e) = self
+            .neighborhood_database
+            .relocate_node(&root_key, &new_node_addr)
+        {
+            error!(
+                self.logger,
+                "Could not move from {} to {}: {:?}", old_node_addr, new_node_addr, e
+            );
+            return;
+        }
+        let root_mut = self.neighborhood_database.root_mut();
+        root_mut.increment_version();
+        root_mut.regenerate_signed_gossip(self.cryptde);
+        info!(
+            self.logger,
+            "Public IP address changed from {} to {}; re-debuting",
+            old_node_addr.ip_addr(),
+            new_ip
+        );
+        self.send_re_debut_gossip();
+    }
+
+    // Neighbors accept a NodeAddr change only in a newer version of our record that comes from
+    // the new address itself, so each one gets a Debut directly from here.
+    fn send_re_debut_gossip(&mut self) {
+        let neighbor_targets = self
+            .neighborhood_database
+            .root()
+            .half_neighbor_keys()
+            .into_iter()
+            .flat_map(|key| {
+                self.neighborhood_database
+                    .node_by_key(key)
+                    .and_then(|node| node.node_addr_opt())
+                    .map(|node_addr| (key.clone(), node_addr))
+            })
+            .collect_vec();
+        if neighbor_targets.is_empty() {
+            self.send_debut_gossip();
+            return;
+        }
+        let gossip = self
+            .gossip_producer
+            .produce_debut(&self.neighborhood_database);
+        neighbor_targets
+            .iter()
+            .for_each(|(key, node_addr)| self.handle_gossip_reply(gossip.clone(), key, node_addr));
+    }
+
     fn handle_route_query_message(&mut self, msg: RouteQueryMessage) -> Option<RouteQueryResponse> {
         let msg_str = format!("{:?}", msg);
         let route_result = if msg.minimum_hop_count == 0 {
@@ -439,6 +609,27 @@ impl Neighborhood {
                 .expect("Neighborhood could not connect to database");
             self.persistent_config_opt = Some(Box::new(PersistentConfigurationReal::from(conn)));
         }
+        if self.malefactor_dao_opt.is_none() {
+            self.malefactor_dao_opt =
+                Some(DaoFactoryReal::new(&self.data_directory, self.chain_id, true).make());
+        }
+    }
+
+    fn load_malefactor_bans(&self) {
+        let bans = self
+            .malefactor_dao_opt
+            .as_ref()
+            .expect("MalefactorDao was not set by StartMessage")
+            .active_bans();
+        bans.iter()
+            .for_each(|malefactor| MALEFACTOR_CACHE.insert(malefactor));
+        if !bans.is_empty() {
+            info!(
+                self.logger,
+                "Loaded {} active malefactor bans from the database",
+                bans.len()
+            );
+        }
     }
 
     fn send_debut_gossip(&mut self) {
@@ -510,11 +701,15 @@ impl Neighborhood {
             .collect();
 
         if agrs.len() < record_count {
-            // TODO: Instead of ignoring non-deserializable Gossip, ban the Node that sent it
             error!(
                 self.logger,
                 "Received non-deserializable Gossip from {}", gossip_source
             );
+            self.ban_malefactor(
+                &agrs,
+                gossip_source,
+                "Sent non-deserializable Gossip".to_string(),
+            );
             self.announce_gossip_handling_completion(record_count);
             return;
         }
@@ -527,19 +722,69 @@ impl Neighborhood {
             )
         };
         if agrs.iter().any(signature_invalid) {
-            // TODO: Instead of ignoring badly-signed Gossip, ban the Node that sent it
             error!(
                 self.logger,
                 "Received Gossip with invalid signature from {}", gossip_source
             );
+            self.ban_malefactor(
+                &agrs,
+                gossip_source,
+                "Sent Gossip with invalid signature".to_string(),
+            );
             self.announce_gossip_handling_completion(record_count);
             return;
         }
 
+        if let Some(observed_ip) = incoming_gossip.observed_ip_opt {
+            self.handle_observed_ip(observed_ip, gossip_source);
+        }
         self.handle_gossip_agrs(agrs, gossip_source);
         self.announce_gossip_handling_completion(record_count);
     }
 
+    // A neighbor reports the address it saw this Node's Gossip come from when no Node it knows
+    // claims that address. Since a single neighbor could lie, the Node moves only when enough of
+    // its neighbors agree.
+    fn handle_observed_ip(&mut self, observed_ip: IpAddr, gossip_source: SocketAddr) {
+        let root = self.neighborhood_database.root();
+        let reporter_key = match self.neighborhood_database.node_by_ip(&gossip_source.ip()) {
+            Some(node) if root.has_half_neighbor(node.public_key()) => node.public_key().clone(),
+            _ => {
+                debug!(
+                    self.logger,
+                    "Ignoring report of public IP address {} from {}: not a neighbor",
+                    observed_ip,
+                    gossip_source
+                );
+                return;
+            }
+        };
+        self.observed_public_ips.insert(reporter_key, observed_ip);
+        self.observed_public_ips
+            .retain(|key, _| root.has_half_neighbor(key));
+        let agreeing_count = self
+            .observed_public_ips
+            .values()
+            .filter(|ip| **ip == observed_ip)
+            .count();
+        let required_count = root
+            .half_neighbor_keys()
+            .len()
+            .min(OBSERVED_IP_CONFIRMATIONS);
+        if agreeing_count < required_count {
+            info!(
+                self.logger,
+                "{} of {} neighbors see this Node at {}; waiting for confirmation",
+                agreeing_count,
+                required_count,
+                observed_ip
+            );
+            return;
+        }
+        self.observed_public_ips.clear();
+        self.handle_new_public_ip(observed_ip);
+    }
+
     fn handle_gossip_failure(&mut self, failure_source: SocketAddr, failure: GossipFailure_0v1) {
         match self
             .initial_neighbors
@@ -585,9 +830,60 @@ impl Neighborhood {
 
     fn handle_gossip_agrs(&mut self, agrs: Vec<AccessibleGossipRecord>, gossip_source: SocketAddr) {
         let neighbor_keys_before = self.neighbor_keys();
+        let unattributed_neighbor_keys = self.unattributed_neighbor_keys(&agrs, gossip_source);
         self.handle_agrs(agrs, gossip_source);
         let neighbor_keys_after = self.neighbor_keys();
         self.handle_database_changes(&neighbor_keys_before, &neighbor_keys_after);
+        self.report_observed_ip(&unattributed_neighbor_keys, gossip_source);
+    }
+
+    // Gossip from an address that no known Node claims may come from a neighbor whose public IP
+    // address has changed behind a NAT router. Any neighbor whose record is in the Gossip might
+    // be the sender.
+    fn unattributed_neighbor_keys(
+        &self,
+        agrs: &[AccessibleGossipRecord],
+        gossip_source: SocketAddr,
+    ) -> Vec<PublicKey> {
+        if self
+            .neighborhood_database
+            .node_by_ip(&gossip_source.ip())
+            .is_some()
+        {
+            return vec![];
+        }
+        let root = self.neighborhood_database.root();
+        agrs.iter()
+            .map(|agr| &agr.inner.public_key)
+            .filter(|key| root.has_half_neighbor(key))
+            .cloned()
+            .collect()
+    }
+
+    // Only the real sender can decrypt the report, so it goes to each candidate in turn.
+    fn report_observed_ip(&self, candidate_keys: &[PublicKey], gossip_source: SocketAddr) {
+        if candidate_keys.is_empty()
+            || self
+                .neighborhood_database
+                .node_by_ip(&gossip_source.ip())
+                .is_some()
+        {
+            return;
+        }
+        let mut gossip = self
+            .gossip_producer
+            .produce_debut(&self.neighborhood_database);
+        gossip.observed_ip_opt = Some(gossip_source.ip());
+        let source_node_addr = NodeAddr::new(&gossip_source.ip(), &[gossip_source.port()]);
+        candidate_keys.iter().for_each(|key| {
+            debug!(
+                self.logger,
+                "Telling neighbor {} that its Gossip came from {}",
+                key,
+                gossip_source.ip()
+            );
+            self.handle_gossip_reply(gossip.clone(), key, &source_node_addr)
+        });
     }
 
     fn neighbor_keys(&self) -> Vec<PublicKey> {
@@ -601,6 +897,7 @@ impl Neighborhood {
 
     fn handle_agrs(&mut self, agrs: Vec<AccessibleGossipRecord>, gossip_source: SocketAddr) {
         let ignored_node_name = self.gossip_source_name(&agrs, gossip_source);
+        let gossip_source_key_opt = self.gossip_source_key(&agrs, gossip_source);
         let gossip_record_count = agrs.len();
         let acceptance_result =
             self.gossip_acceptor
@@ -618,8 +915,7 @@ impl Neighborhood {
                 self.handle_gossip_ignored(ignored_node_name, gossip_record_count)
             }
             GossipAcceptanceResult::Ban(reason) => {
-                warning!(self.logger, "Malefactor detected at {}, but malefactor bans not yet implemented; ignoring: {}", gossip_source, reason
-            );
+                self.ban_malefactor_key(gossip_source_key_opt, gossip_source, reason);
                 self.handle_gossip_ignored(ignored_node_name, gossip_record_count);
             }
         }
@@ -680,6 +976,7 @@ impl Neighborhood {
             target_component: Component::ProxyClient,
             minimum_hop_count: DEFAULT_MINIMUM_HOP_COUNT,
             return_component_opt: Some(Component::ProxyServer),
+            exit_policy_opt: None,
         };
         if self.handle_route_query_message(msg).is_some() {
             self.is_connected = true;
@@ -790,12 +1087,16 @@ impl Neighborhood {
         &mut self,
         msg: RouteQueryMessage,
     ) -> Result<RouteQueryResponse, String> {
+        let exit_policy = msg
+            .exit_policy_opt
+            .unwrap_or_else(|| self.exit_policy.clone());
         let over = self.make_route_segment(
             &self.cryptde.public_key(),
             msg.target_key_opt.as_ref(),
             msg.minimum_hop_count,
             msg.target_component,
             RouteDirection::Over,
+            &exit_policy,
         )?;
         debug!(self.logger, "Route over: {:?}", over);
         let back = self.make_route_segment(
@@ -804,6 +1105,7 @@ impl Neighborhood {
             msg.minimum_hop_count,
             msg.return_component_opt.expect("No return component"),
             RouteDirection::Back,
+            &exit_policy,
         )?;
         debug!(self.logger, "Route back: {:?}", back);
         self.compose_route_query_response(over, back)
@@ -861,29 +1163,213 @@ impl Neighborhood {
         minimum_hop_count: usize,
         target_component: Component,
         direction: RouteDirection,
+        exit_policy: &ExitPolicy,
     ) -> Result<RouteSegment, String> {
-        let mut node_seqs =
-            self.complete_routes(vec![origin], target, minimum_hop_count, direction);
+        let node_seqs = self.complete_routes(
+            vec![origin],
+            target,
+            minimum_hop_count,
+            direction,
+            exit_policy,
+        );
 
         if node_seqs.is_empty() {
             let target_str = match target {
                 Some(t) => format!(" {}", t),
                 None => String::from("Unknown"),
             };
+            let policy_str = if target.is_none() && !exit_policy.is_empty() {
+                " permitted by the exit policy"
+            } else {
+                ""
+            };
             Err(format!(
-                "Couldn't find any routes: at least {}-hop from {} to {:?} at {}",
-                minimum_hop_count, origin, target_component, target_str
+                "Couldn't find any routes{}: at least {}-hop from {} to {:?} at {}",
+                policy_str, minimum_hop_count, origin, target_component, target_str
             ))
         } else {
-            // When the target is Some all exit nodes will be the target and it is not optimal to sort.
-            if target.is_none() {
-                self.sort_routes_by_desirable_exit_nodes(node_seqs.as_mut());
-            }
-            let chosen_node_seq = node_seqs.remove(0);
+            let chosen_node_seq = self.choose_route(node_seqs, target.is_none());
             Ok(RouteSegment::new(chosen_node_seq, target_component))
         }
     }
 
+    fn choose_route<'a>(
+        &self,
+        mut node_seqs: Vec<Vec<&'a PublicKey>>,
+        exit_is_open: bool,
+    ) -> Vec<&'a PublicKey> {
+        // When the target is Some all exit nodes will be the target and it is not optimal to sort.
+        if exit_is_open {
+            self.sort_routes_by_desirable_exit_nodes(node_seqs.as_mut());
+            let desirable_exit_count = node_seqs
+                .iter()
+                .take_while(|node_seq| self.is_exit_desirable(node_seq))
+                .count();
+            if desirable_exit_count > 0 {
+                node_seqs.truncate(desirable_exit_count);
+            }
+        }
+        match self.routing_strategy {
+            // A cheap route is no bargain if its Nodes keep failing, so the cheapest route is
+            // chosen from among those with the fewest unreliable Nodes.
+            RoutingStrategy::Cheapest => node_seqs
+                .into_iter()
+                .min_by_key(|node_seq| {
+                    (
+                        self.unreliable_node_count(node_seq),
+                        self.route_cost(node_seq),
+                    )
+                })
+                .expect("No routes to choose from"),
+            RoutingStrategy::Random => {
+                let index = rand::thread_rng().gen_range(0, node_seqs.len());
+                node_seqs.swap_remove(index)
+            }
+            RoutingStrategy::Reliable => node_seqs
+                .into_iter()
+                .min_by_key(|node_seq| {
+                    (
+                        self.undesirable_node_count(node_seq),
+                        self.route_failure_score(node_seq),
+                        self.route_latency(node_seq),
+                        self.route_cost(node_seq),
+                    )
+                })
+                .expect("No routes to choose from"),
+        }
+    }
+
+    fn handle_node_record_metadata_message(&mut self, msg: NodeRecordMetadataMessage) {
+        match msg {
+            NodeRecordMetadataMessage::Desirable(public_key, desirable) => {
+                if let Some(node_record) = self.neighborhood_database.node_by_key_mut(&public_key) {
+                    node_record.set_desirable(desirable);
+                };
+            }
+            NodeRecordMetadataMessage::RouteSucceeded(route_keys, round_trip) => self
+                .record_route_performance(&route_keys, |performance, is_exit| {
+                    performance.record_success(round_trip, is_exit)
+                }),
+            NodeRecordMetadataMessage::RouteFailed(route_keys) => self
+                .record_route_performance(&route_keys, |performance, is_exit| {
+                    performance.record_failure(is_exit)
+                }),
+            NodeRecordMetadataMessage::DnsFailure(exit_key) => {
+                if let Some(node_record) = self.neighborhood_database.node_by_key_mut(&exit_key) {
+                    node_record.set_desirable(false);
+                    node_record.performance_mut().record_dns_failure();
+                };
+            }
+        };
+    }
+
+    // The exit Node is last in route_keys. We don't keep statistics about ourselves.
+    fn record_route_performance<F>(&mut self, route_keys: &[PublicKey], record: F)
+    where
+        F: Fn(&mut NodePerformance, bool),
+    {
+        let root_key = self.neighborhood_database.root().public_key().clone();
+        let exit_index = route_keys.len().saturating_sub(1);
+        route_keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| **key != root_key)
+            .for_each(|(index, key)| {
+                if let Some(node_record) = self.neighborhood_database.node_by_key_mut(key) {
+                    record(node_record.performance_mut(), index == exit_index);
+                }
+            });
+    }
+
+    fn is_exit_desirable(&self, node_seq: &[&PublicKey]) -> bool {
+        node_seq
+            .last()
+            .and_then(|key| self.neighborhood_database.node_by_key(key))
+            .map(|node| node.is_desirable())
+            .unwrap_or(false)
+    }
+
+    fn undesirable_node_count(&self, node_seq: &[&PublicKey]) -> usize {
+        node_seq
+            .iter()
+            .skip(1)
+            .filter(|key| match self.neighborhood_database.node_by_key(key) {
+                Some(node) => !node.is_desirable(),
+                None => true,
+            })
+            .count()
+    }
+
+    fn unreliable_node_count(&self, node_seq: &[&PublicKey]) -> usize {
+        node_seq
+            .iter()
+            .skip(1)
+            .filter_map(|key| self.neighborhood_database.node_by_key(key))
+            .filter(|node| node.performance().is_unreliable())
+            .count()
+    }
+
+    // Sum of the failure percentages of the Nodes in the route other than the originator, plus
+    // the DNS failure percentage of the exit Node
+    fn route_failure_score(&self, node_seq: &[&PublicKey]) -> u32 {
+        let exit_index = node_seq.len().saturating_sub(1);
+        node_seq
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(index, key)| {
+                self.neighborhood_database
+                    .node_by_key(key)
+                    .map(|node| (index, node.performance()))
+            })
+            .map(|(index, performance)| {
+                let dns_failure_percentage = if index == exit_index {
+                    performance.dns_failure_percentage()
+                } else {
+                    0
+                };
+                performance.failure_percentage() + dns_failure_percentage
+            })
+            .sum()
+    }
+
+    // Slowest round trip observed through any Node in the route other than the originator;
+    // Nodes we haven't timed yet don't count against the route.
+    fn route_latency(&self, node_seq: &[&PublicKey]) -> Duration {
+        node_seq
+            .iter()
+            .skip(1)
+            .filter_map(|key| self.neighborhood_database.node_by_key(key))
+            .filter_map(|node| node.performance().latency_opt)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn route_cost(&self, node_seq: &[&PublicKey]) -> u64 {
+        let originator_key = node_seq.first().copied();
+        let exit_key = node_seq.last().copied();
+        let hop_count = node_seq.len().saturating_sub(1) as u64;
+        node_seq
+            .iter()
+            .map(
+                |key| match self.calculate_expected_service(key, originator_key, exit_key) {
+                    Ok(ExpectedService::Routing(_, _, rate_pack)) => rate_pack
+                        .routing_byte_rate
+                        .saturating_mul(ROUTE_COST_PAYLOAD_BYTES)
+                        .saturating_add(rate_pack.routing_service_rate),
+                    Ok(ExpectedService::Exit(_, _, rate_pack)) => rate_pack
+                        .exit_byte_rate
+                        .saturating_mul(ROUTE_COST_PAYLOAD_BYTES)
+                        .saturating_add(rate_pack.exit_service_rate),
+                    _ => 0,
+                },
+            )
+            .fold(
+                hop_count.saturating_mul(ROUTE_HOP_PENALTY),
+                |total, charge| total.saturating_add(charge),
+            )
+    }
+
     fn sort_routes_by_desirable_exit_nodes(&self, node_seqs: &mut Vec<Vec<&PublicKey>>) {
         if node_seqs.is_empty() {
             panic!("Unable to sort routes by desirable exit nodes: Missing routes.");
@@ -970,10 +1456,18 @@ impl Neighborhood {
         &self,
         last_node_ref: &NodeRecord,
         target_key_ref_opt: Option<&PublicKey>,
+        exit_policy: &ExitPolicy,
     ) -> bool {
         match target_key_ref_opt {
             Some(target_key_ref) => last_node_ref.public_key() == target_key_ref,
-            None => true,
+            None => exit_policy.permits(
+                last_node_ref
+                    .node_addr_opt()
+                    .map(|node_addr| node_addr.ip_addr()),
+                self.geoip_database_opt
+                    .as_ref()
+                    .map(|geoip_database| geoip_database.as_ref() as &dyn CountryFinder),
+            ),
         }
     }
 
@@ -1024,6 +1518,7 @@ impl Neighborhood {
         target_opt: Option<&'a PublicKey>,
         hops_remaining: usize,
         direction: RouteDirection,
+        exit_policy: &ExitPolicy,
     ) -> Vec<Vec<&'a PublicKey>> {
         let first_node_key = prefix.first().expect("Empty prefix");
         let previous_node = self
@@ -1032,7 +1527,7 @@ impl Neighborhood {
             .expect("Last Node magically disappeared");
         // Check to see if we're done. If we are, all three of these qualifications will pass.
         if self.route_length_qualifies(hops_remaining)
-            && self.last_key_qualifies(previous_node, target_opt)
+            && self.last_key_qualifies(previous_node, target_opt, exit_policy)
             && self.validate_last_node_not_too_close_to_first_node(
                 prefix.len(),
                 *first_node_key,
@@ -1068,6 +1563,7 @@ impl Neighborhood {
                         target_opt,
                         new_hops_remaining,
                         direction,
+                        exit_policy,
                     )
                 })
                 .collect()
@@ -1116,6 +1612,109 @@ impl Neighborhood {
         // Maybe something here eventually for keeping statistics
     }
 
+    fn ban_malefactor(
+        &mut self,
+        agrs: &[AccessibleGossipRecord],
+        gossip_source: SocketAddr,
+        reason: String,
+    ) {
+        let public_key_opt = self.gossip_source_key(agrs, gossip_source);
+        self.ban_malefactor_key(public_key_opt, gossip_source, reason)
+    }
+
+    fn ban_malefactor_key(
+        &mut self,
+        public_key_opt: Option<PublicKey>,
+        gossip_source: SocketAddr,
+        reason: String,
+    ) {
+        let public_key_opt = public_key_opt.or_else(|| {
+            self.neighborhood_database
+                .node_by_ip(&gossip_source.ip())
+                .map(|node| node.public_key().clone())
+        });
+        if public_key_opt.as_ref() == Some(self.neighborhood_database.root().public_key()) {
+            warning!(
+                self.logger,
+                "Not banning {}: the Gossip from there was blamed on this Node: {}",
+                gossip_source.ip(),
+                reason
+            );
+            return;
+        }
+        let ip_address_opt = self.malefactor_ip(&public_key_opt, gossip_source.ip());
+        if public_key_opt.is_none() && ip_address_opt.is_none() {
+            warning!(
+                self.logger,
+                "Not banning {}: it's this Node's own address: {}",
+                gossip_source.ip(),
+                reason
+            );
+            return;
+        }
+        warning!(
+            self.logger,
+            "Banning malefactor {} at {} for {} hours: {}",
+            match &public_key_opt {
+                Some(public_key) => public_key.to_string(),
+                None => "with unknown public key".to_string(),
+            },
+            match &ip_address_opt {
+                Some(ip_address) => ip_address.to_string(),
+                None => "unknown address".to_string(),
+            },
+            self.malefactor_ban_duration.as_secs() / 3600,
+            reason
+        );
+        let malefactor = Malefactor::new(
+            public_key_opt.clone(),
+            ip_address_opt,
+            reason,
+            self.malefactor_ban_duration,
+        );
+        self.malefactor_dao_opt
+            .as_ref()
+            .expect("MalefactorDao was not set by StartMessage")
+            .ban(&malefactor);
+        if let Some(public_key) = public_key_opt {
+            let was_neighbor = self
+                .neighborhood_database
+                .root_mut()
+                .remove_half_neighbor_key(&public_key);
+            self.neighborhood_database.remove_node(&public_key);
+            if was_neighbor {
+                self.neighborhood_database.root_mut().increment_version();
+                self.gossip_to_neighbors();
+            }
+        }
+    }
+
+    // The address the Gossip came from is banned only if it belongs to the offender: never this
+    // Node's own address, and not one the database knows the offender isn't at
+    fn malefactor_ip(
+        &self,
+        public_key_opt: &Option<PublicKey>,
+        gossip_source_ip: IpAddr,
+    ) -> Option<IpAddr> {
+        let own_ip_opt = self
+            .neighborhood_database
+            .root()
+            .node_addr_opt()
+            .map(|node_addr| node_addr.ip_addr());
+        if gossip_source_ip.is_loopback() || own_ip_opt == Some(gossip_source_ip) {
+            return None;
+        }
+        let known_ip_opt = public_key_opt
+            .as_ref()
+            .and_then(|public_key| self.neighborhood_database.node_by_key(public_key))
+            .and_then(|node| node.node_addr_opt())
+            .map(|node_addr| node_addr.ip_addr());
+        match known_ip_opt {
+            Some(known_ip) if known_ip != gossip_source_ip => None,
+            _ => Some(gossip_source_ip),
+        }
+    }
+
     fn send_no_lookup_package(
         &self,
         message_type: MessageType,
@@ -1146,18 +1745,29 @@ impl Neighborhood {
         accessible_gossip: &[AccessibleGossipRecord],
         gossip_source: SocketAddr,
     ) -> String {
-        match accessible_gossip.iter().find(|agr| {
-            if let Some(ref node_addr) = agr.node_addr_opt {
-                node_addr.ip_addr() == gossip_source.ip()
-            } else {
-                false
-            }
-        }) {
-            Some(agr) => format!("{}", agr.inner.public_key),
+        match self.gossip_source_key(accessible_gossip, gossip_source) {
+            Some(public_key) => format!("{}", public_key),
             None => format!("{}", gossip_source),
         }
     }
 
+    fn gossip_source_key(
+        &self,
+        accessible_gossip: &[AccessibleGossipRecord],
+        gossip_source: SocketAddr,
+    ) -> Option<PublicKey> {
+        accessible_gossip
+            .iter()
+            .find(|agr| {
+                if let Some(ref node_addr) = agr.node_addr_opt {
+                    node_addr.ip_addr() == gossip_source.ip()
+                } else {
+                    false
+                }
+            })
+            .map(|agr| agr.inner.public_key.clone())
+    }
+
     fn handle_stream_shutdown_msg(&mut self, msg: StreamShutdownMsg) {
         if msg.stream_type != RemovedStreamType::Clandestine {
             panic!("Neighborhood should never get ShutdownStreamMsg about non-clandestine stream")
@@ -1205,6 +1815,97 @@ impl Neighborhood {
         );
     }
 
+    fn handle_node_performance_request(&self, client_id: u64, context_id: u64) {
+        let root_key = self.neighborhood_database.root().public_key();
+        let nodes = self
+            .neighborhood_database
+            .keys()
+            .into_iter()
+            .filter(|key| *key != root_key)
+            .sorted()
+            .flat_map(|key| self.neighborhood_database.node_by_key(key))
+            .map(|node| {
+                let performance = node.performance();
+                UiNodePerformance {
+                    public_key: node.public_key().to_string(),
+                    desirable: node.is_desirable(),
+                    successes: performance.successes,
+                    failures: performance.failures,
+                    exits: performance.exits,
+                    dns_failures: performance.dns_failures,
+                    latency_ms_opt: performance
+                        .latency_opt
+                        .map(|latency| latency.as_millis() as u64),
+                }
+            })
+            .collect_vec();
+        self.send_to_ui(
+            client_id,
+            UiNodePerformanceResponse { nodes }.tmb(context_id),
+        );
+    }
+
+    fn handle_neighborhood_request(
+        &self,
+        client_id: u64,
+        context_id: u64,
+        msg: UiNeighborhoodRequest,
+    ) {
+        let db = &self.neighborhood_database;
+        let hops_from_root = db.hops_from_root();
+        let nodes = db
+            .keys()
+            .into_iter()
+            .sorted()
+            .flat_map(|key| db.node_by_key(key))
+            .map(|node| {
+                let rate_pack = node.rate_pack();
+                UiNeighborhoodNode {
+                    public_key: node.public_key().to_string(),
+                    node_addr_opt: node.node_addr_opt().map(|node_addr| node_addr.to_string()),
+                    version: node.version(),
+                    rate_pack: UiRatePack {
+                        routing_byte_rate: rate_pack.routing_byte_rate,
+                        routing_service_rate: rate_pack.routing_service_rate,
+                        exit_byte_rate: rate_pack.exit_byte_rate,
+                        exit_service_rate: rate_pack.exit_service_rate,
+                    },
+                    accepts_connections: node.accepts_connections(),
+                    routes_data: node.routes_data(),
+                    desirable: node.is_desirable(),
+                    neighbors: node
+                        .half_neighbor_keys()
+                        .into_iter()
+                        .sorted()
+                        .map(|key| key.to_string())
+                        .collect(),
+                    hops_away_opt: hops_from_root.get(node.public_key()).copied(),
+                }
+            })
+            .collect_vec();
+        let response = UiNeighborhoodResponse {
+            our_public_key: db.root().public_key().to_string(),
+            nodes,
+            dot_graph_opt: if msg.dot_graph {
+                Some(db.to_dot_graph())
+            } else {
+                None
+            },
+        };
+        self.send_to_ui(client_id, response.tmb(context_id));
+    }
+
+    fn send_to_ui(&self, client_id: u64, body: MessageBody) {
+        self.node_to_ui_sub
+            .as_ref()
+            .expect("UiGateway is unbound")
+            .try_send(NodeToUiMessage {
+                target: MessageTarget::ClientId(client_id),
+                body,
+            })
+            .expect("UiGateway is dead");
+    }
+
     fn handle_new_password(&mut self, new_password: String) {
         self.db_password_opt = Some(new_password);
     }
@@ -1230,7 +1931,7 @@ mod tests {
     use crate::db_config::persistent_configuration::PersistentConfigError;
     use crate::neighborhood::gossip::GossipBuilder;
     use crate::neighborhood::gossip::Gossip_0v1;
-    use crate::neighborhood::node_record::NodeRecordInner_0v1;
+    use crate::neighborhood::node_record::{NodeRecordInner_0v1, UNRELIABLE_NODE_MIN_FAILURES};
     use crate::stream_messages::{NonClandestineAttributes, RemovedStreamType};
     use crate::sub_lib::cryptde::{decodex, encodex, CryptData};
     use crate::sub_lib::cryptde_null::CryptDENull;
@@ -1250,7 +1951,6 @@ mod tests {
         neighborhood_from_nodes,
     };
     use crate::test_utils::persistent_configuration_mock::PersistentConfigurationMock;
-    use crate::test_utils::rate_pack;
     use crate::test_utils::recorder::make_recorder;
     use crate::test_utils::recorder::peer_actors_builder;
     use crate::test_utils::recorder::Recorder;
@@ -1258,6 +1958,9 @@ mod tests {
     use crate::test_utils::vec_to_set;
     use crate::test_utils::{assert_contains, make_wallet};
     use crate::test_utils::{main_cryptde, make_paying_wallet};
+    use crate::test_utils::{
+        rate_pack, rate_pack_exit, rate_pack_exit_byte, rate_pack_routing, rate_pack_routing_byte,
+    };
     use actix::dev::{MessageResponse, ResponseChannel};
     use actix::Message;
     use actix::Recipient;
@@ -2095,11 +2798,348 @@ mod tests {
         assert_eq!(expected_response, result);
     }
 
-    #[test]
-    fn sort_routes_by_desirable_exit_nodes() {
+    fn make_two_route_subject(
+        routing_strategy: RoutingStrategy,
+    ) -> (Neighborhood, Vec<NodeRecord>, Vec<NodeRecord>) {
         let mut subject = make_standard_subject();
+        subject.routing_strategy = routing_strategy;
+        let p = subject.neighborhood_database.root().clone();
+        let mut cheap_router = make_node_record(1000, false);
+        cheap_router.set_desirable(false);
+        let cheap_exit = make_node_record(2000, false);
+        let expensive_router = make_node_record(8000, false);
+        let expensive_exit = make_node_record(9000, false);
+        {
+            let db = &mut subject.neighborhood_database;
+            db.add_node(cheap_router.clone()).unwrap();
+            db.add_node(cheap_exit.clone()).unwrap();
+            db.add_node(expensive_router.clone()).unwrap();
+            db.add_node(expensive_exit.clone()).unwrap();
+            let mut dual_edge = |a: &NodeRecord, b: &NodeRecord| {
+                db.add_arbitrary_full_neighbor(a.public_key(), b.public_key());
+            };
+            dual_edge(&p, &cheap_router);
+            dual_edge(&cheap_router, &cheap_exit);
+            dual_edge(&p, &expensive_router);
+            dual_edge(&expensive_router, &expensive_exit);
+        }
+        (
+            subject,
+            vec![p.clone(), cheap_router, cheap_exit],
+            vec![p, expensive_router, expensive_exit],
+        )
+    }
 
-        let us = subject.neighborhood_database.root().clone();
+    fn keys_of(nodes: &[NodeRecord]) -> Vec<PublicKey> {
+        nodes.iter().map(|node| node.public_key().clone()).collect()
+    }
+
+    #[test]
+    fn make_route_segment_chooses_cheapest_route_by_default() {
+        let (subject, cheap_route, _) = make_two_route_subject(RoutingStrategy::default());
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &ExitPolicy::default(),
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&cheap_route));
+    }
+
+    #[test]
+    fn make_route_segment_chooses_cheapest_route_without_unreliable_nodes_by_default() {
+        let (mut subject, cheap_route, expensive_route) =
+            make_two_route_subject(RoutingStrategy::default());
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let performance = subject
+            .neighborhood_database
+            .node_by_key_mut(cheap_route[1].public_key())
+            .unwrap()
+            .performance_mut();
+        (0..UNRELIABLE_NODE_MIN_FAILURES).for_each(|_| performance.record_failure(false));
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &ExitPolicy::default(),
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&expensive_route));
+    }
+
+    #[test]
+    fn make_route_segment_chooses_most_reliable_route_when_so_configured() {
+        let (subject, _, expensive_route) = make_two_route_subject(RoutingStrategy::Reliable);
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &ExitPolicy::default(),
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&expensive_route));
+    }
+
+    #[test]
+    fn make_route_segment_avoids_routes_that_have_been_failing_when_reliability_is_configured() {
+        let (mut subject, cheap_route, expensive_route) =
+            make_two_route_subject(RoutingStrategy::Reliable);
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let db = &mut subject.neighborhood_database;
+        db.node_by_key_mut(cheap_route[1].public_key())
+            .unwrap()
+            .set_desirable(true);
+        let expensive_exit = db.node_by_key_mut(expensive_route[2].public_key()).unwrap();
+        expensive_exit
+            .performance_mut()
+            .record_success(Duration::from_millis(100), true);
+        expensive_exit.performance_mut().record_failure(true);
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &ExitPolicy::default(),
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&cheap_route));
+    }
+
+    #[test]
+    fn make_route_segment_prefers_faster_routes_when_reliability_is_equal() {
+        let (mut subject, cheap_route, expensive_route) =
+            make_two_route_subject(RoutingStrategy::Reliable);
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let db = &mut subject.neighborhood_database;
+        db.node_by_key_mut(cheap_route[1].public_key())
+            .unwrap()
+            .set_desirable(true);
+        db.node_by_key_mut(cheap_route[2].public_key())
+            .unwrap()
+            .performance_mut()
+            .record_success(Duration::from_millis(900), true);
+        db.node_by_key_mut(expensive_route[2].public_key())
+            .unwrap()
+            .performance_mut()
+            .record_success(Duration::from_millis(100), true);
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &ExitPolicy::default(),
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&expensive_route));
+    }
+
+    fn make_two_route_subject_with_exit_ips() -> (Neighborhood, Vec<NodeRecord>, Vec<NodeRecord>) {
+        let (mut subject, cheap_route, expensive_route) =
+            make_two_route_subject(RoutingStrategy::default());
+        for (exit, n) in &[(&cheap_route[2], 2000), (&expensive_route[2], 9000)] {
+            let node_addr = make_node_record(*n, true).node_addr_opt().unwrap();
+            subject
+                .neighborhood_database
+                .node_by_key_mut(exit.public_key())
+                .unwrap()
+                .force_node_addr(&node_addr);
+        }
+        (subject, cheap_route, expensive_route)
+    }
+
+    #[test]
+    fn make_route_segment_avoids_exits_excluded_by_ip_range() {
+        let (subject, _, expensive_route) = make_two_route_subject_with_exit_ips();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let exit_policy = ExitPolicy {
+            required: vec![],
+            excluded: ExitPolicy::parse_rules("2.0.0.0/8").unwrap(),
+        };
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &exit_policy,
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&expensive_route));
+    }
+
+    #[test]
+    fn make_route_segment_uses_geoip_database_for_required_countries() {
+        let (mut subject, _, expensive_route) = make_two_route_subject_with_exit_ips();
+        subject.geoip_database_opt = Some(Arc::new(
+            GeoIpDatabase::parse("2.0.0.0,2.255.255.255,CN\n9.0.0.0,9.255.255.255,DE").unwrap(),
+        ));
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let exit_policy = ExitPolicy {
+            required: ExitPolicy::parse_rules("de").unwrap(),
+            excluded: vec![],
+        };
+
+        let result = subject
+            .make_route_segment(
+                &root_key,
+                None,
+                2,
+                Component::ProxyClient,
+                RouteDirection::Over,
+                &exit_policy,
+            )
+            .unwrap();
+
+        assert_eq!(result.keys, keys_of(&expensive_route));
+    }
+
+    #[test]
+    fn make_route_segment_complains_when_exit_policy_rules_out_every_exit() {
+        let (subject, _, _) = make_two_route_subject_with_exit_ips();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let exit_policy = ExitPolicy {
+            required: ExitPolicy::parse_rules("10.0.0.0/8").unwrap(),
+            excluded: vec![],
+        };
+
+        let result = subject.make_route_segment(
+            &root_key,
+            None,
+            2,
+            Component::ProxyClient,
+            RouteDirection::Over,
+            &exit_policy,
+        );
+
+        assert_eq!(
+            result,
+            Err(format!(
+                "Couldn't find any routes permitted by the exit policy: at least 2-hop from {} to ProxyClient at Unknown",
+                root_key
+            ))
+        );
+    }
+
+    #[test]
+    fn route_query_exit_policy_overrides_configured_exit_policy() {
+        let (mut subject, cheap_route, _) = make_two_route_subject_with_exit_ips();
+        subject.exit_policy = ExitPolicy {
+            required: vec![],
+            excluded: ExitPolicy::parse_rules("2.0.0.0/8").unwrap(),
+        };
+        let mut msg = RouteQueryMessage::data_indefinite_route_request(2);
+        msg.exit_policy_opt = Some(ExitPolicy {
+            required: vec![],
+            excluded: ExitPolicy::parse_rules("9.0.0.0/8").unwrap(),
+        });
+
+        let result = subject.make_round_trip_route(msg).unwrap();
+
+        let exit_service_opt = match result.expected_services {
+            ExpectedServices::RoundTrip(over, _, _) => over.last().cloned(),
+            x => panic!("Expected RoundTrip, got {:?}", x),
+        };
+        match exit_service_opt {
+            Some(ExpectedService::Exit(key, _, _)) => assert_eq!(&key, cheap_route[2].public_key()),
+            x => panic!("Expected Exit, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn make_route_segment_chooses_among_acceptable_routes_at_random_when_so_configured() {
+        let (mut subject, cheap_route, expensive_route) =
+            make_two_route_subject(RoutingStrategy::Random);
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let p = subject.neighborhood_database.root().clone();
+        let mut undesirable_exit = make_node_record(3000, false);
+        undesirable_exit.set_desirable(false);
+        let expensive_router_key = expensive_route[1].public_key().clone();
+        subject
+            .neighborhood_database
+            .add_node(undesirable_exit.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&expensive_router_key, undesirable_exit.public_key());
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(undesirable_exit.public_key(), &expensive_router_key);
+        let undesirable_route = vec![
+            p.public_key().clone(),
+            expensive_router_key,
+            undesirable_exit.public_key().clone(),
+        ];
+
+        let results = (0..100)
+            .map(|_| {
+                subject
+                    .make_route_segment(
+                        &root_key,
+                        None,
+                        2,
+                        Component::ProxyClient,
+                        RouteDirection::Over,
+                        &ExitPolicy::default(),
+                    )
+                    .unwrap()
+                    .keys
+            })
+            .collect::<Vec<Vec<PublicKey>>>();
+
+        assert!(results.contains(&keys_of(&cheap_route)));
+        assert!(results.contains(&keys_of(&expensive_route)));
+        assert!(!results.contains(&undesirable_route));
+    }
+
+    #[test]
+    fn route_cost_totals_expected_charges_and_hop_penalty() {
+        let (subject, cheap_route, _) = make_two_route_subject(RoutingStrategy::Cheapest);
+        let keys = keys_of(&cheap_route);
+        let key_refs = keys.iter().collect::<Vec<&PublicKey>>();
+
+        let result = subject.route_cost(&key_refs);
+
+        let routing_charge =
+            rate_pack_routing_byte(1000) * ROUTE_COST_PAYLOAD_BYTES + rate_pack_routing(1000);
+        let exit_charge =
+            rate_pack_exit_byte(2000) * ROUTE_COST_PAYLOAD_BYTES + rate_pack_exit(2000);
+        assert_eq!(result, routing_charge + exit_charge + 2 * ROUTE_HOP_PENALTY);
+    }
+
+    #[test]
+    fn sort_routes_by_desirable_exit_nodes() {
+        let mut subject = make_standard_subject();
+
+        let us = subject.neighborhood_database.root().clone();
         let routing_node = make_node_record(0000, true);
         let desirable_node = make_node_record(1111, false);
         let mut undesirable_node = make_node_record(2222, false);
@@ -2385,26 +3425,50 @@ mod tests {
         };
 
         // At least two hops from p to anywhere standard
-        let routes = subject.complete_routes(vec![p], None, 2, RouteDirection::Over);
+        let routes = subject.complete_routes(
+            vec![p],
+            None,
+            2,
+            RouteDirection::Over,
+            &ExitPolicy::default(),
+        );
 
         assert_eq!(routes, vec![vec![p, s, t]]);
         // no [p, r, s] or [p, s, r] because s and r are both neighbors of p and can't exit for it
 
         // At least two hops over from p to t
-        let routes = subject.complete_routes(vec![p], Some(t), 2, RouteDirection::Over);
+        let routes = subject.complete_routes(
+            vec![p],
+            Some(t),
+            2,
+            RouteDirection::Over,
+            &ExitPolicy::default(),
+        );
 
         contains(&routes, vec![p, s, t]);
         contains(&routes, vec![p, r, s, t]);
         assert_eq!(2, routes.len());
 
         // At least two hops over from t to p
-        let routes = subject.complete_routes(vec![t], Some(p), 2, RouteDirection::Over);
+        let routes = subject.complete_routes(
+            vec![t],
+            Some(p),
+            2,
+            RouteDirection::Over,
+            &ExitPolicy::default(),
+        );
 
         assert_eq!(routes, Vec::<Vec<&PublicKey>>::new());
         // p is consume-only; can't be an exit Node.
 
         // At least two hops back from t to p
-        let routes = subject.complete_routes(vec![t], Some(p), 2, RouteDirection::Back);
+        let routes = subject.complete_routes(
+            vec![t],
+            Some(p),
+            2,
+            RouteDirection::Back,
+            &ExitPolicy::default(),
+        );
 
         contains(&routes, vec![t, s, p]);
         contains(&routes, vec![t, s, r, p]);
@@ -2412,7 +3476,13 @@ mod tests {
         // p is consume-only, but it's the originating Node, so including it is okay
 
         // At least two hops from p to Q - impossible
-        let routes = subject.complete_routes(vec![p], Some(q), 2, RouteDirection::Over);
+        let routes = subject.complete_routes(
+            vec![p],
+            Some(q),
+            2,
+            RouteDirection::Over,
+            &ExitPolicy::default(),
+        );
 
         assert_eq!(routes, Vec::<Vec<&PublicKey>>::new());
     }
@@ -2438,7 +3508,13 @@ mod tests {
         db.add_arbitrary_full_neighbor(q, r);
 
         // At least two hops from P to anywhere standard
-        let routes = subject.complete_routes(vec![p], None, 2, RouteDirection::Over);
+        let routes = subject.complete_routes(
+            vec![p],
+            None,
+            2,
+            RouteDirection::Over,
+            &ExitPolicy::default(),
+        );
 
         let expected: Vec<Vec<&PublicKey>> = vec![];
         assert_eq!(routes, expected);
@@ -3263,96 +4339,585 @@ mod tests {
     }
 
     #[test]
-    fn neighborhood_complains_about_inability_to_ban_when_gossip_acceptor_requests_it() {
+    fn neighborhood_bans_malefactor_when_gossip_acceptor_requests_it() {
         init_test_logging();
-        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
-        let neighbor = make_node_record(1111, true);
-        let mut subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+        let mut subject = make_standard_subject();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
+        ));
+        subject.malefactor_ban_duration = Duration::from_secs(48 * 3600);
+        let set_past_neighbors_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.persistent_config_opt = Some(Box::new(
+            PersistentConfigurationMock::new()
+                .set_past_neighbors_params(&set_past_neighbors_params_arc)
+                .set_past_neighbors_result(Ok(())),
+        ));
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let malefactor = make_node_record(2345, true);
+        let bystander = make_node_record(3456, true);
+        {
+            let db = &mut subject.neighborhood_database;
+            db.add_node(malefactor.clone()).unwrap();
+            db.add_node(bystander.clone()).unwrap();
+            db.add_arbitrary_full_neighbor(&root_key, malefactor.public_key());
+            db.add_arbitrary_full_neighbor(&root_key, bystander.public_key());
+        }
+        let gossip = GossipBuilder::new(&subject.neighborhood_database)
+            .node(malefactor.public_key(), true)
+            .build();
         let gossip_acceptor = GossipAcceptorMock::new()
             .handle_result(GossipAcceptanceResult::Ban("Bad guy".to_string()));
         subject.gossip_acceptor = Box::new(gossip_acceptor);
-        let subject_node = subject.neighborhood_database.root().clone();
         let (hopper, _, hopper_recording_arc) = make_recorder();
         let peer_actors = peer_actors_builder().hopper(hopper).build();
-        let system = System::new("");
+        let system = System::new("neighborhood_bans_malefactor_when_gossip_acceptor_requests_it");
         subject.hopper = Some(peer_actors.hopper.from_hopper_client);
+        let gossip_source: SocketAddr = malefactor.node_addr_opt().unwrap().into();
 
-        subject.handle_gossip(
-            Gossip_0v1::new(vec![]),
-            subject_node.node_addr_opt().unwrap().into(),
-        );
+        subject.handle_gossip(gossip, gossip_source);
 
         System::current().stop();
         system.run();
+        let ban_params = ban_params_arc.lock().unwrap();
+        let banned = &ban_params[0];
+        assert_eq!(banned.public_key_opt, Some(malefactor.public_key().clone()));
+        assert_eq!(banned.ip_address_opt, Some(gossip_source.ip()));
+        assert_eq!(banned.reason, "Bad guy".to_string());
+        assert_eq!(
+            banned.expires_at,
+            banned.banned_at + Duration::from_secs(48 * 3600)
+        );
+        assert_eq!(ban_params.len(), 1);
+        let db = &subject.neighborhood_database;
+        assert!(db.node_by_key(malefactor.public_key()).is_none());
+        assert!(db.node_by_ip(&gossip_source.ip()).is_none());
+        assert!(!db.root().has_half_neighbor(malefactor.public_key()));
+        assert!(db.root().has_half_neighbor(bystander.public_key()));
+        let set_past_neighbors_params = set_past_neighbors_params_arc.lock().unwrap();
+        let (past_neighbors_opt, _) = &set_past_neighbors_params[0];
+        assert_eq!(
+            past_neighbors_opt
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|nd| nd.encryption_public_key.clone())
+                .collect::<Vec<PublicKey>>(),
+            vec![bystander.public_key().clone()]
+        );
         let hopper_recording = hopper_recording_arc.lock().unwrap();
-        assert_eq!(0, hopper_recording.len());
-        let tlh = TestLogHandler::new();
-        tlh.exists_log_containing("WARN: Neighborhood: Malefactor detected at 5.5.5.5:5555, but malefactor bans not yet implemented; ignoring: Bad guy");
+        assert_eq!(hopper_recording.len(), 1);
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: Neighborhood: Banning malefactor {} at {} for 48 hours: Bad guy",
+            malefactor.public_key(),
+            gossip_source.ip()
+        ));
     }
 
     #[test]
-    fn neighborhood_does_not_accept_gossip_if_a_record_is_non_deserializable() {
-        init_test_logging();
+    fn neighborhood_does_not_ban_itself_when_gossip_acceptor_blames_its_address() {
         let mut subject = make_standard_subject();
-        let gossip_acceptor = GossipAcceptorMock::new();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
+        ));
+        let gossip_acceptor = GossipAcceptorMock::new()
+            .handle_result(GossipAcceptanceResult::Ban("Bad guy".to_string()));
         subject.gossip_acceptor = Box::new(gossip_acceptor);
-        let db = &mut subject.neighborhood_database;
-        let one_node_key = &db.add_node(make_node_record(2222, true)).unwrap();
-        let another_node_key = &db.add_node(make_node_record(3333, true)).unwrap();
-        let mut gossip = GossipBuilder::new(db)
-            .node(one_node_key, true)
-            .node(another_node_key, false)
-            .build();
-        gossip.node_records[1].signed_data = PlainData::new(&[1, 2, 3, 4]); // corrupt second record
-        let gossip_source = SocketAddr::from_str("1.2.3.4:1234").unwrap();
+        let subject_node = subject.neighborhood_database.root().clone();
 
-        subject.handle_gossip(gossip, gossip_source);
+        subject.handle_gossip(
+            Gossip_0v1::new(vec![]),
+            subject_node.node_addr_opt().unwrap().into(),
+        );
 
-        // No panic means that subject didn't try to invoke the GossipAcceptorMock: test passes!
-        TestLogHandler::new().exists_log_containing(&format!(
-            "ERROR: Neighborhood: Received non-deserializable Gossip from {}",
-            gossip_source
-        ));
+        let ban_params = ban_params_arc.lock().unwrap();
+        assert!(ban_params.is_empty());
+        assert!(subject
+            .neighborhood_database
+            .node_by_key(subject_node.public_key())
+            .is_some());
     }
 
     #[test]
-    fn neighborhood_does_not_accept_gossip_if_a_record_signature_is_invalid() {
+    fn neighborhood_does_not_ban_its_own_addresses() {
         init_test_logging();
         let mut subject = make_standard_subject();
-        let gossip_acceptor = GossipAcceptorMock::new();
-        subject.gossip_acceptor = Box::new(gossip_acceptor);
-        let db = &mut subject.neighborhood_database;
-        let one_node_key = &db.add_node(make_node_record(2222, true)).unwrap();
-        let another_node_key = &db.add_node(make_node_record(3333, true)).unwrap();
-        let mut gossip = GossipBuilder::new(db)
-            .node(one_node_key, true)
-            .node(another_node_key, false)
-            .build();
-        gossip.node_records[1].signature = CryptData::new(&[1, 2, 3, 4]); // corrupt second record
-        let gossip_source = SocketAddr::from_str("1.2.3.4:1234").unwrap();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
+        ));
+        let own_ip = subject
+            .neighborhood_database
+            .root()
+            .node_addr_opt()
+            .unwrap()
+            .ip_addr();
+        let stranger_key = PublicKey::new(b"stranger");
 
-        subject.handle_gossip(gossip, gossip_source);
+        subject.ban_malefactor_key(
+            None,
+            SocketAddr::from_str("127.0.0.1:1234").unwrap(),
+            "Bad guy".to_string(),
+        );
+        subject.ban_malefactor_key(
+            Some(stranger_key.clone()),
+            SocketAddr::new(own_ip, 1234),
+            "Bad guy".to_string(),
+        );
 
-        // No panic means that subject didn't try to invoke the GossipAcceptorMock: test passes!
-        TestLogHandler::new().exists_log_containing(&format!(
-            "ERROR: Neighborhood: Received Gossip with invalid signature from {}",
-            gossip_source
+        let ban_params = ban_params_arc.lock().unwrap();
+        assert_eq!(ban_params.len(), 1);
+        assert_eq!(ban_params[0].public_key_opt, Some(stranger_key));
+        assert_eq!(ban_params[0].ip_address_opt, None);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: Neighborhood: Not banning 127.0.0.1: it's this Node's own address: Bad guy",
+        );
+    }
+
+    #[test]
+    fn neighborhood_bans_only_the_key_when_the_address_is_not_the_malefactors() {
+        let mut subject = make_standard_subject();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
         ));
+        let malefactor = make_node_record(2345, true);
+        subject
+            .neighborhood_database
+            .add_node(malefactor.clone())
+            .unwrap();
+        let elsewhere = SocketAddr::from_str("1.2.3.4:1234").unwrap();
+
+        subject.ban_malefactor_key(
+            Some(malefactor.public_key().clone()),
+            elsewhere,
+            "Bad guy".to_string(),
+        );
+
+        let ban_params = ban_params_arc.lock().unwrap();
+        assert_eq!(
+            ban_params[0].public_key_opt,
+            Some(malefactor.public_key().clone())
+        );
+        assert_eq!(ban_params[0].ip_address_opt, None);
+        assert!(subject
+            .neighborhood_database
+            .node_by_key(malefactor.public_key())
+            .is_none());
     }
 
     #[test]
-    fn neighborhood_logs_received_gossip_in_dot_graph_format() {
+    fn neighborhood_loads_malefactor_bans_into_cache_on_start() {
         init_test_logging();
-        let cryptde = main_cryptde();
-        let this_node = NodeRecord::new_for_tests(
-            &cryptde.public_key(),
-            Some(&NodeAddr::new(
-                &IpAddr::from_str("5.4.3.2").unwrap(),
-                &[1234],
-            )),
-            100,
-            true,
-            true,
+        let banned_key = PublicKey::new(b"loaded_malefactor");
+        let banned_ip = IpAddr::from_str("6.7.8.9").unwrap();
+        let mut subject = make_standard_subject();
+        subject.malefactor_dao_opt =
+            Some(Box::new(MalefactorDaoMock::new().active_bans_result(vec![
+                Malefactor::new(
+                    Some(banned_key.clone()),
+                    Some(banned_ip),
+                    "Forged Gossip".to_string(),
+                    Duration::from_secs(3600),
+                ),
+            ])));
+
+        subject.load_malefactor_bans();
+
+        assert!(MALEFACTOR_CACHE.is_banned_key(&banned_key));
+        assert!(MALEFACTOR_CACHE.is_banned_ip(&banned_ip));
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Loaded 1 active malefactor bans from the database",
+        );
+    }
+
+    #[test]
+    fn neighborhood_moves_and_re_debuts_to_neighbors_when_public_ip_changes() {
+        init_test_logging();
+        let mut subject = make_standard_subject();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let old_version = subject.neighborhood_database.root().version();
+        let neighbor = make_node_record(2345, true);
+        subject
+            .neighborhood_database
+            .add_node(neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, neighbor.public_key());
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system =
+            System::new("neighborhood_moves_and_re_debuts_to_neighbors_when_public_ip_changes");
+        subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
+        let new_ip = IpAddr::from_str("5.9.9.9").unwrap();
+        let new_node_addr = NodeAddr::new(&new_ip, &[9999]);
+
+        subject.handle_new_public_ip(new_ip);
+
+        System::current().stop();
+        system.run();
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.node_addr_opt(), Some(new_node_addr.clone()));
+        assert_eq!(root.version(), old_version + 1);
+        assert_eq!(
+            subject
+                .neighborhood_database
+                .node_by_ip(&new_ip)
+                .unwrap()
+                .public_key(),
+            &root_key
+        );
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 1);
+        let package = hopper_recording.get_record::<NoLookupIncipientCoresPackage>(0);
+        assert_eq!(&package.public_key, neighbor.public_key());
+        assert_eq!(package.node_addr, neighbor.node_addr_opt().unwrap());
+        let gossip = match decodex::<MessageType>(
+            &CryptDENull::from(neighbor.public_key(), DEFAULT_CHAIN_ID),
+            &package.payload,
+        ) {
+            Ok(MessageType::Gossip(vd)) => Gossip_0v1::try_from(vd).unwrap(),
+            x => panic!("Wanted Gossip, found {:?}", x),
+        };
+        let agrs: Vec<AccessibleGossipRecord> = gossip.try_into().unwrap();
+        assert_eq!(agrs.len(), 1);
+        assert_eq!(agrs[0].inner.public_key, root_key);
+        assert_eq!(agrs[0].inner.version, old_version + 1);
+        assert_eq!(agrs[0].node_addr_opt, Some(new_node_addr));
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Public IP address changed from 9.9.9.9 to 5.9.9.9; re-debuting",
+        );
+    }
+
+    #[test]
+    fn neighborhood_re_debuts_to_initial_neighbors_when_public_ip_changes_before_it_has_neighbors()
+    {
+        let mut subject = make_standard_subject();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system = System::new("neighborhood_re_debuts_to_initial_neighbors_when_public_ip_changes_before_it_has_neighbors");
+        subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
+
+        subject.handle_new_public_ip(IpAddr::from_str("5.9.9.9").unwrap());
+
+        System::current().stop();
+        system.run();
+        let initial_neighbor = make_node_record(9998, true);
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 1);
+        let package = hopper_recording.get_record::<NoLookupIncipientCoresPackage>(0);
+        assert_eq!(&package.public_key, initial_neighbor.public_key());
+        assert_eq!(package.node_addr, initial_neighbor.node_addr_opt().unwrap());
+    }
+
+    #[test]
+    fn neighborhood_ignores_public_ip_change_to_the_same_address() {
+        let mut subject = make_standard_subject();
+        let root_before = subject.neighborhood_database.root().clone();
+
+        subject.handle_new_public_ip(IpAddr::from_str("9.9.9.9").unwrap());
+
+        assert_eq!(subject.neighborhood_database.root(), &root_before);
+    }
+
+    #[test]
+    fn neighborhood_monitors_public_ip_when_it_matches_local_ip() {
+        init_test_logging();
+        let detect_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut subject = make_standard_subject();
+        subject.public_ip_detector = Box::new(
+            PublicIpDetectorMock::new()
+                .detect_params(&detect_params_arc)
+                .detect_result(Some(IpAddr::from_str("9.9.9.9").unwrap())),
+        );
+
+        let result = subject.public_ip_monitor();
+
+        assert_eq!(result, PublicIpMonitor::LocalInterface);
+        let detect_params = detect_params_arc.lock().unwrap();
+        assert_eq!(*detect_params, vec![IpAddr::from_str("9.9.9.9").unwrap()]);
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Monitoring public IP address 9.9.9.9 for changes",
+        );
+    }
+
+    #[test]
+    fn neighborhood_relies_on_neighbors_to_monitor_public_ip_from_behind_nat() {
+        init_test_logging();
+        let mut subject = make_standard_subject();
+        subject.public_ip_detector = Box::new(
+            PublicIpDetectorMock::new()
+                .detect_result(Some(IpAddr::from_str("192.168.0.9").unwrap())),
+        );
+
+        let result = subject.public_ip_monitor();
+
+        assert_eq!(result, PublicIpMonitor::Neighbors);
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Local IP address 192.168.0.9 is not public IP address 9.9.9.9; neighbors will report changes to the public IP address",
+        );
+    }
+
+    #[test]
+    fn neighborhood_reports_observed_ip_to_neighbor_whose_gossip_comes_from_unknown_address() {
+        let mut subject = make_standard_subject();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let neighbor = make_node_record(2345, true);
+        subject
+            .neighborhood_database
+            .add_node(neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, neighbor.public_key());
+        subject.gossip_acceptor =
+            Box::new(GossipAcceptorMock::new().handle_result(GossipAcceptanceResult::Ignored));
+        let gossip = GossipBuilder::new(&subject.neighborhood_database)
+            .node(neighbor.public_key(), true)
+            .build();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system = System::new(
+            "neighborhood_reports_observed_ip_to_neighbor_whose_gossip_comes_from_unknown_address",
+        );
+        subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
+        let gossip_source = SocketAddr::from_str("5.4.3.2:4567").unwrap();
+
+        subject.handle_gossip(gossip, gossip_source);
+
+        System::current().stop();
+        system.run();
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 1);
+        let package = hopper_recording.get_record::<NoLookupIncipientCoresPackage>(0);
+        assert_eq!(&package.public_key, neighbor.public_key());
+        assert_eq!(
+            package.node_addr,
+            NodeAddr::new(&gossip_source.ip(), &[gossip_source.port()])
+        );
+        let report = match decodex::<MessageType>(
+            &CryptDENull::from(neighbor.public_key(), DEFAULT_CHAIN_ID),
+            &package.payload,
+        ) {
+            Ok(MessageType::Gossip(vd)) => Gossip_0v1::try_from(vd).unwrap(),
+            x => panic!("Wanted Gossip, found {:?}", x),
+        };
+        assert_eq!(report.observed_ip_opt, Some(gossip_source.ip()));
+        let agrs: Vec<AccessibleGossipRecord> = report.try_into().unwrap();
+        assert_eq!(agrs.len(), 1);
+        assert_eq!(agrs[0].inner.public_key, root_key);
+    }
+
+    #[test]
+    fn neighborhood_does_not_report_observed_ip_for_gossip_from_known_address() {
+        let mut subject = make_standard_subject();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let neighbor = make_node_record(2345, true);
+        subject
+            .neighborhood_database
+            .add_node(neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, neighbor.public_key());
+        subject.gossip_acceptor =
+            Box::new(GossipAcceptorMock::new().handle_result(GossipAcceptanceResult::Ignored));
+        let gossip = GossipBuilder::new(&subject.neighborhood_database)
+            .node(neighbor.public_key(), true)
+            .build();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system =
+            System::new("neighborhood_does_not_report_observed_ip_for_gossip_from_known_address");
+        subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
+
+        subject.handle_gossip(gossip, neighbor.node_addr_opt().unwrap().into());
+
+        System::current().stop();
+        system.run();
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 0);
+    }
+
+    #[test]
+    fn neighborhood_moves_when_enough_neighbors_report_the_same_observed_ip() {
+        init_test_logging();
+        let mut subject = make_standard_subject();
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        let one_neighbor = make_node_record(2345, true);
+        let another_neighbor = make_node_record(3456, true);
+        subject
+            .neighborhood_database
+            .add_node(one_neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_node(another_neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, one_neighbor.public_key());
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, another_neighbor.public_key());
+        let (hopper, _, _) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system =
+            System::new("neighborhood_moves_when_enough_neighbors_report_the_same_observed_ip");
+        subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
+        let observed_ip = IpAddr::from_str("5.9.9.9").unwrap();
+
+        subject.handle_observed_ip(observed_ip, one_neighbor.node_addr_opt().unwrap().into());
+        let node_addr_after_one_report = subject.neighborhood_database.root().node_addr_opt();
+        subject.handle_observed_ip(
+            observed_ip,
+            another_neighbor.node_addr_opt().unwrap().into(),
+        );
+
+        System::current().stop();
+        system.run();
+        assert_eq!(
+            node_addr_after_one_report,
+            Some(NodeAddr::new(
+                &IpAddr::from_str("9.9.9.9").unwrap(),
+                &[9999]
+            ))
+        );
+        assert_eq!(
+            subject.neighborhood_database.root().node_addr_opt(),
+            Some(NodeAddr::new(&observed_ip, &[9999]))
+        );
+        assert!(subject.observed_public_ips.is_empty());
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: 1 of 2 neighbors see this Node at 5.9.9.9; waiting for confirmation",
+        );
+    }
+
+    #[test]
+    fn neighborhood_ignores_observed_ip_reports_from_non_neighbors() {
+        let mut subject = make_standard_subject();
+        let stranger = make_node_record(2345, true);
+        subject
+            .neighborhood_database
+            .add_node(stranger.clone())
+            .unwrap();
+        let root_before = subject.neighborhood_database.root().clone();
+
+        subject.handle_observed_ip(
+            IpAddr::from_str("5.9.9.9").unwrap(),
+            stranger.node_addr_opt().unwrap().into(),
+        );
+
+        assert_eq!(subject.neighborhood_database.root(), &root_before);
+        assert!(subject.observed_public_ips.is_empty());
+    }
+
+    #[test]
+    fn detect_new_public_ip_reports_only_changed_addresses() {
+        let mut subject = make_standard_subject();
+        subject.public_ip_detector = Box::new(
+            PublicIpDetectorMock::new()
+                .detect_result(Some(IpAddr::from_str("9.9.9.9").unwrap()))
+                .detect_result(None)
+                .detect_result(Some(IpAddr::from_str("5.9.9.9").unwrap())),
+        );
+
+        let unchanged = subject.detect_new_public_ip();
+        let undetectable = subject.detect_new_public_ip();
+        let changed = subject.detect_new_public_ip();
+
+        assert_eq!(unchanged, None);
+        assert_eq!(undetectable, None);
+        assert_eq!(changed, Some(IpAddr::from_str("5.9.9.9").unwrap()));
+    }
+
+    #[test]
+    fn neighborhood_does_not_accept_gossip_if_a_record_is_non_deserializable() {
+        init_test_logging();
+        let mut subject = make_standard_subject();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
+        ));
+        let gossip_acceptor = GossipAcceptorMock::new();
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        let db = &mut subject.neighborhood_database;
+        let one_node_key = &db.add_node(make_node_record(2222, true)).unwrap();
+        let another_node_key = &db.add_node(make_node_record(3333, true)).unwrap();
+        let mut gossip = GossipBuilder::new(db)
+            .node(one_node_key, true)
+            .node(another_node_key, false)
+            .build();
+        gossip.node_records[1].signed_data = PlainData::new(&[1, 2, 3, 4]); // corrupt second record
+        let gossip_source = SocketAddr::from_str("1.2.3.4:1234").unwrap();
+
+        subject.handle_gossip(gossip, gossip_source);
+
+        // No panic means that subject didn't try to invoke the GossipAcceptorMock: test passes!
+        TestLogHandler::new().exists_log_containing(&format!(
+            "ERROR: Neighborhood: Received non-deserializable Gossip from {}",
+            gossip_source
+        ));
+        let ban_params = ban_params_arc.lock().unwrap();
+        assert_eq!(ban_params[0].public_key_opt, None);
+        assert_eq!(ban_params[0].ip_address_opt, Some(gossip_source.ip()));
+        assert_eq!(
+            ban_params[0].reason,
+            "Sent non-deserializable Gossip".to_string()
+        );
+    }
+
+    #[test]
+    fn neighborhood_does_not_accept_gossip_if_a_record_signature_is_invalid() {
+        init_test_logging();
+        let mut subject = make_standard_subject();
+        let ban_params_arc = Arc::new(Mutex::new(vec![]));
+        subject.malefactor_dao_opt = Some(Box::new(
+            MalefactorDaoMock::new().ban_params(&ban_params_arc),
+        ));
+        let gossip_acceptor = GossipAcceptorMock::new();
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        let db = &mut subject.neighborhood_database;
+        let one_node_key = &db.add_node(make_node_record(2222, true)).unwrap();
+        let another_node_key = &db.add_node(make_node_record(3333, true)).unwrap();
+        let mut gossip = GossipBuilder::new(db)
+            .node(one_node_key, true)
+            .node(another_node_key, false)
+            .build();
+        gossip.node_records[1].signature = CryptData::new(&[1, 2, 3, 4]); // corrupt second record
+        let gossip_source = SocketAddr::from_str("1.2.3.4:1234").unwrap();
+
+        subject.handle_gossip(gossip, gossip_source);
+
+        // No panic means that subject didn't try to invoke the GossipAcceptorMock: test passes!
+        TestLogHandler::new().exists_log_containing(&format!(
+            "ERROR: Neighborhood: Received Gossip with invalid signature from {}",
+            gossip_source
+        ));
+        let ban_params = ban_params_arc.lock().unwrap();
+        assert_eq!(ban_params[0].public_key_opt, None);
+        assert_eq!(ban_params[0].ip_address_opt, Some(gossip_source.ip()));
+        assert_eq!(
+            ban_params[0].reason,
+            "Sent Gossip with invalid signature".to_string()
+        );
+    }
+
+    #[test]
+    fn neighborhood_logs_received_gossip_in_dot_graph_format() {
+        init_test_logging();
+        let cryptde = main_cryptde();
+        let this_node = NodeRecord::new_for_tests(
+            &cryptde.public_key(),
+            Some(&NodeAddr::new(
+                &IpAddr::from_str("5.4.3.2").unwrap(),
+                &[1234],
+            )),
+            100,
+            true,
+            true,
         );
         let mut db = db_from_node(&this_node);
         let far_neighbor = make_node_record(1324, true);
@@ -3538,6 +5103,7 @@ mod tests {
             target_component: Component::ProxyClient,
             minimum_hop_count: 3,
             return_component_opt: None,
+            exit_policy_opt: None,
         };
         let unsuccessful_three_hop_route = addr.send(three_hop_route_request);
         let public_key_query = addr.send(NodeQueryMessage::PublicKey(a.public_key().clone()));
@@ -3898,6 +5464,7 @@ mod tests {
             target_component: Component::ProxyClient,
             minimum_hop_count,
             return_component_opt: Some(Component::ProxyServer),
+            exit_policy_opt: None,
         });
 
         assert_eq!(
@@ -3943,6 +5510,7 @@ mod tests {
             target_component: Component::ProxyClient,
             minimum_hop_count,
             return_component_opt: Some(Component::ProxyServer),
+            exit_policy_opt: None,
         });
 
         let next_door_neighbor_cryptde =
@@ -4184,6 +5752,203 @@ mod tests {
             .exists_log_containing("INFO: Neighborhood: Received shutdown order from client 1234");
     }
 
+    #[test]
+    fn node_record_metadata_message_records_route_performance() {
+        let (o, r, e, mut subject) = make_o_r_e_subject();
+        let route_keys = vec![r.public_key().clone(), e.public_key().clone()];
+
+        subject.handle_node_record_metadata_message(NodeRecordMetadataMessage::RouteSucceeded(
+            route_keys,
+            Duration::from_millis(400),
+        ));
+        subject.handle_node_record_metadata_message(NodeRecordMetadataMessage::RouteFailed(vec![
+            o.public_key().clone(),
+            r.public_key().clone(),
+            e.public_key().clone(),
+        ]));
+        subject.handle_node_record_metadata_message(NodeRecordMetadataMessage::DnsFailure(
+            e.public_key().clone(),
+        ));
+
+        let db = &subject.neighborhood_database;
+        assert_eq!(db.root().performance(), &NodePerformance::default());
+        assert_eq!(
+            db.node_by_key(r.public_key()).unwrap().performance(),
+            &NodePerformance {
+                successes: 1,
+                failures: 1,
+                exits: 0,
+                dns_failures: 0,
+                latency_opt: Some(Duration::from_millis(400)),
+            }
+        );
+        let e_record = db.node_by_key(e.public_key()).unwrap();
+        assert_eq!(
+            e_record.performance(),
+            &NodePerformance {
+                successes: 1,
+                failures: 1,
+                exits: 3,
+                dns_failures: 1,
+                latency_opt: Some(Duration::from_millis(400)),
+            }
+        );
+        assert!(!e_record.is_desirable());
+    }
+
+    #[test]
+    fn node_performance_request_is_answered_with_statistics_for_every_other_node() {
+        let system = System::new(
+            "node_performance_request_is_answered_with_statistics_for_every_other_node",
+        );
+        let (_, r, e, mut subject) = make_o_r_e_subject();
+        subject
+            .neighborhood_database
+            .node_by_key_mut(e.public_key())
+            .unwrap()
+            .performance_mut()
+            .record_success(Duration::from_millis(250), true);
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let subject_addr = subject.start();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiNodePerformanceRequest {}.tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let response = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(response.target, MessageTarget::ClientId(1234));
+        let (body, context_id) = UiNodePerformanceResponse::fmb(response.body.clone()).unwrap();
+        assert_eq!(context_id, 4321);
+        let ui_node_performance = |node: &NodeRecord, exits, latency_ms_opt| UiNodePerformance {
+            public_key: node.public_key().to_string(),
+            desirable: true,
+            successes: exits,
+            failures: 0,
+            exits,
+            dns_failures: 0,
+            latency_ms_opt,
+        };
+        let mut expected_nodes = vec![
+            ui_node_performance(&r, 0, None),
+            ui_node_performance(&e, 1, Some(250)),
+        ];
+        if e.public_key() < r.public_key() {
+            expected_nodes.reverse();
+        }
+        assert_eq!(body.nodes, expected_nodes);
+    }
+
+    #[test]
+    fn neighborhood_request_is_answered_with_the_whole_neighborhood() {
+        let system = System::new("neighborhood_request_is_answered_with_the_whole_neighborhood");
+        let (o, r, e, subject) = make_o_r_e_subject();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let subject_addr = subject.start();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiNeighborhoodRequest { dot_graph: true }.tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let response = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(response.target, MessageTarget::ClientId(1234));
+        let (body, context_id) = UiNeighborhoodResponse::fmb(response.body.clone()).unwrap();
+        assert_eq!(context_id, 4321);
+        assert_eq!(body.our_public_key, o.public_key().to_string());
+        let mut keys = vec![o.public_key(), r.public_key(), e.public_key()];
+        keys.sort();
+        assert_eq!(
+            body.nodes
+                .iter()
+                .map(|node| node.public_key.clone())
+                .collect::<Vec<String>>(),
+            keys.into_iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<String>>()
+        );
+        let find = |node: &NodeRecord| {
+            body.nodes
+                .iter()
+                .find(|ui_node| ui_node.public_key == node.public_key().to_string())
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(find(&o).hops_away_opt, Some(0));
+        assert_eq!(
+            find(&o).node_addr_opt,
+            Some(o.node_addr_opt().unwrap().to_string())
+        );
+        let mut r_neighbors = vec![o.public_key(), e.public_key()];
+        r_neighbors.sort();
+        assert_eq!(
+            find(&r),
+            UiNeighborhoodNode {
+                public_key: r.public_key().to_string(),
+                node_addr_opt: None,
+                version: r.version(),
+                rate_pack: UiRatePack {
+                    routing_byte_rate: r.rate_pack().routing_byte_rate,
+                    routing_service_rate: r.rate_pack().routing_service_rate,
+                    exit_byte_rate: r.rate_pack().exit_byte_rate,
+                    exit_service_rate: r.rate_pack().exit_service_rate,
+                },
+                accepts_connections: true,
+                routes_data: true,
+                desirable: true,
+                neighbors: r_neighbors.into_iter().map(|key| key.to_string()).collect(),
+                hops_away_opt: Some(1),
+            }
+        );
+        assert_eq!(find(&e).hops_away_opt, Some(2));
+        let dot_graph = body.dot_graph_opt.unwrap();
+        assert!(dot_graph.starts_with("digraph db {"), "{}", dot_graph);
+        assert!(
+            dot_graph.contains(&r.public_key().to_string()),
+            "{}",
+            dot_graph
+        );
+    }
+
+    #[test]
+    fn neighborhood_request_omits_dot_graph_unless_asked() {
+        let system = System::new("neighborhood_request_omits_dot_graph_unless_asked");
+        let subject = make_standard_subject();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let subject_addr = subject.start();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiNeighborhoodRequest { dot_graph: false }.tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let response = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        let (body, _) = UiNeighborhoodResponse::fmb(response.body.clone()).unwrap();
+        assert_eq!(body.nodes.len(), 1);
+        assert_eq!(body.dot_graph_opt, None);
+    }
+
     #[test]
     fn new_password_message_works() {
         let system = System::new("test");
@@ -4237,6 +6002,7 @@ mod tests {
         let mut subject = neighborhood_from_nodes(&root_node, Some(&neighbor_node));
         let persistent_config = PersistentConfigurationMock::new();
         subject.persistent_config_opt = Some(Box::new(persistent_config));
+        subject.malefactor_dao_opt = Some(Box::new(MalefactorDaoMock::new()));
         subject
     }
 
@@ -4323,6 +6089,67 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    pub struct MalefactorDaoMock {
+        active_bans_results: RefCell<Vec<Vec<Malefactor>>>,
+        ban_params: Arc<Mutex<Vec<Malefactor>>>,
+    }
+
+    impl MalefactorDao for MalefactorDaoMock {
+        fn active_bans(&self) -> Vec<Malefactor> {
+            self.active_bans_results.borrow_mut().remove(0)
+        }
+
+        fn ban(&self, malefactor: &Malefactor) {
+            self.ban_params.lock().unwrap().push(malefactor.clone());
+        }
+    }
+
+    impl MalefactorDaoMock {
+        pub fn new() -> MalefactorDaoMock {
+            Self::default()
+        }
+
+        pub fn active_bans_result(self, result: Vec<Malefactor>) -> MalefactorDaoMock {
+            self.active_bans_results.borrow_mut().push(result);
+            self
+        }
+
+        pub fn ban_params(mut self, params_arc: &Arc<Mutex<Vec<Malefactor>>>) -> MalefactorDaoMock {
+            self.ban_params = params_arc.clone();
+            self
+        }
+    }
+
+    #[derive(Default)]
+    pub struct PublicIpDetectorMock {
+        detect_params: Arc<Mutex<Vec<IpAddr>>>,
+        detect_results: RefCell<Vec<Option<IpAddr>>>,
+    }
+
+    impl PublicIpDetector for PublicIpDetectorMock {
+        fn detect(&self, current_ip: IpAddr) -> Option<IpAddr> {
+            self.detect_params.lock().unwrap().push(current_ip);
+            self.detect_results.borrow_mut().remove(0)
+        }
+    }
+
+    impl PublicIpDetectorMock {
+        pub fn new() -> PublicIpDetectorMock {
+            Self::default()
+        }
+
+        pub fn detect_params(mut self, params_arc: &Arc<Mutex<Vec<IpAddr>>>) -> Self {
+            self.detect_params = params_arc.clone();
+            self
+        }
+
+        pub fn detect_result(self, result: Option<IpAddr>) -> Self {
+            self.detect_results.borrow_mut().push(result);
+            self
+        }
+    }
+
     #[derive(Default)]
     pub struct GossipProducerMock {
         produce_params: Arc<Mutex<Vec<(NeighborhoodDatabase, PublicKey)>>>,
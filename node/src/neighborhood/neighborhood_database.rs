@@ -207,6 +207,40 @@ impl NeighborhoodDatabase {
         }
     }
 
+    // Moves a Node to a new NodeAddr, keeping the IP index in step. Returns Ok(false) if the Node
+    // is already there.
+    pub fn relocate_node(
+        &mut self,
+        node_key: &PublicKey,
+        new_node_addr: &NodeAddr,
+    ) -> Result<bool, NeighborhoodDatabaseError> {
+        Self::check_for_ports(&Some(new_node_addr.clone()))?;
+        let old_ip_addr_opt = match self.node_by_key(node_key) {
+            None => return Err(NeighborhoodDatabaseError::NodeKeyNotFound(node_key.clone())),
+            Some(node) if node.node_addr_opt().as_ref() == Some(new_node_addr) => return Ok(false),
+            Some(node) => node.node_addr_opt().map(|node_addr| node_addr.ip_addr()),
+        };
+        match self.by_ip_addr.get(&new_node_addr.ip_addr()) {
+            Some(occupant) if occupant != node_key => {
+                return Err(NeighborhoodDatabaseError::NodeAddrCollision(
+                    new_node_addr.ip_addr(),
+                ))
+            }
+            _ => (),
+        }
+        if let Some(old_ip_addr) = old_ip_addr_opt {
+            self.by_ip_addr.remove(&old_ip_addr);
+        }
+        let node_record = self.node_by_key_mut(node_key).expect("Node disappeared");
+        node_record.unset_node_addr();
+        node_record
+            .set_node_addr(new_node_addr)
+            .expect("NodeAddr was just unset");
+        self.by_ip_addr
+            .insert(new_node_addr.ip_addr(), node_key.clone());
+        Ok(true)
+    }
+
     pub fn cull_dead_nodes(&mut self) {
         let referenced_node_keys = self.referenced_node_keys();
         let deadline = time_t_timestamp() - ISOLATED_NODE_GRACE_PERIOD_SECS;
@@ -239,6 +273,29 @@ impl NeighborhoodDatabase {
         render_dot_graph(renderables)
     }
 
+    // Breadth-first search along full neighborships; Nodes that can't be reached that way
+    // are left out.
+    pub fn hops_from_root(&self) -> HashMap<&PublicKey, u32> {
+        let mut result: HashMap<&PublicKey, u32> = HashMap::new();
+        let mut frontier = vec![self.root()];
+        result.insert(self.root().public_key(), 0);
+        let mut hops = 0;
+        while !frontier.is_empty() {
+            hops += 1;
+            let mut next_frontier = vec![];
+            for node in frontier {
+                for neighbor in node.full_neighbors(self) {
+                    if !result.contains_key(neighbor.public_key()) {
+                        result.insert(neighbor.public_key(), hops);
+                        next_frontier.push(neighbor);
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        result
+    }
+
     pub fn referenced_node_keys(&self) -> BTreeSet<PublicKey> {
         let mut keys: BTreeSet<PublicKey> = self
             .keys()
@@ -341,6 +398,7 @@ pub enum NeighborhoodDatabaseError {
     NodeKeyCollision(PublicKey),
     SelfNeighborAttempt(PublicKey),
     NodeAddrAlreadySet(NodeAddr),
+    NodeAddrCollision(IpAddr),
     EmptyPortList,
 }
 
@@ -700,6 +758,28 @@ mod tests {
         assert_eq!(2, db.gossip_target_degree(s));
     }
 
+    #[test]
+    fn hops_from_root_follows_full_neighborships_only() {
+        let this_node = make_node_record(1000, true);
+        let mut subject = db_from_node(&this_node);
+        let one_hop = subject.add_node(make_node_record(2000, true)).unwrap();
+        let two_hops = subject.add_node(make_node_record(3000, false)).unwrap();
+        let half_neighbor = subject.add_node(make_node_record(4000, true)).unwrap();
+        let isolated = subject.add_node(make_node_record(5000, false)).unwrap();
+        subject.add_arbitrary_full_neighbor(this_node.public_key(), &one_hop);
+        subject.add_arbitrary_full_neighbor(&one_hop, &two_hops);
+        subject.add_arbitrary_half_neighbor(&two_hops, &half_neighbor);
+
+        let result = subject.hops_from_root();
+
+        assert_eq!(result.get(this_node.public_key()), Some(&0));
+        assert_eq!(result.get(&one_hop), Some(&1));
+        assert_eq!(result.get(&two_hops), Some(&2));
+        assert_eq!(result.get(&half_neighbor), None);
+        assert_eq!(result.get(&isolated), None);
+        assert_eq!(result.len(), 3);
+    }
+
     #[test]
     fn database_can_be_pretty_printed_to_dot_format() {
         let this_node = make_node_record(1234, true); // AQIDBA
@@ -829,6 +909,89 @@ mod tests {
         assert!(!result.ok().expect("should be ok"));
     }
 
+    #[test]
+    fn relocate_node_moves_node_to_new_ip_address() {
+        let this_node = make_node_record(1234, true);
+        let mut subject = db_from_node(&this_node);
+        let other_node = make_node_record(2345, true);
+        subject.add_node(other_node.clone()).unwrap();
+        let new_node_addr = NodeAddr::new(&IpAddr::from_str("3.4.5.6").unwrap(), &[2345]);
+
+        let result = subject.relocate_node(other_node.public_key(), &new_node_addr);
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(
+            subject
+                .node_by_key(other_node.public_key())
+                .unwrap()
+                .node_addr_opt(),
+            Some(new_node_addr.clone())
+        );
+        assert_eq!(
+            subject
+                .node_by_ip(&new_node_addr.ip_addr())
+                .unwrap()
+                .public_key(),
+            other_node.public_key()
+        );
+        assert_eq!(
+            subject.node_by_ip(&other_node.node_addr_opt().unwrap().ip_addr()),
+            None
+        );
+    }
+
+    #[test]
+    fn relocate_node_returns_false_when_node_is_already_there() {
+        let this_node = make_node_record(1234, true);
+        let mut subject = db_from_node(&this_node);
+
+        let result =
+            subject.relocate_node(this_node.public_key(), &this_node.node_addr_opt().unwrap());
+
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn relocate_node_refuses_to_move_node_onto_another_nodes_ip_address() {
+        let this_node = make_node_record(1234, true);
+        let mut subject = db_from_node(&this_node);
+        let other_node = make_node_record(2345, true);
+        subject.add_node(other_node.clone()).unwrap();
+        let occupied_node_addr =
+            NodeAddr::new(&this_node.node_addr_opt().unwrap().ip_addr(), &[2345]);
+
+        let result = subject.relocate_node(other_node.public_key(), &occupied_node_addr);
+
+        assert_eq!(
+            result,
+            Err(NeighborhoodDatabaseError::NodeAddrCollision(
+                occupied_node_addr.ip_addr()
+            ))
+        );
+        assert_eq!(
+            subject
+                .node_by_key(other_node.public_key())
+                .unwrap()
+                .node_addr_opt(),
+            other_node.node_addr_opt()
+        );
+    }
+
+    #[test]
+    fn relocate_node_complains_about_unknown_node() {
+        let this_node = make_node_record(1234, true);
+        let mut subject = db_from_node(&this_node);
+        let unknown_key = PublicKey::new(b"unknown");
+        let new_node_addr = NodeAddr::new(&IpAddr::from_str("3.4.5.6").unwrap(), &[2345]);
+
+        let result = subject.relocate_node(&unknown_key, &new_node_addr);
+
+        assert_eq!(
+            result,
+            Err(NeighborhoodDatabaseError::NodeKeyNotFound(unknown_key))
+        );
+    }
+
     #[test]
     fn cull_dead_nodes_distinguishes_between_nodes_that_stink_and_nodes_that_dont() {
         let root_node = make_node_record(1234, true);
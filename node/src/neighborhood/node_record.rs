@@ -15,6 +15,7 @@ use serde_derive::{Deserialize, Serialize};
 use std::collections::btree_set::BTreeSet;
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
@@ -258,6 +259,14 @@ impl NodeRecord {
         self.metadata.desirable = is_desirable
     }
 
+    pub fn performance(&self) -> &NodePerformance {
+        &self.metadata.performance
+    }
+
+    pub fn performance_mut(&mut self) -> &mut NodePerformance {
+        &mut self.metadata.performance
+    }
+
     pub fn update(&mut self, agr: AccessibleGossipRecord) -> Result<(), String> {
         if &agr.inner.public_key != self.public_key() {
             return Err(format!(
@@ -335,6 +344,7 @@ pub struct NodeRecordMetadata {
     pub desirable: bool,
     pub last_update: u32,
     pub node_addr_opt: Option<NodeAddr>,
+    pub performance: NodePerformance,
 }
 
 impl NodeRecordMetadata {
@@ -343,6 +353,69 @@ impl NodeRecordMetadata {
             desirable: true,
             last_update: time_t_timestamp(),
             node_addr_opt: None,
+            performance: NodePerformance::default(),
+        }
+    }
+}
+
+// A Node that has failed at least this many routes, and more of them than it has delivered,
+// is unreliable
+pub const UNRELIABLE_NODE_MIN_FAILURES: u32 = 3;
+
+// What this Node has seen of another Node in the routes it has used: how many of those routes
+// worked, how often the other Node failed to resolve DNS as an exit Node, and how long round
+// trips through it have taken.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodePerformance {
+    pub successes: u32,
+    pub failures: u32,
+    pub exits: u32,
+    pub dns_failures: u32,
+    pub latency_opt: Option<Duration>,
+}
+
+impl NodePerformance {
+    pub fn record_success(&mut self, round_trip: Duration, as_exit: bool) {
+        self.successes = self.successes.saturating_add(1);
+        if as_exit {
+            self.exits = self.exits.saturating_add(1);
+        }
+        // moving average that gives each new observation a quarter of the weight
+        self.latency_opt = Some(match self.latency_opt {
+            None => round_trip,
+            Some(latency) => (latency * 3 + round_trip) / 4,
+        });
+    }
+
+    pub fn record_failure(&mut self, as_exit: bool) {
+        self.failures = self.failures.saturating_add(1);
+        if as_exit {
+            self.exits = self.exits.saturating_add(1);
+        }
+    }
+
+    pub fn record_dns_failure(&mut self) {
+        self.exits = self.exits.saturating_add(1);
+        self.dns_failures = self.dns_failures.saturating_add(1);
+    }
+
+    pub fn failure_percentage(&self) -> u32 {
+        Self::percentage(self.failures, self.successes as u64 + self.failures as u64)
+    }
+
+    pub fn is_unreliable(&self) -> bool {
+        self.failures >= UNRELIABLE_NODE_MIN_FAILURES && self.failures > self.successes
+    }
+
+    pub fn dns_failure_percentage(&self) -> u32 {
+        Self::percentage(self.dns_failures, self.exits as u64)
+    }
+
+    fn percentage(part: u32, whole: u64) -> u32 {
+        if whole == 0 {
+            0
+        } else {
+            (part as u64 * 100 / whole) as u32
         }
     }
 }
@@ -836,6 +909,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn node_performance_starts_out_knowing_nothing() {
+        let subject = make_node_record(5432, true);
+
+        assert_eq!(subject.performance(), &NodePerformance::default());
+        assert_eq!(subject.performance().failure_percentage(), 0);
+        assert_eq!(subject.performance().dns_failure_percentage(), 0);
+    }
+
+    #[test]
+    fn node_performance_records_successes_and_failures() {
+        let mut subject = NodePerformance::default();
+
+        subject.record_success(Duration::from_millis(400), false);
+        subject.record_success(Duration::from_millis(800), true);
+        subject.record_failure(true);
+        subject.record_dns_failure();
+
+        assert_eq!(
+            subject,
+            NodePerformance {
+                successes: 2,
+                failures: 1,
+                exits: 3,
+                dns_failures: 1,
+                latency_opt: Some(Duration::from_millis(500)),
+            }
+        );
+        assert_eq!(subject.failure_percentage(), 33);
+        assert_eq!(subject.dns_failure_percentage(), 33);
+    }
+
+    #[test]
+    fn node_performance_is_unreliable_only_when_repeated_failures_outnumber_successes() {
+        let mut subject = NodePerformance::default();
+        subject.record_failure(false);
+        subject.record_failure(false);
+        let too_few_failures = subject.is_unreliable();
+        subject.record_failure(false);
+        let enough_failures = subject.is_unreliable();
+        subject.record_success(Duration::from_millis(100), false);
+        subject.record_success(Duration::from_millis(100), false);
+        subject.record_success(Duration::from_millis(100), false);
+        let outnumbered_failures = subject.is_unreliable();
+
+        assert_eq!(too_few_failures, false);
+        assert_eq!(enough_failures, true);
+        assert_eq!(outnumbered_failures, false);
+    }
+
     #[test]
     fn update_works_when_immutable_characteristics_dont_change() {
         let mut subject = make_node_record(1234, true);
@@ -0,0 +1,55 @@
+// Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::str::FromStr;
+
+pub trait PublicIpDetector: Send {
+    fn detect(&self, current_ip: IpAddr) -> Option<IpAddr>;
+}
+
+// Reports the address of the local interface that traffic to the public Internet leaves through.
+// That's the Node's public IP address only if the Node isn't behind a NAT router, so it's only
+// trustworthy if it agrees with the configured public IP address when the Node starts.
+pub struct PublicIpDetectorReal {
+    ipv4_probe: SocketAddr,
+    ipv6_probe: SocketAddr,
+}
+
+impl PublicIpDetector for PublicIpDetectorReal {
+    fn detect(&self, current_ip: IpAddr) -> Option<IpAddr> {
+        let (bind_addr, probe) = match current_ip {
+            IpAddr::V4(_) => ("0.0.0.0:0", self.ipv4_probe),
+            IpAddr::V6(_) => ("[::]:0", self.ipv6_probe),
+        };
+        // Connecting a UDP socket only chooses a route; nothing is sent.
+        let socket = UdpSocket::bind(bind_addr).ok()?;
+        socket.connect(probe).ok()?;
+        socket.local_addr().ok().map(|local_addr| local_addr.ip())
+    }
+}
+
+impl Default for PublicIpDetectorReal {
+    fn default() -> Self {
+        Self {
+            ipv4_probe: SocketAddr::from_str("1.1.1.1:53").expect("Bad IPv4 probe address"),
+            ipv6_probe: SocketAddr::from_str("[2606:4700:4700::1111]:53")
+                .expect("Bad IPv6 probe address"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_reports_the_interface_that_reaches_the_probe() {
+        let subject = PublicIpDetectorReal {
+            ipv4_probe: SocketAddr::from_str("127.0.0.1:53").unwrap(),
+            ipv6_probe: SocketAddr::from_str("[::1]:53").unwrap(),
+        };
+
+        let result = subject.detect(IpAddr::from_str("1.2.3.4").unwrap());
+
+        assert_eq!(result, Some(IpAddr::from_str("127.0.0.1").unwrap()));
+    }
+}
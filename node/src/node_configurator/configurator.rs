@@ -6,28 +6,32 @@ use masq_lib::messages::{
     FromMessageBody, ToMessageBody, UiChangePasswordRequest, UiChangePasswordResponse,
     UiCheckPasswordRequest, UiCheckPasswordResponse, UiConfigurationRequest,
     UiConfigurationResponse, UiGenerateWalletsRequest, UiGenerateWalletsResponse,
-    UiNewPasswordBroadcast, UiRecoverWalletsRequest, UiRecoverWalletsResponse,
-    UiWalletAddressesRequest, UiWalletAddressesResponse,
+    UiNewPasswordBroadcast, UiPaymentCurves, UiRecoverWalletsRequest, UiRecoverWalletsResponse,
+    UiRotateKeysRequest, UiRotateKeysResponse, UiWalletAddressesRequest, UiWalletAddressesResponse,
 };
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{
     MessageBody, MessagePath, MessageTarget, NodeFromUiMessage, NodeToUiMessage,
 };
 
+use crate::accountant::{PaymentCurves, DEFAULT_PAYMENT_CURVES};
 use crate::blockchain::bip32::Bip32ECKeyPair;
 use crate::blockchain::bip39::Bip39;
+use crate::blockchain::blockchain_interface::chain_id_from_name;
 use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::config_dao::ConfigDaoReal;
 use crate::db_config::persistent_configuration::{
-    PersistentConfigError, PersistentConfiguration, PersistentConfigurationReal,
+    NodeKeys, PersistentConfigError, PersistentConfiguration, PersistentConfigurationReal,
 };
 use crate::sub_lib::configurator::NewPasswordMessage;
-use crate::sub_lib::cryptde::PlainData;
+use crate::sub_lib::cryptde::{CryptDE, PlainData};
+use crate::sub_lib::cryptde_real::CryptDEReal;
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::wallet::{Wallet, WalletError};
 use crate::test_utils::main_cryptde;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use masq_lib::constants::DEFAULT_CHAIN_NAME;
 use rustc_hex::ToHex;
 use std::str::FromStr;
 
@@ -46,6 +50,7 @@ pub const EARLY_QUESTIONING_ABOUT_DATA: u64 = CONFIGURATOR_PREFIX | 11;
 
 pub struct Configurator {
     persistent_config: Box<dyn PersistentConfiguration>,
+    chain_id: u8,
     node_to_ui_sub: Option<Recipient<NodeToUiMessage>>,
     new_password_subs: Option<Vec<Recipient<NewPasswordMessage>>>,
     logger: Logger,
@@ -83,6 +88,8 @@ impl Handler<NodeFromUiMessage> for Configurator {
             self.call_handler(msg, |c| c.handle_configuration(body, context_id));
         } else if let Ok((body, context_id)) = UiWalletAddressesRequest::fmb(msg.clone().body) {
             self.call_handler(msg, |c| c.handle_wallet_addresses(body, context_id));
+        } else if let Ok((body, context_id)) = UiRotateKeysRequest::fmb(msg.clone().body) {
+            self.call_handler(msg, |c| c.handle_rotate_keys(body, context_id));
         }
     }
 }
@@ -91,6 +98,7 @@ impl From<Box<dyn PersistentConfiguration>> for Configurator {
     fn from(persistent_config: Box<dyn PersistentConfiguration>) -> Self {
         Configurator {
             persistent_config,
+            chain_id: chain_id_from_name(DEFAULT_CHAIN_NAME),
             node_to_ui_sub: None,
             new_password_subs: None,
             logger: Logger::new("Configurator"),
@@ -109,7 +117,9 @@ impl Configurator {
         let config_dao = ConfigDaoReal::new(conn);
         let persistent_config: Box<dyn PersistentConfiguration> =
             Box::new(PersistentConfigurationReal::new(Box::new(config_dao)));
-        Configurator::from(persistent_config)
+        let mut configurator = Configurator::from(persistent_config);
+        configurator.chain_id = chain_id;
+        configurator
     }
 
     fn handle_check_password(
@@ -244,6 +254,60 @@ impl Configurator {
         Ok((consuming_wallet_address, earning_wallet_address))
     }
 
+    fn handle_rotate_keys(&mut self, msg: UiRotateKeysRequest, context_id: u64) -> MessageBody {
+        match self.rotate_keys(&msg.db_password) {
+            Ok(public_key) => UiRotateKeysResponse { public_key }.tmb(context_id),
+            Err((code, e_msg)) => {
+                warning!(
+                    self.logger,
+                    "Failed to rotate node keys: {}, {}",
+                    code,
+                    e_msg
+                );
+                MessageBody {
+                    opcode: msg.opcode().to_string(),
+                    path: MessagePath::Conversation(context_id),
+                    payload: Err((code, e_msg)),
+                }
+            }
+        }
+    }
+
+    // The running Node keeps its current keys; the new ones take effect when it next starts.
+    fn rotate_keys(&mut self, db_password: &str) -> Result<String, MessageError> {
+        match self
+            .persistent_config
+            .check_password(Some(db_password.to_string()))
+        {
+            Err(e) => {
+                return Err((
+                    CONFIGURATOR_READ_ERROR,
+                    format!("Error checking password: {:?}", e),
+                ))
+            }
+            Ok(true) => (),
+            Ok(false) => {
+                return Err((
+                    BAD_PASSWORD_ERROR,
+                    "Bad password; can't rotate node keys".to_string(),
+                ))
+            }
+        }
+        let main_cryptde = CryptDEReal::new(self.chain_id);
+        let alias_cryptde = CryptDEReal::new(self.chain_id);
+        let node_keys = NodeKeys {
+            main: main_cryptde.key_material(),
+            alias: alias_cryptde.key_material(),
+        };
+        match self
+            .persistent_config
+            .set_node_keys(&node_keys, db_password)
+        {
+            Ok(_) => Ok(main_cryptde.public_key_to_descriptor_fragment(main_cryptde.public_key())),
+            Err(e) => Err((CONFIGURATOR_WRITE_ERROR, format!("{:?}", e))),
+        }
+    }
+
     fn recalculate_consuming_wallet(
         seed: PlainData,
         derivation_path: String,
@@ -496,6 +560,9 @@ impl Configurator {
         let clandestine_port =
             Self::value_required(persistent_config.clandestine_port(), "clandestinePort")?;
         let gas_price = Self::value_required(persistent_config.gas_price(), "gasPrice")?;
+        let payment_curves =
+            Self::value_not_required(persistent_config.payment_curves(), "paymentCurves")?
+                .unwrap_or_else(|| DEFAULT_PAYMENT_CURVES.clone());
         let consuming_wallet_derivation_path_opt = Self::value_not_required(
             persistent_config.consuming_wallet_derivation_path(),
             "consumingWalletDerivationPathOpt",
@@ -531,6 +598,7 @@ impl Configurator {
             current_schema_version,
             clandestine_port,
             gas_price,
+            payment_curves: Self::ui_payment_curves(&payment_curves),
             mnemonic_seed_opt,
             consuming_wallet_derivation_path_opt,
             earning_wallet_address_opt,
@@ -540,6 +608,17 @@ impl Configurator {
         Ok(response.tmb(context_id))
     }
 
+    fn ui_payment_curves(payment_curves: &PaymentCurves) -> UiPaymentCurves {
+        UiPaymentCurves {
+            payment_suggested_after_sec: payment_curves.payment_suggested_after_sec,
+            payment_grace_before_ban_sec: payment_curves.payment_grace_before_ban_sec,
+            permanent_debt_allowed_gwub: payment_curves.permanent_debt_allowed_gwub,
+            balance_to_decrease_from_gwub: payment_curves.balance_to_decrease_from_gwub,
+            balance_decreases_for_sec: payment_curves.balance_decreases_for_sec,
+            unban_when_balance_below_gwub: payment_curves.unban_when_balance_below_gwub,
+        }
+    }
+
     fn value_required<T>(
         result: Result<Option<T>, PersistentConfigError>,
         field_name: &str,
@@ -891,6 +970,137 @@ mod tests {
         );
     }
 
+    #[test]
+    fn handle_rotate_keys_works() {
+        let system = System::new("test");
+        let check_password_params_arc = Arc::new(Mutex::new(vec![]));
+        let set_node_keys_params_arc = Arc::new(Mutex::new(vec![]));
+        let persistent_config = PersistentConfigurationMock::new()
+            .check_password_params(&check_password_params_arc)
+            .check_password_result(Ok(true))
+            .set_node_keys_params(&set_node_keys_params_arc)
+            .set_node_keys_result(Ok(()));
+        let subject = make_subject(Some(persistent_config));
+        let subject_addr = subject.start();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiRotateKeysRequest {
+                    db_password: "password".to_string(),
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let check_password_params = check_password_params_arc.lock().unwrap();
+        assert_eq!(*check_password_params, vec![Some("password".to_string())]);
+        let set_node_keys_params = set_node_keys_params_arc.lock().unwrap();
+        let (node_keys, db_password) = &set_node_keys_params[0];
+        assert_eq!(db_password, "password");
+        assert_ne!(node_keys.main, node_keys.alias);
+        let main_cryptde =
+            CryptDEReal::from_key_material(&node_keys.main, DEFAULT_CHAIN_ID).unwrap();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(0),
+            &NodeToUiMessage {
+                target: MessageTarget::ClientId(1234),
+                body: UiRotateKeysResponse {
+                    public_key: main_cryptde
+                        .public_key_to_descriptor_fragment(main_cryptde.public_key())
+                }
+                .tmb(4321)
+            }
+        );
+        assert_eq!(ui_gateway_recording.len(), 1);
+    }
+
+    #[test]
+    fn handle_rotate_keys_handles_bad_password() {
+        let persistent_config = PersistentConfigurationMock::new().check_password_result(Ok(false));
+        let mut subject = make_subject(Some(persistent_config));
+
+        let result = subject.handle_rotate_keys(
+            UiRotateKeysRequest {
+                db_password: "bad password".to_string(),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "rotateKeys".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    BAD_PASSWORD_ERROR,
+                    "Bad password; can't rotate node keys".to_string()
+                ))
+            }
+        );
+    }
+
+    #[test]
+    fn handle_rotate_keys_handles_error_checking_password() {
+        let persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Err(PersistentConfigError::NotPresent));
+        let mut subject = make_subject(Some(persistent_config));
+
+        let result = subject.handle_rotate_keys(
+            UiRotateKeysRequest {
+                db_password: "password".to_string(),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "rotateKeys".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    CONFIGURATOR_READ_ERROR,
+                    "Error checking password: NotPresent".to_string()
+                ))
+            }
+        );
+    }
+
+    #[test]
+    fn handle_rotate_keys_handles_error_setting_keys() {
+        init_test_logging();
+        let persistent_config = PersistentConfigurationMock::new()
+            .check_password_result(Ok(true))
+            .set_node_keys_result(Err(PersistentConfigError::TransactionError));
+        let mut subject = make_subject(Some(persistent_config));
+
+        let result = subject.handle_rotate_keys(
+            UiRotateKeysRequest {
+                db_password: "password".to_string(),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "rotateKeys".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((CONFIGURATOR_WRITE_ERROR, "TransactionError".to_string()))
+            }
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: Configurator: Failed to rotate node keys: {}, TransactionError",
+            CONFIGURATOR_WRITE_ERROR
+        ));
+    }
+
     #[test]
     fn handle_wallet_addresses_works() {
         let system = System::new("test");
@@ -1657,6 +1867,7 @@ mod tests {
             .current_schema_version_result("1.2.3")
             .clandestine_port_result(Ok(Some(1234)))
             .gas_price_result(Ok(Some(2345)))
+            .payment_curves_result(Ok(Some(PaymentCurves::from_str("1|2|3|4|5|6").unwrap())))
             .mnemonic_seed_result(Ok(None))
             .consuming_wallet_derivation_path_result(Ok(None))
             .past_neighbors_result(Ok(Some(vec![])))
@@ -1680,6 +1891,14 @@ mod tests {
                 current_schema_version: "1.2.3".to_string(),
                 clandestine_port: 1234,
                 gas_price: 2345,
+                payment_curves: UiPaymentCurves {
+                    payment_suggested_after_sec: 1,
+                    payment_grace_before_ban_sec: 2,
+                    permanent_debt_allowed_gwub: 3,
+                    balance_to_decrease_from_gwub: 4,
+                    balance_decreases_for_sec: 5,
+                    unban_when_balance_below_gwub: 6,
+                },
                 mnemonic_seed_opt: None,
                 consuming_wallet_derivation_path_opt: None,
                 earning_wallet_address_opt: None,
@@ -1689,6 +1908,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn configuration_reports_default_payment_curves_when_none_are_stored() {
+        let persistent_config = PersistentConfigurationMock::new()
+            .current_schema_version_result("1.2.3")
+            .clandestine_port_result(Ok(Some(1234)))
+            .gas_price_result(Ok(Some(2345)))
+            .payment_curves_result(Ok(None))
+            .consuming_wallet_derivation_path_result(Ok(None))
+            .earning_wallet_address_result(Ok(None))
+            .start_block_result(Ok(Some(3456)));
+        let mut subject = make_subject(Some(persistent_config));
+
+        let (configuration, _) = UiConfigurationResponse::fmb(subject.handle_configuration(
+            UiConfigurationRequest {
+                db_password_opt: None,
+            },
+            4321,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            configuration.payment_curves,
+            Configurator::ui_payment_curves(&DEFAULT_PAYMENT_CURVES)
+        );
+    }
+
     #[test]
     fn configuration_handles_check_password_error() {
         let persistent_config = PersistentConfigurationMock::new()
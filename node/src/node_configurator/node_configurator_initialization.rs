@@ -1,5 +1,8 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
+use crate::daemon::restart_policy::{
+    RestartPolicy, DEFAULT_MAX_RESTARTS, DEFAULT_RESTART_WINDOW_MINUTES,
+};
 use crate::node_configurator::{app_head, NodeConfigurator};
 use crate::sub_lib::utils::make_new_multi_config;
 use clap::{App, Arg};
@@ -16,11 +19,26 @@ lazy_static! {
         Best to accept the default unless you know what you're doing. Must be between {} and {}.",
         LOWEST_USABLE_INSECURE_PORT, HIGHEST_USABLE_PORT
     );
+    static ref MAX_RESTARTS_HELP: String = format!(
+        "With --auto-restart on, the most times the Daemon will restart the Node within --restart-window. \
+        After that, the Node stays down until a user interface starts it. [default: {}]",
+        DEFAULT_MAX_RESTARTS
+    );
+    static ref RESTART_WINDOW_HELP: String = format!(
+        "With --auto-restart on, the number of minutes over which the Daemon counts restarts against \
+        --max-restarts. The wait before each restart doubles with every restart in the window. [default: {}]",
+        DEFAULT_RESTART_WINDOW_MINUTES
+    );
 }
 
+const AUTO_RESTART_HELP: &str =
+    "Whether the Daemon should restart the Node, with the setup it was last started with, when the Node \
+     crashes. A Node that is shut down on purpose is never restarted. [default: off]";
+
 #[derive(Default, Clone, PartialEq, Debug)]
 pub struct InitializationConfig {
     pub ui_port: u16,
+    pub restart_policy_opt: Option<RestartPolicy>,
 }
 
 pub struct NodeConfiguratorInitialization {}
@@ -53,6 +71,38 @@ pub fn app() -> App<'static, 'static> {
                 .help("Directs MASQ to start the Daemon that controls the Node, rather than the Node itself"),
         )
         .arg(ui_port_arg(&UI_PORT_HELP))
+        .arg(
+            Arg::with_name("auto-restart")
+                .long("auto-restart")
+                .value_name("AUTO-RESTART")
+                .takes_value(true)
+                .possible_values(&["on", "off"])
+                .case_insensitive(true)
+                .help(AUTO_RESTART_HELP),
+        )
+        .arg(
+            Arg::with_name("max-restarts")
+                .long("max-restarts")
+                .value_name("COUNT")
+                .takes_value(true)
+                .validator(validate_positive_integer)
+                .help(&MAX_RESTARTS_HELP),
+        )
+        .arg(
+            Arg::with_name("restart-window")
+                .long("restart-window")
+                .value_name("MINUTES")
+                .takes_value(true)
+                .validator(validate_positive_integer)
+                .help(&RESTART_WINDOW_HELP),
+        )
+}
+
+fn validate_positive_integer(value: String) -> Result<(), String> {
+    match value.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(value),
+    }
 }
 
 mod initialization {
@@ -60,6 +110,7 @@ mod initialization {
     use clap::value_t;
     use masq_lib::constants::DEFAULT_UI_PORT;
     use masq_lib::multi_config::MultiConfig;
+    use std::time::Duration;
 
     pub fn parse_args(
         multi_config: &MultiConfig,
@@ -67,6 +118,19 @@ mod initialization {
         _streams: &mut StdStreams<'_>,
     ) {
         config.ui_port = value_m!(multi_config, "ui-port", u16).unwrap_or(DEFAULT_UI_PORT);
+        config.restart_policy_opt = match value_m!(multi_config, "auto-restart", String) {
+            Some(ref auto_restart) if auto_restart.to_lowercase() == "on" => {
+                let max_restarts =
+                    value_m!(multi_config, "max-restarts", u32).unwrap_or(DEFAULT_MAX_RESTARTS);
+                let window_minutes = value_m!(multi_config, "restart-window", u64)
+                    .unwrap_or(DEFAULT_RESTART_WINDOW_MINUTES);
+                Some(RestartPolicy::new(
+                    max_restarts,
+                    Duration::from_secs(window_minutes * 60),
+                ))
+            }
+            _ => None,
+        };
     }
 }
 
@@ -78,6 +142,7 @@ mod tests {
     use masq_lib::constants::DEFAULT_UI_PORT;
     use masq_lib::multi_config::{CommandLineVcl, VirtualCommandLine};
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
+    use std::time::Duration;
 
     #[test]
     fn parse_args_creates_configuration_with_defaults() {
@@ -94,6 +159,7 @@ mod tests {
         );
 
         assert_eq!(config.ui_port, DEFAULT_UI_PORT);
+        assert_eq!(config.restart_policy_opt, None);
     }
 
     #[test]
@@ -114,4 +180,86 @@ mod tests {
 
         assert_eq!(config.ui_port, 4321);
     }
+
+    #[test]
+    fn parse_args_creates_restart_policy_with_defaults_when_auto_restart_is_on() {
+        let args = ArgsBuilder::new()
+            .opt("--initialization")
+            .param("--auto-restart", "ON");
+        let mut config = InitializationConfig::default();
+        let vcls: Vec<Box<dyn VirtualCommandLine>> =
+            vec![Box::new(CommandLineVcl::new(args.into()))];
+        let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+
+        initialization::parse_args(
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(
+            config.restart_policy_opt,
+            Some(RestartPolicy::new(
+                DEFAULT_MAX_RESTARTS,
+                Duration::from_secs(DEFAULT_RESTART_WINDOW_MINUTES * 60)
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_args_creates_restart_policy_with_values() {
+        let args = ArgsBuilder::new()
+            .opt("--initialization")
+            .param("--auto-restart", "on")
+            .param("--max-restarts", "3")
+            .param("--restart-window", "10");
+        let mut config = InitializationConfig::default();
+        let vcls: Vec<Box<dyn VirtualCommandLine>> =
+            vec![Box::new(CommandLineVcl::new(args.into()))];
+        let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+
+        initialization::parse_args(
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(
+            config.restart_policy_opt,
+            Some(RestartPolicy::new(3, Duration::from_secs(600)))
+        );
+    }
+
+    #[test]
+    fn parse_args_ignores_restart_limits_when_auto_restart_is_off() {
+        let args = ArgsBuilder::new()
+            .opt("--initialization")
+            .param("--auto-restart", "off")
+            .param("--max-restarts", "3");
+        let mut config = InitializationConfig::default();
+        let vcls: Vec<Box<dyn VirtualCommandLine>> =
+            vec![Box::new(CommandLineVcl::new(args.into()))];
+        let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+
+        initialization::parse_args(
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(config.restart_policy_opt, None);
+    }
+
+    #[test]
+    fn validate_positive_integer_rejects_zero_and_garbage() {
+        assert_eq!(validate_positive_integer("7".to_string()), Ok(()));
+        assert_eq!(
+            validate_positive_integer("0".to_string()),
+            Err("0".to_string())
+        );
+        assert_eq!(
+            validate_positive_integer("seven".to_string()),
+            Err("seven".to_string())
+        );
+    }
 }
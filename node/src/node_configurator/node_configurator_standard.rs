@@ -28,13 +28,13 @@ impl NodeConfigurator<BootstrapperConfig> for NodeConfiguratorStandardPrivileged
             streams,
         )?;
         let mut bootstrapper_config = BootstrapperConfig::new();
-        standard::establish_port_configurations(&mut bootstrapper_config);
         standard::privileged_parse_args(
             self.dirs_wrapper.as_ref(),
             &multi_config,
             &mut bootstrapper_config,
             streams,
         )?;
+        standard::establish_port_configurations(&mut bootstrapper_config);
         Ok(bootstrapper_config)
     }
 }
@@ -139,37 +139,48 @@ pub mod standard {
     use clap::value_t;
     use log::LevelFilter;
 
+    use crate::accountant::{PaymentCurves, DEFAULT_PAYMENT_CURVES};
     use crate::blockchain::bip32::Bip32ECKeyPair;
     use crate::blockchain::blockchain_interface::chain_id_from_name;
+    use crate::blockchain::fee_strategy::FeeStrategyKind;
     use crate::bootstrapper::PortConfiguration;
     use crate::db_config::persistent_configuration::{
         PersistentConfigError, PersistentConfiguration,
     };
     use crate::http_request_start_finder::HttpRequestDiscriminatorFactory;
+    use crate::neighborhood::geoip::GeoIpDatabase;
     use crate::node_configurator::{
         data_directory_from_context, determine_config_file_path,
         real_user_data_directory_opt_and_chain_name, request_existing_db_password, DirsWrapper,
     };
+    use crate::socks_discriminator_factory::SocksDiscriminatorFactory;
     use crate::sub_lib::accountant::DEFAULT_EARNING_WALLET;
     use crate::sub_lib::cryptde::{CryptDE, PublicKey};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::cryptde_real::CryptDEReal;
+    use crate::sub_lib::exit_policy::ExitPolicy;
+    use crate::sub_lib::exit_service_policy::ExitServicePolicy;
     use crate::sub_lib::neighborhood::{
-        NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, DEFAULT_RATE_PACK,
+        NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, RoutingStrategy, DEFAULT_RATE_PACK,
     };
     use crate::sub_lib::node_addr::NodeAddr;
+    use crate::sub_lib::ui_gateway::{UiRemoteAccess, UiSecurity};
     use crate::sub_lib::utils::make_new_multi_config;
     use crate::sub_lib::wallet::Wallet;
     use crate::tls_discriminator_factory::TlsDiscriminatorFactory;
     use itertools::Itertools;
     use masq_lib::constants::{
-        DEFAULT_CHAIN_NAME, DEFAULT_GAS_PRICE, DEFAULT_UI_PORT, HTTP_PORT, TLS_PORT,
+        DEFAULT_CHAIN_NAME, DEFAULT_CONFIRMATIONS, DEFAULT_GAS_PRICE,
+        DEFAULT_MALEFACTOR_BAN_DURATION_HOURS, DEFAULT_UI_PORT, HTTP_PORT, TLS_PORT,
     };
     use masq_lib::multi_config::{CommandLineVcl, ConfigFileVcl, EnvironmentVcl, MultiConfig};
     use masq_lib::shared_schema::{ConfiguratorError, ParamError};
     use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
     use rustc_hex::FromHex;
+    use std::path::PathBuf;
     use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
 
     pub fn make_service_mode_multi_config<'a>(
         dirs_wrapper: &dyn DirsWrapper,
@@ -212,6 +223,12 @@ pub mod standard {
                 false,
             ),
         );
+        if let Some(socks_port) = config.socks_port_opt {
+            config.port_configurations.insert(
+                socks_port,
+                PortConfiguration::new(vec![Box::new(SocksDiscriminatorFactory::new())], false),
+            );
+        }
     }
 
     pub fn privileged_parse_args(
@@ -251,6 +268,11 @@ pub mod standard {
 
         privileged_config.ui_gateway_config.ui_port =
             value_m!(multi_config, "ui-port", u16).unwrap_or(DEFAULT_UI_PORT);
+        privileged_config.ui_gateway_config.ui_security =
+            get_ui_security(multi_config, &privileged_config.data_directory)?;
+
+        privileged_config.socks_port_opt =
+            get_socks_port(multi_config, privileged_config.ui_gateway_config.ui_port)?;
 
         privileged_config.crash_point =
             value_m!(multi_config, "crash-point", CrashPoint).unwrap_or(CrashPoint::None);
@@ -282,6 +304,75 @@ pub mod standard {
         Ok(())
     }
 
+    // A clap validator sees only one value at a time, so collisions between the SOCKS port and the
+    // Node's other listening ports have to be caught here.
+    fn get_socks_port(
+        multi_config: &MultiConfig,
+        ui_port: u16,
+    ) -> Result<Option<u16>, ConfiguratorError> {
+        let socks_port = match value_m!(multi_config, "socks-port", u16) {
+            Some(socks_port) => socks_port,
+            None => return Ok(None),
+        };
+        if value_m!(multi_config, "clandestine-port", u16) == Some(socks_port) {
+            return Err(socks_port_collision(socks_port, "--clandestine-port"));
+        }
+        if socks_port == ui_port {
+            return Err(socks_port_collision(socks_port, "--ui-port"));
+        }
+        Ok(Some(socks_port))
+    }
+
+    fn socks_port_collision(socks_port: u16, other_parameter: &str) -> ConfiguratorError {
+        ConfiguratorError::required(
+            "socks-port",
+            &format!(
+                "Cannot use {} for both --socks-port and {}",
+                socks_port, other_parameter
+            ),
+        )
+    }
+
+    fn get_ui_security(
+        multi_config: &MultiConfig,
+        data_directory: &PathBuf,
+    ) -> Result<UiSecurity, ConfiguratorError> {
+        let tokens_directory_opt = match value_m!(multi_config, "ui-auth", String) {
+            Some(ref ui_auth) if ui_auth.to_lowercase() == "on" => Some(data_directory.clone()),
+            _ => None,
+        };
+        let remote_opt = match value_m!(multi_config, "ui-remote-address", SocketAddr) {
+            None => None,
+            Some(address) => {
+                if tokens_directory_opt.is_none() {
+                    return Err(ConfiguratorError::required(
+                        "ui-remote-address",
+                        "Remote UI access requires --ui-auth on",
+                    ));
+                }
+                let tls_identity_path = match value_m!(multi_config, "ui-tls-identity", PathBuf) {
+                    Some(path) => path,
+                    None => {
+                        return Err(ConfiguratorError::required(
+                            "ui-tls-identity",
+                            "Remote UI access requires a TLS identity",
+                        ))
+                    }
+                };
+                Some(UiRemoteAccess {
+                    address,
+                    tls_identity_path,
+                    tls_identity_password: value_m!(multi_config, "ui-tls-password", String)
+                        .unwrap_or_default(),
+                })
+            }
+        };
+        Ok(UiSecurity {
+            tokens_directory_opt,
+            remote_opt,
+        })
+    }
+
     pub fn unprivileged_parse_args(
         multi_config: &MultiConfig,
         unprivileged_config: &mut BootstrapperConfig,
@@ -289,6 +380,18 @@ pub mod standard {
         persistent_config_opt: Option<&mut dyn PersistentConfiguration>,
     ) -> Result<(), ConfiguratorError> {
         unprivileged_config.clandestine_port_opt = value_m!(multi_config, "clandestine-port", u16);
+        if let (None, Some(socks_port), Some(persistent_config)) = (
+            unprivileged_config.clandestine_port_opt,
+            value_m!(multi_config, "socks-port", u16),
+            persistent_config_opt.as_ref(),
+        ) {
+            if persistent_config.clandestine_port() == Ok(Some(socks_port)) {
+                return Err(socks_port_collision(
+                    socks_port,
+                    "the clandestine port saved in the database",
+                ));
+            }
+        }
         let user_specified = multi_config.arg_matches().occurrences_of("gas-price") > 0;
         unprivileged_config.blockchain_bridge_config.gas_price = if user_specified {
             value_m!(multi_config, "gas-price", u64).expect("Value disappeared")
@@ -304,6 +407,88 @@ pub mod standard {
                 None => 1,
             }
         };
+        unprivileged_config.blockchain_bridge_config.batch_payments =
+            match value_m!(multi_config, "batch-payments", String) {
+                Some(ref value) => value.to_lowercase() == "on",
+                None => false,
+            };
+        unprivileged_config.blockchain_bridge_config.fee_strategy =
+            match value_m!(multi_config, "fee-strategy", String) {
+                Some(ref value) if value.to_lowercase() == "oracle" => FeeStrategyKind::Oracle,
+                _ => FeeStrategyKind::Fixed,
+            };
+        unprivileged_config
+            .blockchain_bridge_config
+            .max_fee_per_gas_opt = value_m!(multi_config, "max-fee-per-gas", u64);
+        unprivileged_config.blockchain_bridge_config.confirmations =
+            value_m!(multi_config, "confirmations", u64).unwrap_or(DEFAULT_CONFIRMATIONS);
+        unprivileged_config.malefactor_ban_duration = Duration::from_secs(
+            value_m!(multi_config, "malefactor-ban-duration", u64)
+                .unwrap_or(DEFAULT_MALEFACTOR_BAN_DURATION_HOURS)
+                * 3600,
+        );
+        let parse_exit_rules = |parameter: &str| match value_m!(multi_config, parameter, String) {
+            Some(rules) => {
+                ExitPolicy::parse_rules(&rules).expect("Bad clap validation for exit rules")
+            }
+            None => vec![],
+        };
+        unprivileged_config.exit_policy = ExitPolicy {
+            required: parse_exit_rules("exit-require"),
+            excluded: parse_exit_rules("exit-exclude"),
+        };
+        unprivileged_config.geoip_database_opt =
+            match value_m!(multi_config, "geoip-database", PathBuf) {
+                Some(path) => match GeoIpDatabase::load(&path) {
+                    Ok(geoip_database) => Some(Arc::new(geoip_database)),
+                    Err(e) => return Err(ConfiguratorError::required("geoip-database", &e)),
+                },
+                // The bundled database is big, so it's only read when it's needed.
+                None if unprivileged_config.exit_policy.has_country_rules() => {
+                    match GeoIpDatabase::bundled() {
+                        Ok(geoip_database_opt) => geoip_database_opt.map(Arc::new),
+                        Err(e) => return Err(ConfiguratorError::required("geoip-database", &e)),
+                    }
+                }
+                None => None,
+            };
+        if unprivileged_config.exit_policy.has_country_rules()
+            && unprivileged_config.geoip_database_opt.is_none()
+        {
+            return Err(ConfiguratorError::required(
+                "geoip-database",
+                "Country codes in --exit-require or --exit-exclude need a GeoIP database, and this Node was built without one",
+            ));
+        }
+        let user_specified = multi_config.arg_matches().occurrences_of("payment-curves") > 0;
+        unprivileged_config.accountant_config.payment_curves = if user_specified {
+            value_m!(multi_config, "payment-curves", PaymentCurves).expect("Value disappeared")
+        } else {
+            match persistent_config_opt {
+                Some(ref persistent_config) => match persistent_config.payment_curves() {
+                    Ok(Some(curves)) => curves,
+                    Ok(None) => DEFAULT_PAYMENT_CURVES.clone(),
+                    Err(pce) => return Err(pce.into_configurator_error("payment-curves")),
+                },
+                None => DEFAULT_PAYMENT_CURVES.clone(),
+            }
+        };
+        let user_specified = multi_config
+            .arg_matches()
+            .occurrences_of("routing-strategy")
+            > 0;
+        unprivileged_config.routing_strategy = if user_specified {
+            value_m!(multi_config, "routing-strategy", RoutingStrategy).expect("Value disappeared")
+        } else {
+            match persistent_config_opt {
+                Some(ref persistent_config) => match persistent_config.routing_strategy() {
+                    Ok(Some(strategy)) => strategy,
+                    Ok(None) => RoutingStrategy::default(),
+                    Err(pce) => return Err(pce.into_configurator_error("routing-strategy")),
+                },
+                None => RoutingStrategy::default(),
+            }
+        };
         let mnc_result = if let Some(persistent_config) = persistent_config_opt {
             get_wallets(
                 streams,
@@ -322,6 +507,8 @@ pub mod standard {
         };
         match mnc_result {
             Ok(config) => {
+                unprivileged_config.exit_service_policy =
+                    make_exit_service_policy(multi_config, &config.mode);
                 unprivileged_config.neighborhood_config = config;
                 Ok(())
             }
@@ -329,6 +516,22 @@ pub mod standard {
         }
     }
 
+    fn make_exit_service_policy(
+        multi_config: &MultiConfig,
+        neighborhood_mode: &NeighborhoodMode,
+    ) -> ExitServicePolicy {
+        let operator_rules = match value_m!(multi_config, "exit-service-policy", String) {
+            Some(rules) => ExitServicePolicy::parse_rules(&rules)
+                .expect("Bad clap validation for exit service rules"),
+            None => vec![],
+        };
+        if neighborhood_mode.is_zero_hop() {
+            ExitServicePolicy::without_defaults(operator_rules)
+        } else {
+            ExitServicePolicy::new(operator_rules)
+        }
+    }
+
     pub fn configure_database(
         config: &BootstrapperConfig,
         persistent_config: &mut (dyn PersistentConfiguration),
@@ -343,6 +546,16 @@ pub mod standard {
         {
             return Err(pce.into_configurator_error("gas-price"));
         }
+
+        if let Err(pce) =
+            persistent_config.set_payment_curves(&config.accountant_config.payment_curves)
+        {
+            return Err(pce.into_configurator_error("payment-curves"));
+        }
+
+        if let Err(pce) = persistent_config.set_routing_strategy(config.routing_strategy) {
+            return Err(pce.into_configurator_error("routing-strategy"));
+        }
         Ok(())
     }
 
@@ -851,6 +1064,41 @@ pub mod standard {
             )
         }
 
+        #[test]
+        fn configure_database_handles_error_during_setting_payment_curves() {
+            let mut config = BootstrapperConfig::new();
+            config.clandestine_port_opt = None;
+            let mut persistent_config = PersistentConfigurationMock::new()
+                .set_gas_price_result(Ok(()))
+                .set_payment_curves_result(Err(PersistentConfigError::TransactionError));
+
+            let result = configure_database(&config, &mut persistent_config);
+
+            assert_eq!(
+                result,
+                Err(PersistentConfigError::TransactionError
+                    .into_configurator_error("payment-curves"))
+            )
+        }
+
+        #[test]
+        fn configure_database_handles_error_during_setting_routing_strategy() {
+            let mut config = BootstrapperConfig::new();
+            config.clandestine_port_opt = None;
+            let mut persistent_config = PersistentConfigurationMock::new()
+                .set_gas_price_result(Ok(()))
+                .set_payment_curves_result(Ok(()))
+                .set_routing_strategy_result(Err(PersistentConfigError::TransactionError));
+
+            let result = configure_database(&config, &mut persistent_config);
+
+            assert_eq!(
+                result,
+                Err(PersistentConfigError::TransactionError
+                    .into_configurator_error("routing-strategy"))
+            )
+        }
+
         #[test]
         fn get_earning_wallet_from_address_handles_error_retrieving_earning_wallet_from_address() {
             let args = ArgsBuilder::new().param(
@@ -1089,10 +1337,12 @@ pub mod standard {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::accountant::{PaymentCurves, DEFAULT_PAYMENT_CURVES};
     use crate::blockchain::bip32::Bip32ECKeyPair;
     use crate::blockchain::blockchain_interface::{
         chain_id_from_name, chain_name_from_id, contract_address,
     };
+    use crate::blockchain::fee_strategy::FeeStrategyKind;
     use crate::bootstrapper::RealUser;
     use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
     use crate::db_config::config_dao::{ConfigDao, ConfigDaoReal};
@@ -1105,17 +1355,24 @@ mod tests {
     use crate::sub_lib::cryptde::{CryptDE, PlainData, PublicKey};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::cryptde_real::CryptDEReal;
+    use crate::sub_lib::exit_policy::ExitPolicy;
+    use crate::sub_lib::exit_service_policy::ExitServicePolicy;
     use crate::sub_lib::neighborhood::{
-        NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, DEFAULT_RATE_PACK,
+        NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, RoutingStrategy, DEFAULT_RATE_PACK,
     };
     use crate::sub_lib::node_addr::NodeAddr;
+    use crate::sub_lib::ui_gateway::{UiRemoteAccess, UiSecurity};
     use crate::sub_lib::utils::make_new_test_multi_config;
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils;
     use crate::test_utils::make_default_persistent_configuration;
     use crate::test_utils::persistent_configuration_mock::PersistentConfigurationMock;
     use crate::test_utils::{assert_string_contains, main_cryptde, ArgsBuilder};
-    use masq_lib::constants::{DEFAULT_CHAIN_NAME, DEFAULT_GAS_PRICE, DEFAULT_UI_PORT};
+    use itertools::Itertools;
+    use masq_lib::constants::{
+        DEFAULT_CHAIN_NAME, DEFAULT_CONFIRMATIONS, DEFAULT_GAS_PRICE,
+        DEFAULT_MALEFACTOR_BAN_DURATION_HOURS, DEFAULT_UI_PORT, HTTP_PORT, TLS_PORT,
+    };
     use masq_lib::multi_config::{
         CommandLineVcl, ConfigFileVcl, NameValueVclArg, VclArg, VirtualCommandLine,
     };
@@ -1135,6 +1392,7 @@ mod tests {
     use std::path::PathBuf;
     use std::str::FromStr;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     fn make_default_cli_params() -> ArgsBuilder {
         ArgsBuilder::new().param("--ip", "1.2.3.4")
@@ -1707,6 +1965,7 @@ mod tests {
             .param("--ip", "34.56.78.90")
             .param("--clandestine-port", "1234")
             .param("--ui-port", "5335")
+            .param("--socks-port", "1080")
             .param("--data-directory", home_dir.to_str().unwrap())
             .param("--blockchain-service-url", "http://127.0.0.1:8545")
             .param("--log-level", "trace")
@@ -1746,6 +2005,7 @@ mod tests {
             ),
         );
         assert_eq!(config.ui_gateway_config.ui_port, 5335);
+        assert_eq!(config.socks_port_opt, Some(1080));
         assert_eq!(
             config.neighborhood_config,
             NeighborhoodConfig {
@@ -1767,6 +2027,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn establish_port_configurations_adds_socks_port_only_when_specified() {
+        let mut without_socks = BootstrapperConfig::new();
+        let mut with_socks = BootstrapperConfig::new();
+        with_socks.socks_port_opt = Some(1080);
+
+        standard::establish_port_configurations(&mut without_socks);
+        standard::establish_port_configurations(&mut with_socks);
+
+        let ports_of = |config: &BootstrapperConfig| {
+            config
+                .port_configurations
+                .keys()
+                .cloned()
+                .sorted()
+                .collect::<Vec<u16>>()
+        };
+        assert_eq!(ports_of(&without_socks), vec![HTTP_PORT, TLS_PORT]);
+        assert_eq!(ports_of(&with_socks), vec![HTTP_PORT, TLS_PORT, 1080]);
+        assert!(!with_socks.port_configurations[&1080].is_clandestine);
+    }
+
     #[test]
     fn unprivileged_parse_args_creates_configurations() {
         running_test();
@@ -1962,6 +2244,155 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unprivileged_parse_args_uses_stored_payment_curves_when_none_are_specified() {
+        let multi_config =
+            test_utils::make_multi_config(ArgsBuilder::new().param("--ip", "1.2.3.4"));
+        let mut unprivileged_config = BootstrapperConfig::new();
+        let mut holder = FakeStreamHolder::new();
+        let stored_curves = PaymentCurves::from_str("1|2|3|4|5|6").unwrap();
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .gas_price_result(Ok(Some(1)))
+            .payment_curves_result(Ok(Some(stored_curves.clone())))
+            .earning_wallet_from_address_result(Ok(Some(Wallet::new(
+                "0x0123456789012345678901234567890123456789",
+            ))))
+            .mnemonic_seed_exists_result(Ok(false));
+
+        standard::unprivileged_parse_args(
+            &multi_config,
+            &mut unprivileged_config,
+            &mut holder.streams(),
+            Some(&mut persistent_config),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unprivileged_config.accountant_config.payment_curves,
+            stored_curves
+        );
+    }
+
+    #[test]
+    fn unprivileged_parse_args_prefers_specified_payment_curves_to_stored_ones() {
+        let multi_config = test_utils::make_multi_config(
+            ArgsBuilder::new()
+                .param("--ip", "1.2.3.4")
+                .param("--payment-curves", "10|20|30|40|50|60"),
+        );
+        let mut unprivileged_config = BootstrapperConfig::new();
+        let mut holder = FakeStreamHolder::new();
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .gas_price_result(Ok(Some(1)))
+            .payment_curves_result(Ok(Some(PaymentCurves::from_str("1|2|3|4|5|6").unwrap())))
+            .earning_wallet_from_address_result(Ok(Some(Wallet::new(
+                "0x0123456789012345678901234567890123456789",
+            ))))
+            .mnemonic_seed_exists_result(Ok(false));
+
+        standard::unprivileged_parse_args(
+            &multi_config,
+            &mut unprivileged_config,
+            &mut holder.streams(),
+            Some(&mut persistent_config),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unprivileged_config.accountant_config.payment_curves,
+            PaymentCurves::from_str("10|20|30|40|50|60").unwrap()
+        );
+    }
+
+    #[test]
+    fn unprivileged_parse_args_handles_missing_payment_curves() {
+        let multi_config =
+            test_utils::make_multi_config(ArgsBuilder::new().param("--ip", "1.2.3.4"));
+        let mut unprivileged_config = BootstrapperConfig::new();
+        unprivileged_config.accountant_config.payment_curves =
+            PaymentCurves::from_str("1|2|3|4|5|6").unwrap();
+        let mut holder = FakeStreamHolder::new();
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .gas_price_result(Ok(Some(1)))
+            .payment_curves_result(Ok(None))
+            .earning_wallet_from_address_result(Ok(Some(Wallet::new(
+                "0x0123456789012345678901234567890123456789",
+            ))))
+            .mnemonic_seed_exists_result(Ok(false));
+
+        standard::unprivileged_parse_args(
+            &multi_config,
+            &mut unprivileged_config,
+            &mut holder.streams(),
+            Some(&mut persistent_config),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unprivileged_config.accountant_config.payment_curves,
+            DEFAULT_PAYMENT_CURVES.clone()
+        );
+    }
+
+    #[test]
+    fn unprivileged_parse_args_uses_stored_routing_strategy_when_none_is_specified() {
+        let multi_config =
+            test_utils::make_multi_config(ArgsBuilder::new().param("--ip", "1.2.3.4"));
+        let mut unprivileged_config = BootstrapperConfig::new();
+        let mut holder = FakeStreamHolder::new();
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .gas_price_result(Ok(Some(1)))
+            .routing_strategy_result(Ok(Some(RoutingStrategy::Reliable)))
+            .earning_wallet_from_address_result(Ok(Some(Wallet::new(
+                "0x0123456789012345678901234567890123456789",
+            ))))
+            .mnemonic_seed_exists_result(Ok(false));
+
+        standard::unprivileged_parse_args(
+            &multi_config,
+            &mut unprivileged_config,
+            &mut holder.streams(),
+            Some(&mut persistent_config),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unprivileged_config.routing_strategy,
+            RoutingStrategy::Reliable
+        );
+    }
+
+    #[test]
+    fn unprivileged_parse_args_prefers_specified_routing_strategy_to_stored_one() {
+        let multi_config = test_utils::make_multi_config(
+            ArgsBuilder::new()
+                .param("--ip", "1.2.3.4")
+                .param("--routing-strategy", "Random"),
+        );
+        let mut unprivileged_config = BootstrapperConfig::new();
+        let mut holder = FakeStreamHolder::new();
+        let mut persistent_config = PersistentConfigurationMock::new()
+            .gas_price_result(Ok(Some(1)))
+            .routing_strategy_result(Ok(Some(RoutingStrategy::Reliable)))
+            .earning_wallet_from_address_result(Ok(Some(Wallet::new(
+                "0x0123456789012345678901234567890123456789",
+            ))))
+            .mnemonic_seed_exists_result(Ok(false));
+
+        standard::unprivileged_parse_args(
+            &multi_config,
+            &mut unprivileged_config,
+            &mut holder.streams(),
+            Some(&mut persistent_config),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unprivileged_config.routing_strategy,
+            RoutingStrategy::Random
+        );
+    }
+
     #[test]
     fn privileged_parse_args_creates_configuration_with_defaults() {
         running_test();
@@ -1989,6 +2420,8 @@ mod tests {
         );
         assert_eq!(config.crash_point, CrashPoint::None);
         assert_eq!(config.ui_gateway_config.ui_port, DEFAULT_UI_PORT);
+        assert_eq!(config.ui_gateway_config.ui_security, UiSecurity::default());
+        assert_eq!(config.socks_port_opt, None);
         assert!(config.main_cryptde_null_opt.is_none());
         assert_eq!(
             config.real_user,
@@ -1997,12 +2430,19 @@ mod tests {
     }
 
     #[test]
-    #[cfg(not(target_os = "windows"))]
-    fn privileged_parse_args_with_real_user_defaults_data_directory_properly() {
+    fn privileged_parse_args_configures_ui_security() {
         running_test();
+        let home_dir = ensure_node_home_directory_exists(
+            "node_configurator",
+            "privileged_parse_args_configures_ui_security",
+        );
         let args = ArgsBuilder::new()
             .param("--ip", "1.2.3.4")
-            .param("--real-user", "::/home/booga");
+            .param("--data-directory", home_dir.to_str().unwrap())
+            .param("--ui-auth", "ON")
+            .param("--ui-remote-address", "0.0.0.0:5334")
+            .param("--ui-tls-identity", "/home/booga/identity.p12")
+            .param("--ui-tls-password", "secret");
         let mut config = BootstrapperConfig::new();
         let vcls: Vec<Box<dyn VirtualCommandLine>> =
             vec![Box::new(CommandLineVcl::new(args.into()))];
@@ -2016,28 +2456,189 @@ mod tests {
         )
         .unwrap();
 
-        #[cfg(target_os = "linux")]
         assert_eq!(
-            config.data_directory,
-            PathBuf::from("/home/booga/.local/share/MASQ").join(DEFAULT_CHAIN_NAME)
+            config.ui_gateway_config.ui_security,
+            UiSecurity {
+                tokens_directory_opt: Some(home_dir),
+                remote_opt: Some(UiRemoteAccess {
+                    address: SocketAddr::from_str("0.0.0.0:5334").unwrap(),
+                    tls_identity_path: PathBuf::from("/home/booga/identity.p12"),
+                    tls_identity_password: "secret".to_string(),
+                }),
+            }
         );
+    }
 
-        #[cfg(target_os = "macos")]
-        assert_eq!(
-            config.data_directory,
-            PathBuf::from("/home/booga/Library/Application Support/MASQ").join(DEFAULT_CHAIN_NAME)
+    #[test]
+    fn privileged_parse_args_rejects_socks_port_that_collides_with_another_port() {
+        running_test();
+        let try_with = |extra_args: ArgsBuilder| {
+            let mut config = BootstrapperConfig::new();
+            let vcls: Vec<Box<dyn VirtualCommandLine>> =
+                vec![Box::new(CommandLineVcl::new(extra_args.into()))];
+            let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+            standard::privileged_parse_args(
+                &RealDirsWrapper {},
+                &multi_config,
+                &mut config,
+                &mut FakeStreamHolder::new().streams(),
+            )
+        };
+
+        let clandestine_result = try_with(
+            ArgsBuilder::new()
+                .param("--clandestine-port", "1080")
+                .param("--socks-port", "1080"),
         );
-    }
+        let ui_result = try_with(
+            ArgsBuilder::new()
+                .param("--ui-port", "1080")
+                .param("--socks-port", "1080"),
+        );
+        let default_ui_result =
+            try_with(ArgsBuilder::new().param("--socks-port", &DEFAULT_UI_PORT.to_string()));
 
-    fn make_persistent_config(
-        mnemonic_seed_prefix_opt: Option<&str>,
-        db_password_opt: Option<&str>,
-        consuming_wallet_derivation_path_opt: Option<&str>,
-        earning_wallet_address_opt: Option<&str>,
-        gas_price_opt: Option<&str>,
-        past_neighbors_opt: Option<&str>,
-    ) -> PersistentConfigurationMock {
-        let (mnemonic_seed_result, mnemonic_seed_exists_result) =
+        assert_eq!(
+            clandestine_result,
+            Err(ConfiguratorError::required(
+                "socks-port",
+                "Cannot use 1080 for both --socks-port and --clandestine-port"
+            ))
+        );
+        assert_eq!(
+            ui_result,
+            Err(ConfiguratorError::required(
+                "socks-port",
+                "Cannot use 1080 for both --socks-port and --ui-port"
+            ))
+        );
+        assert_eq!(
+            default_ui_result,
+            Err(ConfiguratorError::required(
+                "socks-port",
+                &format!(
+                    "Cannot use {} for both --socks-port and --ui-port",
+                    DEFAULT_UI_PORT
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn unprivileged_parse_args_rejects_socks_port_that_collides_with_saved_clandestine_port() {
+        running_test();
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--socks-port", "1080");
+        let mut config = BootstrapperConfig::new();
+        let vcls: Vec<Box<dyn VirtualCommandLine>> =
+            vec![Box::new(CommandLineVcl::new(args.into()))];
+        let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+
+        let result = standard::unprivileged_parse_args(
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+            Some(
+                &mut make_default_persistent_configuration()
+                    .clandestine_port_result(Ok(Some(1080))),
+            ),
+        );
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "socks-port",
+                "Cannot use 1080 for both --socks-port and the clandestine port saved in the database"
+            ))
+        );
+    }
+
+    #[test]
+    fn privileged_parse_args_refuses_remote_ui_access_without_authentication_or_identity() {
+        running_test();
+        let parse = |args: ArgsBuilder| {
+            let mut config = BootstrapperConfig::new();
+            let vcls: Vec<Box<dyn VirtualCommandLine>> =
+                vec![Box::new(CommandLineVcl::new(args.into()))];
+            let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+            standard::privileged_parse_args(
+                &RealDirsWrapper {},
+                &multi_config,
+                &mut config,
+                &mut FakeStreamHolder::new().streams(),
+            )
+        };
+
+        let without_auth = parse(
+            ArgsBuilder::new()
+                .param("--ui-remote-address", "0.0.0.0:5334")
+                .param("--ui-tls-identity", "/home/booga/identity.p12"),
+        );
+        let without_identity = parse(
+            ArgsBuilder::new()
+                .param("--ui-auth", "on")
+                .param("--ui-remote-address", "0.0.0.0:5334"),
+        );
+
+        assert_eq!(
+            without_auth,
+            Err(ConfiguratorError::required(
+                "ui-remote-address",
+                "Remote UI access requires --ui-auth on"
+            ))
+        );
+        assert_eq!(
+            without_identity,
+            Err(ConfiguratorError::required(
+                "ui-tls-identity",
+                "Remote UI access requires a TLS identity"
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn privileged_parse_args_with_real_user_defaults_data_directory_properly() {
+        running_test();
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--real-user", "::/home/booga");
+        let mut config = BootstrapperConfig::new();
+        let vcls: Vec<Box<dyn VirtualCommandLine>> =
+            vec![Box::new(CommandLineVcl::new(args.into()))];
+        let multi_config = make_new_test_multi_config(&app(), vcls).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            config.data_directory,
+            PathBuf::from("/home/booga/.local/share/MASQ").join(DEFAULT_CHAIN_NAME)
+        );
+
+        #[cfg(target_os = "macos")]
+        assert_eq!(
+            config.data_directory,
+            PathBuf::from("/home/booga/Library/Application Support/MASQ").join(DEFAULT_CHAIN_NAME)
+        );
+    }
+
+    fn make_persistent_config(
+        mnemonic_seed_prefix_opt: Option<&str>,
+        db_password_opt: Option<&str>,
+        consuming_wallet_derivation_path_opt: Option<&str>,
+        earning_wallet_address_opt: Option<&str>,
+        gas_price_opt: Option<&str>,
+        past_neighbors_opt: Option<&str>,
+    ) -> PersistentConfigurationMock {
+        let (mnemonic_seed_result, mnemonic_seed_exists_result) =
             match (mnemonic_seed_prefix_opt, db_password_opt) {
                 (None, None) => (Ok(None), Ok(false)),
                 (None, Some(_)) => (Ok(None), Ok(false)),
@@ -2791,6 +3392,373 @@ mod tests {
         assert_eq!(config.blockchain_bridge_config.gas_price, 1);
     }
 
+    #[test]
+    fn unprivileged_configuration_gets_parameter_batch_payments() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_parameter_batch_payments",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--batch-payments", "On");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert!(config.blockchain_bridge_config.batch_payments);
+    }
+
+    #[test]
+    fn unprivileged_configuration_does_not_batch_payments_when_not_asked_to() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_does_not_batch_payments_when_not_asked_to",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert!(!config.blockchain_bridge_config.batch_payments);
+    }
+
+    #[test]
+    fn unprivileged_configuration_gets_parameter_fee_strategy() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_parameter_fee_strategy",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--fee-strategy", "oracle");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.blockchain_bridge_config.fee_strategy,
+            FeeStrategyKind::Oracle
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_uses_fixed_fee_strategy_when_not_provided() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_uses_fixed_fee_strategy_when_not_provided",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.blockchain_bridge_config.fee_strategy,
+            FeeStrategyKind::Fixed
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_gets_parameter_max_fee_per_gas() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_parameter_max_fee_per_gas",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--max-fee-per-gas", "150");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.blockchain_bridge_config.max_fee_per_gas_opt,
+            Some(150)
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_gets_parameter_confirmations() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_parameter_confirmations",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--confirmations", "3");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(config.blockchain_bridge_config.confirmations, 3);
+    }
+
+    #[test]
+    fn unprivileged_configuration_sets_default_confirmations_when_not_provided() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_sets_default_confirmations_when_not_provided",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.blockchain_bridge_config.confirmations,
+            DEFAULT_CONFIRMATIONS
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_gets_parameter_malefactor_ban_duration() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_parameter_malefactor_ban_duration",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--malefactor-ban-duration", "72");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.malefactor_ban_duration,
+            Duration::from_secs(72 * 3600)
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_sets_default_malefactor_ban_duration_when_not_provided() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_sets_default_malefactor_ban_duration_when_not_provided",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.malefactor_ban_duration,
+            Duration::from_secs(DEFAULT_MALEFACTOR_BAN_DURATION_HOURS * 3600)
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_gets_exit_policy_and_geoip_database() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_gets_exit_policy_and_geoip_database",
+        );
+        let geoip_path = data_dir.join("geoip.csv");
+        std::fs::write(&geoip_path, "10.0.0.0,10.255.255.255,DE\n").unwrap();
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--exit-require", "de,192.168.0.0/16")
+            .param("--exit-exclude", "10.2.0.0/16")
+            .param("--geoip-database", geoip_path.to_str().unwrap());
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.exit_policy,
+            ExitPolicy {
+                required: ExitPolicy::parse_rules("DE,192.168.0.0/16").unwrap(),
+                excluded: ExitPolicy::parse_rules("10.2.0.0/16").unwrap(),
+            }
+        );
+        assert_eq!(
+            format!("{:?}", config.geoip_database_opt),
+            "Some(GeoIpDatabase { 1 ranges })".to_string()
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_sets_empty_exit_policy_when_not_provided() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_sets_empty_exit_policy_when_not_provided",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(config.exit_policy, ExitPolicy::default());
+        assert!(config.geoip_database_opt.is_none());
+        assert_eq!(config.exit_service_policy, ExitServicePolicy::default());
+    }
+
+    #[test]
+    fn unprivileged_configuration_puts_exit_service_rules_before_default_rules() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_puts_exit_service_rules_before_default_rules",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new().param("--ip", "1.2.3.4").param(
+            "--exit-service-policy",
+            "accept 192.168.1.10:8080, reject *:6660-6669",
+        );
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.exit_service_policy,
+            ExitServicePolicy::new(
+                ExitServicePolicy::parse_rules("accept 192.168.1.10:8080, reject *:6660-6669")
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_leaves_default_exit_service_rules_out_in_zero_hop_mode() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_leaves_default_exit_service_rules_out_in_zero_hop_mode",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--neighborhood-mode", "zero-hop")
+            .param("--exit-service-policy", "reject *:6660-6669");
+        let args_vec: Vec<String> = args.into();
+
+        let config = subject
+            .configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams())
+            .unwrap();
+
+        assert_eq!(
+            config.exit_service_policy,
+            ExitServicePolicy::without_defaults(
+                ExitServicePolicy::parse_rules("reject *:6660-6669").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn unprivileged_configuration_uses_bundled_geoip_database_for_country_exit_rules() {
+        running_test();
+        let _clap_guard = ClapGuard::new();
+        let data_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "unprivileged_configuration_uses_bundled_geoip_database_for_country_exit_rules",
+        );
+        let mut subject = NodeConfiguratorStandardUnprivileged::new(&BootstrapperConfig::new());
+        subject.privileged_config = BootstrapperConfig::new();
+        subject.privileged_config.data_directory = data_dir;
+        let args = ArgsBuilder::new()
+            .param("--ip", "1.2.3.4")
+            .param("--exit-exclude", "CN");
+        let args_vec: Vec<String> = args.into();
+
+        let result = subject.configure(args_vec.as_slice(), &mut FakeStreamHolder::new().streams());
+
+        // Whether there's a bundled database depends on whether the build found one.
+        if GeoIpDatabase::is_bundled() {
+            assert!(result.unwrap().geoip_database_opt.is_some());
+        } else {
+            assert_eq!(
+                result.err(),
+                Some(ConfiguratorError::required(
+                    "geoip-database",
+                    "Country codes in --exit-require or --exit-exclude need a GeoIP database, and this Node was built without one"
+                ))
+            );
+        }
+    }
+
     #[test]
     fn privileged_configuration_rejects_invalid_gas_price() {
         running_test();
@@ -2824,15 +3792,22 @@ mod tests {
         config.earning_wallet = Wallet::new(earning_address);
         config.consuming_wallet = Some(Wallet::from(keypair));
         config.blockchain_bridge_config.gas_price = gas_price;
+        let payment_curves = PaymentCurves::from_str("1|2|3|4|5|6").unwrap();
+        config.accountant_config.payment_curves = payment_curves.clone();
         let set_clandestine_port_params_arc = Arc::new(Mutex::new(vec![]));
         let set_gas_price_params_arc = Arc::new(Mutex::new(vec![]));
+        let set_payment_curves_params_arc = Arc::new(Mutex::new(vec![]));
+        config.routing_strategy = RoutingStrategy::Reliable;
+        let set_routing_strategy_params_arc = Arc::new(Mutex::new(vec![]));
         let mut persistent_config = PersistentConfigurationMock::new()
             .earning_wallet_address_result(Ok(None))
             .consuming_wallet_derivation_path_result(Ok(None))
             .set_clandestine_port_params(&set_clandestine_port_params_arc)
             .set_clandestine_port_result(Ok(()))
             .set_gas_price_params(&set_gas_price_params_arc)
-            .set_gas_price_result(Ok(()));
+            .set_gas_price_result(Ok(()))
+            .set_payment_curves_params(&set_payment_curves_params_arc)
+            .set_routing_strategy_params(&set_routing_strategy_params_arc);
 
         let result = standard::configure_database(&config, &mut persistent_config);
 
@@ -2841,6 +3816,13 @@ mod tests {
         assert_eq!(*set_clandestine_port_params, vec![1234]);
         let set_gas_price_params = set_gas_price_params_arc.lock().unwrap();
         assert_eq!(*set_gas_price_params, vec![gas_price]);
+        let set_payment_curves_params = set_payment_curves_params_arc.lock().unwrap();
+        assert_eq!(*set_payment_curves_params, vec![payment_curves]);
+        let set_routing_strategy_params = set_routing_strategy_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_routing_strategy_params,
+            vec![RoutingStrategy::Reliable]
+        );
     }
 
     #[test]
@@ -16,6 +16,7 @@ use crate::proxy_client::stream_handler_pool::StreamHandlerPoolFactoryReal;
 use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::cryptde::PublicKey;
+use crate::sub_lib::exit_service_policy::ExitServicePolicy;
 use crate::sub_lib::hopper::MessageType;
 use crate::sub_lib::hopper::{ExpiredCoresPackage, IncipientCoresPackage};
 use crate::sub_lib::logger::Logger;
@@ -23,7 +24,9 @@ use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::proxy_client::InboundServerData;
 use crate::sub_lib::proxy_client::ProxyClientConfig;
 use crate::sub_lib::proxy_client::ProxyClientSubs;
-use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+use crate::sub_lib::proxy_client::{
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1,
+};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::route::Route;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
@@ -57,6 +60,7 @@ pub struct ProxyClient {
     stream_contexts: HashMap<StreamKey, StreamContext>,
     exit_service_rate: u64,
     exit_byte_rate: u64,
+    exit_service_policy: ExitServicePolicy,
     logger: Logger,
 }
 
@@ -90,6 +94,7 @@ impl Handler<BindMessage> for ProxyClient {
             msg.peer_actors.proxy_client,
             self.exit_service_rate,
             self.exit_byte_rate,
+            self.exit_service_policy.clone(),
         ));
     }
 }
@@ -176,34 +181,35 @@ impl Handler<DnsResolveFailure_0v1> for ProxyClient {
 
     fn handle(&mut self, msg: DnsResolveFailure_0v1, _ctx: &mut Self::Context) -> Self::Result {
         let stream_key = msg.stream_key;
-        let stream_context_opt = self.stream_contexts.get(&stream_key);
-        match stream_context_opt {
-            Some(stream_context) => {
-                let package = IncipientCoresPackage::new(
-                    self.cryptde,
-                    stream_context.return_route.clone(),
-                    MessageType::DnsResolveFailed(VersionedData::new(
-                        &crate::sub_lib::migrations::dns_resolve_failure::MIGRATIONS,
-                        &msg,
-                    )),
-                    &stream_context.payload_destination_key,
-                )
-                .expect("Failed to create IncipientCoresPackage");
-                self.to_hopper
-                    .as_ref()
-                    .expect("Hopper is unbound")
-                    .try_send(package)
-                    .expect("Hopper is dead");
-                debug!(
-                    self.logger,
-                    "Removing stream key {} for DnsResolveFailure", stream_key
-                );
-                self.stream_contexts.remove(&stream_key);
-            }
-            None => error!(
+        if self.send_failure_to_originator(&stream_key, msg.into()) {
+            debug!(
                 self.logger,
-                "DNS resolution for nonexistent stream ({:?}) failed.", msg.stream_key
-            ),
+                "Removing stream key {} for DnsResolveFailure", stream_key
+            );
+        } else {
+            error!(
+                self.logger,
+                "DNS resolution for nonexistent stream ({:?}) failed.", stream_key
+            )
+        }
+    }
+}
+
+impl Handler<ExitRefusal_0v1> for ProxyClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: ExitRefusal_0v1, _ctx: &mut Self::Context) -> Self::Result {
+        let stream_key = msg.stream_key;
+        if self.send_failure_to_originator(&stream_key, msg.into()) {
+            debug!(
+                self.logger,
+                "Removing stream key {} for ExitRefusal", stream_key
+            );
+        } else {
+            error!(
+                self.logger,
+                "Refused nonexistent stream ({:?}) by exit service policy.", stream_key
+            )
         }
     }
 }
@@ -224,6 +230,7 @@ impl ProxyClient {
             stream_contexts: HashMap::new(),
             exit_service_rate: config.exit_service_rate,
             exit_byte_rate: config.exit_byte_rate,
+            exit_service_policy: config.exit_service_policy,
             logger: Logger::new("ProxyClient"),
         }
     }
@@ -236,9 +243,30 @@ impl ProxyClient {
                 .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
             inbound_server_data: addr.clone().recipient::<InboundServerData>(),
             dns_resolve_failed: addr.clone().recipient::<DnsResolveFailure_0v1>(),
+            exit_refused: addr.clone().recipient::<ExitRefusal_0v1>(),
         }
     }
 
+    fn send_failure_to_originator(&mut self, stream_key: &StreamKey, payload: MessageType) -> bool {
+        let stream_context = match self.stream_contexts.remove(stream_key) {
+            Some(stream_context) => stream_context,
+            None => return false,
+        };
+        let package = IncipientCoresPackage::new(
+            self.cryptde,
+            stream_context.return_route,
+            payload,
+            &stream_context.payload_destination_key,
+        )
+        .expect("Failed to create IncipientCoresPackage");
+        self.to_hopper
+            .as_ref()
+            .expect("Hopper is unbound")
+            .try_send(package)
+            .expect("Hopper is dead");
+        true
+    }
+
     fn send_response_to_hopper(
         &self,
         msg: InboundServerData,
@@ -401,6 +429,7 @@ mod tests {
                     ProxyClientSubs,
                     u64,
                     u64,
+                    ExitServicePolicy,
                 )>,
             >,
         >,
@@ -416,6 +445,7 @@ mod tests {
             proxy_client_subs: ProxyClientSubs,
             exit_service_rate: u64,
             exit_byte_rate: u64,
+            exit_service_policy: ExitServicePolicy,
         ) -> Box<dyn StreamHandlerPool> {
             self.make_parameters.lock().unwrap().push((
                 resolver,
@@ -424,6 +454,7 @@ mod tests {
                 proxy_client_subs,
                 exit_service_rate,
                 exit_byte_rate,
+                exit_service_policy,
             ));
             self.make_results.borrow_mut().remove(0)
         }
@@ -448,6 +479,7 @@ mod tests {
                         ProxyClientSubs,
                         u64,
                         u64,
+                        ExitServicePolicy,
                     )>,
                 >,
             >,
@@ -475,6 +507,7 @@ mod tests {
             dns_servers: vec![],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
     }
 
@@ -494,6 +527,8 @@ mod tests {
             .make_parameters(&mut pool_factory_make_parameters)
             .make_result(Box::new(pool));
         let peer_actors = peer_actors_builder().build();
+        let exit_service_policy =
+            ExitServicePolicy::new(ExitServicePolicy::parse_rules("reject *:22").unwrap());
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde(),
             dns_servers: vec![
@@ -502,6 +537,7 @@ mod tests {
             ],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: exit_service_policy.clone(),
         });
         subject.resolver_wrapper_factory = Box::new(resolver_wrapper_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -534,6 +570,12 @@ mod tests {
         );
         assert_eq!(opts, ResolverOpts::default());
         assert_eq!(resolver_wrapper_new_parameters.is_empty(), true);
+        let pool_factory_make_parameters = pool_factory_make_parameters.lock().unwrap();
+        let (_, _, _, _, exit_service_rate, exit_byte_rate, actual_exit_service_policy) =
+            &pool_factory_make_parameters[0];
+        assert_eq!(*exit_service_rate, 100);
+        assert_eq!(*exit_byte_rate, 200);
+        assert_eq!(*actual_exit_service_policy, exit_service_policy);
     }
 
     #[test]
@@ -565,6 +607,7 @@ mod tests {
             dns_servers: dnss(),
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         let subject_addr: Addr<ProxyClient> = subject.start();
 
@@ -587,6 +630,7 @@ mod tests {
                 dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
                 exit_service_rate: 0,
                 exit_byte_rate: 0,
+                exit_service_policy: ExitServicePolicy::default(),
             });
             let subject_addr = subject.start();
             let subject_subs = ProxyClient::make_subs_from(&subject_addr);
@@ -626,6 +670,7 @@ mod tests {
                 dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
                 exit_service_rate: 0,
                 exit_byte_rate: 0,
+                exit_service_policy: ExitServicePolicy::default(),
             });
             subject.stream_contexts.insert(
                 stream_key_inner,
@@ -673,6 +718,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn forwards_exit_refusal_to_hopper() {
+        init_test_logging();
+        let cryptde = main_cryptde();
+        let (hopper, hopper_awaiter, hopper_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let return_route = make_meaningless_route();
+        let originator_key = make_meaningless_public_key();
+        let stream_key_inner = stream_key.clone();
+        let return_route_inner = return_route.clone();
+        let originator_key_inner = originator_key.clone();
+        thread::spawn(move || {
+            let system = System::new("forwards_exit_refusal_to_hopper");
+            let peer_actors = peer_actors_builder().hopper(hopper).build();
+            let mut subject = ProxyClient::new(ProxyClientConfig {
+                cryptde,
+                dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
+                exit_service_rate: 0,
+                exit_byte_rate: 0,
+                exit_service_policy: ExitServicePolicy::default(),
+            });
+            subject.stream_contexts.insert(
+                stream_key_inner,
+                StreamContext {
+                    return_route: return_route_inner,
+                    payload_destination_key: originator_key_inner,
+                    paying_wallet: None,
+                },
+            );
+            let subject_addr = subject.start();
+            let subject_subs = ProxyClient::make_subs_from(&subject_addr);
+
+            send_bind_message!(subject_subs, peer_actors);
+
+            subject_subs
+                .exit_refused
+                .try_send(ExitRefusal_0v1::new(stream_key_inner))
+                .unwrap();
+
+            subject_subs
+                .exit_refused
+                .try_send(ExitRefusal_0v1::new(stream_key_inner))
+                .unwrap();
+
+            system.run();
+        });
+
+        hopper_awaiter.await_message_count(1);
+
+        let message_type: MessageType = ExitRefusal_0v1::new(stream_key).into();
+        assert_eq!(
+            &IncipientCoresPackage::new(cryptde, return_route, message_type, &originator_key)
+                .unwrap(),
+            hopper_recording_arc
+                .lock()
+                .unwrap()
+                .get_record::<IncipientCoresPackage>(0)
+        );
+        TestLogHandler::new().await_log_containing(
+            &format!(
+                "ERROR: ProxyClient: Refused nonexistent stream ({:?}) by exit service policy.",
+                stream_key
+            ),
+            1000,
+        );
+    }
+
     #[test]
     fn data_from_hopper_is_relayed_to_stream_handler_pool() {
         let cryptde = main_cryptde();
@@ -713,6 +825,7 @@ mod tests {
             dns_servers: dnss(),
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -768,6 +881,7 @@ mod tests {
             dns_servers: dnss(),
             exit_service_rate: rate_pack_exit(100),
             exit_byte_rate: rate_pack_exit_byte(100),
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -833,6 +947,7 @@ mod tests {
             dns_servers: dnss(),
             exit_service_rate: rate_pack_exit(100),
             exit_byte_rate: rate_pack_exit_byte(100),
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -860,6 +975,7 @@ mod tests {
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -996,6 +1112,7 @@ mod tests {
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -1046,6 +1163,7 @@ mod tests {
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -1094,6 +1212,7 @@ mod tests {
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_service_policy: ExitServicePolicy::default(),
         });
         let mut process_package_params_arc = Arc::new(Mutex::new(vec![]));
         let pool = StreamHandlerPoolMock::new()
@@ -7,9 +7,10 @@ use crate::proxy_client::stream_establisher::{StreamEstablisher, StreamEstablish
 use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
 use crate::sub_lib::channel_wrappers::SenderWrapper;
 use crate::sub_lib::cryptde::CryptDE;
+use crate::sub_lib::exit_service_policy::ExitServicePolicy;
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::proxy_client::{error_socket_addr, ProxyClientSubs};
-use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, InboundServerData};
+use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, ExitRefusal_0v1, InboundServerData};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
 use crate::sub_lib::stream_key::StreamKey;
@@ -49,6 +50,7 @@ struct StreamHandlerPoolRealInner {
     establisher_factory: Box<dyn StreamEstablisherFactory>,
     exit_service_rate: u64,
     exit_byte_rate: u64,
+    exit_service_policy: ExitServicePolicy,
 }
 
 impl StreamHandlerPool for StreamHandlerPoolReal {
@@ -69,6 +71,7 @@ impl StreamHandlerPoolReal {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        exit_service_policy: ExitServicePolicy,
     ) -> StreamHandlerPoolReal {
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
@@ -88,6 +91,7 @@ impl StreamHandlerPoolReal {
                 logger: Logger::new("ProxyClient"),
                 exit_service_rate,
                 exit_byte_rate,
+                exit_service_policy,
             })),
             stream_adder_rx,
             stream_killer_rx,
@@ -227,15 +231,37 @@ impl StreamHandlerPoolReal {
         );
 
         match payload.target_hostname {
-            Some(ref target_hostname) => match Self::parse_ip(target_hostname) {
-                Ok(socket_addr) => Self::handle_ip(
-                    payload.clone(),
-                    socket_addr,
-                    inner_arc,
-                    target_hostname.to_string(),
-                ),
-                Err(_) => Self::lookup_dns(inner_arc, target_hostname.to_string(), payload.clone()),
-            },
+            Some(ref target_hostname) => {
+                let ip_addr_result = Self::parse_ip(target_hostname);
+                let refusal_opt = {
+                    let inner = inner_arc.lock().expect("Stream handler pool is poisoned");
+                    let verdict = match ip_addr_result {
+                        Ok(ip_addr) => {
+                            inner
+                                .exit_service_policy
+                                .check(None, ip_addr, payload.target_port)
+                        }
+                        Err(_) => inner
+                            .exit_service_policy
+                            .check_before_resolution(target_hostname, payload.target_port),
+                    };
+                    verdict.err().map(|rule| rule.to_string())
+                };
+                if let Some(rule) = refusal_opt {
+                    return Self::refuse_stream(&inner_arc, payload, target_hostname, &rule);
+                }
+                match ip_addr_result {
+                    Ok(ip_addr) => Self::handle_ip(
+                        payload.clone(),
+                        ip_addr,
+                        inner_arc,
+                        target_hostname.to_string(),
+                    ),
+                    Err(_) => {
+                        Self::lookup_dns(inner_arc, target_hostname.to_string(), payload.clone())
+                    }
+                }
+            }
             None => {
                 error!(
                     logger,
@@ -250,6 +276,35 @@ impl StreamHandlerPoolReal {
         }
     }
 
+    fn refuse_stream(
+        inner_arc: &Arc<Mutex<StreamHandlerPoolRealInner>>,
+        payload: &ClientRequestPayload_0v1,
+        target_hostname: &str,
+        rule: &str,
+    ) -> StreamEstablisherResult {
+        let inner = inner_arc.lock().expect("Stream handler pool is poisoned");
+        warning!(
+            inner.logger,
+            "Refusing stream {:?} to {}:{} by exit service policy rule '{}'",
+            payload.stream_key,
+            target_hostname,
+            payload.target_port,
+            rule
+        );
+        inner
+            .proxy_client_subs
+            .exit_refused
+            .try_send(ExitRefusal_0v1::new(payload.stream_key))
+            .expect("ProxyClient is dead");
+        Box::new(future::err::<
+            Box<dyn SenderWrapper<SequencedPacket> + 'static>,
+            String,
+        >(format!(
+            "Destination {}:{} refused by exit service policy",
+            target_hostname, payload.target_port
+        )))
+    }
+
     fn parse_ip(hostname: &str) -> Result<IpAddr, AddrParseError> {
         let socket_ip = SocketAddr::from_str(hostname).map(|sa| sa.ip());
         if socket_ip.is_ok() {
@@ -287,12 +342,14 @@ impl StreamHandlerPoolReal {
         payload: ClientRequestPayload_0v1,
     ) -> StreamEstablisherResult {
         let fqdn = Self::make_fqdn(&target_hostname);
-        let dns_resolve_failed_sub = inner_arc
-            .lock()
-            .expect("Stream handler pool is poisoned")
-            .proxy_client_subs
-            .dns_resolve_failed
-            .clone();
+        let (dns_resolve_failed_sub, exit_refused_sub, exit_service_policy) = {
+            let inner = inner_arc.lock().expect("Stream handler pool is poisoned");
+            (
+                inner.proxy_client_subs.dns_resolve_failed.clone(),
+                inner.proxy_client_subs.exit_refused.clone(),
+                inner.exit_service_policy.clone(),
+            )
+        };
         let mut establisher = StreamHandlerPoolReal::make_establisher(inner_arc.clone());
         let stream_key = payload.stream_key;
         let logger = StreamHandlerPoolReal::make_logger_copy(&inner_arc);
@@ -313,6 +370,8 @@ impl StreamHandlerPoolReal {
                         target_hostname.to_string(),
                         &payload,
                         lookup_result,
+                        &exit_service_policy,
+                        &exit_refused_sub,
                         logger,
                         &mut establisher,
                     )
@@ -325,6 +384,8 @@ impl StreamHandlerPoolReal {
         target_hostname: String,
         payload: &ClientRequestPayload_0v1,
         lookup_result: Result<LookupIp, ResolveError>,
+        exit_service_policy: &ExitServicePolicy,
+        exit_refused_sub: &Recipient<ExitRefusal_0v1>,
         logger: Logger,
         establisher: &mut StreamEstablisher,
     ) -> io::Result<Box<dyn SenderWrapper<SequencedPacket>>> {
@@ -342,7 +403,38 @@ impl StreamHandlerPoolReal {
             logger,
             "Found IP addresses for {}: {:?}", target_hostname, &ip_addrs
         );
-        establisher.establish_stream(&payload, ip_addrs, target_hostname)
+        let (permitted_ip_addrs, refusals): (Vec<IpAddr>, Vec<String>) = ip_addrs.iter().fold(
+            (vec![], vec![]),
+            |(mut permitted, mut refusals), ip_addr| {
+                match exit_service_policy.check(
+                    Some(&target_hostname),
+                    *ip_addr,
+                    payload.target_port,
+                ) {
+                    Ok(()) => permitted.push(*ip_addr),
+                    Err(rule) => refusals.push(format!("{} by '{}'", ip_addr, rule)),
+                }
+                (permitted, refusals)
+            },
+        );
+        if permitted_ip_addrs.is_empty() && !refusals.is_empty() {
+            warning!(
+                logger,
+                "Refusing stream {:?} to {}:{} by exit service policy: {}",
+                payload.stream_key,
+                target_hostname,
+                payload.target_port,
+                refusals.join(", ")
+            );
+            exit_refused_sub
+                .try_send(ExitRefusal_0v1::new(payload.stream_key))
+                .expect("ProxyClient is dead");
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "Refused by exit service policy",
+            ));
+        }
+        establisher.establish_stream(&payload, permitted_ip_addrs, target_hostname)
     }
 
     fn make_fqdn(target_hostname: &str) -> String {
@@ -459,6 +551,7 @@ pub trait StreamHandlerPoolFactory {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        exit_service_policy: ExitServicePolicy,
     ) -> Box<dyn StreamHandlerPool>;
 }
 
@@ -473,6 +566,7 @@ impl StreamHandlerPoolFactory for StreamHandlerPoolFactoryReal {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        exit_service_policy: ExitServicePolicy,
     ) -> Box<dyn StreamHandlerPool> {
         Box::new(StreamHandlerPoolReal::new(
             resolver,
@@ -481,6 +575,7 @@ impl StreamHandlerPoolFactory for StreamHandlerPoolFactoryReal {
             proxy_client_subs,
             exit_service_rate,
             exit_byte_rate,
+            exit_service_policy,
         ))
     }
 }
@@ -584,6 +679,7 @@ mod tests {
                 }),
                 exit_service_rate: Default::default(),
                 exit_byte_rate: Default::default(),
+                exit_service_policy: ExitServicePolicy::default(),
             };
             let payload = ClientRequestPayload_0v1 {
                 stream_key,
@@ -650,6 +746,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             subject
                 .inner
@@ -711,6 +808,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             subject
                 .inner
@@ -793,6 +891,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -902,6 +1001,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -989,6 +1089,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
 
             run_process_package_in_actix(subject, package);
@@ -1073,6 +1174,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -1166,6 +1268,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -1266,6 +1369,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
 
             let peer_addr = SocketAddr::from_str("3.4.5.6:80").unwrap();
@@ -1359,6 +1463,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             run_process_package_in_actix(subject, package);
         });
@@ -1380,6 +1485,135 @@ mod tests {
         );
     }
 
+    fn process_package_under_exit_service_policy(
+        target_hostname: &str,
+        exit_service_policy: ExitServicePolicy,
+        resolved_ip_addrs: Vec<IpAddr>,
+    ) -> (Vec<String>, ExitRefusal_0v1, InboundServerData) {
+        let cryptde = main_cryptde();
+        let stream_key = make_meaningless_stream_key();
+        let (proxy_client, proxy_client_awaiter, proxy_client_recording_arc) = make_recorder();
+        let lookup_ip_parameters = Arc::new(Mutex::new(vec![]));
+        let lookup_ip_parameters_inner = lookup_ip_parameters.clone();
+        let target_hostname = target_hostname.to_string();
+        thread::spawn(move || {
+            let client_request_payload = ClientRequestPayload_0v1 {
+                stream_key,
+                sequenced_packet: SequencedPacket {
+                    data: b"These are the times".to_vec(),
+                    sequence_number: 0,
+                    last_data: false,
+                },
+                target_hostname: Some(target_hostname),
+                target_port: HTTP_PORT,
+                protocol: ProxyProtocol::HTTP,
+                originator_public_key: PublicKey::new(&b"men's souls"[..]),
+            };
+            let package = ExpiredCoresPackage::new(
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+                Some(make_wallet("consuming")),
+                make_meaningless_route(),
+                client_request_payload.into(),
+                0,
+            );
+            let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+            let resolver = ResolverWrapperMock::new()
+                .lookup_ip_parameters(&lookup_ip_parameters_inner)
+                .lookup_ip_success(resolved_ip_addrs);
+            let subject = StreamHandlerPoolReal::new(
+                Box::new(resolver),
+                cryptde,
+                peer_actors.accountant.report_exit_service_provided.clone(),
+                peer_actors.proxy_client.clone(),
+                100,
+                200,
+                exit_service_policy,
+            );
+            run_process_package_in_actix(subject, package);
+        });
+        proxy_client_awaiter.await_message_count(2);
+        let recording = proxy_client_recording_arc.lock().unwrap();
+        let lookup_ip_parameters = lookup_ip_parameters.lock().unwrap().clone();
+        (
+            lookup_ip_parameters,
+            recording.get_record::<ExitRefusal_0v1>(0).clone(),
+            recording.get_record::<InboundServerData>(1).clone(),
+        )
+    }
+
+    #[test]
+    fn exit_service_policy_refuses_address_without_looking_it_up() {
+        init_test_logging();
+
+        let (lookup_ip_parameters, exit_refusal, inbound_server_data) =
+            process_package_under_exit_service_policy(
+                "127.0.0.1",
+                ExitServicePolicy::default(),
+                vec![],
+            );
+
+        assert!(lookup_ip_parameters.is_empty());
+        assert_eq!(
+            exit_refusal,
+            ExitRefusal_0v1::new(make_meaningless_stream_key())
+        );
+        assert_eq!(inbound_server_data.last_data, true);
+        assert_eq!(inbound_server_data.data, Vec::<u8>::new());
+        TestLogHandler::new().exists_log_containing(
+            "to 127.0.0.1:80 by exit service policy rule 'reject 127.0.0.0/8:*'",
+        );
+    }
+
+    #[test]
+    fn exit_service_policy_refuses_host_name_without_looking_it_up() {
+        init_test_logging();
+        let exit_service_policy = ExitServicePolicy::new(
+            ExitServicePolicy::parse_rules("reject *.corp.example.com").unwrap(),
+        );
+
+        let (lookup_ip_parameters, exit_refusal, _) = process_package_under_exit_service_policy(
+            "mail.corp.example.com",
+            exit_service_policy,
+            vec![IpAddr::from_str("2.3.4.5").unwrap()],
+        );
+
+        assert!(lookup_ip_parameters.is_empty());
+        assert_eq!(
+            exit_refusal,
+            ExitRefusal_0v1::new(make_meaningless_stream_key())
+        );
+        TestLogHandler::new().exists_log_containing(
+            "to mail.corp.example.com:80 by exit service policy rule 'reject *.corp.example.com:*'",
+        );
+    }
+
+    #[test]
+    fn exit_service_policy_refuses_host_name_that_resolves_only_to_refused_addresses() {
+        init_test_logging();
+
+        let (lookup_ip_parameters, exit_refusal, _) = process_package_under_exit_service_policy(
+            "intranet.example.com",
+            ExitServicePolicy::default(),
+            vec![
+                IpAddr::from_str("10.1.2.3").unwrap(),
+                IpAddr::from_str("192.168.1.1").unwrap(),
+            ],
+        );
+
+        assert_eq!(
+            lookup_ip_parameters,
+            vec!["intranet.example.com.".to_string()]
+        );
+        assert_eq!(
+            exit_refusal,
+            ExitRefusal_0v1::new(make_meaningless_stream_key())
+        );
+        TestLogHandler::new().exists_log_containing(
+            "to intranet.example.com:80 by exit service policy: 10.1.2.3 by 'reject 10.0.0.0/8:*', \
+             192.168.1.1 by 'reject 192.168.0.0/16:*'",
+        );
+    }
+
     #[test]
     fn error_from_tx_to_writer_removes_stream() {
         init_test_logging();
@@ -1427,6 +1661,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
             subject
                 .inner
@@ -1484,6 +1719,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                ExitServicePolicy::default(),
             );
 
             subject.inner.lock().unwrap().establisher_factory =
@@ -1520,6 +1756,7 @@ mod tests {
             peer_actors.proxy_client,
             0,
             0,
+            ExitServicePolicy::default(),
         );
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         subject.stream_killer_rx = stream_killer_rx;
@@ -1563,6 +1800,7 @@ mod tests {
             peer_actors.proxy_client,
             0,
             0,
+            ExitServicePolicy::default(),
         );
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         subject.stream_killer_rx = stream_killer_rx;
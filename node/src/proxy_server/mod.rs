@@ -5,11 +5,13 @@ pub mod http_protocol_pack;
 pub mod protocol_pack;
 pub mod server_impersonator_http;
 pub mod server_impersonator_tls;
+pub mod socks5;
 pub mod tls_protocol_pack;
 
 use crate::proxy_server::client_request_payload_factory::ClientRequestPayloadFactory;
 use crate::proxy_server::http_protocol_pack::HttpProtocolPack;
 use crate::proxy_server::protocol_pack::{from_ibcd, from_protocol, ProtocolPack};
+use crate::proxy_server::socks5::{Socks5Session, SocksProgress, SocksReplyCode};
 use crate::stream_messages::NonClandestineAttributes;
 use crate::stream_messages::RemovedStreamType;
 use crate::sub_lib::accountant::ReportExitServiceConsumedMessage;
@@ -27,13 +29,16 @@ use crate::sub_lib::neighborhood::RouteQueryResponse;
 use crate::sub_lib::neighborhood::{ExpectedService, NodeRecordMetadataMessage};
 use crate::sub_lib::neighborhood::{ExpectedServices, DEFAULT_RATE_PACK};
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+use crate::sub_lib::proxy_client::{
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1,
+};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::proxy_server::ProxyServerSubs;
 use crate::sub_lib::proxy_server::{
     AddReturnRouteMessage, AddRouteMessage, DEFAULT_MINIMUM_HOP_COUNT,
 };
 use crate::sub_lib::route::Route;
+use crate::sub_lib::sequence_buffer::SequencedPacket;
 use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
 use crate::sub_lib::stream_key::StreamKey;
@@ -44,16 +49,19 @@ use actix::Actor;
 use actix::Addr;
 use actix::Context;
 use actix::Handler;
+use actix::Message;
 use actix::Recipient;
 use pretty_hex::PrettyHex;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::rc::Rc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::prelude::Future;
 
 pub const CRASH_KEY: &str = "PROXYSERVER";
 pub const RETURN_ROUTE_TTL: Duration = Duration::from_secs(120);
+// A route that hasn't brought back any response in this long has failed
+pub const ROUTE_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
 
 struct ProxyServerOutSubs {
     dispatcher: Recipient<TransmitDataMsg>,
@@ -74,6 +82,7 @@ pub struct ProxyServer {
     keys_and_addrs: BidiHashMap<StreamKey, SocketAddr>,
     tunneled_hosts: HashMap<StreamKey, String>,
     stream_key_routes: HashMap<StreamKey, RouteQueryResponse>,
+    stream_key_send_times: HashMap<StreamKey, Instant>,
     is_decentralized: bool,
     consuming_wallet_balance: Option<i64>,
     main_cryptde: &'static dyn CryptDE,
@@ -81,6 +90,8 @@ pub struct ProxyServer {
     logger: Logger,
     route_ids_to_return_routes: TtlHashMap<u32, AddReturnRouteMessage>,
     browser_proxy_sequence_offset: bool,
+    socks_port_opt: Option<u16>,
+    socks_sessions: HashMap<StreamKey, Socks5Session>,
 }
 
 impl Actor for ProxyServer {
@@ -123,7 +134,9 @@ impl Handler<InboundClientData> for ProxyServer {
     type Result = ();
 
     fn handle(&mut self, msg: InboundClientData, _ctx: &mut Self::Context) -> Self::Result {
-        if msg.is_connect() {
+        if self.socks_port_opt.is_some() && msg.reception_port == self.socks_port_opt {
+            self.handle_socks_client_data(msg);
+        } else if msg.is_connect() {
             self.tls_connect(&msg);
             self.browser_proxy_sequence_offset = true;
         } else {
@@ -159,9 +172,32 @@ impl AddReturnRouteMessage {
 impl Handler<AddRouteMessage> for ProxyServer {
     type Result = ();
 
-    fn handle(&mut self, msg: AddRouteMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: AddRouteMessage, ctx: &mut Self::Context) -> Self::Result {
         debug!(self.logger, "Establishing stream key {}", msg.stream_key);
+        let send_time = Instant::now();
         self.stream_key_routes.insert(msg.stream_key, msg.route);
+        self.stream_key_send_times.insert(msg.stream_key, send_time);
+        ctx.notify_later(
+            RouteResponseTimeout {
+                stream_key: msg.stream_key,
+                send_time,
+            },
+            ROUTE_RESPONSE_TIMEOUT,
+        );
+    }
+}
+
+#[derive(Message, Clone, Debug, PartialEq)]
+struct RouteResponseTimeout {
+    stream_key: StreamKey,
+    send_time: Instant,
+}
+
+impl Handler<RouteResponseTimeout> for ProxyServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: RouteResponseTimeout, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_route_response_timeout(msg)
     }
 }
 
@@ -177,6 +213,18 @@ impl Handler<ExpiredCoresPackage<DnsResolveFailure_0v1>> for ProxyServer {
     }
 }
 
+impl Handler<ExpiredCoresPackage<ExitRefusal_0v1>> for ProxyServer {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: ExpiredCoresPackage<ExitRefusal_0v1>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.handle_exit_refusal(&msg)
+    }
+}
+
 impl Handler<ExpiredCoresPackage<ClientResponsePayload_0v1>> for ProxyServer {
     type Result = ();
 
@@ -203,6 +251,7 @@ impl ProxyServer {
         alias_cryptde: &'static dyn CryptDE,
         is_decentralized: bool,
         consuming_wallet_balance: Option<i64>,
+        socks_port_opt: Option<u16>,
     ) -> ProxyServer {
         ProxyServer {
             subs: None,
@@ -211,6 +260,7 @@ impl ProxyServer {
             keys_and_addrs: BidiHashMap::new(),
             tunneled_hosts: HashMap::new(),
             stream_key_routes: HashMap::new(),
+            stream_key_send_times: HashMap::new(),
             is_decentralized,
             consuming_wallet_balance,
             main_cryptde,
@@ -218,6 +268,8 @@ impl ProxyServer {
             logger: Logger::new("ProxyServer"),
             route_ids_to_return_routes: TtlHashMap::new(RETURN_ROUTE_TTL),
             browser_proxy_sequence_offset: false,
+            socks_port_opt,
+            socks_sessions: HashMap::new(),
         }
     }
 
@@ -231,6 +283,9 @@ impl ProxyServer {
             dns_failure_from_hopper: addr
                 .clone()
                 .recipient::<ExpiredCoresPackage<DnsResolveFailure_0v1>>(),
+            exit_refusal_from_hopper: addr
+                .clone()
+                .recipient::<ExpiredCoresPackage<ExitRefusal_0v1>>(),
             add_return_route: addr.clone().recipient::<AddReturnRouteMessage>(),
             add_route: addr.clone().recipient::<AddRouteMessage>(),
             stream_shutdown_sub: addr.clone().recipient::<StreamShutdownMsg>(),
@@ -243,35 +298,14 @@ impl ProxyServer {
             Some(rri) => rri,
             None => return, // TODO: Eventually we'll have to do something better here, but we'll probably need some heuristics.
         };
-        let exit_public_key = {
-            // ugly, ugly
-            let self_public_key = self.main_cryptde.public_key();
-            return_route_info
-                .find_exit_node_key()
-                .unwrap_or_else(|| {
-                    if return_route_info.is_zero_hop() {
-                        &self_public_key
-                    } else {
-                        panic!(
-                            "Internal error: return_route_info for {} has no exit Node",
-                            return_route_info.return_route_id
-                        );
-                    }
-                })
-                .clone()
-        };
+        let exit_public_key = self.find_exit_public_key(&return_route_info);
         let response = &msg.payload;
         match self.keys_and_addrs.a_to_b(&response.stream_key) {
             Some(socket_addr) => {
-                self.subs
-                    .as_ref()
-                    .expect("Neighborhood unbound in ProxyServer")
-                    .update_node_record_metadata
-                    .try_send(NodeRecordMetadataMessage::Desirable(
-                        exit_public_key.clone(),
-                        false,
-                    ))
-                    .expect("Neighborhood is dead");
+                self.stream_key_send_times.remove(&response.stream_key);
+                self.report_node_record_metadata(NodeRecordMetadataMessage::DnsFailure(
+                    exit_public_key.clone(),
+                ));
 
                 self.report_response_services_consumed(&return_route_info, 0, msg.payload_len);
 
@@ -282,7 +316,8 @@ impl ProxyServer {
                     .try_send(TransmitDataMsg {
                         endpoint: Endpoint::Socket(socket_addr),
                         last_data: true,
-                        sequence_number: Some(0), // DNS resolution errors always happen on the first request
+                        // DNS resolution errors always happen on the first request
+                        sequence_number: Some(self.socks_reply_count(&response.stream_key)),
                         data: from_protocol(return_route_info.protocol)
                             .server_impersonator()
                             .dns_resolution_failure_response(
@@ -311,6 +346,65 @@ impl ProxyServer {
         }
     }
 
+    fn handle_exit_refusal(&mut self, msg: &ExpiredCoresPackage<ExitRefusal_0v1>) {
+        let return_route_info = match self.get_return_route_info(&msg.remaining_route) {
+            Some(rri) => rri,
+            None => return,
+        };
+        let exit_public_key = self.find_exit_public_key(&return_route_info);
+        let stream_key = &msg.payload.stream_key;
+        match self.keys_and_addrs.a_to_b(stream_key) {
+            Some(socket_addr) => {
+                self.stream_key_send_times.remove(stream_key);
+                self.report_response_services_consumed(&return_route_info, 0, msg.payload_len);
+                self.subs
+                    .as_ref()
+                    .expect("Dispatcher unbound in ProxyServer")
+                    .dispatcher
+                    .try_send(TransmitDataMsg {
+                        endpoint: Endpoint::Socket(socket_addr),
+                        last_data: true,
+                        // Exit Nodes refuse a stream before connecting it, so this is the first response too
+                        sequence_number: Some(self.socks_reply_count(stream_key)),
+                        data: from_protocol(return_route_info.protocol)
+                            .server_impersonator()
+                            .exit_refusal_response(
+                                &exit_public_key,
+                                return_route_info.server_name.clone(),
+                            ),
+                    })
+                    .expect("Dispatcher is dead");
+                debug!(
+                    self.logger,
+                    "Retiring stream key {}: ExitRefusal", stream_key
+                );
+                self.purge_stream_key(stream_key);
+            }
+            None => error!(
+                self.logger,
+                "Discarding ExitRefusal message from an unrecognized stream key {:?}", stream_key
+            ),
+        }
+    }
+
+    fn find_exit_public_key(&self, return_route_info: &AddReturnRouteMessage) -> PublicKey {
+        // ugly, ugly
+        let self_public_key = self.main_cryptde.public_key();
+        return_route_info
+            .find_exit_node_key()
+            .unwrap_or_else(|| {
+                if return_route_info.is_zero_hop() {
+                    &self_public_key
+                } else {
+                    panic!(
+                        "Internal error: return_route_info for {} has no exit Node",
+                        return_route_info.return_route_id
+                    );
+                }
+            })
+            .clone()
+    }
+
     fn handle_client_response_payload(
         &mut self,
         msg: &ExpiredCoresPackage<ClientResponsePayload_0v1>,
@@ -334,6 +428,7 @@ impl ProxyServer {
         };
         match self.keys_and_addrs.a_to_b(&response.stream_key) {
             Some(socket_addr) => {
+                self.report_route_success(&response.stream_key);
                 self.report_response_services_consumed(
                     &return_route_info,
                     response.sequenced_packet.data.len(),
@@ -341,10 +436,12 @@ impl ProxyServer {
                 );
 
                 let last_data = response.sequenced_packet.last_data;
-                let sequence_number = Some(
-                    response.sequenced_packet.sequence_number
-                        + self.browser_proxy_sequence_offset as u64,
-                );
+                let sequence_offset = match self.socks_sessions.get(&response.stream_key) {
+                    Some(session) => session.response_sequence_offset(),
+                    None => self.browser_proxy_sequence_offset as u64,
+                };
+                let sequence_number =
+                    Some(response.sequenced_packet.sequence_number + sequence_offset);
                 self
                     .subs
                     .as_ref()
@@ -406,6 +503,113 @@ impl ProxyServer {
         }
     }
 
+    fn handle_socks_client_data(&mut self, msg: InboundClientData) {
+        let stream_key = self.make_stream_key(&msg);
+        let session = self
+            .socks_sessions
+            .entry(stream_key)
+            .or_insert_with(Socks5Session::new);
+        if session.target_opt().is_some() {
+            self.handle_normal_client_data(msg, false);
+            return;
+        }
+        let sequence_number = msg.sequence_number.unwrap_or(0);
+        match session.add_data(&msg.data) {
+            SocksProgress::Incomplete { reply } => {
+                if !reply.is_empty() {
+                    self.send_socks_reply(&stream_key, msg.peer_addr, reply, false);
+                }
+            }
+            SocksProgress::Connected {
+                mut reply,
+                target,
+                leftover,
+            } => {
+                let port = target.port.expect("SOCKS target without port");
+                if self.consuming_wallet_balance.is_none() && self.is_decentralized {
+                    reply.extend(Socks5Session::connect_reply(
+                        SocksReplyCode::NotAllowedByRuleset,
+                    ));
+                    self.send_socks_reply(&stream_key, msg.peer_addr, reply, true);
+                    error!(
+                        self.logger,
+                        "SOCKS request for {}:{} rejected due to missing consuming wallet",
+                        target.name,
+                        port
+                    );
+                    self.purge_stream_key(&stream_key);
+                    return;
+                }
+                reply.extend(Socks5Session::connect_reply(SocksReplyCode::Succeeded));
+                self.send_socks_reply(&stream_key, msg.peer_addr, reply, false);
+                debug!(
+                    self.logger,
+                    "SOCKS client at {} connected to {}:{} on stream key {}",
+                    msg.peer_addr,
+                    target.name,
+                    port,
+                    stream_key
+                );
+                let session = self
+                    .socks_sessions
+                    .get_mut(&stream_key)
+                    .expect("SOCKS session disappeared");
+                if leftover.is_empty() {
+                    session.start_data_sequence_at(sequence_number + 1);
+                } else {
+                    session.start_data_sequence_at(sequence_number);
+                    self.handle_normal_client_data(
+                        InboundClientData {
+                            data: leftover,
+                            ..msg
+                        },
+                        false,
+                    );
+                }
+            }
+            SocksProgress::Refused { reply } => {
+                warning!(
+                    self.logger,
+                    "Refusing SOCKS connection from {}: handshake failed",
+                    msg.peer_addr
+                );
+                self.send_socks_reply(&stream_key, msg.peer_addr, reply, true);
+                self.purge_stream_key(&stream_key);
+            }
+        }
+    }
+
+    fn send_socks_reply(
+        &mut self,
+        stream_key: &StreamKey,
+        peer_addr: SocketAddr,
+        data: Vec<u8>,
+        last_data: bool,
+    ) {
+        let sequence_number = self
+            .socks_sessions
+            .get_mut(stream_key)
+            .map(|session| session.next_reply_sequence_number());
+        self.out_subs("Dispatcher")
+            .dispatcher
+            .try_send(TransmitDataMsg {
+                endpoint: Endpoint::Socket(peer_addr),
+                last_data,
+                sequence_number,
+                data,
+            })
+            .expect("Dispatcher is dead");
+    }
+
+    // Anything the ProxyServer itself says to a SOCKS client has to be numbered after the
+    // handshake replies it has already sent; zero for everybody else.
+    fn socks_reply_count(&self, stream_key: &StreamKey) -> u64 {
+        self.socks_sessions
+            .get(stream_key)
+            .map(|session| session.response_sequence_offset())
+            .unwrap_or(0)
+    }
+
     fn out_subs(&self, actor_name: &str) -> &ProxyServerOutSubs {
         self.subs
             .as_ref()
@@ -444,6 +648,7 @@ impl ProxyServer {
             return;
         }
         let stream_key = self.make_stream_key(&msg);
+        let failure_sequence_number = self.socks_reply_count(&stream_key);
         let payload = match self.make_payload(msg, &stream_key) {
             Ok(payload) => payload,
             Err(_e) => {
@@ -473,6 +678,7 @@ impl ProxyServer {
                     payload,
                     logger,
                     source_addr,
+                    failure_sequence_number,
                     &dispatcher,
                     &accountant_exit_sub,
                     &accountant_routing_sub,
@@ -511,6 +717,7 @@ impl ProxyServer {
                                         payload,
                                         logger,
                                         source_addr,
+                                        failure_sequence_number,
                                         &dispatcher,
                                         &accountant_exit_sub,
                                         &accountant_routing_sub,
@@ -528,6 +735,7 @@ impl ProxyServer {
                                         payload,
                                         &logger,
                                         source_addr,
+                                        failure_sequence_number,
                                         &dispatcher,
                                     );
                                 }
@@ -564,7 +772,11 @@ impl ProxyServer {
             }
             Some(sk) => sk,
         };
-        if msg.report_to_counterpart {
+        let socks_handshake_unfinished = match self.socks_sessions.get(&stream_key) {
+            Some(session) => session.target_opt().is_none(),
+            None => false,
+        };
+        if msg.report_to_counterpart && !socks_handshake_unfinished {
             debug!(
                 self.logger,
                 "Reporting shutdown of {} to counterpart", &stream_key
@@ -612,10 +824,70 @@ impl ProxyServer {
         }
     }
 
+    // A stream can end before any response comes back for reasons that have nothing to do with
+    // the route, such as an impatient client; only ROUTE_RESPONSE_TIMEOUT counts as a failure.
     fn purge_stream_key(&mut self, stream_key: &StreamKey) {
+        let _ = self.stream_key_send_times.remove(stream_key);
         let _ = self.keys_and_addrs.remove_a(stream_key);
         let _ = self.stream_key_routes.remove(stream_key);
         let _ = self.tunneled_hosts.remove(stream_key);
+        let _ = self.socks_sessions.remove(stream_key);
+    }
+
+    fn handle_route_response_timeout(&mut self, msg: RouteResponseTimeout) {
+        if self.stream_key_send_times.get(&msg.stream_key) != Some(&msg.send_time) {
+            return;
+        }
+        self.stream_key_send_times.remove(&msg.stream_key);
+        if let Some(route_keys) = self.route_node_keys(&msg.stream_key) {
+            debug!(
+                self.logger,
+                "No response on stream key {} after {:?}; reporting route failure",
+                msg.stream_key,
+                ROUTE_RESPONSE_TIMEOUT
+            );
+            self.report_node_record_metadata(NodeRecordMetadataMessage::RouteFailed(route_keys));
+        }
+    }
+
+    fn report_route_success(&mut self, stream_key: &StreamKey) {
+        if let Some(send_time) = self.stream_key_send_times.remove(stream_key) {
+            if let Some(route_keys) = self.route_node_keys(stream_key) {
+                self.report_node_record_metadata(NodeRecordMetadataMessage::RouteSucceeded(
+                    route_keys,
+                    send_time.elapsed(),
+                ));
+            }
+        }
+    }
+
+    // The Nodes that carry requests for the stream, with the exit Node last; None for zero-hop
+    // routes, where there's nobody else to keep track of
+    fn route_node_keys(&self, stream_key: &StreamKey) -> Option<Vec<PublicKey>> {
+        let over_services = match &self.stream_key_routes.get(stream_key)?.expected_services {
+            ExpectedServices::OneWay(services) => services,
+            ExpectedServices::RoundTrip(over_services, _, _) => over_services,
+        };
+        let route_keys = over_services
+            .iter()
+            .flat_map(|service| match service {
+                ExpectedService::Routing(public_key, _, _) => Some(public_key.clone()),
+                ExpectedService::Exit(public_key, _, _) => Some(public_key.clone()),
+                ExpectedService::Nothing => None,
+            })
+            .collect::<Vec<PublicKey>>();
+        if route_keys.is_empty() {
+            None
+        } else {
+            Some(route_keys)
+        }
+    }
+
+    fn report_node_record_metadata(&self, msg: NodeRecordMetadataMessage) {
+        self.out_subs("Neighborhood")
+            .update_node_record_metadata
+            .try_send(msg)
+            .expect("Neighborhood is dead");
     }
 
     fn make_payload(
@@ -623,6 +895,9 @@ impl ProxyServer {
         ibcd: InboundClientData,
         stream_key: &StreamKey,
     ) -> Result<ClientRequestPayload_0v1, ()> {
+        if self.socks_sessions.contains_key(stream_key) {
+            return self.make_socks_payload(ibcd, stream_key);
+        }
         let tunnelled_host = self.tunneled_hosts.get(stream_key);
         let new_ibcd = match tunnelled_host {
             Some(_) => InboundClientData {
@@ -651,6 +926,54 @@ impl ProxyServer {
         }
     }
 
+    // SOCKS clients have already told us where they're going, and their sequence numbers start
+    // after the handshake
+    fn make_socks_payload(
+        &mut self,
+        ibcd: InboundClientData,
+        stream_key: &StreamKey,
+    ) -> Result<ClientRequestPayload_0v1, ()> {
+        let originator_public_key = self.alias_cryptde.public_key().clone();
+        let session = self
+            .socks_sessions
+            .get_mut(stream_key)
+            .expect("SOCKS session disappeared");
+        let target = match session.target_opt() {
+            Some(target) => target.clone(),
+            None => {
+                error!(
+                    self.logger,
+                    "Couldn't create ClientRequestPayload: SOCKS handshake for {} isn't finished",
+                    stream_key
+                );
+                return Err(());
+            }
+        };
+        let sequence_number = match ibcd.sequence_number {
+            Some(sequence_number) => session.data_sequence_number(sequence_number),
+            None => {
+                error!(
+                    self.logger,
+                    "internal error: got SOCKS IBCD with no sequence number and {} bytes",
+                    ibcd.data.len()
+                );
+                return Err(());
+            }
+        };
+        Ok(ClientRequestPayload_0v1 {
+            stream_key: *stream_key,
+            protocol: session.protocol(&ibcd.data),
+            sequenced_packet: SequencedPacket {
+                data: ibcd.data,
+                sequence_number,
+                last_data: ibcd.last_data,
+            },
+            target_hostname: Some(target.name),
+            target_port: target.port.expect("SOCKS target without port"),
+            originator_public_key,
+        })
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     #[allow(clippy::too_many_arguments)]
     fn try_transmit_to_hopper(
@@ -660,6 +983,7 @@ impl ProxyServer {
         payload: ClientRequestPayload_0v1,
         logger: Logger,
         source_addr: SocketAddr,
+        failure_sequence_number: u64,
         dispatcher: &Recipient<TransmitDataMsg>,
         accountant_exit_sub: &Recipient<ReportExitServiceConsumedMessage>,
         accountant_routing_sub: &Recipient<ReportRoutingServiceConsumedMessage>,
@@ -795,7 +1119,13 @@ impl ProxyServer {
         };
 
         match destination_key_opt {
-            None => ProxyServer::handle_route_failure(payload, &logger, source_addr, dispatcher),
+            None => ProxyServer::handle_route_failure(
+                payload,
+                &logger,
+                source_addr,
+                failure_sequence_number,
+                dispatcher,
+            ),
             Some(payload_destination_key) => {
                 debug!(
                     logger,
@@ -843,16 +1173,18 @@ impl ProxyServer {
         payload: ClientRequestPayload_0v1,
         logger: &Logger,
         source_addr: SocketAddr,
+        sequence_number: u64,
         dispatcher: &Recipient<TransmitDataMsg>,
     ) {
         let target_hostname = ProxyServer::hostname(&payload);
-        ProxyServer::send_route_failure(payload, source_addr, dispatcher);
+        ProxyServer::send_route_failure(payload, source_addr, sequence_number, dispatcher);
         error!(logger, "Failed to find route to {}", target_hostname);
     }
 
     fn send_route_failure(
         payload: ClientRequestPayload_0v1,
         source_addr: SocketAddr,
+        sequence_number: u64,
         dispatcher: &Recipient<TransmitDataMsg>,
     ) {
         let data = from_protocol(payload.protocol)
@@ -861,7 +1193,7 @@ impl ProxyServer {
         let msg = TransmitDataMsg {
             endpoint: Endpoint::Socket(source_addr),
             last_data: true,
-            sequence_number: Some(0),
+            sequence_number: Some(sequence_number),
             data,
         };
         dispatcher.try_send(msg).expect("Dispatcher is dead");
@@ -964,7 +1296,9 @@ mod tests {
     use crate::sub_lib::hopper::MessageType;
     use crate::sub_lib::neighborhood::ExpectedServices;
     use crate::sub_lib::neighborhood::{ExpectedService, DEFAULT_RATE_PACK};
-    use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+    use crate::sub_lib::proxy_client::{
+        ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1,
+    };
     use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
     use crate::sub_lib::proxy_server::ProxyProtocol;
     use crate::sub_lib::route::Route;
@@ -1171,6 +1505,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1276,6 +1611,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1315,6 +1651,265 @@ mod tests {
         );
     }
 
+    fn make_socks_ibcd(
+        peer_addr: SocketAddr,
+        sequence_number: u64,
+        data: &[u8],
+    ) -> InboundClientData {
+        InboundClientData {
+            peer_addr,
+            reception_port: Some(1080),
+            sequence_number: Some(sequence_number),
+            last_data: false,
+            is_clandestine: false,
+            data: data.to_vec(),
+        }
+    }
+
+    fn make_socks_connect_request(hostname: &str, port: u16) -> Vec<u8> {
+        let mut request = vec![0x05, 0x01, 0x00, 0x03, hostname.len() as u8];
+        request.extend(hostname.as_bytes());
+        request.extend(&port.to_be_bytes());
+        request
+    }
+
+    #[test]
+    fn proxy_server_completes_socks_handshake_and_sends_requested_host_and_port_in_payload() {
+        let main_cryptde = main_cryptde();
+        let alias_cryptde = alias_cryptde();
+        let key = alias_cryptde.public_key();
+        let (hopper_mock, hopper_awaiter, hopper_recording_arc) = make_recorder();
+        let (neighborhood_mock, _, _) = make_recorder();
+        let neighborhood_mock = neighborhood_mock.route_query_response(Some(
+            zero_hop_route_response(&main_cryptde.public_key(), main_cryptde),
+        ));
+        let route = zero_hop_route_response(main_cryptde.public_key(), main_cryptde).route;
+        let (dispatcher_mock, _, dispatcher_recording_arc) = make_recorder();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let stream_key = make_meaningless_stream_key();
+        let greeting = make_socks_ibcd(socket_addr, 0, &[0x05, 0x01, 0x00]);
+        let request = make_socks_ibcd(
+            socket_addr,
+            1,
+            &make_socks_connect_request("realdomain.nu", 8022),
+        );
+        let client_data = make_socks_ibcd(socket_addr, 2, b"SSH-2.0-OpenSSH_8.2\r\n");
+        let expected_payload = ClientRequestPayload_0v1 {
+            stream_key,
+            sequenced_packet: SequencedPacket {
+                data: b"SSH-2.0-OpenSSH_8.2\r\n".to_vec(),
+                sequence_number: 0,
+                last_data: false,
+            },
+            target_hostname: Some(String::from("realdomain.nu")),
+            target_port: 8022,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: key.clone(),
+        };
+        let expected_pkg =
+            IncipientCoresPackage::new(main_cryptde, route, expected_payload.into(), &key).unwrap();
+
+        thread::spawn(move || {
+            let system = System::new(
+                "proxy_server_completes_socks_handshake_and_sends_requested_host_and_port_in_payload",
+            );
+            let mut subject = ProxyServer::new(
+                main_cryptde,
+                alias_cryptde,
+                false,
+                Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                Some(1080),
+            );
+            subject.stream_key_factory =
+                Box::new(StreamKeyFactoryMock::new().make_result(stream_key));
+            let subject_addr: Addr<ProxyServer> = subject.start();
+            let mut peer_actors = peer_actors_builder()
+                .dispatcher(dispatcher_mock)
+                .hopper(hopper_mock)
+                .neighborhood(neighborhood_mock)
+                .build();
+            peer_actors.proxy_server = ProxyServer::make_subs_from(&subject_addr);
+            subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+            subject_addr.try_send(greeting).unwrap();
+            subject_addr.try_send(request).unwrap();
+            subject_addr.try_send(client_data).unwrap();
+            system.run();
+        });
+
+        hopper_awaiter.await_message_count(1);
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(
+            dispatcher_recording.get_record::<TransmitDataMsg>(0),
+            &TransmitDataMsg {
+                endpoint: Endpoint::Socket(socket_addr),
+                last_data: false,
+                sequence_number: Some(0),
+                data: vec![0x05, 0x00],
+            }
+        );
+        assert_eq!(
+            dispatcher_recording.get_record::<TransmitDataMsg>(1),
+            &TransmitDataMsg {
+                endpoint: Endpoint::Socket(socket_addr),
+                last_data: false,
+                sequence_number: Some(1),
+                data: vec![0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0],
+            }
+        );
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &expected_pkg
+        );
+    }
+
+    #[test]
+    fn proxy_server_refuses_socks_connect_when_consuming_wallet_is_missing() {
+        init_test_logging();
+        let system =
+            System::new("proxy_server_refuses_socks_connect_when_consuming_wallet_is_missing");
+        let (dispatcher_mock, _, dispatcher_recording_arc) = make_recorder();
+        let subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None, Some(1080));
+        let subject_addr: Addr<ProxyServer> = subject.start();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let mut data = vec![0x05, 0x01, 0x00];
+        data.extend(make_socks_connect_request("nowhere.com", 80));
+        let mut peer_actors = peer_actors_builder().dispatcher(dispatcher_mock).build();
+        peer_actors.proxy_server = ProxyServer::make_subs_from(&subject_addr);
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(make_socks_ibcd(socket_addr, 0, &data))
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(
+            dispatcher_recording.get_record::<TransmitDataMsg>(0),
+            &TransmitDataMsg {
+                endpoint: Endpoint::Socket(socket_addr),
+                last_data: true,
+                sequence_number: Some(0),
+                data: vec![0x05, 0x00, 0x05, 0x02, 0x00, 0x01, 0, 0, 0, 0, 0, 0],
+            }
+        );
+        assert_eq!(dispatcher_recording.len(), 1);
+        TestLogHandler::new().exists_log_containing(
+            "ERROR: ProxyServer: SOCKS request for nowhere.com:80 rejected due to missing consuming wallet",
+        );
+    }
+
+    #[test]
+    fn proxy_server_refuses_socks_udp_associate() {
+        let system = System::new("proxy_server_refuses_socks_udp_associate");
+        let (dispatcher_mock, _, dispatcher_recording_arc) = make_recorder();
+        let subject = ProxyServer::new(
+            main_cryptde(),
+            alias_cryptde(),
+            false,
+            Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            Some(1080),
+        );
+        let subject_addr: Addr<ProxyServer> = subject.start();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let mut peer_actors = peer_actors_builder().dispatcher(dispatcher_mock).build();
+        peer_actors.proxy_server = ProxyServer::make_subs_from(&subject_addr);
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(make_socks_ibcd(socket_addr, 0, &[0x05, 0x01, 0x00]))
+            .unwrap();
+        subject_addr
+            .try_send(make_socks_ibcd(
+                socket_addr,
+                1,
+                &[0x05, 0x03, 0x00, 0x01, 0, 0, 0, 0, 0, 0],
+            ))
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(
+            dispatcher_recording.get_record::<TransmitDataMsg>(1),
+            &TransmitDataMsg {
+                endpoint: Endpoint::Socket(socket_addr),
+                last_data: true,
+                sequence_number: Some(1),
+                data: vec![0x05, 0x07, 0x00, 0x01, 0, 0, 0, 0, 0, 0],
+            }
+        );
+    }
+
+    #[test]
+    fn handle_client_response_payload_numbers_socks_responses_after_handshake_replies() {
+        let system = System::new(
+            "handle_client_response_payload_numbers_socks_responses_after_handshake_replies",
+        );
+        let (dispatcher_mock, _, dispatcher_log_arc) = make_recorder();
+        let cryptde = main_cryptde();
+        let mut subject = ProxyServer::new(
+            cryptde,
+            alias_cryptde(),
+            false,
+            Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            Some(1080),
+        );
+        let stream_key = make_meaningless_stream_key();
+        subject.stream_key_factory = Box::new(StreamKeyFactoryMock::new().make_result(stream_key));
+        subject.route_ids_to_return_routes.insert(
+            1234,
+            AddReturnRouteMessage {
+                return_route_id: 1234,
+                expected_services: vec![ExpectedService::Nothing],
+                protocol: ProxyProtocol::HTTP,
+                server_name: None,
+            },
+        );
+        let subject_addr: Addr<ProxyServer> = subject.start();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let client_response_payload = ClientResponsePayload_0v1 {
+            stream_key,
+            sequenced_packet: SequencedPacket {
+                data: b"some data".to_vec(),
+                sequence_number: 0,
+                last_data: false,
+            },
+        };
+        let expired_cores_package: ExpiredCoresPackage<ClientResponsePayload_0v1> =
+            ExpiredCoresPackage::new(
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+                Some(make_wallet("irrelevant")),
+                return_route_with_id(cryptde, 1234),
+                client_response_payload.into(),
+                0,
+            );
+        let mut peer_actors = peer_actors_builder().dispatcher(dispatcher_mock).build();
+        peer_actors.proxy_server = ProxyServer::make_subs_from(&subject_addr);
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+        subject_addr
+            .try_send(make_socks_ibcd(socket_addr, 0, &[0x05, 0x01, 0x00]))
+            .unwrap();
+        subject_addr
+            .try_send(make_socks_ibcd(
+                socket_addr,
+                1,
+                &make_socks_connect_request("realdomain.nu", 8022),
+            ))
+            .unwrap();
+
+        subject_addr.try_send(expired_cores_package).unwrap();
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_log_arc.lock().unwrap();
+        let record = dispatcher_recording.get_record::<TransmitDataMsg>(2);
+        assert_eq!(record.data, b"some data".to_vec());
+        assert_eq!(record.sequence_number, Some(2));
+    }
+
     #[test]
     fn handle_client_response_payload_increments_sequence_number_when_browser_proxy_sequence_offset_is_true(
     ) {
@@ -1326,6 +1921,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let stream_key = make_meaningless_stream_key();
@@ -1430,6 +2026,7 @@ mod tests {
                 alias_cryptde(),
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1500,6 +2097,7 @@ mod tests {
                 alias_cryptde(),
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1552,7 +2150,7 @@ mod tests {
         };
         let stream_key_factory = StreamKeyFactoryMock::new(); // can't make any stream keys; shouldn't have to
         let system = System::new("proxy_server_receives_http_request_with_no_consuming_wallet_and_sends_impersonated_response");
-        let mut subject = ProxyServer::new(cryptde, alias_cryptde(), true, None);
+        let mut subject = ProxyServer::new(cryptde, alias_cryptde(), true, None, None);
         subject.stream_key_factory = Box::new(stream_key_factory);
         subject.keys_and_addrs.insert(stream_key, socket_addr);
         let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1611,7 +2209,7 @@ mod tests {
         };
         let stream_key_factory = StreamKeyFactoryMock::new(); // can't make any stream keys; shouldn't have to
         let system = System::new("proxy_server_receives_tls_request_with_no_consuming_wallet_and_sends_impersonated_response");
-        let mut subject = ProxyServer::new(cryptde, alias_cryptde(), true, None);
+        let mut subject = ProxyServer::new(cryptde, alias_cryptde(), true, None, None);
         subject.stream_key_factory = Box::new(stream_key_factory);
         subject.keys_and_addrs.insert(stream_key, socket_addr);
         let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1674,7 +2272,7 @@ mod tests {
             };
             let stream_key_factory = StreamKeyFactoryMock::new(); // can't make any stream keys; shouldn't have to
             let system = System::new("proxy_server_receives_http_request_with_no_consuming_wallet_in_zero_hop_mode_and_handles_normally");
-            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None);
+            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None, None);
             subject.stream_key_factory = Box::new(stream_key_factory);
             subject.keys_and_addrs.insert(stream_key, socket_addr);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1698,7 +2296,8 @@ mod tests {
                 target_key_opt: None,
                 target_component: Component::ProxyClient,
                 minimum_hop_count: 0,
-                return_component_opt: Some(Component::ProxyServer)
+                return_component_opt: Some(Component::ProxyServer),
+                exit_policy_opt: None,
             }
         );
         let dispatcher_recording = dispatcher_log_arc.lock().unwrap();
@@ -1752,7 +2351,7 @@ mod tests {
             };
             let stream_key_factory = StreamKeyFactoryMock::new(); // can't make any stream keys; shouldn't have to
             let system = System::new("proxy_server_receives_tls_request_with_no_consuming_wallet_in_zero_hop_mode_and_handles_normally");
-            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None);
+            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None, None);
             subject.stream_key_factory = Box::new(stream_key_factory);
             subject.keys_and_addrs.insert(stream_key, socket_addr);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -1776,7 +2375,8 @@ mod tests {
                 target_key_opt: None,
                 target_component: Component::ProxyClient,
                 minimum_hop_count: 0,
-                return_component_opt: Some(Component::ProxyServer)
+                return_component_opt: Some(Component::ProxyServer),
+                exit_policy_opt: None,
             }
         );
         let dispatcher_recording = dispatcher_log_arc.lock().unwrap();
@@ -1856,6 +2456,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             subject.keys_and_addrs.insert(stream_key, socket_addr);
@@ -1924,7 +2525,7 @@ mod tests {
         thread::spawn(move || {
             let stream_key_factory = StreamKeyFactoryMock::new(); // can't make any stream keys; shouldn't have to
             let system = System::new("proxy_server_applies_late_wallet_information");
-            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None);
+            let mut subject = ProxyServer::new(main_cryptde, alias_cryptde, false, None, None);
             subject.stream_key_factory = Box::new(stream_key_factory);
             subject.keys_and_addrs.insert(stream_key, socket_addr);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2046,6 +2647,7 @@ mod tests {
                 alias_cryptde,
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2102,6 +2704,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2184,6 +2787,7 @@ mod tests {
                 alias_cryptde,
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2286,6 +2890,7 @@ mod tests {
             payload.clone(),
             logger,
             socket_addr,
+            0,
             &peer_actors.dispatcher.from_dispatcher_client,
             &peer_actors.accountant.report_exit_service_consumed,
             &peer_actors.accountant.report_routing_service_consumed,
@@ -2363,6 +2968,7 @@ mod tests {
             payload.clone(),
             logger,
             socket_addr,
+            0,
             &peer_actors.dispatcher.from_dispatcher_client,
             &peer_actors.accountant.report_exit_service_consumed,
             &peer_actors.accountant.report_routing_service_consumed,
@@ -2428,6 +3034,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2496,6 +3103,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2553,6 +3161,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory = Box::new(stream_key_factory);
             let subject_addr: Addr<ProxyServer> = subject.start();
@@ -2599,6 +3208,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             let subject_addr: Addr<ProxyServer> = subject.start();
             let mut peer_actors = peer_actors_builder()
@@ -2680,6 +3290,7 @@ mod tests {
             payload,
             logger,
             source_addr,
+            0,
             &peer_actors.dispatcher.from_dispatcher_client,
             &peer_actors.accountant.report_exit_service_consumed,
             &peer_actors.accountant.report_routing_service_consumed,
@@ -2730,6 +3341,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             let subject_addr: Addr<ProxyServer> = subject.start();
             let mut peer_actors = peer_actors_builder()
@@ -2829,6 +3441,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory =
                 Box::new(StreamKeyFactoryMock::new().make_result(stream_key.clone()));
@@ -2907,6 +3520,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory =
                 Box::new(StreamKeyFactoryMock::new().make_result(stream_key.clone()));
@@ -2983,6 +3597,7 @@ mod tests {
                 alias_cryptde,
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.stream_key_factory =
                 Box::new(StreamKeyFactoryMock::new().make_result(stream_key.clone()));
@@ -3051,6 +3666,7 @@ mod tests {
                 alias_cryptde(),
                 false,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             let subject_addr: Addr<ProxyServer> = subject.start();
             let mut peer_actors = peer_actors_builder()
@@ -3090,6 +3706,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let stream_key = make_meaningless_stream_key();
@@ -3149,6 +3766,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         subject.subs = Some(ProxyServerOutSubs::default());
 
@@ -3204,6 +3822,173 @@ mod tests {
         assert!(subject.tunneled_hosts.is_empty());
     }
 
+    fn make_timed_route_subject(
+        stream_key: StreamKey,
+        socket_addr: SocketAddr,
+        neighborhood: Recorder,
+    ) -> (ProxyServer, Vec<PublicKey>) {
+        let mut subject = ProxyServer::new(
+            main_cryptde(),
+            alias_cryptde(),
+            true,
+            Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
+        );
+        let peer_actors = peer_actors_builder().neighborhood(neighborhood).build();
+        let mut subs = ProxyServerOutSubs::default();
+        subs.update_node_record_metadata = peer_actors.neighborhood.update_node_record_metadata;
+        subject.subs = Some(subs);
+        let relay_key = PublicKey::new(b"relay");
+        let exit_key = PublicKey::new(b"exit");
+        subject.keys_and_addrs.insert(stream_key, socket_addr);
+        subject.stream_key_routes.insert(
+            stream_key,
+            RouteQueryResponse {
+                route: Route { hops: vec![] },
+                expected_services: ExpectedServices::RoundTrip(
+                    vec![
+                        ExpectedService::Nothing,
+                        ExpectedService::Routing(
+                            relay_key.clone(),
+                            make_wallet("relay"),
+                            rate_pack(101),
+                        ),
+                        ExpectedService::Exit(
+                            exit_key.clone(),
+                            make_wallet("exit"),
+                            rate_pack(102),
+                        ),
+                    ],
+                    vec![],
+                    1234,
+                ),
+            },
+        );
+        subject
+            .stream_key_send_times
+            .insert(stream_key, Instant::now());
+        (subject, vec![relay_key, exit_key])
+    }
+
+    #[test]
+    fn first_response_on_a_stream_reports_route_success_to_neighborhood() {
+        let system =
+            System::new("first_response_on_a_stream_reports_route_success_to_neighborhood");
+        let (neighborhood, _, neighborhood_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let (mut subject, route_keys) =
+            make_timed_route_subject(stream_key, socket_addr, neighborhood);
+        subject.route_ids_to_return_routes.insert(
+            1234,
+            AddReturnRouteMessage {
+                return_route_id: 1234,
+                expected_services: vec![],
+                protocol: ProxyProtocol::HTTP,
+                server_name: None,
+            },
+        );
+        let make_response = |sequence_number| {
+            ExpiredCoresPackage::new(
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+                Some(make_wallet("irrelevant")),
+                return_route_with_id(main_cryptde(), 1234),
+                ClientResponsePayload_0v1 {
+                    stream_key,
+                    sequenced_packet: SequencedPacket::new(vec![], sequence_number, false),
+                }
+                .into(),
+                0,
+            )
+        };
+
+        subject.handle_client_response_payload(&make_response(0));
+        subject.handle_client_response_payload(&make_response(1));
+
+        System::current().stop();
+        system.run();
+        let neighborhood_recording = neighborhood_recording_arc.lock().unwrap();
+        assert_eq!(neighborhood_recording.len(), 1);
+        match neighborhood_recording.get_record::<NodeRecordMetadataMessage>(0) {
+            NodeRecordMetadataMessage::RouteSucceeded(keys, _) => assert_eq!(keys, &route_keys),
+            x => panic!("Expected RouteSucceeded, got {:?}", x),
+        }
+        assert!(subject.stream_key_send_times.is_empty());
+    }
+
+    #[test]
+    fn route_response_timeout_reports_route_failure_to_neighborhood() {
+        let system = System::new("route_response_timeout_reports_route_failure_to_neighborhood");
+        let (neighborhood, _, neighborhood_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let socket_addr = SocketAddr::from_str("3.4.5.6:7890").unwrap();
+        let (mut subject, route_keys) =
+            make_timed_route_subject(stream_key, socket_addr, neighborhood);
+        let send_time = *subject.stream_key_send_times.get(&stream_key).unwrap();
+
+        subject.handle_route_response_timeout(RouteResponseTimeout {
+            stream_key,
+            send_time,
+        });
+
+        System::current().stop();
+        system.run();
+        let neighborhood_recording = neighborhood_recording_arc.lock().unwrap();
+        assert_eq!(
+            neighborhood_recording.get_record::<NodeRecordMetadataMessage>(0),
+            &NodeRecordMetadataMessage::RouteFailed(route_keys)
+        );
+        assert_eq!(neighborhood_recording.len(), 1);
+        assert!(subject.stream_key_send_times.is_empty());
+        assert!(subject.stream_key_routes.contains_key(&stream_key));
+    }
+
+    #[test]
+    fn route_response_timeout_after_a_response_reports_nothing() {
+        let system = System::new("route_response_timeout_after_a_response_reports_nothing");
+        let (neighborhood, _, neighborhood_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let socket_addr = SocketAddr::from_str("3.4.5.6:7890").unwrap();
+        let (mut subject, _) = make_timed_route_subject(stream_key, socket_addr, neighborhood);
+        let send_time = *subject.stream_key_send_times.get(&stream_key).unwrap();
+        subject.stream_key_send_times.remove(&stream_key);
+
+        subject.handle_route_response_timeout(RouteResponseTimeout {
+            stream_key,
+            send_time,
+        });
+
+        System::current().stop();
+        system.run();
+        let neighborhood_recording = neighborhood_recording_arc.lock().unwrap();
+        assert_eq!(neighborhood_recording.len(), 0);
+    }
+
+    #[test]
+    fn stream_shutdown_before_any_response_reports_nothing_to_neighborhood() {
+        let system =
+            System::new("stream_shutdown_before_any_response_reports_nothing_to_neighborhood");
+        let (neighborhood, _, neighborhood_recording_arc) = make_recorder();
+        let socket_addr = SocketAddr::from_str("3.4.5.6:7890").unwrap();
+        let stream_key = StreamKey::new(main_cryptde().public_key().clone(), socket_addr);
+        let (mut subject, _) = make_timed_route_subject(stream_key, socket_addr, neighborhood);
+
+        subject.handle_stream_shutdown_msg(StreamShutdownMsg {
+            peer_addr: socket_addr,
+            stream_type: RemovedStreamType::NonClandestine(NonClandestineAttributes {
+                reception_port: HTTP_PORT,
+                sequence_number: 1234,
+            }),
+            report_to_counterpart: false,
+        });
+
+        System::current().stop();
+        system.run();
+        let neighborhood_recording = neighborhood_recording_arc.lock().unwrap();
+        assert_eq!(neighborhood_recording.len(), 0);
+        assert!(subject.stream_key_send_times.is_empty());
+    }
+
     #[test]
     fn proxy_server_receives_nonterminal_response_from_hopper() {
         let system = System::new("proxy_server_receives_response_from_hopper");
@@ -3215,6 +4000,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let stream_key = make_meaningless_stream_key();
@@ -3397,6 +4183,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
 
         let stream_key = make_meaningless_stream_key();
@@ -3460,6 +4247,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn handle_exit_refusal_sends_refusal_to_dispatcher_without_blaming_exit() {
+        let system = System::new("handle_exit_refusal_sends_refusal_to_dispatcher");
+        let (dispatcher_mock, _, dispatcher_log_arc) = make_recorder();
+        let (neighborhood_mock, _, neighborhood_log_arc) = make_recorder();
+        let cryptde = main_cryptde();
+        let mut subject = ProxyServer::new(
+            cryptde,
+            alias_cryptde(),
+            false,
+            Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
+        );
+        let stream_key = make_meaningless_stream_key();
+        let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        subject
+            .keys_and_addrs
+            .insert(stream_key.clone(), socket_addr.clone());
+        let exit_public_key = PublicKey::from(&b"exit_key"[..]);
+        let subject_addr: Addr<ProxyServer> = subject.start();
+        let expired_cores_package: ExpiredCoresPackage<ExitRefusal_0v1> = ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("irrelevant")),
+            return_route_with_id(cryptde, 1234),
+            ExitRefusal_0v1::new(stream_key),
+            0,
+        );
+        let mut peer_actors = peer_actors_builder()
+            .dispatcher(dispatcher_mock)
+            .neighborhood(neighborhood_mock)
+            .build();
+        peer_actors.proxy_server = ProxyServer::make_subs_from(&subject_addr);
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+        subject_addr
+            .try_send(AddReturnRouteMessage {
+                return_route_id: 1234,
+                expected_services: vec![ExpectedService::Exit(
+                    exit_public_key.clone(),
+                    make_wallet("exit wallet"),
+                    rate_pack(10),
+                )],
+                protocol: ProxyProtocol::HTTP,
+                server_name: Some("server.com".to_string()),
+            })
+            .unwrap();
+
+        subject_addr.try_send(expired_cores_package).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        let dispatcher_recording = dispatcher_log_arc.lock().unwrap();
+        let record = dispatcher_recording.get_record::<TransmitDataMsg>(0);
+        assert_eq!(
+            *record,
+            TransmitDataMsg {
+                endpoint: Endpoint::Socket(socket_addr),
+                last_data: true,
+                sequence_number: Some(0),
+                data: ServerImpersonatorHttp {}
+                    .exit_refusal_response(&exit_public_key, Some("server.com".to_string())),
+            }
+        );
+        assert_eq!(neighborhood_log_arc.lock().unwrap().len(), 0);
+    }
+
     #[test]
     fn handle_dns_resolve_failure_reports_services_consumed() {
         let system = System::new("proxy_server_records_accounting");
@@ -3470,6 +4322,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let stream_key = make_meaningless_stream_key();
@@ -3560,6 +4413,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
 
         let stream_key = make_meaningless_stream_key();
@@ -3614,7 +4468,7 @@ mod tests {
         let record = neighborhood_recording.get_record::<NodeRecordMetadataMessage>(0);
         assert_eq!(
             record,
-            &NodeRecordMetadataMessage::Desirable(exit_public_key, false)
+            &NodeRecordMetadataMessage::DnsFailure(exit_public_key)
         );
     }
 
@@ -3631,6 +4485,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
 
         let stream_key = make_meaningless_stream_key();
@@ -3705,6 +4560,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
 
         let stream_key = make_meaningless_stream_key();
@@ -3777,6 +4633,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         subject.subs = Some(ProxyServerOutSubs::default());
 
@@ -3842,6 +4699,7 @@ mod tests {
             alias_cryptde(),
             false,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         subject
             .keys_and_addrs
@@ -3885,7 +4743,7 @@ mod tests {
     fn panics_if_hopper_is_unbound() {
         let system = System::new("panics_if_hopper_is_unbound");
         let http_request = b"GET /index.html HTTP/1.1\r\nHost: nowhere.com\r\n\r\n";
-        let subject = ProxyServer::new(main_cryptde(), alias_cryptde(), false, None);
+        let subject = ProxyServer::new(main_cryptde(), alias_cryptde(), false, None, None);
         let socket_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let expected_data = http_request.to_vec();
         let msg_from_dispatcher = InboundClientData {
@@ -3917,6 +4775,7 @@ mod tests {
             alias_cryptde(),
             true,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let stream_key = make_meaningless_stream_key();
         subject
@@ -3967,6 +4826,7 @@ mod tests {
             alias_cryptde(),
             true,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let stream_key = make_meaningless_stream_key();
         subject
@@ -4022,6 +4882,7 @@ mod tests {
                 alias_cryptde(),
                 true,
                 Some(STANDARD_CONSUMING_WALLET_BALANCE),
+                None,
             );
             subject.route_ids_to_return_routes = TtlHashMap::new(Duration::from_millis(250));
             subject
@@ -4071,7 +4932,7 @@ mod tests {
 
     #[test]
     fn handle_stream_shutdown_msg_handles_unknown_peer_addr() {
-        let mut subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None);
+        let mut subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None, None);
         let unaffected_socket_addr = SocketAddr::from_str("2.3.4.5:6789").unwrap();
         let unaffected_stream_key =
             StreamKey::new(main_cryptde().public_key().clone(), unaffected_socket_addr);
@@ -4117,6 +4978,7 @@ mod tests {
             alias_cryptde(),
             true,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let unaffected_socket_addr = SocketAddr::from_str("2.3.4.5:6789").unwrap();
         let unaffected_stream_key =
@@ -4239,6 +5101,7 @@ mod tests {
             alias_cryptde(),
             true,
             Some(STANDARD_CONSUMING_WALLET_BALANCE),
+            None,
         );
         let unaffected_socket_addr = SocketAddr::from_str("2.3.4.5:6789").unwrap();
         let unaffected_stream_key =
@@ -4349,7 +5212,7 @@ mod tests {
 
     #[test]
     fn handle_stream_shutdown_msg_does_not_report_to_counterpart_when_unnecessary() {
-        let mut subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None);
+        let mut subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None, None);
         let unaffected_socket_addr = SocketAddr::from_str("2.3.4.5:6789").unwrap();
         let unaffected_stream_key =
             StreamKey::new(main_cryptde().public_key().clone(), unaffected_socket_addr);
@@ -4415,7 +5278,7 @@ mod tests {
     )]
     fn handle_stream_shutdown_complains_about_clandestine_message() {
         let system = System::new("test");
-        let subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None);
+        let subject = ProxyServer::new(main_cryptde(), alias_cryptde(), true, None, None);
         let subject_addr = subject.start();
 
         subject_addr
@@ -70,5 +70,10 @@ pub trait ServerImpersonator {
         exit_key: &PublicKey,
         server_name_opt: Option<String>,
     ) -> Vec<u8>;
+    fn exit_refusal_response(
+        &self,
+        exit_key: &PublicKey,
+        server_name_opt: Option<String>,
+    ) -> Vec<u8>;
     fn consuming_wallet_absent(&self) -> Vec<u8>;
 }
@@ -43,6 +43,25 @@ impl ServerImpersonator for ServerImpersonatorHttp {
         )
     }
 
+    fn exit_refusal_response(
+        &self,
+        exit_key: &PublicKey,
+        server_name_opt: Option<String>,
+    ) -> Vec<u8> {
+        let server_name = server_name_opt.unwrap_or_else(|| "<unspecified>".to_string());
+        ServerImpersonatorHttp::make_error_response(
+            403,
+            "Exit Refused",
+            &format!("Exit Node won't connect to {}", server_name),
+            &format!(
+                "We chose the exit Node {} for your request to {}; but its operator doesn't allow \
+        it to connect to that destination. That's usually because it's on a private network or \
+        uses a port the operator has closed, such as the one for outgoing mail.",
+                exit_key, server_name
+            ),
+        )
+    }
+
     fn consuming_wallet_absent(&self) -> Vec<u8> {
         ServerImpersonatorHttp::make_error_response(
             402,
@@ -217,6 +236,26 @@ mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn exit_refusal_response_produces_expected_error_page() {
+        let subject = ServerImpersonatorHttp {};
+
+        let result = subject.exit_refusal_response(
+            &PublicKey::new(&b"exit"[..]),
+            Some("server.com".to_string()),
+        );
+
+        let expected = ServerImpersonatorHttp::make_error_response(
+            403,
+            "Exit Refused",
+            "Exit Node won't connect to server.com",
+            "We chose the exit Node ZXhpdA for your request to server.com; but its operator doesn't allow \
+            it to connect to that destination. That's usually because it's on a private network or \
+            uses a port the operator has closed, such as the one for outgoing mail.",
+        );
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn consuming_wallet_absent_response_produces_expected_error_page() {
         let subject = ServerImpersonatorHttp {};
@@ -17,6 +17,14 @@ impl ServerImpersonator for ServerImpersonatorTls {
         Vec::from(&TLS_UNRECOGNIZED_NAME_ALERT[..])
     }
 
+    fn exit_refusal_response(
+        &self,
+        _exit_key: &PublicKey,
+        _server_name_opt: Option<String>,
+    ) -> Vec<u8> {
+        Vec::from(&TLS_ACCESS_DENIED_ALERT[..])
+    }
+
     fn consuming_wallet_absent(&self) -> Vec<u8> {
         Vec::from(&TLS_INTERNAL_ERROR_ALERT[..])
     }
@@ -30,6 +38,14 @@ const TLS_INTERNAL_ERROR_ALERT: [u8; 7] = [
     0x50, // internal_error alert
 ];
 
+const TLS_ACCESS_DENIED_ALERT: [u8; 7] = [
+    0x15, // alert
+    0x03, 0x03, // TLS 1.2
+    0x00, 0x02, // packet length
+    0x02, // fatal alert
+    0x31, // access_denied alert
+];
+
 const TLS_UNRECOGNIZED_NAME_ALERT: [u8; 7] = [
     0x15, // alert
     0x03, 0x03, // TLS 1.2
@@ -61,6 +77,15 @@ mod tests {
         assert_eq!(Vec::from(&TLS_UNRECOGNIZED_NAME_ALERT[..]), result);
     }
 
+    #[test]
+    fn exit_refusal_response_produces_access_denied_alert() {
+        let subject = ServerImpersonatorTls {};
+
+        let result = subject.exit_refusal_response(&PublicKey::new(&b"ignored"[..]), None);
+
+        assert_eq!(Vec::from(&TLS_ACCESS_DENIED_ALERT[..]), result);
+    }
+
     #[test]
     fn consuming_wallet_absent_produces_internal_error_alert() {
         let subject = ServerImpersonatorTls {};
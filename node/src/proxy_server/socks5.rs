@@ -0,0 +1,399 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::proxy_server::protocol_pack::Host;
+use crate::sub_lib::proxy_server::ProxyProtocol;
+use std::convert::TryFrom;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+const SOCKS_VERSION: u8 = 0x05;
+const NO_AUTHENTICATION_REQUIRED: u8 = 0x00;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+const CONNECT_COMMAND: u8 = 0x01;
+const IPV4_ADDRESS: u8 = 0x01;
+const DOMAIN_NAME_ADDRESS: u8 = 0x03;
+const IPV6_ADDRESS: u8 = 0x04;
+const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SocksReplyCode {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    NotAllowedByRuleset = 0x02,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SocksProgress {
+    // The handshake isn't over; send the reply to the client, if it isn't empty, and wait for more
+    Incomplete {
+        reply: Vec<u8>,
+    },
+    // The client has asked to CONNECT to target. Send the reply, then the reply to the CONNECT
+    // request; anything in leftover arrived right behind the request and belongs to the stream.
+    Connected {
+        reply: Vec<u8>,
+        target: Host,
+        leftover: Vec<u8>,
+    },
+    // The handshake failed; send the reply, if it isn't empty, and close the stream
+    Refused {
+        reply: Vec<u8>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum SocksState {
+    AwaitingGreeting,
+    AwaitingRequest,
+    Established(Host),
+    Refused,
+}
+
+// One client's side of a SOCKS5 (RFC 1928) conversation on the SOCKS port. Only the "no
+// authentication required" method and the CONNECT command are supported; BIND and UDP ASSOCIATE
+// are refused as unsupported commands.
+//
+// The handshake uses up sequence numbers in both directions that the far end of the stream
+// mustn't see, so the session also keeps track of how far to shift them.
+#[derive(Debug)]
+pub struct Socks5Session {
+    state: SocksState,
+    data_so_far: Vec<u8>,
+    replies_sent: u64,
+    data_sequence_offset: u64,
+    protocol_opt: Option<ProxyProtocol>,
+}
+
+impl Default for Socks5Session {
+    fn default() -> Self {
+        Self {
+            state: SocksState::AwaitingGreeting,
+            data_so_far: vec![],
+            replies_sent: 0,
+            data_sequence_offset: 0,
+            protocol_opt: None,
+        }
+    }
+}
+
+impl Socks5Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect_reply(code: SocksReplyCode) -> Vec<u8> {
+        // We don't reveal the address of the exit Node's connection, so BND.ADDR is 0.0.0.0:0.
+        let mut reply = vec![SOCKS_VERSION, code as u8, 0x00, IPV4_ADDRESS];
+        reply.extend(&[0; 6]);
+        reply
+    }
+
+    pub fn target_opt(&self) -> Option<&Host> {
+        match &self.state {
+            SocksState::Established(host) => Some(host),
+            _ => None,
+        }
+    }
+
+    pub fn add_data(&mut self, data: &[u8]) -> SocksProgress {
+        self.data_so_far.extend(data);
+        let mut reply = vec![];
+        loop {
+            match self.state {
+                SocksState::AwaitingGreeting => match self.take_greeting() {
+                    None => return SocksProgress::Incomplete { reply },
+                    Some(Ok(greeting_reply)) => {
+                        reply.extend(greeting_reply);
+                        self.state = SocksState::AwaitingRequest;
+                    }
+                    Some(Err(greeting_reply)) => {
+                        reply.extend(greeting_reply);
+                        return self.refuse(reply);
+                    }
+                },
+                SocksState::AwaitingRequest => match self.take_request() {
+                    None => return SocksProgress::Incomplete { reply },
+                    Some(Ok(target)) => {
+                        self.state = SocksState::Established(target.clone());
+                        return SocksProgress::Connected {
+                            reply,
+                            target,
+                            leftover: self.data_so_far.drain(..).collect(),
+                        };
+                    }
+                    Some(Err(code)) => {
+                        reply.extend(Self::connect_reply(code));
+                        return self.refuse(reply);
+                    }
+                },
+                SocksState::Established(_) | SocksState::Refused => {
+                    return SocksProgress::Refused { reply }
+                }
+            }
+        }
+    }
+
+    // Sequence number for the next handshake reply to the client
+    pub fn next_reply_sequence_number(&mut self) -> u64 {
+        self.replies_sent += 1;
+        self.replies_sent - 1
+    }
+
+    // How far to shift the sequence numbers of data coming back from the server
+    pub fn response_sequence_offset(&self) -> u64 {
+        self.replies_sent
+    }
+
+    // Call once the handshake is over, with the sequence number of the first piece of client data
+    // that belongs to the stream
+    pub fn start_data_sequence_at(&mut self, sequence_number: u64) {
+        self.data_sequence_offset = sequence_number;
+    }
+
+    pub fn data_sequence_number(&self, sequence_number: u64) -> u64 {
+        sequence_number.saturating_sub(self.data_sequence_offset)
+    }
+
+    // SOCKS doesn't say what protocol the client is going to speak, but the ProxyServer needs to
+    // know so that it can describe failures in terms the client will understand: a client that
+    // opens with a TLS handshake gets TLS alerts, and anybody else gets HTTP error pages.
+    pub fn protocol(&mut self, data: &[u8]) -> ProxyProtocol {
+        if self.protocol_opt.is_none() && !data.is_empty() {
+            self.protocol_opt = Some(if data[0] == TLS_HANDSHAKE_RECORD {
+                ProxyProtocol::TLS
+            } else {
+                ProxyProtocol::HTTP
+            });
+        }
+        self.protocol_opt.unwrap_or(ProxyProtocol::HTTP)
+    }
+
+    fn refuse(&mut self, reply: Vec<u8>) -> SocksProgress {
+        self.state = SocksState::Refused;
+        self.data_so_far.clear();
+        SocksProgress::Refused { reply }
+    }
+
+    fn take_greeting(&mut self) -> Option<Result<Vec<u8>, Vec<u8>>> {
+        let data = &self.data_so_far;
+        if data.is_empty() {
+            return None;
+        }
+        if data[0] != SOCKS_VERSION {
+            return Some(Err(vec![]));
+        }
+        let method_count = *data.get(1)? as usize;
+        let methods = data.get(2..2 + method_count)?;
+        let result = if methods.contains(&NO_AUTHENTICATION_REQUIRED) {
+            Ok(vec![SOCKS_VERSION, NO_AUTHENTICATION_REQUIRED])
+        } else {
+            Err(vec![SOCKS_VERSION, NO_ACCEPTABLE_METHODS])
+        };
+        self.data_so_far.drain(..2 + method_count);
+        Some(result)
+    }
+
+    fn take_request(&mut self) -> Option<Result<Host, SocksReplyCode>> {
+        let data = &self.data_so_far;
+        let header = data.get(0..4)?;
+        if header[0] != SOCKS_VERSION {
+            return Some(Err(SocksReplyCode::GeneralFailure));
+        }
+        let (address_end, name) = match header[3] {
+            IPV4_ADDRESS => {
+                let octets = <[u8; 4]>::try_from(data.get(4..8)?).expect("Length checked");
+                (8, Ok(Ipv4Addr::from(octets).to_string()))
+            }
+            DOMAIN_NAME_ADDRESS => {
+                let length = *data.get(4)? as usize;
+                let name_bytes = data.get(5..5 + length)?;
+                let name = match String::from_utf8(name_bytes.to_vec()) {
+                    Ok(ref name) if !name.is_empty() => Ok(name.clone()),
+                    _ => Err(SocksReplyCode::GeneralFailure),
+                };
+                (5 + length, name)
+            }
+            IPV6_ADDRESS => {
+                let octets = <[u8; 16]>::try_from(data.get(4..20)?).expect("Length checked");
+                (20, Ok(Ipv6Addr::from(octets).to_string()))
+            }
+            _ => return Some(Err(SocksReplyCode::AddressTypeNotSupported)),
+        };
+        let port_bytes = data.get(address_end..address_end + 2)?;
+        let port = u16::from_be_bytes([port_bytes[0], port_bytes[1]]);
+        let command = header[1];
+        self.data_so_far.drain(..address_end + 2);
+        if command != CONNECT_COMMAND {
+            return Some(Err(SocksReplyCode::CommandNotSupported));
+        }
+        Some(name.map(|name| Host {
+            name,
+            port: Some(port),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connect_request(address: &[u8], port: u16) -> Vec<u8> {
+        let mut request = vec![SOCKS_VERSION, CONNECT_COMMAND, 0x00];
+        request.extend(address);
+        request.extend(&port.to_be_bytes());
+        request
+    }
+
+    #[test]
+    fn handshake_in_separate_pieces_connects_to_domain_name() {
+        let mut subject = Socks5Session::new();
+
+        let greeting_result = subject.add_data(&[0x05, 0x02, 0x02, 0x00]);
+        let partial_result = subject.add_data(&[0x05, 0x01, 0x00, 0x03, 0x0B]);
+        let request_result = subject.add_data(b"example.com\x01\xBB");
+
+        assert_eq!(
+            greeting_result,
+            SocksProgress::Incomplete {
+                reply: vec![0x05, 0x00]
+            }
+        );
+        assert_eq!(partial_result, SocksProgress::Incomplete { reply: vec![] });
+        let target = Host {
+            name: "example.com".to_string(),
+            port: Some(443),
+        };
+        assert_eq!(
+            request_result,
+            SocksProgress::Connected {
+                reply: vec![],
+                target: target.clone(),
+                leftover: vec![],
+            }
+        );
+        assert_eq!(subject.target_opt(), Some(&target));
+    }
+
+    #[test]
+    fn handshake_in_one_piece_connects_to_ip_addresses_and_keeps_leftover_data() {
+        let mut ipv4_data = vec![0x05, 0x01, 0x00];
+        ipv4_data.extend(connect_request(&[0x01, 1, 2, 3, 4], 80));
+        ipv4_data.extend(b"GET / HTTP/1.1\r\n");
+        let mut ipv6_data = vec![0x05, 0x01, 0x00];
+        let mut ipv6_address = vec![0x04];
+        ipv6_address.extend(&Ipv6Addr::LOCALHOST.octets());
+        ipv6_data.extend(connect_request(&ipv6_address, 8080));
+
+        let ipv4_result = Socks5Session::new().add_data(&ipv4_data);
+        let ipv6_result = Socks5Session::new().add_data(&ipv6_data);
+
+        assert_eq!(
+            ipv4_result,
+            SocksProgress::Connected {
+                reply: vec![0x05, 0x00],
+                target: Host {
+                    name: "1.2.3.4".to_string(),
+                    port: Some(80)
+                },
+                leftover: b"GET / HTTP/1.1\r\n".to_vec(),
+            }
+        );
+        assert_eq!(
+            ipv6_result,
+            SocksProgress::Connected {
+                reply: vec![0x05, 0x00],
+                target: Host {
+                    name: "::1".to_string(),
+                    port: Some(8080)
+                },
+                leftover: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn clients_that_insist_on_authentication_are_refused() {
+        let mut subject = Socks5Session::new();
+
+        let result = subject.add_data(&[0x05, 0x01, 0x02]);
+
+        assert_eq!(
+            result,
+            SocksProgress::Refused {
+                reply: vec![0x05, 0xFF]
+            }
+        );
+        assert_eq!(
+            subject.add_data(&[0x05]),
+            SocksProgress::Refused { reply: vec![] }
+        );
+    }
+
+    #[test]
+    fn clients_that_dont_speak_socks5_are_refused_without_a_reply() {
+        let mut subject = Socks5Session::new();
+
+        let result = subject.add_data(&[0x04, 0x01, 0x00, 0x50]);
+
+        assert_eq!(result, SocksProgress::Refused { reply: vec![] });
+    }
+
+    #[test]
+    fn bind_and_udp_associate_are_refused_as_unsupported() {
+        [0x02u8, 0x03].iter().for_each(|command| {
+            let mut subject = Socks5Session::new();
+            subject.add_data(&[0x05, 0x01, 0x00]);
+
+            let result = subject.add_data(&[0x05, *command, 0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+
+            assert_eq!(
+                result,
+                SocksProgress::Refused {
+                    reply: Socks5Session::connect_reply(SocksReplyCode::CommandNotSupported)
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn unknown_address_types_are_refused() {
+        let mut subject = Socks5Session::new();
+        subject.add_data(&[0x05, 0x01, 0x00]);
+
+        let result = subject.add_data(&[0x05, 0x01, 0x00, 0x07, 1, 2, 3, 4, 0, 80]);
+
+        assert_eq!(
+            result,
+            SocksProgress::Refused {
+                reply: vec![0x05, 0x08, 0x00, 0x01, 0, 0, 0, 0, 0, 0]
+            }
+        );
+    }
+
+    #[test]
+    fn sequence_numbers_are_shifted_past_the_handshake() {
+        let mut subject = Socks5Session::new();
+
+        assert_eq!(subject.next_reply_sequence_number(), 0);
+        assert_eq!(subject.next_reply_sequence_number(), 1);
+        subject.start_data_sequence_at(2);
+
+        assert_eq!(subject.response_sequence_offset(), 2);
+        assert_eq!(subject.data_sequence_number(2), 0);
+        assert_eq!(subject.data_sequence_number(5), 3);
+    }
+
+    #[test]
+    fn protocol_is_decided_by_the_first_data() {
+        let mut tls_subject = Socks5Session::new();
+        let mut http_subject = Socks5Session::new();
+
+        assert_eq!(tls_subject.protocol(&[]), ProxyProtocol::HTTP);
+        assert_eq!(
+            tls_subject.protocol(&[0x16, 0x03, 0x01]),
+            ProxyProtocol::TLS
+        );
+        assert_eq!(tls_subject.protocol(b"GET"), ProxyProtocol::TLS);
+        assert_eq!(http_subject.protocol(b"SSH-2.0"), ProxyProtocol::HTTP);
+    }
+}
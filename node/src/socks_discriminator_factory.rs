@@ -0,0 +1,97 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::Discriminator;
+use crate::discriminator::DiscriminatorFactory;
+use crate::null_masquerader::NullMasquerader;
+use crate::sub_lib::framer::FramedChunk;
+use crate::sub_lib::framer::Framer;
+
+// SOCKS5 has no framing of its own once the handshake is over, and the ProxyServer parses the
+// handshake itself, so whatever arrives on the SOCKS port is passed along just as it was read.
+#[derive(Default)]
+pub struct SocksFramer {
+    data_so_far: Vec<u8>,
+}
+
+impl Framer for SocksFramer {
+    fn add_data(&mut self, data: &[u8]) {
+        self.data_so_far.extend(data);
+    }
+
+    fn take_frame(&mut self) -> Option<FramedChunk> {
+        if self.data_so_far.is_empty() {
+            None
+        } else {
+            Some(FramedChunk {
+                chunk: self.data_so_far.drain(..).collect(),
+                last_chunk: false,
+            })
+        }
+    }
+}
+
+impl SocksFramer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SocksDiscriminatorFactory {}
+
+impl DiscriminatorFactory for SocksDiscriminatorFactory {
+    fn make(&self) -> Discriminator {
+        Discriminator::new(
+            Box::new(SocksFramer::new()),
+            vec![Box::new(NullMasquerader::new())],
+        )
+    }
+
+    fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
+        Box::new(SocksDiscriminatorFactory {})
+    }
+}
+
+impl SocksDiscriminatorFactory {
+    pub fn new() -> SocksDiscriminatorFactory {
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discriminator::UnmaskedChunk;
+
+    #[test]
+    fn framer_passes_everything_it_has_as_one_frame() {
+        let mut subject = SocksFramer::new();
+        assert_eq!(subject.take_frame(), None);
+
+        subject.add_data(&[0x05, 0x01]);
+        subject.add_data(&[0x00]);
+
+        assert_eq!(
+            subject.take_frame(),
+            Some(FramedChunk {
+                chunk: vec![0x05, 0x01, 0x00],
+                last_chunk: false,
+            })
+        );
+        assert_eq!(subject.take_frame(), None);
+    }
+
+    #[test]
+    fn factory_makes_discriminator_that_passes_socks_data_to_proxy_server() {
+        let data: &[u8] = &[0x05, 0x01, 0x00];
+        let subject = SocksDiscriminatorFactory::new();
+
+        let mut result = subject.make();
+
+        result.add_data(data);
+        assert_eq!(
+            result.take_chunk(),
+            Some(UnmaskedChunk::new(Vec::from(data), true, true))
+        );
+        subject.duplicate();
+    }
+}
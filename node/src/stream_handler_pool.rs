@@ -2,6 +2,7 @@
 use crate::bootstrapper::PortConfiguration;
 use crate::discriminator::DiscriminatorFactory;
 use crate::json_masquerader::JsonMasquerader;
+use crate::malefactor_dao::MALEFACTOR_CACHE;
 use crate::masquerader::Masquerader;
 use crate::stream_messages::*;
 use crate::stream_reader::StreamReaderReal;
@@ -307,6 +308,15 @@ impl StreamHandlerPool {
         &mut self,
         msg: AddStreamMsg,
     ) -> <Self as Handler<AddStreamMsg>>::Result {
+        let peer_addr = msg.connection_info.peer_addr;
+        if msg.port_configuration.is_clandestine && MALEFACTOR_CACHE.is_banned_ip(&peer_addr.ip()) {
+            warning!(
+                self.logger,
+                "Refusing connection from banned malefactor at {}",
+                peer_addr
+            );
+            return;
+        }
         let port_config = msg.port_configuration.clone();
         self.set_up_stream_writer(
             msg.connection_info.writer,
@@ -382,6 +392,22 @@ impl StreamHandlerPool {
         // TODO: Picking the first port is a temporary hack. This problem should go away with clandestine ports.
         let peer_addr = SocketAddr::new(node_addr.ip_addr(), node_addr.ports()[0]);
 
+        let banned_key = match &msg.result {
+            Some(metadata) => MALEFACTOR_CACHE.is_banned_key(&metadata.public_key),
+            None => false,
+        };
+        if banned_key || MALEFACTOR_CACHE.is_banned_ip(&peer_addr.ip()) {
+            warning!(
+                self.logger,
+                "Refusing to send {} bytes to banned malefactor at {}",
+                msg.context.data.len(),
+                peer_addr
+            );
+            self.stream_writers
+                .remove(&StreamWriterKey::from(peer_addr));
+            return;
+        }
+
         let sw_key = StreamWriterKey::from(peer_addr);
         match self.stream_writers.get(&sw_key) {
             Some(Some(tx_box)) => {
@@ -551,6 +577,7 @@ mod tests {
     use crate::http_request_start_finder::HttpRequestDiscriminatorFactory;
     use crate::json_discriminator_factory::JsonDiscriminatorFactory;
     use crate::json_masquerader::JsonMasquerader;
+    use crate::malefactor_dao::Malefactor;
     use crate::masquerader::Masquerader;
     use crate::node_test_utils::FailingMasquerader;
     use crate::sub_lib::cryptde::CryptDE;
@@ -1925,4 +1952,92 @@ mod tests {
             1000,
         );
     }
+
+    #[test]
+    fn stream_handler_pool_refuses_clandestine_connection_from_banned_malefactor() {
+        init_test_logging();
+        let peer_addr = SocketAddr::from_str("6.5.4.1:7654").unwrap();
+        MALEFACTOR_CACHE.insert(&Malefactor::new(
+            None,
+            Some(peer_addr.ip()),
+            "Forged Gossip".to_string(),
+            Duration::from_secs(3600),
+        ));
+        let mut subject = StreamHandlerPool::new(vec![]);
+        let connection_info = ConnectionInfo {
+            reader: Box::new(ReadHalfWrapperMock::new()),
+            writer: Box::new(WriteHalfWrapperMock::new()),
+            local_addr: SocketAddr::from_str("1.2.3.5:7654").unwrap(),
+            peer_addr,
+        };
+
+        subject.handle_add_stream_msg(AddStreamMsg::new(
+            connection_info,
+            Some(7654),
+            PortConfiguration::new(vec![Box::new(JsonDiscriminatorFactory::new())], true),
+        ));
+
+        assert!(subject.stream_writers.is_empty());
+        TestLogHandler::new().exists_log_containing(
+            "WARN: Dispatcher: Refusing connection from banned malefactor at 6.5.4.1:7654",
+        );
+    }
+
+    #[test]
+    fn node_query_response_handler_refuses_to_connect_to_banned_malefactor() {
+        init_test_logging();
+        let key = PublicKey::new(b"banned_by_key");
+        MALEFACTOR_CACHE.insert(&Malefactor::new(
+            Some(key.clone()),
+            None,
+            "Forged Gossip".to_string(),
+            Duration::from_secs(3600),
+        ));
+        let key_a = key.clone();
+
+        thread::spawn(move || {
+            let system =
+                System::new("node_query_response_handler_refuses_to_connect_to_banned_malefactor");
+            let mut subject = StreamHandlerPool::new(vec![]);
+            subject.stream_connector = Box::new(StreamConnectorMock::new()); // this will panic if a connection is attempted
+            let subject_addr: Addr<StreamHandlerPool> = subject.start();
+            let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
+            let peer_actors = peer_actors_builder().build();
+            subject_subs
+                .bind
+                .try_send(PoolBindMessage {
+                    dispatcher_subs: peer_actors.dispatcher,
+                    stream_handler_pool_subs: subject_subs.clone(),
+                    neighborhood_subs: peer_actors.neighborhood,
+                })
+                .unwrap();
+
+            subject_subs
+                .node_query_response
+                .try_send(DispatcherNodeQueryResponse {
+                    result: Some(NodeQueryResponseMetadata::new(
+                        key_a.clone(),
+                        Some(NodeAddr::new(
+                            &IpAddr::from_str("6.5.4.2").unwrap(),
+                            &[7654],
+                        )),
+                        rate_pack(100),
+                    )),
+                    context: TransmitDataMsg {
+                        endpoint: Endpoint::Key(key_a),
+                        last_data: false,
+                        sequence_number: None,
+                        data: b"hello".to_vec(),
+                    },
+                })
+                .unwrap();
+
+            system.run();
+        });
+
+        TestLogHandler::new().await_log_containing(
+            "WARN: Dispatcher: Refusing to send 5 bytes to banned malefactor at 6.5.4.2:7654",
+            1000,
+        );
+    }
 }
@@ -1,5 +1,5 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
-use crate::accountant::{ReceivedPayments, SentPayments};
+use crate::accountant::{PaymentCurves, ReceivedPayments, ReportTransactionReceipts, SentPayments};
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::wallet::Wallet;
 use actix::Message;
@@ -22,6 +22,7 @@ lazy_static! {
 pub struct AccountantConfig {
     pub payable_scan_interval: Duration,
     pub payment_received_scan_interval: Duration,
+    pub payment_curves: PaymentCurves,
 }
 
 #[derive(Clone)]
@@ -34,6 +35,7 @@ pub struct AccountantSubs {
     pub report_exit_service_consumed: Recipient<ReportExitServiceConsumedMessage>,
     pub report_new_payments: Recipient<ReceivedPayments>,
     pub report_sent_payments: Recipient<SentPayments>,
+    pub report_transaction_receipts: Recipient<ReportTransactionReceipts>,
     pub ui_message_sub: Recipient<NodeFromUiMessage>,
 }
 
@@ -112,6 +114,7 @@ mod tests {
             report_exit_service_consumed: recipient!(recorder, ReportExitServiceConsumedMessage),
             report_new_payments: recipient!(recorder, ReceivedPayments),
             report_sent_payments: recipient!(recorder, SentPayments),
+            report_transaction_receipts: recipient!(recorder, ReportTransactionReceipts),
             ui_message_sub: recipient!(recorder, NodeFromUiMessage),
         };
 
@@ -1,8 +1,9 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 use crate::accountant::payable_dao::{PayableAccount, Payment};
-use crate::blockchain::blockchain_bridge::RetrieveTransactions;
+use crate::blockchain::blockchain_bridge::{RequestTransactionReceipts, RetrieveTransactions};
 use crate::blockchain::blockchain_interface::BlockchainResult;
+use crate::blockchain::fee_strategy::FeeStrategyKind;
 use crate::sub_lib::peer_actors::BindMessage;
 use actix::Message;
 use actix::Recipient;
@@ -15,6 +16,12 @@ pub struct BlockchainBridgeConfig {
     pub blockchain_service_url: Option<String>,
     pub chain_id: u8,
     pub gas_price: u64,
+    pub batch_payments: bool,
+    pub fee_strategy: FeeStrategyKind,
+    // In Gwei, like gas_price; None means whatever the fee strategy suggests goes
+    pub max_fee_per_gas_opt: Option<u64>,
+    // How deep a block must be before the payments in it are credited
+    pub confirmations: u64,
 }
 
 #[derive(Clone)]
@@ -22,6 +29,7 @@ pub struct BlockchainBridgeSubs {
     pub bind: Recipient<BindMessage>,
     pub report_accounts_payable: Recipient<ReportAccountsPayable>,
     pub retrieve_transactions: Recipient<RetrieveTransactions>,
+    pub request_transaction_receipts: Recipient<RequestTransactionReceipts>,
     pub ui_sub: Recipient<NodeFromUiMessage>,
 }
 
@@ -67,6 +75,7 @@ mod tests {
             bind: recipient!(recorder, BindMessage),
             report_accounts_payable: recipient!(recorder, ReportAccountsPayable),
             retrieve_transactions: recipient!(recorder, RetrieveTransactions),
+            request_transaction_receipts: recipient!(recorder, RequestTransactionReceipts),
             ui_sub: recipient!(recorder, NodeFromUiMessage),
         };
 
@@ -185,7 +185,46 @@ impl CryptDEReal {
     pub fn new(chain_id: u8) -> Self {
         let (e_public, e_secret) = encryption::gen_keypair();
         let (s_public, s_secret) = signing::gen_keypair();
-        let public_key = Self::local_public_key_from(&e_public, &s_public);
+        Self::from_key_pairs(&e_public, e_secret, &s_public, s_secret, chain_id)
+    }
+
+    // Key material is the encryption secret key followed by the signing secret key; both public
+    // keys can be recomputed from it.
+    pub fn from_key_material(key_material: &PlainData, chain_id: u8) -> Result<Self, String> {
+        let bytes = key_material.as_slice();
+        if bytes.len() != cxsp::SECRETKEYBYTES + signing::SECRETKEYBYTES {
+            return Err(format!(
+                "Key material must be {} bytes, not {}",
+                cxsp::SECRETKEYBYTES + signing::SECRETKEYBYTES,
+                bytes.len()
+            ));
+        }
+        let e_secret = encryption::SecretKey::from_slice(&bytes[..cxsp::SECRETKEYBYTES])
+            .expect("Encryption secret key length was already checked");
+        let s_secret = signing::SecretKey::from_slice(&bytes[cxsp::SECRETKEYBYTES..])
+            .expect("Signing secret key length was already checked");
+        let e_public = e_secret.public_key();
+        let s_public = s_secret.public_key();
+        Ok(Self::from_key_pairs(
+            &e_public, e_secret, &s_public, s_secret, chain_id,
+        ))
+    }
+
+    pub fn key_material(&self) -> PlainData {
+        let mut bytes: Vec<u8> = Vec::with_capacity(cxsp::SECRETKEYBYTES + signing::SECRETKEYBYTES);
+        bytes.extend(&self.encryption_secret_key.0[..]);
+        bytes.extend(&self.signing_secret_key.0[..]);
+        PlainData::from(bytes)
+    }
+
+    fn from_key_pairs(
+        e_public: &encryption::PublicKey,
+        e_secret: encryption::SecretKey,
+        s_public: &signing::PublicKey,
+        s_secret: signing::SecretKey,
+        chain_id: u8,
+    ) -> Self {
+        let public_key = Self::local_public_key_from(e_public, s_public);
         let digest = cryptde::create_digest(&public_key, &contract_address(chain_id));
         let pre_shared_data = contract_address(chain_id).0;
 
@@ -244,6 +283,33 @@ mod tests {
         assert_ne!(first_subject.public_key(), second_subject.public_key());
     }
 
+    #[test]
+    fn key_material_survives_a_round_trip() {
+        let subject = CryptDEReal::default();
+        let data = PlainData::new(b"Secret stuff");
+        let encoded = subject.encode(subject.public_key(), &data).unwrap();
+        let signature = subject.sign(&data).unwrap();
+
+        let result =
+            CryptDEReal::from_key_material(&subject.key_material(), DEFAULT_CHAIN_ID).unwrap();
+
+        assert_eq!(result.public_key(), subject.public_key());
+        assert_eq!(result.digest(), subject.digest());
+        assert_eq!(result.decode(&encoded).unwrap(), data);
+        assert!(subject.verify_signature(&data, &result.sign(&data).unwrap(), result.public_key()));
+        assert!(result.verify_signature(&data, &signature, subject.public_key()));
+    }
+
+    #[test]
+    fn from_key_material_rejects_key_material_of_the_wrong_length() {
+        let result = CryptDEReal::from_key_material(&PlainData::new(&[1, 2, 3]), DEFAULT_CHAIN_ID);
+
+        assert_eq!(
+            result.err(),
+            Some("Key material must be 96 bytes, not 3".to_string())
+        );
+    }
+
     #[test]
     fn dup_produces_identical_keys() {
         let subject = CryptDEReal::default();
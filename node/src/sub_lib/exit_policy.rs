@@ -0,0 +1,282 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+pub trait CountryFinder {
+    fn country_of(&self, ip_addr: &IpAddr) -> Option<String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip_addr: &IpAddr) -> bool {
+        match (self.network, ip_addr) {
+            (IpAddr::V4(network), IpAddr::V4(ip_addr)) => {
+                Self::prefix_matches(&network.octets(), &ip_addr.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip_addr)) => {
+                Self::prefix_matches(&network.octets(), &ip_addr.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+
+    fn prefix_matches(network: &[u8], ip_addr: &[u8], prefix_len: u8) -> bool {
+        let whole_bytes = (prefix_len / 8) as usize;
+        let leftover_bits = prefix_len % 8;
+        if network[..whole_bytes] != ip_addr[..whole_bytes] {
+            return false;
+        }
+        if leftover_bits == 0 {
+            return true;
+        }
+        let mask = 0xFFu8 << (8 - leftover_bits);
+        (network[whole_bytes] & mask) == (ip_addr[whole_bytes] & mask)
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pieces = s.splitn(2, '/');
+        let network = IpAddr::from_str(pieces.next().expect("splitn is broken"))
+            .map_err(|_| format!("Bad IP address in '{}'", s))?;
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match pieces.next() {
+            None => max_prefix_len,
+            Some(prefix_len_str) => match u8::from_str(prefix_len_str) {
+                Ok(prefix_len) if prefix_len <= max_prefix_len => prefix_len,
+                _ => return Err(format!("Bad prefix length in '{}'", s)),
+            },
+        };
+        Ok(IpRange {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl Display for IpRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExitRule {
+    IpRange(IpRange),
+    Country(String),
+}
+
+impl ExitRule {
+    fn matches(&self, ip_addr: &IpAddr, country_finder_opt: Option<&dyn CountryFinder>) -> bool {
+        match self {
+            ExitRule::IpRange(ip_range) => ip_range.contains(ip_addr),
+            ExitRule::Country(country) => match country_finder_opt {
+                Some(country_finder) => country_finder
+                    .country_of(ip_addr)
+                    .map(|found| found.eq_ignore_ascii_case(country))
+                    .unwrap_or(false),
+                None => false,
+            },
+        }
+    }
+}
+
+// A two-letter rule is an ISO 3166 country code; anything else must be an IP address or CIDR range.
+impl FromStr for ExitRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+            Ok(ExitRule::Country(s.to_uppercase()))
+        } else {
+            Ok(ExitRule::IpRange(IpRange::from_str(s)?))
+        }
+    }
+}
+
+impl Display for ExitRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitRule::IpRange(ip_range) => write!(f, "{}", ip_range),
+            ExitRule::Country(country) => write!(f, "{}", country),
+        }
+    }
+}
+
+// An exit Node qualifies if it matches at least one required rule (when there are any) and no
+// excluded rule. Rules can only match Nodes whose IP addresses we know, so as soon as there's
+// any policy at all, exit Nodes that haven't revealed their IP addresses don't qualify. Likewise,
+// without a GeoIP database to resolve country rules, no exit Node qualifies.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExitPolicy {
+    pub required: Vec<ExitRule>,
+    pub excluded: Vec<ExitRule>,
+}
+
+impl ExitPolicy {
+    pub fn parse_rules(rules: &str) -> Result<Vec<ExitRule>, String> {
+        rules
+            .split(',')
+            .map(|rule| rule.trim())
+            .filter(|rule| !rule.is_empty())
+            .map(ExitRule::from_str)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.excluded.is_empty()
+    }
+
+    pub fn has_country_rules(&self) -> bool {
+        self.required
+            .iter()
+            .chain(self.excluded.iter())
+            .any(|rule| matches!(rule, ExitRule::Country(_)))
+    }
+
+    pub fn permits(
+        &self,
+        ip_addr_opt: Option<IpAddr>,
+        country_finder_opt: Option<&dyn CountryFinder>,
+    ) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        if self.has_country_rules() && country_finder_opt.is_none() {
+            return false;
+        }
+        let ip_addr = match ip_addr_opt {
+            Some(ip_addr) => ip_addr,
+            None => return false,
+        };
+        let rule_matches = |rule: &ExitRule| rule.matches(&ip_addr, country_finder_opt);
+        (self.required.is_empty() || self.required.iter().any(rule_matches))
+            && !self.excluded.iter().any(rule_matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct CountryFinderMock {
+        countries: HashMap<IpAddr, String>,
+    }
+
+    impl CountryFinder for CountryFinderMock {
+        fn country_of(&self, ip_addr: &IpAddr) -> Option<String> {
+            self.countries.get(ip_addr).cloned()
+        }
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn ip_range_parses_and_matches_ipv4_and_ipv6() {
+        let ipv4 = IpRange::from_str("10.20.0.0/14").unwrap();
+        let ipv6 = IpRange::from_str("2001:db8::/32").unwrap();
+        let single = IpRange::from_str("1.2.3.4").unwrap();
+
+        assert!(ipv4.contains(&ip("10.23.255.255")));
+        assert!(!ipv4.contains(&ip("10.24.0.0")));
+        assert!(!ipv4.contains(&ip("::ffff:10.20.0.1")));
+        assert!(ipv6.contains(&ip("2001:db8:1234::1")));
+        assert!(!ipv6.contains(&ip("2001:db9::1")));
+        assert!(single.contains(&ip("1.2.3.4")));
+        assert!(!single.contains(&ip("1.2.3.5")));
+        assert_eq!(single.to_string(), "1.2.3.4/32".to_string());
+    }
+
+    #[test]
+    fn ip_range_rejects_nonsense() {
+        assert_eq!(
+            IpRange::from_str("10.0.0.0/33"),
+            Err("Bad prefix length in '10.0.0.0/33'".to_string())
+        );
+        assert_eq!(
+            IpRange::from_str("10.0.0/8"),
+            Err("Bad IP address in '10.0.0/8'".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_rules_recognizes_countries_and_ranges() {
+        let result = ExitPolicy::parse_rules("de, 10.0.0.0/8,,2001:db8::/32");
+
+        assert_eq!(
+            result,
+            Ok(vec![
+                ExitRule::Country("DE".to_string()),
+                ExitRule::IpRange(IpRange::from_str("10.0.0.0/8").unwrap()),
+                ExitRule::IpRange(IpRange::from_str("2001:db8::/32").unwrap()),
+            ])
+        );
+        assert_eq!(
+            ExitPolicy::parse_rules("DE,Germany"),
+            Err("Bad IP address in 'Germany'".to_string())
+        );
+    }
+
+    #[test]
+    fn empty_policy_permits_everything() {
+        let subject = ExitPolicy::default();
+
+        assert!(subject.permits(None, None));
+        assert!(subject.permits(Some(ip("1.2.3.4")), None));
+        assert!(!subject.has_country_rules());
+    }
+
+    #[test]
+    fn policy_requires_and_excludes() {
+        let country_finder = CountryFinderMock {
+            countries: vec![
+                (ip("10.1.1.1"), "DE".to_string()),
+                (ip("10.2.2.2"), "DE".to_string()),
+                (ip("10.3.3.3"), "FR".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let subject = ExitPolicy {
+            required: ExitPolicy::parse_rules("de,192.168.0.0/16").unwrap(),
+            excluded: ExitPolicy::parse_rules("10.2.0.0/16").unwrap(),
+        };
+        let permits = |ip_str: &str| subject.permits(Some(ip(ip_str)), Some(&country_finder));
+
+        assert!(permits("10.1.1.1"));
+        assert!(!permits("10.2.2.2"));
+        assert!(!permits("10.3.3.3"));
+        assert!(permits("192.168.5.5"));
+        assert!(!permits("172.16.0.1"));
+        assert!(!subject.permits(None, Some(&country_finder)));
+        assert!(!subject.permits(Some(ip("10.1.1.1")), None));
+        assert!(subject.has_country_rules());
+    }
+
+    #[test]
+    fn policy_with_country_rules_permits_nothing_without_a_country_finder() {
+        let excluding = ExitPolicy {
+            required: vec![],
+            excluded: ExitPolicy::parse_rules("de").unwrap(),
+        };
+        let ranges_only = ExitPolicy {
+            required: vec![],
+            excluded: ExitPolicy::parse_rules("10.2.0.0/16").unwrap(),
+        };
+
+        assert!(!excluding.permits(Some(ip("10.1.1.1")), None));
+        assert!(ranges_only.permits(Some(ip("10.1.1.1")), None));
+    }
+}
@@ -0,0 +1,523 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::sub_lib::exit_policy::IpRange;
+use lazy_static::lazy_static;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+lazy_static! {
+    // Destinations an exit Node refuses unless its operator explicitly accepts them: itself, the
+    // LAN it sits on, and everything else that isn't the public Internet; plus outbound SMTP.
+    static ref DEFAULT_RULES: Vec<ExitServiceRule> = ExitServicePolicy::parse_rules(
+        "reject 0.0.0.0/8, reject 10.0.0.0/8, reject 100.64.0.0/10, reject 127.0.0.0/8, \
+         reject 169.254.0.0/16, reject 172.16.0.0/12, reject 192.168.0.0/16, reject 224.0.0.0/4, \
+         reject 240.0.0.0/4, reject [::]/128, reject [::1]/128, reject [fc00::]/7, reject [fe80::]/10, \
+         reject [ff00::]/8, reject *:25"
+    )
+    .expect("Default exit service rules are bad");
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitServiceAction {
+    Accept,
+    Reject,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExitServiceTarget {
+    Any,
+    IpRange(IpRange),
+    Host(String),
+    Domain(String),
+}
+
+impl ExitServiceTarget {
+    // None means the rule can't be decided until the host name has been resolved.
+    fn matches(&self, hostname_opt: Option<&str>, ip_addr_opt: Option<IpAddr>) -> Option<bool> {
+        match self {
+            ExitServiceTarget::Any => Some(true),
+            ExitServiceTarget::IpRange(ip_range) => {
+                ip_addr_opt.map(|ip_addr| ip_range.contains(&ip_addr))
+            }
+            ExitServiceTarget::Host(host) => Some(
+                hostname_opt
+                    .map(|hostname| hostname == host)
+                    .unwrap_or(false),
+            ),
+            ExitServiceTarget::Domain(domain) => Some(
+                hostname_opt
+                    .map(|hostname| {
+                        hostname.len() > domain.len()
+                            && hostname.ends_with(domain.as_str())
+                            && hostname[..hostname.len() - domain.len()].ends_with('.')
+                    })
+                    .unwrap_or(false),
+            ),
+        }
+    }
+}
+
+impl FromStr for ExitServiceTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "*" {
+            return Ok(ExitServiceTarget::Any);
+        }
+        if let Some(bracketed) = s.strip_prefix('[') {
+            let ip_range = bracketed.replacen(']', "", 1);
+            return Ok(ExitServiceTarget::IpRange(IpRange::from_str(&ip_range)?));
+        }
+        if s.chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == '/')
+        {
+            return Ok(ExitServiceTarget::IpRange(IpRange::from_str(s)?));
+        }
+        let (host, is_domain) = if let Some(domain) = s.strip_prefix("*.") {
+            (domain, true)
+        } else {
+            (s, false)
+        };
+        let is_label = |label: &str| {
+            !label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if !host.split('.').all(is_label) {
+            return Err(format!("Bad destination in '{}'", s));
+        }
+        let host = host.to_lowercase();
+        Ok(if is_domain {
+            ExitServiceTarget::Domain(host)
+        } else {
+            ExitServiceTarget::Host(host)
+        })
+    }
+}
+
+impl Display for ExitServiceTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitServiceTarget::Any => write!(f, "*"),
+            ExitServiceTarget::IpRange(ip_range) => {
+                let ip_range = ip_range.to_string();
+                if ip_range.contains(':') {
+                    let mut pieces = ip_range.splitn(2, '/');
+                    let network = pieces.next().expect("splitn is broken");
+                    let prefix_len = pieces.next().expect("IpRange has no prefix length");
+                    write!(f, "[{}]/{}", network, prefix_len)
+                } else {
+                    write!(f, "{}", ip_range)
+                }
+            }
+            ExitServiceTarget::Host(host) => write!(f, "{}", host),
+            ExitServiceTarget::Domain(domain) => write!(f, "*.{}", domain),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExitServiceRule {
+    pub action: ExitServiceAction,
+    pub target: ExitServiceTarget,
+    pub ports: (u16, u16),
+}
+
+impl ExitServiceRule {
+    fn matches(
+        &self,
+        hostname_opt: Option<&str>,
+        ip_addr_opt: Option<IpAddr>,
+        port: u16,
+    ) -> Option<bool> {
+        if port < self.ports.0 || port > self.ports.1 {
+            return Some(false);
+        }
+        self.target.matches(hostname_opt, ip_addr_opt)
+    }
+
+    fn parse_ports(s: &str) -> Option<(u16, u16)> {
+        if s == "*" {
+            return Some((1, 65535));
+        }
+        let mut pieces = s.splitn(2, '-');
+        let low = u16::from_str(pieces.next().expect("splitn is broken")).ok()?;
+        let high = match pieces.next() {
+            Some(high) => u16::from_str(high).ok()?,
+            None => low,
+        };
+        if low == 0 || high < low {
+            None
+        } else {
+            Some((low, high))
+        }
+    }
+}
+
+// A rule is "accept" or "reject", then a destination, then optionally a colon and a port or port
+// range: for example, "reject 10.0.0.0/8", "accept *.example.com:443", "reject [fe80::]/10:*",
+// "reject *:6660-6669". IPv6 addresses go in brackets so that their colons aren't taken for ports.
+impl FromStr for ExitServiceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let action = match words.next().map(|word| word.to_lowercase()) {
+            Some(ref word) if word == "accept" => ExitServiceAction::Accept,
+            Some(ref word) if word == "reject" => ExitServiceAction::Reject,
+            _ => return Err(format!("Rule '{}' must start with accept or reject", s)),
+        };
+        let destination = match (words.next(), words.next()) {
+            (Some(destination), None) => destination,
+            _ => return Err(format!("Rule '{}' must have exactly one destination", s)),
+        };
+        if !destination.starts_with('[') && destination.matches(':').count() > 1 {
+            return Err(format!("IPv6 addresses must be in brackets in '{}'", s));
+        }
+        let port_colon_opt = match destination.rfind(']') {
+            Some(bracket) => destination[bracket..].find(':').map(|idx| bracket + idx),
+            None => destination.rfind(':'),
+        };
+        let (target_str, ports) = match port_colon_opt {
+            Some(colon) => (
+                &destination[..colon],
+                Self::parse_ports(&destination[colon + 1..])
+                    .ok_or_else(|| format!("Bad port or port range in '{}'", s))?,
+            ),
+            None => (destination, (1, 65535)),
+        };
+        Ok(ExitServiceRule {
+            action,
+            target: ExitServiceTarget::from_str(target_str)?,
+            ports,
+        })
+    }
+}
+
+impl Display for ExitServiceRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            ExitServiceAction::Accept => "accept",
+            ExitServiceAction::Reject => "reject",
+        };
+        let ports = match self.ports {
+            (1, 65535) => "*".to_string(),
+            (low, high) if low == high => format!("{}", low),
+            (low, high) => format!("{}-{}", low, high),
+        };
+        write!(f, "{} {}:{}", action, self.target, ports)
+    }
+}
+
+// Decides which destinations an exit Node will connect to on behalf of other Nodes. The operator's
+// rules come first, then the default rules; the first rule that matches a request decides it, and
+// a request no rule matches is accepted. Requests are checked once before the target host name is
+// resolved, so that names and ports that are refused outright are never looked up, and again for
+// every IP address the name resolves to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExitServicePolicy {
+    rules: Vec<ExitServiceRule>,
+}
+
+impl Default for ExitServicePolicy {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl ExitServicePolicy {
+    pub fn new(operator_rules: Vec<ExitServiceRule>) -> Self {
+        let mut rules = operator_rules;
+        rules.extend(DEFAULT_RULES.iter().cloned());
+        Self { rules }
+    }
+
+    // A zero-hop Node is its own exit and carries only its owner's traffic, so the default rules
+    // would do nothing but keep the owner away from their own LAN.
+    pub fn without_defaults(operator_rules: Vec<ExitServiceRule>) -> Self {
+        Self {
+            rules: operator_rules,
+        }
+    }
+
+    pub fn parse_rules(rules: &str) -> Result<Vec<ExitServiceRule>, String> {
+        rules
+            .split(',')
+            .map(|rule| rule.trim())
+            .filter(|rule| !rule.is_empty())
+            .map(ExitServiceRule::from_str)
+            .collect()
+    }
+
+    pub fn check_before_resolution(
+        &self,
+        hostname: &str,
+        port: u16,
+    ) -> Result<(), &ExitServiceRule> {
+        let hostname = Self::normalize_hostname(hostname);
+        for rule in &self.rules {
+            match rule.matches(Some(&hostname), None, port) {
+                Some(true) => return Self::verdict(rule),
+                Some(false) => continue,
+                None => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check(
+        &self,
+        hostname_opt: Option<&str>,
+        ip_addr: IpAddr,
+        port: u16,
+    ) -> Result<(), &ExitServiceRule> {
+        let hostname_opt = hostname_opt.map(Self::normalize_hostname);
+        let ip_addr = Self::unmap_ipv4(ip_addr);
+        match self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(hostname_opt.as_deref(), Some(ip_addr), port) == Some(true))
+        {
+            Some(rule) => Self::verdict(rule),
+            None => Ok(()),
+        }
+    }
+
+    fn verdict(rule: &ExitServiceRule) -> Result<(), &ExitServiceRule> {
+        match rule.action {
+            ExitServiceAction::Accept => Ok(()),
+            ExitServiceAction::Reject => Err(rule),
+        }
+    }
+
+    fn normalize_hostname(hostname: &str) -> String {
+        hostname.trim_end_matches('.').to_lowercase()
+    }
+
+    // Otherwise ::ffff:127.0.0.1 would slip past a rule for 127.0.0.0/8.
+    fn unmap_ipv4(ip_addr: IpAddr) -> IpAddr {
+        match ip_addr {
+            IpAddr::V6(ipv6) => match ipv6.segments() {
+                [0, 0, 0, 0, 0, 0xFFFF, high, low] => IpAddr::V4(Ipv4Addr::new(
+                    (high >> 8) as u8,
+                    high as u8,
+                    (low >> 8) as u8,
+                    low as u8,
+                )),
+                _ => ip_addr,
+            },
+            IpAddr::V4(_) => ip_addr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn rule(s: &str) -> ExitServiceRule {
+        ExitServiceRule::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn rules_parse_and_display() {
+        let result = ExitServicePolicy::parse_rules(
+            "reject 10.0.0.0/8, ACCEPT *.Example.com:443,,reject [fe80::]/10:*, reject *:6660-6669, accept localhost",
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                ExitServiceRule {
+                    action: ExitServiceAction::Reject,
+                    target: ExitServiceTarget::IpRange(IpRange::from_str("10.0.0.0/8").unwrap()),
+                    ports: (1, 65535),
+                },
+                ExitServiceRule {
+                    action: ExitServiceAction::Accept,
+                    target: ExitServiceTarget::Domain("example.com".to_string()),
+                    ports: (443, 443),
+                },
+                ExitServiceRule {
+                    action: ExitServiceAction::Reject,
+                    target: ExitServiceTarget::IpRange(IpRange::from_str("fe80::/10").unwrap()),
+                    ports: (1, 65535),
+                },
+                ExitServiceRule {
+                    action: ExitServiceAction::Reject,
+                    target: ExitServiceTarget::Any,
+                    ports: (6660, 6669),
+                },
+                ExitServiceRule {
+                    action: ExitServiceAction::Accept,
+                    target: ExitServiceTarget::Host("localhost".to_string()),
+                    ports: (1, 65535),
+                },
+            ]
+        );
+        let displayed: Vec<String> = result.iter().map(|rule| rule.to_string()).collect();
+        assert_eq!(
+            displayed,
+            vec![
+                "reject 10.0.0.0/8:*",
+                "accept *.example.com:443",
+                "reject [fe80::]/10:*",
+                "reject *:6660-6669",
+                "accept localhost:*",
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_reject_nonsense() {
+        let error = |s: &str| ExitServiceRule::from_str(s).err().unwrap();
+
+        assert_eq!(
+            error("allow 1.2.3.4"),
+            "Rule 'allow 1.2.3.4' must start with accept or reject".to_string()
+        );
+        assert_eq!(
+            error("reject"),
+            "Rule 'reject' must have exactly one destination".to_string()
+        );
+        assert_eq!(
+            error("reject 1.2.3.4 5.6.7.8"),
+            "Rule 'reject 1.2.3.4 5.6.7.8' must have exactly one destination".to_string()
+        );
+        assert_eq!(
+            error("reject *:0"),
+            "Bad port or port range in 'reject *:0'".to_string()
+        );
+        assert_eq!(
+            error("reject *:80-79"),
+            "Bad port or port range in 'reject *:80-79'".to_string()
+        );
+        assert_eq!(
+            error("reject fe80::/10"),
+            "IPv6 addresses must be in brackets in 'reject fe80::/10'".to_string()
+        );
+        assert_eq!(
+            error("reject 10.0.0.0/33"),
+            "Bad prefix length in '10.0.0.0/33'".to_string()
+        );
+        assert_eq!(
+            error("reject fe80::/10:*"),
+            "IPv6 addresses must be in brackets in 'reject fe80::/10:*'".to_string()
+        );
+        assert_eq!(
+            error("reject [fe80::]/129"),
+            "Bad prefix length in 'fe80::/129'".to_string()
+        );
+        assert_eq!(
+            error("reject http://example.com"),
+            "Bad port or port range in 'reject http://example.com'".to_string()
+        );
+        assert_eq!(
+            error("reject exa$mple.com"),
+            "Bad destination in 'exa$mple.com'".to_string()
+        );
+    }
+
+    #[test]
+    fn default_policy_refuses_local_destinations_and_smtp() {
+        let subject = ExitServicePolicy::default();
+        let refused = |ip_str: &str, port: u16| subject.check(None, ip(ip_str), port).is_err();
+
+        assert!(refused("127.0.0.1", 80));
+        assert!(refused("10.1.2.3", 443));
+        assert!(refused("172.31.255.255", 443));
+        assert!(refused("192.168.0.1", 80));
+        assert!(refused("169.254.169.254", 80));
+        assert!(refused("0.0.0.0", 80));
+        assert!(refused("::1", 80));
+        assert!(refused("::ffff:127.0.0.1", 80));
+        assert!(refused("fd00::1", 80));
+        assert!(refused("fe80::1", 80));
+        assert!(refused("93.184.216.34", 25));
+        assert!(!refused("93.184.216.34", 443));
+        assert!(!refused("172.32.0.1", 80));
+        assert!(!refused("2606:2800:220:1::1", 80));
+        assert_eq!(
+            subject.check(None, ip("10.1.2.3"), 443),
+            Err(&rule("reject 10.0.0.0/8"))
+        );
+    }
+
+    #[test]
+    fn policy_without_defaults_applies_only_operator_rules() {
+        let subject = ExitServicePolicy::without_defaults(
+            ExitServicePolicy::parse_rules("reject *:6660-6669").unwrap(),
+        );
+        let refused = |ip_str: &str, port: u16| subject.check(None, ip(ip_str), port).is_err();
+
+        assert!(!refused("192.168.0.1", 80));
+        assert!(!refused("127.0.0.1", 8080));
+        assert!(!refused("93.184.216.34", 25));
+        assert!(refused("93.184.216.34", 6667));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let subject = ExitServicePolicy::new(
+            ExitServicePolicy::parse_rules(
+                "accept 192.168.1.10:8080, reject *.internal.example.com, accept *.example.com, reject *:1-1023",
+            )
+            .unwrap(),
+        );
+        let permits = |hostname_opt: Option<&str>, ip_str: &str, port: u16| {
+            subject.check(hostname_opt, ip(ip_str), port).is_ok()
+        };
+
+        assert!(permits(None, "192.168.1.10", 8080));
+        assert!(!permits(None, "192.168.1.10", 8081));
+        assert!(!permits(
+            Some("db.internal.example.com."),
+            "93.184.216.34",
+            5432
+        ));
+        assert!(permits(Some("WWW.Example.com"), "93.184.216.34", 80));
+        assert!(permits(Some("www.example.com"), "10.0.0.1", 80));
+        assert!(!permits(Some("example.com"), "93.184.216.34", 80));
+        assert!(!permits(Some("notexample.com"), "93.184.216.34", 80));
+        assert!(permits(Some("notexample.com"), "93.184.216.34", 8443));
+    }
+
+    #[test]
+    fn check_before_resolution_refuses_only_what_it_can_decide_without_addresses() {
+        let subject = ExitServicePolicy::new(
+            ExitServicePolicy::parse_rules("reject *.corp.example.com, reject *:1-79").unwrap(),
+        );
+
+        assert_eq!(
+            subject.check_before_resolution("mail.corp.example.com", 443),
+            Err(&rule("reject *.corp.example.com"))
+        );
+        assert_eq!(
+            subject.check_before_resolution("www.example.com", 22),
+            Err(&rule("reject *:1-79"))
+        );
+        assert_eq!(
+            subject.check_before_resolution("www.example.com", 25),
+            Err(&rule("reject *:1-79"))
+        );
+        assert_eq!(
+            subject.check_before_resolution("www.example.com", 443),
+            Ok(())
+        );
+        assert_eq!(
+            ExitServicePolicy::default().check_before_resolution("localhost", 80),
+            Ok(())
+        );
+        assert_eq!(
+            ExitServicePolicy::default().check_before_resolution("mail.example.com", 25),
+            Ok(())
+        );
+    }
+}
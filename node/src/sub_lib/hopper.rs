@@ -8,7 +8,9 @@ use crate::sub_lib::dispatcher::InboundClientData;
 use crate::sub_lib::neighborhood::GossipFailure_0v1;
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+use crate::sub_lib::proxy_client::{
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ExitRefusal_0v1,
+};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::route::Route;
 use crate::sub_lib::versioned_data::VersionedData;
@@ -70,6 +72,7 @@ pub enum MessageType {
     Gossip(VersionedData<Gossip_0v1>),
     GossipFailure(VersionedData<GossipFailure_0v1>),
     DnsResolveFailed(VersionedData<DnsResolveFailure_0v1>),
+    ExitRefused(VersionedData<ExitRefusal_0v1>),
 }
 
 impl IncipientCoresPackage {
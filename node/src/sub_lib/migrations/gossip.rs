@@ -7,6 +7,7 @@ use crate::sub_lib::versioned_data::{
 use lazy_static::lazy_static;
 use serde_cbor::Value;
 use std::convert::TryFrom;
+use std::net::IpAddr;
 
 lazy_static! {
     static ref MIGRATIONS: Migrations = {
@@ -70,9 +71,18 @@ impl TryFrom<&Value> for Gossip_0v1 {
                             _ => (error_opt, node_records),
                         }
                     });
+                let observed_ip_opt = map
+                    .get(&Value::Text("observed_ip_opt".to_string()))
+                    .and_then(|value| {
+                        serde_cbor::value::from_value::<Option<IpAddr>>(value.clone()).ok()
+                    })
+                    .flatten();
                 match error_opt {
                     Some(e) => Err(e),
-                    None => Ok(Gossip_0v1 { node_records }),
+                    None => Ok(Gossip_0v1 {
+                        node_records,
+                        observed_ip_opt,
+                    }),
                 }
             }
             _ => unimplemented!(), //Err (StepError::SemanticError("Inscrutable future version".to_string())),
@@ -87,12 +97,14 @@ mod tests {
     use crate::sub_lib::versioned_data::DataVersion;
     use crate::test_utils::neighborhood_test_utils::{db_from_node, make_node_record};
     use serde_derive::{Deserialize, Serialize};
+    use std::str::FromStr;
 
     #[test]
     fn can_migrate_from_the_future() {
         #[derive(Serialize, Deserialize)]
         struct ExampleFutureGossip {
             pub node_records: Vec<GossipNodeRecord>,
+            pub observed_ip_opt: Option<IpAddr>,
             pub another_field: String,
             pub yet_another_field: u64,
         }
@@ -101,12 +113,14 @@ mod tests {
         let mut db = db_from_node(&one_node);
         db.add_node(another_node.clone()).unwrap();
         db.add_arbitrary_full_neighbor(one_node.public_key(), another_node.public_key());
-        let expected_gossip = GossipBuilder::new(&db)
+        let mut expected_gossip = GossipBuilder::new(&db)
             .node(one_node.public_key(), true)
             .node(another_node.public_key(), true)
             .build();
+        expected_gossip.observed_ip_opt = Some(IpAddr::from_str("4.3.2.1").unwrap());
         let future_gossip = ExampleFutureGossip {
             node_records: expected_gossip.node_records.clone(),
+            observed_ip_opt: expected_gossip.observed_ip_opt,
             another_field: "These are the times that try men's souls".to_string(),
             yet_another_field: 1234567890,
         };
@@ -3,6 +3,7 @@
 pub mod client_request_payload;
 pub mod client_response_payload;
 pub mod dns_resolve_failure;
+pub mod exit_refusal;
 pub mod gossip;
 pub mod gossip_failure;
 pub mod node_record_inner;
@@ -20,6 +20,8 @@ pub mod cryptde_null;
 pub mod cryptde_real;
 pub mod data_version;
 pub mod dispatcher;
+pub mod exit_policy;
+pub mod exit_service_policy;
 pub mod framer;
 pub mod framer_utils;
 pub mod hop;
@@ -5,6 +5,7 @@ use crate::neighborhood::node_record::NodeRecord;
 use crate::sub_lib::configurator::NewPasswordMessage;
 use crate::sub_lib::cryptde::{CryptDE, PublicKey};
 use crate::sub_lib::dispatcher::{Component, StreamShutdownMsg};
+use crate::sub_lib::exit_policy::ExitPolicy;
 use crate::sub_lib::hopper::ExpiredCoresPackage;
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
@@ -24,6 +25,7 @@ use serde_derive::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter};
 use std::net::IpAddr;
 use std::str::FromStr;
+use std::time::Duration;
 
 pub const DEFAULT_RATE_PACK: RatePack = RatePack {
     routing_byte_rate: 100,
@@ -237,6 +239,43 @@ pub struct NeighborhoodConfig {
     pub mode: NeighborhoodMode,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoutingStrategy {
+    Cheapest,
+    Random,
+    Reliable,
+}
+
+impl Default for RoutingStrategy {
+    fn default() -> Self {
+        RoutingStrategy::Cheapest
+    }
+}
+
+impl FromStr for RoutingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cheapest" => Ok(RoutingStrategy::Cheapest),
+            "random" => Ok(RoutingStrategy::Random),
+            "reliable" => Ok(RoutingStrategy::Reliable),
+            _ => Err(format!("Unknown routing strategy: '{}'", s)),
+        }
+    }
+}
+
+impl Display for RoutingStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RoutingStrategy::Cheapest => "cheapest",
+            RoutingStrategy::Random => "random",
+            RoutingStrategy::Reliable => "reliable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 lazy_static! {
     static ref EMPTY_CONFIGS: Vec<NodeDescriptor> = vec![];
 }
@@ -288,11 +327,6 @@ impl NodeQueryResponseMetadata {
 #[derive(Clone, Debug, Message, PartialEq)]
 pub struct BootstrapNeighborhoodNowMessage {}
 
-#[derive(Clone, Debug, Message, PartialEq)]
-pub struct NeighborhoodDotGraphRequest {
-    pub client_id: u64,
-}
-
 #[derive(Clone, Debug, PartialEq)]
 pub enum NodeQueryMessage {
     IpAddress(IpAddr),
@@ -316,6 +350,7 @@ pub struct RouteQueryMessage {
     pub target_component: Component,
     pub minimum_hop_count: usize,
     pub return_component_opt: Option<Component>,
+    pub exit_policy_opt: Option<ExitPolicy>,
 }
 
 impl Message for RouteQueryMessage {
@@ -329,6 +364,7 @@ impl RouteQueryMessage {
             target_component: Component::ProxyClient,
             minimum_hop_count,
             return_component_opt: Some(Component::ProxyServer),
+            exit_policy_opt: None,
         }
     }
 }
@@ -357,9 +393,21 @@ pub struct RemoveNeighborMessage {
     pub public_key: PublicKey,
 }
 
+#[derive(Clone, Debug, Message, PartialEq)]
+pub struct NewPublicIp {
+    pub new_ip: IpAddr,
+}
+
 #[derive(Clone, Debug, Message, PartialEq)]
 pub enum NodeRecordMetadataMessage {
     Desirable(PublicKey, bool),
+    // Nodes in a route, in order with the exit Node last, and the time it took for the first
+    // response to come back
+    RouteSucceeded(Vec<PublicKey>, Duration),
+    // Nodes in a route that brought back no response before the ProxyServer gave up waiting
+    RouteFailed(Vec<PublicKey>),
+    // Exit Node that couldn't resolve a hostname
+    DnsFailure(PublicKey),
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -641,6 +689,7 @@ mod tests {
                 target_component: Component::ProxyClient,
                 minimum_hop_count: 2,
                 return_component_opt: Some(Component::ProxyServer),
+                exit_policy_opt: None,
             }
         );
     }
@@ -756,4 +805,28 @@ mod tests {
             assert_eq!(&gf.to_string(), expected_string);
         });
     }
+
+    #[test]
+    fn routing_strategy_survives_a_trip_through_a_string() {
+        vec![
+            RoutingStrategy::Cheapest,
+            RoutingStrategy::Random,
+            RoutingStrategy::Reliable,
+        ]
+        .into_iter()
+        .for_each(|strategy| {
+            assert_eq!(
+                RoutingStrategy::from_str(&strategy.to_string()),
+                Ok(strategy)
+            )
+        });
+        assert_eq!(
+            RoutingStrategy::from_str("Reliable"),
+            Ok(RoutingStrategy::Reliable)
+        );
+        assert_eq!(
+            RoutingStrategy::from_str("booga"),
+            Err("Unknown routing strategy: 'booga'".to_string())
+        );
+    }
 }
@@ -1,5 +1,6 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::sub_lib::cryptde::CryptDE;
+use crate::sub_lib::exit_service_policy::ExitServicePolicy;
 use crate::sub_lib::hopper::{ExpiredCoresPackage, MessageType};
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
@@ -24,6 +25,7 @@ pub struct ProxyClientConfig {
     pub dns_servers: Vec<SocketAddr>,
     pub exit_service_rate: u64,
     pub exit_byte_rate: u64,
+    pub exit_service_policy: ExitServicePolicy,
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -45,6 +47,18 @@ impl DnsResolveFailure_0v1 {
     }
 }
 
+#[derive(Message, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[allow(non_camel_case_types)]
+pub struct ExitRefusal_0v1 {
+    pub stream_key: StreamKey,
+}
+
+impl ExitRefusal_0v1 {
+    pub fn new(stream_key: StreamKey) -> Self {
+        Self { stream_key }
+    }
+}
+
 impl Into<MessageType> for ClientResponsePayload_0v1 {
     fn into(self) -> MessageType {
         MessageType::ClientResponse(VersionedData::new(
@@ -63,12 +77,22 @@ impl Into<MessageType> for DnsResolveFailure_0v1 {
     }
 }
 
+impl Into<MessageType> for ExitRefusal_0v1 {
+    fn into(self) -> MessageType {
+        MessageType::ExitRefused(VersionedData::new(
+            &crate::sub_lib::migrations::exit_refusal::MIGRATIONS,
+            &self,
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct ProxyClientSubs {
     pub bind: Recipient<BindMessage>,
     pub from_hopper: Recipient<ExpiredCoresPackage<ClientRequestPayload_0v1>>,
     pub inbound_server_data: Recipient<InboundServerData>,
     pub dns_resolve_failed: Recipient<DnsResolveFailure_0v1>,
+    pub exit_refused: Recipient<ExitRefusal_0v1>,
 }
 
 impl Debug for ProxyClientSubs {
@@ -135,6 +159,7 @@ mod tests {
             from_hopper: recipient!(recorder, ExpiredCoresPackage<ClientRequestPayload_0v1>),
             inbound_server_data: recipient!(recorder, InboundServerData),
             dns_resolve_failed: recipient!(recorder, DnsResolveFailure_0v1),
+            exit_refused: recipient!(recorder, ExitRefusal_0v1),
         };
 
         assert_eq!(format!("{:?}", subject), "ProxyClientSubs");
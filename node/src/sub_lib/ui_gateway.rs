@@ -4,16 +4,44 @@ use crate::sub_lib::peer_actors::BindMessage;
 use actix::Recipient;
 use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
 use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub struct UiGatewayConfig {
     pub ui_port: u16,
+    pub ui_security: UiSecurity,
     pub node_descriptor: String, // TODO: This really shouldn't be here; it exists only to answer
                                  // the GetNodeDescriptor message, which A) is part of MASQNode-UI,
                                  // and B) shouldn't be answered by the UiGateway anyway. Move it
                                  // to the Dispatcher part of the BootstrapperConfig.
 }
 
+// Without a tokens directory, any local process may use the UI port, as it always could. With one,
+// every UI connection must present a token that the Daemon has issued into that directory.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiSecurity {
+    pub tokens_directory_opt: Option<PathBuf>,
+    pub remote_opt: Option<UiRemoteAccess>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct UiRemoteAccess {
+    pub address: SocketAddr,
+    pub tls_identity_path: PathBuf,
+    pub tls_identity_password: String,
+}
+
+impl Debug for UiRemoteAccess {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "UiRemoteAccess {{ address: {}, tls_identity_path: {:?}, tls_identity_password: ******** }}",
+            self.address, self.tls_identity_path
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct UiGatewaySubs {
     pub bind: Recipient<BindMessage>,
@@ -34,6 +62,7 @@ mod tests {
     use crate::sub_lib::ui_gateway::UiGatewaySubs;
     use crate::test_utils::recorder::Recorder;
     use actix::Actor;
+    use std::str::FromStr;
 
     #[test]
     fn ui_gateway_subs_debug() {
@@ -47,4 +76,18 @@ mod tests {
 
         assert_eq!(format!("{:?}", subject), "UiGatewaySubs");
     }
+
+    #[test]
+    fn ui_remote_access_debug_hides_password() {
+        let subject = UiRemoteAccess {
+            address: SocketAddr::from_str("0.0.0.0:5334").unwrap(),
+            tls_identity_path: PathBuf::from("/home/booga/identity.p12"),
+            tls_identity_password: "secret".to_string(),
+        };
+
+        assert_eq!(
+            format!("{:?}", subject),
+            "UiRemoteAccess { address: 0.0.0.0:5334, tls_identity_path: \"/home/booga/identity.p12\", tls_identity_password: ******** }"
+        );
+    }
 }
@@ -1,8 +1,11 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
-use crate::db_config::persistent_configuration::{PersistentConfigError, PersistentConfiguration};
+use crate::accountant::PaymentCurves;
+use crate::db_config::persistent_configuration::{
+    NodeKeys, PersistentConfigError, PersistentConfiguration,
+};
 use crate::sub_lib::cryptde::PlainData;
-use crate::sub_lib::neighborhood::NodeDescriptor;
+use crate::sub_lib::neighborhood::{NodeDescriptor, RoutingStrategy};
 use crate::sub_lib::wallet::Wallet;
 use std::cell::RefCell;
 use std::sync::{Arc, Mutex};
@@ -21,6 +24,12 @@ pub struct PersistentConfigurationMock {
     gas_price_results: RefCell<Vec<Result<Option<u64>, PersistentConfigError>>>,
     set_gas_price_params: Arc<Mutex<Vec<u64>>>,
     set_gas_price_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
+    payment_curves_results: RefCell<Vec<Result<Option<PaymentCurves>, PersistentConfigError>>>,
+    set_payment_curves_params: Arc<Mutex<Vec<PaymentCurves>>>,
+    set_payment_curves_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
+    routing_strategy_results: RefCell<Vec<Result<Option<RoutingStrategy>, PersistentConfigError>>>,
+    set_routing_strategy_params: Arc<Mutex<Vec<RoutingStrategy>>>,
+    set_routing_strategy_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
     mnemonic_seed_params: Arc<Mutex<Vec<String>>>,
     mnemonic_seed_results: RefCell<Vec<Result<Option<PlainData>, PersistentConfigError>>>,
     mnemonic_seed_exists_params: Arc<Mutex<Vec<()>>>,
@@ -39,6 +48,10 @@ pub struct PersistentConfigurationMock {
         RefCell<Vec<Result<Option<Vec<NodeDescriptor>>, PersistentConfigError>>>,
     set_past_neighbors_params: Arc<Mutex<Vec<(Option<Vec<NodeDescriptor>>, String)>>>,
     set_past_neighbors_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
+    node_keys_params: Arc<Mutex<Vec<String>>>,
+    node_keys_results: RefCell<Vec<Result<Option<NodeKeys>, PersistentConfigError>>>,
+    set_node_keys_params: Arc<Mutex<Vec<(NodeKeys, String)>>>,
+    set_node_keys_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
     start_block_results: RefCell<Vec<Result<Option<u64>, PersistentConfigError>>>,
     set_start_block_params: Arc<Mutex<Vec<u64>>>,
     set_start_block_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
@@ -90,6 +103,45 @@ impl PersistentConfiguration for PersistentConfigurationMock {
         self.set_gas_price_results.borrow_mut().remove(0)
     }
 
+    fn payment_curves(&self) -> Result<Option<PaymentCurves>, PersistentConfigError> {
+        if self.payment_curves_results.borrow().is_empty() {
+            return Ok(None);
+        }
+        Self::result_from(&self.payment_curves_results)
+    }
+
+    fn set_payment_curves(&mut self, curves: &PaymentCurves) -> Result<(), PersistentConfigError> {
+        self.set_payment_curves_params
+            .lock()
+            .unwrap()
+            .push(curves.clone());
+        if self.set_payment_curves_results.borrow().is_empty() {
+            return Ok(());
+        }
+        Self::result_from(&self.set_payment_curves_results)
+    }
+
+    fn routing_strategy(&self) -> Result<Option<RoutingStrategy>, PersistentConfigError> {
+        if self.routing_strategy_results.borrow().is_empty() {
+            return Ok(None);
+        }
+        Self::result_from(&self.routing_strategy_results)
+    }
+
+    fn set_routing_strategy(
+        &mut self,
+        strategy: RoutingStrategy,
+    ) -> Result<(), PersistentConfigError> {
+        self.set_routing_strategy_params
+            .lock()
+            .unwrap()
+            .push(strategy);
+        if self.set_routing_strategy_results.borrow().is_empty() {
+            return Ok(());
+        }
+        Self::result_from(&self.set_routing_strategy_results)
+    }
+
     fn mnemonic_seed(&self, db_password: &str) -> Result<Option<PlainData>, PersistentConfigError> {
         self.mnemonic_seed_params
             .lock()
@@ -154,6 +206,26 @@ impl PersistentConfiguration for PersistentConfigurationMock {
         self.set_past_neighbors_results.borrow_mut().remove(0)
     }
 
+    fn node_keys(&self, db_password: &str) -> Result<Option<NodeKeys>, PersistentConfigError> {
+        self.node_keys_params
+            .lock()
+            .unwrap()
+            .push(db_password.to_string());
+        self.node_keys_results.borrow_mut().remove(0)
+    }
+
+    fn set_node_keys(
+        &mut self,
+        node_keys: &NodeKeys,
+        db_password: &str,
+    ) -> Result<(), PersistentConfigError> {
+        self.set_node_keys_params
+            .lock()
+            .unwrap()
+            .push((node_keys.clone(), db_password.to_string()));
+        self.set_node_keys_results.borrow_mut().remove(0)
+    }
+
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError> {
         if self.start_block_results.borrow().is_empty() {
             return Ok(Some(0));
@@ -310,6 +382,48 @@ impl PersistentConfigurationMock {
         self
     }
 
+    pub fn payment_curves_result(
+        self,
+        result: Result<Option<PaymentCurves>, PersistentConfigError>,
+    ) -> Self {
+        self.payment_curves_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn set_payment_curves_params(
+        mut self,
+        params: &Arc<Mutex<Vec<PaymentCurves>>>,
+    ) -> PersistentConfigurationMock {
+        self.set_payment_curves_params = params.clone();
+        self
+    }
+
+    pub fn set_payment_curves_result(self, result: Result<(), PersistentConfigError>) -> Self {
+        self.set_payment_curves_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn routing_strategy_result(
+        self,
+        result: Result<Option<RoutingStrategy>, PersistentConfigError>,
+    ) -> Self {
+        self.routing_strategy_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn set_routing_strategy_params(
+        mut self,
+        params: &Arc<Mutex<Vec<RoutingStrategy>>>,
+    ) -> PersistentConfigurationMock {
+        self.set_routing_strategy_params = params.clone();
+        self
+    }
+
+    pub fn set_routing_strategy_result(self, result: Result<(), PersistentConfigError>) -> Self {
+        self.set_routing_strategy_results.borrow_mut().push(result);
+        self
+    }
+
     pub fn past_neighbors_params(
         mut self,
         params: &Arc<Mutex<Vec<String>>>,
@@ -343,6 +457,38 @@ impl PersistentConfigurationMock {
         self
     }
 
+    pub fn node_keys_params(
+        mut self,
+        params: &Arc<Mutex<Vec<String>>>,
+    ) -> PersistentConfigurationMock {
+        self.node_keys_params = params.clone();
+        self
+    }
+
+    pub fn node_keys_result(
+        self,
+        result: Result<Option<NodeKeys>, PersistentConfigError>,
+    ) -> PersistentConfigurationMock {
+        self.node_keys_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn set_node_keys_params(
+        mut self,
+        params: &Arc<Mutex<Vec<(NodeKeys, String)>>>,
+    ) -> PersistentConfigurationMock {
+        self.set_node_keys_params = params.clone();
+        self
+    }
+
+    pub fn set_node_keys_result(
+        self,
+        result: Result<(), PersistentConfigError>,
+    ) -> PersistentConfigurationMock {
+        self.set_node_keys_results.borrow_mut().push(result);
+        self
+    }
+
     pub fn earning_wallet_from_address_result(
         self,
         result: Result<Option<Wallet>, PersistentConfigError>,
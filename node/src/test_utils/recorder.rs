@@ -1,8 +1,10 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::accountant::payable_dao::Payment;
-use crate::accountant::{ReceivedPayments, SentPayments};
-use crate::blockchain::blockchain_bridge::RetrieveTransactions;
-use crate::blockchain::blockchain_interface::{BlockchainError, BlockchainResult, Transaction};
+use crate::accountant::{ReceivedPayments, ReportTransactionReceipts, SentPayments};
+use crate::blockchain::blockchain_bridge::{
+    RequestTransactionReceipts, RetrieveTransactions, RetrievedTransactions, TransactionReceipts,
+};
+use crate::blockchain::blockchain_interface::{BlockchainError, BlockchainResult};
 use crate::daemon::crash_notification::CrashNotification;
 use crate::daemon::DaemonBindMessage;
 use crate::neighborhood::gossip::Gossip_0v1;
@@ -20,7 +22,6 @@ use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg};
 use crate::sub_lib::hopper::IncipientCoresPackage;
 use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
 use crate::sub_lib::hopper::{HopperSubs, MessageType};
-use crate::sub_lib::neighborhood::NeighborhoodDotGraphRequest;
 use crate::sub_lib::neighborhood::NeighborhoodSubs;
 use crate::sub_lib::neighborhood::NodeQueryMessage;
 use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
@@ -32,7 +33,7 @@ use crate::sub_lib::neighborhood::{DispatcherNodeQueryMessage, GossipFailure_0v1
 use crate::sub_lib::peer_actors::PeerActors;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, InboundServerData};
-use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, ProxyClientSubs};
+use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, ExitRefusal_0v1, ProxyClientSubs};
 use crate::sub_lib::proxy_server::ProxyServerSubs;
 use crate::sub_lib::proxy_server::{
     AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1,
@@ -60,8 +61,9 @@ pub struct Recorder {
     recording: Arc<Mutex<Recording>>,
     node_query_responses: Vec<Option<NodeQueryResponseMetadata>>,
     route_query_responses: Vec<Option<RouteQueryResponse>>,
-    retrieve_transactions_responses: Vec<Result<Vec<Transaction>, BlockchainError>>,
+    retrieve_transactions_responses: Vec<Result<RetrievedTransactions, BlockchainError>>,
     report_accounts_payable_responses: Vec<Result<Vec<BlockchainResult<Payment>>, String>>,
+    request_transaction_receipts_responses: Vec<TransactionReceipts>,
 }
 
 #[derive(Default)]
@@ -98,9 +100,11 @@ recorder_message_handler!(DaemonBindMessage);
 recorder_message_handler!(DispatcherNodeQueryMessage);
 recorder_message_handler!(DispatcherNodeQueryResponse);
 recorder_message_handler!(DnsResolveFailure_0v1);
+recorder_message_handler!(ExitRefusal_0v1);
 recorder_message_handler!(ExpiredCoresPackage<ClientRequestPayload_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<ClientResponsePayload_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<DnsResolveFailure_0v1>);
+recorder_message_handler!(ExpiredCoresPackage<ExitRefusal_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<Gossip_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<GossipFailure_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<MessageType>);
@@ -108,7 +112,6 @@ recorder_message_handler!(GetFinancialStatisticsMessage);
 recorder_message_handler!(InboundClientData);
 recorder_message_handler!(InboundServerData);
 recorder_message_handler!(IncipientCoresPackage);
-recorder_message_handler!(NeighborhoodDotGraphRequest);
 recorder_message_handler!(NewPasswordMessage);
 recorder_message_handler!(NodeFromUiMessage);
 recorder_message_handler!(NodeToUiMessage);
@@ -122,6 +125,7 @@ recorder_message_handler!(ReportExitServiceConsumedMessage);
 recorder_message_handler!(ReportExitServiceProvidedMessage);
 recorder_message_handler!(ReportRoutingServiceConsumedMessage);
 recorder_message_handler!(ReportRoutingServiceProvidedMessage);
+recorder_message_handler!(ReportTransactionReceipts);
 recorder_message_handler!(SentPayments);
 recorder_message_handler!(SetConsumingWalletMessage);
 recorder_message_handler!(SetDbPasswordMsg);
@@ -194,6 +198,22 @@ impl Handler<ReportAccountsPayable> for Recorder {
     }
 }
 
+impl Handler<RequestTransactionReceipts> for Recorder {
+    type Result = MessageResult<RequestTransactionReceipts>;
+
+    fn handle(
+        &mut self,
+        msg: RequestTransactionReceipts,
+        _ctx: &mut Self::Context,
+    ) -> <Self as Handler<RequestTransactionReceipts>>::Result {
+        self.record(msg);
+        MessageResult(extract_response(
+            &mut self.request_transaction_receipts_responses,
+            "No RequestTransactionReceiptsResponses prepared for RequestTransactionReceipts",
+        ))
+    }
+}
+
 fn extract_response<T>(responses: &mut Vec<T>, err_msg: &str) -> T
 where
     T: Clone,
@@ -242,7 +262,7 @@ impl Recorder {
 
     pub fn retrieve_transactions_response(
         mut self,
-        response: Result<Vec<Transaction>, BlockchainError>,
+        response: Result<RetrievedTransactions, BlockchainError>,
     ) -> Recorder {
         self.retrieve_transactions_responses.push(response);
         self
@@ -255,6 +275,14 @@ impl Recorder {
         self.report_accounts_payable_responses.push(response);
         self
     }
+
+    pub fn request_transaction_receipts_response(
+        mut self,
+        response: TransactionReceipts,
+    ) -> Recorder {
+        self.request_transaction_receipts_responses.push(response);
+        self
+    }
 }
 
 impl Recording {
@@ -339,6 +367,9 @@ pub fn make_proxy_server_subs_from(addr: &Addr<Recorder>) -> ProxyServerSubs {
         dns_failure_from_hopper: addr
             .clone()
             .recipient::<ExpiredCoresPackage<DnsResolveFailure_0v1>>(),
+        exit_refusal_from_hopper: addr
+            .clone()
+            .recipient::<ExpiredCoresPackage<ExitRefusal_0v1>>(),
         add_return_route: recipient!(addr, AddReturnRouteMessage),
         add_route: recipient!(addr, AddRouteMessage),
         stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
@@ -373,6 +404,7 @@ pub fn make_proxy_client_subs_from(addr: &Addr<Recorder>) -> ProxyClientSubs {
             .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
         inbound_server_data: recipient!(addr, InboundServerData),
         dns_resolve_failed: recipient!(addr, DnsResolveFailure_0v1),
+        exit_refused: recipient!(addr, ExitRefusal_0v1),
     }
 }
 
@@ -410,6 +442,7 @@ pub fn make_accountant_subs_from(addr: &Addr<Recorder>) -> AccountantSubs {
         report_exit_service_consumed: recipient!(addr, ReportExitServiceConsumedMessage),
         report_new_payments: recipient!(addr, ReceivedPayments),
         report_sent_payments: recipient!(addr, SentPayments),
+        report_transaction_receipts: recipient!(addr, ReportTransactionReceipts),
         ui_message_sub: recipient!(addr, NodeFromUiMessage),
     }
 }
@@ -427,6 +460,7 @@ pub fn make_blockchain_bridge_subs_from(addr: &Addr<Recorder>) -> BlockchainBrid
         bind: recipient!(addr, BindMessage),
         report_accounts_payable: recipient!(addr, ReportAccountsPayable),
         retrieve_transactions: recipient!(addr, RetrieveTransactions),
+        request_transaction_receipts: recipient!(addr, RequestTransactionReceipts),
         ui_sub: recipient!(addr, NodeFromUiMessage),
     }
 }
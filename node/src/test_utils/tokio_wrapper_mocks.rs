@@ -1,14 +1,19 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
+use crate::sub_lib::tokio_wrappers::TokioListenerWrapper;
 use crate::sub_lib::tokio_wrappers::WriteHalfWrapper;
+use crate::test_utils::logging::TestLog;
+use std::cell::RefCell;
 use std::io;
 use std::io::Read;
 use std::io::Write;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncWrite;
+use tokio::net::TcpStream;
 use tokio::prelude::Async;
 
 type PollReadResult = (Vec<u8>, Result<Async<usize>, io::Error>);
@@ -134,3 +139,42 @@ impl WriteHalfWrapperMock {
         self.shutdown_result(Ok(Async::Ready(())))
     }
 }
+
+#[derive(Default)]
+pub struct TokioListenerWrapperMock {
+    pub log: Arc<TestLog>,
+    bind_results: Vec<io::Result<()>>,
+    poll_accept_results: RefCell<Vec<io::Result<Async<(TcpStream, SocketAddr)>>>>,
+}
+
+impl TokioListenerWrapper for TokioListenerWrapperMock {
+    fn bind(&mut self, addr: SocketAddr) -> io::Result<()> {
+        self.log.log(format!("bind ({:?})", addr));
+        self.bind_results.remove(0)
+    }
+
+    fn poll_accept(&mut self) -> io::Result<Async<(TcpStream, SocketAddr)>> {
+        self.poll_accept_results.borrow_mut().remove(0)
+    }
+}
+
+impl TokioListenerWrapperMock {
+    pub fn new() -> TokioListenerWrapperMock {
+        Self::default()
+    }
+
+    pub fn bind_result(mut self, result: io::Result<()>) -> TokioListenerWrapperMock {
+        self.bind_results.push(result);
+        self
+    }
+
+    pub fn poll_accept_results(
+        self,
+        result_vec: Vec<Result<Async<(TcpStream, SocketAddr)>, io::Error>>,
+    ) -> TokioListenerWrapperMock {
+        result_vec
+            .into_iter()
+            .for_each(|result| self.poll_accept_results.borrow_mut().push(result));
+        self
+    }
+}
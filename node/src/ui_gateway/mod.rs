@@ -1,5 +1,6 @@
 // Copyright (c) 2017-2018, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
+pub mod ui_tokens;
 mod websocket_supervisor;
 
 #[cfg(test)]
@@ -10,6 +11,7 @@ use crate::sub_lib::logger::Logger;
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::ui_gateway::UiGatewayConfig;
 use crate::sub_lib::ui_gateway::UiGatewaySubs;
+use crate::sub_lib::ui_gateway::UiSecurity;
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
 use crate::ui_gateway::websocket_supervisor::WebSocketSupervisor;
 use crate::ui_gateway::websocket_supervisor::WebSocketSupervisorReal;
@@ -24,6 +26,7 @@ pub const CRASH_KEY: &str = "UIGATEWAY";
 
 pub struct UiGateway {
     port: u16,
+    security: UiSecurity,
     websocket_supervisor: Option<Box<dyn WebSocketSupervisor>>,
     incoming_message_recipients: Vec<Recipient<NodeFromUiMessage>>,
     logger: Logger,
@@ -33,6 +36,7 @@ impl UiGateway {
     pub fn new(config: &UiGatewayConfig) -> UiGateway {
         UiGateway {
             port: config.ui_port,
+            security: config.ui_security.clone(),
             websocket_supervisor: None,
             incoming_message_recipients: vec![],
             logger: Logger::new("UiGateway"),
@@ -65,6 +69,7 @@ impl Handler<BindMessage> for UiGateway {
         ];
         self.websocket_supervisor = match WebSocketSupervisorReal::new(
             self.port,
+            &self.security,
             msg.peer_actors.ui_gateway.node_from_ui_message_sub,
         ) {
             Ok(wss) => Some(Box::new(wss)),
@@ -80,11 +85,14 @@ impl Handler<DaemonBindMessage> for UiGateway {
     fn handle(&mut self, msg: DaemonBindMessage, ctx: &mut Self::Context) -> Self::Result {
         ctx.set_mailbox_capacity(NODE_MAILBOX_CAPACITY);
         self.incoming_message_recipients = msg.from_ui_message_recipients;
-        self.websocket_supervisor =
-            match WebSocketSupervisorReal::new(self.port, msg.from_ui_message_recipient) {
-                Ok(wss) => Some(Box::new(wss)),
-                Err(e) => panic!("Couldn't start WebSocketSupervisor: {:?}", e),
-            };
+        self.websocket_supervisor = match WebSocketSupervisorReal::new(
+            self.port,
+            &self.security,
+            msg.from_ui_message_recipient,
+        ) {
+            Ok(wss) => Some(Box::new(wss)),
+            Err(e) => panic!("Couldn't start WebSocketSupervisor: {:?}", e),
+        };
         debug!(self.logger, "UIGateway bound");
     }
 }
@@ -117,6 +125,7 @@ impl Handler<NodeFromUiMessage> for UiGateway {
 #[cfg(test)]
 mod tests {
     use super::*;
+
     use crate::test_utils::recorder::peer_actors_builder;
     use crate::test_utils::recorder::{make_recorder, Recording};
     use crate::ui_gateway::websocket_supervisor_mock::WebSocketSupervisorMock;
@@ -142,6 +151,7 @@ mod tests {
         let (hopper, _, hopper_recording_arc) = make_recorder();
         let subject = UiGateway::new(&UiGatewayConfig {
             ui_port: find_free_port(),
+            ui_security: UiSecurity::default(),
             node_descriptor: String::from(""),
         });
         let system = System::new("test");
@@ -198,6 +208,7 @@ mod tests {
             WebSocketSupervisorMock::new().send_msg_parameters(&send_msg_parameters_arc);
         let mut subject = UiGateway::new(&UiGatewayConfig {
             ui_port: find_free_port(),
+            ui_security: UiSecurity::default(),
             node_descriptor: String::from(""),
         });
         let system = System::new("test");
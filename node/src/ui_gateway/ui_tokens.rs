@@ -0,0 +1,181 @@
+// Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use rand::RngCore;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+pub const UI_TOKENS_FILENAME: &str = "ui-tokens";
+pub const FIRST_UI_TOKEN_FILENAME: &str = "ui-token";
+const TOKEN_SIZE: usize = 32;
+
+// The tokens the Daemon has issued to user interfaces, kept in the data directory so that the Node
+// can check them too. Only a SHA-1 hash of each token is stored, one per line, so the file doesn't
+// have to be kept secret. It's read afresh for every check, so a newly-issued token works at once.
+pub struct UiTokens {
+    path: PathBuf,
+}
+
+impl UiTokens {
+    pub fn new(data_directory: &Path) -> Self {
+        Self {
+            path: data_directory.join(UI_TOKENS_FILENAME),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes().is_empty()
+    }
+
+    pub fn is_valid(&self, token: &str) -> bool {
+        let hash = Self::hash(token);
+        self.hashes().iter().any(|candidate| candidate == &hash)
+    }
+
+    pub fn issue(&self) -> io::Result<String> {
+        let token = Self::generate();
+        self.record(&token)?;
+        Ok(token)
+    }
+
+    // Whoever asks first can't simply be handed the first token, because that could be any local
+    // process. Instead it goes into a file only its owner can read, and the caller gives that file
+    // to the user the token is meant for.
+    pub fn issue_first(&self) -> io::Result<PathBuf> {
+        let token = Self::generate();
+        let path = self.path.with_file_name(FIRST_UI_TOKEN_FILENAME);
+        self.make_directory()?;
+        match fs::remove_file(&path) {
+            Ok(()) => (),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+        let mut file = Self::create_private(&path)?;
+        writeln!(file, "{}", token)?;
+        self.record(&token)?;
+        Ok(path)
+    }
+
+    fn generate() -> String {
+        let mut token_bytes = [0u8; TOKEN_SIZE];
+        rand::thread_rng().fill_bytes(&mut token_bytes);
+        base64::encode_config(&token_bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    fn record(&self, token: &str) -> io::Result<()> {
+        self.make_directory()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", Self::hash(token))
+    }
+
+    fn make_directory(&self) -> io::Result<()> {
+        match self.path.parent() {
+            Some(directory) => fs::create_dir_all(directory),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn create_private(path: &Path) -> io::Result<File> {
+        use std::os::unix::fs::OpenOptionsExt;
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn create_private(path: &Path) -> io::Result<File> {
+        // The data directory is under the user's own profile, which other users can't read.
+        OpenOptions::new().write(true).create_new(true).open(path)
+    }
+
+    fn hashes(&self) -> Vec<String> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => contents
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect(),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => panic!("Couldn't read {:?}: {:?}", self.path, e),
+        }
+    }
+
+    fn hash(token: &str) -> String {
+        let mut hash = sha1::Sha1::new();
+        hash.update(token.trim().as_bytes());
+        hash.digest().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+
+    #[test]
+    fn issued_tokens_are_valid_and_stored_only_as_hashes() {
+        let data_dir =
+            ensure_node_home_directory_exists("ui_tokens", "issued_tokens_are_valid_and_stored");
+        let subject = UiTokens::new(&data_dir);
+        assert!(subject.is_empty());
+
+        let first = subject.issue().unwrap();
+        let second = subject.issue().unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(first.len(), 43);
+        assert!(!subject.is_empty());
+        assert!(subject.is_valid(&first));
+        assert!(subject.is_valid(&format!("{}\n", second)));
+        assert!(!subject.is_valid("booga"));
+        let contents = fs::read_to_string(data_dir.join(UI_TOKENS_FILENAME)).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(!contents.contains(&first));
+        assert!(!contents.contains(&second));
+    }
+
+    #[test]
+    fn first_token_is_written_to_a_private_file() {
+        let data_dir =
+            ensure_node_home_directory_exists("ui_tokens", "first_token_is_written_to_a_private");
+        let token_path = data_dir.join(FIRST_UI_TOKEN_FILENAME);
+        fs::write(&token_path, "stale token\n").unwrap();
+        let subject = UiTokens::new(&data_dir);
+
+        let result = subject.issue_first().unwrap();
+
+        assert_eq!(result, token_path);
+        let token = fs::read_to_string(&token_path).unwrap();
+        assert_eq!(token.lines().count(), 1);
+        assert!(subject.is_valid(token.trim_end()));
+        assert!(!subject.is_valid("stale token"));
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&token_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn issue_creates_missing_data_directory() {
+        let home_dir = ensure_node_home_directory_exists("ui_tokens", "issue_creates_missing");
+        let data_dir = home_dir.join("not").join("yet");
+        let subject = UiTokens::new(&data_dir);
+
+        let token = subject.issue().unwrap();
+
+        assert!(UiTokens::new(&data_dir).is_valid(&token));
+    }
+}
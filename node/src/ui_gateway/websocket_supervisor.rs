@@ -1,5 +1,7 @@
 // Copyright (c) 2017-2018, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::ui_gateway::{UiRemoteAccess, UiSecurity};
+use crate::ui_gateway::ui_tokens::UiTokens;
 use actix::Recipient;
 use bytes::BytesMut;
 use futures::future::FutureResult;
@@ -17,18 +19,22 @@ use masq_lib::ui_gateway::{MessageBody, MessageTarget, NodeFromUiMessage, NodeTo
 use masq_lib::ui_traffic_converter::UiTrafficConverter;
 use masq_lib::ui_traffic_converter::UnmarshalError::{Critical, NonCritical};
 use masq_lib::utils::localhost;
+use native_tls::{Identity, TlsAcceptor};
 use std::any::Any;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::fs;
+use std::io;
 use std::net::SocketAddr;
 use std::sync::Mutex;
 use std::sync::{Arc, MutexGuard};
 use tokio::reactor::Handle;
 use websocket::client::r#async::Framed;
+use websocket::header::{Authorization, Bearer};
 use websocket::r#async::MessageCodec;
-use websocket::r#async::TcpStream;
 use websocket::server::r#async::Server;
 use websocket::server::upgrade::WsUpgrade;
+use websocket::stream::r#async::Stream as AsyncStream;
 use websocket::OwnedMessage;
 use websocket::WebSocketError;
 
@@ -38,11 +44,11 @@ trait ClientWrapper: Send + Any {
     fn flush(&mut self) -> Result<(), WebSocketError>;
 }
 
-struct ClientWrapperReal {
-    delegate: Wait<SplitSink<Framed<TcpStream, MessageCodec<OwnedMessage>>>>,
+struct ClientWrapperReal<S> {
+    delegate: Wait<SplitSink<Framed<S, MessageCodec<OwnedMessage>>>>,
 }
 
-impl ClientWrapper for ClientWrapperReal {
+impl<S: AsyncStream + Send + 'static> ClientWrapper for ClientWrapperReal<S> {
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -69,6 +75,7 @@ struct WebSocketSupervisorInner {
     port: u16,
     next_client_id: u64,
     from_ui_message_sub: Recipient<NodeFromUiMessage>,
+    tokens_opt: Option<UiTokens>,
     client_id_by_socket_addr: HashMap<SocketAddr, u64>,
     client_by_id: HashMap<u64, Box<dyn ClientWrapper>>,
 }
@@ -83,24 +90,61 @@ impl WebSocketSupervisor for WebSocketSupervisorReal {
 impl WebSocketSupervisorReal {
     pub fn new(
         port: u16,
+        security: &UiSecurity,
         from_ui_message_sub: Recipient<NodeFromUiMessage>,
-    ) -> std::io::Result<WebSocketSupervisorReal> {
+    ) -> io::Result<WebSocketSupervisorReal> {
+        let tls_acceptor_opt = match &security.remote_opt {
+            Some(remote) => Some(Self::make_tls_acceptor(remote)?),
+            None => None,
+        };
         let inner = Arc::new(Mutex::new(WebSocketSupervisorInner {
             port,
             next_client_id: 0,
             from_ui_message_sub,
+            tokens_opt: security
+                .tokens_directory_opt
+                .as_ref()
+                .map(|directory| UiTokens::new(directory)),
             client_id_by_socket_addr: HashMap::new(),
             client_by_id: HashMap::new(),
         }));
         let logger = Logger::new("WebSocketSupervisor");
-        let logger_1 = logger.clone();
         let server_address = SocketAddr::new(localhost(), port);
         let server = Server::bind(server_address, &Handle::default())
             .unwrap_or_else(|e| panic!("Could not start UI server at {}: {}", server_address, e));
-        let upgrade_tuple_stream = Self::remove_failures(server.incoming(), &logger);
-        let inner_clone = inner.clone();
+        Self::serve(server.incoming(), inner.clone(), logger.clone());
+        if let (Some(remote), Some(tls_acceptor)) = (&security.remote_opt, tls_acceptor_opt) {
+            let server = Server::bind_secure(remote.address, tls_acceptor, &Handle::default())?;
+            info!(logger, "Accepting TLS UI connections at {}", remote.address);
+            Self::serve(server.incoming(), inner.clone(), logger);
+        }
+        Ok(WebSocketSupervisorReal { inner })
+    }
+
+    fn make_tls_acceptor(remote: &UiRemoteAccess) -> io::Result<TlsAcceptor> {
+        let pkcs12 = fs::read(&remote.tls_identity_path)?;
+        let identity =
+            Identity::from_pkcs12(&pkcs12, &remote.tls_identity_password).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{:?}: {}", remote.tls_identity_path, e),
+                )
+            })?;
+        TlsAcceptor::new(identity).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    fn serve<S, E>(
+        incoming: impl Stream<Item = (WsUpgrade<S, BytesMut>, SocketAddr), Error = E> + Send + 'static,
+        inner: Arc<Mutex<WebSocketSupervisorInner>>,
+        logger: Logger,
+    ) where
+        S: AsyncStream + Send + 'static,
+        E: Debug + Send + 'static,
+    {
+        let logger_1 = logger.clone();
+        let upgrade_tuple_stream = Self::remove_failures(incoming, &logger);
         let foreach_result = upgrade_tuple_stream.for_each(move |(upgrade, socket_addr)| {
-            Self::handle_upgrade_request(upgrade, socket_addr, inner_clone.clone(), &logger);
+            Self::handle_upgrade_request(upgrade, socket_addr, inner.clone(), &logger);
             Ok(())
         });
         tokio::spawn(foreach_result.then(move |result| match result {
@@ -116,7 +160,6 @@ impl WebSocketSupervisorReal {
                 Err(())
             }
         }));
-        Ok(WebSocketSupervisorReal { inner })
     }
 
     fn send_msg(locked_inner: &mut MutexGuard<WebSocketSupervisorInner>, msg: NodeToUiMessage) {
@@ -155,24 +198,45 @@ impl WebSocketSupervisorReal {
             .map(|option| option.expect("A None magically got through the filter"))
     }
 
-    fn handle_upgrade_request(
-        upgrade: WsUpgrade<TcpStream, BytesMut>,
+    fn handle_upgrade_request<S: AsyncStream + Send + 'static>(
+        upgrade: WsUpgrade<S, BytesMut>,
         socket_addr: SocketAddr,
         inner: Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
     ) {
-        if upgrade
+        if !upgrade
             .protocols()
             .contains(&String::from(NODE_UI_PROTOCOL))
         {
-            Self::accept_upgrade_request(upgrade, socket_addr, inner, logger);
-        } else {
             Self::reject_upgrade_request(upgrade, &logger);
+        } else if !Self::is_authorized(&upgrade, &inner) {
+            warning!(
+                logger,
+                "UI at {} presented no valid token; refusing connection",
+                socket_addr
+            );
+            tokio::spawn(upgrade.reject().then(|_| ok::<(), ()>(())));
+        } else {
+            Self::accept_upgrade_request(upgrade, socket_addr, inner, logger);
+        }
+    }
+
+    fn is_authorized<S: AsyncStream>(
+        upgrade: &WsUpgrade<S, BytesMut>,
+        inner: &Arc<Mutex<WebSocketSupervisorInner>>,
+    ) -> bool {
+        let locked_inner = inner.lock().expect("WebSocketSupervisor is poisoned");
+        match &locked_inner.tokens_opt {
+            None => true,
+            Some(tokens) => match upgrade.request.headers.get::<Authorization<Bearer>>() {
+                Some(Authorization(bearer)) => tokens.is_valid(&bearer.token),
+                None => false,
+            },
         }
     }
 
-    fn accept_upgrade_request(
-        upgrade: WsUpgrade<TcpStream, BytesMut>,
+    fn accept_upgrade_request<S: AsyncStream + Send + 'static>(
+        upgrade: WsUpgrade<S, BytesMut>,
         socket_addr: SocketAddr,
         inner: Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
@@ -194,7 +258,10 @@ impl WebSocketSupervisorReal {
         }));
     }
 
-    fn reject_upgrade_request(upgrade: WsUpgrade<TcpStream, BytesMut>, logger: &Logger) {
+    fn reject_upgrade_request<S: AsyncStream + Send + 'static>(
+        upgrade: WsUpgrade<S, BytesMut>,
+        logger: &Logger,
+    ) {
         info!(
             logger,
             "UI attempted connection without protocol {}: {:?}",
@@ -204,8 +271,8 @@ impl WebSocketSupervisorReal {
         tokio::spawn(upgrade.reject().then(|_| ok::<(), ()>(())));
     }
 
-    fn handle_connection(
-        client: Framed<TcpStream, MessageCodec<OwnedMessage>>,
+    fn handle_connection<S: AsyncStream + Send + 'static>(
+        client: Framed<S, MessageCodec<OwnedMessage>>,
         inner: &Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
         socket_addr: SocketAddr,
@@ -459,6 +526,7 @@ mod tests {
         UNMARSHAL_ERROR,
     };
     use masq_lib::test_utils::ui_connection::UiConnection;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
     use masq_lib::ui_gateway::MessagePath::FireAndForget;
     use masq_lib::ui_gateway::NodeFromUiMessage;
     use masq_lib::ui_traffic_converter::UiTrafficConverter;
@@ -469,6 +537,7 @@ mod tests {
     use std::thread;
     use std::time::Duration;
     use websocket::client::sync::Client;
+    use websocket::header::Headers;
     use websocket::stream::sync::TcpStream;
     use websocket::ClientBuilder;
     use websocket::Message;
@@ -585,6 +654,18 @@ mod tests {
             .connect_insecure()
     }
 
+    fn make_client_with_token(port: u16, token: &str) -> Result<Client<TcpStream>, WebSocketError> {
+        let mut headers = Headers::new();
+        headers.set(Authorization(Bearer {
+            token: token.to_string(),
+        }));
+        ClientBuilder::new(format!("ws://127.0.0.1:{}", port).as_str())
+            .expect("ClientBuilder could not be built")
+            .add_protocol(NODE_UI_PROTOCOL)
+            .custom_headers(&headers)
+            .connect_insecure()
+    }
+
     fn wait_for_client(port: u16, protocol: &str) -> Client<TcpStream> {
         let mut one_client_opt: Option<Client<TcpStream>> = None;
         wait_for(None, None, || match make_client(port, protocol) {
@@ -627,7 +708,9 @@ mod tests {
             let system = System::new("logs_pre_upgrade_connection_errors");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -649,7 +732,9 @@ mod tests {
             let system = System::new("rejects_connection_attempt_with_improper_protocol_name");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -666,6 +751,119 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rejects_connection_attempt_without_valid_token_when_tokens_are_required() {
+        init_test_logging();
+        let port = find_free_port();
+        let data_dir = ensure_node_home_directory_exists(
+            "websocket_supervisor",
+            "rejects_connection_attempt_without_valid_token_when_tokens_are_required",
+        );
+        UiTokens::new(&data_dir).issue().unwrap();
+        let security = UiSecurity {
+            tokens_directory_opt: Some(data_dir),
+            remote_opt: None,
+        };
+        let (ui_gateway, _, _) = make_recorder();
+
+        thread::spawn(move || {
+            let system = System::new(
+                "rejects_connection_attempt_without_valid_token_when_tokens_are_required",
+            );
+            let ui_message_sub = subs(ui_gateway);
+            let subject = lazy(move || {
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &security, ui_message_sub).unwrap();
+                Ok(())
+            });
+            actix::spawn(subject);
+            system.run();
+        });
+        wait_for_server(port);
+
+        make_client(port, NODE_UI_PROTOCOL).err().unwrap();
+        make_client_with_token(port, "booga").err().unwrap();
+
+        let tlh = TestLogHandler::new();
+        tlh.await_log_matching(
+            "UI at 127\\.0\\.0\\.1:\\d+ presented no valid token; refusing connection",
+            1000,
+        );
+    }
+
+    #[test]
+    fn accepts_connection_attempt_with_valid_token() {
+        let port = find_free_port();
+        let data_dir = ensure_node_home_directory_exists(
+            "websocket_supervisor",
+            "accepts_connection_attempt_with_valid_token",
+        );
+        let token = UiTokens::new(&data_dir).issue().unwrap();
+        let security = UiSecurity {
+            tokens_directory_opt: Some(data_dir),
+            remote_opt: None,
+        };
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+
+        thread::spawn(move || {
+            let system = System::new("accepts_connection_attempt_with_valid_token");
+            let ui_message_sub = subs(ui_gateway);
+            let subject = lazy(move || {
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &security, ui_message_sub).unwrap();
+                Ok(())
+            });
+            actix::spawn(subject);
+            system.run();
+        });
+        wait_for_server(port);
+
+        let mut client = make_client_with_token(port, &token).unwrap();
+        client
+            .send_message(&Message::text(UiTrafficConverter::new_marshal(
+                UiShutdownRequest {}.tmb(1),
+            )))
+            .unwrap();
+
+        let message = await_value(None, || {
+            let recording = ui_gateway_recording_arc.lock().unwrap();
+            match recording.len() {
+                0 => Err("no message yet".to_string()),
+                _ => Ok(recording.get_record::<NodeFromUiMessage>(0).clone()),
+            }
+        })
+        .unwrap();
+        assert_eq!(message.body.opcode, "shutdown".to_string());
+    }
+
+    #[test]
+    fn refuses_to_start_with_unreadable_tls_identity() {
+        let data_dir = ensure_node_home_directory_exists(
+            "websocket_supervisor",
+            "refuses_to_start_with_unreadable_tls_identity",
+        );
+        let identity_path = data_dir.join("identity.p12");
+        fs::write(&identity_path, b"not a PKCS#12 file").unwrap();
+        let security = UiSecurity {
+            tokens_directory_opt: Some(data_dir),
+            remote_opt: Some(UiRemoteAccess {
+                address: SocketAddr::new(localhost(), find_free_port()),
+                tls_identity_path: identity_path.clone(),
+                tls_identity_password: "password".to_string(),
+            }),
+        };
+        let (ui_gateway, _, _) = make_recorder();
+        let system = System::new("refuses_to_start_with_unreadable_tls_identity");
+
+        let result = WebSocketSupervisorReal::new(find_free_port(), &security, subs(ui_gateway));
+
+        let error = result.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains(&format!("{:?}", identity_path)));
+        System::current().stop();
+        system.run();
+    }
+
     #[test]
     fn logs_unexpected_binary_ping_pong_websocket_messages() {
         init_test_logging();
@@ -676,7 +874,9 @@ mod tests {
             let system = System::new("logs_unexpected_binary_ping_pong_websocket_messages");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -713,7 +913,9 @@ mod tests {
             let system = System::new("can_connect_two_clients_and_receive_messages_from_them");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -1027,7 +1229,9 @@ mod tests {
             let system = System::new("once_a_client_sends_a_close_no_more_data_is_accepted");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -1063,7 +1267,9 @@ mod tests {
             let system = System::new("a_client_that_violates_the_protocol_is_terminated");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject =
+                    WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub)
+                        .unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -1096,7 +1302,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1137,7 +1344,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1180,7 +1388,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1230,7 +1439,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_tries_to_send_message_and_panics_on_flush");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Err(WebSocketError::NoDataAvailable));
@@ -1262,7 +1472,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_tries_to_send_message_and_panics");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let mock_client =
                 ClientWrapperMock::new().send_result(Err(WebSocketError::NoDataAvailable));
             let msg = NodeToUiMessage {
@@ -1292,7 +1503,8 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_fails_to_look_up_client_to_send_to");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject =
+                WebSocketSupervisorReal::new(port, &UiSecurity::default(), ui_message_sub).unwrap();
             let msg = NodeToUiMessage {
                 target: MessageTarget::ClientId(7),
                 body: MessageBody {